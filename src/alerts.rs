@@ -0,0 +1,136 @@
+// ============================================================================
+// alerts.rs — EvoLenia v2 Research Lab
+// Alert rules on metrics: at every metrics sample each enabled rule checks
+// its metric (a metrics record field, a provider metric or a custom metric) against a fixed
+// threshold, or against the extreme of the look-back window for relative
+// rules ("entropy drops by 50% within 1000 frames"). A rule fires once its
+// condition held for `sustained` consecutive samples, then stays quiet until
+// the condition clears.
+// ============================================================================
+
+use crate::config::{AlertRule, Comparator};
+use crate::expr::CompiledMetric;
+use crate::lab::MetricsRecord;
+
+/// Value of a metric by name in a record: built-in or provider field
+/// first, then custom.
+pub fn metric_value(name: &str, record: &MetricsRecord, custom: &[CompiledMetric]) -> Option<f64> {
+    match MetricsRecord::field_names().iter().position(|f| *f == name) {
+        Some(i) => Some(record.field_values()[i]),
+        None => custom.iter().find(|m| m.name == name).map(|m| m.eval(record)),
+    }
+}
+
+/// One-line description for logs, e.g. "entropy drops by 50% within 1000 frames".
+pub fn describe(rule: &AlertRule) -> String {
+    let condition = match rule.comparator {
+        Comparator::Above => format!("> {}", rule.threshold),
+        Comparator::Below => format!("< {}", rule.threshold),
+        Comparator::DropsBy => format!("drops by {}% within {} frames", rule.threshold, rule.window_frames),
+        Comparator::RisesBy => format!("rises by {}% within {} frames", rule.threshold, rule.window_frames),
+    };
+    let sustained = if rule.sustained > 1 { format!(" for {} samples", rule.sustained) } else { String::new() };
+    format!("{} {}{}", rule.metric, condition, sustained)
+}
+
+/// Whether the rule's condition holds at the last record of `history`.
+pub fn condition_met(rule: &AlertRule, history: &[MetricsRecord], custom: &[CompiledMetric]) -> bool {
+    let Some((last, earlier)) = history.split_last() else {
+        return false;
+    };
+    let Some(value) = metric_value(&rule.metric, last, custom).filter(|v| v.is_finite()) else {
+        return false;
+    };
+    if !rule.comparator.is_relative() {
+        return match rule.comparator {
+            Comparator::Below => value < rule.threshold,
+            _ => value > rule.threshold,
+        };
+    }
+    // Extreme over the earlier samples within the window
+    let since = last.frame.saturating_sub(rule.window_frames);
+    let window = earlier
+        .iter()
+        .rev()
+        .take_while(|r| r.frame >= since && r.frame <= last.frame)
+        .filter_map(|r| metric_value(&rule.metric, r, custom))
+        .filter(|v| v.is_finite());
+    let reference = match rule.comparator {
+        Comparator::DropsBy => window.fold(f64::NEG_INFINITY, f64::max),
+        _ => window.fold(f64::INFINITY, f64::min),
+    };
+    if !reference.is_finite() || reference == 0.0 {
+        return false;
+    }
+    let change = (value - reference) / reference.abs() * 100.0;
+    match rule.comparator {
+        Comparator::DropsBy => -change >= rule.threshold,
+        _ => change >= rule.threshold,
+    }
+}
+
+/// A rule that just fired.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertTrigger {
+    pub rule: usize,
+    pub description: String,
+    pub value: f64,
+    pub pause: bool,
+    pub snapshot: bool,
+}
+
+/// Per-rule streaks for the rules in `params.alert_rules`.
+#[derive(Clone, Debug, Default)]
+pub struct AlertMonitor {
+    rules: Vec<AlertRule>,
+    streaks: Vec<u32>, // consecutive samples meeting the condition
+}
+
+impl AlertMonitor {
+    /// Take new rule definitions; streaks restart when they change.
+    pub fn sync(&mut self, rules: &[AlertRule]) {
+        if self.rules != rules {
+            self.rules = rules.to_vec();
+            self.streaks = vec![0; rules.len()];
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.streaks.iter_mut().for_each(|s| *s = 0);
+    }
+
+    /// Consecutive samples rule `i` has held its condition.
+    pub fn streak(&self, i: usize) -> u32 {
+        self.streaks.get(i).copied().unwrap_or(0)
+    }
+
+    /// Whether rule `i` has fired and not yet re-armed.
+    pub fn is_firing(&self, i: usize) -> bool {
+        self.rules.get(i).is_some_and(|r| self.streak(i) >= r.sustained.max(1))
+    }
+
+    /// Check every enabled rule against the newest record of `history`.
+    pub fn check(&mut self, history: &[MetricsRecord], custom: &[CompiledMetric]) -> Vec<AlertTrigger> {
+        let mut fired = Vec::new();
+        let Some(last) = history.last() else {
+            return fired;
+        };
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.enabled || !condition_met(rule, history, custom) {
+                self.streaks[i] = 0;
+                continue;
+            }
+            self.streaks[i] = self.streaks[i].saturating_add(1);
+            if self.streaks[i] == rule.sustained.max(1) {
+                fired.push(AlertTrigger {
+                    rule: i,
+                    description: describe(rule),
+                    value: metric_value(&rule.metric, last, custom).unwrap_or(f64::NAN),
+                    pause: rule.pause,
+                    snapshot: rule.snapshot,
+                });
+            }
+        }
+        fired
+    }
+}
@@ -0,0 +1,194 @@
+// ============================================================================
+// analysis_window.rs — EvoLenia v2 Research Lab
+// Detached analysis panel: a second OS window (e.g. on another monitor) with
+// its own surface, egui context and renderer, sharing the GPU device with the
+// main window, which then keeps the simulation view. The panel contents are
+// drawn by lab_ui::render_analysis_window. The stream viewer (viewer.rs)
+// uses the same egui-only window.
+// ============================================================================
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use winit::event::WindowEvent;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Window, WindowAttributes, WindowId};
+
+use crate::app::render_egui_pass;
+use crate::settings::WindowGeometry;
+
+/// Minimum time between two redraws: plots change at the diagnostics rate,
+/// so the second window need not follow the simulation frame rate.
+pub const ANALYSIS_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+pub struct AnalysisWindow {
+    pub window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+    egui_ctx: egui::Context,
+    egui_winit_state: egui_winit::State,
+    egui_renderer: egui_wgpu::Renderer,
+    last_redraw: Option<Instant>,
+    pub focused: bool,
+}
+
+impl AnalysisWindow {
+    /// Open the window at `geometry` (a default size when None). The present
+    /// mode follows the main window's when the surface supports it.
+    pub fn open(
+        event_loop: &ActiveEventLoop,
+        title: &str,
+        instance: &wgpu::Instance,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        present_mode: wgpu::PresentMode,
+        geometry: Option<WindowGeometry>,
+    ) -> Result<Self, String> {
+        let mut attrs = WindowAttributes::default()
+            .with_title(title)
+            .with_inner_size(winit::dpi::LogicalSize::new(900u32, 720u32));
+        if let Some(geom) = geometry {
+            attrs = attrs
+                .with_inner_size(winit::dpi::PhysicalSize::new(geom.width.max(320), geom.height.max(240)))
+                .with_maximized(geom.maximized);
+            if let Some([x, y]) = geom.position {
+                attrs = attrs.with_position(winit::dpi::PhysicalPosition::new(x, y));
+            }
+        }
+        let window = Arc::new(event_loop.create_window(attrs).map_err(|e| e.to_string())?);
+        let surface = instance.create_surface(window.clone()).map_err(|e| e.to_string())?;
+
+        let caps = surface.get_capabilities(adapter);
+        let format = caps
+            .formats
+            .iter()
+            .find(|f| f.is_srgb())
+            .or(caps.formats.first())
+            .copied()
+            .ok_or("No compatible surface format")?;
+        let size = window.inner_size();
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: if caps.present_modes.contains(&present_mode) {
+                present_mode
+            } else {
+                wgpu::PresentMode::Fifo
+            },
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(device, &config);
+
+        let egui_ctx = egui::Context::default();
+        let egui_winit_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui::ViewportId::from_hash_of("analysis_window"),
+            event_loop,
+            Some(window.scale_factor() as f32),
+            None,
+            None,
+        );
+        let egui_renderer = egui_wgpu::Renderer::new(device, format, None, 1, false);
+
+        Ok(Self {
+            window,
+            surface,
+            config,
+            egui_ctx,
+            egui_winit_state,
+            egui_renderer,
+            last_redraw: None,
+            focused: false,
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn geometry(&self) -> WindowGeometry {
+        crate::app::window_geometry(&self.window)
+    }
+
+    /// Feed a window event to egui; requests a redraw when egui wants one.
+    pub fn on_window_event(&mut self, event: &WindowEvent) {
+        let response = self.egui_winit_state.on_window_event(&self.window, event);
+        if response.repaint {
+            self.window.request_redraw();
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(device, &self.config);
+    }
+
+    /// Whether the next redraw is due (see ANALYSIS_FRAME_INTERVAL).
+    pub fn redraw_due(&self, now: Instant) -> bool {
+        self.last_redraw.is_none_or(|last| now.duration_since(last) >= ANALYSIS_FRAME_INTERVAL)
+    }
+
+    /// Run one egui frame with `ui` and present it. Style and zoom follow
+    /// the main window's context.
+    pub fn redraw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        main: &egui::Context,
+        ui: impl FnMut(&egui::Context),
+    ) {
+        self.last_redraw = Some(Instant::now());
+        let output = match self.surface.get_current_texture() {
+            Ok(t) => t,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(device, &self.config);
+                return;
+            }
+            Err(e) => {
+                log::error!("Analysis window surface error: {:?}", e);
+                return;
+            }
+        };
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        if !Arc::ptr_eq(&self.egui_ctx.style(), &main.style()) {
+            self.egui_ctx.set_style(main.style());
+        }
+        self.egui_ctx.set_zoom_factor(main.zoom_factor());
+        let raw_input = self.egui_winit_state.take_egui_input(&self.window);
+        let full_output = self.egui_ctx.run(raw_input, ui);
+        self.egui_winit_state.handle_platform_output(&self.window, full_output.platform_output);
+
+        let paint_jobs = self.egui_ctx.tessellate(full_output.shapes, full_output.pixels_per_point);
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.egui_renderer.update_texture(device, queue, *id, image_delta);
+        }
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.config.width, self.config.height],
+            pixels_per_point: full_output.pixels_per_point,
+        };
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("analysis_window_encoder"),
+        });
+        self.egui_renderer.update_buffers(device, queue, &mut encoder, &paint_jobs, &screen_descriptor);
+        render_egui_pass(
+            &self.egui_renderer,
+            &mut encoder,
+            &view,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            &paint_jobs,
+            &screen_descriptor,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        for id in &full_output.textures_delta.free {
+            self.egui_renderer.free_texture(id);
+        }
+    }
+}
@@ -3,25 +3,65 @@
 // Application state and winit event-loop handler with egui UI integration.
 // ============================================================================
 
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use winit::{
     application::ApplicationHandler,
-    event::{MouseScrollDelta, WindowEvent},
+    event::{MouseButton, MouseScrollDelta, WindowEvent},
     keyboard::{Key, NamedKey},
     window::{Window, WindowAttributes},
 };
 
+use crate::analysis_window::AnalysisWindow;
+use crate::arena::{list_library, load_contestant, round_robin, standings, ArenaJob, ArenaRequest};
+use crate::averages;
 use crate::camera::CameraState;
-use crate::config::{SimulationParams, VIS_MODE_COUNT};
-use crate::input::KeysHeld;
-use crate::lab::LabState;
+use crate::capture::{encode_png, open_frame_sink, CapturedFrame, Capturer};
+use crate::channels::{SPECIES, TRAIL};
+use crate::legend::{composite as composite_legend, legend_for};
+use crate::config::{DisplayAdjust, DisplayTransform, HistogramField, SimulationParams, VIS_MODE_COUNT};
+use crate::crash;
+use crate::gpu_debug::{self, GpuErrorLog};
+use crate::display;
+use crate::flow::FlowField;
+use crate::histogram::{max_value as histogram_max, FieldHistogram, HistogramParams};
+use crate::forensics::{report_dir, Extinction, ExtinctionReport};
+use crate::i18n::{self, tr, trf};
+use crate::input::{KeysHeld, MouseState};
+use crate::invasion::{inject_mutant, AssayJob};
+use crate::lab::{new_run_id, EventSeverity, LabState, MetricsRecord, ReferenceMass};
 use crate::lab_ui;
-use crate::metrics::SimDiagnostics;
-use crate::pipeline::{create_pipelines, Pipelines};
+use crate::ltee::{dilute, list_samples, load_sample, sample_path, save_sample, spawn_sample, transfer_rng, FossilSample};
+use crate::landscape::MicroWorld;
+use crate::metrics::{split_divergence, SimDiagnostics};
+use crate::pacing::{BackgroundClock, FrameTiming, GpuStepTimer, RenderClock, RenderDue, SpeedGuard, StepBudget};
+use crate::pattern::{load_pattern, pattern_path, save_pattern, Pattern};
+use crate::creature::{PortraitCapture, CREATURES_DIR};
+use crate::pipeline::{create_pipelines, smooth_sampling_supported, Pipelines};
+use crate::postfx::{PostFx, HDR_FORMAT};
+use crate::popgen::{export_population, population_path};
+use crate::profile::ProfileLine;
+use crate::queue::{BackgroundJob, EntryStatus, QueueMode};
 use crate::renderer::HudRenderer;
-use crate::state_io;
+use crate::replay::{self, ReplayJob};
+use crate::resize::{self, ResizeJob};
+use crate::speciation::genome_clusters;
+use crate::reserves::{reserve_stats, zone_mean_genome, ReserveShape};
+use crate::resources::{format_bytes, MemoryReport};
+use crate::session_log;
+use crate::territory::{territory_rgba, TerritoryMap};
+use crate::thumbnail::save_thumbnail;
+use crate::transplant::{Patch, Transplant};
+use crate::schedule::apply_schedules;
+use crate::user_modes::{load_user_modes, UserMode, USER_MODES_DIR};
+use crate::settings::{UiSettings, WindowGeometry, SETTINGS_PATH};
+use crate::startup::{Recommendation, StartupBench, STARTUP_BENCH_DURATION, STARTUP_WARMUP_STEPS};
+use crate::state_io::{self, SNAPSHOT_EXTENSION};
+use crate::theme::UiTheme;
+use crate::tape::{self, PlaybackResume, SequenceExport, TapeHeader, TapePlayer, TapeReader, TapeWriter};
+use crate::vectors;
 use crate::world::*;
 
 // ======================== Application ========================
@@ -35,6 +75,9 @@ pub struct App {
 pub struct AppConfig {
     pub initial_state_path: Option<String>,
     pub diag_interval: u32,
+    pub grid: GridType, // lattice of the initial world; restarts follow SimulationParams::grid
+    pub world_size: [u32; 2], // of the initial world; a resize replaces it (resize.rs)
+    pub gpu_validation: bool, // validation layers and error scopes, whatever the saved setting
 }
 
 impl Default for AppConfig {
@@ -42,12 +85,17 @@ impl Default for AppConfig {
         Self {
             initial_state_path: None,
             diag_interval: 300,
+            grid: GridType::Square,
+            world_size: DEFAULT_WORLD_SIZE,
+            gpu_validation: false,
         }
     }
 }
 
 struct AppState {
-    // GPU
+    // GPU (instance and adapter kept for the surfaces of extra windows)
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface: wgpu::Surface<'static>,
@@ -56,14 +104,20 @@ struct AppState {
     // Simulation
     world: WorldState,
     pipelines: Pipelines,
+    postfx: PostFx,
+    user_modes: Vec<UserMode>, // compiled vis_modes/ files, modes VIS_MODE_COUNT..
 
     // Window
     window: Arc<Window>,
+    analysis_window: Option<AnalysisWindow>, // detached analysis panel
 
     // Camera & Input
     camera: CameraState,
     keys: KeysHeld,
+    mouse: MouseState,
     sim_params: SimulationParams,
+    // Mode + adjustment the equalization LUT was last built for
+    display_lut_key: Option<(u32, DisplayAdjust)>,
 
     // HUD (minimal, kept as fallback)
     hud: HudRenderer,
@@ -79,6 +133,19 @@ struct AppState {
     // Timing
     last_redraw: Instant,
     fps: f32,
+    gpu_timer: Option<GpuStepTimer>, // None without timestamp query support
+    step_budget: StepBudget,
+    render_clock: RenderClock,
+    speed_guard: SpeedGuard,
+    last_turn: Option<Instant>, // end of the previous event loop turn that simulated
+    vsync_applied: bool,        // present mode currently configured for vsync
+    gpu_errors: GpuErrorLog,
+    captures: Capturer<ShotTag>, // screenshots and sequence images in flight
+
+    // Background throttling
+    focused: bool,
+    occluded: bool,
+    background_clock: BackgroundClock,
 
     // Diagnostics
     last_diag: Option<SimDiagnostics>,
@@ -97,29 +164,57 @@ impl ApplicationHandler for App {
             return;
         }
 
-        let window_attrs = WindowAttributes::default()
+        let settings = UiSettings::load(SETTINGS_PATH);
+
+        let mut window_attrs = WindowAttributes::default()
             .with_title("EvoLenia v2 — Research Lab")
             .with_inner_size(winit::dpi::LogicalSize::new(1280u32, 1024u32));
+        if let Some(geom) = settings.as_ref().and_then(|s| s.window) {
+            window_attrs = window_attrs
+                .with_inner_size(winit::dpi::PhysicalSize::new(geom.width.max(320), geom.height.max(240)))
+                .with_maximized(geom.maximized);
+            if let Some([x, y]) = geom.position {
+                window_attrs = window_attrs.with_position(winit::dpi::PhysicalPosition::new(x, y));
+            }
+        }
 
         let window = Arc::new(event_loop.create_window(window_attrs).unwrap());
 
+        // Read at startup: the instance cannot change its layers later
+        let gpu_validation = self.config.gpu_validation || settings.as_ref().is_some_and(|s| s.gpu_validation);
+        if gpu_validation {
+            log::info!("GPU validation enabled: errors are reported in the event log");
+        }
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
+            flags: gpu_debug::instance_flags(gpu_validation),
             ..Default::default()
         });
 
         let surface = instance.create_surface(window.clone()).unwrap();
 
-        let (device, queue, surface_config) =
-            pollster::block_on(init_gpu(&instance, &surface, &window));
+        let (adapter, device, queue, surface_config) =
+            pollster::block_on(init_gpu(&instance, &surface, &window, self.config.world_size));
 
         surface.configure(&device, &surface_config);
-
-        let mut world = WorldState::new(&device);
+        let gpu_errors = GpuErrorLog::new(&device, gpu_validation);
+
+        // `--world` was only range-checked: fall back to the default size
+        // when this GPU cannot hold the one asked for
+        let mut size = self.config.world_size;
+        let mut size_error = None;
+        if let Err(e) = resize::check_startup_size(size, self.config.grid, &device.limits()) {
+            log::warn!("A {}×{} world does not fit this GPU: {}", size[0], size[1], e);
+            size = DEFAULT_WORLD_SIZE;
+            crash::set_world(size);
+            size_error = Some(trf("status.world_size_refused", &[("error", &e)]));
+        }
+        let mut world = WorldState::new_with_grid(&device, &queue, None, self.config.grid, size);
+        let mut load_error = None;
         if let Some(path) = &self.config.initial_state_path {
             match state_io::load_snapshot(path) {
                 Ok(snapshot) => {
-                    if world.apply_snapshot(&queue, &snapshot) {
+                    if world.apply_snapshot(&queue, &state_io::fit_snapshot(snapshot, size)) {
                         log::info!("Loaded simulation state from {}", path);
                     } else {
                         log::warn!("State file {} has incompatible dimensions; using fresh world", path);
@@ -127,36 +222,27 @@ impl ApplicationHandler for App {
                 }
                 Err(err) => {
                     log::warn!("Failed to load state from {}: {}", path, err);
+                    load_error = Some(trf("status.state_load_failed", &[("path", path), ("error", &err.to_string())]));
                 }
             }
         }
-        let pipelines = create_pipelines(&device, &world, surface_config.format);
+        for warning in MemoryReport::for_world(&world, &device.limits()).warnings() {
+            log::warn!(
+                "Buffer {} ({}) is at {:.0}% of the device limit: {}",
+                warning.buffer,
+                format_bytes(warning.bytes),
+                warning.fraction() * 100.0,
+                tr(warning.limit_key),
+            );
+        }
+        let pipelines = gpu_errors.scope(&device, "create_pipelines", || create_pipelines(&device, &world, HDR_FORMAT));
+        let postfx = PostFx::new(&device, surface_config.format, surface_config.width, surface_config.height);
         let hud = HudRenderer::new(&device, &queue, surface_config.format);
 
         // ---- Initialize egui ----
         let egui_ctx = egui::Context::default();
         
-        // Enhanced dark theme - OPAQUE backgrounds with vibrant accents
-        let mut visuals = egui::Visuals::dark();
-        // Fully opaque panel backgrounds
-        visuals.window_fill = egui::Color32::from_rgb(22, 24, 32);
-        visuals.panel_fill = egui::Color32::from_rgb(18, 20, 28);
-        visuals.extreme_bg_color = egui::Color32::from_rgb(12, 14, 20);
-        visuals.faint_bg_color = egui::Color32::from_rgb(28, 32, 42);
-        // Vibrant accent colors
-        visuals.window_stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(70, 130, 180));
-        visuals.widgets.noninteractive.bg_fill = egui::Color32::from_rgb(32, 36, 48);
-        visuals.widgets.noninteractive.fg_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(160, 170, 190));
-        visuals.widgets.inactive.bg_fill = egui::Color32::from_rgb(45, 50, 65);
-        visuals.widgets.inactive.fg_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(180, 190, 210));
-        visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(60, 90, 130);
-        visuals.widgets.hovered.fg_stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(220, 230, 255));
-        visuals.widgets.active.bg_fill = egui::Color32::from_rgb(80, 140, 200);
-        visuals.widgets.active.fg_stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
-        visuals.selection.bg_fill = egui::Color32::from_rgb(50, 120, 180);
-        visuals.selection.stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 180, 255));
-        visuals.hyperlink_color = egui::Color32::from_rgb(100, 200, 255);
-        egui_ctx.set_visuals(visuals);
+        egui_ctx.set_visuals(UiTheme::Dark.visuals());
         
         // Larger default font size for better readability
         let mut style = (*egui_ctx.style()).clone();
@@ -199,22 +285,34 @@ impl ApplicationHandler for App {
 
         log::info!(
             "EvoLenia v2 Research Lab initialized: {}x{}, target mass = {:.0}",
-            WORLD_WIDTH,
-            WORLD_HEIGHT,
-            target_total_mass()
+            size[0],
+            size[1],
+            target_mass_of(size)
         );
 
+        let gpu_timer = GpuStepTimer::new(&device, &queue);
+        if gpu_timer.is_none() {
+            log::info!("GPU timestamps unavailable: step budget uses frame time only");
+        }
+
         self.state = Some(AppState {
+            instance,
+            adapter,
             device,
             queue,
             surface,
             surface_config,
             world,
             pipelines,
+            postfx,
+            user_modes: Vec::new(),
             window: window.clone(),
+            analysis_window: None,
             camera: CameraState::default(),
             keys: KeysHeld::default(),
-            sim_params: SimulationParams::default(),
+            mouse: MouseState::default(),
+            sim_params: SimulationParams { grid: self.config.grid, ..Default::default() },
+            display_lut_key: None,
             hud,
             egui_ctx,
             egui_winit_state,
@@ -222,32 +320,143 @@ impl ApplicationHandler for App {
             lab: LabState::default(),
             last_redraw: Instant::now(),
             fps: 0.0,
+            gpu_timer,
+            step_budget: StepBudget::default(),
+            render_clock: RenderClock::new(Instant::now()),
+            speed_guard: SpeedGuard::default(),
+            last_turn: None,
+            vsync_applied: false,
+            gpu_errors,
+            captures: Capturer::default(),
+            focused: true,
+            occluded: false,
+            background_clock: BackgroundClock::new(Instant::now()),
             last_diag: None,
             diag_interval: self.config.diag_interval.max(1),
         });
 
+        if let Some(state) = &mut self.state {
+            state.lab.world_size = size;
+            state.lab.resize.target = size;
+            state.lab.smooth_sampling_supported = smooth_sampling_supported(&state.device);
+            reload_user_modes(state);
+            if let Some(msg) = size_error.or(load_error) {
+                state.lab.set_warning(msg);
+            }
+        }
+
+        // Restore the previous session's layout and camera
+        if let (Some(state), Some(settings)) = (&mut self.state, &settings) {
+            settings.apply(&mut state.lab, &mut state.camera);
+            session_log::set_level(state.lab.session_log_level);
+            state.egui_ctx.set_zoom_factor(state.lab.ui_scale);
+            log::info!("Restored session settings from {}", SETTINGS_PATH);
+        }
+
+        // First launch: benchmark the GPU once the first frame is up;
+        // later launches restore the settings chosen then
+        if let Some(state) = &mut self.state {
+            match &state.lab.startup_choice {
+                Some(choice) if choice.applied => {
+                    state.sim_params.simulation_speed = choice.recommendation.simulation_speed;
+                    state.sim_params.vsync = choice.recommendation.vsync;
+                }
+                Some(_) => {}
+                None => state.lab.startup_bench_requested = true,
+            }
+            state.lab.gpu_validation_active = state.gpu_errors.enabled;
+        }
+
         // Initial redraw — required on macOS with winit 0.30
         window.request_redraw();
     }
 
-    fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
-        if let Some(state) = &self.state {
-            state.window.request_redraw();
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let Some(state) = &mut self.state else {
+            return;
+        };
+        sync_analysis_window(state, event_loop);
+        if !is_throttled(state) {
+            let now = Instant::now();
+            let simulating = !state.sim_params.paused && !state.lab.playback.is_active();
+            if let Some(last) = state.last_turn.filter(|_| simulating) {
+                observe_turn(state, now.duration_since(last));
+            }
+            state.last_turn = simulating.then_some(now);
+            match state.render_clock.due(&state.sim_params.render_cadence, now, simulating) {
+                RenderDue::Now => {
+                    event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+                    state.window.request_redraw();
+                }
+                // Between rendered frames: simulate only, as in the background
+                RenderDue::Simulate => {
+                    event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+                    let steps = if state.sim_params.step_budget.enabled {
+                        state.lab.steps_per_frame.max(1)
+                    } else {
+                        state.speed_guard.steps(state.sim_params.simulation_speed)
+                    };
+                    run_unrendered_batch(state, steps);
+                }
+                RenderDue::WaitUntil(at) => {
+                    event_loop.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(at));
+                }
+            }
+            if let Some(analysis) = state.analysis_window.as_ref().filter(|w| w.redraw_due(Instant::now())) {
+                analysis.window.request_redraw();
+            }
+            return;
+        }
+
+        // Background: simulate at the throttled rate without rendering
+        state.last_turn = None;
+        let rate = state.lab.background_throttle.steps_per_sec;
+        if state.sim_params.paused || state.lab.playback.is_active() || rate == 0 {
+            event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait);
+            return;
+        }
+        let now = Instant::now();
+        let steps = state.background_clock.steps_due(now, rate);
+        if steps > 0 {
+            run_unrendered_batch(state, steps);
+        }
+        event_loop.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(
+            state.background_clock.next_wake(now, rate),
+        ));
+    }
+
+    fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        if let Some(state) = &mut self.state {
+            if let Some(analysis) = &state.analysis_window {
+                state.lab.analysis_window = Some(analysis.geometry());
+            }
+            let settings = UiSettings::capture(&state.lab, &state.camera, Some(window_geometry(&state.window)));
+            match settings.save(SETTINGS_PATH) {
+                Ok(()) => log::info!("Session settings saved to {}", SETTINGS_PATH),
+                Err(e) => log::error!("{}", e),
+            }
         }
     }
 
     fn window_event(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
-        _window_id: winit::window::WindowId,
+        window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
         let Some(state) = &mut self.state else {
             return;
         };
+        if state.analysis_window.as_ref().is_some_and(|w| w.id() == window_id) {
+            handle_analysis_window_event(state, event);
+            return;
+        }
 
         // Pass events to egui first
         let egui_response = state.egui_winit_state.on_window_event(&state.window, &event);
+        // The dock area covers the whole window, so egui claims every pointer
+        // event; pointer input over the simulation tab still belongs to the view.
+        let pointer_consumed = egui_response.consumed && !state.lab.viewport_hovered;
 
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
@@ -258,25 +467,75 @@ impl ApplicationHandler for App {
                 handle_keyboard(state, event_loop, &event, egui_response.consumed);
             }
 
-            WindowEvent::MouseWheel { delta, .. } => {
-                if !egui_response.consumed {
-                    let scroll = match &delta {
-                        MouseScrollDelta::LineDelta(_, y) => *y,
-                        MouseScrollDelta::PixelDelta(pos) => pos.y as f32 * 0.01,
-                    };
+            WindowEvent::MouseWheel { delta, .. } if !pointer_consumed => {
+                let scroll = match &delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32 * 0.01,
+                };
+                if state.camera.surface_view {
+                    state.camera.orbit.apply_scroll(scroll);
+                } else if state.camera.snap_zoom {
+                    state.camera.step_integer_zoom(
+                        scroll,
+                        state.surface_config.width,
+                        state.surface_config.height,
+                        state.world.size,
+                    );
+                } else {
                     state.camera.apply_scroll(scroll);
                 }
             }
 
-            WindowEvent::Resized(new_size) => {
-                if new_size.width > 0 && new_size.height > 0 {
-                    state.surface_config.width = new_size.width;
-                    state.surface_config.height = new_size.height;
-                    state.surface.configure(&state.device, &state.surface_config);
+            WindowEvent::CursorMoved { position, .. } => {
+                state.mouse.position = [position.x as f32, position.y as f32];
+                if let Some(last) = state.mouse.orbit_drag {
+                    let [x, y] = state.mouse.position;
+                    state.camera.orbit.orbit(x - last[0], y - last[1]);
+                    state.mouse.orbit_drag = Some(state.mouse.position);
                 }
+                if state.mouse.flow_stroke.is_some() {
+                    continue_flow_stroke(state);
+                }
+                if state.mouse.dragging_profile {
+                    let uv = cursor_world_uv(state);
+                    if let Some(line) = &mut state.lab.profile_line {
+                        line.end = uv;
+                    }
+                }
+            }
+
+            WindowEvent::MouseInput {
+                state: button_state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let pressed = button_state.is_pressed();
+                if state.camera.surface_view {
+                    handle_orbit_drag(state, pressed, pointer_consumed);
+                } else if !handle_portrait_pick(state, pressed, pointer_consumed)
+                    && !handle_transplant_pick(state, pressed, pointer_consumed)
+                    && !handle_landscape_pick(state, pressed, pointer_consumed)
+                    && !handle_fossil_pick(state, pressed, pointer_consumed)
+                    && !handle_reserve_pick(state, pressed, pointer_consumed)
+                    && !handle_flow_brush(state, pressed, pointer_consumed)
+                {
+                    handle_profile_drag(state, pressed, pointer_consumed);
+                }
+            }
+
+            WindowEvent::Resized(new_size) if new_size.width > 0 && new_size.height > 0 => {
+                state.surface_config.width = new_size.width;
+                state.surface_config.height = new_size.height;
+                state.surface.configure(&state.device, &state.surface_config);
+                resize_scene_target(state);
             }
 
-            WindowEvent::RedrawRequested => {
+            WindowEvent::Focused(focused) => set_background_state(state, focused, state.occluded),
+
+            WindowEvent::Occluded(occluded) => set_background_state(state, state.focused, occluded),
+
+            // Throttled windows are not rendered at all
+            WindowEvent::RedrawRequested if !is_throttled(state) => {
                 redraw(state);
             }
 
@@ -285,13 +544,137 @@ impl ApplicationHandler for App {
     }
 }
 
+// ======================== Speed Guard ========================
+
+/// Feed the speed guard one event loop turn, logging when it starts and
+/// stops holding the speed down. The step budget paces itself instead.
+fn observe_turn(state: &mut AppState, turn: Duration) {
+    let speed = state.sim_params.simulation_speed;
+    if state.sim_params.step_budget.enabled {
+        state.speed_guard = SpeedGuard::default();
+        state.lab.speed_limit = None;
+        return;
+    }
+    let turn_ms = turn.as_secs_f32() * 1000.0;
+    state.speed_guard.observe(&state.sim_params.speed_guard, turn_ms, speed);
+    let limit = state.speed_guard.limited(speed);
+    match (state.lab.speed_limit, limit) {
+        (None, Some(steps)) => state.lab.log_event_with_severity(
+            state.world.frame,
+            EventSeverity::Warn,
+            "SPEED_GUARD",
+            &format!("Speed reduced to {} of {} steps per frame ({:.0} ms per frame)", steps, speed, turn_ms),
+        ),
+        (Some(_), None) => state.lab.log_event(state.world.frame, "SPEED_GUARD", &format!("Speed restored to {} steps per frame", speed)),
+        _ => {}
+    }
+    state.lab.speed_limit = limit;
+}
+
+// ======================== Background Throttling ========================
+
+/// Unfocused, covered, or minimized with the low-power mode enabled.
+fn is_throttled(state: &AppState) -> bool {
+    let analysis_focused = state.analysis_window.as_ref().is_some_and(|w| w.focused);
+    state.lab.background_throttle.enabled
+        && ((!state.focused && !analysis_focused) || state.occluded || state.window.is_minimized() == Some(true))
+}
+
+fn set_background_state(state: &mut AppState, focused: bool, occluded: bool) {
+    let was_throttled = is_throttled(state);
+    state.focused = focused;
+    state.occluded = occluded;
+    background_transition(state, was_throttled);
+}
+
+/// Start or end background throttling after a focus or visibility change.
+fn background_transition(state: &mut AppState, was_throttled: bool) {
+    match (was_throttled, is_throttled(state)) {
+        (false, true) => {
+            state.background_clock.reset(Instant::now());
+            log::info!("Window in background: rendering paused, simulation throttled");
+        }
+        (true, false) => {
+            // Don't count the time away as one long frame
+            state.last_redraw = Instant::now();
+            state.window.request_redraw();
+        }
+        _ => {}
+    }
+}
+
+// ======================== Analysis Window ========================
+
+/// Open or close the detached analysis window to match the lab toggles.
+/// A window that cannot be opened docks the panel back.
+fn sync_analysis_window(state: &mut AppState, event_loop: &winit::event_loop::ActiveEventLoop) {
+    let wanted = state.lab.show_analysis_panel && state.lab.analysis_detached;
+    match (&state.analysis_window, wanted) {
+        (None, true) => {
+            match AnalysisWindow::open(
+                event_loop,
+                "EvoLenia v2 — Analysis",
+                &state.instance,
+                &state.adapter,
+                &state.device,
+                state.surface_config.present_mode,
+                state.lab.analysis_window,
+            ) {
+                Ok(window) => state.analysis_window = Some(window),
+                Err(e) => {
+                    log::warn!("Could not open the analysis window: {}", e);
+                    state.lab.analysis_detached = false;
+                    state.lab.set_warning(trf("status.analysis_window_failed", &[("error", &e)]));
+                }
+            }
+        }
+        (Some(window), false) => {
+            state.lab.analysis_window = Some(window.geometry());
+            state.analysis_window = None;
+        }
+        _ => {}
+    }
+}
+
+/// Events of the detached analysis window: egui input, resizing, focus and
+/// redraws. Closing it hides the panel; it reopens detached (F9).
+fn handle_analysis_window_event(state: &mut AppState, event: WindowEvent) {
+    let Some(analysis) = &mut state.analysis_window else {
+        return;
+    };
+    analysis.on_window_event(&event);
+    match event {
+        WindowEvent::CloseRequested => state.lab.show_analysis_panel = false,
+        WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+            analysis.resize(&state.device, size.width, size.height);
+        }
+        WindowEvent::Focused(focused) => {
+            let was_throttled = is_throttled(state);
+            if let Some(analysis) = &mut state.analysis_window {
+                analysis.focused = focused;
+            }
+            background_transition(state, was_throttled);
+        }
+        WindowEvent::RedrawRequested => {
+            i18n::set_language(state.lab.language);
+            let params = &mut state.sim_params;
+            let lab = &mut state.lab;
+            analysis.redraw(&state.device, &state.queue, &state.egui_ctx, |ctx| {
+                lab_ui::render_analysis_window(ctx, params, lab);
+            });
+        }
+        _ => {}
+    }
+}
+
 // ======================== GPU Initialization ========================
 
 async fn init_gpu(
     instance: &wgpu::Instance,
     surface: &wgpu::Surface<'_>,
     window: &Window,
-) -> (wgpu::Device, wgpu::Queue, wgpu::SurfaceConfiguration) {
+    world_size: [u32; 2],
+) -> (wgpu::Adapter, wgpu::Device, wgpu::Queue, wgpu::SurfaceConfiguration) {
     let adapter = instance
         .request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::HighPerformance,
@@ -305,12 +688,15 @@ async fn init_gpu(
         );
 
     log::info!("GPU: {}", adapter.get_info().name);
+    crash::set_gpu(adapter.get_info(), world_size);
 
     let (device, queue) = adapter
         .request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("evolenia_device"),
-                required_features: wgpu::Features::empty(),
+                // Optional: GPU step timing for the wall-clock step budget,
+                // filterable f32 textures for smooth render sampling
+                required_features: adapter.features() & (wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::FLOAT32_FILTERABLE),
                 required_limits: wgpu::Limits {
                     max_storage_buffers_per_shader_stage: 12,
                     max_storage_buffer_binding_size: 256 * 1024 * 1024,
@@ -355,7 +741,7 @@ async fn init_gpu(
         desired_maximum_frame_latency: 2,
     };
 
-    (device, queue, surface_config)
+    (adapter, device, queue, surface_config)
 }
 
 // ======================== Keyboard Handling ========================
@@ -410,6 +796,9 @@ fn handle_keyboard(
             "r" | "R" if pressed => {
                 state.lab.restart_requested = true;
             }
+            "t" | "T" if pressed => {
+                state.camera.surface_view = !state.camera.surface_view;
+            }
             "h" | "H" if pressed => {
                 state.sim_params.show_extended_ui = !state.sim_params.show_extended_ui;
             }
@@ -420,13 +809,7 @@ fn handle_keyboard(
             "5" if pressed => state.sim_params.visualization_mode = 4,
             "v" | "V" if pressed => {
                 state.sim_params.vsync = !state.sim_params.vsync;
-                let mode = if state.sim_params.vsync {
-                    wgpu::PresentMode::AutoVsync
-                } else {
-                    wgpu::PresentMode::Immediate
-                };
-                state.surface_config.present_mode = mode;
-                state.surface.configure(&state.device, &state.surface_config);
+                sync_vsync(state);
             }
             "[" if pressed => {
                 state.sim_params.mutation_rate =
@@ -442,7 +825,7 @@ fn handle_keyboard(
         Key::Named(named) => match named {
             NamedKey::Tab if pressed => {
                 state.sim_params.visualization_mode =
-                    (state.sim_params.visualization_mode + 1) % VIS_MODE_COUNT;
+                    (state.sim_params.visualization_mode + 1) % (VIS_MODE_COUNT + state.user_modes.len() as u32);
             }
             NamedKey::ArrowUp if pressed => {
                 state.sim_params.time_step =
@@ -467,8 +850,186 @@ fn handle_keyboard(
     }
 }
 
+// ======================== Session Settings ========================
+
+/// Current window geometry for the settings file.
+pub(crate) fn window_geometry(window: &Window) -> WindowGeometry {
+    let size = window.inner_size();
+    WindowGeometry {
+        width: size.width,
+        height: size.height,
+        position: window.outer_position().ok().map(|p| [p.x, p.y]),
+        maximized: window.is_maximized(),
+    }
+}
+
+// ======================== Mouse Handling ========================
+
+/// World-UV coordinate under the cursor.
+fn cursor_world_uv(state: &AppState) -> [f32; 2] {
+    let (w, h) = (state.surface_config.width, state.surface_config.height);
+    let screen_uv = [state.mouse.position[0] / w as f32, state.mouse.position[1] / h as f32];
+    state.camera.screen_to_world(screen_uv, w, h, state.world.size)
+}
+
+/// While picking is armed, a left click on the world chooses the creature to
+/// portrait and starts the capture. True when the click was used.
+fn handle_portrait_pick(state: &mut AppState, pressed: bool, egui_consumed: bool) -> bool {
+    if !pressed || egui_consumed || !state.lab.portrait_pick_active {
+        return false;
+    }
+    state.lab.portrait_center = cursor_world_uv(state);
+    state.lab.portrait_pick_active = false;
+    state.lab.portrait_requested = true;
+    true
+}
+
+/// While a transplant end is armed, a left click on the world moves it
+/// there. True when the click was used.
+fn handle_transplant_pick(state: &mut AppState, pressed: bool, egui_consumed: bool) -> bool {
+    if !pressed || egui_consumed {
+        return false;
+    }
+    let uv = cursor_world_uv(state);
+    if std::mem::take(&mut state.lab.transplant_pick_from) {
+        state.lab.transplant_from = uv;
+    } else if std::mem::take(&mut state.lab.transplant_pick_to) {
+        state.lab.transplant_to = uv;
+    } else {
+        return false;
+    }
+    true
+}
+
+/// While the landscape probe is armed, a left click on the world moves its
+/// center there. True when the click was used.
+fn handle_landscape_pick(state: &mut AppState, pressed: bool, egui_consumed: bool) -> bool {
+    if !pressed || egui_consumed || !std::mem::take(&mut state.lab.landscape.pick) {
+        return false;
+    }
+    state.lab.landscape.settings.center = cursor_world_uv(state);
+    true
+}
+
+/// While the fossil spawner is armed, a left click on the world moves its
+/// center there. True when the click was used.
+fn handle_fossil_pick(state: &mut AppState, pressed: bool, egui_consumed: bool) -> bool {
+    if !pressed || egui_consumed || !std::mem::take(&mut state.lab.fossils.spawn_pick) {
+        return false;
+    }
+    state.lab.fossils.spawn_center = cursor_world_uv(state);
+    true
+}
+
+/// While a reserve zone is being placed, a left click moves a circle there
+/// (and ends placing) or adds a polygon vertex. True when the click was used.
+fn handle_reserve_pick(state: &mut AppState, pressed: bool, egui_consumed: bool) -> bool {
+    if !pressed || egui_consumed {
+        return false;
+    }
+    let uv = cursor_world_uv(state);
+    let Some(zone) = state.lab.reserve_pick.and_then(|k| state.sim_params.reserves.get_mut(k)) else {
+        return false;
+    };
+    match &mut zone.shape {
+        ReserveShape::Circle { center, .. } => {
+            *center = uv;
+            state.lab.reserve_pick = None;
+        }
+        ReserveShape::Polygon { points } => points.push(uv),
+    }
+    true
+}
+
+/// Left-button drag orbits the camera in the 3D surface view (world tools
+/// work on the flat view only).
+fn handle_orbit_drag(state: &mut AppState, pressed: bool, egui_consumed: bool) {
+    state.mouse.orbit_drag = (pressed && !egui_consumed).then_some(state.mouse.position);
+}
+
+/// Left-button drag paints the flow field along the stroke while the flow
+/// brush is armed. True when the press or release belonged to the brush.
+fn handle_flow_brush(state: &mut AppState, pressed: bool, egui_consumed: bool) -> bool {
+    if pressed {
+        if egui_consumed || !state.lab.flow_brush_active {
+            return false;
+        }
+        state.mouse.flow_stroke = Some(cursor_world_cell(state));
+        return true;
+    }
+    if state.mouse.flow_stroke.take().is_none() {
+        return false;
+    }
+    let action = if state.lab.flow_brush_erase { "erased" } else { "painted" };
+    state.lab.log_event(state.world.frame, "FLOW", &format!("Flow field {} by brush", action));
+    true
+}
+
+/// Brush the flow field from the last stroke point to the cursor: the
+/// stroke direction is the wind painted (nothing when erasing).
+fn continue_flow_stroke(state: &mut AppState) {
+    let Some(last) = state.mouse.flow_stroke else {
+        return;
+    };
+    let cell = cursor_world_cell(state);
+    let delta = [cell[0] - last[0], cell[1] - last[1]];
+    let len = delta[0].hypot(delta[1]);
+    if len < 1.0 {
+        return; // wait for a direction to emerge
+    }
+    let dir = if state.lab.flow_brush_erase { [0.0; 2] } else { [delta[0] / len, delta[1] / len] };
+    state.world.flow.paint(cell, dir, state.lab.flow_brush_radius, state.world.size);
+    state.world.upload_flow(&state.queue);
+    state.mouse.flow_stroke = Some(cell);
+}
+
+/// World cell coordinate (continuous) under the cursor.
+fn cursor_world_cell(state: &AppState) -> [f32; 2] {
+    let uv = cursor_world_uv(state);
+    [uv[0] * state.world.size[0] as f32, uv[1] * state.world.size[1] as f32]
+}
+
+/// Left-button drag draws the cross-section line while the profile tool is active.
+fn handle_profile_drag(state: &mut AppState, pressed: bool, egui_consumed: bool) {
+    if pressed {
+        if egui_consumed || !state.lab.profile_tool_active {
+            return;
+        }
+        let uv = cursor_world_uv(state);
+        state.lab.profile_line = Some(ProfileLine { start: uv, end: uv });
+        state.mouse.dragging_profile = true;
+    } else if state.mouse.dragging_profile {
+        state.mouse.dragging_profile = false;
+        match state.lab.profile_line {
+            Some(line) if line.length_px(state.world.size) >= 2.0 => {
+                state.lab.profile_refresh_requested = true;
+                state.lab.log_event(
+                    state.world.frame,
+                    "PROFILE",
+                    &format!(
+                        "Line ({:.3},{:.3}) → ({:.3},{:.3}), {:.0} px",
+                        line.start[0], line.start[1], line.end[0], line.end[1], line.length_px(state.world.size),
+                    ),
+                );
+            }
+            _ => {
+                state.lab.profile_line = None;
+                state.lab.profile_samples.clear();
+            }
+        }
+    }
+}
+
 // ======================== Frame Rendering ========================
 
+/// Size the offscreen scene target for the window and the camera's render
+/// scale (the composite resamples it onto the swapchain).
+fn resize_scene_target(state: &mut AppState) {
+    let max_dim = state.device.limits().max_texture_dimension_2d;
+    let [w, h] = state.camera.render_size(state.surface_config.width, state.surface_config.height, max_dim);
+    state.postfx.resize(&state.device, w, h);
+}
+
 fn redraw(state: &mut AppState) {
     // Get window dimensions early (needed for camera aspect ratio)
     let win_w = state.surface_config.width;
@@ -478,68 +1039,228 @@ fn redraw(state: &mut AppState) {
     let now = Instant::now();
     let dt = now.duration_since(state.last_redraw).as_secs_f32().max(0.0001);
     state.last_redraw = now;
+    state.render_clock.rendered(now);
+    crash::refresh(state.world.frame, &state.sim_params, &state.lab.events);
     state.fps = state.fps * 0.95 + (1.0 / dt) * 0.05;
+    let mut timing = FrameTiming { frame_ms: dt * 1000.0, ..Default::default() };
 
     // Camera movement from held keys
-    state
-        .camera
-        .apply_pan(state.keys.w, state.keys.s, state.keys.a, state.keys.d);
-    state
-        .camera
-        .apply_zoom_keys(state.keys.e, state.keys.q);
+    if state.camera.surface_view {
+        let orbit = &mut state.camera.orbit;
+        orbit.apply_pan(state.keys.w, state.keys.s, state.keys.a, state.keys.d, state.world.size);
+        orbit.apply_zoom_keys(state.keys.e, state.keys.q);
+        orbit.advance(dt);
+    } else {
+        state
+            .camera
+            .apply_pan(state.keys.w, state.keys.s, state.keys.a, state.keys.d);
+        state
+            .camera
+            .apply_zoom_keys(state.keys.e, state.keys.q);
+    }
 
     // Upload camera uniform with window dimensions for aspect ratio correction
     state.queue.write_buffer(
         &state.pipelines.camera_buffer,
         0,
-        bytemuck::bytes_of(&state.camera.uniforms(win_w, win_h)),
+        bytemuck::bytes_of(&state.camera.uniforms(win_w, win_h, state.world.size)),
     );
 
-    // Upload render params with current visualization mode
+    // Upload render params with current visualization mode and display adjustment
+    let adjust = *state.sim_params.current_display_adjust();
     let render_params = RenderParams {
-        width: WORLD_WIDTH,
-        height: WORLD_HEIGHT,
+        width: state.world.size[0],
+        height: state.world.size[1],
         visualization_mode: state.sim_params.visualization_mode,
-        _pad: 0,
+        diff_range: state.sim_params.diff_overlay.range,
+        exposure: adjust.exposure,
+        gamma: adjust.gamma,
+        range_min: adjust.range_min,
+        range_max: adjust.range_max,
+        transform: adjust.transform.shader_id(),
+        hdr_emission: if state.sim_params.bloom.enabled {
+            state.sim_params.bloom.emission
+        } else {
+            0.0
+        },
+        trail_opacity: if state.sim_params.trail.enabled {
+            state.sim_params.trail.opacity
+        } else {
+            0.0
+        },
+        diff_opacity: if state.sim_params.diff_overlay.enabled && state.lab.diff_reference.is_some() {
+            state.sim_params.diff_overlay.opacity
+        } else {
+            0.0
+        },
+        territory_opacity: if state.sim_params.territory.enabled && state.lab.territory.samples() > 0 {
+            state.sim_params.territory.opacity
+        } else {
+            0.0
+        },
+        particle_opacity: if state.sim_params.particles.enabled {
+            state.sim_params.particles.opacity
+        } else {
+            0.0
+        },
+        flow_opacity: state.sim_params.flow.overlay_opacity,
+        species_colors: (state.sim_params.species_map.enabled && state.sim_params.species_map.colors) as u32,
     };
     state.queue.write_buffer(
         &state.world.render_params_buffer,
         0,
         bytemuck::bytes_of(&render_params),
     );
+    state.queue.write_buffer(
+        &state.world.palette_buffer,
+        0,
+        bytemuck::bytes_of(&state.lab.palette.uniforms()),
+    );
+    state.world.upload_species_palette(&state.queue, &state.lab.palette.uniforms().cyclic);
+    resize_scene_target(state); // follows render scale edits
+    if state.sim_params.vsync != state.vsync_applied {
+        sync_vsync(state);
+    }
+    state.postfx.update(&state.queue, &state.sim_params.bloom);
+
+    if state.egui_ctx.style().visuals.dark_mode != (state.lab.ui_theme == UiTheme::Dark) {
+        state.egui_ctx.set_visuals(state.lab.ui_theme.visuals());
+    }
 
     // ---- egui frame ----
+    i18n::set_language(state.lab.language);
+    state.lab.current_frame = state.world.frame;
+    state.lab.world_size = state.world.size;
+    state.lab.sync_custom_metrics(&state.sim_params.custom_metrics);
+    state.lab.alerts.sync(&state.sim_params.alert_rules);
+    if state.lab.show_resources_panel {
+        let report = MemoryReport::for_world(&state.world, &state.device.limits());
+        state.lab.memory_report = Some(report.with_cpu_usage(&state.lab));
+    }
+    state.lab.poll_jobs(state.world.frame);
+    let egui_start = Instant::now();
     let raw_input = state.egui_winit_state.take_egui_input(&state.window);
+    let zoom_before = state.egui_ctx.zoom_factor();
     let full_output = state.egui_ctx.run(raw_input, |ctx| {
-        lab_ui::render_lab_ui(ctx, &mut state.sim_params, &mut state.lab);
+        lab_ui::render_lab_ui(ctx, &mut state.sim_params, &mut state.lab, &mut state.camera);
     });
     state
         .egui_winit_state
         .handle_platform_output(&state.window, full_output.platform_output);
+    timing.egui_ms += elapsed_ms(egui_start);
+
+    // UI scale: slider edits win; otherwise follow egui's own Ctrl +/- zoom
+    if state.lab.ui_scale != zoom_before {
+        state.egui_ctx.set_zoom_factor(state.lab.ui_scale);
+    } else {
+        state.lab.ui_scale = state.egui_ctx.zoom_factor();
+    }
 
     // ---- Handle lab actions ----
+    if state.lab.zoom_snap_requested {
+        state.camera.snap_to_integer_zoom(win_w, win_h, state.world.size);
+        state.lab.zoom_snap_requested = false;
+    }
+
+    update_experiment_queue(state);
+    save_pending_thumbnail(state); // before a queued restart replaces the final state
+
     // Restart
     if state.lab.restart_requested {
+        stop_tape(state); // frames of the new world would not follow on
+        close_playback(state);
+        let restart_start = Instant::now();
         let seed = state.sim_params.effective_seed();
-        state.world = WorldState::new_with_seed(&state.device, seed);
-        state.pipelines =
-            create_pipelines(&state.device, &state.world, state.surface_config.format);
+        // Same-size world: reinitialize in place, keeping the buffers, the
+        // bind groups on them and the (authored, not simulated) flow field.
+        // The grid is compiled into the shaders: only it forces a rebuild.
+        let grid_changed = state.world.grid != state.sim_params.grid;
+        state.world.reset(&state.queue, seed, state.sim_params.grid);
+        if grid_changed {
+            state.pipelines = state.gpu_errors.scope(&state.device, "create_pipelines", || {
+                create_pipelines(&state.device, &state.world, HDR_FORMAT)
+            });
+            reload_user_modes(state);
+        }
         state.lab.restart_requested = false;
+        state.lab.clear_barrier();
+        state.lab.transplants.clear();
+        state.lab.speciation.reset();
+        state.lab.rewind.clear();
         state.last_diag = None;
+        state.display_lut_key = None;
         state.lab.log_event(state.world.frame, "RESTART", "Simulation restarted");
         if let Some(s) = seed {
-            state.lab.log_event(state.world.frame, "SEED", &format!("Seed: {}", s));
+            state.lab.log_event_with_payload(
+                state.world.frame,
+                EventSeverity::Info,
+                "SEED",
+                &format!("Seed: {}", s),
+                serde_json::json!({ "seed": s }),
+            );
         }
-        log::info!("Simulation restarted (seed: {:?})", seed);
+        log::info!(
+            "Simulation restarted in {:.0} ms (seed: {:?}, pipelines {})",
+            elapsed_ms(restart_start),
+            seed,
+            if grid_changed { "rebuilt" } else { "reused" },
+        );
+    }
+
+    // ---- World resize: built in the background, swapped in here ----
+    if std::mem::take(&mut state.lab.resize.requested) {
+        start_world_resize(state);
+    }
+    poll_world_resize(state);
+
+    // ---- User visualization modes ----
+    if std::mem::take(&mut state.lab.user_modes_reload_requested) {
+        reload_user_modes(state);
+    }
+
+    // ---- Flow field presets ----
+    if state.lab.flow_generate_requested || state.lab.flow_clear_requested {
+        handle_flow_requests(state);
     }
 
+    // ---- Tape playback (stands in for the simulation while open) ----
+    if let Some(path) = state.lab.playback.open_path.take() {
+        open_playback(state, &path);
+    }
+    if std::mem::take(&mut state.lab.playback.close_requested) {
+        close_playback(state);
+    }
+    if let Some(range) = state.lab.playback.export_requested.take() {
+        start_sequence_export(state, range);
+    }
+    show_playback_frame(state, dt);
+
+    let [width, height] = state.world.size;
+    let dispatch_x = width.div_ceil(WORKGROUP_X);
+    let dispatch_y = height.div_ceil(WORKGROUP_Y);
+    let dispatch_linear = (width * height).div_ceil(WORKGROUP_LINEAR);
+
     // ---- Handle perturbation ----
-    if state.sim_params.perturbation_active {
-        state.world.apply_perturbation(
-            &state.device,
-            &state.queue,
-            &state.sim_params,
-        );
+    if state.sim_params.perturbation_active && state.sim_params.perturbation_type.plugin().barrier() {
+        let x = state.sim_params.perturbation_center_x * width as f32;
+        state.world.raise_barrier(&state.queue, x, state.sim_params.barrier_frames);
+        state.sim_params.perturbation_active = false;
+        state.lab.clear_barrier();
+        state.lab.barrier_split = Some(x);
+        log::info!("Barrier raised at x={:.0} for {} frames", x, state.sim_params.barrier_frames);
+        state.lab.set_status(trf(
+            "status.barrier_raised",
+            &[("frames", &state.sim_params.barrier_frames)],
+        ));
+    } else if state.sim_params.perturbation_active {
+        state.world.update_perturbation_uniform(&state.queue, &state.sim_params, state.sim_params.perturbation_intensity);
+        let mut encoder = state
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("perturbation_encoder"),
+            });
+        encode_perturbation_pass(&mut encoder, &state.pipelines, state.world.cur(), dispatch_x, dispatch_y);
+        state.queue.submit(std::iter::once(encoder.finish()));
         state.sim_params.perturbation_active = false;
         log::info!(
             "Perturbation applied: {} intensity={:.2} radius={:.2}",
@@ -547,9 +1268,9 @@ fn redraw(state: &mut AppState) {
             state.sim_params.perturbation_intensity,
             state.sim_params.perturbation_radius,
         );
-        state.lab.set_status(format!(
-            "Perturbation '{}' applied",
-            state.sim_params.perturbation_type.name(),
+        state.lab.set_status(trf(
+            "status.perturbation_applied",
+            &[("name", &tr(state.sim_params.perturbation_type.label_key()))],
         ));
     }
 
@@ -562,6 +1283,7 @@ fn redraw(state: &mut AppState) {
             &state.device,
             &state.queue,
             &state.sim_params,
+            state.world.size,
             state.world.frame,
             state.fps,
             state.camera.zoom,
@@ -570,46 +1292,50 @@ fn redraw(state: &mut AppState) {
         );
     }
 
-    let dispatch_x = (WORLD_WIDTH + WORKGROUP_X - 1) / WORKGROUP_X;
-    let dispatch_y = (WORLD_HEIGHT + WORKGROUP_Y - 1) / WORKGROUP_Y;
-    let dispatch_linear = (total_pixels() + 255) / 256;
-
     // ---- Simulation steps ----
-    if !state.sim_params.paused {
-        let steps = state.sim_params.simulation_speed;
-        for _ in 0..steps {
-            state
-                .world
-                .update_step_uniforms_dynamic(&state.queue, &state.sim_params);
-
-            let cur = state.world.cur();
-            let mut sim_encoder = state
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("sim_encoder"),
-                });
-            encode_simulation_passes(
-                &mut sim_encoder,
-                &state.pipelines,
-                cur,
-                dispatch_x,
-                dispatch_y,
-                dispatch_linear,
-            );
-            state.queue.submit(std::iter::once(sim_encoder.finish()));
-            state.world.swap();
-        }
+    let encode_start = Instant::now();
+    if let Some(timer) = &mut state.gpu_timer {
+        timer.poll(&state.device);
+    }
+    let gpu_step_ms = state.gpu_timer.as_ref().and_then(|t| t.last_step_ms);
+    state.lab.gpu_step_ms = gpu_step_ms;
+    if state.lab.playback.is_active() {
+        state.lab.step_requested = false;
+    } else if !state.sim_params.paused {
+        let steps = if state.sim_params.step_budget.enabled {
+            state.step_budget.update(&state.sim_params.step_budget, dt * 1000.0, gpu_step_ms)
+        } else {
+            state.speed_guard.steps(state.sim_params.simulation_speed)
+        };
+        state.lab.steps_per_frame = steps;
+        run_simulation_batch(state, steps);
+        timing.gpu_sim_ms = gpu_step_ms.map(|ms| ms * steps as f32);
     } else if state.lab.step_requested {
         // Single step while paused
-        state
-            .world
-            .update_step_uniforms_dynamic(&state.queue, &state.sim_params);
+        apply_schedules(&mut state.sim_params, state.world.frame);
+        let passes = StepPasses {
+            normalize: state.world.update_step_uniforms_dynamic(&state.queue, &state.sim_params),
+            velocity: state.sim_params.separate_velocity_pass,
+        };
         let cur = state.world.cur();
         let mut sim_encoder = state
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("step_encoder"),
             });
+        if let Some(intensity) = state.sim_params.continuous_perturbation_intensity() {
+            state.world.update_perturbation_uniform(&state.queue, &state.sim_params, intensity);
+            encode_perturbation_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if state.world.barrier_step() {
+            encode_barrier_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if state.sim_params.particles.enabled {
+            encode_particle_passes(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y, &state.sim_params);
+        }
+        if state.world.convolution(&state.sim_params).separable {
+            encode_convolution_passes(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
         encode_simulation_passes(
             &mut sim_encoder,
             &state.pipelines,
@@ -617,13 +1343,36 @@ fn redraw(state: &mut AppState) {
             dispatch_x,
             dispatch_y,
             dispatch_linear,
+            passes,
         );
-        state.queue.submit(std::iter::once(sim_encoder.finish()));
+        if state.sim_params.trail.enabled {
+            encode_trail_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if state.sim_params.averages.enabled {
+            state.world.update_average_uniform(&state.queue, &state.sim_params.averages);
+            encode_average_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        state.gpu_errors.scope(&state.device, "simulation_submit", || state.queue.submit(std::iter::once(sim_encoder.finish())));
         state.world.swap();
         state.lab.step_requested = false;
         state.lab.log_event(state.world.frame, "CONTROL", "Single step");
     }
 
+    // ---- Barrier lifetime ----
+    let barrier_frames_left = state.world.barrier.map_or(0, |b| b.frames_left);
+    if state.lab.barrier_frames_left > 0 && barrier_frames_left == 0 {
+        let index = state.lab.barrier_divergence.and_then(|d| d.index());
+        state.lab.log_event_with_payload(
+            state.world.frame,
+            EventSeverity::Info,
+            "BARRIER",
+            &format!("Barrier lifted (divergence index {})", index.map_or("n/a".into(), |i| format!("{i:.3}"))),
+            serde_json::json!({ "divergence_index": index }),
+        );
+        state.lab.set_status(tr("status.barrier_lifted").to_string());
+    }
+    state.lab.barrier_frames_left = barrier_frames_left;
+
     // ---- Render pass ----
     let render_cur = 1 - state.world.cur();
     let mut encoder = state
@@ -648,12 +1397,29 @@ fn redraw(state: &mut AppState) {
         .texture
         .create_view(&wgpu::TextureViewDescriptor::default());
 
-    // Simulation render pass
+    if state.lab.trail_clear_requested {
+        if let Some(trail) = state.world.channels.buffer(TRAIL, 0) {
+            encoder.clear_buffer(trail, 0, None);
+        }
+        state.lab.trail_clear_requested = false;
+    }
+
+    if state.sim_params.histogram.enabled && histogram_stale(state) {
+        update_histogram(state);
+    }
+
+    let species_map = state.sim_params.species_map;
+    if species_map.enabled && species_map.colors {
+        state.world.upload_species(&state.queue, state.lab.territory.species());
+        encode_species_pass(&mut encoder, &state.pipelines, render_cur, dispatch_x, dispatch_y);
+    }
+
+    // Simulation render pass (into the HDR offscreen target)
     {
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("render_pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
+                view: state.postfx.hdr_view(),
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -669,220 +1435,2121 @@ fn redraw(state: &mut AppState) {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        pass.set_pipeline(&state.pipelines.render_pipeline);
+        let user_mode = state.sim_params.visualization_mode.checked_sub(VIS_MODE_COUNT);
+        let (flat, surface) = match user_mode.and_then(|k| state.user_modes.get(k as usize)) {
+            Some(mode) => (&mode.views[0], &mode.views[1]),
+            None => (&state.pipelines.render_pipeline, &state.pipelines.surface_pipeline),
+        };
+        pass.set_pipeline(if state.camera.surface_view { surface } else { flat });
         pass.set_bind_group(0, &state.pipelines.render_bind_groups[render_cur], &[]);
         pass.draw(0..6, 0..1);
+    }
 
-        // HUD overlay (only when Lab UI hidden)
-        if !state.lab.show_lab_ui {
-            state.hud.render(&mut pass);
-        }
+    // Bloom + tonemap onto the swapchain
+    state.postfx.encode(&mut encoder, &view);
+
+    // HUD overlay (only when Lab UI hidden)
+    if !state.lab.show_lab_ui {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("hud_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        state.hud.render(&mut pass);
     }
 
     // ---- Screenshot capture (from simulation render, before egui overlay) ----
-    let do_screenshot = state.lab.screenshot_requested;
-    let mut screenshot_staging: Option<wgpu::Buffer> = None;
-    let mut screenshot_padded_bpr: u32 = 0;
-
-    if do_screenshot {
-        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let unpadded_bpr = win_w * 4;
-        let padded_bpr = (unpadded_bpr + align - 1) / align * align;
-        screenshot_padded_bpr = padded_bpr;
-
-        let staging = state.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("screenshot_staging"),
-            size: (padded_bpr * win_h) as u64,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+    if std::mem::take(&mut state.lab.screenshot_requested) {
+        state.captures.request(ShotTag {
+            frame: state.world.frame,
+            mode: state.sim_params.visualization_mode,
+            sequence: state.lab.playback.exporting(),
         });
-
-        encoder.copy_texture_to_buffer(
-            wgpu::TexelCopyTextureInfo {
-                texture: &output.texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::TexelCopyBufferInfo {
-                buffer: &staging,
-                layout: wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bpr),
-                    rows_per_image: Some(win_h),
-                },
-            },
-            wgpu::Extent3d {
-                width: win_w,
-                height: win_h,
-                depth_or_array_layers: 1,
+    }
+    for (tag, error) in state.captures.encode_copies(&state.device, &mut encoder, &output.texture) {
+        screenshot_failed(state, tag.frame, &error);
+    }
+
+    // Submit the simulation render encoder (with optional screenshot copy)
+    state.gpu_errors.scope(&state.device, "render_submit", || state.queue.submit(std::iter::once(encoder.finish())));
+    state.captures.submitted();
+    timing.encode_ms = elapsed_ms(encode_start);
+
+    // ---- egui render pass (on top of simulation, separate encoder) ----
+    let egui_start = Instant::now();
+    let paint_jobs = state
+        .egui_ctx
+        .tessellate(full_output.shapes, full_output.pixels_per_point);
+
+    for (id, image_delta) in &full_output.textures_delta.set {
+        state
+            .egui_renderer
+            .update_texture(&state.device, &state.queue, *id, image_delta);
+    }
+
+    let screen_descriptor = egui_wgpu::ScreenDescriptor {
+        size_in_pixels: [win_w, win_h],
+        pixels_per_point: full_output.pixels_per_point,
+    };
+
+    let mut egui_encoder = state
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("egui_encoder"),
+        });
+
+    state.egui_renderer.update_buffers(
+        &state.device,
+        &state.queue,
+        &mut egui_encoder,
+        &paint_jobs,
+        &screen_descriptor,
+    );
+
+    render_egui_pass(
+        &state.egui_renderer,
+        &mut egui_encoder,
+        &view,
+        wgpu::LoadOp::Load, // on top of the simulation
+        &paint_jobs,
+        &screen_descriptor,
+    );
+
+    state.gpu_errors.scope(&state.device, "egui_submit", || state.queue.submit(std::iter::once(egui_encoder.finish())));
+    timing.egui_ms += elapsed_ms(egui_start);
+    report_gpu_errors(state);
+
+    // ---- Collect finished screenshots (mapped asynchronously) ----
+    let readback_start = Instant::now();
+    for captured in state.captures.collect(&state.device, false) {
+        save_capture(state, captured);
+    }
+
+    // ---- Snapshot (state save) ----
+    if state.lab.snapshot_requested {
+        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
+            let path = state.lab.snapshot_save_path.take().unwrap_or_else(|| {
+                state
+                    .lab
+                    .run_dir
+                    .join(format!("snapshot_frame{:06}.{}", state.world.frame, SNAPSHOT_EXTENSION))
+            });
+            state.lab.log_event_with_payload(
+                state.world.frame,
+                EventSeverity::Info,
+                "SNAPSHOT",
+                &format!("Saving to {:?}", path),
+                serde_json::json!({ "path": path.display().to_string() }),
+            );
+            let job = state.lab.notifications.start_job(tr("job.snapshot").to_string(), "SNAPSHOT");
+            std::thread::spawn(move || {
+                let result = state_io::save_snapshot_with_progress(
+                    path.to_str().unwrap_or("snapshot.snap"),
+                    &snap,
+                    |fraction| job.progress(fraction),
+                )
+                    .map(|()| trf("status.snapshot_saved", &[("path", &format!("{:?}", path))]))
+                    .map_err(|e| {
+                        log::error!("Snapshot save failed: {}", e);
+                        e.to_string()
+                    });
+                job.finish(result);
+            });
+        }
+        state.lab.snapshot_requested = false;
+    }
+
+    // ---- Thumbnail of a run finalized from the UI ----
+    save_pending_thumbnail(state);
+
+    // ---- Snapshot load (chosen in a file dialog) ----
+    if let Some(path) = state.lab.snapshot_load_path.take() {
+        load_snapshot_file(state, &path);
+    }
+
+    // ---- Population genetics export (now, or once the scheduled frame is reached) ----
+    let popgen_due = state.lab.popgen_scheduled && state.world.frame >= state.lab.popgen_frame;
+    if state.lab.popgen_export_requested || popgen_due {
+        state.lab.popgen_export_requested = false;
+        state.lab.popgen_scheduled &= !popgen_due;
+        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
+            let path = population_path(&state.lab.run_dir, state.world.frame, state.lab.popgen_format);
+            state.lab.log_event_with_payload(
+                state.world.frame,
+                EventSeverity::Info,
+                "POPGEN",
+                &format!("Exporting live cells to {:?}", path),
+                serde_json::json!({ "path": path.display().to_string() }),
+            );
+            let job = state.lab.notifications.start_job(tr("job.population").to_string(), "POPGEN");
+            std::thread::spawn(move || {
+                let result = export_population(&snap, snap.size[0], &path)
+                    .map(|count| trf("status.population_exported", &[("count", &count), ("path", &format!("{:?}", path))]))
+                    .inspect_err(|e| log::error!("Population export failed: {}", e));
+                job.finish(result);
+            });
+        }
+    }
+
+    // ---- Time-averaged fields ----
+    if std::mem::take(&mut state.lab.average_reset_requested) {
+        state.world.reset_averages();
+        state.lab.log_event(state.world.frame, "AVERAGES", "Running means reset");
+    }
+    if std::mem::take(&mut state.lab.average_export_requested) {
+        export_field_averages(state);
+    }
+    state.lab.average_samples = state.world.average_samples;
+
+    // ---- Velocity field export (one-shot) ----
+    if std::mem::take(&mut state.lab.velocity_export_requested) {
+        export_velocity_field(state, true);
+    }
+
+    // ---- Simulation tape ----
+    if std::mem::take(&mut state.lab.tape.start_requested)
+        && !state.lab.tape.is_recording()
+        && !state.lab.playback.is_active()
+    {
+        start_tape(state);
+    }
+    if state.lab.tape.due(state.world.frame) {
+        record_tape_frame(state);
+    }
+    if std::mem::take(&mut state.lab.tape.stop_requested) {
+        stop_tape(state);
+    }
+
+    // ---- Lenia pattern import/export ----
+    if state.lab.pattern_import_requested || state.lab.pattern_export_requested {
+        handle_pattern_requests(state);
+    }
+
+    // ---- Translocation experiments ----
+    if state.lab.transplant_requested || state.lab.transplant_undo_requested {
+        handle_transplant_requests(state);
+    }
+
+    // ---- Fitness landscape probe ----
+    if std::mem::take(&mut state.lab.landscape.requested) {
+        start_landscape_probe(state);
+    }
+    poll_landscape_probe(state);
+
+    // ---- LTEE fossil spawn ----
+    if std::mem::take(&mut state.lab.fossils.spawn_requested) {
+        spawn_fossil_sample(state);
+    }
+
+    // ---- Invasion assay on a cloned world ----
+    if std::mem::take(&mut state.lab.invasion.requested) {
+        start_invasion_assay(state);
+    }
+    poll_invasion_assay(state);
+
+    // ---- Deterministic replay check on a background world ----
+    if std::mem::take(&mut state.lab.replay.requested) {
+        start_replay_check(state);
+    }
+    poll_replay_check(state);
+
+    // ---- Head-to-head arena ----
+    if let Some(request) = state.lab.arena.requested.take() {
+        start_arena(state, request);
+    }
+    poll_arena(state);
+
+    // ---- Reserve residents from the zone's occupants ----
+    if let Some(k) = state.lab.reserve_capture_requested.take() {
+        capture_reserve_residents(state, k);
+    }
+
+    // ---- Creature portrait (one frame every few steps) ----
+    let portrait_due = state.lab.portrait_capture.as_ref().is_some_and(|c| c.due(state.world.frame));
+    if state.lab.portrait_requested || portrait_due {
+        update_portrait(state);
+    }
+
+    // ---- Reference snapshot for the diff overlay ----
+    if state.lab.diff_load_requested || state.lab.diff_capture_requested {
+        handle_diff_reference_requests(state);
+    }
+
+    // ---- Display auto-normalization (one-shot) ----
+    if state.lab.auto_normalize_requested {
+        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
+            apply_auto_normalize(&mut state.sim_params, &snap);
+            let adjust = state.sim_params.current_display_adjust();
+            let (min, max) = (format!("{:.3}", adjust.range_min), format!("{:.3}", adjust.range_max));
+            state.lab.set_status(trf("status.range_fitted", &[("min", &min), ("max", &max)]));
+        }
+        state.lab.auto_normalize_requested = false;
+    }
+
+    // ---- Histogram-equalization LUT (rebuilt when mode/adjustment changes) ----
+    let lut_key = (state.sim_params.visualization_mode, adjust);
+    if adjust.transform == DisplayTransform::Equalize && state.display_lut_key != Some(lut_key) {
+        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
+            upload_display_lut(state, &snap);
+        }
+    }
+
+    // ---- Cross-section profile refresh ----
+    if state.lab.profile_refresh_requested {
+        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
+            state.lab.update_profile(&snap);
+        }
+    }
+
+    timing.readback_ms = elapsed_ms(readback_start);
+
+    output.present();
+
+    for id in &full_output.textures_delta.free {
+        state.egui_renderer.free_texture(id);
+    }
+    state.hud.trim();
+
+    // ---- Periodic diagnostics ----
+    if !state.sim_params.paused
+        && state.world.frame > 0
+        && state.world.frame.is_multiple_of(state.diag_interval)
+    {
+        let diag_start = Instant::now();
+        sample_diagnostics(state);
+        timing.readback_ms += elapsed_ms(diag_start);
+    }
+    state.lab.frame_stats.push(timing);
+
+    // After presenting, so the window is not blank while it runs
+    if std::mem::take(&mut state.lab.startup_bench_requested) {
+        run_startup_bench(state);
+    }
+}
+
+/// Present with vsync (AutoVsync) or without (Immediate), per the setting.
+fn sync_vsync(state: &mut AppState) {
+    state.surface_config.present_mode = if state.sim_params.vsync {
+        wgpu::PresentMode::AutoVsync
+    } else {
+        wgpu::PresentMode::Immediate
+    };
+    state.surface.configure(&state.device, &state.surface_config);
+    state.vsync_applied = state.sim_params.vsync;
+}
+
+/// Time plain simulation steps for STARTUP_BENCH_DURATION and queue the
+/// recommended settings dialog. The world is read back first and restored
+/// afterwards, so the run continues where it was.
+fn run_startup_bench(state: &mut AppState) {
+    let Some(snapshot) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        log::warn!("GPU readback failed, skipping the startup benchmark");
+        return;
+    };
+    let frame = state.world.frame;
+    let [width, height] = state.world.size;
+    let dispatch = (
+        width.div_ceil(WORKGROUP_X),
+        height.div_ceil(WORKGROUP_Y),
+        (width * height).div_ceil(WORKGROUP_LINEAR),
+    );
+    let bench_steps = |state: &mut AppState, steps: u32| {
+        for _ in 0..steps {
+            let passes = StepPasses {
+                normalize: state.world.update_step_uniforms_dynamic(&state.queue, &state.sim_params),
+                velocity: state.sim_params.separate_velocity_pass,
+            };
+            let cur = state.world.cur();
+            let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("startup_bench_encoder"),
+            });
+            if state.world.convolution(&state.sim_params).separable {
+                encode_convolution_passes(&mut encoder, &state.pipelines, cur, dispatch.0, dispatch.1);
+            }
+            encode_simulation_passes(&mut encoder, &state.pipelines, cur, dispatch.0, dispatch.1, dispatch.2, passes);
+            state.queue.submit(std::iter::once(encoder.finish()));
+            state.world.swap();
+        }
+        state.device.poll(wgpu::Maintain::Wait);
+    };
+
+    log::info!("Startup benchmark: timing simulation steps for {:?}", STARTUP_BENCH_DURATION);
+    bench_steps(state, STARTUP_WARMUP_STEPS);
+    let started = Instant::now();
+    let mut steps = 0u32;
+    while started.elapsed() < STARTUP_BENCH_DURATION {
+        bench_steps(state, 5);
+        steps += 5;
+    }
+    let ms_per_step = elapsed_ms(started) / steps as f32;
+
+    state.world.apply_snapshot(&state.queue, &snapshot);
+    state.world.frame = frame;
+
+    let info = state.adapter.get_info();
+    let bench = StartupBench {
+        gpu: info.name,
+        integrated: !matches!(info.device_type, wgpu::DeviceType::DiscreteGpu),
+        ms_per_step,
+    };
+    let recommendation = Recommendation::for_bench(&bench, state.world.size[0]);
+    log::info!("Startup benchmark: {:.3} ms/step on {} -> {:?}", ms_per_step, bench.gpu, recommendation);
+    state.lab.startup_pending = Some((bench, recommendation));
+}
+
+fn elapsed_ms(since: Instant) -> f32 {
+    since.elapsed().as_secs_f32() * 1000.0
+}
+
+/// Run `steps` simulation steps without rendering (background throttling,
+/// render cadence), sampling diagnostics whenever the batch crosses a
+/// diagnostics boundary.
+fn run_unrendered_batch(state: &mut AppState, steps: u32) {
+    let frame_before = state.world.frame;
+    run_simulation_batch(state, steps);
+    if frame_before / state.diag_interval != state.world.frame / state.diag_interval {
+        sample_diagnostics(state);
+    }
+}
+
+/// Run `steps` simulation steps (timed on the GPU when supported).
+fn run_simulation_batch(state: &mut AppState, steps: u32) {
+    let frame_before = state.world.frame;
+    let [width, height] = state.world.size;
+    let dispatch_x = width.div_ceil(WORKGROUP_X);
+    let dispatch_y = height.div_ceil(WORKGROUP_Y);
+    let dispatch_linear = (width * height).div_ceil(WORKGROUP_LINEAR);
+    let perturbation = state.sim_params.continuous_perturbation_intensity();
+    if let Some(intensity) = perturbation {
+        state.world.update_perturbation_uniform(&state.queue, &state.sim_params, intensity);
+    }
+
+    for step in 0..steps {
+        apply_schedules(&mut state.sim_params, state.world.frame);
+        let passes = StepPasses {
+            normalize: state.world.update_step_uniforms_dynamic(&state.queue, &state.sim_params),
+            velocity: state.sim_params.separate_velocity_pass,
+        };
+
+        let cur = state.world.cur();
+        let mut sim_encoder = state
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("sim_encoder"),
+            });
+        if let Some(timer) = &mut state.gpu_timer {
+            if step == 0 {
+                timer.begin(&mut sim_encoder);
+            }
+        }
+        if perturbation.is_some() {
+            encode_perturbation_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if state.world.barrier_step() {
+            encode_barrier_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if state.sim_params.particles.enabled {
+            encode_particle_passes(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y, &state.sim_params);
+        }
+        if state.world.convolution(&state.sim_params).separable {
+            encode_convolution_passes(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        encode_simulation_passes(
+            &mut sim_encoder,
+            &state.pipelines,
+            cur,
+            dispatch_x,
+            dispatch_y,
+            dispatch_linear,
+            passes,
+        );
+        if state.sim_params.trail.enabled {
+            encode_trail_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if state.sim_params.averages.enabled {
+            state.world.update_average_uniform(&state.queue, &state.sim_params.averages);
+            encode_average_pass(&mut sim_encoder, &state.pipelines, cur, dispatch_x, dispatch_y);
+        }
+        if let Some(timer) = &mut state.gpu_timer {
+            if step + 1 == steps {
+                timer.end(&mut sim_encoder, steps);
+            }
+        }
+        state.gpu_errors.scope(&state.device, "simulation_submit", || state.queue.submit(std::iter::once(sim_encoder.finish())));
+        if let Some(timer) = &mut state.gpu_timer {
+            if step + 1 == steps {
+                timer.after_submit();
+            }
+        }
+        state.world.swap();
+    }
+    state.render_clock.add_steps(steps);
+    if state.sim_params.ltee.due(frame_before, state.world.frame) {
+        ltee_transfer(state);
+    }
+}
+
+/// LTEE serial transfer: freeze a genome sample to the run's archive, then
+/// apply the bottleneck, if any.
+fn ltee_transfer(state: &mut AppState) {
+    let Some(mut snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let settings = state.sim_params.ltee;
+    let frame = state.world.frame;
+    let mut rng = transfer_rng(frame);
+    let Some(mut sample) = FossilSample::freeze(&snap, settings.sample_size, &mut rng) else {
+        state.lab.log_event_with_severity(frame, EventSeverity::Warn, "LTEE_TRANSFER", "No living cells to archive");
+        return;
+    };
+    let transfer = list_samples(&state.lab.run_dir).len() as u32 + 1;
+    sample.run_id = state.lab.run_id.clone();
+    sample.frame = frame;
+    sample.transfer = transfer;
+    let path = sample_path(&state.lab.run_dir, transfer, frame);
+    if let Err(e) = save_sample(&path, &sample) {
+        state.lab.log_event_with_severity(frame, EventSeverity::Warn, "LTEE_TRANSFER", &e);
+        state.lab.set_warning(trf("status.ltee_failed", &[("error", &e)]));
+    }
+    state.lab.fossils.archive = list_samples(&state.lab.run_dir);
+
+    let (kept, removed) = if settings.bottleneck() {
+        let counts = dilute(&mut snap, settings.keep, &mut rng);
+        state.world.apply_snapshot(&state.queue, &snap);
+        counts
+    } else {
+        (sample.living_cells, 0)
+    };
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "LTEE_TRANSFER",
+        &format!("Transfer {}: {} genomes archived, {} of {} living cells kept", transfer, sample.cells.len(), kept, kept + removed),
+        serde_json::json!({
+            "transfer": transfer,
+            "path": path.display().to_string(),
+            "sample_size": sample.cells.len(),
+            "kept": kept,
+            "removed": removed,
+        }),
+    );
+}
+
+/// Step a world other than the visible one (queued background experiments):
+/// the same passes as `run_simulation_batch`, without trails or GPU timing.
+/// Scheduled parameters take their value at the world's frame.
+pub(crate) fn step_world(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    world: &mut WorldState,
+    pipelines: &Pipelines,
+    params: &SimulationParams,
+) {
+    let scheduled;
+    let params = if params.schedules.is_empty() {
+        params
+    } else {
+        scheduled = {
+            let mut p = params.clone();
+            apply_schedules(&mut p, world.frame);
+            p
+        };
+        &scheduled
+    };
+    let [width, height] = world.size;
+    let dispatch_x = width.div_ceil(WORKGROUP_X);
+    let dispatch_y = height.div_ceil(WORKGROUP_Y);
+    let passes = StepPasses {
+        normalize: world.update_step_uniforms_dynamic(queue, params),
+        velocity: params.separate_velocity_pass,
+    };
+    let cur = world.cur();
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("queue_sim_encoder"),
+    });
+    if let Some(intensity) = params.continuous_perturbation_intensity() {
+        world.update_perturbation_uniform(queue, params, intensity);
+        encode_perturbation_pass(&mut encoder, pipelines, cur, dispatch_x, dispatch_y);
+    }
+    if world.barrier_step() {
+        encode_barrier_pass(&mut encoder, pipelines, cur, dispatch_x, dispatch_y);
+    }
+    if params.particles.enabled {
+        encode_particle_passes(&mut encoder, pipelines, cur, dispatch_x, dispatch_y, params);
+    }
+    if world.convolution(params).separable {
+        encode_convolution_passes(&mut encoder, pipelines, cur, dispatch_x, dispatch_y);
+    }
+    encode_simulation_passes(
+        &mut encoder,
+        pipelines,
+        cur,
+        dispatch_x,
+        dispatch_y,
+        (width * height).div_ceil(WORKGROUP_LINEAR),
+        passes,
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+    world.swap();
+}
+
+/// Track the genome clusters of this sample and report persistent splits,
+/// with a screenshot and a snapshot of the moment when asked to.
+fn detect_speciation(state: &mut AppState, snap: &BufferSnapshot) {
+    let settings = state.sim_params.speciation;
+    let frame = state.world.frame;
+    let events = state.lab.speciation.update(&genome_clusters(snap), frame, &settings);
+    for event in events {
+        state.lab.log_event_with_payload(
+            frame,
+            EventSeverity::Info,
+            "SPECIATION",
+            &format!(
+                "S{} split from S{} at frame {} (shares {:.1}% / {:.1}%)",
+                event.child,
+                event.parent,
+                event.split_frame,
+                event.shares[0] * 100.0,
+                event.shares[1] * 100.0
+            ),
+            serde_json::json!({
+                "parent": event.parent,
+                "child": event.child,
+                "split_frame": event.split_frame,
+                "parent_centroid": event.centroids[0],
+                "child_centroid": event.centroids[1],
+                "shares": event.shares,
+            }),
+        );
+        state.lab.notifications.notify(
+            EventSeverity::Info,
+            trf("speciation.detected", &[("child", &event.child), ("parent", &event.parent), ("frame", &frame)]),
+        );
+        if settings.capture {
+            state.lab.screenshot_requested = true;
+            state.lab.snapshot_requested = true;
+        }
+    }
+}
+
+/// Gather the last diagnostics, the events since and the newest rewind
+/// snapshot from before the loss into an extinction report folder.
+fn write_extinction_report(state: &mut AppState, what: Extinction) {
+    let frame = state.world.frame;
+    let lab = &state.lab;
+    let start = lab.metrics_history.len().saturating_sub(state.sim_params.forensics.history.max(1));
+    let history = &lab.metrics_history[start..];
+    let since = history.first().map_or(frame, |m| m.frame);
+    let snapshot = match &what {
+        Extinction::Population => lab.rewind.latest_where(|s| s.mass.iter().any(|&m| m > 0.0)),
+        Extinction::Lineage(_) => lab.rewind.latest_where(|_| true),
+    };
+    let report = ExtinctionReport {
+        what: what.clone(),
+        frame,
+        run_id: &lab.run_id,
+        history,
+        custom: &lab.custom_metrics,
+        conditions: &lab.conditions,
+        events: lab.events.iter().filter(|e| e.frame >= since).collect(),
+        snapshot,
+    };
+    let dir = report_dir(&lab.run_dir, frame, &what);
+    let snapshot_frame = snapshot.map(|(f, _)| f);
+    let (samples, events) = (report.history.len(), report.events.len());
+    match report.write(&dir) {
+        Ok(files) => {
+            state.lab.log_event_with_payload(
+                frame,
+                EventSeverity::Info,
+                "EXTINCTION_REPORT",
+                &format!("Extinction report for {} in {:?}", what.describe(), dir),
+                serde_json::json!({
+                    "what": what.slug(),
+                    "dir": dir.display().to_string(),
+                    "samples": samples,
+                    "events": events,
+                    "snapshot_frame": snapshot_frame,
+                    "files": files.len(),
+                }),
+            );
+            state.lab.set_status(trf("status.extinction_report", &[("path", &dir.display())]));
+            state.lab.last_extinction_report = Some(dir);
+        }
+        Err(e) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "EXTINCTION_REPORT", &e);
+            state.lab.set_warning(trf("status.extinction_report_failed", &[("error", &e)]));
+        }
+    }
+}
+
+/// Read back the world, record metrics, and refresh readback-driven views.
+fn sample_diagnostics(state: &mut AppState) {
+    if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
+        let diag = SimDiagnostics::from_snapshot(&snap);
+        state
+            .lab
+            .record_metrics(&diag, &snap, state.world.frame, state.fps);
+        if std::mem::take(&mut state.lab.alert_pause_requested) && !state.sim_params.paused {
+            state.sim_params.paused = true;
+            state.lab.log_event(state.world.frame, "CONTROL", "Paused by alert rule");
+        }
+        state.lab.update_profile(&snap);
+        state.lab.diff_divergence = state.lab.diff_reference.as_ref().and_then(|r| r.divergence(&snap.mass));
+        if !state.sim_params.reserves.is_empty() {
+            state.lab.reserve_stats = reserve_stats(&snap, &state.world.reserve_ids, &state.world.reserves);
+        }
+        if let Some(split) = state.lab.barrier_split {
+            state.lab.record_split_divergence(split_divergence(&snap, snap.size[0], split), state.world.frame);
+        }
+        if state.sim_params.species_map.enabled {
+            update_species_map(state, &snap);
+        } else if state.sim_params.territory.enabled {
+            update_territory(state, &snap);
+        }
+        if state.sim_params.speciation.enabled {
+            detect_speciation(state, &snap);
+        }
+        if state.sim_params.histogram.enabled {
+            update_histogram(state);
+        }
+        let forensics = state.sim_params.forensics;
+        if std::mem::take(&mut state.lab.extinction_report_requested) && forensics.enabled {
+            write_extinction_report(state, Extinction::Population);
+        } else if forensics.enabled && forensics.lineages && state.sim_params.speciation.enabled {
+            for lineage in std::mem::take(&mut state.lab.speciation.lost) {
+                write_extinction_report(state, Extinction::Lineage(lineage));
+            }
+        }
+        if forensics.enabled {
+            state.lab.rewind.push(state.world.frame, &snap, forensics.rewind_depth);
+        }
+        if state.sim_params.velocity_export.enabled {
+            state.lab.velocity_samples += 1;
+            if state.sim_params.velocity_export.due(state.lab.velocity_samples) {
+                export_velocity_field(state, false);
+            }
+        }
+        if state.sim_params.current_display_adjust().auto_normalize {
+            apply_auto_normalize(&mut state.sim_params, &snap);
+        }
+        if state.sim_params.current_display_adjust().transform == DisplayTransform::Equalize {
+            upload_display_lut(state, &snap);
+        }
+        diag.log(
+            state.world.frame,
+            target_mass_of(state.world.size),
+            state.last_diag.as_ref(),
+        );
+        let target = target_mass_of(state.world.size) * state.sim_params.target_mass_multiplier;
+        state.world.mass_drift = Some((diag.total_mass / target - 1.0).abs());
+        state.last_diag = Some(diag);
+    }
+    let conv = state.world.convolution(&state.sim_params);
+    state.lab.interactions = if state.sim_params.interaction_matrix {
+        state.world.read_interactions(&state.device, &state.queue)
+    } else {
+        None
+    };
+    let particles = state.sim_params.particles;
+    state.lab.particles = if particles.enabled {
+        state.world.read_particle_stats(&state.device, &state.queue, particles.active_count(), particles.capacity)
+    } else {
+        None
+    };
+    state.lab.convolution_error = if conv.separable && conv.compare_exact {
+        state.world.read_convolution_error(&state.device, &state.queue)
+    } else {
+        None
+    };
+}
+
+/// Add a labeling pass to the territory window and refresh the overlay.
+fn update_territory(state: &mut AppState, snap: &BufferSnapshot) {
+    state.lab.territory.record(snap, state.sim_params.territory.window as usize);
+    refresh_territory(state);
+}
+
+/// Label the snapshot's state with the species map pass, found clusters for
+/// the genomes it left unassigned and upload them for the next passes; the
+/// labels feed the territory window when it is on.
+fn update_species_map(state: &mut AppState, snap: &BufferSnapshot) {
+    let side = 1 - state.world.cur(); // species bind groups index like the render ones
+    state.world.upload_species(&state.queue, state.lab.territory.species());
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("species_encoder"),
+    });
+    encode_species_pass(
+        &mut encoder,
+        &state.pipelines,
+        side,
+        state.world.size[0].div_ceil(WORKGROUP_X),
+        state.world.size[1].div_ceil(WORKGROUP_Y),
+    );
+    state.queue.submit(std::iter::once(encoder.finish()));
+    let Some(map) = state.world.channels.read(&state.device, &state.queue, state.world.layout, SPECIES, side) else {
+        return;
+    };
+    let labels = state.lab.territory.resolve(&map, snap);
+    state.world.upload_species(&state.queue, state.lab.territory.species());
+    if state.sim_params.territory.enabled {
+        state.lab.territory.push(labels, state.sim_params.territory.window as usize);
+        refresh_territory(state);
+    }
+}
+
+/// Field and range the histogram overlay should show now.
+fn histogram_target(params: &SimulationParams) -> (HistogramField, f32) {
+    let field = params.histogram.field.resolve(params.visualization_mode);
+    (field, histogram_max(field, params.current_display_adjust()))
+}
+
+/// Whether the last histogram no longer matches the field or range shown
+/// (mode, field or display window changed since the last sample).
+fn histogram_stale(state: &AppState) -> bool {
+    let (field, max_value) = histogram_target(&state.sim_params);
+    state.lab.histogram.as_ref().is_none_or(|h| h.field != field || h.max_value != max_value)
+}
+
+/// Bin the drawn state with the histogram pass and read the counts back.
+fn update_histogram(state: &mut AppState) {
+    let (field, max_value) = histogram_target(&state.sim_params);
+    let side = 1 - state.world.cur(); // histogram bind groups index like the render ones
+    state.queue.write_buffer(
+        &state.world.histogram_params_buffer,
+        0,
+        bytemuck::bytes_of(&HistogramParams::new(field, max_value)),
+    );
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("histogram_encoder"),
+    });
+    encoder.clear_buffer(&state.world.histogram_bins, 0, None);
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("histogram_pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&state.pipelines.histogram_pipeline);
+        pass.set_bind_group(0, &state.pipelines.histogram_bind_groups[side], &[]);
+        pass.dispatch_workgroups(state.world.size[0].div_ceil(WORKGROUP_X), state.world.size[1].div_ceil(WORKGROUP_Y), 1);
+    }
+    state.queue.submit(std::iter::once(encoder.finish()));
+    state.lab.histogram = state.world.read_histogram(&state.device, &state.queue).map(|counts| FieldHistogram {
+        field,
+        counts,
+        max_value,
+        frame: state.world.frame,
+    });
+}
+
+/// Rebuild the territory overlay and areas from the window.
+fn refresh_territory(state: &mut AppState) {
+    let owners = state.lab.territory.owners();
+    let rgba = territory_rgba(&owners, &state.lab.palette.uniforms().cyclic);
+    state.world.upload_territory(&state.queue, &rgba);
+    state.lab.territory_areas = TerritoryMap::areas(&owners);
+}
+
+/// Fit the active mode's display range to the current frame statistics.
+/// Save the creature under the pattern center and/or stamp the named pattern
+/// file there (both go through a CPU snapshot: these are one-shot actions).
+/// Advance the experiment queue: follow the running protocol and start the
+/// next one when it ends. Visible protocols restart the world and record a
+/// normal run; background ones run on their own world (see queue.rs).
+fn update_experiment_queue(state: &mut AppState) {
+    let frame = state.world.frame;
+    if let Some((index, status)) = state.lab.experiment_queue.poll_background() {
+        log_queue_end(&mut state.lab, index, &status);
+    }
+
+    if let Some(index) = state.lab.experiment_queue.active() {
+        let entry = &state.lab.experiment_queue.entries[index];
+        if entry.protocol.mode == QueueMode::Visible {
+            let status = if !state.lab.run_active {
+                Some(EntryStatus::Cancelled) // run finalized by hand
+            } else if frame >= entry.protocol.frames {
+                state.lab.finalize_run(&state.sim_params);
+                Some(EntryStatus::Done)
+            } else {
+                None
+            };
+            match status {
+                Some(status) => {
+                    state.lab.experiment_queue.finish(index, status.clone());
+                    log_queue_end(&mut state.lab, index, &status);
+                }
+                None => state.lab.experiment_queue.set_progress(index, frame),
+            }
+        }
+    }
+
+    let Some(index) = state.lab.experiment_queue.next_to_start() else {
+        if state.lab.experiment_queue.stop_if_complete() {
+            state.lab.set_status(tr("status.queue_finished").to_string());
+        }
+        return;
+    };
+    let protocol = state.lab.experiment_queue.entries[index].protocol.clone();
+    let label = protocol.run_label(index);
+    let run_dir = match protocol.mode {
+        QueueMode::Visible => {
+            state.lab.finalize_run(&state.sim_params);
+            let vis = state.sim_params.visualization_mode;
+            state.sim_params = protocol.run_params();
+            state.sim_params.visualization_mode = vis;
+            state.lab.restart_requested = true;
+            state.lab.start_run(&state.sim_params, Some(&label));
+            if let Err(e) = protocol.save(&state.lab.run_dir) {
+                log::error!("{}", e);
+            }
+            state.lab.experiment_queue.begin(index, state.lab.run_dir.clone());
+            state.lab.run_dir.clone()
+        }
+        QueueMode::Background => {
+            let (run_id, run_dir) = new_run_id(&state.lab.output_root, &chrono::Local::now(), Some(&label));
+            let job = BackgroundJob {
+                protocol: protocol.clone(),
+                run_id,
+                run_dir: run_dir.clone(),
+                metrics_interval: state.lab.metrics_sample_interval,
+                metadata: state.lab.metadata.clone(),
+                size: state.world.size,
+            };
+            state.lab.experiment_queue.start_background(index, state.device.clone(), state.queue.clone(), job);
+            run_dir
+        }
+    };
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "QUEUE_START",
+        &format!("Protocol {} started ({:?})", protocol.name, protocol.mode),
+        serde_json::json!({
+            "protocol": protocol.name,
+            "frames": protocol.frames,
+            "seed": protocol.seed,
+            "run_dir": run_dir.display().to_string(),
+        }),
+    );
+}
+
+fn log_queue_end(lab: &mut LabState, index: usize, status: &EntryStatus) {
+    let name = lab.experiment_queue.entries[index].protocol.name.clone();
+    let (severity, outcome) = match status {
+        EntryStatus::Failed(e) => (EventSeverity::Warn, format!("failed: {}", e)),
+        EntryStatus::Cancelled => (EventSeverity::Info, String::from("cancelled")),
+        _ => (EventSeverity::Info, String::from("done")),
+    };
+    lab.log_event_with_payload(
+        lab.current_frame,
+        severity,
+        "QUEUE_END",
+        &format!("Protocol {} {}", name, outcome),
+        serde_json::json!({ "protocol": name, "outcome": outcome }),
+    );
+    if let EntryStatus::Failed(e) = status {
+        lab.set_warning(trf("status.queue_failed", &[("name", &name), ("error", e)]));
+    }
+}
+
+/// Start a portrait on the picked creature or add its next frame; a complete
+/// portrait is encoded on a worker thread.
+fn update_portrait(state: &mut AppState) {
+    let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let frame = state.world.frame;
+    let palette = state.lab.palette.uniforms();
+    if std::mem::take(&mut state.lab.portrait_requested) {
+        let [u, v] = state.lab.portrait_center;
+        let center = [u * snap.size[0] as f32, v * snap.size[1] as f32];
+        match PortraitCapture::start(&snap, center, frame, &state.lab.run_id, state.lab.portrait, &palette) {
+            Some(capture) => {
+                state.lab.log_event_with_payload(
+                    frame,
+                    EventSeverity::Info,
+                    "PORTRAIT",
+                    &format!("Following creature at ({:.0}, {:.0}) as {}", center[0], center[1], capture.info.name),
+                    serde_json::json!({ "name": capture.info.name, "genome": capture.info.genome }),
+                );
+                state.lab.portrait_capture = Some(capture);
+            }
+            None => state.lab.set_warning(tr("status.portrait_empty").to_string()),
+        }
+    } else if let Some(capture) = &mut state.lab.portrait_capture {
+        if let Err(e) = capture.add_frame(&snap, frame, &palette) {
+            state.lab.portrait_capture = None;
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "PORTRAIT", &e);
+            state.lab.set_warning(trf("status.portrait_failed", &[("error", &e)]));
+        }
+    }
+    if let Some(capture) = state.lab.portrait_capture.take_if(|c| c.is_complete()) {
+        let job = state.lab.notifications.start_job(tr("job.portrait").to_string(), "PORTRAIT");
+        std::thread::spawn(move || {
+            let result = capture
+                .save(Path::new(CREATURES_DIR), |fraction| job.progress(fraction))
+                .map(|path| trf("status.portrait_saved", &[("path", &format!("{:?}", path))]));
+            job.finish(result);
+        });
+    }
+}
+
+fn handle_pattern_requests(state: &mut AppState) {
+    let import = std::mem::take(&mut state.lab.pattern_import_requested);
+    let export = std::mem::take(&mut state.lab.pattern_export_requested);
+    let Some(mut snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let [u, v] = state.lab.pattern_center;
+    let center = [
+        ((u * snap.size[0] as f32) as u32).min(snap.size[0] - 1),
+        ((v * snap.size[1] as f32) as u32).min(snap.size[1] - 1),
+    ];
+    let name = state.lab.pattern_name.clone();
+    let path = pattern_path(&name);
+    let frame = state.world.frame;
+
+    if export {
+        match Pattern::extract(&snap, center, state.lab.pattern_extent, &name) {
+            Some(pattern) => match save_pattern(&path, &pattern) {
+                Ok(()) => {
+                    state.lab.log_event_with_payload(
+                        frame,
+                        EventSeverity::Info,
+                        "PATTERN",
+                        &format!("Exported {}x{} to {:?}", pattern.width, pattern.height, path),
+                        serde_json::json!({ "path": path.display().to_string(), "genome": pattern.genome }),
+                    );
+                    state.lab.set_status(trf("status.pattern_exported", &[("path", &format!("{:?}", path))]));
+                }
+                Err(e) => state.lab.set_warning(trf("status.pattern_failed", &[("error", &e)])),
             },
+            None => state.lab.set_warning(tr("status.pattern_empty").to_string()),
+        }
+    }
+
+    if import {
+        match load_pattern(&path).and_then(|(pattern, notes)| pattern.fits(snap.size).map(|_| (pattern, notes))) {
+            Ok((pattern, notes)) => {
+                pattern.stamp(&mut snap, center);
+                state.world.apply_snapshot(&state.queue, &snap);
+                for note in &notes {
+                    log::warn!("Pattern {}: {}", name, note);
+                }
+                state.lab.log_event_with_payload(
+                    frame,
+                    EventSeverity::Info,
+                    "PATTERN",
+                    &format!("Imported '{}' ({}x{}) from {:?}", pattern.name, pattern.width, pattern.height, path),
+                    serde_json::json!({ "path": path.display().to_string(), "genome": pattern.genome, "approximations": notes }),
+                );
+                let count = notes.len();
+                state.lab.set_status(trf("status.pattern_imported", &[("name", &pattern.name), ("count", &count)]));
+            }
+            Err(e) => {
+                state.lab.log_event_with_severity(frame, EventSeverity::Warn, "PATTERN", &e);
+                state.lab.set_warning(trf("status.pattern_failed", &[("error", &e)]));
+            }
+        }
+    }
+}
+
+/// Paste the source region's population over the destination, or undo the
+/// last transplant (both go through a CPU snapshot, like patterns).
+fn handle_transplant_requests(state: &mut AppState) {
+    let transplant = std::mem::take(&mut state.lab.transplant_requested);
+    let undo = std::mem::take(&mut state.lab.transplant_undo_requested);
+    let Some(mut snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let [width, height] = snap.size;
+    let to_cell = |[u, v]: [f32; 2]| {
+        [
+            ((u * width as f32) as u32).min(width - 1),
+            ((v * height as f32) as u32).min(height - 1),
+        ]
+    };
+    let frame = state.world.frame;
+
+    if undo {
+        if let Some(t) = state.lab.transplants.undo(&mut snap) {
+            state.world.apply_snapshot(&state.queue, &snap);
+            state.lab.log_event_with_payload(
+                frame,
+                EventSeverity::Info,
+                "TRANSPLANT",
+                &format!("Undid transplant of frame {} into ({}, {})", t.frame, t.to[0], t.to[1]),
+                serde_json::json!({ "undo": true, "frame": t.frame, "from": t.from, "to": t.to }),
+            );
+            state.lab.set_status(trf("status.transplant_undone", &[("frame", &t.frame)]));
+        }
+    }
+
+    if transplant {
+        let (from, to) = (to_cell(state.lab.transplant_from), to_cell(state.lab.transplant_to));
+        let patch = Patch::copy(&snap, from, state.lab.transplant_half);
+        let replaced = patch.paste(&mut snap, to);
+        state.world.apply_snapshot(&state.queue, &snap);
+        let (moved, lost) = (patch.total_mass(), replaced.total_mass());
+        state.lab.log_event_with_payload(
+            frame,
+            EventSeverity::Info,
+            "TRANSPLANT",
+            &format!(
+                "Transplanted {}x{} from ({}, {}) to ({}, {}): mass {:.1} over {:.1}",
+                patch.side, patch.side, from[0], from[1], to[0], to[1], moved, lost
+            ),
+            serde_json::json!({ "from": from, "to": to, "side": patch.side, "mass": moved, "replaced_mass": lost }),
         );
-        screenshot_staging = Some(staging);
+        state.lab.transplants.push(Transplant { frame, from, to, replaced });
+        state.lab.set_status(trf("status.transplanted", &[("mass", &format!("{moved:.1}"))]));
+    }
+}
+
+/// Clone the micro-world around the probe center and sweep it in the
+/// background.
+fn start_landscape_probe(state: &mut AppState) {
+    if state.lab.landscape.is_running() {
+        return;
+    }
+    let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let [u, v] = state.lab.landscape.settings.center;
+    let center = [
+        ((u * snap.size[0] as f32) as u32).min(snap.size[0] - 1),
+        ((v * snap.size[1] as f32) as u32).min(snap.size[1] - 1),
+    ];
+    let world = MicroWorld::clone_from(&snap, center);
+    if world.total_mass() <= 0.0 {
+        state.lab.set_warning(tr("status.landscape_empty").to_string());
+        return;
+    }
+    let settings = state.lab.landscape.settings;
+    state.lab.log_event_with_payload(
+        state.world.frame,
+        EventSeverity::Info,
+        "LANDSCAPE",
+        &format!("Probing the fitness landscape at ({}, {})", center[0], center[1]),
+        serde_json::json!({
+            "center": center,
+            "genome": world.genome,
+            "x_gene": settings.x.gene,
+            "y_gene": settings.y.map(|y| y.gene),
+            "resolution": settings.resolution,
+            "steps": settings.steps,
+        }),
+    );
+    state.lab.landscape.start(world);
+}
+
+/// Report a finished landscape probe.
+fn poll_landscape_probe(state: &mut AppState) {
+    let Some(result) = state.lab.landscape.poll() else {
+        return;
+    };
+    let best = result.and_then(|landscape| landscape.best().map(|(point, growth)| (landscape.describe(point), growth)));
+    match best {
+        Some((genes, growth)) => {
+            state.lab.set_status(trf("status.landscape_done", &[("growth", &format!("{growth:.2}")), ("genes", &genes)]))
+        }
+        None => state.lab.set_status(tr("status.landscape_cancelled").to_string()),
+    }
+}
+
+/// Spawn the selected archived LTEE sample around the spawn center.
+fn spawn_fossil_sample(state: &mut AppState) {
+    let Some(path) = state.lab.fossils.selected.clone() else {
+        return;
+    };
+    let sample = match load_sample(&path) {
+        Ok(sample) => sample,
+        Err(e) => {
+            state.lab.set_warning(trf("status.ltee_failed", &[("error", &e)]));
+            return;
+        }
+    };
+    let Some(mut snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let [u, v] = state.lab.fossils.spawn_center;
+    let center = [
+        ((u * snap.size[0] as f32) as u32).min(snap.size[0] - 1),
+        ((v * snap.size[1] as f32) as u32).min(snap.size[1] - 1),
+    ];
+    let frame = state.world.frame;
+    let spawned = spawn_sample(&mut snap, &sample, center, state.lab.fossils.spawn_radius, &mut transfer_rng(frame));
+    state.world.apply_snapshot(&state.queue, &snap);
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "FOSSIL_SPAWN",
+        &format!(
+            "Spawned transfer {} (frame {} of {}) at ({}, {}): {} cells",
+            sample.transfer, sample.frame, sample.run_id, center[0], center[1], spawned
+        ),
+        serde_json::json!({
+            "path": path.display().to_string(),
+            "run_id": sample.run_id,
+            "transfer": sample.transfer,
+            "sample_frame": sample.frame,
+            "mean_genome": sample.mean_genome(),
+            "center": center,
+            "cells": spawned,
+        }),
+    );
+    state.lab.set_status(trf("status.fossil_spawned", &[("transfer", &sample.transfer), ("frame", &sample.frame)]));
+}
+
+/// Clone the world with the mutant injected and run the invasion assay on a
+/// background world.
+fn start_invasion_assay(state: &mut AppState) {
+    if state.lab.invasion.is_running() {
+        return;
+    }
+    let Some(mut snapshot) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let settings = state.lab.invasion.settings;
+    let injected = inject_mutant(&mut snapshot, &settings);
+    if injected == 0 {
+        state.lab.set_warning(tr("status.invasion_no_cells").to_string());
+        return;
+    }
+    let frame = state.world.frame;
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "INVASION",
+        &format!("Invasion assay: {} mutant cells injected into a clone, {} steps", injected, settings.steps),
+        serde_json::json!({
+            "mutant": settings.mutant,
+            "mutation_rate": settings.mutation_rate,
+            "density": settings.density,
+            "injected": injected,
+            "steps": settings.steps,
+            "seed": settings.seed,
+        }),
+    );
+    let job = AssayJob {
+        snapshot,
+        params: state.sim_params.clone(),
+        settings,
+        frame,
+        injected,
+        csv_path: state.lab.run_dir.join(format!("invasion_{frame}.csv")),
+    };
+    state.lab.invasion.start(state.device.clone(), state.queue.clone(), job);
+}
+
+/// Log the outcome of a finished invasion assay.
+fn poll_invasion_assay(state: &mut AppState) {
+    let Some(result) = state.lab.invasion.poll() else {
+        return;
+    };
+    let frame = state.world.frame;
+    match result {
+        Ok(result) => {
+            let outcome = result.outcome();
+            let fitness = result.fitness();
+            let share = result.samples.last().map_or(0.0, |s| s.share());
+            let path = state.lab.invasion.csv_path.clone().unwrap_or_default();
+            state.lab.log_event_with_payload(
+                frame,
+                EventSeverity::Info,
+                "INVASION",
+                &format!("Invasion assay finished: {:?}, final mutant share {:.4}", outcome, share),
+                serde_json::json!({
+                    "outcome": outcome.map(|o| format!("{o:?}")),
+                    "fitness": fitness,
+                    "final_share": share,
+                    "csv": path.display().to_string(),
+                }),
+            );
+            let verdict = outcome.map_or("", |o| tr(o.label_key()));
+            state.lab.set_status(trf("status.invasion_done", &[("outcome", &verdict)]));
+        }
+        Err(e) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "INVASION", &e);
+            state.lab.set_warning(trf("status.invasion_failed", &[("error", &e)]));
+        }
+    }
+}
+
+/// Load the contestants and play a match or a round-robin tournament in the
+/// background.
+fn start_arena(state: &mut AppState, request: ArenaRequest) {
+    if state.lab.arena.is_running() {
+        return;
+    }
+    let arena = &mut state.lab.arena;
+    let paths: Vec<std::path::PathBuf> = match request {
+        ArenaRequest::Match => arena.a.iter().chain(&arena.b).cloned().collect(),
+        ArenaRequest::Tournament => arena.library.get_or_insert_with(list_library).clone(),
+    };
+    let mut contestants = Vec::new();
+    for path in &paths {
+        match load_contestant(path) {
+            Ok(contestant) => contestants.push(contestant),
+            Err(e) => state.lab.log_event_with_severity(state.world.frame, EventSeverity::Warn, "ARENA", &e),
+        }
+    }
+    if contestants.len() < 2 {
+        state.lab.set_warning(tr("status.arena_no_contestants").to_string());
+        return;
+    }
+    let pairs = round_robin(contestants.len());
+    let settings = state.lab.arena.settings;
+    let frame = state.world.frame;
+    let names: Vec<&str> = contestants.iter().map(|c| c.name.as_str()).collect();
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "ARENA",
+        &format!("Arena: {} contestants, {} matches of {} steps", contestants.len(), pairs.len(), settings.steps),
+        serde_json::json!({
+            "request": format!("{request:?}"),
+            "contestants": names,
+            "matches": pairs.len(),
+            "steps": settings.steps,
+            "seeds": settings.seeds,
+            "seed": settings.seed,
+        }),
+    );
+    let job = ArenaJob {
+        contestants,
+        pairs,
+        params: settings.neutral_params(&state.sim_params),
+        settings,
+        csv_path: state.lab.run_dir.join(format!("arena_{frame}.csv")),
+        size: state.world.size,
+    };
+    state.lab.arena.start(state.device.clone(), state.queue.clone(), job);
+}
+
+/// Log the standings of a finished arena run.
+fn poll_arena(state: &mut AppState) {
+    let Some(result) = state.lab.arena.poll() else {
+        return;
+    };
+    let frame = state.world.frame;
+    match result {
+        Ok(results) => {
+            let table = standings(results);
+            let matches: Vec<_> = results
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "a": r.a,
+                        "b": r.b,
+                        "winner": r.winner(),
+                        "final_share_a": r.samples.last().map(|s| s.share_a()),
+                    })
+                })
+                .collect();
+            let ranking: Vec<_> = table
+                .iter()
+                .map(|s| serde_json::json!({ "name": s.name, "wins": s.wins, "draws": s.draws, "losses": s.losses }))
+                .collect();
+            let leader = table.first().map_or_else(String::new, |s| s.name.clone());
+            let path = state.lab.arena.csv_path.clone().unwrap_or_default();
+            state.lab.log_event_with_payload(
+                frame,
+                EventSeverity::Info,
+                "ARENA",
+                &format!("Arena finished: {} matches, {} leads", matches.len(), leader),
+                serde_json::json!({
+                    "matches": matches,
+                    "standings": ranking,
+                    "csv": path.display().to_string(),
+                }),
+            );
+            state.lab.set_status(trf("status.arena_done", &[("leader", &leader)]));
+        }
+        Err(e) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "ARENA", &e);
+            state.lab.set_warning(trf("status.arena_failed", &[("error", &e)]));
+        }
+    }
+}
+
+/// Make the mass-weighted mean genome of zone `k`'s current occupants its
+/// resident lineage.
+fn capture_reserve_residents(state: &mut AppState, k: usize) {
+    state.world.sync_reserves(&state.queue, &state.sim_params.reserves);
+    let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let Some(zone) = state.sim_params.reserves.get_mut(k) else {
+        return;
+    };
+    match zone_mean_genome(&snap, &state.world.reserve_ids, k + 1) {
+        Some(genome) => {
+            zone.resident = genome;
+            state.lab.log_event_with_payload(
+                state.world.frame,
+                EventSeverity::Info,
+                "RESERVE",
+                &format!("Reserve '{}' residents set from its occupants", zone.name),
+                serde_json::json!({ "reserve": zone.name, "resident": genome }),
+            );
+            state.lab.set_status(trf("status.reserve_captured", &[("name", &zone.name)]));
+        }
+        None => state.lab.set_warning(trf("status.reserve_empty", &[("name", &zone.name)])),
     }
+}
+
+/// Draw the final state of a just-finalized run into its directory for the
+/// run gallery.
+fn save_pending_thumbnail(state: &mut AppState) {
+    let Some(run_dir) = state.lab.thumbnail_pending.take() else {
+        return;
+    };
+    let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        log::warn!("Thumbnail skipped for {:?}: readback failed", run_dir);
+        return;
+    };
+    match save_thumbnail(&run_dir, &snap, &state.lab.palette.uniforms()) {
+        Ok(path) => {
+            if let Some(run) = state.lab.completed_runs.iter_mut().rev().find(|r| r.run_dir == run_dir) {
+                run.thumbnail = Some(path);
+            }
+        }
+        Err(e) => log::warn!("Thumbnail failed: {}", e),
+    }
+}
+
+/// Read back the running means and write them (arrays, PNGs, sidecar) to a
+/// folder of the run directory in the background.
+fn export_field_averages(state: &mut AppState) {
+    let frame = state.world.frame;
+    let Some(averages) = state.world.read_field_averages(&state.device, &state.queue) else {
+        state.lab.set_warning(tr("status.averages_empty").to_string());
+        return;
+    };
+    let dir = averages::export_dir(&state.lab.run_dir, frame);
+    let window = state.sim_params.averages.window;
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "AVERAGES",
+        &format!("Exporting field averages over {} steps to {:?}", averages.samples, dir),
+        serde_json::json!({ "path": dir.display().to_string(), "samples": averages.samples, "window": window }),
+    );
+    state.lab.last_average_export = Some(dir.clone());
+    let run_id = state.lab.run_id.clone();
+    let job = state.lab.notifications.start_job(tr("job.averages").to_string(), "AVERAGES");
+    std::thread::spawn(move || {
+        let result = averages::export(&dir, &averages, frame, window, &run_id)
+            .map(|files| trf("status.averages_exported", &[("count", &files.len()), ("path", &format!("{:?}", dir))]))
+            .inspect_err(|e| log::error!("Field average export failed: {}", e));
+        job.finish(result);
+    });
+}
+
+/// Read back the velocity field and write its array and/or quiver plot in
+/// the background. Periodic exports only log failures; one-shot exports
+/// are logged and reported like other exports.
+fn export_velocity_field(state: &mut AppState, one_shot: bool) {
+    let frame = state.world.frame;
+    let Some(velocity) = state.world.read_velocity(&state.device, &state.queue) else {
+        return;
+    };
+    let size = state.world.size;
+    let mut settings = state.sim_params.velocity_export;
+    if one_shot && !settings.npy && !settings.quiver {
+        settings.npy = true;
+    }
+    let run_dir = state.lab.run_dir.clone();
+    let stem = vectors::velocity_stem(&run_dir, frame);
+    state.lab.last_velocity_export = Some(stem.clone());
+    if !one_shot {
+        std::thread::spawn(move || {
+            if let Err(e) = vectors::export_velocity(&run_dir, frame, &velocity, size, &settings) {
+                log::error!("Velocity export failed: {}", e);
+            }
+        });
+        return;
+    }
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "VELOCITY_EXPORT",
+        &format!("Exporting the velocity field to {:?}", stem),
+        serde_json::json!({ "path": stem.display().to_string(), "npy": settings.npy, "quiver": settings.quiver }),
+    );
+    let job = state.lab.notifications.start_job(tr("job.velocity").to_string(), "VELOCITY_EXPORT");
+    std::thread::spawn(move || {
+        let result = vectors::export_velocity(&run_dir, frame, &velocity, size, &settings)
+            .map(|files| trf("status.velocity_exported", &[("count", &files.len()), ("path", &format!("{:?}", stem))]))
+            .inspect_err(|e| log::error!("Velocity export failed: {}", e));
+        job.finish(result);
+    });
+}
+
+/// Open a tape in the run directory and capture the current frame. The
+/// full-resolution state and the parameters go next to it for replays.
+fn start_tape(state: &mut AppState) {
+    let frame = state.world.frame;
+    let path = tape::tape_path(&state.lab.run_dir, frame);
+    let header = TapeHeader::new(&state.lab.run_id, &state.lab.tape.settings, state.world.size);
+    let Some(start) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let params = serde_json::to_string_pretty(&state.sim_params).map_err(std::io::Error::other);
+    let result = std::fs::create_dir_all(&state.lab.run_dir)
+        .and_then(|_| state_io::save_snapshot(&tape::start_snapshot_path(&path).to_string_lossy(), &start))
+        .and_then(|_| std::fs::write(tape::start_params_path(&path), params?))
+        .and_then(|_| TapeWriter::create(&path, header));
+    match result {
+        Ok(writer) => {
+            let size = writer.header.size;
+            state.lab.log_event_with_payload(
+                frame,
+                EventSeverity::Info,
+                "TAPE_START",
+                &format!("Recording tape {:?} ({}x{}, every {} steps)", path, size[0], size[1], writer.header.interval),
+                serde_json::json!({ "path": path.display().to_string(), "size": size, "interval": writer.header.interval }),
+            );
+            state.lab.tape.writer = Some(writer);
+            state.lab.tape.next_frame = frame;
+            state.lab.tape.last_path = Some(path);
+            record_tape_frame(state);
+        }
+        Err(e) => state.lab.set_warning(trf("status.tape_failed", &[("error", &e)])),
+    }
+}
+
+/// Append the current frame, with metrics computed from the same readback.
+fn record_tape_frame(state: &mut AppState) {
+    let frame = state.world.frame;
+    let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let time_ms = state.lab.run_start.elapsed().as_secs_f64() * 1000.0;
+    let metrics =
+        MetricsRecord::from_diagnostics(&SimDiagnostics::from_snapshot(&snap), frame, time_ms, state.fps).with_plugins(&snap);
+    let Some(writer) = state.lab.tape.writer.as_mut() else {
+        return;
+    };
+    let result = writer.write_frame(frame, &metrics, &snap);
+    state.lab.tape.next_frame = frame + writer.header.interval;
+    if let Err(e) = result {
+        state.lab.set_warning(trf("status.tape_failed", &[("error", &e)]));
+        stop_tape(state);
+    }
+}
+
+/// Close the tape, check that it reads back, and log it.
+fn stop_tape(state: &mut AppState) {
+    let Some(writer) = state.lab.tape.writer.take() else {
+        return;
+    };
+    let (frames, bytes, last_frame) = (writer.frames, writer.bytes, writer.last_frame);
+    let cells = (writer.header.size[0] * writer.header.size[1]) as usize;
+    let verified = writer.finish().and_then(|path| {
+        let mut reader = TapeReader::open(&path)?;
+        if !reader.is_empty() {
+            let last = reader.read(reader.len() - 1)?;
+            if Some(last.frame) != last_frame || last.metrics.frame != last.frame || last.snapshot.mass.len() != cells {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "the last frame does not read back"));
+            }
+        }
+        Ok((path, reader.len()))
+    });
+    match verified {
+        Ok((path, indexed)) => {
+            state.lab.log_event_with_payload(
+                state.world.frame,
+                EventSeverity::Info,
+                "TAPE_STOP",
+                &format!("Tape {:?} closed: {} frames, {}", path, indexed, format_bytes(bytes)),
+                serde_json::json!({ "path": path.display().to_string(), "frames": indexed, "bytes": bytes }),
+            );
+            let size = format_bytes(bytes);
+            state.lab.set_status(trf("status.tape_saved", &[("frames", &frames), ("size", &size), ("path", &path.display())]));
+        }
+        Err(e) => state.lab.set_warning(trf("status.tape_failed", &[("error", &e)])),
+    }
+}
+
+/// Re-simulate the chosen tape or snapshot folder on a background world.
+fn start_replay_check(state: &mut AppState) {
+    if state.lab.replay.is_running() {
+        return;
+    }
+    let Some(source) = state.lab.replay.source.clone() else {
+        return;
+    };
+    let frame = state.world.frame;
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "REPLAY",
+        &format!("Replay check of {:?}", source.path()),
+        serde_json::json!({ "path": source.path().display().to_string(), "tolerance": state.lab.replay.settings.tolerance }),
+    );
+    let job = ReplayJob {
+        csv_path: replay::report_path(&state.lab.run_dir, &source),
+        source,
+        params: state.sim_params.clone(),
+        settings: state.lab.replay.settings,
+    };
+    state.lab.replay.start(state.device.clone(), state.queue.clone(), job);
+}
+
+/// Log the outcome of a finished replay check.
+fn poll_replay_check(state: &mut AppState) {
+    let Some(result) = state.lab.replay.poll() else {
+        return;
+    };
+    let frame = state.world.frame;
+    match result {
+        Ok(result) => {
+            let (checked, start_frame, cancelled) = (result.checkpoints.len(), result.start_frame, result.cancelled);
+            let divergence = result.first_divergence().map(|c| {
+                let worst = c.channels.iter().filter(|d| d.differing > 0).max_by(|a, b| a.max_diff.total_cmp(&b.max_diff));
+                let channel = worst.map_or("", |d| d.name);
+                let cell = worst.and_then(|d| d.first_cell).map(|cell| result.cell_xy(cell));
+                (c.frame, channel, cell, worst.map_or(0.0, |d| d.max_diff))
+            });
+            state.lab.log_event_with_payload(
+                frame,
+                if divergence.is_some() { EventSeverity::Warn } else { EventSeverity::Info },
+                "REPLAY",
+                &match divergence {
+                    Some((at, channel, cell, diff)) => {
+                        format!("Replay diverged at frame {} ({}: max difference {:e} at {:?})", at, channel, diff, cell)
+                    }
+                    None => format!("Replay matched the recording at {} checkpoints", checked),
+                },
+                serde_json::json!({
+                    "start_frame": start_frame,
+                    "checkpoints": checked,
+                    "first_divergence": divergence.map(|d| d.0),
+                    "channel": divergence.map(|d| d.1),
+                    "cell": divergence.and_then(|d| d.2),
+                    "cancelled": cancelled,
+                }),
+            );
+            match divergence {
+                Some((at, channel, _, _)) => {
+                    state.lab.set_warning(trf("status.replay_diverged", &[("frame", &at), ("channel", &channel)]))
+                }
+                None => state.lab.set_status(trf("status.replay_matched", &[("count", &checked)])),
+            }
+        }
+        Err(e) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "REPLAY", &e);
+            state.lab.set_warning(trf("status.replay_failed", &[("error", &e)]));
+        }
+    }
+}
+
+/// Why the world cannot be swapped for another size now: background runs
+/// and tapes follow the world they started on.
+fn resize_blocker(state: &AppState) -> Option<&'static str> {
+    let lab = &state.lab;
+    if lab.replay.is_running()
+        || lab.arena.is_running()
+        || lab.invasion.is_running()
+        || lab.landscape.is_running()
+        || lab.experiment_queue.active().is_some()
+        || state.sim_params.ltee.enabled
+    {
+        Some("status.resize_busy")
+    } else if lab.playback.is_active() || lab.tape.is_recording() {
+        Some("status.resize_tape")
+    } else {
+        None
+    }
+}
 
-    // Submit the simulation render encoder (with optional screenshot copy)
-    state.queue.submit(std::iter::once(encoder.finish()));
+/// Build the world at the size asked for in the Lab, in the background.
+fn start_world_resize(state: &mut AppState) {
+    let size = state.lab.resize.target;
+    if state.lab.resize.is_running() || size == state.world.size {
+        return;
+    }
+    if let Some(key) = resize_blocker(state) {
+        state.lab.set_warning(tr(key).to_string());
+        return;
+    }
+    let limits = state.device.limits();
+    let report = MemoryReport::for_world(&state.world, &limits);
+    let job = ResizeJob { size, layout: state.world.layout, grid: state.world.grid };
+    if let Err(e) = resize::check_resize(size, job.layout, job.grid, &report, limits.max_texture_dimension_2d) {
+        state.lab.set_warning(trf("status.resize_refused", &[("error", &e)]));
+        return;
+    }
+    state.lab.log_event_with_payload(
+        state.world.frame,
+        EventSeverity::Info,
+        "RESIZE",
+        &format!("Building a {}×{} world", size[0], size[1]),
+        serde_json::json!({ "from": state.world.size, "to": size }),
+    );
+    state.lab.set_status(trf("status.resize_started", &[("width", &size[0]), ("height", &size[1])]));
+    state.lab.resize.start(state.device.clone(), state.queue.clone(), job);
+}
 
-    // ---- egui render pass (on top of simulation, separate encoder) ----
-    let paint_jobs = state
-        .egui_ctx
-        .tessellate(full_output.shapes, full_output.pixels_per_point);
+/// Swap in a world built by `start_world_resize`, carrying the live one over.
+fn poll_world_resize(state: &mut AppState) {
+    let Some(result) = state.lab.resize.poll() else {
+        return;
+    };
+    let frame = state.world.frame;
+    let outcome = result.and_then(|mut resized| {
+        // A tape or background run may have started during the build
+        if let Some(key) = resize_blocker(state) {
+            return Err(tr(key).to_string());
+        }
+        resize::carry_over(&state.device, &state.queue, &state.world, &mut resized.world)?;
+        Ok(resized)
+    });
+    let resized = match outcome {
+        Ok(resized) => resized,
+        Err(e) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "RESIZE", &e);
+            state.lab.set_warning(trf("status.resize_failed", &[("error", &e)]));
+            return;
+        }
+    };
+    let from = state.world.size;
+    let size = resized.world.size;
+    crash::set_world(size);
+    state.world = resized.world;
+    state.lab.world_size = size;
+    state.pipelines = resized.pipelines;
+    reload_user_modes(state);
+    // CPU state indexed by the cells of the old world
+    state.lab.clear_barrier();
+    state.lab.transplants.clear();
+    state.lab.rewind.clear();
+    state.lab.territory.clear();
+    state.lab.territory_areas.clear();
+    state.lab.diff_reference = None;
+    state.lab.diff_divergence = None;
+    state.last_diag = None;
+    state.display_lut_key = None;
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "RESIZE",
+        &format!("World resized from {}×{} to {}×{}", from[0], from[1], size[0], size[1]),
+        serde_json::json!({ "from": from, "to": size }),
+    );
+    state.lab.set_status(trf("status.resized", &[("width", &size[0]), ("height", &size[1])]));
+}
 
-    for (id, image_delta) in &full_output.textures_delta.set {
-        state
-            .egui_renderer
-            .update_texture(&state.device, &state.queue, *id, image_delta);
+/// Open a tape for playback: the live world is read back and set aside,
+/// and the simulation stops until playback is closed.
+fn open_playback(state: &mut AppState, path: &Path) {
+    if state.lab.tape.is_recording() {
+        state.lab.set_warning(tr("status.playback_recording").to_string());
+        return;
     }
+    close_playback(state);
+    let frame = state.world.frame;
+    let opened = TapeReader::open(path).map_err(|e| e.to_string()).and_then(|reader| {
+        if reader.header.world != state.world.size {
+            let [w, h] = reader.header.world;
+            Err(trf("status.playback_world", &[("size", &format!("{w}x{h}"))]))
+        } else if reader.is_empty() {
+            Err(tr("status.playback_empty").to_string())
+        } else {
+            Ok(reader)
+        }
+    });
+    let reader = match opened {
+        Ok(reader) => reader,
+        Err(error) => {
+            state.lab.set_warning(trf("status.playback_failed", &[("error", &error)]));
+            return;
+        }
+    };
+    let Some(snapshot) = state.world.readback_snapshot(&state.device, &state.queue) else {
+        return;
+    };
+    let frames = reader.len();
+    let resume = PlaybackResume { snapshot, frame, paused: state.sim_params.paused };
+    state.lab.playback.player = Some(TapePlayer::new(reader, path, resume));
+    state.sim_params.paused = true;
+    state.lab.log_event_with_payload(
+        frame,
+        EventSeverity::Info,
+        "PLAYBACK",
+        &format!("Playing tape {:?} ({} frames)", path, frames),
+        serde_json::json!({ "path": path.display().to_string(), "frames": frames }),
+    );
+    state.lab.set_status(trf("status.playback_opened", &[("frames", &frames), ("path", &path.display())]));
+}
 
-    let screen_descriptor = egui_wgpu::ScreenDescriptor {
-        size_in_pixels: [win_w, win_h],
-        pixels_per_point: full_output.pixels_per_point,
+/// Close the tape and put the live world back as it was.
+fn close_playback(state: &mut AppState) {
+    let Some(player) = state.lab.playback.player.take() else {
+        return;
     };
+    let resume = player.resume;
+    state.world.apply_snapshot(&state.queue, &resume.snapshot);
+    state.world.frame = resume.frame;
+    state.sim_params.paused = resume.paused;
+    state.display_lut_key = None;
+    if player.export.is_some() {
+        state.lab.set_warning(tr("status.sequence_aborted").to_string());
+    }
+    state.lab.log_event(resume.frame, "PLAYBACK", &format!("Closed tape {:?}", player.path));
+}
 
-    let mut egui_encoder = state
-        .device
-        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("egui_encoder"),
-        });
+/// Advance the transport and upload the frame to show when it changed.
+fn show_playback_frame(state: &mut AppState, dt: f32) {
+    let Some(player) = state.lab.playback.player.as_mut() else {
+        return;
+    };
+    player.tick(dt);
+    if !player.needs_upload() {
+        return;
+    }
+    let position = player.position;
+    let shown = player.reader.read(position).map(|frame| {
+        let mut world = tape::upsample(&frame.snapshot, &player.reader.header);
+        world.flow = player.resume.snapshot.flow.clone(); // not recorded: keep the live field
+        player.shown = Some(position);
+        player.metrics = Some(frame.metrics);
+        (frame.frame, world)
+    });
+    match shown {
+        Ok((frame, world)) => {
+            state.world.apply_snapshot(&state.queue, &world);
+            state.world.frame = frame;
+            if state.sim_params.current_display_adjust().auto_normalize {
+                apply_auto_normalize(&mut state.sim_params, &world);
+            }
+            if state.sim_params.current_display_adjust().transform == DisplayTransform::Equalize {
+                upload_display_lut(state, &world);
+            }
+            if state.lab.playback.exporting() {
+                state.lab.screenshot_requested = true;
+            }
+        }
+        Err(e) => {
+            state.lab.set_warning(trf("status.playback_failed", &[("error", &e)]));
+            close_playback(state);
+        }
+    }
+}
 
-    state.egui_renderer.update_buffers(
-        &state.device,
-        &state.queue,
-        &mut egui_encoder,
-        &paint_jobs,
-        &screen_descriptor,
-    );
+/// What a screenshot readback is for, fixed when it was requested.
+struct ShotTag {
+    frame: u32,
+    mode: u32,
+    sequence: bool, // next image of a tape sequence export
+}
 
-    render_egui_pass(
-        &state.egui_renderer,
-        &mut egui_encoder,
-        &view,
-        &paint_jobs,
-        &screen_descriptor,
-    );
+/// Composite the legend into a finished capture and save it: as the next
+/// sequence image, or as a screenshot PNG encoded on a worker (slow at
+/// large window sizes).
+fn save_capture(state: &mut AppState, captured: CapturedFrame<ShotTag>) {
+    let CapturedFrame { tag, width, height, rgba } = captured;
+    let mut rgba = match rgba {
+        Ok(rgba) => rgba,
+        Err(e) => return screenshot_failed(state, tag.frame, &e),
+    };
 
-    state.queue.submit(std::iter::once(egui_encoder.finish()));
+    // The legend overlay is egui, painted after the capture
+    let legend = state.sim_params.legend;
+    if legend.enabled && legend.in_screenshots {
+        let palette = state.lab.palette.uniforms();
+        let areas = &state.lab.territory_areas;
+        if let Some(legend) = legend_for(&state.sim_params, &palette, areas, state.lab.territory.species_count()) {
+            if let Err(e) = composite_legend(&mut rgba, width, height, &legend) {
+                log::warn!("Legend not added to the screenshot: {}", e);
+            }
+        }
+    }
 
-    // ---- Read back screenshot ----
-    if do_screenshot {
-        if let Some(staging) = &screenshot_staging {
-            let slice = staging.slice(..);
-            let (tx, rx) = std::sync::mpsc::channel();
-            slice.map_async(wgpu::MapMode::Read, move |result| {
-                let _ = tx.send(result);
-            });
-            state.device.poll(wgpu::Maintain::Wait);
-
-            if let Ok(Ok(())) = rx.recv() {
-                let data = slice.get_mapped_range();
-                // Extract RGBA data, removing row padding & swapping BGRA→RGBA
-                let mut rgba = Vec::with_capacity((win_w * win_h * 4) as usize);
-                for row in 0..win_h {
-                    let start = (row * screenshot_padded_bpr) as usize;
-                    let end = start + (win_w * 4) as usize;
-                    let row_data = &data[start..end];
-                    for chunk in row_data.chunks_exact(4) {
-                        // BGRA → RGBA swap
-                        rgba.push(chunk[2]); // R
-                        rgba.push(chunk[1]); // G
-                        rgba.push(chunk[0]); // B
-                        rgba.push(chunk[3]); // A
+    if tag.sequence {
+        return write_sequence_frame(state, &rgba, width, height);
+    }
+    let path = match state.lab.screenshot_path(tag.frame, tag.mode) {
+        Ok(path) => path,
+        Err(e) => return screenshot_failed(state, tag.frame, &e),
+    };
+    state.lab.log_event_with_payload(
+        tag.frame,
+        EventSeverity::Info,
+        "SCREENSHOT",
+        &format!("Saving to {:?}", path),
+        serde_json::json!({ "path": path.display().to_string() }),
+    );
+    let job = state.lab.notifications.start_job(tr("job.screenshot").to_string(), "SCREENSHOT");
+    let tensorboard = state.lab.tensorboard_log();
+    let tag_name = format!("screenshots/{}", state.lab.mode_name(tag.mode).replace('/', "_"));
+    std::thread::spawn(move || {
+        let result = encode_png(&rgba, width, height)
+            .and_then(|png| {
+                if let Some(log) = &tensorboard {
+                    if let Err(e) = log.image(tag.frame, &tag_name, width, height, &png) {
+                        log::warn!("TensorBoard screenshot failed: {}", e);
                     }
                 }
-                drop(data);
-                staging.unmap();
+                std::fs::write(&path, png).map_err(|e| e.to_string())
+            })
+            .map(|()| {
+                log::info!("Screenshot saved: {:?}", path);
+                trf("status.screenshot_saved", &[("path", &format!("{:?}", path))])
+            })
+            .map_err(|e| {
+                log::error!("Screenshot failed: {}", e);
+                e
+            });
+        job.finish(result);
+    });
+}
 
-                match state.lab.save_screenshot(
-                    state.world.frame,
-                    win_w,
-                    win_h,
-                    &rgba,
-                    state.sim_params.visualization_mode,
-                ) {
-                    Ok(path) => {
-                        state.lab.set_status(format!("Screenshot saved: {:?}", path));
-                        state.lab.log_event(
-                            state.world.frame,
-                            "SCREENSHOT",
-                            &format!("Saved to {:?}", path),
-                        );
-                    }
-                    Err(e) => {
-                        state.lab.set_status(format!("Screenshot failed: {}", e));
-                        log::error!("Screenshot failed: {}", e);
-                    }
-                }
-            }
+fn screenshot_failed(state: &mut AppState, frame: u32, error: &str) {
+    state.lab.set_warning(trf("status.screenshot_failed", &[("error", &error)]));
+    state.lab.log_event_with_severity(frame, EventSeverity::Warn, "SCREENSHOT", &format!("Failed: {}", error));
+    log::error!("Screenshot failed: {}", error);
+}
+
+/// Start rendering tape entries `from..=to` to a numbered PNG sequence, or
+/// to a video at the playback frame rate.
+fn start_sequence_export(state: &mut AppState, (from, to): (usize, usize)) {
+    let Some(player) = state.lab.playback.player.as_mut() else {
+        return;
+    };
+    let stem = player.path.file_stem().map_or_else(|| "tape".into(), |s| s.to_string_lossy());
+    let name = format!("{}_sequence_f{:06}", stem, player.frame_at(from));
+    let path = match state.lab.playback.export_video {
+        true => state.lab.run_dir.join(format!("{name}.mp4")),
+        false => state.lab.run_dir.join(name),
+    };
+    let sink = match open_frame_sink(&path, player.fps) {
+        Ok(sink) => sink,
+        Err(e) => return state.lab.set_warning(trf("status.sequence_failed", &[("error", &e)])),
+    };
+    let end = to.min(player.reader.len() - 1);
+    player.playing = false;
+    player.shown = None; // render the first image even when it is on show
+    player.export = Some(SequenceExport { path, sink, next: from.min(end), end, written: 0 });
+}
+
+/// Write the rendered tape frame as the next image of the sequence, and
+/// move on or finish.
+fn write_sequence_frame(state: &mut AppState, rgba: &[u8], width: u32, height: u32) {
+    let Some(export) = state.lab.playback.player.as_mut().and_then(|p| p.export.as_mut()) else {
+        return;
+    };
+    let result = export.sink.write_frame(state.world.frame, rgba, width, height);
+    if let Err(e) = result {
+        state.lab.set_warning(trf("status.sequence_failed", &[("error", &e)]));
+        if let Some(player) = state.lab.playback.player.as_mut() {
+            player.export = None;
         }
-        state.lab.screenshot_requested = false;
+        return;
     }
+    export.written += 1;
+    export.next += 1;
+    if export.next <= export.end {
+        return;
+    }
+    let (dir, written) = (export.path.clone(), export.written);
+    let finished = export.sink.finish();
+    if let Some(player) = state.lab.playback.player.as_mut() {
+        player.export = None;
+    }
+    if let Err(e) = finished {
+        return state.lab.set_warning(trf("status.sequence_failed", &[("error", &e)]));
+    }
+    state.lab.log_event_with_payload(
+        state.world.frame,
+        EventSeverity::Info,
+        "SEQUENCE_EXPORT",
+        &format!("Wrote {} tape frames to {:?}", written, dir),
+        serde_json::json!({ "path": dir.display().to_string(), "frames": written }),
+    );
+    state.lab.set_status(trf("status.sequence_exported", &[("count", &written), ("path", &dir.display())]));
+}
 
-    // ---- Snapshot (state save) ----
-    if state.lab.snapshot_requested {
-        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
-            let path = state
-                .lab
-                .run_dir
-                .join(format!("snapshot_frame{:06}.snap", state.world.frame));
-            match state_io::save_snapshot(path.to_str().unwrap_or("snapshot.snap"), &snap) {
-                Ok(()) => {
-                    state
-                        .lab
-                        .set_status(format!("Snapshot saved: {:?}", path));
-                    state.lab.log_event(
-                        state.world.frame,
-                        "SNAPSHOT",
-                        &format!("Saved to {:?}", path),
-                    );
-                }
-                Err(e) => {
-                    log::error!("Snapshot save failed: {}", e);
-                    state.lab.set_status(format!("Snapshot failed: {}", e));
-                }
-            }
+/// Replace the world's fields with a snapshot file; the frame counter and
+/// the run carry on.
+fn load_snapshot_file(state: &mut AppState, path: &Path) {
+    let frame = state.world.frame;
+    let name = path.display().to_string();
+    let result = state_io::load_snapshot(&name).map_err(|e| e.to_string()).and_then(|snap| {
+        if state.world.apply_snapshot(&state.queue, &state_io::fit_snapshot(snap, state.world.size)) {
+            Ok(())
+        } else {
+            Err(tr("status.diff_reference_size").to_string())
+        }
+    });
+    match result {
+        Ok(()) => {
+            state.last_diag = None;
+            state.lab.transplants.clear();
+            state.lab.speciation.reset();
+            state.lab.rewind.clear();
+            state.world.reset_averages();
+            state.lab.log_event_with_payload(
+                frame,
+                EventSeverity::Info,
+                "STATE_LOAD",
+                &format!("Loaded snapshot {:?}", path),
+                serde_json::json!({ "path": name }),
+            );
+            state.lab.set_status(trf("status.snapshot_loaded", &[("path", &name)]));
+        }
+        Err(error) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "STATE_LOAD", &error);
+            state.lab.set_warning(trf("status.state_load_failed", &[("path", &name), ("error", &error)]));
         }
-        state.lab.snapshot_requested = false;
     }
+}
 
-    output.present();
+/// Set the diff overlay's reference from a snapshot file or from the
+/// current world, and upload its mass field for the render shader.
+fn handle_diff_reference_requests(state: &mut AppState) {
+    let load = std::mem::take(&mut state.lab.diff_load_requested);
+    let capture = std::mem::take(&mut state.lab.diff_capture_requested);
+    let frame = state.world.frame;
+    let reference = if load {
+        let path = state.lab.diff_reference_path.trim().to_string();
+        state_io::load_snapshot(&path)
+            .map_err(|e| e.to_string())
+            .map(|snap| ReferenceMass { label: path, mass: state_io::fit_snapshot(snap, state.world.size).mass })
+    } else if capture {
+        state
+            .world
+            .readback_snapshot(&state.device, &state.queue)
+            .map(|snap| ReferenceMass { label: format!("frame {}", frame), mass: snap.mass })
+            .ok_or_else(|| String::from("readback failed"))
+    } else {
+        return;
+    };
 
-    for id in &full_output.textures_delta.free {
-        state.egui_renderer.free_texture(id);
+    match reference {
+        Ok(reference) if state.world.upload_reference_mass(&state.queue, &reference.mass) => {
+            state.lab.log_event(frame, "REFERENCE", &format!("Diff reference set to {}", reference.label));
+            state.lab.set_status(trf("status.diff_reference_set", &[("name", &reference.label)]));
+            state.lab.diff_divergence = None;
+            state.lab.diff_reference = Some(reference);
+        }
+        Ok(_) => {
+            let error = tr("status.diff_reference_size").to_string();
+            state.lab.set_warning(trf("status.diff_reference_failed", &[("error", &error)]));
+        }
+        Err(error) => {
+            state.lab.log_event_with_severity(frame, EventSeverity::Warn, "REFERENCE", &error);
+            state.lab.set_warning(trf("status.diff_reference_failed", &[("error", &error)]));
+        }
     }
-    state.hud.trim();
+}
 
-    // ---- Periodic diagnostics ----
-    if !state.sim_params.paused
-        && state.world.frame > 0
-        && state.world.frame % state.diag_interval == 0
-    {
-        if let Some(snap) = state.world.readback_snapshot(&state.device, &state.queue) {
-            let diag = SimDiagnostics::from_snapshot(&snap);
-            state
-                .lab
-                .record_metrics(&diag, state.world.frame, state.fps);
-            diag.log(
-                state.world.frame,
-                target_total_mass(),
-                state.last_diag.as_ref(),
-            );
-            state.last_diag = Some(diag);
-        }
+fn apply_auto_normalize(params: &mut SimulationParams, snap: &BufferSnapshot) {
+    let mode = params.visualization_mode;
+    let adjust = params.current_display_adjust_mut();
+    if let Some(values) = display::display_field(snap, mode) {
+        let (lo, hi) = display::auto_range(values, adjust.exposure);
+        adjust.range_min = lo;
+        adjust.range_max = hi;
     }
+}
 
-    state.window.request_redraw();
+/// Rebuild the histogram-equalization LUT for the active mode and upload it.
+/// Modes without a CPU-side field get the identity table.
+fn upload_display_lut(state: &mut AppState, snap: &BufferSnapshot) {
+    let mode = state.sim_params.visualization_mode;
+    let adjust = *state.sim_params.current_display_adjust();
+    let lut = match display::display_field(snap, mode) {
+        Some(values) => display::equalization_lut(values, &adjust),
+        None => display::identity_lut(),
+    };
+    state
+        .queue
+        .write_buffer(&state.world.display_lut, 0, bytemuck::cast_slice(&lut));
+    state.display_lut_key = Some((mode, adjust));
 }
 
 // ======================== egui Render Helper ========================
 
+/// Replace the flow field with the selected preset, or clear it.
+/// Recompile the user visualization modes against the current render
+/// pipelines, reporting the files that fail; a selected mode that is gone
+/// falls back to the first built-in one.
+/// Log GPU errors caught since the last frame (GPU validation on), once
+/// per distinct error.
+fn report_gpu_errors(state: &mut AppState) {
+    for report in state.gpu_errors.drain() {
+        log::warn!("GPU error in {}: {}", report.scope, report.message);
+        state.lab.log_event_with_severity(
+            state.world.frame,
+            EventSeverity::Warn,
+            "GPU_ERROR",
+            &format!("{}: {}", report.scope, report.message),
+        );
+        state.lab.set_warning(trf("status.gpu_error", &[("scope", &report.scope)]));
+    }
+    state.lab.gpu_errors = state.gpu_errors.counts();
+}
+
+fn reload_user_modes(state: &mut AppState) {
+    let (modes, errors) = state.gpu_errors.scope(&state.device, "create_pipelines", || {
+        load_user_modes(&state.device, &state.pipelines, Path::new(USER_MODES_DIR))
+    });
+    for (file, error) in errors {
+        log::warn!("User visualization mode {} failed: {}", file, error);
+        state.lab.log_event_with_severity(
+            state.world.frame,
+            EventSeverity::Warn,
+            "USER_MODE",
+            &format!("{}: {}", file, error),
+        );
+        state.lab.set_warning(trf("status.user_mode_failed", &[("file", &file), ("error", &error)]));
+    }
+    if !modes.is_empty() {
+        log::info!("Loaded {} user visualization mode(s) from {}/", modes.len(), USER_MODES_DIR);
+    }
+    state.lab.user_modes = modes.iter().map(|m| m.name.clone()).collect();
+    state.user_modes = modes;
+    if state.sim_params.visualization_mode >= VIS_MODE_COUNT + state.user_modes.len() as u32 {
+        state.sim_params.visualization_mode = 0;
+    }
+}
+
+fn handle_flow_requests(state: &mut AppState) {
+    let lab = &mut state.lab;
+    let details = if std::mem::take(&mut lab.flow_clear_requested) {
+        lab.flow_generate_requested = false;
+        state.world.flow = FlowField::zeros(state.world.size);
+        "Flow field cleared".to_string()
+    } else {
+        lab.flow_generate_requested = false;
+        state.world.flow = FlowField::generate(lab.flow_preset, lab.flow_angle.to_radians(), lab.flow_seed, state.world.size);
+        format!("Flow field generated: {:?} (angle {:.0}°, seed {})", lab.flow_preset, lab.flow_angle, lab.flow_seed)
+    };
+    state.world.upload_flow(&state.queue);
+    lab.log_event(state.world.frame, "FLOW", &details);
+    lab.set_status(tr("flow.status_updated").to_string());
+}
+
 /// Render egui paint jobs into a render pass.
 /// Extracted as a free function to decouple the egui::Renderer lifetime
 /// from the AppState borrow, allowing the render pass encoder to be local.
-fn render_egui_pass(
+pub(crate) fn render_egui_pass(
     renderer: &egui_wgpu::Renderer,
     encoder: &mut wgpu::CommandEncoder,
     view: &wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
     paint_jobs: &[egui::ClippedPrimitive],
     screen_descriptor: &egui_wgpu::ScreenDescriptor,
 ) {
@@ -892,7 +3559,7 @@ fn render_egui_pass(
             view,
             resolve_target: None,
             ops: wgpu::Operations {
-                load: wgpu::LoadOp::Load,
+                load,
                 store: wgpu::StoreOp::Store,
             },
         })],
@@ -908,6 +3575,42 @@ fn render_egui_pass(
 
 // ======================== Simulation Encoding ========================
 
+/// Optional passes of one simulation step.
+#[derive(Clone, Copy)]
+struct StepPasses {
+    normalize: NormalizeStep,
+    velocity: bool, // separate velocity pass instead of the fused one
+}
+
+/// Separable convolution: filter mass[cur] along x, then along y into the
+/// per-tier potential texture read by the evolution pass.
+fn encode_convolution_passes(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    cur: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+) {
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("separable_h_pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipelines.separable_h_pipeline);
+        pass.set_bind_group(0, &pipelines.separable_h_bind_groups[cur], &[]);
+        pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+    }
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("separable_v_pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipelines.separable_v_pipeline);
+        pass.set_bind_group(0, &pipelines.separable_v_bind_group, &[]);
+        pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+    }
+}
+
 fn encode_simulation_passes(
     encoder: &mut wgpu::CommandEncoder,
     pipelines: &Pipelines,
@@ -915,9 +3618,10 @@ fn encode_simulation_passes(
     dispatch_x: u32,
     dispatch_y: u32,
     dispatch_linear: u32,
+    passes: StepPasses,
 ) {
-    // Pass 1: Velocity field
-    {
+    // Pass 1: Velocity field (debug; normally fused into the evolution pass)
+    if passes.velocity {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("velocity_pass"),
             timestamp_writes: None,
@@ -949,8 +3653,8 @@ fn encode_simulation_passes(
         pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
     }
 
-    // Pass 4a: Sum total mass (reduction)
-    {
+    // Pass 4a: Sum total mass (reduction), at the normalization cadence
+    if passes.normalize == NormalizeStep::Measure {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("sum_mass_pass"),
             timestamp_writes: None,
@@ -961,7 +3665,7 @@ fn encode_simulation_passes(
     }
 
     // Pass 4b: Normalize mass to target
-    {
+    if passes.normalize != NormalizeStep::Skip {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("normalize_pass"),
             timestamp_writes: None,
@@ -971,3 +3675,117 @@ fn encode_simulation_passes(
         pass.dispatch_workgroups(dispatch_linear, 1, 1);
     }
 }
+
+/// Fold the particle flux of the last update into the current state, then
+/// move the particles over it (see compute_particles.wgsl).
+fn encode_particle_passes(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    cur: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+    params: &SimulationParams,
+) {
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("particle_apply_pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipelines.particle_apply_pipeline);
+        pass.set_bind_group(0, &pipelines.particle_apply_bind_groups[cur], &[]);
+        pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+    }
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("particle_update_pass"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(&pipelines.particle_update_pipeline);
+    pass.set_bind_group(0, &pipelines.particle_update_bind_groups[cur], &[]);
+    pass.dispatch_workgroups(params.particles.active_count().div_ceil(WORKGROUP_LINEAR), 1, 1);
+}
+
+/// Decay the trail layer and imprint the mass flux of the step just encoded.
+fn encode_trail_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    cur: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("trail_pass"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(&pipelines.trail_pipeline);
+    pass.set_bind_group(0, &pipelines.trail_bind_groups[cur], &[]);
+    pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+}
+
+/// Label every cell of the state render bind group `side` draws with its
+/// species cluster (the species map channel).
+fn encode_species_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    side: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("species_pass"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(&pipelines.species_pipeline);
+    pass.set_bind_group(0, &pipelines.species_bind_groups[side], &[]);
+    pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+}
+
+/// Move the running means towards the step just encoded.
+fn encode_average_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    cur: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("average_pass"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(&pipelines.average_pipeline);
+    pass.set_bind_group(0, &pipelines.average_bind_groups[cur], &[]);
+    pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+}
+
+/// Perturbation: edit the current state (index `cur`) in place.
+fn encode_perturbation_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    cur: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("perturbation_pass"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(&pipelines.perturbation_pipeline);
+    pass.set_bind_group(0, &pipelines.perturbation_bind_groups[cur], &[]);
+    pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+}
+
+/// Barrier walls: clear the current state under them, in place.
+fn encode_barrier_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    pipelines: &Pipelines,
+    cur: usize,
+    dispatch_x: u32,
+    dispatch_y: u32,
+) {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("barrier_pass"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(&pipelines.perturbation_pipeline);
+    pass.set_bind_group(0, &pipelines.barrier_bind_groups[cur], &[]);
+    pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+}
@@ -0,0 +1,490 @@
+// ============================================================================
+// arena.rs — EvoLenia v2 Research Lab
+// Head-to-head competition arena: two creatures from the library (portraits
+// in creatures/ or pattern files in patterns/) are seeded into a fresh,
+// neutral world — empty, full resources, A in the left half and B at the
+// mirrored positions in the right half — and run with a fixed seed on a
+// background world sharing the GPU. Each living cell is credited to the
+// contestant whose genome is nearer, so mutated descendants stay in their
+// lineage. A round-robin tournament plays every pair of the library.
+// ============================================================================
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::app::step_world;
+use crate::config::SimulationParams;
+use crate::creature::{CreatureInfo, CREATURES_DIR};
+use crate::metrics::genome_distance;
+use crate::pattern::{load_pattern, Pattern, PATTERN_DIR};
+use crate::pipeline::create_pipelines;
+use crate::world::{BufferSnapshot, WorldState};
+
+/// Cells lighter than this are not alive (not credited to a lineage).
+const LIVING_MASS: f32 = 0.01;
+/// Final share of A within 0.5 ± this margin is a draw.
+pub const DRAW_MARGIN: f32 = 0.1;
+/// Empty arena cells: the initial world's energy, genome and resources.
+const ARENA_ENERGY: f32 = 0.5;
+const ARENA_GENOME: [f32; 4] = [10.0, 0.15, 0.017, 0.0];
+const ARENA_MUTATION_RATE: f32 = 0.003;
+const ARENA_RESOURCE: f32 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArenaSettings {
+    pub seeds: u32,  // placements per contestant
+    pub radius: u32, // of a genome-only seed (a portrait has no mass pattern)
+    pub steps: u32,
+    pub sample_interval: u32,
+    pub seed: u64, // placements and demographic noise
+}
+
+impl Default for ArenaSettings {
+    fn default() -> Self {
+        Self { seeds: 3, radius: 10, steps: 5000, sample_interval: 100, seed: 1 }
+    }
+}
+
+impl ArenaSettings {
+    /// Seed centers of A in the left half of a `size` world; B gets
+    /// `mirror` of each.
+    pub fn placements(&self, [width, height]: [u32; 2]) -> Vec<[u32; 2]> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        (0..self.seeds.max(1))
+            .map(|_| [rng.gen_range(width / 8..width * 3 / 8), rng.gen_range(0..height)])
+            .collect()
+    }
+
+    /// The arena run with the current parameters, minus everything that is
+    /// not left-right symmetric, and with the arena's noise seed.
+    pub fn neutral_params(&self, params: &SimulationParams) -> SimulationParams {
+        let mut params = params.clone();
+        params.reserves.clear();
+        params.flow.enabled = false;
+        params.particles.enabled = false;
+        params.perturbation_active = false;
+        params.perturbation_continuous = false;
+        params.demographic_noise.seed = self.seed as u32;
+        params
+    }
+}
+
+/// Mirror image of `center` across the vertical midline of a `size` arena.
+pub fn mirror(center: [u32; 2], size: [u32; 2]) -> [u32; 2] {
+    [size[0] - 1 - center[0], center[1]]
+}
+
+// ======================== Contestants ========================
+
+/// One side of a match: a genome, with the creature's mass pattern when the
+/// library file has one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contestant {
+    pub name: String,
+    pub genome: [f32; 4], // r, mu, sigma, aggressivity
+    pub mutation_rate: f32,
+    pub pattern: Option<Pattern>,
+}
+
+impl Contestant {
+    /// Seed the contestant around `center`: its pattern, or a Gaussian blob
+    /// of `radius` as in the initial world's clusters.
+    pub fn seed(&self, snap: &mut BufferSnapshot, center: [u32; 2], radius: u32) {
+        if let Some(pattern) = &self.pattern {
+            pattern.stamp(snap, center);
+            return;
+        }
+        let r = radius.max(1) as i64;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let d2 = (dx * dx + dy * dy) as f32;
+                if d2 > (r * r) as f32 {
+                    continue;
+                }
+                let x = (center[0] as i64 + dx).rem_euclid(snap.size[0] as i64) as usize;
+                let y = (center[1] as i64 + dy).rem_euclid(snap.size[1] as i64) as usize;
+                let i = y * snap.size[0] as usize + x;
+                snap.mass[i] = (-d2 / (0.5 * (r * r) as f32)).exp();
+                snap.energy[i] = ARENA_ENERGY;
+                snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&self.genome);
+                snap.genome_b[i] = self.mutation_rate;
+            }
+        }
+    }
+}
+
+/// Read a library file: a portrait's JSON (genome only) or a Lenia pattern.
+pub fn load_contestant(path: &Path) -> Result<Contestant, String> {
+    let name = path.file_stem().map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    if let Ok(info) = serde_json::from_str::<CreatureInfo>(&json) {
+        return Ok(Contestant { name, genome: info.genome, mutation_rate: info.mutation_rate, pattern: None });
+    }
+    let (pattern, _notes) = load_pattern(path)?;
+    Ok(Contestant { name, genome: pattern.genome, mutation_rate: pattern.mutation_rate, pattern: Some(pattern) })
+}
+
+/// The creature library: portraits and patterns, sorted by path.
+pub fn list_library() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = [CREATURES_DIR, PATTERN_DIR]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// An empty `size` arena with A seeded at `placements` and B at their
+/// mirror.
+pub fn neutral_arena(a: &Contestant, b: &Contestant, settings: &ArenaSettings, size: [u32; 2]) -> BufferSnapshot {
+    let n = (size[0] * size[1]) as usize;
+    let mut snap = BufferSnapshot {
+        size,
+        mass: vec![0.0; n],
+        energy: vec![ARENA_ENERGY; n],
+        genome_a: ARENA_GENOME.repeat(n),
+        genome_b: vec![ARENA_MUTATION_RATE; n],
+        resource: vec![ARENA_RESOURCE; n],
+        flow: Vec::new(),
+        channels: BTreeMap::new(),
+    };
+    for center in settings.placements(size) {
+        a.seed(&mut snap, center, settings.radius);
+        b.seed(&mut snap, mirror(center, size), settings.radius);
+    }
+    snap
+}
+
+// ======================== Matches ========================
+
+/// Living mass of each lineage at one step of a match.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArenaSample {
+    pub step: u32,
+    pub mass_a: f32,
+    pub mass_b: f32,
+}
+
+impl ArenaSample {
+    /// Credit each living cell to the contestant with the nearer genome.
+    pub fn measure(snap: &BufferSnapshot, a: [f32; 4], b: [f32; 4], step: u32) -> Self {
+        let tuple = |g: &[f32]| (g[0], g[1], g[2], g[3]);
+        let (a, b) = (tuple(&a), tuple(&b));
+        let mut sample = ArenaSample { step, ..Default::default() };
+        for (i, &m) in snap.mass.iter().enumerate() {
+            if m < LIVING_MASS {
+                continue;
+            }
+            let genome = tuple(&snap.genome_a[i * 4..i * 4 + 4]);
+            if genome_distance(genome, a) <= genome_distance(genome, b) {
+                sample.mass_a += m;
+            } else {
+                sample.mass_b += m;
+            }
+        }
+        sample
+    }
+
+    /// A's share of the living mass (0.5 when nothing lives).
+    pub fn share_a(&self) -> f32 {
+        let total = self.mass_a + self.mass_b;
+        if total > 0.0 {
+            self.mass_a / total
+        } else {
+            0.5
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchOutcome {
+    WinA,
+    WinB,
+    Draw,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResult {
+    pub a: String,
+    pub b: String,
+    pub samples: Vec<ArenaSample>, // the first at step 0, as seeded
+    pub cancelled: bool,
+}
+
+impl MatchResult {
+    /// Who holds the larger share of the living mass at the end.
+    pub fn outcome(&self) -> Option<MatchOutcome> {
+        let share = self.samples.last()?.share_a();
+        Some(if share > 0.5 + DRAW_MARGIN {
+            MatchOutcome::WinA
+        } else if share < 0.5 - DRAW_MARGIN {
+            MatchOutcome::WinB
+        } else {
+            MatchOutcome::Draw
+        })
+    }
+
+    /// Name of the dominant lineage, None for a draw.
+    pub fn winner(&self) -> Option<&str> {
+        match self.outcome()? {
+            MatchOutcome::WinA => Some(&self.a),
+            MatchOutcome::WinB => Some(&self.b),
+            MatchOutcome::Draw => None,
+        }
+    }
+}
+
+/// Every pair of `n` contestants once: (0, 1), (0, 2), … (n-2, n-1).
+pub fn round_robin(n: usize) -> Vec<(usize, usize)> {
+    (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect()
+}
+
+/// One row of the tournament table.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Standing {
+    /// 1 per win, ½ per draw.
+    pub fn points(&self) -> f32 {
+        self.wins as f32 + 0.5 * self.draws as f32
+    }
+}
+
+/// Tournament table of `results`, best first (ties by name).
+pub fn standings(results: &[MatchResult]) -> Vec<Standing> {
+    let mut table: Vec<Standing> = Vec::new();
+    let mut row = |name: &str| -> usize {
+        match table.iter().position(|s| s.name == name) {
+            Some(k) => k,
+            None => {
+                table.push(Standing { name: name.to_string(), ..Default::default() });
+                table.len() - 1
+            }
+        }
+    };
+    let mut tally = Vec::new();
+    for result in results {
+        let (a, b) = (row(&result.a), row(&result.b));
+        tally.push((a, b, result.outcome()));
+    }
+    for (a, b, outcome) in tally {
+        match outcome {
+            Some(MatchOutcome::WinA) => {
+                table[a].wins += 1;
+                table[b].losses += 1;
+            }
+            Some(MatchOutcome::WinB) => {
+                table[b].wins += 1;
+                table[a].losses += 1;
+            }
+            Some(MatchOutcome::Draw) => {
+                table[a].draws += 1;
+                table[b].draws += 1;
+            }
+            None => {}
+        }
+    }
+    table.sort_by(|x, y| y.points().total_cmp(&x.points()).then_with(|| x.name.cmp(&y.name)));
+    table
+}
+
+/// One line per sample of every match.
+pub fn write_csv(results: &[MatchResult], path: &Path) -> Result<(), String> {
+    let mut file = fs::File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    writeln!(file, "match,a,b,step,mass_a,mass_b,share_a").map_err(|e| format!("Write error: {}", e))?;
+    for (k, result) in results.iter().enumerate() {
+        for s in &result.samples {
+            writeln!(file, "{},{},{},{},{:.4},{:.4},{:.6}", k + 1, result.a, result.b, s.step, s.mass_a, s.mass_b, s.share_a())
+                .map_err(|e| format!("Write error: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// ======================== Background Runner ========================
+
+enum ArenaUpdate {
+    Progress(f32),
+    Match(MatchResult),
+    Finished(Result<(), String>),
+}
+
+struct ArenaWorker {
+    updates: Receiver<ArenaUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Matches ready to run.
+pub struct ArenaJob {
+    pub contestants: Vec<Contestant>,
+    pub pairs: Vec<(usize, usize)>,
+    pub params: SimulationParams, // already neutral (ArenaSettings::neutral_params)
+    pub settings: ArenaSettings,
+    pub csv_path: PathBuf,
+    pub size: [u32; 2], // the visible world's when the tournament starts
+}
+
+/// Run one match on a fresh background world.
+fn run_match(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    job: &ArenaJob,
+    (a, b): (&Contestant, &Contestant),
+    cancel: &AtomicBool,
+    progress: &dyn Fn(f32),
+) -> Result<MatchResult, String> {
+    let settings = job.settings;
+    for pattern in [&a.pattern, &b.pattern].into_iter().flatten() {
+        pattern.fits(job.size)?;
+    }
+    let snapshot = neutral_arena(a, b, &settings, job.size);
+    let mut world = WorldState::new_with_grid(device, queue, Some(settings.seed), job.params.grid, job.size);
+    if !world.apply_snapshot(queue, &snapshot) {
+        return Err(String::from("the arena does not fit a new world"));
+    }
+    let pipelines = create_pipelines(device, &world, wgpu::TextureFormat::Rgba8Unorm);
+    let interval = settings.sample_interval.max(1);
+    let mut result = MatchResult {
+        a: a.name.clone(),
+        b: b.name.clone(),
+        samples: vec![ArenaSample::measure(&snapshot, a.genome, b.genome, 0)],
+        cancelled: false,
+    };
+    for step in 1..=settings.steps {
+        if cancel.load(Ordering::SeqCst) {
+            result.cancelled = true;
+            break;
+        }
+        step_world(device, queue, &mut world, &pipelines, &job.params);
+        if step.is_multiple_of(interval) || step == settings.steps {
+            let snap = world
+                .readback_snapshot(device, queue)
+                .ok_or_else(|| format!("GPU readback failed at step {}", step))?;
+            result.samples.push(ArenaSample::measure(&snap, a.genome, b.genome, step));
+            progress(step as f32 / settings.steps as f32);
+        }
+    }
+    Ok(result)
+}
+
+/// Play the job's matches in order, then write the CSV.
+fn run_job(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    job: &ArenaJob,
+    cancel: &AtomicBool,
+    updates: &Sender<ArenaUpdate>,
+) -> Result<(), String> {
+    let total = job.pairs.len().max(1) as f32;
+    let mut results = Vec::new();
+    for (k, &(i, j)) in job.pairs.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        let progress = |fraction: f32| {
+            let _ = updates.send(ArenaUpdate::Progress((k as f32 + fraction) / total));
+        };
+        let result = run_match(device, queue, job, (&job.contestants[i], &job.contestants[j]), cancel, &progress)?;
+        let _ = updates.send(ArenaUpdate::Match(result.clone()));
+        results.push(result);
+    }
+    if let Some(dir) = job.csv_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    write_csv(&results, &job.csv_path)
+}
+
+/// What the arena's buttons ask for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaRequest {
+    Match,      // the two chosen contestants
+    Tournament, // round robin over the whole library
+}
+
+/// The arena of the Lab: its contestants, the matches in flight and their
+/// results (shown in the results dialog).
+#[derive(Default)]
+pub struct Arena {
+    pub settings: ArenaSettings,
+    pub library: Option<Vec<PathBuf>>, // None: rescan (list_library)
+    pub a: Option<PathBuf>,
+    pub b: Option<PathBuf>,
+    pub requested: Option<ArenaRequest>,
+    pub progress: Option<f32>,
+    pub results: Vec<MatchResult>, // of the last run, in play order
+    pub selected: usize,           // match plotted in the results dialog
+    pub csv_path: Option<PathBuf>,
+    pub show_results: bool,
+    worker: Option<ArenaWorker>,
+}
+
+impl Arena {
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Play `job` on background worlds sharing the GPU.
+    pub fn start(&mut self, device: wgpu::Device, queue: wgpu::Queue, job: ArenaJob) {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        self.csv_path = Some(job.csv_path.clone());
+        std::thread::spawn(move || {
+            let result = run_job(&device, &queue, &job, &flag, &tx);
+            let _ = tx.send(ArenaUpdate::Finished(result));
+        });
+        self.results.clear();
+        self.selected = 0;
+        self.progress = Some(0.0);
+        self.worker = Some(ArenaWorker { updates: rx, cancel });
+    }
+
+    /// Stop after the current match's samples so far.
+    pub fn cancel(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Apply the worker's updates. Returns the outcome of a run that just
+    /// ended: the results (also kept in `results`) or an error.
+    pub fn poll(&mut self) -> Option<Result<&[MatchResult], String>> {
+        let updates: Vec<ArenaUpdate> = self.worker.as_ref()?.updates.try_iter().collect();
+        let mut finished = None;
+        for update in updates {
+            match update {
+                ArenaUpdate::Progress(fraction) => self.progress = Some(fraction),
+                ArenaUpdate::Match(result) => {
+                    self.selected = self.results.len();
+                    self.results.push(result);
+                }
+                ArenaUpdate::Finished(result) => finished = Some(result),
+            }
+        }
+        let result = finished?;
+        self.worker = None;
+        self.progress = None;
+        Some(match result {
+            Ok(()) => {
+                self.show_results = true;
+                Ok(&self.results)
+            }
+            Err(e) => Err(e),
+        })
+    }
+}
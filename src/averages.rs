@@ -0,0 +1,151 @@
+// ============================================================================
+// averages.rs — EvoLenia v2 Research Lab
+// Time-averaged fields: the averaging pass (compute_average.wgsl) keeps
+// running means of mass, resource and flow speed on the GPU, so figures of
+// long-term spatial structure are free of the step-to-step flicker of the
+// live fields. An export reads them back once and writes each as a NumPy
+// array (.npy, float32, rows × columns) and as a color-mapped PNG, with a
+// JSON sidecar giving the window and the PNG value ranges.
+// ============================================================================
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use image::ImageEncoder;
+
+use crate::display::auto_range;
+
+/// Color ramp of the PNGs (dark blue → teal → yellow), evenly spaced stops.
+const RAMP: [[f32; 3]; 5] = [
+    [0.05, 0.03, 0.20],
+    [0.23, 0.32, 0.55],
+    [0.13, 0.57, 0.55],
+    [0.37, 0.79, 0.38],
+    [0.99, 0.91, 0.15],
+];
+
+/// The time-averaged fields, row-major.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldAverages {
+    pub size: [u32; 2], // width × height of the world averaged
+    pub mass: Vec<f32>,
+    pub resource: Vec<f32>,
+    pub speed: Vec<f32>, // |velocity|, cells per step
+    pub samples: u32,    // steps averaged
+}
+
+impl FieldAverages {
+    /// (name, values) of each field, in export order.
+    pub fn fields(&self) -> [(&'static str, &[f32]); 3] {
+        [("mass", &self.mass), ("resource", &self.resource), ("speed", &self.speed)]
+    }
+}
+
+/// Export folder of the averages at `frame`.
+pub fn export_dir(run_dir: &Path, frame: u32) -> PathBuf {
+    run_dir.join(format!("averages_{frame:06}"))
+}
+
+// ======================== NumPy ========================
+
+/// The header of a version 1.0 .npy file of little-endian float32 values
+/// with the given C-order `shape`, padded to a multiple of 64 bytes.
+pub fn npy_header(shape: &[usize]) -> Vec<u8> {
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    let shape = match dims.len() {
+        1 => format!("({},)", dims[0]),
+        _ => format!("({})", dims.join(", ")),
+    };
+    let mut dict = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}", shape);
+    // magic (6) + version (2) + length (2) + dict + newline
+    let padding = (64 - (10 + dict.len() + 1) % 64) % 64;
+    dict.extend(std::iter::repeat_n(' ', padding));
+    dict.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend((dict.len() as u16).to_le_bytes());
+    header.extend(dict.as_bytes());
+    header
+}
+
+/// Write `values` as a float32 .npy array of `shape`.
+pub fn write_npy(path: &Path, values: &[f32], shape: &[usize]) -> Result<(), String> {
+    if shape.iter().product::<usize>() != values.len() {
+        return Err(format!("{} values do not fill shape {:?}", values.len(), shape));
+    }
+    let mut file = fs::File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    file.write_all(&npy_header(shape)).map_err(|e| format!("Write error: {}", e))?;
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    file.write_all(&bytes).map_err(|e| format!("Write error: {}", e))
+}
+
+// ======================== PNG ========================
+
+/// RAMP color of `t` in [0, 1].
+fn ramp(t: f32) -> [f32; 3] {
+    let x = t.clamp(0.0, 1.0) * (RAMP.len() - 1) as f32;
+    let k = (x as usize).min(RAMP.len() - 2);
+    let f = x - k as f32;
+    std::array::from_fn(|c| RAMP[k][c] + (RAMP[k + 1][c] - RAMP[k][c]) * f)
+}
+
+/// Display range of a field: the 1st to 99th percentile of its non-empty
+/// cells, as the live views' auto range.
+pub fn png_range(values: &[f32]) -> (f32, f32) {
+    auto_range(values, 1.0)
+}
+
+/// Color-mapped RGBA8 image of a world-sized field over `(lo, hi)`.
+pub fn field_rgba(values: &[f32], (lo, hi): (f32, f32)) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|&v| {
+            let [r, g, b] = ramp((v - lo) / (hi - lo));
+            [r, g, b, 1.0].map(|c| (c * 255.0).round() as u8)
+        })
+        .collect()
+}
+
+fn write_png(path: &Path, rgba: &[u8], [width, height]: [u32; 2]) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    image::codecs::png::PngEncoder::new(std::io::BufWriter::new(file))
+        .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| format!("Failed to encode {:?}: {}", path, e))
+}
+
+// ======================== Export ========================
+
+/// Write `<field>_mean.npy` and `<field>_mean.png` for each field, and
+/// `averages.json`, into `dir`. Returns the files written.
+pub fn export(dir: &Path, averages: &FieldAverages, frame: u32, window: u32, run_id: &str) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let shape = [averages.size[1] as usize, averages.size[0] as usize];
+    let mut files = Vec::new();
+    let mut ranges = serde_json::Map::new();
+    for (name, values) in averages.fields() {
+        let npy = dir.join(format!("{name}_mean.npy"));
+        write_npy(&npy, values, &shape)?;
+        files.push(npy);
+
+        let range = png_range(values);
+        let png = dir.join(format!("{name}_mean.png"));
+        write_png(&png, &field_rgba(values, range), averages.size)?;
+        files.push(png);
+        ranges.insert(name.to_string(), serde_json::json!([range.0, range.1]));
+    }
+
+    let meta = dir.join("averages.json");
+    let json = serde_json::json!({
+        "run_id": run_id,
+        "frame": frame,
+        "window": window,
+        "samples": averages.samples,
+        "shape": shape,
+        "png_ranges": ranges,
+    });
+    let text = serde_json::to_string_pretty(&json).map_err(|e| format!("Failed to serialize averages: {}", e))?;
+    fs::write(&meta, text).map_err(|e| format!("Failed to write {:?}: {}", meta, e))?;
+    files.push(meta);
+    Ok(files)
+}
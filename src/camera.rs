@@ -1,9 +1,11 @@
 // ============================================================================
 // camera.rs — EvoLenia v2
-// Camera state & GPU uniform for pan/zoom navigation.
+// Camera state & GPU uniform for pan/zoom navigation, and the orbit camera of
+// the 3D surface view.
 // ============================================================================
 
-use crate::world::{WORLD_WIDTH, WORLD_HEIGHT};
+use serde::{Deserialize, Serialize};
+
 
 /// GPU-side camera uniforms uploaded every frame.
 #[repr(C)]
@@ -13,9 +15,20 @@ pub struct CameraUniforms {
     pub zoom: f32,
     pub aspect_ratio: f32,      // window_width / window_height
     pub world_aspect: f32,       // world_width / world_height
+    pub grid_alpha: f32,         // pixel-grid overlay opacity (0 = hidden)
+    pub cell_px: f32,            // on-screen size of one world cell, in pixels
+    pub smooth_sampling: f32,    // 1 = bilinear field sampling, 0 = nearest cell
+    // 3D surface view (render.wgsl fs_surface), in the surface frame
+    pub eye: [f32; 3],
+    pub height_scale: f32,       // surface height at full display intensity
+    pub right: [f32; 3],
+    pub tan_half_fov: f32,       // vertical
+    pub up: [f32; 3],
+    pub _pad0: f32,
+    pub forward: [f32; 3],
     pub _pad1: f32,
+    pub letterbox: [f32; 3],     // color around the world
     pub _pad2: f32,
-    pub _pad3: f32,
 }
 
 impl Default for CameraUniforms {
@@ -24,18 +37,178 @@ impl Default for CameraUniforms {
             offset: [0.0, 0.0],
             zoom: 1.0,
             aspect_ratio: 1.0,
-            world_aspect: WORLD_WIDTH as f32 / WORLD_HEIGHT as f32,
+            world_aspect: 1.0,
+            grid_alpha: 0.0,
+            cell_px: 1.0,
+            smooth_sampling: 0.0,
+            eye: [0.0, 0.0, 1.0],
+            height_scale: 0.0,
+            right: [1.0, 0.0, 0.0],
+            tan_half_fov: 1.0,
+            up: [0.0, 1.0, 0.0],
+            _pad0: 0.0,
+            forward: [0.0, 0.0, -1.0],
             _pad1: 0.0,
+            letterbox: DEFAULT_LETTERBOX,
             _pad2: 0.0,
-            _pad3: 0.0,
         }
     }
 }
 
-/// CPU-side camera state used to track pan/zoom between frames.
+/// On-screen cell size (px) at which the pixel grid starts fading in.
+const GRID_FADE_START_PX: f32 = 6.0;
+/// On-screen cell size (px) at which the pixel grid is fully visible.
+const GRID_FADE_FULL_PX: f32 = 12.0;
+
+/// Range of the render resolution factor: 0.5 renders at half the window
+/// resolution, 2 supersamples (2×2 samples per screen pixel).
+pub const RENDER_SCALE_RANGE: (f32, f32) = (0.25, 2.0);
+/// Color around the world when it does not cover the window.
+pub const DEFAULT_LETTERBOX: [f32; 3] = [0.08, 0.08, 0.10];
+
+/// How the world is fitted to the window at zoom 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorldFit {
+    #[default]
+    Fit,     // whole world visible, letterboxed on the window's longer side
+    Fill,    // window covered, world cropped on its longer side
+    Integer, // whole world at a whole number of screen px per cell (or cells per px)
+}
+
+impl WorldFit {
+    pub fn all() -> &'static [WorldFit] {
+        &[WorldFit::Fit, WorldFit::Fill, WorldFit::Integer]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            WorldFit::Fit => "fit.fit",
+            WorldFit::Fill => "fit.fill",
+            WorldFit::Integer => "fit.integer",
+        }
+    }
+}
+
+/// Vertical field of view of the 3D surface view, in radians.
+pub const ORBIT_FOV_Y: f32 = std::f32::consts::FRAC_PI_4;
+const ORBIT_PITCH_RANGE: (f32, f32) = (0.05, 1.5);
+const ORBIT_DISTANCE_RANGE: (f32, f32) = (0.2, 5.0);
+/// Orbit angle per dragged pixel, in radians.
+const ORBIT_DRAG_SPEED: f32 = 0.005;
+/// Presentation turntable speed, in radians per second.
+const AUTO_ROTATE_SPEED: f32 = 0.2;
+
+/// Width over height of a `world`-sized world.
+fn world_aspect([width, height]: [u32; 2]) -> f32 {
+    width as f32 / height as f32
+}
+
+/// Orbit camera of the 3D surface view. The surface frame has x across the
+/// world width (scaled by the world aspect), z down the world height, both
+/// centered on the world, and y up; the ground plane is y = 0.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrbitCamera {
+    pub target: [f32; 2], // world UV on the ground the camera looks at
+    pub yaw: f32,         // around the vertical axis, radians
+    pub pitch: f32,       // above the horizon, radians
+    pub distance: f32,    // from the target, in world heights
+    pub height_scale: f32, // surface height at full display intensity
+    pub auto_rotate: bool, // slow turntable for presentations
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self { target: [0.5, 0.5], yaw: 0.6, pitch: 0.7, distance: 1.6, height_scale: 0.15, auto_rotate: false }
+    }
+}
+
+impl OrbitCamera {
+    /// The target in the surface frame of a `world`-sized world.
+    pub fn target_point(&self, world: [u32; 2]) -> [f32; 3] {
+        [(self.target[0] - 0.5) * world_aspect(world), 0.0, self.target[1] - 0.5]
+    }
+
+    pub fn eye(&self, world: [u32; 2]) -> [f32; 3] {
+        let t = self.target_point(world);
+        let (sy, cy) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        [t[0] + self.distance * cp * sy, t[1] + self.distance * sp, t[2] + self.distance * cp * cy]
+    }
+
+    /// Camera axes (right, up, forward), orthonormal.
+    pub fn basis(&self) -> [[f32; 3]; 3] {
+        let (sy, cy) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        let forward = [-cp * sy, -sp, -cp * cy];
+        let right = [cy, 0.0, -sy]; // forward × world up, normalized
+        let up = [
+            right[1] * forward[2] - right[2] * forward[1],
+            right[2] * forward[0] - right[0] * forward[2],
+            right[0] * forward[1] - right[1] * forward[0],
+        ];
+        [right, up, forward]
+    }
+
+    /// Turn and tilt by a mouse drag, in pixels.
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * ORBIT_DRAG_SPEED;
+        self.pitch = (self.pitch + dy * ORBIT_DRAG_SPEED).clamp(ORBIT_PITCH_RANGE.0, ORBIT_PITCH_RANGE.1);
+    }
+
+    /// Move closer on scroll up.
+    pub fn apply_scroll(&mut self, scroll_y: f32) {
+        self.distance = (self.distance / (1.0 + scroll_y * 0.1)).clamp(ORBIT_DISTANCE_RANGE.0, ORBIT_DISTANCE_RANGE.1);
+    }
+
+    pub fn apply_zoom_keys(&mut self, zoom_in: bool, zoom_out: bool) {
+        if zoom_in {
+            self.distance = (self.distance * 0.98).max(ORBIT_DISTANCE_RANGE.0);
+        }
+        if zoom_out {
+            self.distance = (self.distance * 1.02).min(ORBIT_DISTANCE_RANGE.1);
+        }
+    }
+
+    /// Move the target along the ground of a `world`-sized world, relative
+    /// to the view direction.
+    pub fn apply_pan(&mut self, up: bool, down: bool, left: bool, right: bool, world: [u32; 2]) {
+        let speed = 0.005 * self.distance;
+        let world_aspect = world_aspect(world);
+        let (sy, cy) = self.yaw.sin_cos();
+        let forward = (up as i32 - down as i32) as f32;
+        let side = (right as i32 - left as i32) as f32;
+        // Ground forward is (-sin yaw, -cos yaw), ground right is (cos yaw, -sin yaw)
+        let dx = (-sy * forward + cy * side) * speed;
+        let dz = (-cy * forward - sy * side) * speed;
+        self.target[0] = (self.target[0] + dx / world_aspect).clamp(0.0, 1.0);
+        self.target[1] = (self.target[1] + dz).clamp(0.0, 1.0);
+    }
+
+    /// Advance the turntable by `dt` seconds.
+    pub fn advance(&mut self, dt: f32) {
+        if self.auto_rotate {
+            self.yaw = (self.yaw + AUTO_ROTATE_SPEED * dt) % std::f32::consts::TAU;
+        }
+    }
+}
+
+/// CPU-side camera state used to track pan/zoom between frames
+/// (persisted in the session settings file).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CameraState {
     pub offset: [f32; 2],
     pub zoom: f32,
+    pub pixel_grid: bool,  // draw cell boundaries when zoomed in far enough
+    pub snap_zoom: bool,   // scroll steps through integer screen-px-per-cell levels
+    pub smooth_sampling: bool, // bilinear field sampling (needs FLOAT32_FILTERABLE)
+    pub surface_view: bool,    // 3D height-mapped surface instead of the flat view
+    pub orbit: OrbitCamera,
+    pub render_scale: f32,     // offscreen scene resolution / window resolution
+    pub fit: WorldFit,
+    pub letterbox: [f32; 3],
 }
 
 impl Default for CameraState {
@@ -43,6 +216,14 @@ impl Default for CameraState {
         Self {
             offset: [0.0, 0.0],
             zoom: 1.0,
+            pixel_grid: true,
+            snap_zoom: false,
+            smooth_sampling: false,
+            surface_view: false,
+            orbit: OrbitCamera::default(),
+            render_scale: 1.0,
+            fit: WorldFit::Fit,
+            letterbox: DEFAULT_LETTERBOX,
         }
     }
 }
@@ -82,16 +263,143 @@ impl CameraState {
         self.zoom = self.zoom.clamp(0.1, 50.0);
     }
 
-    /// Build the GPU uniform from current state.
-    pub fn uniforms(&self, win_w: u32, win_h: u32) -> CameraUniforms {
+    /// Screen pixels covered by one cell of a `world`-sized world when it
+    /// is fitted to the window's limiting dimension (see the aspect
+    /// correction in render.wgsl).
+    fn contain_cell_px(win_w: u32, win_h: u32, world: [u32; 2]) -> f32 {
+        let ratio = (win_w as f32 / win_h as f32) / world_aspect(world);
+        if ratio > 1.0 {
+            win_h as f32 / world[1] as f32
+        } else {
+            win_w as f32 / world[0] as f32
+        }
+    }
+
+    /// Magnification of the fit mode over the contained fit.
+    pub fn fit_scale(&self, win_w: u32, win_h: u32, world: [u32; 2]) -> f32 {
+        match self.fit {
+            WorldFit::Fit => 1.0,
+            WorldFit::Fill => {
+                let ratio = (win_w as f32 / win_h as f32) / world_aspect(world);
+                ratio.max(1.0 / ratio)
+            }
+            WorldFit::Integer => {
+                let px = Self::contain_cell_px(win_w, win_h, world);
+                let whole = if px >= 1.0 { px.floor() } else { 1.0 / (1.0 / px).ceil() };
+                whole / px
+            }
+        }
+    }
+
+    /// Screen pixels covered by one world cell at zoom 1.
+    fn base_cell_px(&self, win_w: u32, win_h: u32, world: [u32; 2]) -> f32 {
+        Self::contain_cell_px(win_w, win_h, world) * self.fit_scale(win_w, win_h, world)
+    }
+
+    /// Zoom over the contained fit, as the shader applies it.
+    fn view_zoom(&self, win_w: u32, win_h: u32, world: [u32; 2]) -> f32 {
+        self.zoom * self.fit_scale(win_w, win_h, world)
+    }
+
+    /// Screen pixels covered by one world cell at the current zoom.
+    pub fn cell_px(&self, win_w: u32, win_h: u32, world: [u32; 2]) -> f32 {
+        self.zoom * self.base_cell_px(win_w, win_h, world)
+    }
+
+    /// Size of the offscreen scene target for a window: the window scaled by
+    /// `render_scale`, shrunk to at most `max_dim` texels per side.
+    pub fn render_size(&self, win_w: u32, win_h: u32, max_dim: u32) -> [u32; 2] {
+        let longest = win_w.max(win_h).max(1) as f32;
+        let scale = self
+            .render_scale
+            .clamp(RENDER_SCALE_RANGE.0, RENDER_SCALE_RANGE.1)
+            .min(max_dim as f32 / longest);
+        [win_w, win_h].map(|d| ((d as f32 * scale).round() as u32).clamp(1, max_dim))
+    }
+
+    /// Snap zoom so each world cell covers a whole number of screen pixels
+    /// (no-op below 1 px per cell, where cells cannot be resolved anyway).
+    pub fn snap_to_integer_zoom(&mut self, win_w: u32, win_h: u32, world: [u32; 2]) {
+        let px = self.cell_px(win_w, win_h, world);
+        if px >= 1.0 {
+            self.zoom = (px.round() / self.base_cell_px(win_w, win_h, world)).clamp(0.1, 50.0);
+        }
+    }
+
+    /// Step to the next/previous integer px-per-cell level (scroll with snap on).
+    pub fn step_integer_zoom(&mut self, scroll_y: f32, win_w: u32, win_h: u32, world: [u32; 2]) {
+        let base = self.base_cell_px(win_w, win_h, world);
+        let px = self.cell_px(win_w, win_h, world).round().max(1.0);
+        let target = if scroll_y > 0.0 {
+            px + 1.0
+        } else if scroll_y < 0.0 {
+            (px - 1.0).max(1.0)
+        } else {
+            px
+        };
+        self.zoom = (target / base).clamp(0.1, 50.0);
+    }
+
+    /// Convert a normalized screen position ([0,1]², origin top-left) to
+    /// world UV. Mirrors the aspect correction in render.wgsl `fs_main`.
+    pub fn screen_to_world(&self, screen_uv: [f32; 2], win_w: u32, win_h: u32, world: [u32; 2]) -> [f32; 2] {
+        let mut c = [screen_uv[0] - 0.5, screen_uv[1] - 0.5];
+        let ratio = (win_w as f32 / win_h as f32) / world_aspect(world);
+        if ratio > 1.0 {
+            c[0] *= ratio;
+        } else {
+            c[1] /= ratio;
+        }
+        let zoom = self.view_zoom(win_w, win_h, world);
+        [
+            c[0] / zoom + 0.5 + self.offset[0],
+            c[1] / zoom + 0.5 + self.offset[1],
+        ]
+    }
+
+    /// Inverse of [`screen_to_world`](Self::screen_to_world).
+    pub fn world_to_screen(&self, world_uv: [f32; 2], win_w: u32, win_h: u32, world: [u32; 2]) -> [f32; 2] {
+        let zoom = self.view_zoom(win_w, win_h, world);
+        let mut c = [
+            (world_uv[0] - 0.5 - self.offset[0]) * zoom,
+            (world_uv[1] - 0.5 - self.offset[1]) * zoom,
+        ];
+        let ratio = (win_w as f32 / win_h as f32) / world_aspect(world);
+        if ratio > 1.0 {
+            c[0] /= ratio;
+        } else {
+            c[1] *= ratio;
+        }
+        [c[0] + 0.5, c[1] + 0.5]
+    }
+
+    /// Build the GPU uniform from current state, for a `world`-sized world.
+    pub fn uniforms(&self, win_w: u32, win_h: u32, world: [u32; 2]) -> CameraUniforms {
+        let cell_px = self.cell_px(win_w, win_h, world);
+        let [right, up, forward] = self.orbit.basis();
+        let grid_alpha = if self.pixel_grid {
+            ((cell_px - GRID_FADE_START_PX) / (GRID_FADE_FULL_PX - GRID_FADE_START_PX)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
         CameraUniforms {
             offset: self.offset,
-            zoom: self.zoom,
+            zoom: self.view_zoom(win_w, win_h, world),
             aspect_ratio: win_w as f32 / win_h as f32,
-            world_aspect: WORLD_WIDTH as f32 / WORLD_HEIGHT as f32,
+            world_aspect: world_aspect(world),
+            grid_alpha,
+            cell_px,
+            smooth_sampling: if self.smooth_sampling { 1.0 } else { 0.0 },
+            eye: self.orbit.eye(world),
+            height_scale: self.orbit.height_scale,
+            right,
+            tan_half_fov: (ORBIT_FOV_Y * 0.5).tan(),
+            up,
+            _pad0: 0.0,
+            forward,
             _pad1: 0.0,
+            letterbox: self.letterbox,
             _pad2: 0.0,
-            _pad3: 0.0,
         }
     }
 }
@@ -0,0 +1,472 @@
+// ============================================================================
+// capture.rs — EvoLenia v2 Research Lab
+// Frame capture: screenshots and image sequences read back from a rendered
+// texture. Requests are queued with a caller tag (frame, mode, destination),
+// copies go to pooled staging buffers and are mapped asynchronously, so a
+// capture costs no GPU stall; finished frames are collected on a later
+// redraw. Pixel conversion follows the texture format: the channel order
+// (RGBA or BGRA), the texel size and whether the bytes are already sRGB
+// encoded. sRGB surfaces are copied as they are; linear surfaces (8-bit
+// unorm, 10-bit, 16-bit float) hold the shader output before encoding and
+// are converted to sRGB, so the PNG shows the colors the render intends.
+// Sinks write single PNGs, and runs of frames (FrameSink) to a numbered PNG
+// sequence, to a video through an ffmpeg process fed raw RGBA on stdin, or
+// to the frame stream (stream.rs). Window captures and the frames headless
+// runs draw on the CPU go through the same sinks.
+// ============================================================================
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use image::ImageEncoder;
+
+/// Texel layout of a capturable surface format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TexelLayout {
+    Rgba8,
+    Bgra8,
+    Rgb10a2, // packed little-endian u32: R in the low bits
+    Rgba16Float,
+}
+
+/// How to turn a surface texture readback into sRGB RGBA8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CaptureFormat {
+    pub layout: TexelLayout,
+    pub srgb: bool, // bytes already sRGB encoded
+}
+
+impl CaptureFormat {
+    /// None for formats screenshots do not support.
+    pub fn for_texture(format: wgpu::TextureFormat) -> Option<Self> {
+        use wgpu::TextureFormat as F;
+        let (layout, srgb) = match format {
+            F::Rgba8UnormSrgb => (TexelLayout::Rgba8, true),
+            F::Rgba8Unorm => (TexelLayout::Rgba8, false),
+            F::Bgra8UnormSrgb => (TexelLayout::Bgra8, true),
+            F::Bgra8Unorm => (TexelLayout::Bgra8, false),
+            F::Rgb10a2Unorm => (TexelLayout::Rgb10a2, false),
+            F::Rgba16Float => (TexelLayout::Rgba16Float, false),
+            _ => return None,
+        };
+        Some(Self { layout, srgb })
+    }
+
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self.layout {
+            TexelLayout::Rgba8 | TexelLayout::Bgra8 | TexelLayout::Rgb10a2 => 4,
+            TexelLayout::Rgba16Float => 8,
+        }
+    }
+
+    /// Row pitch of the readback buffer (COPY_BYTES_PER_ROW_ALIGNMENT).
+    pub fn padded_bytes_per_row(&self, width: u32) -> u32 {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        (width * self.bytes_per_pixel()).div_ceil(align) * align
+    }
+
+    /// Convert a padded readback of `width`×`height` texels to tightly
+    /// packed sRGB RGBA8.
+    pub fn srgb_rgba8(&self, data: &[u8], width: u32, height: u32, padded_bpr: u32) -> Vec<u8> {
+        let bpp = self.bytes_per_pixel() as usize;
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for row in 0..height as usize {
+            let start = row * padded_bpr as usize;
+            for texel in data[start..start + width as usize * bpp].chunks_exact(bpp) {
+                rgba.extend_from_slice(&self.texel(texel));
+            }
+        }
+        rgba
+    }
+
+    fn texel(&self, t: &[u8]) -> [u8; 4] {
+        match (self.layout, self.srgb) {
+            (TexelLayout::Rgba8, true) => [t[0], t[1], t[2], t[3]],
+            (TexelLayout::Bgra8, true) => [t[2], t[1], t[0], t[3]],
+            (TexelLayout::Rgba8, false) => encode([t[0], t[1], t[2]].map(unorm8), t[3]),
+            (TexelLayout::Bgra8, false) => encode([t[2], t[1], t[0]].map(unorm8), t[3]),
+            (TexelLayout::Rgb10a2, _) => {
+                let v = u32::from_le_bytes([t[0], t[1], t[2], t[3]]);
+                let channel = |shift: u32| ((v >> shift) & 0x3ff) as f32 / 1023.0;
+                let alpha = ((v >> 30) as f32 / 3.0 * 255.0).round() as u8;
+                encode([channel(0), channel(10), channel(20)], alpha)
+            }
+            (TexelLayout::Rgba16Float, _) => {
+                let channel = |i: usize| f16_to_f32(u16::from_le_bytes([t[2 * i], t[2 * i + 1]]));
+                let alpha = (channel(3).clamp(0.0, 1.0) * 255.0).round() as u8;
+                encode([channel(0), channel(1), channel(2)], alpha)
+            }
+        }
+    }
+}
+
+fn unorm8(v: u8) -> f32 {
+    v as f32 / 255.0
+}
+
+fn encode(linear: [f32; 3], alpha: u8) -> [u8; 4] {
+    let [r, g, b] = linear.map(|v| (linear_to_srgb(v) * 255.0).round() as u8);
+    [r, g, b, alpha]
+}
+
+/// sRGB transfer function (IEC 61966-2-1), input clamped to [0, 1].
+pub fn linear_to_srgb(v: f32) -> f32 {
+    let v = v.clamp(0.0, 1.0);
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// IEEE 754 half to f32 (subnormals included; NaN and infinities kept).
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+// ======================== Readback ========================
+
+/// Staging buffers kept for reuse between captures.
+pub const STAGING_POOL: usize = 2;
+
+const MAP_PENDING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+/// Free readback buffers, reused when the size matches (same window size).
+pub struct StagingPool<B> {
+    free: Vec<(B, u64)>,
+    capacity: usize,
+}
+
+impl<B> StagingPool<B> {
+    pub fn new(capacity: usize) -> Self {
+        Self { free: Vec::new(), capacity }
+    }
+
+    /// A free buffer of `size` bytes, or a new one from `create`.
+    pub fn acquire(&mut self, size: u64, create: impl FnOnce(u64) -> B) -> B {
+        match self.free.iter().position(|(_, s)| *s == size) {
+            Some(i) => self.free.swap_remove(i).0,
+            None => create(size),
+        }
+    }
+
+    /// Keep `buffer` for later, dropping the oldest beyond the capacity.
+    pub fn release(&mut self, buffer: B, size: u64) {
+        self.free.push((buffer, size));
+        if self.free.len() > self.capacity {
+            self.free.remove(0);
+        }
+    }
+}
+
+/// One finished capture, tightly packed sRGB RGBA8.
+pub struct CapturedFrame<T> {
+    pub tag: T,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Result<Vec<u8>, String>,
+}
+
+struct InFlight<T> {
+    tag: T,
+    buffer: wgpu::Buffer,
+    size: u64,
+    width: u32,
+    height: u32,
+    padded_bpr: u32,
+    format: CaptureFormat,
+    state: Arc<AtomicU8>,
+    mapping: bool, // map_async issued (after the copy was submitted)
+}
+
+/// Capture requests, their readbacks in flight and the staging pool. `T`
+/// is what the caller needs to handle the frame once it arrives.
+pub struct Capturer<T> {
+    requests: VecDeque<T>,
+    in_flight: Vec<InFlight<T>>,
+    pool: StagingPool<wgpu::Buffer>,
+}
+
+impl<T> Default for Capturer<T> {
+    fn default() -> Self {
+        Self { requests: VecDeque::new(), in_flight: Vec::new(), pool: StagingPool::new(STAGING_POOL) }
+    }
+}
+
+impl<T> Capturer<T> {
+    /// Capture the next rendered frame.
+    pub fn request(&mut self, tag: T) {
+        self.requests.push_back(tag);
+    }
+
+    /// Encode a copy of `texture` for every queued request. Requests are
+    /// handed back with an error when the texture format is not supported.
+    pub fn encode_copies(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Vec<(T, String)> {
+        let Some(format) = CaptureFormat::for_texture(texture.format()) else {
+            let error = format!("unsupported surface format {:?}", texture.format());
+            return self.requests.drain(..).map(|tag| (tag, error.clone())).collect();
+        };
+        let (width, height) = (texture.width(), texture.height());
+        let padded_bpr = format.padded_bytes_per_row(width);
+        let size = (padded_bpr * height) as u64;
+        while let Some(tag) = self.requests.pop_front() {
+            let buffer = self.pool.acquire(size, |size| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("capture_staging"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            });
+            encoder.copy_texture_to_buffer(
+                texture.as_image_copy(),
+                wgpu::TexelCopyBufferInfo {
+                    buffer: &buffer,
+                    layout: wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded_bpr),
+                        rows_per_image: Some(height),
+                    },
+                },
+                wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            );
+            self.in_flight.push(InFlight {
+                tag,
+                buffer,
+                size,
+                width,
+                height,
+                padded_bpr,
+                format,
+                state: Arc::new(AtomicU8::new(MAP_PENDING)),
+                mapping: false,
+            });
+        }
+        Vec::new()
+    }
+
+    /// Start mapping the copies encoded since the last call; call once the
+    /// encoder holding them is submitted.
+    pub fn submitted(&mut self) {
+        for capture in self.in_flight.iter_mut().filter(|c| !c.mapping) {
+            let state = capture.state.clone();
+            capture.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                state.store(if result.is_ok() { MAP_DONE } else { MAP_FAILED }, Ordering::Release);
+            });
+            capture.mapping = true;
+        }
+    }
+
+    /// Frames whose readback has finished. Never blocks unless `wait`.
+    pub fn collect(&mut self, device: &wgpu::Device, wait: bool) -> Vec<CapturedFrame<T>> {
+        if self.in_flight.iter().all(|c| !c.mapping) {
+            return Vec::new();
+        }
+        device.poll(if wait { wgpu::Maintain::Wait } else { wgpu::Maintain::Poll });
+        let mut done = Vec::new();
+        let mut i = 0;
+        while i < self.in_flight.len() {
+            let capture = &self.in_flight[i];
+            if !capture.mapping || capture.state.load(Ordering::Acquire) == MAP_PENDING {
+                i += 1;
+                continue;
+            }
+            let capture = self.in_flight.remove(i);
+            let rgba = if capture.state.load(Ordering::Acquire) == MAP_DONE {
+                let data = capture.buffer.slice(..).get_mapped_range();
+                let rgba = capture.format.srgb_rgba8(&data, capture.width, capture.height, capture.padded_bpr);
+                drop(data);
+                capture.buffer.unmap();
+                Ok(rgba)
+            } else {
+                Err(String::from("GPU readback failed"))
+            };
+            self.pool.release(capture.buffer, capture.size);
+            done.push(CapturedFrame { tag: capture.tag, width: capture.width, height: capture.height, rgba });
+        }
+        done
+    }
+}
+
+// ======================== Sinks ========================
+
+/// PNG-encode an RGBA8 frame in memory.
+pub fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// Write an RGBA8 frame as a PNG file.
+pub fn write_png(path: &Path, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+    let png = encode_png(rgba, width, height)?;
+    std::fs::write(path, png).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Image `index` of a numbered sequence in `dir`, numbered from 0 so video
+/// encoders take it as is (`ffmpeg -i frame_%05d.png`).
+pub fn sequence_frame_path(dir: &Path, index: u32) -> PathBuf {
+    dir.join(format!("frame_{:05}.png", index))
+}
+
+/// Destination of a run of frames, each tightly packed sRGB RGBA8 and
+/// tagged with the simulation frame it shows.
+pub trait FrameSink {
+    fn write_frame(&mut self, frame: u32, rgba: &[u8], width: u32, height: u32) -> Result<(), String>;
+
+    /// Flush and close after the last frame.
+    fn finish(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Numbered PNG images in a directory (`sequence_frame_path`).
+pub struct PngSequence {
+    dir: PathBuf,
+    written: u32,
+}
+
+impl PngSequence {
+    pub fn create(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        Ok(Self { dir: dir.to_path_buf(), written: 0 })
+    }
+}
+
+impl FrameSink for PngSequence {
+    fn write_frame(&mut self, _frame: u32, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+        write_png(&sequence_frame_path(&self.dir, self.written), rgba, width, height)?;
+        self.written += 1;
+        Ok(())
+    }
+}
+
+/// Encoder program of video sinks, looked up on the PATH.
+pub const FFMPEG: &str = "ffmpeg";
+/// File extensions written as videos; other paths are PNG sequence directories.
+pub const VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "mkv", "mov", "webm"];
+
+pub fn is_video_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// ffmpeg arguments encoding raw RGBA frames from stdin to `path`, with the
+/// container's default codec. Odd sizes are padded to even for yuv420p.
+pub fn ffmpeg_args(path: &Path, width: u32, height: u32, fps: f32) -> Vec<String> {
+    let size = format!("{width}x{height}");
+    let rate = format!("{fps}");
+    let output = path.display().to_string();
+    [
+        "-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba", "-s", &size, "-r", &rate, "-i", "-", "-vf",
+        "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p", &output,
+    ]
+    .iter()
+    .map(|a| a.to_string())
+    .collect()
+}
+
+/// A video file written by an ffmpeg process, started at the first frame
+/// (which fixes the size). Dropping the sink finishes the file.
+pub struct VideoSink {
+    path: PathBuf,
+    fps: f32,
+    encoder: Option<(Child, ChildStdin)>,
+    size: Option<(u32, u32)>,
+}
+
+impl VideoSink {
+    pub fn new(path: &Path, fps: f32) -> Self {
+        Self { path: path.to_path_buf(), fps: fps.max(1.0), encoder: None, size: None }
+    }
+
+    fn start(&self, width: u32, height: u32) -> Result<(Child, ChildStdin), String> {
+        let mut child = Command::new(FFMPEG)
+            .args(ffmpeg_args(&self.path, width, height, self.fps))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", FFMPEG, e))?;
+        let stdin = child.stdin.take().ok_or_else(|| format!("{} has no stdin", FFMPEG))?;
+        Ok((child, stdin))
+    }
+}
+
+impl FrameSink for VideoSink {
+    fn write_frame(&mut self, _frame: u32, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+        match self.size {
+            Some(size) if size != (width, height) => {
+                return Err(format!("frame size changed from {}x{} to {}x{}", size.0, size.1, width, height));
+            }
+            Some(_) => {}
+            None => {
+                self.encoder = Some(self.start(width, height)?);
+                self.size = Some((width, height));
+            }
+        }
+        let Some((_, stdin)) = self.encoder.as_mut() else {
+            return Err(format!("{} already finished", FFMPEG));
+        };
+        stdin.write_all(rgba).map_err(|e| format!("{} stopped: {}", FFMPEG, e))
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        let Some((mut child, stdin)) = self.encoder.take() else {
+            return Ok(());
+        };
+        drop(stdin); // end of input: ffmpeg writes the trailer and exits
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("{} failed ({}) writing {:?}", FFMPEG, status, self.path));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VideoSink {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            log::warn!("Video not finished: {}", e);
+        }
+    }
+}
+
+/// A video sink for video file extensions, else a PNG sequence in `path`.
+/// Fails up front when ffmpeg is missing rather than at the first frame.
+pub fn open_frame_sink(path: &Path, fps: f32) -> Result<Box<dyn FrameSink>, String> {
+    if is_video_path(path) {
+        Command::new(FFMPEG)
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| format!("{} is needed for video ({})", FFMPEG, e))?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        return Ok(Box::new(VideoSink::new(path, fps)));
+    }
+    Ok(Box::new(PngSequence::create(path)?))
+}
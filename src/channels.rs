@@ -0,0 +1,308 @@
+// ============================================================================
+// channels.rs — EvoLenia v2
+// Channel registry: auxiliary per-cell fields declared once in CHANNELS.
+// From its spec a channel gets its GPU buffers (one, or a ping-pong pair
+// stepped with the cell state), its bindings in the passes that use it
+// (layout entries, bind group entries and the WGSL declarations, appended
+// to group 0 from CHANNEL_BINDING), and, if it is part of the state, its
+// place in snapshots, state files and readback. Channel buffers are flat in
+// the world's cell layout, `components` f32 per cell; CPU copies are
+// row-major. The cell state and genome A stay hand-wired (world.rs).
+// ============================================================================
+
+use std::collections::BTreeMap;
+
+use wgpu::util::DeviceExt;
+
+use crate::pipeline::{bg_buffer, bgl_storage_ro, bgl_storage_rw};
+use crate::world::CellLayout;
+
+/// First binding of the registry channels in a pass; the hand-wired
+/// bindings of every pass stay below it.
+pub const CHANNEL_BINDING: u32 = 32;
+
+/// Mass-flux afterimage drawn over the render (compute_trail.wgsl).
+pub const TRAIL: &str = "trail";
+
+/// Nearest species cluster of each cell (compute_species.wgsl): the id as
+/// f32, -1 for empty cells, -2 for genomes no cluster is close to yet.
+pub const SPECIES: &str = "species";
+
+/// Registered channels, in binding order.
+pub const CHANNELS: &[ChannelSpec] = &[
+    ChannelSpec {
+        name: TRAIL,
+        components: 1,
+        ping_pong: false,
+        snapshot: false,
+        staging: false,
+        passes: &[ChannelPass::Trail, ChannelPass::Render],
+    },
+    ChannelSpec {
+        name: SPECIES,
+        components: 1,
+        ping_pong: false,
+        snapshot: false,
+        staging: true, // read back every diagnostics sample
+        passes: &[ChannelPass::Species, ChannelPass::Render],
+    },
+];
+
+/// Passes a channel can be bound in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelPass {
+    Evolution,
+    Trail,
+    Species,
+    Render,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChannelSpec {
+    pub name: &'static str,
+    pub components: usize, // f32 per cell: 1, 2 or 4 (WGSL f32 / vec2 / vec4)
+    pub ping_pong: bool,   // one buffer per side; evolution reads `<name>_in`, writes `<name>_out`
+    pub snapshot: bool,    // saved in snapshots and state files, restored on load
+    pub staging: bool,     // keeps a staging buffer for frequent readback
+    pub passes: &'static [ChannelPass],
+}
+
+impl ChannelSpec {
+    fn wgsl_type(&self) -> &'static str {
+        match self.components {
+            1 => "f32",
+            2 => "vec2<f32>",
+            _ => "vec4<f32>",
+        }
+    }
+
+    /// Variables the channel is bound as in `pass`: (suffix, writable).
+    /// Evolution writes every channel; the trail and species passes write
+    /// single ones; other passes only read, the side of the step they follow.
+    fn views(&self, pass: ChannelPass) -> &'static [(&'static str, bool)] {
+        match (pass, self.ping_pong) {
+            (ChannelPass::Evolution, true) => &[("_in", false), ("_out", true)],
+            (ChannelPass::Evolution | ChannelPass::Trail | ChannelPass::Species, false) => &[("", true)],
+            _ => &[("", false)],
+        }
+    }
+}
+
+/// (channel, variable suffix, writable, binding) of the channels bound in `pass`.
+fn bindings(pass: ChannelPass) -> Vec<(&'static ChannelSpec, &'static str, bool, u32)> {
+    let views = CHANNELS
+        .iter()
+        .filter(|spec| spec.passes.contains(&pass))
+        .flat_map(|spec| spec.views(pass).iter().map(move |&(suffix, writable)| (spec, suffix, writable)));
+    views.zip(CHANNEL_BINDING..).map(|((spec, suffix, writable), binding)| (spec, suffix, writable, binding)).collect()
+}
+
+/// WGSL declarations of the channels bound in `pass`, prepended to its shader.
+pub fn wgsl_declarations(pass: ChannelPass) -> String {
+    bindings(pass)
+        .into_iter()
+        .map(|(spec, suffix, writable, binding)| {
+            let access = if writable { "read_write" } else { "read" };
+            format!(
+                "@group(0) @binding({binding}) var<storage, {access}> {}{suffix}: array<{}>;\n",
+                spec.name,
+                spec.wgsl_type()
+            )
+        })
+        .collect()
+}
+
+/// `source` with the declarations of the channels bound in `pass`.
+pub fn with_channels(pass: ChannelPass, source: &str) -> String {
+    format!("{}{}", wgsl_declarations(pass), source)
+}
+
+/// Bind group layout entries of the channels bound in `pass`.
+pub fn layout_entries(pass: ChannelPass) -> Vec<wgpu::BindGroupLayoutEntry> {
+    bindings(pass)
+        .into_iter()
+        .map(|(_, _, writable, binding)| if writable { bgl_storage_rw(binding) } else { bgl_storage_ro(binding) })
+        .collect()
+}
+
+// ======================== GPU Buffers ========================
+
+struct ChannelBuffer {
+    spec: &'static ChannelSpec,
+    sides: Vec<wgpu::Buffer>, // two for ping-pong channels
+    staging: Option<wgpu::Buffer>,
+}
+
+/// GPU buffers of every registered channel.
+pub struct ChannelBuffers {
+    channels: Vec<ChannelBuffer>,
+    size: [u32; 2], // world width × height the buffers were sized for
+}
+
+impl ChannelBuffers {
+    /// Zeroed buffers for every registered channel of a `size` world.
+    pub fn new(device: &wgpu::Device, size: [u32; 2]) -> Self {
+        let cells = (size[0] * size[1]) as usize;
+        let channels = CHANNELS
+            .iter()
+            .map(|spec| {
+                let zeros = vec![0.0f32; cells * spec.components];
+                let sides = (0..if spec.ping_pong { 2 } else { 1 })
+                    .map(|side| {
+                        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: Some(&format!("{}_{side}", spec.name)),
+                            contents: bytemuck::cast_slice(&zeros),
+                            usage: wgpu::BufferUsages::STORAGE
+                                | wgpu::BufferUsages::COPY_SRC
+                                | wgpu::BufferUsages::COPY_DST,
+                        })
+                    })
+                    .collect();
+                let staging = spec.staging.then(|| {
+                    device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("staging_{}", spec.name)),
+                        size: (zeros.len() * std::mem::size_of::<f32>()) as u64,
+                        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    })
+                });
+                ChannelBuffer { spec, sides, staging }
+            })
+            .collect();
+        Self { channels, size }
+    }
+
+    fn get(&self, name: &str) -> Option<&ChannelBuffer> {
+        self.channels.iter().find(|c| c.spec.name == name)
+    }
+
+    /// Buffer of channel `name` holding side `cur` (the only buffer of a
+    /// single channel).
+    pub fn buffer(&self, name: &str, cur: usize) -> Option<&wgpu::Buffer> {
+        self.get(name).map(|c| &c.sides[cur.min(c.sides.len() - 1)])
+    }
+
+    /// Bind group entries of the channels bound in `pass`, for the step
+    /// with the given `cur`: evolution reads side `cur` and writes the
+    /// other; the passes after a step read the side it wrote.
+    pub fn bind_entries(&self, pass: ChannelPass, cur: usize) -> Vec<wgpu::BindGroupEntry<'_>> {
+        bindings(pass)
+            .into_iter()
+            .map(|(spec, suffix, _, binding)| {
+                let side = match (pass, suffix) {
+                    (ChannelPass::Evolution, "_in") => cur,
+                    _ => 1 - cur,
+                };
+                bg_buffer(binding, self.buffer(spec.name, side).expect("registered channel"))
+            })
+            .collect()
+    }
+
+    /// Zero every channel.
+    pub fn clear(&self, queue: &wgpu::Queue) {
+        for buffer in self.channels.iter().flat_map(|c| &c.sides) {
+            queue.write_buffer(buffer, 0, &vec![0u8; buffer.size() as usize]);
+        }
+    }
+
+    /// Load the snapshot channels from `data` (row-major, by name), both
+    /// sides; channels missing from `data` or of the wrong size are zeroed.
+    pub fn upload(&self, queue: &wgpu::Queue, layout: CellLayout, data: &BTreeMap<String, Vec<f32>>) {
+        let cells = (self.size[0] * self.size[1]) as usize;
+        for channel in self.channels.iter().filter(|c| c.spec.snapshot) {
+            let components = channel.spec.components;
+            let values = match data.get(channel.spec.name) {
+                Some(values) if values.len() == cells * components => {
+                    arrange(layout, self.size[0], values, components)
+                }
+                _ => vec![0.0; cells * components],
+            };
+            for buffer in &channel.sides {
+                queue.write_buffer(buffer, 0, bytemuck::cast_slice(&values));
+            }
+        }
+    }
+
+    /// Synchronous readback of side `cur` of channel `name`, row-major.
+    pub fn read(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: CellLayout,
+        name: &str,
+        cur: usize,
+    ) -> Option<Vec<f32>> {
+        let channel = self.get(name)?;
+        let source = self.buffer(name, cur)?;
+        let owned;
+        let staging = match &channel.staging {
+            Some(staging) => staging,
+            None => {
+                owned = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("staging_channel"),
+                    size: source.size(),
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                &owned
+            }
+        };
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("channel_readback"),
+        });
+        encoder.copy_buffer_to_buffer(source, 0, staging, 0, source.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        let data: Vec<f32> = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+        staging.unmap();
+        Some(row_major(layout, self.size[0], &data, channel.spec.components))
+    }
+
+    /// Readback of every snapshot channel at side `cur`, by name.
+    pub fn read_snapshot(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: CellLayout,
+        cur: usize,
+    ) -> Option<BTreeMap<String, Vec<f32>>> {
+        self.channels
+            .iter()
+            .filter(|c| c.spec.snapshot)
+            .map(|c| Some((c.spec.name.to_string(), self.read(device, queue, layout, c.spec.name, cur)?)))
+            .collect()
+    }
+
+    /// (name, buffer count, staging) of every channel, for the memory report.
+    pub fn entries(&self) -> impl Iterator<Item = (&'static str, &wgpu::Buffer, u32, Option<&wgpu::Buffer>)> {
+        self.channels.iter().map(|c| (c.spec.name, &c.sides[0], c.sides.len() as u32, c.staging.as_ref()))
+    }
+}
+
+/// Row-major `components`-wide values of a `width`-wide world reordered
+/// into `layout`.
+pub fn arrange(layout: CellLayout, width: u32, values: &[f32], components: usize) -> Vec<f32> {
+    let mut out = vec![0.0; values.len()];
+    for (i, value) in values.chunks_exact(components).enumerate() {
+        let j = layout.index(i as u32 % width, i as u32 / width, width);
+        out[j * components..(j + 1) * components].copy_from_slice(value);
+    }
+    out
+}
+
+/// Inverse of `arrange`.
+pub fn row_major(layout: CellLayout, width: u32, values: &[f32], components: usize) -> Vec<f32> {
+    (0..values.len() / components)
+        .flat_map(|i| {
+            let j = layout.index(i as u32 % width, i as u32 / width, width);
+            values[j * components..(j + 1) * components].iter().copied()
+        })
+        .collect()
+}
@@ -0,0 +1,323 @@
+// ============================================================================
+// condition_stats.rs — EvoLenia v2 Research Lab
+// Statistics of labelled conditions (conditions.rs): the metrics samples of
+// a condition, pooled over its spans, summarized as mean, standard
+// deviation and 95% confidence interval (Student t), and two conditions
+// compared by effect size (Cohen's d, pooled SD) and the Mann-Whitney U test
+// (normal approximation, tie-corrected, two-sided). A condition is taken
+// from the current run or from a completed run's metrics.csv and
+// conditions.json, so conditions compare within a run and across runs.
+// ============================================================================
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::alerts::metric_value;
+use crate::conditions::Conditions;
+use crate::expr::CompiledMetric;
+use crate::lab::{LabState, MetricsRecord};
+
+/// Metrics compared until the user picks others.
+pub const DEFAULT_METRICS: &[&str] = &["total_mass", "entropy", "species"];
+
+/// A condition of a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupRef {
+    pub run: Option<PathBuf>, // completed run directory; None: the current run
+    pub run_id: String,
+    pub condition: String,
+}
+
+impl GroupRef {
+    pub fn label(&self) -> String {
+        format!("{} · {}", self.run_id, self.condition)
+    }
+}
+
+/// Selection of the statistics panel.
+#[derive(Clone, Debug)]
+pub struct ConditionStats {
+    pub metrics: Vec<String>,
+    pub a: Option<GroupRef>,
+    pub b: Option<GroupRef>,
+    pub results: Option<Result<Vec<MetricComparison>, String>>, // last comparison
+}
+
+impl Default for ConditionStats {
+    fn default() -> Self {
+        Self {
+            metrics: DEFAULT_METRICS.iter().map(|m| m.to_string()).collect(),
+            a: None,
+            b: None,
+            results: None,
+        }
+    }
+}
+
+/// Mean, spread and 95% confidence interval of one sample.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: f64,
+    pub std_dev: f64, // sample (n - 1)
+    pub ci_low: f64,
+    pub ci_high: f64,
+}
+
+/// Mann-Whitney U of the first sample, its z score and two-sided p value.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct MannWhitney {
+    pub u: f64,
+    pub z: f64,
+    pub p: f64,
+}
+
+/// One metric compared between two conditions.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MetricComparison {
+    pub metric: String,
+    pub a: Option<Summary>,
+    pub b: Option<Summary>,
+    pub effect_size: Option<f64>, // Cohen's d of b relative to a
+    pub mann_whitney: Option<MannWhitney>,
+}
+
+/// Two-sided 95% Student t quantiles for 1..=30 degrees of freedom.
+const T95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131,
+    2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+/// Two-sided 95% t quantile; past 30 degrees of freedom 1.96 + 2.5/df is
+/// within 0.002 of the table value.
+pub fn t95(df: usize) -> f64 {
+    match df {
+        0 => f64::NAN,
+        1..=30 => T95[df - 1],
+        _ => 1.96 + 2.5 / df as f64,
+    }
+}
+
+/// Summary of the finite values of `values` (None below two).
+pub fn summarize(values: &[f64]) -> Option<Summary> {
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let n = values.len();
+    if n < 2 {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    let std_dev = variance.sqrt();
+    let half = t95(n - 1) * std_dev / (n as f64).sqrt();
+    Some(Summary { n, mean, std_dev, ci_low: mean - half, ci_high: mean + half })
+}
+
+/// Cohen's d of `b` relative to `a` (None when both are constant).
+pub fn cohens_d(a: &Summary, b: &Summary) -> Option<f64> {
+    let df = (a.n + b.n - 2) as f64;
+    let pooled = (((a.n - 1) as f64 * a.std_dev.powi(2) + (b.n - 1) as f64 * b.std_dev.powi(2)) / df).sqrt();
+    (pooled > 0.0).then(|| (b.mean - a.mean) / pooled)
+}
+
+/// Mann-Whitney U test of the finite values of `a` against `b`.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+    let mut all: Vec<(f64, bool)> = a
+        .iter()
+        .filter(|v| v.is_finite())
+        .map(|&v| (v, true))
+        .chain(b.iter().filter(|v| v.is_finite()).map(|&v| (v, false)))
+        .collect();
+    let n1 = all.iter().filter(|(_, first)| *first).count() as f64;
+    let n2 = all.len() as f64 - n1;
+    if n1 == 0.0 || n2 == 0.0 {
+        return None;
+    }
+    all.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Average ranks over ties, and the tie correction term
+    let (mut rank_sum, mut ties) = (0.0, 0.0);
+    let mut i = 0;
+    while i < all.len() {
+        let j = i + all[i..].iter().take_while(|(v, _)| *v == all[i].0).count();
+        let rank = (i + j + 1) as f64 / 2.0; // mean of ranks i+1 ..= j
+        rank_sum += rank * all[i..j].iter().filter(|(_, first)| *first).count() as f64;
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+    let n = n1 + n2;
+    let u = rank_sum - n1 * (n1 + 1.0) / 2.0;
+    let mu = n1 * n2 / 2.0;
+    let sigma = (n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)))).sqrt();
+    if sigma <= 0.0 || !sigma.is_finite() {
+        return Some(MannWhitney { u, z: 0.0, p: 1.0 });
+    }
+    // Continuity correction toward the mean
+    let z = (u - mu - 0.5 * (u - mu).signum()) / sigma;
+    let p = (2.0 * (1.0 - normal_cdf(z.abs()))).clamp(0.0, 1.0);
+    Some(MannWhitney { u, z, p })
+}
+
+/// Standard normal CDF (Abramowitz and Stegun 7.1.26, error below 1.5e-7).
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// `metric` at every sample of `history` labelled `condition`.
+pub fn condition_samples(
+    history: &[MetricsRecord],
+    conditions: &Conditions,
+    condition: &str,
+    metric: &str,
+    custom: &[CompiledMetric],
+) -> Vec<f64> {
+    history
+        .iter()
+        .filter(|r| conditions.label_at(r.frame) == Some(condition))
+        .filter_map(|r| metric_value(metric, r, custom))
+        .collect()
+}
+
+/// Compare `metric` between two samples.
+pub fn compare(metric: &str, a: &[f64], b: &[f64]) -> MetricComparison {
+    let (sa, sb) = (summarize(a), summarize(b));
+    MetricComparison {
+        metric: metric.to_string(),
+        a: sa,
+        b: sb,
+        effect_size: sa.zip(sb).and_then(|(sa, sb)| cohens_d(&sa, &sb)),
+        mann_whitney: mann_whitney(a, b),
+    }
+}
+
+/// Every condition of the current run and of the completed runs that saved
+/// conditions.json, current run first.
+pub fn available_groups(lab: &LabState) -> Vec<GroupRef> {
+    let mut groups = Vec::new();
+    let mut add = |run: Option<&Path>, run_id: &str, conditions: &Conditions| {
+        let start = groups.len();
+        for span in &conditions.spans {
+            if !groups[start..].iter().any(|g: &GroupRef| g.condition == span.name) {
+                groups.push(GroupRef {
+                    run: run.map(Path::to_path_buf),
+                    run_id: run_id.to_string(),
+                    condition: span.name.clone(),
+                });
+            }
+        }
+    };
+    add(None, &lab.run_id, &lab.conditions);
+    for run in &lab.completed_runs {
+        // A finalized run keeps its conditions in the lab until the next run
+        if run.run_dir != lab.run_dir {
+            add(Some(&run.run_dir), &run.run_id, &Conditions::load(&run.run_dir));
+        }
+    }
+    groups
+}
+
+/// Metrics history and conditions of a group's run.
+fn group_data(lab: &LabState, group: &GroupRef) -> Result<(Vec<MetricsRecord>, Conditions), String> {
+    match &group.run {
+        None => Ok((lab.metrics_history.clone(), lab.conditions.clone())),
+        Some(dir) => Ok((
+            LabState::load_comparison_metrics(&dir.join("metrics.csv"))?,
+            Conditions::load(dir),
+        )),
+    }
+}
+
+/// Compare the selected metrics between the two selected conditions.
+pub fn compare_groups(lab: &LabState, stats: &ConditionStats) -> Result<Vec<MetricComparison>, String> {
+    let (Some(a), Some(b)) = (&stats.a, &stats.b) else {
+        return Err(String::from("pick two conditions"));
+    };
+    let (history_a, conditions_a) = group_data(lab, a)?;
+    let (history_b, conditions_b) = group_data(lab, b)?;
+    Ok(stats
+        .metrics
+        .iter()
+        .map(|metric| {
+            let sa = condition_samples(&history_a, &conditions_a, &a.condition, metric, &lab.custom_metrics);
+            let sb = condition_samples(&history_b, &conditions_b, &b.condition, metric, &lab.custom_metrics);
+            compare(metric, &sa, &sb)
+        })
+        .collect())
+}
+
+/// Report section: each condition of the run summarized for the selected
+/// metrics, then the selected comparison when there is one. Empty for a
+/// run without conditions.
+pub fn conditions_section(lab: &LabState, stats: &ConditionStats) -> String {
+    if lab.conditions.spans.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("## Conditions\n\n");
+    out.push_str("| Condition | Frames |");
+    for metric in &stats.metrics {
+        out.push_str(&format!(" {} (mean [95% CI]) |", metric));
+    }
+    out.push_str(&format!("\n|-----------|--------|{}\n", "------|".repeat(stats.metrics.len())));
+    for span in &lab.conditions.spans {
+        let end = span.end.map_or_else(|| String::from("end"), |e| e.to_string());
+        out.push_str(&format!("| {} | {}–{} |", span.name.replace('|', "\\|"), span.start, end));
+        for metric in &stats.metrics {
+            let samples: Vec<f64> = lab
+                .metrics_history
+                .iter()
+                .filter(|r| span.contains(r.frame))
+                .filter_map(|r| metric_value(metric, r, &lab.custom_metrics))
+                .collect();
+            out.push_str(&format!(" {} |", summary_cell(summarize(&samples).as_ref())));
+        }
+        out.push('\n');
+    }
+    if let (Some(a), Some(b), Ok(results)) = (&stats.a, &stats.b, compare_groups(lab, stats)) {
+        out.push_str(&format!("\n### {} vs {}\n\n", a.label(), b.label()));
+        out.push_str(&comparison_table(&results));
+    }
+    out
+}
+
+/// Markdown table of comparisons.
+pub fn comparison_table(results: &[MetricComparison]) -> String {
+    let mut out = String::from(
+        "| Metric | A mean [95% CI] | B mean [95% CI] | Cohen's d | U | p |\n\
+         |--------|-----------------|-----------------|-----------|---|---|\n",
+    );
+    for r in results {
+        let (u, p) = r.mann_whitney.map_or((String::from("—"), String::from("—")), |m| (format!("{:.0}", m.u), format_p(m.p)));
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            r.metric,
+            summary_cell(r.a.as_ref()),
+            summary_cell(r.b.as_ref()),
+            r.effect_size.map_or_else(|| String::from("—"), |d| format!("{:.2}", d)),
+            u,
+            p,
+        ));
+    }
+    out
+}
+
+/// "mean [low, high] (n)", or a dash below two samples.
+pub fn summary_cell(summary: Option<&Summary>) -> String {
+    summary.map_or_else(
+        || String::from("—"),
+        |s| format!("{} [{}, {}] (n={})", short(s.mean), short(s.ci_low), short(s.ci_high), s.n),
+    )
+}
+
+pub fn format_p(p: f64) -> String {
+    if p < 0.001 { String::from("<0.001") } else { format!("{:.3}", p) }
+}
+
+/// Compact number: fixed with four decimals, scientific when very large or small.
+fn short(x: f64) -> String {
+    if x != 0.0 && (x.abs() >= 1e5 || x.abs() < 1e-3) { format!("{:.3e}", x) } else { format!("{:.4}", x) }
+}
@@ -0,0 +1,91 @@
+// ============================================================================
+// conditions.rs — EvoLenia v2 Research Lab
+// Named experimental conditions: labelled frame ranges of a run ("baseline",
+// "post-drought"). Beginning a condition ends the one in progress, so the
+// spans follow each other; a span can also be ended without a successor.
+// They are drawn as shaded bands on the analysis plots, added as a
+// `condition` column to metrics.csv and written to conditions.json, so
+// analysis scripts can group samples by condition.
+// ============================================================================
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub const CONDITIONS_FILE: &str = "conditions.json";
+
+/// One labelled span of frames, `start` included, `end` excluded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub name: String,
+    pub start: u32,
+    pub end: Option<u32>, // None: in progress
+}
+
+impl Condition {
+    pub fn contains(&self, frame: u32) -> bool {
+        frame >= self.start && self.end.is_none_or(|end| frame < end)
+    }
+}
+
+/// Conditions of the current run, in start order.
+#[derive(Clone, Debug, Default)]
+pub struct Conditions {
+    pub spans: Vec<Condition>,
+}
+
+impl Conditions {
+    /// The condition in progress, if any.
+    pub fn current(&self) -> Option<&Condition> {
+        self.spans.last().filter(|c| c.end.is_none())
+    }
+
+    /// Start condition `name` at `frame`, ending the one in progress.
+    pub fn begin(&mut self, name: &str, frame: u32) {
+        self.end(frame);
+        self.spans.push(Condition { name: name.trim().to_string(), start: frame, end: None });
+    }
+
+    /// End the condition in progress at `frame`; returns it.
+    pub fn end(&mut self, frame: u32) -> Option<&Condition> {
+        let current = self.spans.last_mut().filter(|c| c.end.is_none())?;
+        current.end = Some(frame.max(current.start));
+        Some(current)
+    }
+
+    /// Name of the condition `frame` belongs to (the latest started, when
+    /// spans overlap after an edit).
+    pub fn label_at(&self, frame: u32) -> Option<&str> {
+        self.spans.iter().rev().find(|c| c.contains(frame)).map(|c| c.name.as_str())
+    }
+
+    pub fn clear(&mut self) {
+        self.spans.clear();
+    }
+
+    /// Conditions saved in `dir` (none when it has no readable conditions.json).
+    pub fn load(dir: &Path) -> Self {
+        let spans = fs::read_to_string(dir.join(CONDITIONS_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { spans }
+    }
+
+    /// Write conditions.json into `dir` (nothing when there are none).
+    pub fn save(&self, dir: &Path) -> Result<Option<PathBuf>, String> {
+        if self.spans.is_empty() {
+            return Ok(None);
+        }
+        let path = dir.join(CONDITIONS_FILE);
+        let json = serde_json::to_string_pretty(&self.spans).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        Ok(Some(path))
+    }
+}
+
+/// Condition name as a CSV field: commas and line breaks become `_`.
+pub fn csv_label(name: &str) -> String {
+    name.chars().map(|c| if c == ',' || c == '\n' || c == '\r' { '_' } else { c }).collect()
+}
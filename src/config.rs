@@ -6,6 +6,14 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::kernel::{GENOME_RADIUS_RANGE, KERNEL_LUT_MAX_BINS, KERNEL_MAX_R};
+use crate::particles::MAX_PARTICLES;
+use crate::pattern::{GENOME_MU_RANGE, GENOME_SIGMA_RANGE};
+use crate::perturbation::PerturbationType;
+use crate::reserves::ReserveZone;
+use crate::schedule::ParamSchedule;
+use crate::world::GridType;
+
 /// Runtime simulation parameters adjustable via the Research Lab UI.
 /// Every field here is wired to either a GPU uniform or engine state.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,26 +23,84 @@ pub struct SimulationParams {
     pub simulation_speed: u32,
     pub time_step: f32,
     pub vsync: bool,
+    #[serde(default)]
+    pub step_budget: StepBudgetSettings,
+    #[serde(default)]
+    pub render_cadence: RenderCadenceSettings,
+    #[serde(default)]
+    pub speed_guard: SpeedGuardSettings,
+    #[serde(default)]
+    pub separate_velocity_pass: bool, // debug: velocity field in its own pass, not fused into evolution
 
     // -- Visualization --
     pub visualization_mode: u32,
     pub show_extended_ui: bool,
+    #[serde(default)]
+    pub display_adjust: [DisplayAdjust; VIS_MODE_COUNT as usize],
+    #[serde(default)]
+    pub bloom: BloomSettings,
+    #[serde(default)]
+    pub trail: TrailSettings,
+    #[serde(default)]
+    pub diff_overlay: DiffOverlaySettings,
+    #[serde(default)]
+    pub territory: TerritorySettings,
+    #[serde(default)]
+    pub species_map: SpeciesMapSettings,
+    #[serde(default)]
+    pub legend: LegendSettings,
+    #[serde(default)]
+    pub histogram: HistogramSettings,
 
     // -- Evolution / Mutation --
     pub mutation_rate: f32,
+    #[serde(default)]
+    pub mutation: MutationSettings,
+    #[serde(default)]
+    pub gene_constraints: GeneConstraints,
+    #[serde(default)]
+    pub convolution: ConvolutionSettings,
 
     // -- Predation --
     pub predation_factor: f32,
+    #[serde(default)]
+    pub interaction_matrix: bool, // count who-eats-whom takeovers (analysis panel)
 
     // -- Resources (Gray-Scott) --
     pub resource_diffusion: f32,
     pub resource_feed_rate: f32,
     pub resource_consumption: f32,
 
+    // -- Transport --
+    #[serde(default = "isotropic")]
+    pub anisotropy: f32, // horizontal vs vertical strength of advection and diffusion (1 = isotropic)
+    #[serde(default)]
+    pub grid: GridType, // lattice of the next world created (restart)
+    #[serde(default)]
+    pub rotation: RotationSettings,
+
+    // -- Particles --
+    #[serde(default)]
+    pub particles: ParticleSettings,
+
+    // -- Flow field --
+    #[serde(default)]
+    pub flow: FlowSettings,
+
+    // -- Reserve zones (reserves.rs) --
+    #[serde(default)]
+    pub reserves: Vec<ReserveZone>,
+
     // -- Mass normalization --
     pub mass_normalization_enabled: bool,
     pub mass_damping: f32,
     pub target_mass_multiplier: f32,
+    #[serde(default)]
+    pub normalization_cadence: NormalizationCadence,
+    #[serde(default)]
+    pub carrying_capacity: CarryingCapacitySettings,
+    #[serde(default)]
+    pub demographic_noise: DemographicNoiseSettings,
 
     // -- Non-linear trade-offs --
     pub radius_cost_exponent: f32,   // exponent for radius metabolic cost (1.0=linear, 2.0=quadratic)
@@ -48,6 +114,18 @@ pub struct SimulationParams {
     pub perturbation_active: bool,     // fire once (auto-clears)
     pub perturbation_center_x: f32,    // center in world-space [0,1]
     pub perturbation_center_y: f32,
+    #[serde(default)]
+    pub perturbation_continuous: bool, // apply a fraction every step while enabled
+    #[serde(default = "default_barrier_frames")]
+    pub barrier_frames: u32,           // lifetime of a Barrier perturbation, in steps
+
+    // -- Parameter schedules (schedule.rs) --
+    #[serde(default)]
+    pub schedules: Vec<ParamSchedule>, // applied before every step
+
+    // -- Long-term evolution experiment (ltee.rs) --
+    #[serde(default)]
+    pub ltee: LteeSettings,
 
     // -- Initial conditions (applied on restart) --
     pub num_seed_clusters: u32,
@@ -58,6 +136,20 @@ pub struct SimulationParams {
     pub seed: Option<u64>,
     pub use_fixed_seed: bool,
     pub fixed_seed_value: u64,
+
+    // -- Analysis --
+    #[serde(default)]
+    pub custom_metrics: Vec<CustomMetric>, // derived metrics (expr.rs), plotted and exported
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>, // checked at every metrics sample (alerts.rs)
+    #[serde(default)]
+    pub speciation: SpeciationSettings, // split detection at every metrics sample (speciation.rs)
+    #[serde(default)]
+    pub forensics: ForensicsSettings, // extinction reports (forensics.rs)
+    #[serde(default)]
+    pub averages: FieldAverageSettings, // time-averaged fields on the GPU (averages.rs)
+    #[serde(default)]
+    pub velocity_export: VelocityExportSettings, // velocity field files at diagnostics cadence (vectors.rs)
 }
 
 impl Default for SimulationParams {
@@ -67,20 +159,47 @@ impl Default for SimulationParams {
             simulation_speed: 1,
             time_step: 1.0,
             vsync: false,
+            step_budget: StepBudgetSettings::default(),
+            render_cadence: RenderCadenceSettings::default(),
+            speed_guard: SpeedGuardSettings::default(),
+            separate_velocity_pass: false,
 
             visualization_mode: 0,
             show_extended_ui: false,
+            display_adjust: Default::default(),
+            bloom: BloomSettings::default(),
+            trail: TrailSettings::default(),
+            diff_overlay: DiffOverlaySettings::default(),
+            territory: TerritorySettings::default(),
+            species_map: SpeciesMapSettings::default(),
+            legend: LegendSettings::default(),
+            histogram: HistogramSettings::default(),
 
             mutation_rate: 0.5,
+            mutation: MutationSettings::default(),
+            gene_constraints: GeneConstraints::default(),
+            convolution: ConvolutionSettings::default(),
             predation_factor: 1.0,
+            interaction_matrix: false,
 
             resource_diffusion: 0.08,
             resource_feed_rate: 0.012,
             resource_consumption: 0.06,
 
+            anisotropy: 1.0,
+            grid: GridType::Square,
+            rotation: RotationSettings::default(),
+
+            particles: ParticleSettings::default(),
+            flow: FlowSettings::default(),
+            reserves: Vec::new(),
+
             mass_normalization_enabled: true,
             mass_damping: 0.3,
             target_mass_multiplier: 1.0,
+            normalization_cadence: NormalizationCadence::default(),
+            carrying_capacity: CarryingCapacitySettings::default(),
+            demographic_noise: DemographicNoiseSettings::default(),
 
             radius_cost_exponent: 1.3,
             agg_mobility_tradeoff: 0.3,
@@ -92,6 +211,10 @@ impl Default for SimulationParams {
             perturbation_active: false,
             perturbation_center_x: 0.5,
             perturbation_center_y: 0.5,
+            perturbation_continuous: false,
+            barrier_frames: default_barrier_frames(),
+            schedules: Vec::new(),
+            ltee: LteeSettings::default(),
 
             num_seed_clusters: 30,
             seed_cluster_size: 1.0,
@@ -100,11 +223,28 @@ impl Default for SimulationParams {
             seed: None,
             use_fixed_seed: false,
             fixed_seed_value: 42,
+
+            custom_metrics: Vec::new(),
+            alert_rules: Vec::new(),
+            speciation: SpeciationSettings::default(),
+            forensics: ForensicsSettings::default(),
+            averages: FieldAverageSettings::default(),
+            velocity_export: VelocityExportSettings::default(),
         }
     }
 }
 
 impl SimulationParams {
+    /// Display adjustment of the active visualization mode.
+    pub fn current_display_adjust(&self) -> &DisplayAdjust {
+        &self.display_adjust[display_adjust_slot(self.visualization_mode)]
+    }
+
+    /// Mutable display adjustment of the active visualization mode.
+    pub fn current_display_adjust_mut(&mut self) -> &mut DisplayAdjust {
+        &mut self.display_adjust[display_adjust_slot(self.visualization_mode)]
+    }
+
     /// Compute the effective seed for reproducibility.
     pub fn effective_seed(&self) -> Option<u64> {
         if self.use_fixed_seed {
@@ -113,38 +253,877 @@ impl SimulationParams {
             self.seed
         }
     }
+
+    /// Per-step intensity of the continuous perturbation, if one is running.
+    pub fn continuous_perturbation_intensity(&self) -> Option<f32> {
+        (self.perturbation_continuous && self.perturbation_type.plugin().continuous())
+            .then(|| self.perturbation_intensity / CONTINUOUS_PERTURBATION_STEPS)
+    }
+}
+
+/// Per-mode gain/contrast applied in render.wgsl to the mode's primary field
+/// (mass for most modes, flow speed for Advection Flux):
+/// `t = clamp((v * exposure - min) / (max - min), 0, 1)`, then the tone
+/// transform, then `pow(t, 1 / gamma)`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisplayAdjust {
+    pub exposure: f32,
+    pub gamma: f32,
+    pub range_min: f32,
+    pub range_max: f32,
+    pub auto_normalize: bool, // refit min/max on every diagnostics readback
+    #[serde(default)]
+    pub transform: DisplayTransform,
+}
+
+impl Default for DisplayAdjust {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            gamma: 1.0,
+            range_min: 0.0,
+            range_max: 1.0,
+            auto_normalize: false,
+            transform: DisplayTransform::Linear,
+        }
+    }
+}
+
+/// HDR bloom post-process (cosmetic). Energetic predators and dense blooms
+/// are boosted above 1.0 by `emission` in render.wgsl; everything brighter
+/// than `threshold` is blurred and added back, then soft-tonemapped.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BloomSettings {
+    pub enabled: bool,
+    pub emission: f32,  // HDR boost of energetic regions (0 = none)
+    pub threshold: f32, // luminance above which pixels glow
+    pub intensity: f32, // strength of the blurred glow
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emission: 1.5,
+            threshold: 0.9,
+            intensity: 0.8,
+        }
+    }
+}
+
+/// Wall-clock step budget: instead of the fixed `simulation_speed`, run as
+/// many steps per frame as fit while the UI keeps `target_fps`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepBudgetSettings {
+    pub enabled: bool,
+    pub target_fps: f32,
+    pub max_steps: u32, // upper bound on steps per frame
+}
+
+impl Default for StepBudgetSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_fps: 30.0,
+            max_steps: 200,
+        }
+    }
+}
+
+/// Responsiveness guard: steps per frame drop below `simulation_speed`
+/// while event loop turns take longer than `max_frame_ms`, and come back
+/// once there is headroom (see pacing::SpeedGuard).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedGuardSettings {
+    pub enabled: bool,
+    pub max_frame_ms: f32,
+}
+
+impl Default for SpeedGuardSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_frame_ms: 33.0,
+        }
+    }
+}
+
+/// How often the world is rendered, independently of stepping: loop
+/// iterations in between only simulate (see pacing::RenderClock).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderCadenceSettings {
+    pub every_n_steps: u32, // render once per this many simulated steps (1 = every frame)
+    pub max_fps: f32,       // render at most this often (0 = uncapped)
+}
+
+impl Default for RenderCadenceSettings {
+    fn default() -> Self {
+        Self {
+            every_n_steps: 1,
+            max_fps: 0.0,
+        }
+    }
+}
+
+/// Afterimage layer: a decaying imprint of mass × flow speed, composited
+/// additively over any visualization mode.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrailSettings {
+    pub enabled: bool,
+    pub decay: f32,   // per-step retention (higher = longer trails)
+    pub gain: f32,    // imprint scale for mass × speed
+    pub opacity: f32, // strength of the layer in the render
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            decay: 0.95,
+            gain: 20.0,
+            opacity: 0.8,
+        }
+    }
+}
+
+/// Grazer / disperser particle layer (particles.rs): how many run, how they
+/// move and feed, and how visible they are.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleSettings {
+    pub enabled: bool,
+    pub count: u32,       // active particles, up to MAX_PARTICLES
+    pub speed: f32,       // cells per step
+    pub graze_rate: f32,  // share of the cell's mass taken per step
+    pub capacity: f32,    // mass a particle can carry
+    pub drop_rate: f32,   // share of the cargo dropped per step on empty ground
+    pub metabolism: f32,  // energy spent per step; grazed mass refills it
+    pub opacity: f32,     // strength of the particle markers in the render
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 2_000,
+            speed: 0.6,
+            graze_rate: 0.1,
+            capacity: 0.5,
+            drop_rate: 0.2,
+            metabolism: 0.002,
+            opacity: 0.9,
+        }
+    }
+}
+
+impl ParticleSettings {
+    /// Particles the passes run over: 0 when the layer is off.
+    pub fn active_count(&self) -> u32 {
+        if self.enabled {
+            self.count.min(MAX_PARTICLES)
+        } else {
+            0
+        }
+    }
+}
+
+/// Rotating world: a Coriolis-like deflection of the advection velocity.
+/// The world is seen from above a pole at `center`: the deflection is
+/// strongest there and fades to nothing at the farthest point of the torus,
+/// like the Coriolis parameter from pole to equator.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RotationSettings {
+    pub enabled: bool,
+    pub omega: f32,       // angular velocity, radians per step; > 0 turns motion clockwise on screen
+    pub center: [f32; 2], // pole position in world UV
+}
+
+impl Default for RotationSettings {
+    fn default() -> Self {
+        Self { enabled: false, omega: 0.1, center: [0.5, 0.5] }
+    }
+}
+
+/// Range of `RotationSettings::omega`: at the pole velocities turn by up to
+/// 2·|ω| radians per step.
+pub const OMEGA_RANGE: f32 = 0.5;
+
+impl RotationSettings {
+    /// Angular velocity as uploaded to the shaders: 0 when off.
+    pub fn effective_omega(&self) -> f32 {
+        if self.enabled {
+            self.omega.clamp(-OMEGA_RANGE, OMEGA_RANGE)
+        } else {
+            0.0
+        }
+    }
+
+    /// Pole position in cells of a `size` world.
+    pub fn center_cells(&self, [width, height]: [u32; 2]) -> [f32; 2] {
+        [self.center[0] * width as f32, self.center[1] * height as f32]
+    }
 }
 
-/// Perturbation types for ecological experiments.
+/// Global flow field (flow.rs): whether it acts, how strongly it pushes
+/// organisms and carries resources, and the streamline preview.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlowSettings {
+    pub enabled: bool,
+    pub strength: f32,           // cells per step added to the advection velocity at |v| = 1
+    pub resource_transport: f32, // share of the flow the resource field moves with
+    pub overlay_opacity: f32,    // streamline preview (0 = hidden), drawn even while disabled
+}
+
+impl Default for FlowSettings {
+    fn default() -> Self {
+        Self { enabled: false, strength: 0.3, resource_transport: 1.0, overlay_opacity: 0.0 }
+    }
+}
+
+impl FlowSettings {
+    /// Scale applied to the field in the advection velocity: 0 when off.
+    pub fn velocity_scale(&self) -> f32 {
+        if self.enabled {
+            self.strength
+        } else {
+            0.0
+        }
+    }
+
+    /// Scale applied to the field when advecting resources.
+    pub fn resource_scale(&self) -> f32 {
+        self.velocity_scale() * self.resource_transport
+    }
+}
+
+/// User-defined metric: an expression over the metrics record fields,
+/// e.g. `total_energy / total_mass` (see expr.rs).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomMetric {
+    pub name: String,
+    pub expression: String,
+}
+
+/// How an alert rule compares a metric to its threshold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparator {
+    #[default]
+    Above,
+    Below,
+    DropsBy, // threshold in % below the highest value within the window
+    RisesBy, // threshold in % above the lowest value within the window
+}
+
+impl Comparator {
+    pub fn all() -> [Comparator; 4] {
+        [Comparator::Above, Comparator::Below, Comparator::DropsBy, Comparator::RisesBy]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Comparator::Above => "alert.above",
+            Comparator::Below => "alert.below",
+            Comparator::DropsBy => "alert.drops_by",
+            Comparator::RisesBy => "alert.rises_by",
+        }
+    }
+
+    /// Relative comparators look back over `window_frames`.
+    pub fn is_relative(self) -> bool {
+        matches!(self, Comparator::DropsBy | Comparator::RisesBy)
+    }
+}
+
+/// Fires when a metric (built-in field or custom metric name) meets its
+/// condition for `sustained` consecutive samples; re-arms once it stops.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub enum PerturbationType {
-    None,
-    Drought,         // reduces resources in area
-    NutrientPulse,   // boosts resources in area
-    MassStorm,       // randomizes mass/energy in area (catastrophe)
-    MutationBurst,   // locally amplifies mutation rate
+pub struct AlertRule {
+    pub enabled: bool,
+    pub metric: String,
+    pub comparator: Comparator,
+    pub threshold: f64,
+    pub window_frames: u32, // look-back of DropsBy / RisesBy
+    pub sustained: u32,     // consecutive samples required (at least 1)
+    pub pause: bool,        // pause the simulation when fired
+    pub snapshot: bool,     // save a snapshot when fired
 }
 
-impl PerturbationType {
-    pub fn all() -> &'static [PerturbationType] {
+impl Default for AlertRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            metric: String::from("entropy"),
+            comparator: Comparator::DropsBy,
+            threshold: 50.0,
+            window_frames: 1000,
+            sustained: 1,
+            pause: false,
+            snapshot: false,
+        }
+    }
+}
+
+/// Overlay of mass minus a reference snapshot, to see where a run drifts
+/// away from a saved state.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiffOverlaySettings {
+    pub enabled: bool,
+    pub opacity: f32, // strength of the overlay in the render
+    pub range: f32,   // |Δmass| shown at full color
+}
+
+impl Default for DiffOverlaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            opacity: 0.7,
+            range: 0.2,
+        }
+    }
+}
+
+/// Territory overlay: which species held each region over the last `window`
+/// diagnostics samples (territory.rs).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TerritorySettings {
+    pub enabled: bool,
+    pub opacity: f32, // strength of the overlay in the render
+    pub window: u32,  // samples kept (one per metrics sample)
+}
+
+impl Default for TerritorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            opacity: 0.6,
+            window: 16,
+        }
+    }
+}
+
+/// GPU species map (compute_species.wgsl): every cell labelled with its
+/// nearest species cluster. When enabled, territories are labelled from it
+/// and `colors` gives each species its own hue in the Species Color mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeciesMapSettings {
+    pub enabled: bool,
+    pub colors: bool,
+}
+
+/// On-screen legend of the active visualization mode (legend.rs), also
+/// composited into screenshots when `in_screenshots` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LegendSettings {
+    pub enabled: bool,
+    pub in_screenshots: bool,
+}
+
+impl Default for LegendSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            in_screenshots: true,
+        }
+    }
+}
+
+/// Quantity the histogram overlay (histogram.rs) bins; `Auto` follows the
+/// field the active visualization mode shades.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistogramField {
+    #[default]
+    Auto,
+    Mass,
+    Energy,
+    Resource,
+}
+
+impl HistogramField {
+    pub fn all() -> &'static [HistogramField] {
         &[
-            PerturbationType::None,
-            PerturbationType::Drought,
-            PerturbationType::NutrientPulse,
-            PerturbationType::MassStorm,
-            PerturbationType::MutationBurst,
+            HistogramField::Auto,
+            HistogramField::Mass,
+            HistogramField::Energy,
+            HistogramField::Resource,
         ]
     }
 
-    pub fn name(&self) -> &'static str {
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
         match self {
-            PerturbationType::None => "None",
-            PerturbationType::Drought => "Drought",
-            PerturbationType::NutrientPulse => "Nutrient Pulse",
-            PerturbationType::MassStorm => "Mass Storm",
-            PerturbationType::MutationBurst => "Mutation Burst",
+            HistogramField::Auto => "histogram.auto",
+            HistogramField::Mass => "histogram.mass",
+            HistogramField::Energy => "histogram.energy",
+            HistogramField::Resource => "histogram.resource",
         }
     }
+
+    /// The field binned in visualization `mode`: energy for the energy
+    /// heatmap, mass (the display-adjusted intensity) otherwise.
+    pub fn resolve(self, mode: u32) -> HistogramField {
+        match self {
+            HistogramField::Auto if mode == 1 => HistogramField::Energy,
+            HistogramField::Auto => HistogramField::Mass,
+            field => field,
+        }
+    }
+
+    /// Selector value understood by compute_histogram.wgsl.
+    pub fn shader_id(&self) -> u32 {
+        match self {
+            HistogramField::Auto | HistogramField::Mass => 0,
+            HistogramField::Energy => 1,
+            HistogramField::Resource => 2,
+        }
+    }
+}
+
+/// Live histogram overlay of the visualized field, in the bottom-right
+/// corner of the viewport; `log_scale` draws bar heights as log counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistogramSettings {
+    pub enabled: bool,
+    pub field: HistogramField,
+    pub log_scale: bool,
+}
+
+/// Lenia convolution mode. The separable mode approximates each kernel tier
+/// by a few horizontal × vertical filter pairs (kernel.rs); `compare_exact`
+/// additionally runs the exact convolution to measure the difference. The
+/// exact convolution reads its weights from a `lut_bins`-radius lookup table
+/// when `kernel_lut` is set, instead of calling exp() per neighbor.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvolutionSettings {
+    pub separable: bool,
+    pub compare_exact: bool, // only meaningful in separable mode
+    pub kernel_lut: bool,
+    pub lut_bins: u32, // radius samples across the genome radius range
+}
+
+impl Default for ConvolutionSettings {
+    fn default() -> Self {
+        Self {
+            separable: false,
+            compare_exact: false,
+            kernel_lut: true,
+            lut_bins: 64,
+        }
+    }
+}
+
+impl ConvolutionSettings {
+    /// Selector value understood by compute_evolution.wgsl.
+    pub fn shader_mode(&self) -> u32 {
+        match (self.separable, self.compare_exact) {
+            (false, _) => 0,
+            (true, false) => 1,
+            (true, true) => 2,
+        }
+    }
+
+    /// LUT radius bins the shader should read, 0 when the LUT is off.
+    pub fn kernel_lut_bins(&self) -> u32 {
+        if self.kernel_lut {
+            self.lut_bins.clamp(2, KERNEL_LUT_MAX_BINS)
+        } else {
+            0
+        }
+    }
+}
+
+/// Range of `SimulationParams::anisotropy`. Within it the explicit resource
+/// diffusion stays stable over the whole diffusion slider range.
+pub const ANISOTROPY_RANGE: (f32, f32) = (0.25, 4.0);
+
+fn isotropic() -> f32 {
+    1.0
+}
+
+impl SimulationParams {
+    /// Anisotropy clamped to its supported range, as uploaded to the shaders.
+    pub fn transport_anisotropy(&self) -> f32 {
+        self.anisotropy.clamp(ANISOTROPY_RANGE.0, ANISOTROPY_RANGE.1)
+    }
+}
+
+/// Longest supported gap between two mass sum passes.
+pub const MAX_SUM_INTERVAL: u32 = 16;
+
+/// Cadence of the mass normalization passes. The sum pass (a reduction over
+/// the whole world) runs every `sum_interval` steps; in between, the
+/// normalize pass extrapolates the total from the last two sums, so the total
+/// converges to the target more slowly while mass changes fast. Both passes
+/// are skipped while the drift measured at the last readback is below
+/// `drift_threshold` (a fraction of the target mass, 0 = never skip).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizationCadence {
+    pub sum_interval: u32,
+    pub drift_threshold: f32,
+}
+
+impl Default for NormalizationCadence {
+    fn default() -> Self {
+        Self {
+            sum_interval: 1,
+            drift_threshold: 0.0,
+        }
+    }
+}
+
+/// Local carrying capacity: growth saturates logistically against a per-cell
+/// capacity K = base + per_resource · resource, instead of (or alongside)
+/// the global mass normalization. Mass above K decays toward it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CarryingCapacitySettings {
+    pub enabled: bool,
+    pub base: f32,                // capacity of a cell without resources
+    pub per_resource: f32,        // capacity added per unit of resource
+    pub keep_normalization: bool, // also run the global normalization passes
+}
+
+impl Default for CarryingCapacitySettings {
+    fn default() -> Self {
+        Self { enabled: false, base: 0.1, per_resource: 0.8, keep_normalization: false }
+    }
+}
+
+impl CarryingCapacitySettings {
+    /// Whether the capacity takes over from the global normalization, whose
+    /// passes are then skipped.
+    pub fn replaces_normalization(&self) -> bool {
+        self.enabled && !self.keep_normalization
+    }
+
+    /// Capacity of a cell holding `resource`, as computed in compute_evolution.wgsl.
+    pub fn capacity(&self, resource: f32) -> f32 {
+        (self.base + self.per_resource * resource).clamp(CAPACITY_MIN, 1.0)
+    }
+}
+
+/// Floor of the carrying capacity, so empty ground can still be crossed.
+pub const CAPACITY_MIN: f32 = 0.01;
+
+/// Distribution of the per-gene mutation steps. Each step is a deviate of
+/// the operator scaled by the cell's mutation rate, the global multiplier and
+/// the gene's own rate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutationOperator {
+    #[default]
+    Uniform,    // uniform in [-1, 1]
+    Gaussian,   // standard normal
+    Cauchy,     // heavy-tailed: mostly small steps, occasional large ones
+    Jump,       // no change, or rarely a discrete jump of ± jump_size
+    Correlated, // normal, with a share of one deviate common to all genes
+}
+
+impl MutationOperator {
+    pub fn all() -> &'static [MutationOperator] {
+        &[
+            MutationOperator::Uniform,
+            MutationOperator::Gaussian,
+            MutationOperator::Cauchy,
+            MutationOperator::Jump,
+            MutationOperator::Correlated,
+        ]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            MutationOperator::Uniform => "mutation.uniform",
+            MutationOperator::Gaussian => "mutation.gaussian",
+            MutationOperator::Cauchy => "mutation.cauchy",
+            MutationOperator::Jump => "mutation.jump",
+            MutationOperator::Correlated => "mutation.correlated",
+        }
+    }
+
+    /// Selector value understood by compute_evolution.wgsl.
+    pub fn shader_id(&self) -> u32 {
+        match self {
+            MutationOperator::Uniform => 0,
+            MutationOperator::Gaussian => 1,
+            MutationOperator::Cauchy => 2,
+            MutationOperator::Jump => 3,
+            MutationOperator::Correlated => 4,
+        }
+    }
+}
+
+/// Names of the mutable genes, in genome order (translation keys).
+pub const GENE_KEYS: [&str; 4] =
+    ["mutation.gene_radius", "mutation.gene_mu", "mutation.gene_sigma", "mutation.gene_aggressivity"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MutationSettings {
+    pub operator: MutationOperator,
+    pub gene_rates: [f32; 4], // per-gene step multiplier (r, mu, sigma, aggressivity); 0 freezes a gene
+    pub jump_size: f32,       // Jump: size of a jump in mean steps; jumps happen with probability 1 / jump_size
+    pub correlation: f32,     // Correlated: share of the variance common to all genes (0..1)
+}
+
+impl Default for MutationSettings {
+    fn default() -> Self {
+        Self {
+            operator: MutationOperator::Uniform,
+            gene_rates: [1.0; 4],
+            jump_size: 20.0,
+            correlation: 0.5,
+        }
+    }
+}
+
+/// Widest range of each gene, in genome order (the mutation step's clamps).
+pub const GENE_LIMITS: [(f32, f32); 4] = [GENOME_RADIUS_RANGE, GENOME_MU_RANGE, GENOME_SIGMA_RANGE, (0.0, 1.0)];
+
+/// Per-gene limits for controlled experiments: mutations keep each gene in
+/// [min, max], and a locked gene does not mutate at all.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneConstraints {
+    pub min: [f32; 4],
+    pub max: [f32; 4],
+    pub locked: [bool; 4],
+}
+
+impl Default for GeneConstraints {
+    fn default() -> Self {
+        Self {
+            min: GENE_LIMITS.map(|(lo, _)| lo),
+            max: GENE_LIMITS.map(|(_, hi)| hi),
+            locked: [false; 4],
+        }
+    }
+}
+
+impl GeneConstraints {
+    /// The bounds as uploaded to the shader: inside GENE_LIMITS, min ≤ max.
+    pub fn bounds(&self) -> ([f32; 4], [f32; 4]) {
+        let mut lo = [0.0; 4];
+        let mut hi = [0.0; 4];
+        for g in 0..4 {
+            let (limit_lo, limit_hi) = GENE_LIMITS[g];
+            lo[g] = self.min[g].clamp(limit_lo, limit_hi);
+            hi[g] = self.max[g].clamp(lo[g], limit_hi);
+        }
+        (lo, hi)
+    }
+
+    /// Locked genes as a bit mask (bit g = gene g).
+    pub fn lock_mask(&self) -> u32 {
+        self.locked.iter().enumerate().filter(|(_, &l)| l).map(|(g, _)| 1 << g).sum()
+    }
+}
+
+/// Serial-transfer protocol of the LTEE mode: every `interval` frames,
+/// freeze `sample_size` genomes to the run's archive and keep only a `keep`
+/// fraction of the living cells (1 = no bottleneck).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LteeSettings {
+    pub enabled: bool,
+    pub interval: u32,
+    pub sample_size: usize,
+    pub keep: f32,
+}
+
+impl Default for LteeSettings {
+    fn default() -> Self {
+        Self { enabled: false, interval: 5000, sample_size: 500, keep: 1.0 }
+    }
+}
+
+impl LteeSettings {
+    /// Whether a transfer falls in the frames (`before`, `after`].
+    pub fn due(&self, before: u32, after: u32) -> bool {
+        let k = self.interval.max(1);
+        self.enabled && after / k != before / k
+    }
+
+    pub fn bottleneck(&self) -> bool {
+        self.keep < 1.0
+    }
+}
+
+/// Speciation detection: a genome cluster that splits off an existing one is
+/// reported once both have lasted `persistence` metrics samples, each
+/// holding at least `min_share` of the living mass. `capture` also takes a
+/// screenshot and a snapshot at that moment.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeciationSettings {
+    pub enabled: bool,
+    pub persistence: u32,
+    pub min_share: f32,
+    pub capture: bool,
+}
+
+impl Default for SpeciationSettings {
+    fn default() -> Self {
+        Self { enabled: false, persistence: 5, min_share: 0.05, capture: true }
+    }
+}
+
+/// Extinction forensics: keep the last `rewind_depth` diagnostics snapshots
+/// so that, when the population (or, with `lineages`, a tracked lineage)
+/// dies out, a report with the last `history` metrics samples, the events
+/// since and the newest snapshot from before the loss can be written.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForensicsSettings {
+    pub enabled: bool,
+    pub history: usize,
+    pub rewind_depth: usize,
+    pub lineages: bool,
+}
+
+impl Default for ForensicsSettings {
+    fn default() -> Self {
+        Self { enabled: true, history: 50, rewind_depth: 2, lineages: true }
+    }
+}
+
+/// Time-averaged fields: running means of mass, resource and flow speed
+/// accumulated on the GPU every step. The mean covers all steps until
+/// `window` have been seen, then decays with a time constant of `window`
+/// steps (an exponential moving average).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldAverageSettings {
+    pub enabled: bool,
+    pub window: u32, // steps
+}
+
+impl Default for FieldAverageSettings {
+    fn default() -> Self {
+        Self { enabled: false, window: 1000 }
+    }
+}
+
+impl FieldAverageSettings {
+    /// Weight of the newest step when `samples` steps are already averaged.
+    pub fn weight(&self, samples: u32) -> f32 {
+        1.0 / (samples.saturating_add(1)).min(self.window.max(1)) as f32
+    }
+}
+
+/// Velocity field export: every `every`-th diagnostics sample, write the
+/// velocity buffer as a .npy array and/or a quiver plot PNG with one arrow
+/// per `stride` × `stride` block.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VelocityExportSettings {
+    pub enabled: bool,
+    pub every: u32,
+    pub npy: bool,
+    pub quiver: bool,
+    pub stride: u32, // pixels
+}
+
+impl Default for VelocityExportSettings {
+    fn default() -> Self {
+        Self { enabled: false, every: 10, npy: true, quiver: true, stride: 16 }
+    }
+}
+
+impl VelocityExportSettings {
+    /// Whether diagnostics sample number `sample` (1 for the first) exports.
+    pub fn due(&self, sample: u32) -> bool {
+        self.enabled && (self.npy || self.quiver) && sample.is_multiple_of(self.every.max(1))
+    }
+}
+
+/// Demographic stochasticity: growth is multiplied by 1 + amplitude · ξ / √m,
+/// ξ a standard normal drawn per cell and step from a seeded GPU hash. The
+/// same seed replays the same noise.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DemographicNoiseSettings {
+    pub enabled: bool,
+    pub amplitude: f32,
+    pub seed: u32,
+}
+
+impl Default for DemographicNoiseSettings {
+    fn default() -> Self {
+        Self { enabled: false, amplitude: 0.05, seed: 0 }
+    }
+}
+
+/// Range of `DemographicNoiseSettings::amplitude`.
+pub const NOISE_AMPLITUDE_MAX: f32 = 0.5;
+
+impl DemographicNoiseSettings {
+    /// Amplitude as uploaded to the shader: 0 when off.
+    pub fn effective_amplitude(&self) -> f32 {
+        if self.enabled {
+            self.amplitude.clamp(0.0, NOISE_AMPLITUDE_MAX)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Tone transform applied to the windowed display value, for fields with
+/// heavy-tailed distributions (e.g. mass after blooms).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayTransform {
+    #[default]
+    Linear,
+    Log,      // log1p compression: lifts sparse regions, keeps dense ones distinct
+    Equalize, // histogram equalization via a CPU-computed CDF lookup table
+}
+
+impl DisplayTransform {
+    pub fn all() -> &'static [DisplayTransform] {
+        &[
+            DisplayTransform::Linear,
+            DisplayTransform::Log,
+            DisplayTransform::Equalize,
+        ]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            DisplayTransform::Linear => "transform.linear",
+            DisplayTransform::Log => "transform.log",
+            DisplayTransform::Equalize => "transform.equalize",
+        }
+    }
+
+    /// Selector value understood by render.wgsl.
+    pub fn shader_id(&self) -> u32 {
+        match self {
+            DisplayTransform::Linear => 0,
+            DisplayTransform::Log => 1,
+            DisplayTransform::Equalize => 2,
+        }
+    }
+}
+
+/// A continuous perturbation delivers one full-intensity application over
+/// this many steps.
+pub const CONTINUOUS_PERTURBATION_STEPS: f32 = 60.0;
+
+/// Width in cells of each wall of a Barrier: wider than the largest kernel
+/// radius, so no convolution reaches across, and than any one-step advection.
+pub const BARRIER_WIDTH: f32 = KERNEL_MAX_R as f32 + 3.0;
+
+fn default_barrier_frames() -> u32 {
+    2000
 }
 
 /// Returns the display name for a given visualization mode index.
@@ -162,5 +1141,27 @@ pub fn visualization_mode_name(mode: u32) -> &'static str {
     }
 }
 
-/// Total number of visualization modes available.
+/// Translation key for a visualization mode's UI label.
+pub fn visualization_mode_key(mode: u32) -> &'static str {
+    match mode {
+        0 => "mode.species",
+        1 => "mode.energy",
+        2 => "mode.mass",
+        3 => "mode.diversity",
+        4 => "mode.predator_prey",
+        5 => "mode.stress",
+        6 => "mode.flux",
+        7 => "mode.trophic",
+        _ => "mode.user",
+    }
+}
+
+/// Number of built-in visualization modes; user modes (user_modes.rs)
+/// follow from this index.
 pub const VIS_MODE_COUNT: u32 = 8;
+
+/// Display adjustment slot of a mode. User modes see the display-adjusted
+/// mass, so they share the Mass Density slot.
+pub fn display_adjust_slot(mode: u32) -> usize {
+    if mode < VIS_MODE_COUNT { mode as usize } else { 2 }
+}
@@ -0,0 +1,173 @@
+// ============================================================================
+// crash.rs — EvoLenia v2 Research Lab
+// Crash reports: a panic hook writes <run>/crash_<time>.txt with the panic
+// message and backtrace, the GPU adapter, the world size, the current
+// parameters and the last lab events, so a bug report carries what is
+// needed to reproduce it. The context is kept in a process-wide slot that
+// the app refreshes at most once per second; the hook only reads it.
+// ============================================================================
+
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::config::SimulationParams;
+use crate::lab::LabEvent;
+
+/// Lab events included in a report (newest last).
+pub const CRASH_EVENTS: usize = 50;
+
+/// Minimum time between two refreshes of the parameters and events.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What is known about the process when a panic happens.
+#[derive(Clone, Debug, Default)]
+pub struct CrashContext {
+    pub adapter: Option<wgpu::AdapterInfo>,
+    pub world: [u32; 2],
+    pub run_dir: Option<PathBuf>, // reports go to the current directory without one
+    pub frame: u32,
+    pub params_json: Option<String>, // pretty JSON, None in headless runs
+    pub events: Vec<String>,         // LabEvent::to_log_line, oldest first
+    pub refreshed: Option<Instant>,  // last refresh of the parameters and events
+}
+
+impl CrashContext {
+    const fn empty() -> Self {
+        Self {
+            adapter: None,
+            world: [0, 0],
+            run_dir: None,
+            frame: 0,
+            params_json: None,
+            events: Vec::new(),
+            refreshed: None,
+        }
+    }
+
+    /// Record the frame, and the parameters and last events unless they
+    /// were recorded less than REFRESH_INTERVAL ago.
+    pub fn refresh(&mut self, now: Instant, frame: u32, params: &SimulationParams, events: &[LabEvent]) {
+        self.frame = frame;
+        if self.refreshed.is_some_and(|at| now.duration_since(at) < REFRESH_INTERVAL) {
+            return;
+        }
+        self.refreshed = Some(now);
+        self.params_json = serde_json::to_string_pretty(params).ok();
+        let first = events.len().saturating_sub(CRASH_EVENTS);
+        self.events = events[first..].iter().map(LabEvent::to_log_line).collect();
+    }
+
+    /// Report file for a panic at local time `time` (`YYYYmmdd_HHMMSS`).
+    pub fn report_path(&self, time: &str) -> PathBuf {
+        self.run_dir.as_deref().unwrap_or(Path::new(".")).join(format!("crash_{time}.txt"))
+    }
+
+    /// Plain-text report body.
+    pub fn format_report(&self, time: &str, message: &str, location: &str, backtrace: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# EvoLenia crash report — {time}");
+        let _ = writeln!(out, "version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(out, "panic: {message}");
+        let _ = writeln!(out, "location: {location}");
+        let _ = writeln!(out, "thread: {}", std::thread::current().name().unwrap_or("<unnamed>"));
+        let _ = writeln!(out, "\n## GPU");
+        match &self.adapter {
+            Some(info) => {
+                let _ = writeln!(out, "adapter: {} ({:?}, {:?})", info.name, info.device_type, info.backend);
+                let _ = writeln!(out, "vendor: {:#06x}  device: {:#06x}", info.vendor, info.device);
+                let _ = writeln!(out, "driver: {} {}", info.driver, info.driver_info);
+            }
+            None => {
+                let _ = writeln!(out, "adapter: not initialized");
+            }
+        }
+        let _ = writeln!(out, "\n## World");
+        let _ = writeln!(out, "size: {}x{}", self.world[0], self.world[1]);
+        let _ = writeln!(out, "frame: {}", self.frame);
+        match &self.run_dir {
+            Some(dir) => {
+                let _ = writeln!(out, "run: {}", dir.display());
+            }
+            None => {
+                let _ = writeln!(out, "run: none");
+            }
+        }
+        let _ = writeln!(out, "\n## Parameters");
+        let _ = writeln!(out, "{}", self.params_json.as_deref().unwrap_or("not recorded"));
+        let _ = writeln!(out, "\n## Last {} events", self.events.len());
+        for line in &self.events {
+            let _ = writeln!(out, "{line}");
+        }
+        let _ = writeln!(out, "\n## Backtrace\n{backtrace}");
+        out
+    }
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext::empty());
+
+fn with_context(f: impl FnOnce(&mut CrashContext)) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        f(&mut context);
+    }
+}
+
+/// Record the adapter and world size once the GPU is set up.
+pub fn set_gpu(info: wgpu::AdapterInfo, world: [u32; 2]) {
+    with_context(|c| {
+        c.adapter = Some(info);
+        c.world = world;
+    });
+}
+
+/// Record the size of a world swapped in by a resize.
+pub fn set_world(world: [u32; 2]) {
+    with_context(|c| c.world = world);
+}
+
+/// Directory reports are written to (the current run's).
+pub fn set_run_dir(run_dir: &Path) {
+    with_context(|c| c.run_dir = Some(run_dir.to_path_buf()));
+}
+
+/// Record the current frame (headless runs have no lab state).
+pub fn set_frame(frame: u32) {
+    with_context(|c| c.frame = frame);
+}
+
+/// See CrashContext::refresh; call once per frame.
+pub fn refresh(frame: u32, params: &SimulationParams, events: &[LabEvent]) {
+    with_context(|c| c.refresh(Instant::now(), frame, params, events));
+}
+
+/// Install the panic hook. The default hook still prints the panic first.
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        write_report(info);
+    }));
+}
+
+fn write_report(info: &PanicHookInfo) {
+    // The panic may have happened while the slot was held: never block here
+    let context = match CONTEXT.try_lock() {
+        Ok(context) => context.clone(),
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().clone(),
+        Err(TryLockError::WouldBlock) => CrashContext::default(),
+    };
+    let message = info.payload_as_str().unwrap_or("<non-string panic payload>");
+    let location = info.location().map_or_else(|| String::from("unknown"), |l| l.to_string());
+    let time = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let report = context.format_report(&time, message, &location, &Backtrace::force_capture().to_string());
+    // The logger may be the one that panicked: report on stderr only
+    let path = context.report_path(&time);
+    match path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&path, report)) {
+        Ok(()) => eprintln!("Crash report written to {}", path.display()),
+        Err(e) => eprintln!("Failed to write crash report {}: {}", path.display(), e),
+    }
+}
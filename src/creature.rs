@@ -0,0 +1,242 @@
+// ============================================================================
+// creature.rs — EvoLenia v2 Research Lab
+// Creature portraits: a blob picked on the world is followed for a short
+// sequence, each frame re-centered on its mass (a camera locked to the
+// creature), and encoded as a looping GIF in creatures/ next to a JSON file
+// recording its genome. Frames are drawn on the CPU in species colors like
+// run thumbnails, so the camera and visualization mode do not matter.
+// ============================================================================
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::display::auto_range;
+use crate::pattern::Pattern;
+use crate::theme::PaletteUniforms;
+use crate::thumbnail::{cell_color, srgb8};
+use crate::world::BufferSnapshot;
+
+/// Portrait gallery, relative to the working directory (like `patterns/`).
+pub const CREATURES_DIR: &str = "creatures";
+
+/// Side of the portrait in pixels; the followed window is upscaled to it.
+pub const PORTRAIT_SIZE: u32 = 192;
+
+const TRACK_MIN_MASS: f32 = 0.5 / 255.0; // as pattern export
+const TRACK_ITERATIONS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PortraitSettings {
+    pub frames: u32,   // frames in the loop
+    pub spacing: u32,  // simulation steps between frames
+    pub half: u32,     // half-size of the followed window, in cells
+    pub delay_ms: u32, // GIF frame delay
+}
+
+impl Default for PortraitSettings {
+    fn default() -> Self {
+        Self { frames: 48, spacing: 4, half: 32, delay_ms: 60 }
+    }
+}
+
+fn wrapped_index([width, height]: [u32; 2], x: i32, y: i32) -> usize {
+    let x = x.rem_euclid(width as i32) as usize;
+    let y = y.rem_euclid(height as i32) as usize;
+    y * width as usize + x
+}
+
+/// Mass centroid near `center` (cells, wrapping): a few mean-shift steps
+/// over the `2 * half + 1` square. None when the square holds no mass.
+pub fn track_blob(snap: &BufferSnapshot, center: [f32; 2], half: u32) -> Option<[f32; 2]> {
+    let half = half as i32;
+    let mut center = center;
+    for _ in 0..TRACK_ITERATIONS {
+        let (cx, cy) = (center[0].floor() as i32, center[1].floor() as i32);
+        let (mut sx, mut sy, mut total) = (0.0f64, 0.0f64, 0.0f64);
+        for dy in -half..=half {
+            for dx in -half..=half {
+                let m = snap.mass[wrapped_index(snap.size, cx + dx, cy + dy)];
+                if m > TRACK_MIN_MASS {
+                    sx += dx as f64 * m as f64;
+                    sy += dy as f64 * m as f64;
+                    total += m as f64;
+                }
+            }
+        }
+        if total == 0.0 {
+            return None;
+        }
+        let shift = [(sx / total) as f32, (sy / total) as f32];
+        center = [
+            (cx as f32 + 0.5 + shift[0]).rem_euclid(snap.size[0] as f32),
+            (cy as f32 + 0.5 + shift[1]).rem_euclid(snap.size[1] as f32),
+        ];
+        if shift[0].abs() < 0.5 && shift[1].abs() < 0.5 {
+            break;
+        }
+    }
+    Some(center)
+}
+
+/// Square sRGB RGBA8 frame, `size` pixels wide, of the `2 * half + 1` cells
+/// around `center` (nearest-neighbour upscale, wrapping).
+pub fn portrait_rgba(
+    snap: &BufferSnapshot,
+    center: [f32; 2],
+    half: u32,
+    size: u32,
+    range: (f32, f32),
+    palette: &PaletteUniforms,
+) -> Vec<u8> {
+    let side = 2 * half + 1;
+    let (cx, cy) = (center[0].floor() as i32, center[1].floor() as i32);
+    let mut rgba = Vec::with_capacity((size * size * 4) as usize);
+    for py in 0..size {
+        let dy = (py * side / size) as i32 - half as i32;
+        for px in 0..size {
+            let dx = (px * side / size) as i32 - half as i32;
+            let color = cell_color(snap, wrapped_index(snap.size, cx + dx, cy + dy), range, palette);
+            rgba.extend(color.map(srgb8));
+            rgba.push(255);
+        }
+    }
+    rgba
+}
+
+// ======================== Capture ========================
+
+/// Genome and provenance saved next to a portrait as `<name>.json`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CreatureInfo {
+    pub name: String,
+    pub created: String,
+    pub run_id: String,
+    pub start_frame: u32,
+    pub end_frame: u32,
+    pub frames: u32,
+    pub spacing: u32,
+    pub genome: [f32; 4], // r, mu, sigma, aggressivity (mass-weighted mean at the first frame)
+    pub mutation_rate: f32,
+    pub size: [usize; 2], // living extent in cells at the first frame
+}
+
+/// A portrait being captured, one frame every `spacing` steps.
+pub struct PortraitCapture {
+    pub settings: PortraitSettings,
+    pub info: CreatureInfo,
+    center: [f32; 2],
+    range: (f32, f32), // fixed at the first frame so the loop does not flicker
+    next_frame: u32,
+    frames: Vec<Vec<u8>>,
+}
+
+impl PortraitCapture {
+    /// Start on the blob nearest `center` (cells) and take the first frame.
+    /// None when there is no living cell around it.
+    pub fn start(
+        snap: &BufferSnapshot,
+        center: [f32; 2],
+        frame: u32,
+        run_id: &str,
+        settings: PortraitSettings,
+        palette: &PaletteUniforms,
+    ) -> Option<Self> {
+        let center = track_blob(snap, center, settings.half)?;
+        let now = Local::now();
+        let name = format!("creature_{}", now.format("%Y%m%d_%H%M%S"));
+        let pattern = Pattern::extract(snap, [center[0] as u32, center[1] as u32], settings.half, &name)?;
+        let info = CreatureInfo {
+            name,
+            created: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            run_id: run_id.to_string(),
+            start_frame: frame,
+            end_frame: frame,
+            frames: 0,
+            spacing: settings.spacing,
+            genome: pattern.genome,
+            mutation_rate: pattern.mutation_rate,
+            size: [pattern.width, pattern.height],
+        };
+        let range = (0.0, auto_range(&snap.mass, 1.0).1);
+        let mut capture = Self { settings, info, center, range, next_frame: frame, frames: Vec::new() };
+        capture.add_frame(snap, frame, palette).ok()?;
+        Some(capture)
+    }
+
+    pub fn due(&self, frame: u32) -> bool {
+        frame >= self.next_frame
+    }
+
+    pub fn captured(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.frames.len() >= self.settings.frames as usize
+    }
+
+    /// Follow the creature and draw the next frame. Err when it died or
+    /// left the followed window.
+    pub fn add_frame(&mut self, snap: &BufferSnapshot, frame: u32, palette: &PaletteUniforms) -> Result<(), String> {
+        self.center = track_blob(snap, self.center, self.settings.half)
+            .ok_or_else(|| String::from("creature lost: no living cells left around it"))?;
+        self.frames.push(portrait_rgba(snap, self.center, self.settings.half, PORTRAIT_SIZE, self.range, palette));
+        self.info.end_frame = frame;
+        self.info.frames = self.frames.len() as u32;
+        self.next_frame = frame + self.settings.spacing.max(1);
+        Ok(())
+    }
+
+    /// Write `<dir>/<name>.gif` (looping) and `<dir>/<name>.json`; returns
+    /// the GIF path. Runs on a worker thread.
+    pub fn save(self, dir: &Path, progress: impl Fn(f32)) -> Result<PathBuf, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let gif = dir.join(format!("{}.gif", self.info.name));
+        let file = fs::File::create(&gif).map_err(|e| format!("Failed to create {:?}: {}", gif, e))?;
+        let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), 10);
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("GIF: {}", e))?;
+        let delay = Delay::from_numer_denom_ms(self.settings.delay_ms, 1);
+        let count = self.frames.len();
+        for (i, rgba) in self.frames.into_iter().enumerate() {
+            let image = RgbaImage::from_raw(PORTRAIT_SIZE, PORTRAIT_SIZE, rgba)
+                .ok_or_else(|| String::from("GIF: frame size mismatch"))?;
+            encoder.encode_frame(Frame::from_parts(image, 0, 0, delay)).map_err(|e| format!("GIF: {}", e))?;
+            progress((i + 1) as f32 / count as f32);
+        }
+        let json = serde_json::to_string_pretty(&self.info).map_err(|e| format!("Failed to serialize: {}", e))?;
+        let info_path = gif.with_extension("json");
+        fs::write(&info_path, json).map_err(|e| format!("Failed to write {:?}: {}", info_path, e))?;
+        Ok(gif)
+    }
+}
+
+// ======================== Gallery ========================
+
+pub struct CreatureEntry {
+    pub gif: PathBuf,
+    pub info: CreatureInfo,
+}
+
+/// Portraits in `dir` (a GIF with its JSON), newest first.
+pub fn list_creatures(dir: &Path) -> Vec<CreatureEntry> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut creatures: Vec<CreatureEntry> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .filter_map(|json| {
+            let info = serde_json::from_str(&fs::read_to_string(&json).ok()?).ok()?;
+            let gif = json.with_extension("gif");
+            gif.is_file().then_some(CreatureEntry { gif, info })
+        })
+        .collect();
+    creatures.sort_by(|a, b| b.info.created.cmp(&a.info.created).then_with(|| b.info.name.cmp(&a.info.name)));
+    creatures
+}
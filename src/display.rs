@@ -0,0 +1,90 @@
+// ============================================================================
+// display.rs — EvoLenia v2
+// CPU-side display statistics feeding the render shader's gain/contrast
+// controls (auto-normalization of visualization ranges, histogram
+// equalization lookup tables).
+// ============================================================================
+
+use crate::config::DisplayAdjust;
+use crate::world::BufferSnapshot;
+
+/// Visualization mode whose primary field (flow speed) is not part of the
+/// CPU snapshot, so it cannot be auto-normalized.
+pub const ADVECTION_FLUX_MODE: u32 = 6;
+
+/// Values below this are treated as empty space when fitting ranges.
+pub(crate) const LIVE_THRESHOLD: f32 = 0.001;
+
+/// Number of entries in the histogram-equalization lookup table
+/// (must match EQ_BINS in render.wgsl).
+pub const EQUALIZE_BINS: usize = 256;
+
+/// Field that a visualization mode maps to display intensity, if it is
+/// available from a CPU snapshot.
+pub fn display_field(snap: &BufferSnapshot, mode: u32) -> Option<&[f32]> {
+    if mode == ADVECTION_FLUX_MODE {
+        None
+    } else {
+        Some(&snap.mass)
+    }
+}
+
+/// Fit a display range to the 1st–99th percentile of live values (after
+/// exposure), so a handful of saturated pixels cannot wash out the rest.
+/// Falls back to [0, 1] when nothing is alive.
+pub fn auto_range(values: &[f32], exposure: f32) -> (f32, f32) {
+    let mut live: Vec<f32> = values
+        .iter()
+        .filter(|v| **v > LIVE_THRESHOLD)
+        .map(|v| v * exposure)
+        .collect();
+    if live.is_empty() {
+        return (0.0, 1.0);
+    }
+
+    let lo_idx = (live.len() - 1) / 100;
+    let hi_idx = (live.len() - 1) * 99 / 100;
+    let (_, lo, _) = live.select_nth_unstable_by(lo_idx, f32::total_cmp);
+    let lo = *lo;
+    let (_, hi, _) = live.select_nth_unstable_by(hi_idx, f32::total_cmp);
+    let hi = *hi;
+
+    (lo, hi.max(lo + 1e-4))
+}
+
+/// Lookup table that leaves the windowed value unchanged.
+pub fn identity_lut() -> Vec<f32> {
+    (0..EQUALIZE_BINS)
+        .map(|i| i as f32 / (EQUALIZE_BINS - 1) as f32)
+        .collect()
+}
+
+/// Histogram-equalization table over the windowed display value
+/// `t = clamp((v * exposure - min) / (max - min), 0, 1)` of live pixels.
+/// Entry `i` is the normalized CDF of bin `i`, shifted so the lowest occupied
+/// bin maps to 0 (empty space stays dark). Falls back to identity when
+/// nothing is alive.
+pub fn equalization_lut(values: &[f32], adjust: &DisplayAdjust) -> Vec<f32> {
+    let span = (adjust.range_max - adjust.range_min).max(1e-6);
+    let last = (EQUALIZE_BINS - 1) as f32;
+    let mut hist = vec![0u32; EQUALIZE_BINS];
+    let mut total = 0u32;
+    for &v in values.iter().filter(|v| **v > LIVE_THRESHOLD) {
+        let t = ((v * adjust.exposure - adjust.range_min) / span).clamp(0.0, 1.0);
+        hist[(t * last) as usize] += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return identity_lut();
+    }
+
+    let cdf_min = hist.iter().copied().find(|c| *c > 0).unwrap_or(0);
+    let denom = (total - cdf_min).max(1) as f32;
+    let mut cum = 0u32;
+    hist.iter()
+        .map(|c| {
+            cum += c;
+            (cum.saturating_sub(cdf_min)) as f32 / denom
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+// ============================================================================
+// dock.rs — EvoLenia v2 Research Lab
+// Docking layout for the lab UI: panels are tabs that can be rearranged,
+// split, stacked, or floated. The simulation shows through the Viewport tab.
+// ============================================================================
+
+use egui::Rect;
+use egui_dock::{DockState, Node, NodeIndex, SurfaceIndex};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+/// Dockable lab panels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LabTab {
+    Viewport, // transparent: the simulation render shows through
+    Controls,
+    Analysis,
+    Logs,
+    Performance,
+    Resources,
+    Queue,
+    Report,
+}
+
+impl LabTab {
+    pub fn title(&self) -> &'static str {
+        match self {
+            LabTab::Viewport => tr("tab.viewport"),
+            LabTab::Controls => tr("tab.controls"),
+            LabTab::Analysis => tr("tab.analysis"),
+            LabTab::Logs => tr("tab.logs"),
+            LabTab::Performance => tr("tab.performance"),
+            LabTab::Resources => tr("tab.resources"),
+            LabTab::Queue => tr("tab.queue"),
+            LabTab::Report => tr("tab.report"),
+        }
+    }
+
+    /// The viewport and main controls cannot be closed; hide the whole UI
+    /// with F1 instead.
+    pub fn closeable(&self) -> bool {
+        matches!(self, LabTab::Analysis | LabTab::Logs | LabTab::Performance | LabTab::Resources | LabTab::Queue | LabTab::Report)
+    }
+}
+
+/// Initial arrangement: controls left, viewport center, logs below.
+/// The analysis (F9), performance and resources panels are added on demand.
+pub fn default_dock_state() -> DockState<LabTab> {
+    let mut dock = DockState::new(vec![LabTab::Viewport]);
+    let surface = dock.main_surface_mut();
+    let [viewport, _controls] = surface.split_left(NodeIndex::root(), 0.24, vec![LabTab::Controls]);
+    surface.split_below(viewport, 0.85, vec![LabTab::Logs]);
+    dock
+}
+
+/// Add or remove `tab` so its presence matches `wanted`. New tabs are docked
+/// beside the viewport (analysis and report to the right, others below).
+pub fn sync_tab(dock: &mut DockState<LabTab>, tab: LabTab, wanted: bool) {
+    match (dock.find_tab(&tab), wanted) {
+        (Some(location), false) => {
+            dock.remove_tab(location);
+        }
+        (None, true) => {
+            let anchor = match dock.find_tab(&LabTab::Viewport) {
+                Some((SurfaceIndex(0), node, _)) => node,
+                _ => NodeIndex::root(),
+            };
+            let surface = dock.main_surface_mut();
+            if matches!(tab, LabTab::Analysis | LabTab::Report) {
+                surface.split_right(anchor, 0.7, vec![tab]);
+            } else {
+                surface.split_below(anchor, 0.85, vec![tab]);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Copy of `dock` that survives a JSON round trip. Nodes that have not been
+/// laid out yet carry infinite rects, which serialize as `null` and fail to
+/// load; they are recomputed every frame, so zeroing them loses nothing.
+pub fn persistable(dock: &DockState<LabTab>) -> DockState<LabTab> {
+    fn sanitize(rect: &mut Rect) {
+        if !rect.is_finite() {
+            *rect = Rect::ZERO;
+        }
+    }
+
+    let mut dock = dock.clone();
+    for (_, node) in dock.iter_all_nodes_mut() {
+        match node {
+            Node::Leaf { rect, viewport, .. } => {
+                sanitize(rect);
+                sanitize(viewport);
+            }
+            Node::Vertical { rect, .. } | Node::Horizontal { rect, .. } => sanitize(rect),
+            Node::Empty => {}
+        }
+    }
+    dock
+}
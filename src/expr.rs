@@ -0,0 +1,286 @@
+// ============================================================================
+// expr.rs — EvoLenia v2 Research Lab
+// Custom metric expressions: arithmetic over the fields of a metrics record,
+// e.g. `predator_fraction * live_pixels` or `total_energy / total_mass`.
+// Field names are resolved when parsing, so an expression is checked once
+// and then evaluated per record against the record's value array.
+//
+// Grammar (usual precedence, `^` right-associative and above unary minus):
+//   expr  := term (('+' | '-') term)*
+//   term  := unary (('*' | '/') unary)*
+//   unary := '-' unary | power
+//   power := atom ('^' unary)?
+//   atom  := number | field | function '(' expr (',' expr)* ')' | '(' expr ')'
+// ============================================================================
+
+use crate::config::CustomMetric;
+use crate::lab::MetricsRecord;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Func {
+    Abs,
+    Sqrt,
+    Ln,
+    Log10,
+    Exp,
+    Min,
+    Max,
+}
+
+impl Func {
+    pub fn all() -> [Func; 7] {
+        [Func::Abs, Func::Sqrt, Func::Ln, Func::Log10, Func::Exp, Func::Min, Func::Max]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Func::Abs => "abs",
+            Func::Sqrt => "sqrt",
+            Func::Ln => "ln",
+            Func::Log10 => "log10",
+            Func::Exp => "exp",
+            Func::Min => "min",
+            Func::Max => "max",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Func::Min | Func::Max => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        match self {
+            Func::Abs => args[0].abs(),
+            Func::Sqrt => args[0].sqrt(),
+            Func::Ln => args[0].ln(),
+            Func::Log10 => args[0].log10(),
+            Func::Exp => args[0].exp(),
+            Func::Min => args[0].min(args[1]),
+            Func::Max => args[0].max(args[1]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// Parsed expression; `Field` indexes the value array given to `eval`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Field(usize),
+    Neg(Box<Expr>),
+    Bin(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+impl Expr {
+    /// Parse `source`, resolving identifiers against `fields`.
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Expr, String> {
+        let mut parser = Parser { chars: source.char_indices().collect(), pos: 0, fields };
+        let expr = parser.expr()?;
+        parser.skip_spaces();
+        match parser.peek() {
+            None => Ok(expr),
+            Some(c) => Err(format!("unexpected '{}' at {}", c, parser.offset())),
+        }
+    }
+
+    /// Value for one record (NaN or ±inf for undefined results, e.g. x / 0).
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        match self {
+            Expr::Num(x) => *x,
+            Expr::Field(i) => values.get(*i).copied().unwrap_or(f64::NAN),
+            Expr::Neg(e) => -e.eval(values),
+            Expr::Bin(op, a, b) => {
+                let (a, b) = (a.eval(values), b.eval(values));
+                match op {
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div => a / b,
+                    BinOp::Pow => a.powf(b),
+                }
+            }
+            Expr::Call(f, args) => {
+                let args: Vec<f64> = args.iter().map(|a| a.eval(values)).collect();
+                f.apply(&args)
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Vec<(usize, char)>,
+    pos: usize,
+    fields: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    /// Byte offset of the current character (for error messages).
+    fn offset(&self) -> usize {
+        self.chars.get(self.pos).map_or_else(|| self.chars.last().map_or(0, |&(i, c)| i + c.len_utf8()), |&(i, _)| i)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` (after spaces) if it is next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinOp::Add
+            } else if self.eat('-') {
+                BinOp::Sub
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Bin(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinOp::Mul
+            } else if self.eat('/') {
+                BinOp::Div
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Bin(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Bin(BinOp::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        self.skip_spaces();
+        let start = self.pos;
+        match self.peek() {
+            None => Err(String::from("unexpected end of expression")),
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                if !self.eat(')') {
+                    return Err(format!("expected ')' at {}", self.offset()));
+                }
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                // Exponent: 1e-3, 2.5E6
+                if self.peek().is_some_and(|c| c == 'e' || c == 'E') {
+                    let mark = self.pos;
+                    self.pos += 1;
+                    if self.peek().is_some_and(|c| c == '+' || c == '-') {
+                        self.pos += 1;
+                    }
+                    if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.pos = mark;
+                    }
+                    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.pos += 1;
+                    }
+                }
+                let text: String = self.chars[start..self.pos].iter().map(|&(_, c)| c).collect();
+                text.parse().map(Expr::Num).map_err(|_| format!("invalid number '{}'", text))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().map(|&(_, c)| c).collect();
+                if self.eat('(') {
+                    let func = Func::all()
+                        .into_iter()
+                        .find(|f| f.name() == name)
+                        .ok_or_else(|| format!("unknown function '{}'", name))?;
+                    let mut args = vec![self.expr()?];
+                    while self.eat(',') {
+                        args.push(self.expr()?);
+                    }
+                    if !self.eat(')') {
+                        return Err(format!("expected ')' at {}", self.offset()));
+                    }
+                    if args.len() != func.arity() {
+                        return Err(format!("{}() takes {} argument(s), got {}", name, func.arity(), args.len()));
+                    }
+                    return Ok(Expr::Call(func, args));
+                }
+                self.fields
+                    .iter()
+                    .position(|f| *f == name)
+                    .map(Expr::Field)
+                    .ok_or_else(|| format!("unknown field '{}'", name))
+            }
+            Some(c) => Err(format!("unexpected '{}' at {}", c, self.offset())),
+        }
+    }
+}
+
+/// A custom metric ready to evaluate.
+#[derive(Clone, Debug)]
+pub struct CompiledMetric {
+    pub name: String,
+    pub expr: Expr,
+}
+
+impl CompiledMetric {
+    pub fn eval(&self, record: &MetricsRecord) -> f64 {
+        self.expr.eval(&record.field_values())
+    }
+}
+
+/// Parse a definition against the metrics record fields.
+pub fn compile_metric(def: &CustomMetric) -> Result<CompiledMetric, String> {
+    if def.name.trim().is_empty() {
+        return Err(String::from("missing name"));
+    }
+    let expr = Expr::parse(&def.expression, &MetricsRecord::field_names())?;
+    Ok(CompiledMetric { name: def.name.trim().to_string(), expr })
+}
+
+/// Valid definitions, in order; invalid ones are skipped (the editor shows why).
+pub fn compile_metrics(defs: &[CustomMetric]) -> Vec<CompiledMetric> {
+    defs.iter().filter_map(|d| compile_metric(d).ok()).collect()
+}
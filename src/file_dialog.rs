@@ -0,0 +1,100 @@
+// ============================================================================
+// file_dialog.rs — EvoLenia v2 Research Lab
+// Native open/save/folder dialogs (rfd) for snapshots, presets, report
+// templates, LTEE fossil samples, simulation tapes and the run output
+// directory. Dialogs are modal and block the UI thread until closed,
+// which also pauses the simulation loop for that time. Each returns None
+// when the user cancels.
+// ============================================================================
+
+use std::path::{Path, PathBuf};
+
+use crate::i18n::tr;
+use crate::state_io::SNAPSHOT_EXTENSION;
+use crate::tape::TAPE_EXTENSION;
+
+/// Start in `dir` when it exists, else in the working directory.
+fn dialog_in(dir: &Path) -> rfd::FileDialog {
+    let dialog = rfd::FileDialog::new();
+    if dir.is_dir() {
+        dialog.set_directory(dir)
+    } else {
+        dialog
+    }
+}
+
+/// Pick a snapshot file to load.
+pub fn pick_snapshot(dir: &Path) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.load_snapshot"))
+        .add_filter(tr("dialog.snapshot_filter"), &[SNAPSHOT_EXTENSION])
+        .pick_file()
+}
+
+/// Choose where to save a snapshot; `.snap` is added when missing.
+pub fn save_snapshot(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.save_snapshot"))
+        .add_filter(tr("dialog.snapshot_filter"), &[SNAPSHOT_EXTENSION])
+        .set_file_name(file_name)
+        .save_file()
+        .map(|path| with_extension(path, SNAPSHOT_EXTENSION))
+}
+
+/// Pick a preset (JSON simulation parameters) to load.
+pub fn pick_preset(dir: &Path) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.load_preset"))
+        .add_filter(tr("dialog.preset_filter"), &["json"])
+        .pick_file()
+}
+
+/// Pick an archived LTEE genome sample (ltee.rs) to spawn.
+pub fn pick_fossil_sample(dir: &Path) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.load_fossil"))
+        .add_filter(tr("dialog.fossil_filter"), &["json"])
+        .pick_file()
+}
+
+/// Pick a report template (Handlebars markdown).
+pub fn pick_template(dir: &Path) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.load_template"))
+        .add_filter(tr("dialog.template_filter"), &["hbs", "md"])
+        .pick_file()
+}
+
+/// Choose where to save a copy of the built-in report template.
+pub fn save_template(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.save_template"))
+        .add_filter(tr("dialog.template_filter"), &["hbs"])
+        .set_file_name(file_name)
+        .save_file()
+}
+
+/// Pick a simulation tape (tape.rs) to play back.
+pub fn pick_tape(dir: &Path) -> Option<PathBuf> {
+    dialog_in(dir)
+        .set_title(tr("dialog.load_tape"))
+        .add_filter(tr("dialog.tape_filter"), &[TAPE_EXTENSION])
+        .pick_file()
+}
+
+/// Choose the directory new runs are written under.
+pub fn pick_directory(dir: &Path) -> Option<PathBuf> {
+    dialog_in(dir).set_title(tr("dialog.output_dir")).pick_folder()
+}
+
+/// `path` with `extension` appended unless it already has it.
+pub fn with_extension(path: PathBuf, extension: &str) -> PathBuf {
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case(extension)) {
+        path
+    } else {
+        let mut name = path.into_os_string();
+        name.push(".");
+        name.push(extension);
+        PathBuf::from(name)
+    }
+}
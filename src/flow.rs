@@ -0,0 +1,171 @@
+// ============================================================================
+// flow.rs — EvoLenia v2
+// Global flow field ("wind"): a vector per cell added to the organisms'
+// advection velocity and carrying the resource field along. The field is
+// authored on the CPU — generated from a preset, then touched up with a
+// brush — and uploaded whole; FlowSettings::strength scales it on the GPU,
+// so a field keeps its shape when the strength slider moves. Vectors are
+// kept at |v| ≤ 1.
+// ============================================================================
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::TAU;
+
+/// Sinusoids summed into the curl-noise stream function.
+const CURL_MODES: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlowPreset {
+    Uniform, // constant wind along the angle
+    Vortex,  // whirl around the world center, spiralling in/out with the angle
+    Shear,   // opposite currents in alternating bands
+    Curl,    // divergence-free turbulence (curl of a periodic noise)
+}
+
+impl FlowPreset {
+    pub fn all() -> &'static [FlowPreset] {
+        &[FlowPreset::Uniform, FlowPreset::Vortex, FlowPreset::Shear, FlowPreset::Curl]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            FlowPreset::Uniform => "flow.preset_uniform",
+            FlowPreset::Vortex => "flow.preset_vortex",
+            FlowPreset::Shear => "flow.preset_shear",
+            FlowPreset::Curl => "flow.preset_curl",
+        }
+    }
+}
+
+/// Row-major flow vectors of the whole world, in cells per step at strength 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowField {
+    pub data: Vec<[f32; 2]>,
+}
+
+impl FlowField {
+    /// Still field of a `size` world.
+    pub fn zeros([width, height]: [u32; 2]) -> Self {
+        Self { data: vec![[0.0; 2]; (width * height) as usize] }
+    }
+
+    /// Field of `preset`. `angle` (radians) orients the uniform wind and the
+    /// shear, and turns the vortex from a pure whirl (0) into a sink or
+    /// source (±90°); `seed` drives the curl noise.
+    pub fn generate(preset: FlowPreset, angle: f32, seed: u64, size: [u32; 2]) -> Self {
+        let (w, h) = (size[0] as f32, size[1] as f32);
+        let rotate = |v: [f32; 2]| {
+            let (s, c) = angle.sin_cos();
+            [v[0] * c - v[1] * s, v[0] * s + v[1] * c]
+        };
+        let modes = curl_modes(seed);
+        let mut field = Self::zeros(size);
+        for (i, v) in field.data.iter_mut().enumerate() {
+            let x = (i as u32 % size[0]) as f32 + 0.5;
+            let y = (i as u32 / size[0]) as f32 + 0.5;
+            *v = match preset {
+                FlowPreset::Uniform => rotate([1.0, 0.0]),
+                FlowPreset::Vortex => {
+                    // Tangential speed peaks at 1 on the ring r = R and
+                    // vanishes toward the edges of the torus
+                    let radius = w.min(h) / 6.0;
+                    let d = [(x - w * 0.5) / radius, (y - h * 0.5) / radius];
+                    let r2 = d[0] * d[0] + d[1] * d[1];
+                    let speed = (0.5 * (1.0 - r2)).exp();
+                    rotate([-d[1] * speed, d[0] * speed])
+                }
+                FlowPreset::Shear => rotate([(TAU * y / h).sin(), 0.0]),
+                FlowPreset::Curl => curl_at(&modes, x / w, y / h, size),
+            };
+        }
+        if preset == FlowPreset::Curl {
+            field.normalize();
+        }
+        field
+    }
+
+    pub fn max_speed(&self) -> f32 {
+        self.data.iter().map(|v| v[0].hypot(v[1])).fold(0.0, f32::max)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.data.iter().all(|v| *v == [0.0; 2])
+    }
+
+    /// Blend the field toward `dir` (clamped to unit length) under a soft
+    /// round brush centered at `center` (cells), wrapping around the torus
+    /// of a `size` world. A zero `dir` erases.
+    pub fn paint(&mut self, center: [f32; 2], dir: [f32; 2], radius: f32, size: [u32; 2]) {
+        let len = dir[0].hypot(dir[1]);
+        let dir = if len > 1.0 { [dir[0] / len, dir[1] / len] } else { dir };
+        let radius = radius.max(1.0);
+        let reach = radius.ceil() as i32;
+        let (w, h) = (size[0] as i32, size[1] as i32);
+        let (cx, cy) = (center[0].floor() as i32, center[1].floor() as i32);
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let d2 = ((dx * dx + dy * dy) as f32) / (radius * radius);
+                if d2 > 1.0 {
+                    continue;
+                }
+                let weight = (-4.0 * d2).exp();
+                let x = (cx + dx).rem_euclid(w);
+                let y = (cy + dy).rem_euclid(h);
+                let v = &mut self.data[(y * w + x) as usize];
+                v[0] += (dir[0] - v[0]) * weight;
+                v[1] += (dir[1] - v[1]) * weight;
+            }
+        }
+    }
+
+    /// Two values per cell (x, y), row-major — the snapshot channel layout.
+    pub fn flat(&self) -> Vec<f32> {
+        self.data.iter().flatten().copied().collect()
+    }
+
+    /// Inverse of `flat`; None unless it covers the whole `size` world.
+    pub fn from_flat(values: &[f32], [width, height]: [u32; 2]) -> Option<Self> {
+        if values.len() != (width * height) as usize * 2 {
+            return None;
+        }
+        Some(Self { data: values.chunks_exact(2).map(|v| [v[0], v[1]]).collect() })
+    }
+
+    /// Rescale so that the fastest cell moves at 1.
+    fn normalize(&mut self) {
+        let max = self.max_speed();
+        if max > 0.0 {
+            for v in &mut self.data {
+                v[0] /= max;
+                v[1] /= max;
+            }
+        }
+    }
+}
+
+/// Random periodic modes (kx, ky, phase, amplitude) of the stream function.
+fn curl_modes(seed: u64) -> Vec<[f32; 4]> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..CURL_MODES)
+        .map(|_| {
+            let kx = rng.gen_range(-3i32..=3) as f32;
+            let ky = rng.gen_range(1i32..=3) as f32;
+            [kx, ky, rng.gen_range(0.0..TAU), rng.gen_range(0.5..1.0)]
+        })
+        .collect()
+}
+
+/// Velocity (∂ψ/∂y, −∂ψ/∂x) of ψ = Σ a·sin(2π(kx·u + ky·v) + φ) at world
+/// UV (u, v) of a `size` world; integer wave numbers keep it seamless
+/// across the wrap.
+fn curl_at(modes: &[[f32; 4]], u: f32, v: f32, [width, height]: [u32; 2]) -> [f32; 2] {
+    let mut vel = [0.0; 2];
+    for &[kx, ky, phase, amp] in modes {
+        let c = amp * (TAU * (kx * u + ky * v) + phase).cos();
+        vel[0] += c * ky / height as f32;
+        vel[1] -= c * kx / width as f32;
+    }
+    vel
+}
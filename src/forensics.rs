@@ -0,0 +1,215 @@
+// ============================================================================
+// forensics.rs — EvoLenia v2 Research Lab
+// Extinction forensics: a short rewind buffer of diagnostics snapshots, and
+// the report folder written when the population or a tracked lineage dies
+// out — the last metrics samples, the events logged over that window, the
+// newest snapshot from before the loss (loadable, to replay the collapse)
+// and a README-style summary.
+// ============================================================================
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::conditions::Conditions;
+use crate::expr::CompiledMetric;
+use crate::lab::{write_metrics_csv, LabEvent, MetricsRecord};
+use crate::speciation::Lineage;
+use crate::state_io::{save_snapshot, SNAPSHOT_EXTENSION};
+use crate::world::BufferSnapshot;
+
+/// Rows of the diagnostics table in the summary (the CSV has them all).
+const SUMMARY_ROWS: usize = 10;
+
+// ======================== Rewind Buffer ========================
+
+/// The most recent diagnostics snapshots, oldest first.
+#[derive(Default)]
+pub struct RewindBuffer {
+    frames: VecDeque<(u32, BufferSnapshot)>,
+}
+
+impl RewindBuffer {
+    /// Keep `snap` (taken at `frame`), dropping the oldest beyond `depth`.
+    pub fn push(&mut self, frame: u32, snap: &BufferSnapshot, depth: usize) {
+        while self.frames.len() >= depth.max(1) {
+            self.frames.pop_front();
+        }
+        if depth > 0 {
+            self.frames.push_back((frame, snap.clone()));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Newest snapshot `keep` accepts (e.g. one that still has life).
+    pub fn latest_where(&self, keep: impl Fn(&BufferSnapshot) -> bool) -> Option<(u32, &BufferSnapshot)> {
+        self.frames.iter().rev().find(|(_, snap)| keep(snap)).map(|(frame, snap)| (*frame, snap))
+    }
+
+    /// CPU memory held by the buffer.
+    pub fn bytes(&self) -> u64 {
+        let floats = |s: &BufferSnapshot| s.mass.len() + s.energy.len() + s.genome_a.len() + s.genome_b.len() + s.resource.len() + s.flow.len();
+        self.frames.iter().map(|(_, s)| (floats(s) * 4) as u64).sum()
+    }
+}
+
+// ======================== Reports ========================
+
+/// What died out.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Extinction {
+    Population,
+    Lineage(Lineage),
+}
+
+impl Extinction {
+    /// Folder name suffix: `population` or `lineage_s4`.
+    pub fn slug(&self) -> String {
+        match self {
+            Extinction::Population => String::from("population"),
+            Extinction::Lineage(lineage) => format!("lineage_s{}", lineage.id),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Extinction::Population => String::from("the whole population"),
+            Extinction::Lineage(lineage) => format!("lineage {}", lineage.label()),
+        }
+    }
+}
+
+/// Report folder of an extinction detected at `frame`.
+pub fn report_dir(run_dir: &Path, frame: u32, what: &Extinction) -> PathBuf {
+    run_dir.join(format!("extinction_{frame:06}_{}", what.slug()))
+}
+
+/// Everything gathered for one report.
+pub struct ExtinctionReport<'a> {
+    pub what: Extinction,
+    pub frame: u32,
+    pub run_id: &'a str,
+    pub history: &'a [MetricsRecord], // the last samples, oldest first
+    pub custom: &'a [CompiledMetric],
+    pub conditions: &'a Conditions, // labels the diagnostics.csv samples
+    pub events: Vec<&'a LabEvent>, // logged since the first of `history`
+    pub snapshot: Option<(u32, &'a BufferSnapshot)>,
+}
+
+impl ExtinctionReport<'_> {
+    /// Write the report folder; returns the files written.
+    pub fn write(&self, dir: &Path) -> Result<Vec<PathBuf>, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let mut files = Vec::new();
+
+        let csv = dir.join("diagnostics.csv");
+        write_metrics_csv(&csv, self.history, self.custom, self.conditions)?;
+        files.push(csv);
+
+        let events = dir.join("events.jsonl");
+        let lines: String = self.events.iter().map(|e| format!("{}\n", e.to_json_record())).collect();
+        fs::write(&events, lines).map_err(|e| format!("Failed to write {:?}: {}", events, e))?;
+        files.push(events);
+
+        if let Some((frame, snap)) = self.snapshot {
+            let path = dir.join(format!("snapshot_frame{frame:06}.{SNAPSHOT_EXTENSION}"));
+            save_snapshot(&path.to_string_lossy(), snap).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+            files.push(path);
+        }
+
+        if let Extinction::Lineage(lineage) = &self.what {
+            let path = dir.join("lineage.json");
+            let json = serde_json::json!({
+                "id": lineage.id,
+                "label": lineage.label(),
+                "parent": lineage.parent,
+                "born": lineage.born,
+                "samples_tracked": lineage.age,
+                "last_centroid": lineage.centroid,
+                "last_share": lineage.share,
+            });
+            let text = serde_json::to_string_pretty(&json).map_err(|e| format!("Failed to serialize lineage: {}", e))?;
+            fs::write(&path, text).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+            files.push(path);
+        }
+
+        let summary = dir.join("README.md");
+        fs::write(&summary, self.summary(&files)).map_err(|e| format!("Failed to write {:?}: {}", summary, e))?;
+        files.push(summary);
+        Ok(files)
+    }
+
+    /// The folder's README: what died, the last samples, the events and the
+    /// files next to it.
+    pub fn summary(&self, files: &[PathBuf]) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# Extinction report: {}\n", self.what.describe());
+        let _ = writeln!(md, "Run `{}`, detected at frame {}.\n", self.run_id, self.frame);
+        if let Extinction::Lineage(lineage) = &self.what {
+            let [r, mu, sigma, agg] = lineage.centroid;
+            let _ = writeln!(
+                md,
+                "First seen at frame {}, tracked for {} samples; last centroid r={:.2} μ={:.4} σ={:.4} a={:.3} at {:.1}% of the living mass.\n",
+                lineage.born,
+                lineage.age,
+                r,
+                mu,
+                sigma,
+                agg,
+                lineage.share * 100.0
+            );
+        }
+
+        let _ = writeln!(md, "## Last diagnostics\n");
+        let _ = writeln!(md, "| frame | total mass | live pixels | species | energy | resource |");
+        let _ = writeln!(md, "|---:|---:|---:|---:|---:|---:|");
+        let start = self.history.len().saturating_sub(SUMMARY_ROWS);
+        for m in &self.history[start..] {
+            let _ = writeln!(
+                md,
+                "| {} | {:.1} | {} | {} | {:.3} | {:.3} |",
+                m.frame, m.total_mass, m.live_pixels, m.species, m.avg_energy, m.avg_resource
+            );
+        }
+
+        let since = self.history.first().map_or(self.frame, |m| m.frame);
+        let _ = writeln!(md, "\n## Events since frame {}\n", since);
+        if self.events.is_empty() {
+            let _ = writeln!(md, "None.");
+        }
+        for e in &self.events {
+            let _ = writeln!(md, "- frame {} `{}` {}", e.frame, e.event_type, e.details);
+        }
+
+        let _ = writeln!(md, "\n## Files\n");
+        for file in files {
+            let name = file.file_name().map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+            let what = match name.as_str() {
+                "diagnostics.csv" => format!("the last {} metrics samples", self.history.len()),
+                "events.jsonl" => String::from("the events above, as in the run's events.jsonl"),
+                "lineage.json" => String::from("the lost lineage"),
+                _ => match self.snapshot {
+                    Some((frame, _)) => format!("world state at frame {frame}, before the loss (Load snapshot to replay it)"),
+                    None => String::new(),
+                },
+            };
+            let _ = writeln!(md, "- `{}`: {}", name, what);
+        }
+        if self.snapshot.is_none() {
+            let _ = writeln!(md, "\nNo snapshot from before the loss was in the rewind buffer.");
+        }
+        md
+    }
+}
@@ -0,0 +1,102 @@
+// ============================================================================
+// gpu_debug.rs — EvoLenia v2 Research Lab
+// GPU validation toggle: with the option on (Run management, or
+// `--gpu-validation`), the wgpu instance is created with the validation and
+// debug layers, pipeline creation and per-frame submissions run inside
+// error scopes, and uncaptured errors are caught instead of aborting. Each
+// distinct error reaches the lab event log once (repeats are counted), so
+// a black screen comes with the first error that caused it rather than
+// thousands of identical lines on stderr.
+// ============================================================================
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Scope label for errors raised outside any error scope.
+pub const UNCAPTURED: &str = "uncaptured";
+
+/// Instance flags for the validation setting. WGPU_VALIDATION / WGPU_DEBUG
+/// in the environment still override them.
+pub fn instance_flags(validation: bool) -> wgpu::InstanceFlags {
+    let flags = if validation { wgpu::InstanceFlags::debugging() } else { wgpu::InstanceFlags::from_build_config() };
+    flags.with_env()
+}
+
+/// One GPU error, first occurrence of its kind.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GpuErrorReport {
+    pub scope: &'static str, // hot path that raised it, or UNCAPTURED
+    pub message: String,
+}
+
+/// Error scopes and uncaptured errors of one device.
+#[derive(Default)]
+pub struct GpuErrorLog {
+    pub enabled: bool,
+    pending: Arc<Mutex<Vec<GpuErrorReport>>>,
+    seen: HashMap<(&'static str, String), u32>, // occurrences per distinct error
+}
+
+impl GpuErrorLog {
+    /// Catch `device`'s uncaptured errors when enabled; otherwise wgpu's
+    /// default handler is kept.
+    pub fn new(device: &wgpu::Device, enabled: bool) -> Self {
+        let log = Self { enabled, ..Default::default() };
+        if enabled {
+            let pending = log.pending.clone();
+            device.on_uncaptured_error(Box::new(move |error| {
+                if let Ok(mut pending) = pending.lock() {
+                    pending.push(GpuErrorReport { scope: UNCAPTURED, message: error.to_string() });
+                }
+            }));
+        }
+        log
+    }
+
+    /// Run `f` inside validation and out-of-memory error scopes labelled
+    /// `scope`. Without validation, just run it.
+    pub fn scope<R>(&self, device: &wgpu::Device, scope: &'static str, f: impl FnOnce() -> R) -> R {
+        if !self.enabled {
+            return f();
+        }
+        device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = f();
+        for _ in 0..2 {
+            // Resolved immediately on native backends
+            if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+                self.record(scope, error.to_string());
+            }
+        }
+        result
+    }
+
+    /// Queue an error for the next `drain`.
+    pub fn record(&self, scope: &'static str, message: String) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.push(GpuErrorReport { scope, message });
+        }
+    }
+
+    /// Errors raised since the last call, first occurrences only.
+    pub fn drain(&mut self) -> Vec<GpuErrorReport> {
+        let pending = match self.pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return Vec::new(),
+        };
+        let mut fresh = Vec::new();
+        for report in pending {
+            let count = self.seen.entry((report.scope, report.message.clone())).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                fresh.push(report);
+            }
+        }
+        fresh
+    }
+
+    /// (distinct errors, total occurrences) so far.
+    pub fn counts(&self) -> (usize, u32) {
+        (self.seen.len(), self.seen.values().sum())
+    }
+}
@@ -1,12 +1,45 @@
 // ============================================================================
 // headless.rs — EvoLenia v2
-// Headless simulation runner for fast long-horizon batches.
+// Headless simulation runner for fast long-horizon batches, and the `bench`
+// subcommand comparing cell layouts of the per-pixel buffers. Headless runs
+// render no window: frames for the stream and for `--record` are drawn on
+// the CPU from a readback and written through the capture sinks.
 // ============================================================================
 
+use crate::capture::{open_frame_sink, FrameSink};
+use crate::crash;
+use crate::metrics::SimDiagnostics;
+use crate::monitor::MetricsServer;
 use crate::pipeline::{create_pipelines, Pipelines};
+use crate::popgen::export_population;
+use crate::resize;
 use crate::state_io;
-use crate::world::{total_pixels, WORKGROUP_X, WORKGROUP_Y, WorldState, WORLD_HEIGHT, WORLD_WIDTH};
-use std::time::Instant;
+use crate::stream::StreamServer;
+use crate::tensorboard::TensorBoardLog;
+use crate::theme::ColorPalette;
+use crate::thumbnail::{thumbnail_rgba, thumbnail_size};
+use crate::world::{
+    target_mass_of, CellLayout, DEFAULT_WORLD_SIZE, GridType, WORKGROUP_LINEAR, WORKGROUP_X, WORKGROUP_Y, WorldState,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How headless progress is reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Progress bar on stderr when it is a terminal, log lines otherwise.
+    #[default]
+    Auto,
+    /// Log lines only (`log::info!`, visible with RUST_LOG=info).
+    Log,
+    /// No progress output (`--quiet`).
+    Quiet,
+    /// One JSON object per line on stdout (`--json-progress`).
+    Json,
+}
 
 #[derive(Clone, Debug)]
 pub struct HeadlessConfig {
@@ -14,6 +47,20 @@ pub struct HeadlessConfig {
     pub load_state_path: Option<String>,
     pub save_state_path: Option<String>,
     pub progress_interval: u32,
+    pub progress_mode: ProgressMode,
+    pub tensorboard_dir: Option<String>, // `--tensorboard <logdir>`: diagnostics as scalars
+    pub metrics_addr: Option<String>,    // `--metrics-addr <host:port>`: Prometheus endpoint
+    pub diag_interval: u32,              // steps between diagnostics samples (TensorBoard, endpoint)
+    pub population_path: Option<String>, // `--population <file>`: live-cell genome table (.csv/.parquet)
+    pub population_frame: Option<u32>,   // frame of that export; None = end of run
+    pub grid: GridType,                  // `--grid <square|hex>`: lattice of the world
+    pub world_size: [u32; 2],            // `--world <WxH>`: width × height of the world
+    pub stream_addr: Option<String>,     // `--stream-addr <host:port>`: frames and metrics for `view`
+    pub stream_interval: u32,            // steps between streamed frames
+    pub stream_width: u32,               // width of streamed frames in pixels
+    pub record_path: Option<String>,     // `--record <dir|file.mp4>`: PNG sequence or video of the run
+    pub record_interval: u32,            // steps between recorded frames
+    pub record_width: u32,               // width of recorded frames in pixels
 }
 
 impl Default for HeadlessConfig {
@@ -23,11 +70,237 @@ impl Default for HeadlessConfig {
             load_state_path: None,
             save_state_path: None,
             progress_interval: 5000,
+            progress_mode: ProgressMode::Auto,
+            tensorboard_dir: None,
+            metrics_addr: None,
+            diag_interval: 300,
+            population_path: None,
+            population_frame: None,
+            grid: GridType::Square,
+            world_size: DEFAULT_WORLD_SIZE,
+            stream_addr: None,
+            stream_interval: 30,
+            stream_width: 256,
+            record_path: None,
+            record_interval: 30,
+            record_width: 512,
+        }
+    }
+}
+
+/// Frame rate of `--record` videos.
+const RECORD_FPS: f32 = 30.0;
+
+/// Result of a headless run that did not fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeadlessOutcome {
+    pub frames_done: u32,
+    pub interrupted: bool, // stopped early by Ctrl+C (state was still saved)
+}
+
+// ======================== Ctrl+C ========================
+
+static HEADLESS_ACTIVE: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Route Ctrl+C to a graceful stop while a headless run is active. A second
+/// Ctrl+C, or one outside a headless run, exits immediately as usual.
+fn install_interrupt_handler() {
+    let result = ctrlc::set_handler(|| {
+        if HEADLESS_ACTIVE.load(Ordering::SeqCst) && !INTERRUPTED.swap(true, Ordering::SeqCst) {
+            eprintln!("\nInterrupt received: finishing the current step and saving (Ctrl+C again to abort)");
+        } else {
+            std::process::exit(130);
+        }
+    });
+    if let Err(e) = result {
+        log::warn!("Could not install Ctrl+C handler: {}", e);
+    }
+}
+
+// ======================== Progress Reporting ========================
+
+/// Throughput and ETA at one point of a run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressSample {
+    pub done: u32,
+    pub total: u32,
+    pub elapsed_secs: f64,
+    pub steps_per_sec: f64,        // average since start
+    pub window_steps_per_sec: f64, // since the previous report
+}
+
+impl ProgressSample {
+    pub fn eta_secs(&self) -> f64 {
+        let remaining = self.total.saturating_sub(self.done) as f64;
+        if self.steps_per_sec > 1e-6 {
+            remaining / self.steps_per_sec
+        } else {
+            0.0
+        }
+    }
+
+    /// Machine-readable progress line for `--json-progress`.
+    pub fn json_line(&self, event: &str) -> String {
+        serde_json::json!({
+            "event": event,
+            "step": self.done,
+            "total": self.total,
+            "fraction": self.done as f64 / self.total.max(1) as f64,
+            "elapsed_secs": self.elapsed_secs,
+            "steps_per_sec": self.steps_per_sec,
+            "window_steps_per_sec": self.window_steps_per_sec,
+            "eta_secs": self.eta_secs(),
+        })
+        .to_string()
+    }
+}
+
+struct ProgressReporter {
+    mode: ProgressMode,
+    interval: u32,
+    total: u32,
+    started: Instant,
+    last_report: Instant,
+    last_report_frame: u32,
+    bar: Option<ProgressBar>,
+}
+
+impl ProgressReporter {
+    fn new(mode: ProgressMode, interval: u32, total: u32) -> Self {
+        let mode = match mode {
+            ProgressMode::Auto if std::io::stderr().is_terminal() => ProgressMode::Auto,
+            ProgressMode::Auto => ProgressMode::Log,
+            other => other,
+        };
+        let bar = (mode == ProgressMode::Auto).then(|| {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "{bar:40.cyan/blue} {percent:>3}% {pos}/{len} | {per_sec} | ETA {eta_precise}",
+                )
+                .unwrap_or_else(|_| ProgressStyle::default_bar()),
+            );
+            bar.enable_steady_tick(Duration::from_millis(250));
+            bar
+        });
+        let now = Instant::now();
+        Self { mode, interval, total, started: now, last_report: now, last_report_frame: 0, bar }
+    }
+
+    fn sample(&self, done: u32) -> ProgressSample {
+        let elapsed = self.started.elapsed().as_secs_f64().max(1e-6);
+        let window_elapsed = self.last_report.elapsed().as_secs_f64().max(1e-6);
+        ProgressSample {
+            done,
+            total: self.total,
+            elapsed_secs: elapsed,
+            steps_per_sec: done as f64 / elapsed,
+            window_steps_per_sec: (done - self.last_report_frame) as f64 / window_elapsed,
         }
     }
+
+    fn step(&mut self, done: u32) {
+        if let Some(bar) = &self.bar {
+            bar.set_position(done as u64);
+            return;
+        }
+        if self.interval == 0 || !done.is_multiple_of(self.interval) {
+            return;
+        }
+        let sample = self.sample(done);
+        match self.mode {
+            ProgressMode::Log => log::info!(
+                "Headless progress: {}/{} | fps={:.0} (window {:.0}) | ETA={:.1} min",
+                sample.done,
+                sample.total,
+                sample.steps_per_sec,
+                sample.window_steps_per_sec,
+                sample.eta_secs() / 60.0,
+            ),
+            ProgressMode::Json => println!("{}", sample.json_line("progress")),
+            ProgressMode::Auto | ProgressMode::Quiet => {}
+        }
+        self.last_report = Instant::now();
+        self.last_report_frame = done;
+    }
+
+    fn finish(&mut self, done: u32, summary: &HeadlessSummary) {
+        let sample = self.sample(done);
+        if let Some(bar) = self.bar.take() {
+            if summary.interrupted {
+                bar.abandon();
+            } else {
+                bar.finish();
+            }
+        }
+        match self.mode {
+            ProgressMode::Json => {
+                let mut line: serde_json::Value =
+                    serde_json::from_str(&sample.json_line("done")).unwrap_or_default();
+                line["interrupted"] = summary.interrupted.into();
+                line["snapshot"] = summary.snapshot_path.clone().into();
+                line["final"] = summary.final_metrics.clone();
+                println!("{}", line);
+            }
+            ProgressMode::Quiet => {}
+            _ => eprintln!("{}", summary.text(&sample)),
+        }
+    }
+}
+
+/// End-of-run summary (printed, and merged into the JSON "done" line).
+pub struct HeadlessSummary {
+    pub interrupted: bool,
+    pub snapshot_path: Option<String>,
+    pub final_metrics: serde_json::Value, // Null when no snapshot was read back
 }
 
-pub fn run_headless(config: &HeadlessConfig) -> Result<(), String> {
+impl HeadlessSummary {
+    pub fn text(&self, sample: &ProgressSample) -> String {
+        let mut text = format!(
+            "Headless run {}: {}/{} frames in {:.1} s ({:.0} fps)",
+            if self.interrupted { "interrupted" } else { "finished" },
+            sample.done,
+            sample.total,
+            sample.elapsed_secs,
+            sample.steps_per_sec,
+        );
+        if let Some(path) = &self.snapshot_path {
+            text += &format!("\n  Final state saved to {}", path);
+        }
+        let m = &self.final_metrics;
+        if !m.is_null() {
+            text += &format!(
+                "\n  Final metrics: mass={:.0} | live={} | species={} | entropy={:.2} bits | avg_energy={:.3}",
+                m["total_mass"].as_f64().unwrap_or(0.0),
+                m["live_pixels"],
+                m["species"],
+                m["entropy"].as_f64().unwrap_or(0.0),
+                m["avg_energy"].as_f64().unwrap_or(0.0),
+            );
+        }
+        text
+    }
+}
+
+/// Headline metrics of the final state.
+fn final_metrics_json(diag: &SimDiagnostics) -> serde_json::Value {
+    serde_json::json!({
+        "total_mass": diag.total_mass,
+        "live_pixels": diag.live_pixels,
+        "live_fraction": diag.live_fraction,
+        "avg_energy": diag.avg_energy,
+        "avg_resource": diag.avg_resource,
+        "entropy": diag.genetic_entropy,
+        "species": diag.species_count,
+        "predator_fraction": diag.genome_stats.predator_fraction,
+        "effective_diversity": diag.effective_diversity,
+    })
+}
+
+/// Device for a `size` world (recorded for crash reports).
+fn create_device(size: [u32; 2]) -> Result<(wgpu::Device, wgpu::Queue), String> {
     let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
         backends: wgpu::Backends::all(),
         ..Default::default()
@@ -39,8 +312,9 @@ pub fn run_headless(config: &HeadlessConfig) -> Result<(), String> {
         force_fallback_adapter: false,
     }))
     .ok_or_else(|| String::from("Failed to get GPU adapter for headless mode"))?;
+    crash::set_gpu(adapter.get_info(), size);
 
-    let (device, queue) = pollster::block_on(adapter.request_device(
+    pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: Some("evolenia_headless_device"),
             required_features: wgpu::Features::empty(),
@@ -53,92 +327,343 @@ pub fn run_headless(config: &HeadlessConfig) -> Result<(), String> {
         },
         None,
     ))
-    .map_err(|e| format!("Failed to create headless device: {e}"))?;
+    .map_err(|e| format!("Failed to create headless device: {e}"))
+}
 
-    let mut world = WorldState::new(&device);
+/// Encode and submit one simulation step.
+fn run_step(device: &wgpu::Device, queue: &wgpu::Queue, world: &mut WorldState, pipelines: &Pipelines) {
+    world.update_step_uniforms(queue);
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("headless_sim_encoder"),
+    });
+    encode_simulation_passes(
+        &mut encoder,
+        pipelines,
+        world.cur(),
+        world.size[0].div_ceil(WORKGROUP_X),
+        world.size[1].div_ceil(WORKGROUP_Y),
+        (world.size[0] * world.size[1]).div_ceil(WORKGROUP_LINEAR),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+    world.swap();
+}
+
+pub fn run_headless(config: &HeadlessConfig) -> Result<HeadlessOutcome, String> {
+    let [width, height] = config.world_size;
+    let (device, queue) = create_device(config.world_size)?;
+    resize::check_startup_size(config.world_size, config.grid, &device.limits())
+        .map_err(|e| format!("A {}x{} world does not fit this GPU: {}", width, height, e))?;
+
+    let mut world = WorldState::new_with_grid(&device, &queue, None, config.grid, config.world_size);
     if let Some(path) = &config.load_state_path {
         let snap = state_io::load_snapshot(path)
             .map_err(|e| format!("Failed to load state {}: {}", path, e))?;
-        if !world.apply_snapshot(&queue, &snap) {
+        if !world.apply_snapshot(&queue, &state_io::fit_snapshot(snap, world.size)) {
             return Err(format!("Loaded state {} has incompatible dimensions", path));
         }
     }
 
     let pipelines = create_pipelines(&device, &world, wgpu::TextureFormat::Rgba8Unorm);
 
-    let dispatch_x = (WORLD_WIDTH + WORKGROUP_X - 1) / WORKGROUP_X;
-    let dispatch_y = (WORLD_HEIGHT + WORKGROUP_Y - 1) / WORKGROUP_Y;
-    let dispatch_linear = (total_pixels() + 255) / 256;
-
     log::info!(
         "Headless run started: {} frames on {}x{}",
         config.frames,
-        WORLD_WIDTH,
-        WORLD_HEIGHT
+        width,
+        height
     );
 
-    let started = Instant::now();
-    let mut last_report = Instant::now();
-    let mut last_report_frame = 0u32;
+    let tensorboard = match &config.tensorboard_dir {
+        Some(dir) => Some(
+            TensorBoardLog::create(Path::new(dir))
+                .map_err(|e| format!("Failed to create TensorBoard log in {}: {}", dir, e))?,
+        ),
+        None => None,
+    };
+    let mut server = match &config.metrics_addr {
+        Some(addr) => {
+            let server = MetricsServer::start(addr, config.frames)
+                .map_err(|e| format!("Failed to start metrics endpoint on {}: {}", addr, e))?;
+            log::info!("Metrics endpoint listening on http://{}/metrics", server.addr());
+            Some(server)
+        }
+        None => None,
+    };
+    let mut stream = match &config.stream_addr {
+        Some(addr) => {
+            let stream = StreamServer::start(addr, world.size, config.frames)
+                .map_err(|e| format!("Failed to start frame stream on {}: {}", addr, e))?;
+            log::info!("Frame stream listening on {} (evolenia view {})", stream.addr(), stream.addr());
+            Some(stream)
+        }
+        None => None,
+    };
+    let mut record = match &config.record_path {
+        Some(path) => {
+            let sink = open_frame_sink(Path::new(path), RECORD_FPS)
+                .map_err(|e| format!("Failed to start recording to {}: {}", path, e))?;
+            log::info!("Recording every {} steps to {}", config.record_interval.max(1), path);
+            Some(sink)
+        }
+        None => None,
+    };
+    let diag_interval = config.diag_interval.max(1);
+    let stream_interval = config.stream_interval.max(1);
+    let record_interval = config.record_interval.max(1);
+
+    let mut progress = ProgressReporter::new(config.progress_mode, config.progress_interval, config.frames);
 
+    install_interrupt_handler();
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    HEADLESS_ACTIVE.store(true, Ordering::SeqCst);
+
+    let mut frames_done = 0;
     for step in 0..config.frames {
-        world.update_step_uniforms(&queue);
-        let cur = world.cur();
+        // Checked between steps, so the saved state is always a whole step
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            break;
+        }
+        if config.population_frame == Some(step) {
+            write_population(config, &device, &queue, &world);
+        }
+        run_step(&device, &queue, &mut world, &pipelines);
 
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("headless_sim_encoder"),
-        });
-        encode_simulation_passes(
-            &mut encoder,
-            &pipelines,
-            cur,
-            dispatch_x,
-            dispatch_y,
-            dispatch_linear,
-        );
-        queue.submit(std::iter::once(encoder.finish()));
-        world.swap();
+        frames_done = step + 1;
+        crash::set_frame(frames_done);
+        if let Some(server) = &mut server {
+            server.set_frame(frames_done);
+        }
+        if (tensorboard.is_some() || server.is_some() || stream.is_some()) && frames_done.is_multiple_of(diag_interval) {
+            publish_diagnostics(&device, &queue, &world, tensorboard.as_ref(), server.as_mut(), stream.as_mut());
+        }
+        if let Some(stream) = &mut stream {
+            stream.set_frame(frames_done);
+        }
+        // Stream frames are only drawn while someone is watching
+        let stream_due = stream.as_ref().is_some_and(|s| frames_done.is_multiple_of(stream_interval) && s.viewer_count() > 0);
+        let record_due = record.is_some() && frames_done.is_multiple_of(record_interval);
+        if stream_due || record_due {
+            let mut sinks: Vec<(&mut dyn FrameSink, u32)> = Vec::new();
+            if let Some(stream) = stream.as_mut().filter(|_| stream_due) {
+                sinks.push((stream, config.stream_width));
+            }
+            if let Some(record) = record.as_mut().filter(|_| record_due) {
+                sinks.push((record.as_mut(), config.record_width));
+            }
+            if let Err(e) = write_frames(&device, &queue, &world, &mut sinks) {
+                // A broken recording stops; the run carries on
+                log::warn!("Recording stopped at frame {}: {}", world.frame, e);
+                record = None;
+            }
+        }
+        progress.step(frames_done);
+    }
+    let interrupted = INTERRUPTED.load(Ordering::SeqCst);
+    if let Some(mut record) = record {
+        if let Err(e) = record.finish() {
+            log::warn!("Recording not finished: {}", e);
+        }
+    }
+    // At the end of the run when no frame was given, or it was not reached
+    if config.population_frame.is_none_or(|frame| frame >= frames_done) {
+        write_population(config, &device, &queue, &world);
+    }
 
-        if config.progress_interval > 0 && (step + 1) % config.progress_interval == 0 {
-            let done = step + 1;
-            let total_elapsed = started.elapsed().as_secs_f64().max(1e-6);
-            let total_fps = done as f64 / total_elapsed;
+    let mut summary = HeadlessSummary { interrupted, snapshot_path: None, final_metrics: serde_json::Value::Null };
+    let result = save_final_state(config, &device, &queue, &world, frames_done, &mut summary);
+    HEADLESS_ACTIVE.store(false, Ordering::SeqCst);
+    result?;
 
-            let window_elapsed = last_report.elapsed().as_secs_f64().max(1e-6);
-            let window_frames = done - last_report_frame;
-            let window_fps = window_frames as f64 / window_elapsed;
+    // Reported last so "done" means every output file is written
+    progress.finish(frames_done, &summary);
 
-            let remaining = config.frames.saturating_sub(done);
-            let eta_secs = if total_fps > 1e-6 {
-                remaining as f64 / total_fps
-            } else {
-                0.0
-            };
-            let eta_min = eta_secs / 60.0;
+    Ok(HeadlessOutcome { frames_done, interrupted })
+}
 
-            log::info!(
-                "Headless progress: {}/{} | fps={:.0} (window {:.0}) | ETA={:.1} min",
-                done,
-                config.frames,
-                total_fps,
-                window_fps,
-                eta_min,
-            );
+/// Export the live-cell genome table if `--population` was given. Failures
+/// are logged and do not stop the run.
+fn write_population(config: &HeadlessConfig, device: &wgpu::Device, queue: &wgpu::Queue, world: &WorldState) {
+    let Some(path) = &config.population_path else {
+        return;
+    };
+    let Some(snapshot) = world.readback_snapshot(device, queue) else {
+        log::warn!("GPU readback failed, skipping population export at frame {}", world.frame);
+        return;
+    };
+    match export_population(&snapshot, world.size[0], Path::new(path)) {
+        Ok(count) => log::info!("Exported {} live cells at frame {} to {}", count, world.frame, path),
+        Err(e) => log::warn!("Population export failed: {}", e),
+    }
+}
+
+/// Read back the world once and draw it as a species-color frame of each
+/// sink's width. A readback failure is logged and skips the frame; a sink
+/// error is returned.
+fn write_frames(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    world: &WorldState,
+    sinks: &mut [(&mut dyn FrameSink, u32)],
+) -> Result<(), String> {
+    let Some(snapshot) = world.readback_snapshot(device, queue) else {
+        log::warn!("GPU readback failed, skipping frame {}", world.frame);
+        return Ok(());
+    };
+    let palette = ColorPalette::default().uniforms();
+    for (sink, width) in sinks.iter_mut() {
+        let (width, height) = thumbnail_size(*width, snapshot.size);
+        let rgba = thumbnail_rgba(&snapshot, width, &palette);
+        sink.write_frame(world.frame, &rgba, width, height)?;
+    }
+    Ok(())
+}
+
+/// Read back the world and publish its diagnostics as TensorBoard scalars,
+/// on the metrics endpoint and to stream viewers. Failures are logged and
+/// do not stop the run.
+fn publish_diagnostics(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    world: &WorldState,
+    tensorboard: Option<&TensorBoardLog>,
+    server: Option<&mut MetricsServer>,
+    stream: Option<&mut StreamServer>,
+) {
+    let Some(snapshot) = world.readback_snapshot(device, queue) else {
+        log::warn!("GPU readback failed, skipping diagnostics sample at frame {}", world.frame);
+        return;
+    };
+    let scalars = SimDiagnostics::from_snapshot(&snapshot).named_scalars();
+    if let Some(log) = tensorboard {
+        if let Err(e) = log.scalars(world.frame, &scalars) {
+            log::warn!("TensorBoard write failed: {}", e);
+        }
+    }
+    if let Some(stream) = stream {
+        stream.set_diagnostics(world.frame, &scalars);
+    }
+    if let Some(server) = server {
+        server.set_diagnostics(world.frame, scalars);
+    }
+}
+
+// ======================== Layout Benchmark ========================
+
+/// Steps run before timing, so pipeline compilation and the initial bloom
+/// do not skew the first layout.
+const BENCH_WARMUP_STEPS: u32 = 50;
+/// Every layout starts from the same world.
+const BENCH_SEED: u64 = 42;
+
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+    pub frames: u32,
+    pub layouts: Vec<CellLayout>,
+    pub world_size: [u32; 2],
+}
+
+/// Throughput of one layout over the timed steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchResult {
+    pub layout: CellLayout,
+    pub frames: u32,
+    pub elapsed_secs: f64,
+}
+
+impl BenchResult {
+    pub fn steps_per_sec(&self) -> f64 {
+        self.frames as f64 / self.elapsed_secs.max(1e-9)
+    }
 
-            last_report = Instant::now();
-            last_report_frame = done;
+    pub fn ms_per_step(&self) -> f64 {
+        self.elapsed_secs * 1000.0 / self.frames.max(1) as f64
+    }
+}
+
+/// Run the same seeded world for `frames` steps in each layout and time it
+/// (wall clock, GPU idle at both ends).
+pub fn run_bench(config: &BenchConfig) -> Result<Vec<BenchResult>, String> {
+    let [width, height] = config.world_size;
+    let (device, queue) = create_device(config.world_size)?;
+    let mut results = Vec::new();
+    for &layout in &config.layouts {
+        if !layout.supported(width, height) {
+            return Err(format!(
+                "Layout {} needs a square power-of-two world (world is {}x{})",
+                layout.name(),
+                width,
+                height
+            ));
+        }
+        let mut world = WorldState::new_with_layout(&device, &queue, Some(BENCH_SEED), layout, config.world_size);
+        let pipelines = create_pipelines(&device, &world, wgpu::TextureFormat::Rgba8Unorm);
+        for _ in 0..BENCH_WARMUP_STEPS {
+            run_step(&device, &queue, &mut world, &pipelines);
         }
+        device.poll(wgpu::Maintain::Wait);
+
+        let started = Instant::now();
+        for _ in 0..config.frames {
+            run_step(&device, &queue, &mut world, &pipelines);
+        }
+        device.poll(wgpu::Maintain::Wait);
+        let result = BenchResult { layout, frames: config.frames, elapsed_secs: started.elapsed().as_secs_f64() };
+        log::info!("Bench {}: {:.3} ms/step", layout.name(), result.ms_per_step());
+        results.push(result);
     }
+    Ok(results)
+}
 
-    if let Some(path) = &config.save_state_path {
-        let snapshot = world
-            .readback_snapshot(&device, &queue)
-            .ok_or_else(|| String::from("GPU readback failed at end of headless run"))?;
-        state_io::save_snapshot(path, &snapshot)
-            .map_err(|e| format!("Failed to save snapshot {}: {}", path, e))?;
-        log::info!("Saved final state to {}", path);
+/// Result table of a `size` world, with each layout's speedup relative to
+/// the first one.
+pub fn bench_report(results: &[BenchResult], [width, height]: [u32; 2]) -> String {
+    let mut text = format!("Layout benchmark on {}x{}:", width, height);
+    let baseline = results.first().map(|r| r.ms_per_step());
+    for r in results {
+        text += &format!(
+            "\n  {:<10} {:>8} steps in {:>7.2} s | {:>8.1} steps/s | {:.3} ms/step",
+            r.layout.name(),
+            r.frames,
+            r.elapsed_secs,
+            r.steps_per_sec(),
+            r.ms_per_step(),
+        );
+        if let Some(base) = baseline.filter(|_| r.layout != results[0].layout) {
+            text += &format!(" | {:+.1}% vs {}", (base / r.ms_per_step() - 1.0) * 100.0, results[0].layout.name());
+        }
     }
+    text
+}
 
+fn save_final_state(
+    config: &HeadlessConfig,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    world: &WorldState,
+    frames_done: u32,
+    summary: &mut HeadlessSummary,
+) -> Result<(), String> {
+    let Some(path) = &config.save_state_path else { return Ok(()) };
+    let snapshot = world
+        .readback_snapshot(device, queue)
+        .ok_or_else(|| String::from("GPU readback failed at end of headless run"))?;
+    state_io::save_snapshot(path, &snapshot)
+        .map_err(|e| format!("Failed to save snapshot {}: {}", path, e))?;
+    log::info!("Saved final state to {}", path);
+
+    // Final metrics next to the snapshot, so interrupted runs are still usable
+    let diag = SimDiagnostics::from_snapshot(&snapshot);
+    diag.log(world.frame, target_mass_of(world.size), None);
+    summary.snapshot_path = Some(path.clone());
+    summary.final_metrics = final_metrics_json(&diag);
+    let metrics_path = format!("{}.metrics.json", path);
+    let metrics = serde_json::json!({
+        "frames_done": frames_done,
+        "frames_requested": config.frames,
+        "interrupted": summary.interrupted,
+        "metrics": summary.final_metrics,
+    });
+    let json = serde_json::to_string_pretty(&metrics).map_err(|e| format!("Failed to serialize metrics: {}", e))?;
+    std::fs::write(&metrics_path, json).map_err(|e| format!("Failed to write {}: {}", metrics_path, e))?;
     Ok(())
 }
 
@@ -150,16 +675,7 @@ fn encode_simulation_passes(
     dispatch_y: u32,
     dispatch_linear: u32,
 ) {
-    {
-        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some("velocity_pass"),
-            timestamp_writes: None,
-        });
-        pass.set_pipeline(&pipelines.velocity_pipeline);
-        pass.set_bind_group(0, &pipelines.velocity_bind_groups[cur], &[]);
-        pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
-    }
-
+    // Velocities are fused into the evolution pass (update_step_uniforms)
     {
         let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("evolution_pass"),
@@ -0,0 +1,98 @@
+// ============================================================================
+// histogram.rs — EvoLenia v2
+// Live histogram of the visualized field for the viewport overlay: a GPU
+// pass (compute_histogram.wgsl) bins mass, energy or resource at every
+// diagnostics sample, and the overlay draws the counts with the display
+// window of the active mode, to help pick ranges and spot saturation.
+// ============================================================================
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::config::{DisplayAdjust, HistogramField};
+use crate::display::LIVE_THRESHOLD;
+
+/// Bins of the histogram (size of the GPU counter buffer).
+pub const HISTOGRAM_BINS: u32 = 64;
+
+/// Uniform of the histogram pass.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct HistogramParams {
+    pub field: u32,
+    pub bins: u32,
+    pub max_value: f32,
+    pub live_mass: f32,
+}
+
+impl HistogramParams {
+    pub fn new(field: HistogramField, max_value: f32) -> Self {
+        Self {
+            field: field.shader_id(),
+            bins: HISTOGRAM_BINS,
+            max_value,
+            live_mass: LIVE_THRESHOLD,
+        }
+    }
+}
+
+/// Upper edge of the binned range: energy and resource live in [0, 1];
+/// mass covers at least the display window of the active mode, so the last
+/// bin only collects values the display saturates.
+pub fn max_value(field: HistogramField, adjust: &DisplayAdjust) -> f32 {
+    match field {
+        HistogramField::Auto | HistogramField::Mass => (adjust.range_max / adjust.exposure.max(1e-6)).max(1.0),
+        HistogramField::Energy | HistogramField::Resource => 1.0,
+    }
+}
+
+/// One histogram readback.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldHistogram {
+    pub field: HistogramField, // resolved: never Auto
+    pub counts: Vec<u32>,      // HISTOGRAM_BINS bins over [0, max_value]
+    pub max_value: f32,
+    pub frame: u32,
+}
+
+impl FieldHistogram {
+    /// Cells counted.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&c| c as u64).sum()
+    }
+
+    /// Width of one bin.
+    pub fn bin_width(&self) -> f32 {
+        self.max_value / self.counts.len().max(1) as f32
+    }
+
+    /// Share of the counted cells in the last bin (at or near max_value).
+    pub fn saturated_share(&self) -> f32 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        self.counts.last().copied().unwrap_or(0) as f32 / total as f32
+    }
+
+    /// Share of the counted cells in bins starting at or above `value`.
+    pub fn share_above(&self, value: f32) -> f32 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        let first = (value / self.bin_width()).ceil().max(0.0) as usize;
+        let above: u64 = self.counts.iter().skip(first).map(|&c| c as u64).sum();
+        above as f32 / total as f32
+    }
+
+    /// Bar heights in [0, 1], relative to the tallest bin (log counts when
+    /// `log_scale`, so sparse tails stay visible).
+    pub fn heights(&self, log_scale: bool) -> Vec<f32> {
+        let scale = |c: u32| if log_scale { (c as f32).ln_1p() } else { c as f32 };
+        let top = self.counts.iter().map(|&c| scale(c)).fold(0.0f32, f32::max);
+        if top <= 0.0 {
+            return vec![0.0; self.counts.len()];
+        }
+        self.counts.iter().map(|&c| scale(c) / top).collect()
+    }
+}
@@ -0,0 +1,102 @@
+// ============================================================================
+// i18n.rs — EvoLenia v2
+// UI localization. Strings live in flat JSON bundles under locales/ (one per
+// language, embedded at build time) and are looked up by key at runtime.
+// Missing translations fall back to English, then to the key itself.
+// ============================================================================
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub fn all() -> &'static [Language] {
+        &[Language::English, Language::French]
+    }
+
+    /// Native name, shown untranslated in the language selector.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "Français",
+        }
+    }
+
+    /// Raw JSON bundle for this language.
+    pub fn bundle_source(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.json"),
+            Language::French => include_str!("../locales/fr.json"),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Language::English => 0,
+            Language::French => 1,
+        }
+    }
+}
+
+// ======================== Active Language ========================
+
+// Global so HUD and status messages can translate without threading state.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(lang: Language) {
+    CURRENT.store(lang.index() as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    Language::all()[CURRENT.load(Ordering::Relaxed) as usize]
+}
+
+// ======================== Lookup ========================
+
+/// Parse a bundle into key → text.
+pub fn parse_bundle(source: &str) -> Result<HashMap<String, String>, String> {
+    serde_json::from_str(source).map_err(|e| format!("Invalid translation bundle: {}", e))
+}
+
+fn bundle(lang: Language) -> &'static HashMap<String, String> {
+    static BUNDLES: [OnceLock<HashMap<String, String>>; 2] = [OnceLock::new(), OnceLock::new()];
+    BUNDLES[lang.index()].get_or_init(|| {
+        parse_bundle(lang.bundle_source()).unwrap_or_else(|e| {
+            log::error!("{} ({:?})", e, lang);
+            HashMap::new()
+        })
+    })
+}
+
+/// Translated text for `key` in the active language.
+pub fn tr(key: &'static str) -> &'static str {
+    bundle(language())
+        .get(key)
+        .or_else(|| bundle(Language::English).get(key))
+        .map_or(key, |s| s.as_str())
+}
+
+/// Translated text with `{name}` placeholders substituted, e.g.
+/// `trf("status.exported", &[("path", &path.display())])`.
+pub fn trf(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    substitute(tr(key), args)
+}
+
+/// `text` with its `{name}` placeholders replaced by `args`.
+pub fn substitute(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = text.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
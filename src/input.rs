@@ -1,6 +1,7 @@
 // ============================================================================
 // input.rs — EvoLenia v2
-// Keyboard state tracking for continuous held-key actions.
+// Keyboard and mouse state tracking for continuous held-key actions and
+// pointer-driven world tools.
 // ============================================================================
 
 /// Tracks which navigation keys are currently held down.
@@ -13,3 +14,12 @@ pub struct KeysHeld {
     pub q: bool,
     pub e: bool,
 }
+
+/// Tracks the cursor position (physical pixels) and active drag gestures.
+#[derive(Default)]
+pub struct MouseState {
+    pub position: [f32; 2],
+    pub dragging_profile: bool,
+    pub flow_stroke: Option<[f32; 2]>, // last brushed cell while painting the flow field
+    pub orbit_drag: Option<[f32; 2]>, // last cursor position while orbiting the 3D view
+}
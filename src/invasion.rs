@@ -0,0 +1,300 @@
+// ============================================================================
+// invasion.rs — EvoLenia v2 Research Lab
+// Invasion assay: can a rare mutant spread in the current community? The
+// world is cloned onto a background world sharing the GPU, a small random
+// fraction of its living cells get the mutant genome, and the clone runs for
+// a set number of steps while the mutant's share of the living mass is
+// sampled. A share that grows means the mutant invades. The visible world is
+// left untouched; the samples go to a CSV in the run directory.
+// ============================================================================
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::app::step_world;
+use crate::config::SimulationParams;
+use crate::metrics::genome_distance;
+use crate::pipeline::create_pipelines;
+use crate::world::{BufferSnapshot, WorldState};
+
+/// Cells lighter than this are not alive (not counted, never converted).
+const LIVING_MASS: f32 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvasionSettings {
+    pub mutant: [f32; 4],    // genome (r, mu, sigma, aggressivity)
+    pub mutation_rate: f32,  // the mutant's own mutation rate (genome_b)
+    pub density: f32,        // fraction of living cells converted to the mutant
+    pub tolerance: f32,      // genome distance still counted as the mutant
+    pub steps: u32,
+    pub sample_interval: u32,
+    pub seed: u64,           // which cells are converted
+}
+
+impl Default for InvasionSettings {
+    fn default() -> Self {
+        Self {
+            mutant: [8.0, 0.15, 0.03, 0.5],
+            mutation_rate: 0.003,
+            density: 0.01,
+            tolerance: 0.05,
+            steps: 2000,
+            sample_interval: 100,
+            seed: 1,
+        }
+    }
+}
+
+impl InvasionSettings {
+    fn is_mutant(&self, genome: &[f32]) -> bool {
+        let tuple = |g: &[f32]| (g[0], g[1], g[2], g[3]);
+        genome_distance(tuple(genome), tuple(&self.mutant)) <= self.tolerance
+    }
+}
+
+/// Give the mutant genome to a random `density` fraction of the living
+/// cells of `snap`. Returns how many were converted.
+pub fn inject_mutant(snap: &mut BufferSnapshot, settings: &InvasionSettings) -> usize {
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let mut injected = 0;
+    for i in 0..snap.mass.len() {
+        if snap.mass[i] >= LIVING_MASS && rng.gen::<f32>() < settings.density {
+            snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&settings.mutant);
+            snap.genome_b[i] = settings.mutation_rate;
+            injected += 1;
+        }
+    }
+    injected
+}
+
+/// Mutant mass and total living mass at one step of the assay.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InvasionSample {
+    pub step: u32,
+    pub mutant_mass: f32,
+    pub total_mass: f32,
+}
+
+impl InvasionSample {
+    pub fn measure(snap: &BufferSnapshot, settings: &InvasionSettings, step: u32) -> Self {
+        let mut sample = InvasionSample { step, ..Default::default() };
+        for (i, &m) in snap.mass.iter().enumerate() {
+            if m < LIVING_MASS {
+                continue;
+            }
+            sample.total_mass += m;
+            if settings.is_mutant(&snap.genome_a[i * 4..i * 4 + 4]) {
+                sample.mutant_mass += m;
+            }
+        }
+        sample
+    }
+
+    /// Mutant share of the living mass.
+    pub fn share(&self) -> f32 {
+        if self.total_mass > 0.0 {
+            self.mutant_mass / self.total_mass
+        } else {
+            0.0
+        }
+    }
+}
+
+/// How the mutant fared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvasionOutcome {
+    Invades,  // its share grew
+    Declines, // its share shrank but it is still there
+    Lost,     // no mutant mass left
+}
+
+impl InvasionOutcome {
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            InvasionOutcome::Invades => "invasion.invades",
+            InvasionOutcome::Declines => "invasion.declines",
+            InvasionOutcome::Lost => "invasion.lost",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvasionResult {
+    pub settings: InvasionSettings,
+    pub frame: u32, // frame of the visible world that was cloned
+    pub injected: usize,
+    pub samples: Vec<InvasionSample>, // the first at step 0, right after injection
+    pub cancelled: bool,
+}
+
+impl InvasionResult {
+    pub fn outcome(&self) -> Option<InvasionOutcome> {
+        let (first, last) = (self.samples.first()?, self.samples.last()?);
+        Some(if last.mutant_mass <= 0.0 {
+            InvasionOutcome::Lost
+        } else if last.share() > first.share() {
+            InvasionOutcome::Invades
+        } else {
+            InvasionOutcome::Declines
+        })
+    }
+
+    /// Invasion fitness: growth rate of the mutant's log share, per 1000
+    /// steps (> 0 = invades). None once the mutant is lost.
+    pub fn fitness(&self) -> Option<f32> {
+        let (first, last) = (self.samples.first()?, self.samples.last()?);
+        if first.share() <= 0.0 || last.share() <= 0.0 || last.step == first.step {
+            return None;
+        }
+        Some((last.share() / first.share()).ln() / (last.step - first.step) as f32 * 1000.0)
+    }
+
+    /// One line per sample.
+    pub fn write_csv(&self, path: &Path) -> Result<(), String> {
+        let mut file = fs::File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+        writeln!(file, "step,mutant_mass,total_mass,mutant_share").map_err(|e| format!("Write error: {}", e))?;
+        for s in &self.samples {
+            writeln!(file, "{},{:.4},{:.4},{:.6}", s.step, s.mutant_mass, s.total_mass, s.share())
+                .map_err(|e| format!("Write error: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+// ======================== Background Assay ========================
+
+enum AssayUpdate {
+    Progress(f32),
+    Finished(Result<InvasionResult, String>),
+}
+
+struct AssayWorker {
+    updates: Receiver<AssayUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// An assay ready to run: the world with the mutant already injected.
+pub struct AssayJob {
+    pub snapshot: BufferSnapshot,
+    pub params: SimulationParams,
+    pub settings: InvasionSettings,
+    pub frame: u32,
+    pub injected: usize,
+    pub csv_path: PathBuf,
+}
+
+/// Step the cloned world, sampling the mutant share, then write the CSV.
+fn run_assay(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    job: &AssayJob,
+    cancel: &AtomicBool,
+    updates: &Sender<AssayUpdate>,
+) -> Result<InvasionResult, String> {
+    let mut world = WorldState::new_with_grid(device, queue, None, job.params.grid, job.snapshot.size);
+    if !world.apply_snapshot(queue, &job.snapshot) {
+        return Err(String::from("the world snapshot does not fit a new world"));
+    }
+    let pipelines = create_pipelines(device, &world, wgpu::TextureFormat::Rgba8Unorm);
+    let settings = job.settings;
+    let interval = settings.sample_interval.max(1);
+    let mut result = InvasionResult {
+        settings,
+        frame: job.frame,
+        injected: job.injected,
+        samples: vec![InvasionSample::measure(&job.snapshot, &settings, 0)],
+        cancelled: false,
+    };
+
+    for step in 1..=settings.steps {
+        if cancel.load(Ordering::SeqCst) {
+            result.cancelled = true;
+            break;
+        }
+        step_world(device, queue, &mut world, &pipelines, &job.params);
+        if step.is_multiple_of(interval) || step == settings.steps {
+            let snap = world
+                .readback_snapshot(device, queue)
+                .ok_or_else(|| format!("GPU readback failed at step {}", step))?;
+            result.samples.push(InvasionSample::measure(&snap, &settings, step));
+            let _ = updates.send(AssayUpdate::Progress(step as f32 / settings.steps as f32));
+        }
+    }
+
+    if let Some(dir) = job.csv_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    result.write_csv(&job.csv_path)?;
+    Ok(result)
+}
+
+/// The invasion assay of the Lab: its settings, the run in flight and the
+/// last result (shown in the results dialog).
+#[derive(Default)]
+pub struct InvasionAssay {
+    pub settings: InvasionSettings,
+    pub requested: bool,
+    pub progress: Option<f32>,
+    pub result: Option<InvasionResult>,
+    pub csv_path: Option<PathBuf>, // of the last result
+    pub show_results: bool,
+    worker: Option<AssayWorker>,
+}
+
+impl InvasionAssay {
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Run `job` on a background world sharing the GPU.
+    pub fn start(&mut self, device: wgpu::Device, queue: wgpu::Queue, job: AssayJob) {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        self.csv_path = Some(job.csv_path.clone());
+        std::thread::spawn(move || {
+            let result = run_assay(&device, &queue, &job, &flag, &tx);
+            let _ = tx.send(AssayUpdate::Finished(result));
+        });
+        self.progress = Some(0.0);
+        self.worker = Some(AssayWorker { updates: rx, cancel });
+    }
+
+    /// Stop the run early; the samples so far are still reported.
+    pub fn cancel(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Apply the worker's updates. Returns the outcome of a run that just
+    /// ended: the result (also kept in `result`) or an error.
+    pub fn poll(&mut self) -> Option<Result<&InvasionResult, String>> {
+        let updates: Vec<AssayUpdate> = self.worker.as_ref()?.updates.try_iter().collect();
+        let mut finished = None;
+        for update in updates {
+            match update {
+                AssayUpdate::Progress(fraction) => self.progress = Some(fraction),
+                AssayUpdate::Finished(result) => finished = Some(result),
+            }
+        }
+        let result = finished?;
+        self.worker = None;
+        self.progress = None;
+        Some(match result {
+            Ok(result) => {
+                self.show_results = true;
+                Ok(self.result.insert(result))
+            }
+            Err(e) => Err(e),
+        })
+    }
+}
@@ -0,0 +1,269 @@
+// ============================================================================
+// kernel.rs — EvoLenia v2
+// Lenia ring kernel on the CPU: the four radius tiers blended by
+// compute_evolution.wgsl, and their low-rank separable decomposition for the
+// separable convolution mode (compute_separable.wgsl).
+//
+// Each tier kernel is a symmetric 27×27 matrix K, so K = Σ λ_k v_k v_kᵀ with
+// eigenpairs (λ_k, v_k). Keeping the SEPARABLE_RANK largest terms turns the
+// 2D convolution into a horizontal pass with v_k and a vertical pass with
+// λ_k v_k: O(r) samples per cell instead of O(r²). The ring is not Gaussian,
+// but three terms reproduce the first three tiers to under 1% of their peak
+// weight. The xlarge tier is clipped by the distance cutoff at max_r, a
+// sharp circular edge no low-rank sum captures: it stays within ~4% locally,
+// and its total weight (what the potential is normalized by) within 0.1%.
+//
+// The exact mode can read its weights from a lookup table instead of calling
+// exp() per neighbor: the blended kernel only depends on the squared offset
+// distance and the genome radius, tabulated over GENOME_RADIUS_RANGE.
+// ============================================================================
+
+use bytemuck::{Pod, Zeroable};
+
+/// Convolution reach in cells (matches `max_r` in compute_evolution.wgsl).
+pub const KERNEL_MAX_R: i32 = 13;
+/// Taps per axis of the kernel window.
+pub const KERNEL_TAPS: usize = (2 * KERNEL_MAX_R + 1) as usize;
+/// Genome radii of the kernel tiers (small, mid, large, xlarge).
+pub const TIER_RADII: [f32; 4] = [3.0, 6.0, 10.0, 15.0];
+/// Separable terms kept per tier.
+pub const SEPARABLE_RANK: usize = 3;
+
+/// Weights at or below this are skipped by the exact convolution.
+const MIN_WEIGHT: f32 = 0.001;
+
+/// Genome radius clamp applied by the mutation step.
+pub const GENOME_RADIUS_RANGE: (f32, f32) = (3.0, 15.0);
+/// Largest squared offset distance inside the kernel window.
+pub const KERNEL_MAX_D2: usize = (KERNEL_MAX_R * KERNEL_MAX_R) as usize;
+/// Floats per LUT radius bin: one weight per squared distance, then the sum.
+pub const KERNEL_LUT_STRIDE: usize = KERNEL_MAX_D2 + 2;
+/// Upper bound on LUT radius bins (sizes the GPU buffer).
+pub const KERNEL_LUT_MAX_BINS: u32 = 256;
+
+type Matrix = [[f64; KERNEL_TAPS]; KERNEL_TAPS];
+
+// ======================== Tier Kernels ========================
+
+/// Ring kernel weight: K(d, r) = exp(-((d/r - 0.5)^2 / (2 * 0.15^2))).
+pub fn kernel_weight(dist: f32, radius: f32) -> f32 {
+    let diff = dist / radius - 0.5;
+    (-(diff * diff) / (2.0 * 0.15 * 0.15)).exp()
+}
+
+/// Weight of tier `tier` at offset (dx, dy), as the exact convolution sees
+/// it when blending towards the next tier: the center is excluded and the
+/// blended-out tiers are cut at 1.5 × their radius.
+pub fn tier_weight(tier: usize, dx: i32, dy: i32) -> f32 {
+    let radius = TIER_RADII[tier];
+    let dist = ((dx * dx + dy * dy) as f32).sqrt();
+    if dist < 0.5 || dist > KERNEL_MAX_R as f32 || dist > radius * 1.5 {
+        return 0.0;
+    }
+    let w = kernel_weight(dist, radius);
+    if w > MIN_WEIGHT {
+        w
+    } else {
+        0.0
+    }
+}
+
+/// Every (dx, dy) offset of the kernel window.
+pub fn window() -> impl Iterator<Item = (i32, i32)> {
+    (-KERNEL_MAX_R..=KERNEL_MAX_R).flat_map(|dy| (-KERNEL_MAX_R..=KERNEL_MAX_R).map(move |dx| (dx, dy)))
+}
+
+/// Weight of the radius-blended kernel at `dist` for genome radius `r`,
+/// mirroring exact_potential in compute_evolution.wgsl (before the
+/// MIN_WEIGHT cut).
+pub fn blended_weight(dist: f32, r: f32) -> f32 {
+    let faded = |tier: usize| {
+        let radius = TIER_RADII[tier];
+        if dist <= radius * 1.5 {
+            kernel_weight(dist, radius)
+        } else {
+            0.0
+        }
+    };
+    let tier_t = |lo: usize| ((r - TIER_RADII[lo]) / (TIER_RADII[lo + 1] - TIER_RADII[lo])).clamp(0.0, 1.0);
+    if r <= TIER_RADII[0] {
+        faded(0)
+    } else if r <= TIER_RADII[2] {
+        let lo = if r <= TIER_RADII[1] { 0 } else { 1 };
+        let t = tier_t(lo);
+        let w = faded(lo) + (kernel_weight(dist, TIER_RADII[lo + 1]) - faded(lo)) * t;
+        if dist > TIER_RADII[lo + 1] * 1.5 {
+            w * (1.0 - t)
+        } else {
+            w
+        }
+    } else {
+        let t = tier_t(2);
+        faded(2) + (kernel_weight(dist, TIER_RADII[3]) - faded(2)) * t
+    }
+}
+
+/// Nonzero taps (dx, dy, weight) of the exact kernel for genome radius `r`,
+/// with weights normalized to sum to 1 like exact_potential's.
+pub fn kernel_taps(r: f32) -> Vec<(i32, i32, f32)> {
+    let mut taps: Vec<(i32, i32, f32)> = window()
+        .filter_map(|(dx, dy)| {
+            let dist = ((dx * dx + dy * dy) as f32).sqrt();
+            if dist < 0.5 || dist > KERNEL_MAX_R as f32 {
+                return None;
+            }
+            let w = blended_weight(dist, r);
+            (w > MIN_WEIGHT).then_some((dx, dy, w))
+        })
+        .collect();
+    let sum: f32 = taps.iter().map(|t| t.2).sum();
+    for tap in &mut taps {
+        tap.2 /= sum;
+    }
+    taps
+}
+
+/// Genome radius sampled by LUT bin `bin` of `bins`.
+pub fn lut_bin_radius(bin: u32, bins: u32) -> f32 {
+    let (lo, hi) = GENOME_RADIUS_RANGE;
+    lo + (hi - lo) * bin as f32 / (bins - 1) as f32
+}
+
+/// Kernel lookup table with `bins` radius samples across GENOME_RADIUS_RANGE.
+/// Bin b holds the weight for every squared distance 0..=KERNEL_MAX_D2
+/// (0 where the exact convolution skips the offset), then the kernel sum.
+pub fn kernel_lut(bins: u32) -> Vec<f32> {
+    let bins = bins.clamp(2, KERNEL_LUT_MAX_BINS);
+    let mut lut = vec![0.0; bins as usize * KERNEL_LUT_STRIDE];
+    for (bin, row) in (0..bins).zip(lut.chunks_exact_mut(KERNEL_LUT_STRIDE)) {
+        let r = lut_bin_radius(bin, bins);
+        for (d2, w) in row[..=KERNEL_MAX_D2].iter_mut().enumerate().skip(1) {
+            let weight = blended_weight((d2 as f32).sqrt(), r);
+            *w = if weight > MIN_WEIGHT { weight } else { 0.0 };
+        }
+        let sum = window()
+            .map(|(dx, dy)| (dx * dx + dy * dy) as usize)
+            .filter(|&d2| d2 <= KERNEL_MAX_D2)
+            .map(|d2| row[d2])
+            .sum();
+        row[KERNEL_LUT_STRIDE - 1] = sum;
+    }
+    lut
+}
+
+fn tier_matrix(tier: usize) -> Matrix {
+    let mut k = [[0.0; KERNEL_TAPS]; KERNEL_TAPS];
+    for (row, dy) in k.iter_mut().zip(-KERNEL_MAX_R..) {
+        for (w, dx) in row.iter_mut().zip(-KERNEL_MAX_R..) {
+            *w = tier_weight(tier, dx, dy) as f64;
+        }
+    }
+    k
+}
+
+// ======================== Separable Decomposition ========================
+
+/// One separable term: weight(dx, dy) ≈ Σ scale · taps[dy] · taps[dx].
+#[derive(Clone, Copy, Debug)]
+pub struct SeparableTerm {
+    pub scale: f32,
+    pub taps: [f32; KERNEL_TAPS],
+}
+
+/// The SEPARABLE_RANK dominant terms of a tier kernel.
+pub fn separable_terms(tier: usize) -> [SeparableTerm; SEPARABLE_RANK] {
+    let (values, vectors) = symmetric_eigen(tier_matrix(tier));
+    let mut order: Vec<usize> = (0..KERNEL_TAPS).collect();
+    order.sort_by(|&a, &b| values[b].abs().total_cmp(&values[a].abs()));
+    std::array::from_fn(|k| {
+        let col = order[k];
+        SeparableTerm {
+            scale: values[col] as f32,
+            taps: std::array::from_fn(|i| vectors[i][col] as f32),
+        }
+    })
+}
+
+/// Weight the separable approximation gives tier `tier` at (dx, dy).
+pub fn separable_weight(terms: &[SeparableTerm], dx: i32, dy: i32) -> f32 {
+    let (ix, iy) = ((dx + KERNEL_MAX_R) as usize, (dy + KERNEL_MAX_R) as usize);
+    terms.iter().map(|t| t.scale * t.taps[iy] * t.taps[ix]).sum()
+}
+
+/// Eigen-decomposition of a symmetric matrix by cyclic Jacobi rotations.
+/// Returns the eigenvalues and the eigenvectors as columns.
+fn symmetric_eigen(mut a: Matrix) -> ([f64; KERNEL_TAPS], Matrix) {
+    let mut v = [[0.0; KERNEL_TAPS]; KERNEL_TAPS];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    for _sweep in 0..100 {
+        let off: f64 = (0..KERNEL_TAPS)
+            .flat_map(|p| ((p + 1)..KERNEL_TAPS).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q] * a[p][q])
+            .sum();
+        if off < 1e-24 {
+            break;
+        }
+        for p in 0..KERNEL_TAPS {
+            for q in (p + 1)..KERNEL_TAPS {
+                let apq = a[p][q];
+                if apq.abs() < 1e-30 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                a[p][p] -= t * apq;
+                a[q][q] += t * apq;
+                a[p][q] = 0.0;
+                a[q][p] = 0.0;
+                for r in 0..KERNEL_TAPS {
+                    if r != p && r != q {
+                        let (arp, arq) = (a[r][p], a[r][q]);
+                        a[r][p] = c * arp - s * arq;
+                        a[p][r] = a[r][p];
+                        a[r][q] = s * arp + c * arq;
+                        a[q][r] = a[r][q];
+                    }
+                    let (vrp, vrq) = (v[r][p], v[r][q]);
+                    v[r][p] = c * vrp - s * vrq;
+                    v[r][q] = s * vrp + c * vrq;
+                }
+            }
+        }
+    }
+    (std::array::from_fn(|i| a[i][i]), v)
+}
+
+// ======================== GPU Upload ========================
+
+/// Uniform read by compute_separable.wgsl and compute_evolution.wgsl. Each
+/// vec4 holds one value per tier; taps are indexed `tap * SEPARABLE_RANK + k`.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct SeparableKernelUniform {
+    pub horizontal: [[f32; 4]; KERNEL_TAPS * SEPARABLE_RANK], // v_k
+    pub vertical: [[f32; 4]; KERNEL_TAPS * SEPARABLE_RANK],   // λ_k · v_k
+    pub sums: [f32; 4], // total weight of each approximated tier kernel
+}
+
+impl SeparableKernelUniform {
+    pub fn compute() -> Self {
+        let mut uniform = Self::zeroed();
+        for tier in 0..TIER_RADII.len() {
+            let terms = separable_terms(tier);
+            for (k, term) in terms.iter().enumerate() {
+                for (tap, &w) in term.taps.iter().enumerate() {
+                    uniform.horizontal[tap * SEPARABLE_RANK + k][tier] = w;
+                    uniform.vertical[tap * SEPARABLE_RANK + k][tier] = term.scale * w;
+                }
+            }
+            uniform.sums[tier] = window()
+                .map(|(dx, dy)| separable_weight(&terms, dx, dy))
+                .sum();
+        }
+        uniform
+    }
+}
@@ -4,21 +4,67 @@
 // screenshot capture, and data export.
 // ============================================================================
 
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use chrono::Local;
-use serde::Serialize;
+use egui_dock::DockState;
+use serde::{Deserialize, Serialize};
 
-use crate::config::SimulationParams;
-use crate::metrics::SimDiagnostics;
-use crate::world::{WORLD_HEIGHT, WORLD_WIDTH};
+use crate::alerts::AlertMonitor;
+use crate::arena::Arena;
+use crate::config::{visualization_mode_name, CustomMetric, SimulationParams, VIS_MODE_COUNT};
+use crate::crash;
+use crate::creature::{CreatureEntry, PortraitCapture, PortraitSettings};
+use crate::dock::{default_dock_state, LabTab};
+use crate::expr::{compile_metrics, CompiledMetric};
+use crate::flow::FlowPreset;
+use crate::forensics::RewindBuffer;
+use crate::histogram::FieldHistogram;
+use crate::i18n::{trf, Language};
+use crate::invasion::InvasionAssay;
+use crate::ltee::FossilRecord;
+use crate::landscape::LandscapeProbe;
+use crate::condition_stats::{conditions_section, ConditionStats};
+use crate::conditions::{csv_label, Conditions};
+use crate::param_lock::ParamLocks;
+use crate::preset_diff::PresetDiff;
+use crate::metadata::ExperimentMetadata;
+use crate::metric_plugins::{compute_plugins, plugin_fields};
+use crate::metrics::{InteractionMatrix, SimDiagnostics, SplitDivergence};
+use crate::particles::ParticleStats;
+use crate::notify::NotificationCenter;
+use crate::pacing::{BackgroundThrottle, FrameStats};
+use crate::plot_export::{export_plot, plot_path, PlotExportSettings, PlotFigure};
+use crate::popgen::PopgenFormat;
+use crate::report::{
+    final_metrics_section, load_template, param_table, plot_files, plots_section, render_template, report_figures,
+    species_section, territory_shares, timeline_section, write_report_plots, ReportPreview, SpeciesSummary, REPORT_FILE,
+};
+use crate::reserves::ReserveStats;
+use crate::speciation::SpeciationTracker;
+use crate::resources::MemoryReport;
+use crate::session_log::{self, SessionLogLevel};
+use crate::settings::WindowGeometry;
+use crate::startup::{Recommendation, StartupBench, StartupChoice};
+use crate::replay::ReplayCheck;
+use crate::resize::WorldResize;
+use crate::tape::{TapePlayback, TapeRecorder};
+use crate::tensorboard::TensorBoardLog;
+use crate::territory::TerritoryMap;
+use crate::thumbnail::THUMBNAIL_FILE;
+use crate::transplant::TransplantHistory;
+use crate::queue::ExperimentQueue;
+use crate::profile::{sample_line_profile, ProfileLine, ProfileSample};
+use crate::theme::{ColorPalette, UiTheme};
+use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
 
 // ======================== Metrics Record ========================
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MetricsRecord {
     pub frame: u32,
     pub time_ms: f64,
@@ -44,9 +90,117 @@ pub struct MetricsRecord {
     pub genome_variance: f32,
     pub total_energy: f32,
     pub energy_flux: f32,
+    // Provider metrics (metric_plugins.rs), in `plugin_fields()` order
+    #[serde(default)]
+    pub plugins: Vec<f64>,
 }
 
 impl MetricsRecord {
+    pub fn from_diagnostics(diag: &SimDiagnostics, frame: u32, time_ms: f64, fps: f32) -> Self {
+        Self {
+            frame,
+            time_ms,
+            fps,
+            total_mass: diag.total_mass,
+            avg_energy: diag.avg_energy,
+            entropy: diag.genetic_entropy,
+            species: diag.species_count,
+            live_pixels: diag.live_pixels,
+            live_fraction: diag.live_fraction,
+            predator_fraction: diag.genome_stats.predator_fraction,
+            avg_resource: diag.avg_resource,
+            mass_std_dev: diag.mass_std_dev,
+            avg_radius: diag.genome_stats.avg_radius,
+            avg_mu: diag.genome_stats.avg_mu,
+            avg_sigma: diag.genome_stats.avg_sigma,
+            avg_aggressivity: diag.genome_stats.avg_aggressivity,
+            avg_mutation_rate: diag.genome_stats.avg_mutation_rate,
+            prey_fraction: diag.prey_fraction,
+            opportunist_fraction: diag.opportunist_fraction,
+            effective_diversity: diag.effective_diversity,
+            genome_variance: diag.genome_variance,
+            total_energy: diag.total_energy,
+            energy_flux: diag.energy_flux,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// The record with the provider metrics of the readback it was computed from.
+    pub fn with_plugins(mut self, snapshot: &BufferSnapshot) -> Self {
+        self.plugins = compute_plugins(snapshot);
+        self
+    }
+
+    /// Built-in field names, as in the CSV header.
+    pub const FIELDS: [&'static str; 23] = [
+        "frame",
+        "time_ms",
+        "fps",
+        "total_mass",
+        "avg_energy",
+        "entropy",
+        "species",
+        "live_pixels",
+        "live_fraction",
+        "predator_fraction",
+        "avg_resource",
+        "mass_std_dev",
+        "avg_radius",
+        "avg_mu",
+        "avg_sigma",
+        "avg_aggressivity",
+        "avg_mutation_rate",
+        "prey_fraction",
+        "opportunist_fraction",
+        "effective_diversity",
+        "genome_variance",
+        "total_energy",
+        "energy_flux",
+    ];
+
+    /// Built-in fields, then provider metrics: the names custom metric
+    /// expressions and alert rules can use.
+    pub fn field_names() -> Vec<&'static str> {
+        Self::FIELDS.iter().copied().chain(plugin_fields()).collect()
+    }
+
+    /// Values in `field_names` order (NaN for provider metrics the record
+    /// lacks, e.g. loaded from an older CSV).
+    pub fn field_values(&self) -> Vec<f64> {
+        let mut values = vec![
+            self.frame as f64,
+            self.time_ms,
+            self.fps as f64,
+            self.total_mass as f64,
+            self.avg_energy as f64,
+            self.entropy as f64,
+            self.species as f64,
+            self.live_pixels as f64,
+            self.live_fraction as f64,
+            self.predator_fraction as f64,
+            self.avg_resource as f64,
+            self.mass_std_dev as f64,
+            self.avg_radius as f64,
+            self.avg_mu as f64,
+            self.avg_sigma as f64,
+            self.avg_aggressivity as f64,
+            self.avg_mutation_rate as f64,
+            self.prey_fraction as f64,
+            self.opportunist_fraction as f64,
+            self.effective_diversity as f64,
+            self.genome_variance as f64,
+            self.total_energy as f64,
+            self.energy_flux as f64,
+        ];
+        values.extend((0..plugin_fields().len()).map(|i| self.plugin(i)));
+        values
+    }
+
+    /// Provider metric `i` of `plugin_fields()`, NaN if the record lacks it.
+    pub fn plugin(&self, i: usize) -> f64 {
+        self.plugins.get(i).copied().unwrap_or(f64::NAN)
+    }
+
     pub fn csv_header() -> &'static str {
         "frame,time_ms,fps,total_mass,avg_energy,entropy,species,live_pixels,live_fraction,predator_fraction,avg_resource,mass_std_dev,avg_radius,avg_mu,avg_sigma,avg_aggressivity,avg_mutation_rate,prey_fraction,opportunist_fraction,effective_diversity,genome_variance,total_energy,energy_flux"
     }
@@ -68,21 +222,145 @@ impl MetricsRecord {
 
 // ======================== Lab Event ========================
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum EventSeverity {
+    #[default]
+    Info,
+    Warn,  // something failed or needs a look, the run continues
+    Alert, // the ecosystem itself changed drastically (e.g. extinction)
+}
+
+impl EventSeverity {
+    pub fn all() -> &'static [EventSeverity] {
+        &[EventSeverity::Info, EventSeverity::Warn, EventSeverity::Alert]
+    }
+
+    /// Fixed tag used in exported logs.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            EventSeverity::Info => "INFO",
+            EventSeverity::Warn => "WARN",
+            EventSeverity::Alert => "ALERT",
+        }
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            EventSeverity::Info => "severity.info",
+            EventSeverity::Warn => "severity.warn",
+            EventSeverity::Alert => "severity.alert",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct LabEvent {
     pub frame: u32,
     pub time_ms: f64,
+    pub wall_time: String, // RFC 3339, local time zone
+    pub severity: EventSeverity,
     pub event_type: String,
     pub details: String,
+    pub payload: serde_json::Value, // typed fields for events.jsonl (Null if none)
 }
 
 impl LabEvent {
     pub fn to_log_line(&self) -> String {
         format!(
-            "[{:.1}ms] frame={} {} — {}",
-            self.time_ms, self.frame, self.event_type, self.details,
+            "[{:.1}ms] frame={} {} {} — {}",
+            self.time_ms,
+            self.frame,
+            self.severity.tag(),
+            self.event_type,
+            self.details,
         )
     }
+
+    /// One events.jsonl record (see `EVENTS_SCHEMA_VERSION`).
+    pub fn to_json_record(&self) -> serde_json::Value {
+        serde_json::json!({
+            "schema": EVENTS_SCHEMA_VERSION,
+            "frame": self.frame,
+            "time_ms": self.time_ms,
+            "wall_time": self.wall_time,
+            "severity": self.severity.tag().to_lowercase(),
+            "type": self.event_type,
+            "details": self.details,
+            "payload": self.payload,
+        })
+    }
+}
+
+/// Event types drawn as markers on the analysis plots; alerts always are.
+pub const TIMELINE_EVENT_TYPES: &[&str] = &[
+    "RESTART",
+    "SEED",
+    "PARAM_CHANGE",
+    "PERTURBATION",
+    "BARRIER",
+    "PATTERN",
+    "TRANSPLANT",
+    "RESERVE",
+    "SPECIATION",
+    "LTEE_TRANSFER",
+    "FOSSIL_SPAWN",
+    "STATE_LOAD",
+    "QUEUE_START",
+    "QUEUE_END",
+];
+
+impl LabEvent {
+    /// Whether the event marks a cause worth lining up with the metric plots.
+    pub fn on_timeline(&self) -> bool {
+        self.severity == EventSeverity::Alert || TIMELINE_EVENT_TYPES.contains(&self.event_type.as_str())
+    }
+}
+
+/// Timeline events within `tolerance` frames of `frame` (plot hover).
+pub fn events_near<'a>(events: &[&'a LabEvent], frame: f64, tolerance: f64) -> Vec<&'a LabEvent> {
+    events
+        .iter()
+        .filter(|e| (e.frame as f64 - frame).abs() <= tolerance)
+        .copied()
+        .collect()
+}
+
+/// Version of the events.jsonl record layout. Every line is one object:
+/// `schema` (u32), `frame` (u32), `time_ms` (f64, since run start),
+/// `wall_time` (RFC 3339 string), `severity` ("info" | "warn" | "alert"),
+/// `type` (string), `details` (human-readable string) and `payload`
+/// (event-specific object, or null). Bump on any incompatible change.
+pub const EVENTS_SCHEMA_VERSION: u32 = 1;
+
+/// Logs panel filter: minimum severity, exact event type, and a
+/// case-insensitive search over type and details.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    pub min_severity: EventSeverity,
+    pub event_type: Option<String>,
+    pub search: String,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &LabEvent) -> bool {
+        if event.severity < self.min_severity {
+            return false;
+        }
+        if self.event_type.as_ref().is_some_and(|t| *t != event.event_type) {
+            return false;
+        }
+        let needle = self.search.trim().to_lowercase();
+        needle.is_empty()
+            || event.event_type.to_lowercase().contains(&needle)
+            || event.details.to_lowercase().contains(&needle)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.min_severity != EventSeverity::Info
+            || self.event_type.is_some()
+            || !self.search.trim().is_empty()
+    }
 }
 
 // ======================== Run Summary ========================
@@ -94,6 +372,141 @@ pub struct RunSummary {
     pub start_time: String,
     pub total_frames: u32,
     pub metrics_count: usize,
+    pub final_metrics: Option<MetricsRecord>, // last metrics sample, shown in the gallery
+    pub thumbnail: Option<PathBuf>,           // set once the final state is drawn
+}
+
+impl RunSummary {
+    /// Simulation parameters from the run's config.json.
+    pub fn load_params(&self) -> Result<SimulationParams, String> {
+        let path = self.run_dir.join("config.json");
+        let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let mut config: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse {:?}: {}", path, e))?;
+        serde_json::from_value(config["params"].take()).map_err(|e| format!("Invalid params in {:?}: {}", path, e))
+    }
+}
+
+/// Default output root for runs, relative to the working directory.
+pub const RUNS_DIR: &str = "runs";
+
+/// Run id and output directory for a run starting at `now`:
+/// `<root>/<date>/run_<date>_<time>[_<label>]`.
+pub fn new_run_id(root: &Path, now: &chrono::DateTime<Local>, label: Option<&str>) -> (String, PathBuf) {
+    let mut run_id = format!("run_{}", now.format("%Y%m%d_%H%M%S"));
+    if let Some(label) = label {
+        run_id = format!("{}_{}", run_id, label);
+    }
+    let run_dir = root.join(now.format("%Y-%m-%d").to_string()).join(&run_id);
+    (run_id, run_dir)
+}
+
+/// Contents of the config.json of a run on a `size` world.
+pub fn run_config_json(
+    run_id: &str,
+    timestamp: &str,
+    params: &SimulationParams,
+    metadata: &ExperimentMetadata,
+    size: [u32; 2],
+) -> serde_json::Value {
+    serde_json::json!({
+        "run_id": run_id,
+        "timestamp": timestamp,
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "world_width": size[0],
+        "world_height": size[1],
+        "metadata": metadata.to_json(),
+        "params": params,
+    })
+}
+
+/// Write metrics records as CSV (header + one line per record), with one
+/// extra column per provider metric and per custom metric after the
+/// built-in ones, and a last `condition` column when the run has named
+/// conditions (empty for samples outside all of them).
+pub fn write_metrics_csv(
+    path: &Path,
+    records: &[MetricsRecord],
+    custom: &[CompiledMetric],
+    conditions: &Conditions,
+) -> Result<(), String> {
+    let mut file = fs::File::create(path)
+        .map_err(|e| format!("Failed to create metrics.csv: {}", e))?;
+    let labelled = !conditions.spans.is_empty();
+
+    let mut header = metrics_csv_header(custom);
+    if labelled {
+        header += ",condition";
+    }
+    writeln!(file, "{}", header)
+        .map_err(|e| format!("Write error: {}", e))?;
+
+    for record in records {
+        let mut line = metrics_csv_line(record, custom);
+        if labelled {
+            line.push(',');
+            line.push_str(&conditions.label_at(record.frame).map(csv_label).unwrap_or_default());
+        }
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("Write error: {}", e))?;
+    }
+    Ok(())
+}
+
+/// metrics.csv header: built-in columns, provider metrics, then custom
+/// metric names with commas and whitespace replaced by `_`.
+fn metrics_csv_header(custom: &[CompiledMetric]) -> String {
+    let mut header = MetricsRecord::csv_header().to_string();
+    for name in plugin_fields() {
+        header.push(',');
+        header.push_str(name);
+    }
+    for metric in custom {
+        header.push(',');
+        header.extend(metric.name.chars().map(|c| if c == ',' || c.is_whitespace() { '_' } else { c }));
+    }
+    header
+}
+
+fn metrics_csv_line(record: &MetricsRecord, custom: &[CompiledMetric]) -> String {
+    let mut line = record.to_csv_line();
+    for i in 0..plugin_fields().len() {
+        line += &format!(",{}", record.plugin(i));
+    }
+    for metric in custom {
+        line += &format!(",{}", metric.eval(record));
+    }
+    line
+}
+
+/// One metrics sample (genome averages and custom metrics included) as a
+/// tab-separated header line and value line, for pasting into a spreadsheet.
+pub fn metrics_tsv(record: &MetricsRecord, custom: &[CompiledMetric]) -> String {
+    format!(
+        "{}\n{}\n",
+        metrics_csv_header(custom).replace(',', "\t"),
+        metrics_csv_line(record, custom).replace(',', "\t")
+    )
+}
+
+// ======================== Reference Diff ========================
+
+/// Mass field the diff overlay compares the running world against.
+#[derive(Clone, Debug)]
+pub struct ReferenceMass {
+    pub label: String,  // snapshot path or "frame N"
+    pub mass: Vec<f32>, // row-major, one value per cell
+}
+
+impl ReferenceMass {
+    /// Mean |mass - reference| over the world; None if sizes differ.
+    pub fn divergence(&self, mass: &[f32]) -> Option<f32> {
+        if mass.len() != self.mass.len() || mass.is_empty() {
+            return None;
+        }
+        let sum: f64 = mass.iter().zip(&self.mass).map(|(a, b)| (a - b).abs() as f64).sum();
+        Some((sum / mass.len() as f64) as f32)
+    }
 }
 
 // ======================== Lab State ========================
@@ -104,97 +517,431 @@ pub struct LabState {
     pub run_start: Instant,
     pub run_start_time: String,
     pub run_dir: PathBuf,
+    pub output_root: PathBuf, // new runs go to <output_root>/<date>/<run_id>
     pub run_active: bool,
+    pub tensorboard_enabled: bool, // mirror metrics/screenshots to <run_dir>/tensorboard
+    pub tensorboard: Option<TensorBoardLog>, // opened on first write for the current run dir
 
     // -- Metrics --
     pub metrics_history: Vec<MetricsRecord>,
     pub metrics_sample_interval: u32,
 
+    // -- Pacing (mirrored from the app each frame) --
+    pub steps_per_frame: u32,
+    pub gpu_step_ms: Option<f32>, // measured GPU time per step, if timestamps are supported
+    pub speed_limit: Option<u32>, // steps per frame while the speed guard holds the speed down
+    pub startup_choice: Option<StartupChoice>, // first-launch benchmark and the choice made (saved)
+    pub startup_pending: Option<(StartupBench, Recommendation)>, // result awaiting the dialog
+    pub startup_bench_requested: bool,
+    pub frame_stats: FrameStats,  // recent frame cost breakdown (performance panel)
+    pub memory_report: Option<MemoryReport>, // refreshed while the resources panel is open
+    pub background_throttle: BackgroundThrottle,
+    pub session_log_level: SessionLogLevel,
+    pub gpu_validation: bool,        // saved setting, applies at the next launch
+    pub gpu_validation_active: bool, // this session's instance has the validation layers
+    pub gpu_errors: (usize, u32),    // (distinct GPU errors, occurrences) caught so far
+
     // -- Events --
     pub events: Vec<LabEvent>,
+    pub event_filter: EventFilter,
+    pub marked_frame: Option<u32>, // set from a log entry, drawn on the analysis plots
+    pub show_event_markers: bool,  // timeline events drawn on the analysis plots
+    pub current_frame: u32,        // mirrored from the world each frame so UI events carry it
+    pub world_size: [u32; 2],      // mirrored from the world each frame for the UI and run records
 
     // -- UI state --
     pub show_lab_ui: bool,
     pub show_analysis_panel: bool,
+    pub analysis_detached: bool,                   // shown in its own OS window
+    pub analysis_window: Option<WindowGeometry>,   // last placement of that window
     pub show_logs_panel: bool,
+    pub show_performance_panel: bool,
+    pub show_resources_panel: bool,
+    pub show_queue_panel: bool,
+    pub show_report_panel: bool,
+    pub ui_scale: f32,
+    pub ui_theme: UiTheme,
+    pub language: Language,
+    pub palette: ColorPalette,
+    pub dock_state: DockState<LabTab>,
+    pub viewport_hovered: bool, // pointer over the simulation tab (not a panel)
+    pub smooth_sampling_supported: bool, // device can filter the field textures
+    pub convolution_error: Option<f32>, // mean |separable - exact| potential, compare mode
+    pub interactions: Option<InteractionMatrix>, // predation counts over the last sample interval
+    pub particles: Option<ParticleStats>,        // grazer pool at the last sample
 
     // -- Actions --
     pub restart_requested: bool,
     pub step_requested: bool,
     pub screenshot_requested: bool,
     pub snapshot_requested: bool,
+    pub snapshot_save_path: Option<PathBuf>, // "Save snapshot as…" target, else the run dir
+    pub snapshot_load_path: Option<PathBuf>,
+    pub auto_normalize_requested: bool,
+    pub trail_clear_requested: bool,
+    pub zoom_snap_requested: bool,
 
     // -- Comparison --
     pub completed_runs: Vec<RunSummary>,
+    pub thumbnail_pending: Option<PathBuf>, // finalized run dir awaiting its thumbnail
+    pub thumbnails: HashMap<(egui::ViewportId, PathBuf), Option<egui::TextureHandle>>, // gallery textures per window (None: unreadable)
+    pub report: ReportPreview,
+    pub report_template: String, // template file path; empty: built-in
+    pub metadata: ExperimentMetadata, // author, description, tags, license for exports
     pub comparison_a: Option<usize>,
     pub comparison_b: Option<usize>,
 
     // -- Config presets --
     pub preset_name: String,
-    pub selected_preset_index: usize,
+    pub preset_diff: Option<PresetDiff>, // preset being loaded, until applied or cancelled
+
+    // -- Parameter locks and safety mode (param_lock.rs) --
+    pub param_locks: ParamLocks,
 
-    // -- Status messages --
-    pub status_message: Option<(String, Instant)>,
+    // -- Named experimental conditions (conditions.rs) --
+    pub conditions: Conditions,
+    pub condition_name: String, // name of the next condition to begin
+    pub condition_stats: ConditionStats, // metrics and conditions compared (condition_stats.rs)
+
+    // -- Experiment queue --
+    pub experiment_queue: ExperimentQueue,
+
+    // -- Lenia patterns (import/export) --
+    pub pattern_name: String,
+    pub pattern_center: [f32; 2], // world UV
+    pub pattern_extent: u32,      // export half-size in pixels
+    pub pattern_import_requested: bool,
+    pub pattern_export_requested: bool,
+
+    // -- Translocation experiments (transplant.rs) --
+    pub transplant_from: [f32; 2], // world UV of the source region
+    pub transplant_to: [f32; 2],   // world UV of the destination region
+    pub transplant_half: u32,      // region half-size in pixels
+    pub transplant_pick_from: bool, // next click on the world picks the source
+    pub transplant_pick_to: bool,   // ... or the destination
+    pub transplant_requested: bool,
+    pub transplant_undo_requested: bool,
+    pub transplants: TransplantHistory,
+
+    // -- Fitness landscape probe (landscape.rs) --
+    pub landscape: LandscapeProbe,
+
+    // -- Invasion assay (invasion.rs) --
+    pub invasion: InvasionAssay,
+
+    // -- Long-term evolution experiment (ltee.rs) --
+    pub fossils: FossilRecord,
+
+    // -- Speciation detection (speciation.rs) --
+    pub speciation: SpeciationTracker,
+
+    // -- Extinction forensics (forensics.rs) --
+    pub rewind: RewindBuffer,
+    pub extinction_report_requested: bool, // set with the EXTINCTION event
+    pub last_extinction_report: Option<PathBuf>,
+
+    // -- Time-averaged fields (averages.rs) --
+    pub average_samples: u32, // steps in the running means (mirrors the world)
+    pub average_reset_requested: bool,
+    pub average_export_requested: bool,
+    pub last_average_export: Option<PathBuf>,
+
+    // -- Velocity field export (vectors.rs) --
+    pub velocity_samples: u32, // diagnostics samples since periodic export was enabled
+    pub velocity_export_requested: bool,
+    pub last_velocity_export: Option<PathBuf>,
+
+    // -- Simulation tapes (tape.rs) --
+    pub tape: TapeRecorder,
+    pub playback: TapePlayback,
+
+    // -- Deterministic replay check (replay.rs) --
+    pub replay: ReplayCheck,
+
+    // -- Head-to-head arena (arena.rs) --
+    pub arena: Arena,
+
+    // -- Runtime world resize (resize.rs) --
+    pub resize: WorldResize,
+
+    // -- Creature portraits --
+    pub portrait: PortraitSettings,
+    pub portrait_center: [f32; 2],  // world UV
+    pub portrait_pick_active: bool, // next click on the world picks the creature
+    pub portrait_requested: bool,
+    pub portrait_capture: Option<PortraitCapture>,
+    pub creatures: Option<Vec<CreatureEntry>>, // gallery; None: rescan creatures/
+
+    // -- Population genetics export --
+    pub popgen_format: PopgenFormat,
+    pub popgen_frame: u32,       // frame of the scheduled export
+    pub popgen_scheduled: bool,  // export once the world reaches popgen_frame
+    pub popgen_export_requested: bool,
+
+    // -- Custom metrics (compiled from params.custom_metrics) --
+    pub custom_metrics: Vec<CompiledMetric>,
+    pub custom_metric_defs: Vec<CustomMetric>, // definitions `custom_metrics` was compiled from
+
+    // -- Alert rules (definitions in params.alert_rules) --
+    pub alerts: AlertMonitor,
+    pub alert_pause_requested: bool,
+
+    // -- Plot export (analysis charts as SVG/PNG) --
+    pub plot_export: PlotExportSettings,
+
+    // -- Territory overlay --
+    pub territory: TerritoryMap,
+    pub territory_areas: Vec<(u16, usize)>, // cells owned per species, largest first
+
+    // -- Histogram overlay (last readback) --
+    pub histogram: Option<FieldHistogram>,
+
+    // -- Reference diff overlay --
+    pub diff_reference_path: String,
+    pub diff_reference: Option<ReferenceMass>,
+    pub diff_divergence: Option<f32>, // mean |Δmass| at the last diagnostics sample
+    pub diff_load_requested: bool,
+    pub diff_capture_requested: bool,
+
+    // -- Cross-section profile --
+    pub profile_tool_active: bool,
+    pub profile_line: Option<ProfileLine>,
+    pub profile_samples: Vec<ProfileSample>,
+    pub profile_refresh_requested: bool,
+
+    // -- Flow field editor (flow.rs) --
+    pub flow_preset: FlowPreset,
+    pub flow_angle: f32, // degrees
+    pub flow_seed: u64,  // curl noise
+    pub flow_generate_requested: bool,
+    pub flow_clear_requested: bool,
+    pub flow_brush_active: bool,
+    pub flow_brush_radius: f32, // cells
+    pub flow_brush_erase: bool,
+
+    // -- User visualization modes (user_modes.rs; pipelines live in the app) --
+    pub user_modes: Vec<String>, // names of the compiled modes, from mode VIS_MODE_COUNT on
+    pub user_modes_reload_requested: bool,
+
+    // -- Reserve zones (reserves.rs; the zones themselves are in params) --
+    pub reserve_stats: Vec<ReserveStats>, // per zone, at the last diagnostics sample
+    pub reserve_capture_requested: Option<usize>, // zone whose occupants become its residents
+    pub reserve_pick: Option<usize>, // clicks on the world place this zone (center / polygon vertices)
+
+    // -- Barrier (vicariance) --
+    pub barrier_split: Option<f32>,    // wall column (pixels) of the last barrier, until restart
+    pub barrier_frames_left: u32,      // steps before the walls come down (0 = lifted)
+    pub barrier_divergence: Option<SplitDivergence>, // at the last diagnostics sample
+    pub barrier_history: Vec<[f64; 2]>, // (frame, divergence index)
+
+    // -- Notifications (status toasts, background jobs) --
+    pub notifications: NotificationCenter,
 }
 
 impl Default for LabState {
     fn default() -> Self {
         let now = Local::now();
-        let run_id = format!("run_{}", now.format("%Y%m%d_%H%M%S"));
-        let run_dir = PathBuf::from(format!(
-            "runs/{}/{}",
-            now.format("%Y-%m-%d"),
-            &run_id
-        ));
+        let (run_id, run_dir) = new_run_id(Path::new(RUNS_DIR), &now, None);
 
         Self {
             run_id,
             run_start: Instant::now(),
             run_start_time: now.format("%Y-%m-%d %H:%M:%S").to_string(),
             run_dir,
+            output_root: PathBuf::from(RUNS_DIR),
             run_active: false,
+            tensorboard_enabled: false,
+            tensorboard: None,
 
             metrics_history: Vec::with_capacity(10_000),
             metrics_sample_interval: 300,
 
+            steps_per_frame: 1,
+            gpu_step_ms: None,
+            speed_limit: None,
+            startup_choice: None,
+            startup_pending: None,
+            startup_bench_requested: false,
+            frame_stats: FrameStats::default(),
+            memory_report: None,
+            background_throttle: BackgroundThrottle::default(),
+            session_log_level: SessionLogLevel::default(),
+            gpu_validation: false,
+            gpu_validation_active: false,
+            gpu_errors: (0, 0),
+
             events: Vec::with_capacity(1_000),
+            event_filter: EventFilter::default(),
+            marked_frame: None,
+            show_event_markers: true,
+            current_frame: 0,
+            world_size: DEFAULT_WORLD_SIZE,
 
             show_lab_ui: true,
             show_analysis_panel: false,
+            analysis_detached: false,
+            analysis_window: None,
             show_logs_panel: true,
+            show_performance_panel: false,
+            show_resources_panel: false,
+            show_queue_panel: false,
+            show_report_panel: false,
+            ui_scale: 1.0,
+            ui_theme: UiTheme::default(),
+            language: Language::default(),
+            palette: ColorPalette::default(),
+            dock_state: default_dock_state(),
+            viewport_hovered: false,
+            smooth_sampling_supported: false,
+            convolution_error: None,
+            interactions: None,
+            particles: None,
 
             restart_requested: false,
             step_requested: false,
             screenshot_requested: false,
             snapshot_requested: false,
+            snapshot_save_path: None,
+            snapshot_load_path: None,
+            auto_normalize_requested: false,
+            trail_clear_requested: false,
+            zoom_snap_requested: false,
 
             completed_runs: Vec::new(),
+            thumbnail_pending: None,
+            thumbnails: HashMap::new(),
+            report: ReportPreview::default(),
+            report_template: String::new(),
+            metadata: ExperimentMetadata::default(),
             comparison_a: None,
             comparison_b: None,
 
             preset_name: String::from("default"),
-            selected_preset_index: 0,
+            preset_diff: None,
+
+            param_locks: ParamLocks::default(),
+
+            conditions: Conditions::default(),
+            condition_name: String::new(),
+            condition_stats: ConditionStats::default(),
+
+            experiment_queue: ExperimentQueue::default(),
+
+            pattern_name: String::from("creature"),
+            pattern_center: [0.5, 0.5],
+            pattern_extent: 32,
+            pattern_import_requested: false,
+            pattern_export_requested: false,
+
+            transplant_from: [0.25, 0.5],
+            transplant_to: [0.75, 0.5],
+            transplant_half: 24,
+            transplant_pick_from: false,
+            transplant_pick_to: false,
+            transplant_requested: false,
+            transplant_undo_requested: false,
+            transplants: TransplantHistory::default(),
 
-            status_message: None,
+            landscape: LandscapeProbe::default(),
+
+            invasion: InvasionAssay::default(),
+
+            fossils: FossilRecord::default(),
+            speciation: SpeciationTracker::default(),
+            rewind: RewindBuffer::default(),
+            extinction_report_requested: false,
+            last_extinction_report: None,
+            average_samples: 0,
+            average_reset_requested: false,
+            average_export_requested: false,
+            last_average_export: None,
+            velocity_samples: 0,
+            velocity_export_requested: false,
+            last_velocity_export: None,
+            tape: TapeRecorder::default(),
+            playback: TapePlayback::default(),
+            replay: ReplayCheck::default(),
+            arena: Arena::default(),
+            resize: WorldResize::default(),
+
+            portrait: PortraitSettings::default(),
+            portrait_center: [0.5, 0.5],
+            portrait_pick_active: false,
+            portrait_requested: false,
+            portrait_capture: None,
+            creatures: None,
+
+            popgen_format: PopgenFormat::default(),
+            popgen_frame: 10_000,
+            popgen_scheduled: false,
+            popgen_export_requested: false,
+
+            custom_metrics: Vec::new(),
+            custom_metric_defs: Vec::new(),
+
+            alerts: AlertMonitor::default(),
+            alert_pause_requested: false,
+
+            plot_export: PlotExportSettings::default(),
+
+            territory: TerritoryMap::default(),
+            territory_areas: Vec::new(),
+            histogram: None,
+
+            diff_reference_path: String::new(),
+            diff_reference: None,
+            diff_divergence: None,
+            diff_load_requested: false,
+            diff_capture_requested: false,
+
+            profile_tool_active: false,
+            profile_line: None,
+            profile_samples: Vec::new(),
+            profile_refresh_requested: false,
+
+            flow_preset: FlowPreset::Uniform,
+            flow_angle: 0.0,
+            flow_seed: 1,
+            flow_generate_requested: false,
+            flow_clear_requested: false,
+            flow_brush_active: false,
+            flow_brush_radius: 16.0,
+            flow_brush_erase: false,
+
+            user_modes: Vec::new(),
+            user_modes_reload_requested: false,
+
+            reserve_stats: Vec::new(),
+            reserve_capture_requested: None,
+            reserve_pick: None,
+
+            barrier_split: None,
+            barrier_frames_left: 0,
+            barrier_divergence: None,
+            barrier_history: Vec::new(),
+
+            notifications: NotificationCenter::default(),
         }
     }
 }
 
 impl LabState {
     /// Start a new run: create output directory, save initial config.
-    pub fn start_run(&mut self, params: &SimulationParams) {
+    /// `label` is appended to the run id (queued experiments).
+    pub fn start_run(&mut self, params: &SimulationParams, label: Option<&str>) {
         let now = Local::now();
-        self.run_id = format!("run_{}", now.format("%Y%m%d_%H%M%S"));
-        self.run_dir = PathBuf::from(format!(
-            "runs/{}/{}",
-            now.format("%Y-%m-%d"),
-            &self.run_id
-        ));
+        (self.run_id, self.run_dir) = new_run_id(&self.output_root, &now, label);
         self.run_start = Instant::now();
         self.run_start_time = now.format("%Y-%m-%d %H:%M:%S").to_string();
         self.run_active = true;
+        self.param_locks.unlocked = false; // safety mode re-engages for every run
+        self.tensorboard = None; // next write opens a file in the new run dir
+        self.territory.clear();
+        self.territory_areas.clear();
+        self.alerts.clear();
         self.metrics_history.clear();
         self.events.clear();
+        self.conditions.clear();
 
         // Create directories
         if let Err(e) = fs::create_dir_all(&self.run_dir) {
@@ -205,23 +952,30 @@ impl LabState {
         if let Err(e) = fs::create_dir_all(&screenshots_dir) {
             log::error!("Failed to create screenshots dir: {}", e);
         }
+        if let Err(e) = session_log::open(&self.run_dir, &self.run_id) {
+            log::error!("{}", e);
+        }
+        crash::set_run_dir(&self.run_dir);
 
         // Save config
         self.save_config(params);
-        self.log_event(0, "RUN_START", &format!("Run {} started", self.run_id));
-        self.set_status(format!("Run {} started", self.run_id));
+        self.log_event_with_payload(
+            0,
+            EventSeverity::Info,
+            "RUN_START",
+            &format!("Run {} started", self.run_id),
+            serde_json::json!({ "run_id": self.run_id, "mutation": params.mutation }),
+        );
+        self.set_status(trf("status.run_started", &[("id", &self.run_id)]));
     }
 
-    /// Save config.json for the current run.
+    /// Save config.json (and .zenodo.json when metadata is filled in) for
+    /// the current run.
     pub fn save_config(&self, params: &SimulationParams) {
-        let config = serde_json::json!({
-            "run_id": self.run_id,
-            "timestamp": self.run_start_time,
-            "app_version": env!("CARGO_PKG_VERSION"),
-            "world_width": WORLD_WIDTH,
-            "world_height": WORLD_HEIGHT,
-            "params": params,
-        });
+        let config = run_config_json(&self.run_id, &self.run_start_time, params, &self.metadata, self.world_size);
+        if let Err(e) = self.metadata.save_zenodo(&self.run_dir, &self.run_id) {
+            log::error!("{}", e);
+        }
 
         let path = self.run_dir.join("config.json");
         match serde_json::to_string_pretty(&config) {
@@ -236,62 +990,218 @@ impl LabState {
         }
     }
 
-    /// Record a metrics sample from GPU readback diagnostics.
-    pub fn record_metrics(&mut self, diag: &SimDiagnostics, frame: u32, fps: f32) {
+    /// Record a metrics sample from GPU readback diagnostics and the
+    /// snapshot they were computed from (for the provider metrics).
+    pub fn record_metrics(&mut self, diag: &SimDiagnostics, snapshot: &BufferSnapshot, frame: u32, fps: f32) {
         let time_ms = self.run_start.elapsed().as_secs_f64() * 1000.0;
-        let record = MetricsRecord {
-            frame,
-            time_ms,
-            fps,
-            total_mass: diag.total_mass,
-            avg_energy: diag.avg_energy,
-            entropy: diag.genetic_entropy,
-            species: diag.species_count,
-            live_pixels: diag.live_pixels,
-            live_fraction: diag.live_fraction,
-            predator_fraction: diag.genome_stats.predator_fraction,
-            avg_resource: diag.avg_resource,
-            mass_std_dev: diag.mass_std_dev,
-            avg_radius: diag.genome_stats.avg_radius,
-            avg_mu: diag.genome_stats.avg_mu,
-            avg_sigma: diag.genome_stats.avg_sigma,
-            avg_aggressivity: diag.genome_stats.avg_aggressivity,
-            avg_mutation_rate: diag.genome_stats.avg_mutation_rate,
-            prey_fraction: diag.prey_fraction,
-            opportunist_fraction: diag.opportunist_fraction,
-            effective_diversity: diag.effective_diversity,
-            genome_variance: diag.genome_variance,
-            total_energy: diag.total_energy,
-            energy_flux: diag.energy_flux,
-        };
+        let record = MetricsRecord::from_diagnostics(diag, frame, time_ms, fps).with_plugins(snapshot);
+        let was_alive = self.metrics_history.last().is_some_and(|m| m.live_pixels > 0);
+        if was_alive && record.live_pixels == 0 {
+            self.log_event_with_payload(
+                frame,
+                EventSeverity::Alert,
+                "EXTINCTION",
+                "No live pixels remain",
+                serde_json::json!({ "live_pixels": 0 }),
+            );
+            self.extinction_report_requested = true;
+        }
+        if let Some(log) = self.tensorboard_log() {
+            let custom: Vec<(String, f32)> =
+                self.custom_metrics.iter().map(|m| (format!("custom/{}", m.name), m.eval(&record) as f32)).collect();
+            let plugins: Vec<(String, f32)> =
+                plugin_fields().iter().enumerate().map(|(i, name)| (format!("plugin/{name}"), record.plugin(i) as f32)).collect();
+            let mut scalars = diag.named_scalars();
+            scalars.push(("performance/fps", fps));
+            scalars.extend(plugins.iter().chain(&custom).map(|(name, value)| (name.as_str(), *value)));
+            if let Err(e) = log.scalars(frame, &scalars) {
+                self.tensorboard_failed(&e.to_string());
+            }
+        }
         self.metrics_history.push(record);
+        self.check_alert_rules(frame);
+    }
+
+    /// Record how far the halves of a split world have diverged.
+    pub fn record_split_divergence(&mut self, divergence: SplitDivergence, frame: u32) {
+        if let Some(index) = divergence.index() {
+            self.barrier_history.push([frame as f64, index as f64]);
+        }
+        self.barrier_divergence = Some(divergence);
+    }
+
+    /// Forget the last barrier and its divergence series (new world).
+    pub fn clear_barrier(&mut self) {
+        self.barrier_split = None;
+        self.barrier_frames_left = 0;
+        self.barrier_divergence = None;
+        self.barrier_history.clear();
+    }
+
+    /// Fire the alert rules met by the newest sample: log, notify, and
+    /// request the pause / snapshot actions (applied by the app).
+    fn check_alert_rules(&mut self, frame: u32) {
+        for trigger in self.alerts.check(&self.metrics_history, &self.custom_metrics) {
+            self.log_event_with_payload(
+                frame,
+                EventSeverity::Alert,
+                "ALERT_RULE",
+                &format!("{} (value {:.4})", trigger.description, trigger.value),
+                serde_json::json!({
+                    "rule": trigger.rule,
+                    "condition": trigger.description,
+                    "value": trigger.value,
+                    "pause": trigger.pause,
+                    "snapshot": trigger.snapshot,
+                }),
+            );
+            self.notifications.notify(
+                EventSeverity::Alert,
+                trf("alert.fired", &[("rule", &trigger.description), ("frame", &frame)]),
+            );
+            self.alert_pause_requested |= trigger.pause;
+            self.snapshot_requested |= trigger.snapshot;
+        }
     }
 
-    /// Log an event.
+    /// TensorBoard writer for the current run, if enabled. The event file is
+    /// created on first use; on failure export is turned off with a warning.
+    pub fn tensorboard_log(&mut self) -> Option<TensorBoardLog> {
+        if !self.tensorboard_enabled {
+            return None;
+        }
+        if self.tensorboard.is_none() {
+            match TensorBoardLog::create(&self.run_dir.join("tensorboard")) {
+                Ok(log) => {
+                    log::info!("TensorBoard events: {:?}", log.path());
+                    self.tensorboard = Some(log);
+                }
+                Err(e) => self.tensorboard_failed(&e.to_string()),
+            }
+        }
+        self.tensorboard.clone()
+    }
+
+    fn tensorboard_failed(&mut self, error: &str) {
+        log::error!("TensorBoard export failed: {}", error);
+        self.tensorboard_enabled = false;
+        self.tensorboard = None;
+        self.set_warning(trf("status.tensorboard_failed", &[("error", &error)]));
+    }
+
+    /// Resample the cross-section profile (if a line is set) from a readback.
+    pub fn update_profile(&mut self, snap: &BufferSnapshot) {
+        if let Some(line) = &self.profile_line {
+            self.profile_samples = sample_line_profile(snap, line);
+        }
+        self.profile_refresh_requested = false;
+    }
+
+    /// Log an informational event.
     pub fn log_event(&mut self, frame: u32, event_type: &str, details: &str) {
+        self.log_event_with_severity(frame, EventSeverity::Info, event_type, details);
+    }
+
+    pub fn log_event_with_severity(
+        &mut self,
+        frame: u32,
+        severity: EventSeverity,
+        event_type: &str,
+        details: &str,
+    ) {
+        self.log_event_with_payload(frame, severity, event_type, details, serde_json::Value::Null);
+    }
+
+    /// Log an event with typed fields for machine-readable export.
+    pub fn log_event_with_payload(
+        &mut self,
+        frame: u32,
+        severity: EventSeverity,
+        event_type: &str,
+        details: &str,
+        payload: serde_json::Value,
+    ) {
         let time_ms = self.run_start.elapsed().as_secs_f64() * 1000.0;
         self.events.push(LabEvent {
             frame,
             time_ms,
+            wall_time: Local::now().to_rfc3339(),
+            severity,
             event_type: event_type.to_string(),
             details: details.to_string(),
+            payload,
         });
     }
 
-    /// Export metrics to CSV.
-    pub fn export_metrics_csv(&self) -> Result<PathBuf, String> {
-        let path = self.run_dir.join("metrics.csv");
-        let mut file = fs::File::create(&path)
-            .map_err(|e| format!("Failed to create metrics.csv: {}", e))?;
+    /// Events drawn on the analysis plots (empty when markers are hidden).
+    pub fn timeline_events(&self) -> Vec<&LabEvent> {
+        if !self.show_event_markers {
+            return Vec::new();
+        }
+        self.events.iter().filter(|e| e.on_timeline()).collect()
+    }
 
-        writeln!(file, "{}", MetricsRecord::csv_header())
-            .map_err(|e| format!("Write error: {}", e))?;
+    /// Log a parameter change. `value` is the displayed value; numbers and
+    /// booleans are stored typed in the payload.
+    pub fn log_param_change(&mut self, frame: u32, param: &str, value: &str) {
+        let typed = if let Ok(b) = value.parse::<bool>() {
+            serde_json::Value::from(b)
+        } else if let Ok(x) = value.parse::<f64>() {
+            serde_json::Value::from(x)
+        } else {
+            serde_json::Value::from(value)
+        };
+        self.log_event_with_payload(
+            frame,
+            EventSeverity::Info,
+            "PARAM_CHANGE",
+            &format!("{}={}", param, value),
+            serde_json::json!({ "param": param, "value": typed }),
+        );
+    }
 
-        for record in &self.metrics_history {
-            writeln!(file, "{}", record.to_csv_line())
-                .map_err(|e| format!("Write error: {}", e))?;
+    /// Recompile the custom metrics when their definitions changed
+    /// (editor, preset load).
+    pub fn sync_custom_metrics(&mut self, defs: &[CustomMetric]) {
+        if self.custom_metric_defs != defs {
+            self.custom_metric_defs = defs.to_vec();
+            self.custom_metrics = compile_metrics(defs);
         }
+    }
+
+    /// Export an analysis chart to `<run>/plots` in the background, tagged
+    /// with the run, frame, seed and parameters.
+    /// Metadata embedded in exported figures.
+    fn plot_metadata(&self, title: &str, params: &SimulationParams) -> Vec<(String, String)> {
+        vec![
+            ("Title".into(), title.to_string()),
+            ("Software".into(), format!("EvoLenia {}", env!("CARGO_PKG_VERSION"))),
+            ("Creation Time".into(), Local::now().to_rfc3339()),
+            ("Run".into(), self.run_id.clone()),
+            ("Frame".into(), self.current_frame.to_string()),
+            ("Seed".into(), params.effective_seed().map_or("random".into(), |s| s.to_string())),
+            ("Parameters".into(), serde_json::to_string(params).unwrap_or_default()),
+        ]
+    }
 
+    pub fn export_plot(&mut self, mut figure: PlotFigure, params: &SimulationParams) {
+        figure.metadata = self.plot_metadata(&figure.title, params);
+        let path = plot_path(&self.run_dir, &figure.title, self.current_frame, self.plot_export.format);
+        let settings = self.plot_export;
+        let job = self.notifications.start_job(trf("job.plot", &[("title", &figure.title)]), "EXPORT");
+        std::thread::spawn(move || {
+            let result = export_plot(&figure, settings, &path)
+                .map(|()| trf("status.plot_exported", &[("path", &format!("{:?}", path))]))
+                .inspect_err(|e| log::error!("Plot export failed: {}", e));
+            job.finish(result);
+        });
+    }
+
+    /// Export metrics to CSV.
+    pub fn export_metrics_csv(&self) -> Result<PathBuf, String> {
+        let path = self.run_dir.join("metrics.csv");
+        write_metrics_csv(&path, &self.metrics_history, &self.custom_metrics, &self.conditions)?;
+        self.conditions.save(&self.run_dir)?;
         log::info!("Exported {} metrics records to {:?}", self.metrics_history.len(), path);
         Ok(path)
     }
@@ -306,71 +1216,128 @@ impl LabState {
             writeln!(file, "{}", event.to_log_line())
                 .map_err(|e| format!("Write error: {}", e))?;
         }
+        self.export_events_jsonl()?;
 
         log::info!("Exported {} events to {:?}", self.events.len(), path);
         Ok(path)
     }
 
+    /// Export events as JSON Lines (events.jsonl), one record per event.
+    pub fn export_events_jsonl(&self) -> Result<PathBuf, String> {
+        let path = self.run_dir.join("events.jsonl");
+        let mut file = fs::File::create(&path)
+            .map_err(|e| format!("Failed to create events.jsonl: {}", e))?;
+
+        for event in &self.events {
+            writeln!(file, "{}", event.to_json_record())
+                .map_err(|e| format!("Write error: {}", e))?;
+        }
+        Ok(path)
+    }
+
     /// Export a full run report (markdown).
     pub fn export_report(&self, params: &SimulationParams) -> Result<PathBuf, String> {
-        let path = self.run_dir.join("report.md");
+        let report = self.generate_report(params)?;
+        self.save_report(&report)
+    }
+
+    /// Write report markdown (generated or edited in the preview) to report.md.
+    pub fn save_report(&self, markdown: &str) -> Result<PathBuf, String> {
+        let path = self.run_dir.join(REPORT_FILE);
         let mut file = fs::File::create(&path)
             .map_err(|e| format!("Failed to create report.md: {}", e))?;
-
-        let last_metrics = self.metrics_history.last();
-
-        let report = format!(
-            "# EvoLenia Experiment Report\n\n\
-             ## Run Info\n\
-             - **Run ID**: {}\n\
-             - **Start**: {}\n\
-             - **Frames**: {}\n\
-             - **Metrics Samples**: {}\n\
-             - **App Version**: {}\n\
-             - **World Size**: {}×{}\n\n\
-             ## Parameters\n\
-             ```json\n{}\n```\n\n\
-             ## Final Metrics\n\
-             {}\n\n\
-             ## Events Summary\n\
-             - Total events: {}\n\
-             {}\n",
-            self.run_id,
-            self.run_start_time,
-            last_metrics.map_or(0, |m| m.frame),
-            self.metrics_history.len(),
-            env!("CARGO_PKG_VERSION"),
-            WORLD_WIDTH,
-            WORLD_HEIGHT,
-            serde_json::to_string_pretty(params).unwrap_or_default(),
-            if let Some(m) = last_metrics {
-                format!(
-                    "| Metric | Value |\n|--------|-------|\n\
-                     | Total Mass | {:.1} |\n\
-                     | Avg Energy | {:.4} |\n\
-                     | Entropy | {:.3} bits |\n\
-                     | Species | {} |\n\
-                     | Live Pixels | {} ({:.1}%) |\n\
-                     | Predator % | {:.1}% |\n\
-                     | FPS | {:.0} |",
-                    m.total_mass, m.avg_energy, m.entropy, m.species,
-                    m.live_pixels, m.live_fraction * 100.0,
-                    m.predator_fraction * 100.0, m.fps,
-                )
-            } else {
-                "No metrics collected.".to_string()
-            },
-            self.events.len(),
-            self.events.iter().rev().take(10)
-                .map(|e| format!("- {}", e.to_log_line()))
-                .collect::<Vec<_>>().join("\n"),
-        );
-
-        write!(file, "{}", report).map_err(|e| format!("Write error: {}", e))?;
+        write!(file, "{}", markdown).map_err(|e| format!("Write error: {}", e))?;
         log::info!("Exported report to {:?}", path);
         Ok(path)
     }
 
+    /// Report markdown for the current run. The report plots are written to
+    /// <run>/plots first so its image links resolve.
+    pub fn generate_report(&self, params: &SimulationParams) -> Result<String, String> {
+        if !self.metrics_history.is_empty() {
+            let mut figures = report_figures(&self.metrics_history, &self.events);
+            for (_, figure) in &mut figures {
+                figure.metadata = self.plot_metadata(&figure.title, params);
+            }
+            write_report_plots(&self.run_dir, &figures)?;
+        }
+        let template = load_template(&self.report_template)?;
+        render_template(&template, &self.report_context(params))
+    }
+
+    /// Data available to report templates (see templates/report.md.hbs).
+    pub fn report_context(&self, params: &SimulationParams) -> serde_json::Value {
+        let last = self.metrics_history.last();
+        let params_value = serde_json::to_value(params).unwrap_or_default();
+        let custom: Vec<serde_json::Value> = match last {
+            Some(m) => self
+                .custom_metrics
+                .iter()
+                .map(|c| serde_json::json!({ "name": c.name, "value": c.eval(m) }))
+                .collect(),
+            None => Vec::new(),
+        };
+        let plugins: Vec<serde_json::Value> = match last {
+            Some(m) => plugin_fields()
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| serde_json::json!({ "name": name, "value": m.plugin(i) }))
+                .collect(),
+            None => Vec::new(),
+        };
+        let mut timeline: Vec<&LabEvent> = self.events.iter().filter(|e| e.on_timeline()).collect();
+        timeline.sort_by_key(|e| e.frame);
+        let thumbnail = self.run_dir.join(THUMBNAIL_FILE).is_file().then_some(THUMBNAIL_FILE);
+        serde_json::json!({
+            "run": {
+                "id": self.run_id,
+                "start": self.run_start_time,
+                "frames": last.map_or(0, |m| m.frame),
+                "samples": self.metrics_history.len(),
+                "app_version": env!("CARGO_PKG_VERSION"),
+                "world_width": self.world_size[0],
+                "world_height": self.world_size[1],
+                "seed": params.effective_seed(),
+            },
+            "metadata": {
+                "authors": self.metadata.authors().join("; "),
+                "description": self.metadata.description.trim(),
+                "tags": self.metadata.tag_list().join(", "),
+                "license": self.metadata.license.trim(),
+            },
+            "params_json": serde_json::to_string_pretty(params).unwrap_or_default(),
+            "param_table": param_table(&params_value),
+            "params": params_value,
+            "metrics": last,
+            "custom_metrics": custom,
+            "plugin_metrics": plugins,
+            "plots": plot_files(&self.run_dir)
+                .into_iter()
+                .map(|(name, path)| serde_json::json!({ "name": name, "path": path }))
+                .collect::<Vec<_>>(),
+            "thumbnail": thumbnail,
+            "timeline": timeline
+                .iter()
+                .map(|e| serde_json::json!({ "frame": e.frame, "type": e.event_type, "details": e.details }))
+                .collect::<Vec<_>>(),
+            "event_count": self.events.len(),
+            "recent_events": self.events.iter().rev().take(10).map(|e| e.to_log_line()).collect::<Vec<_>>(),
+            "species": SpeciesSummary::from_history(&self.metrics_history),
+            "conditions": self.conditions.spans,
+            "territories": territory_shares(&self.territory_areas, 5)
+                .into_iter()
+                .map(|(species, cells, share)| serde_json::json!({ "species": species, "cells": cells, "share": share }))
+                .collect::<Vec<_>>(),
+            "sections": {
+                "final_metrics": final_metrics_section(last),
+                "plots": plots_section(&self.run_dir),
+                "timeline": timeline_section(&self.events),
+                "species": species_section(&self.metrics_history, &self.territory_areas),
+                "conditions": conditions_section(self, &self.condition_stats),
+            },
+        })
+    }
+
     /// Finalize the current run: export all data and archive.
     pub fn finalize_run(&mut self, params: &SimulationParams) {
         if !self.run_active {
@@ -397,22 +1364,63 @@ impl LabState {
             start_time: self.run_start_time.clone(),
             total_frames,
             metrics_count: self.metrics_history.len(),
+            final_metrics: self.metrics_history.last().cloned(),
+            thumbnail: None,
         });
+        self.thumbnail_pending = Some(self.run_dir.clone());
 
-        self.log_event(total_frames, "RUN_END", &format!("Run {} finalized", self.run_id));
-        self.set_status(format!("Run {} finalized — data exported", self.run_id));
+        self.log_event_with_payload(
+            total_frames,
+            EventSeverity::Info,
+            "RUN_END",
+            &format!("Run {} finalized", self.run_id),
+            serde_json::json!({ "run_id": self.run_id, "total_frames": total_frames }),
+        );
+        self.set_status(trf("status.run_finalized", &[("id", &self.run_id)]));
         self.run_active = false;
+        session_log::close();
     }
 
-    /// Save a screenshot to the run's screenshots directory.
-    pub fn save_screenshot(
-        &self,
-        frame: u32,
-        width: u32,
-        height: u32,
-        rgba_data: &[u8],
-        vis_mode: u32,
-    ) -> Result<PathBuf, String> {
+    /// Restore a completed run's parameters and restart the world with them
+    /// (same seed when the run used a fixed one).
+    pub fn reload_run(&mut self, index: usize, params: &mut SimulationParams) {
+        let Some(run) = self.completed_runs.get(index) else {
+            return;
+        };
+        let run_id = run.run_id.clone();
+        match run.load_params() {
+            Ok(loaded) => {
+                let vis = params.visualization_mode;
+                *params = loaded;
+                params.visualization_mode = vis;
+                self.restart_requested = true;
+                self.log_event_with_payload(
+                    self.current_frame,
+                    EventSeverity::Info,
+                    "RUN_RELOAD",
+                    &format!("Reloaded parameters of run {}", run_id),
+                    serde_json::json!({ "run_id": run_id, "seed": params.effective_seed() }),
+                );
+                self.set_status(trf("status.run_reloaded", &[("id", &run_id)]));
+            }
+            Err(e) => {
+                self.log_event_with_severity(self.current_frame, EventSeverity::Warn, "RUN_RELOAD", &e);
+                self.set_warning(trf("status.run_reload_failed", &[("error", &e)]));
+            }
+        }
+    }
+
+    /// Display name of a visualization mode, built-in or user-defined.
+    pub fn mode_name(&self, mode: u32) -> String {
+        match mode.checked_sub(VIS_MODE_COUNT) {
+            Some(k) => self.user_modes.get(k as usize).cloned().unwrap_or_else(|| "Unknown".to_string()),
+            None => visualization_mode_name(mode).to_string(),
+        }
+    }
+
+    /// Path for a new screenshot in the run's screenshots directory
+    /// (the directory is created if needed).
+    pub fn screenshot_path(&self, frame: u32, vis_mode: u32) -> Result<PathBuf, String> {
         let screenshots_dir = self.run_dir.join("screenshots");
         fs::create_dir_all(&screenshots_dir)
             .map_err(|e| format!("Failed to create screenshots dir: {}", e))?;
@@ -420,39 +1428,37 @@ impl LabState {
         let filename = format!(
             "frame{:06}_{}_{}.png",
             frame,
-            crate::config::visualization_mode_name(vis_mode).replace('/', "_"),
+            self.mode_name(vis_mode).replace('/', "_"),
             &self.run_id,
         );
-        let path = screenshots_dir.join(&filename);
-
-        image::save_buffer(
-            &path,
-            rgba_data,
-            width,
-            height,
-            image::ColorType::Rgba8,
-        )
-        .map_err(|e| format!("Failed to save screenshot: {}", e))?;
-
-        log::info!("Screenshot saved: {:?}", path);
-        Ok(path)
+        Ok(screenshots_dir.join(&filename))
     }
 
-    /// Set a temporary status message.
+    /// Show a status notification.
     pub fn set_status(&mut self, msg: String) {
-        self.status_message = Some((msg, Instant::now()));
+        self.notifications.notify(EventSeverity::Info, msg);
     }
 
-    /// Get the current status message (auto-clears after 5 seconds).
-    pub fn current_status(&mut self) -> Option<&str> {
-        let should_clear = matches!(
-            &self.status_message,
-            Some((_, when)) if when.elapsed().as_secs() >= 5
-        );
-        if should_clear {
-            self.status_message = None;
+    /// Show a warning notification (failed operation).
+    pub fn set_warning(&mut self, msg: String) {
+        self.notifications.notify(EventSeverity::Warn, msg);
+    }
+
+    /// Apply background job updates; failures are logged as events.
+    pub fn poll_jobs(&mut self, frame: u32) {
+        for job in self.notifications.poll() {
+            if job.event_type == "PORTRAIT" && job.result.is_ok() {
+                self.creatures = None; // show the new portrait
+            }
+            if let Err(e) = job.result {
+                self.log_event_with_severity(
+                    frame,
+                    EventSeverity::Warn,
+                    job.event_type,
+                    &format!("Failed: {}", e),
+                );
+            }
         }
-        self.status_message.as_ref().map(|(msg, _)| msg.as_str())
     }
 
     /// Load metrics from a previous run CSV for comparison.
@@ -460,6 +1466,10 @@ impl LabState {
         let content = fs::read_to_string(path)
             .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
         let mut records = Vec::new();
+        // Provider metric columns are found by name: they depend on the build
+        let header: Vec<&str> = content.lines().next().unwrap_or_default().split(',').collect();
+        let plugin_columns: Vec<Option<usize>> =
+            plugin_fields().into_iter().map(|name| header.iter().position(|h| *h == name)).collect();
         for (i, line) in content.lines().enumerate() {
             if i == 0 { continue; } // skip header
             let fields: Vec<&str> = line.split(',').collect();
@@ -489,6 +1499,10 @@ impl LabState {
                 genome_variance: fields.get(20).and_then(|s| s.parse().ok()).unwrap_or(0.0),
                 total_energy: fields.get(21).and_then(|s| s.parse().ok()).unwrap_or(0.0),
                 energy_flux: fields.get(22).and_then(|s| s.parse().ok()).unwrap_or(0.0),
+                plugins: plugin_columns
+                    .iter()
+                    .map(|c| c.and_then(|c| fields.get(c)).and_then(|s| s.parse().ok()).unwrap_or(f64::NAN))
+                    .collect(),
             };
             records.push(record);
         }
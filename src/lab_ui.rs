@@ -4,36 +4,225 @@
 // metrics visualization, experiment management, and data export.
 // ============================================================================
 
-use egui_plot::{Line, Plot, PlotPoints};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use crate::config::{visualization_mode_name, PerturbationType, SimulationParams, VIS_MODE_COUNT};
-use crate::lab::LabState;
-use crate::world::{target_total_mass, WORLD_HEIGHT, WORLD_WIDTH};
+use egui_plot::{Legend, Line, Plot, PlotPoints, Polygon, VLine};
+
+use crate::arena::{list_library, round_robin, standings, ArenaRequest, MatchOutcome};
+use crate::camera::{CameraState, OrbitCamera, WorldFit, RENDER_SCALE_RANGE};
+use crate::config::{
+    visualization_mode_key, visualization_mode_name, AlertRule, Comparator, CustomMetric, DisplayAdjust,
+    DisplayTransform, GeneConstraints, HistogramField, MutationOperator, SimulationParams, ANISOTROPY_RANGE, CAPACITY_MIN,
+    GENE_KEYS, GENE_LIMITS, MAX_SUM_INTERVAL, NOISE_AMPLITUDE_MAX, OMEGA_RANGE, VIS_MODE_COUNT,
+};
+use crate::display;
+use crate::dock::{self, LabTab};
+use crate::expr::{compile_metric, Func};
+use crate::file_dialog;
+use crate::flow::FlowPreset;
+use crate::i18n::{tr, trf, Language};
+use crate::kernel::KERNEL_LUT_MAX_BINS;
+use crate::invasion::InvasionSample;
+use crate::landscape::{FitnessLandscape, SweepAxis, MAX_RESOLUTION as LANDSCAPE_MAX_RESOLUTION};
+use crate::ltee::{list_samples, ARCHIVE_DIR};
+use crate::lab::{events_near, metrics_tsv, EventFilter, EventSeverity, LabEvent, LabState, MetricsRecord};
+use crate::metadata::ExperimentMetadata;
+use crate::metric_plugins::plugin_fields;
+use crate::metrics::{InteractionMatrix, AGG_CLASSES};
+use crate::pacing::{FrameStats, FrameTiming};
+use crate::creature::{list_creatures, CREATURES_DIR};
+use crate::pattern::PATTERN_DIR;
+use crate::plot_export::{PlotFigure, PlotFormat, PlotSeries, MAX_SIZE, MIN_SIZE};
+use crate::popgen::PopgenFormat;
+use crate::condition_stats::{available_groups, compare_groups, comparison_table, format_p, summary_cell, GroupRef};
+use crate::conditions::Condition;
+use crate::param_lock;
+use crate::preset_diff::PresetDiff;
+use crate::queue::{EntryStatus, QueueMode};
+use crate::replay::ReplaySource;
+use crate::reserves::{ReserveShape, ReserveZone, MAX_RESERVES};
+use crate::schedule::{active_schedule, Keyframe, ParamSchedule, ScheduleCurve, SCHEDULABLE};
+use crate::report::{inline_spans, parse_markdown, Block, ReportPreview, DEFAULT_TEMPLATE, TEMPLATES_DIR};
+use crate::resources::{format_bytes, BufferKind, PROJECTED_SIZES};
+use crate::session_log::{self, SessionLogLevel};
+use crate::startup::StartupChoice;
+use crate::state_io::SNAPSHOT_EXTENSION;
+use crate::legend::{legend_for, scale_color, ModeLegend};
+use crate::territory::species_color;
+use crate::thumbnail::THUMBNAIL_WIDTH;
+use crate::theme::{ColorPalette, UiTheme};
+use crate::user_modes::USER_MODES_DIR;
+use crate::particles::MAX_PARTICLES;
+use crate::perturbation::PerturbationType;
+use crate::tape::tape_size;
+use crate::world::{target_mass_of, GridType, MAX_WORLD_EDGE, MIN_WORLD_EDGE};
+
+/// Contents of the detached analysis window (analysis_window.rs): the
+/// analysis panel, under the same parameter guard as the main UI.
+pub fn render_analysis_window(ctx: &egui::Context, params: &mut SimulationParams, lab: &mut LabState) {
+    let guard = lab.param_locks.is_engaged(lab.run_active).then(|| (params.clone(), lab.events.len()));
+    egui::CentralPanel::default().show(ctx, |ui| render_analysis_tab(ui, params, lab));
+    if let Some((before, events)) = guard {
+        guard_params(&before, events, params, lab);
+    }
+}
 
 /// Main entry point for rendering all Research Lab UI panels.
 pub fn render_lab_ui(
     ctx: &egui::Context,
     params: &mut SimulationParams,
     lab: &mut LabState,
+    camera: &mut CameraState,
+) {
+    // Blocked edits are put back once the UI pass is over
+    let guard = lab.param_locks.is_engaged(lab.run_active).then(|| (params.clone(), lab.events.len()));
+    render_lab_ui_unguarded(ctx, params, lab, camera);
+    if let Some((before, events)) = guard {
+        guard_params(&before, events, params, lab);
+    }
+}
+
+fn render_lab_ui_unguarded(
+    ctx: &egui::Context,
+    params: &mut SimulationParams,
+    lab: &mut LabState,
+    camera: &mut CameraState,
 ) {
+    render_profile_overlay(ctx, lab, camera);
+    render_legend_overlay(ctx, params, lab);
+    render_histogram_overlay(ctx, params, lab);
+    lab.viewport_hovered = false;
+
+    // Toasts, job progress and history stay visible with the UI hidden
+    render_notifications(ctx, lab);
+    render_invasion_results(ctx, lab);
+    render_arena_results(ctx, lab);
+    render_preset_diff(ctx, params, lab);
+    render_startup_recommendation(ctx, params, lab, camera);
+
     if !lab.show_lab_ui {
         // Minimal overlay when UI is hidden
         render_minimal_overlay(ctx, params, lab);
         return;
     }
 
-    render_left_panel(ctx, params, lab);
+    render_dock(ctx, params, lab, camera);
+}
+
+/// Put back the edits the locks block and log the attempt. When every edit
+/// of the pass was blocked, the PARAM_CHANGE events it logged (from index
+/// `events` on) are dropped: those changes did not happen.
+fn guard_params(before: &SimulationParams, events: usize, params: &mut SimulationParams, lab: &mut LabState) {
+    let Some(blocked) = param_lock::enforce(&lab.param_locks, lab.run_active, before, params) else {
+        return;
+    };
+    if blocked.all && events <= lab.events.len() {
+        let mut i = events;
+        while i < lab.events.len() {
+            if lab.events[i].event_type == "PARAM_CHANGE" {
+                lab.events.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+    let fields: Vec<&str> = blocked.attempted.keys().map(String::as_str).collect();
+    let fields = fields.join(", ");
+    lab.log_event_with_payload(
+        lab.current_frame,
+        EventSeverity::Warn,
+        "PARAM_BLOCKED",
+        &format!("Blocked edit of {}", fields),
+        serde_json::json!({ "attempted": blocked.attempted }),
+    );
+    lab.set_warning(trf("status.param_blocked", &[("fields", &fields)]));
+}
+
+// ======================== Dock Layout ========================
+
+/// Hosts the lab panels as dockable tabs. Tabs can be dragged into splits,
+/// stacked, or torn off into floating windows (inside the main window; egui
+/// here renders a single native viewport).
+struct LabTabViewer<'a> {
+    params: &'a mut SimulationParams,
+    lab: &'a mut LabState,
+    camera: &'a mut CameraState,
+}
+
+impl egui_dock::TabViewer for LabTabViewer<'_> {
+    type Tab = LabTab;
+
+    fn title(&mut self, tab: &mut LabTab) -> egui::WidgetText {
+        tab.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut LabTab) {
+        match tab {
+            LabTab::Viewport => {
+                // Transparent hole: the simulation shows through, and mouse
+                // input over it is routed to the camera and profile tool.
+                let (_, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+                self.lab.viewport_hovered = response.hovered();
+            }
+            LabTab::Controls => render_controls_tab(ui, self.params, self.lab, self.camera),
+            LabTab::Analysis => render_analysis_tab(ui, self.params, self.lab),
+            LabTab::Logs => render_logs_tab(ui, self.lab),
+            LabTab::Performance => render_performance_tab(ui, self.lab),
+            LabTab::Resources => render_resources_tab(ui, self.lab),
+            LabTab::Queue => render_queue_tab(ui, self.params, self.lab),
+            LabTab::Report => render_report_tab(ui, self.params, self.lab),
+        }
+    }
+
+    fn closeable(&mut self, tab: &mut LabTab) -> bool {
+        tab.closeable()
+    }
+
+    fn allowed_in_windows(&self, tab: &mut LabTab) -> bool {
+        *tab != LabTab::Viewport
+    }
 
-    if lab.show_analysis_panel {
-        render_right_analysis_panel(ctx, lab);
+    fn clear_background(&self, tab: &LabTab) -> bool {
+        *tab != LabTab::Viewport
     }
 
-    if lab.show_logs_panel {
-        render_bottom_logs_panel(ctx, lab);
+    fn scroll_bars(&self, _tab: &LabTab) -> [bool; 2] {
+        [false, false] // each tab manages its own scroll areas
     }
+}
+
+fn render_dock(
+    ctx: &egui::Context,
+    params: &mut SimulationParams,
+    lab: &mut LabState,
+    camera: &mut CameraState,
+) {
+    // Take the layout out of `lab` so the tab viewer can borrow it mutably.
+    let mut dock = std::mem::replace(&mut lab.dock_state, egui_dock::DockState::new(Vec::new()));
+    dock::sync_tab(&mut dock, LabTab::Analysis, lab.show_analysis_panel && !lab.analysis_detached);
+    dock::sync_tab(&mut dock, LabTab::Logs, lab.show_logs_panel);
+    dock::sync_tab(&mut dock, LabTab::Performance, lab.show_performance_panel);
+    dock::sync_tab(&mut dock, LabTab::Resources, lab.show_resources_panel);
+    dock::sync_tab(&mut dock, LabTab::Queue, lab.show_queue_panel);
+    dock::sync_tab(&mut dock, LabTab::Report, lab.show_report_panel);
+
+    let mut viewer = LabTabViewer { params, lab, camera };
+    egui_dock::DockArea::new(&mut dock)
+        .style(egui_dock::Style::from_egui(ctx.style().as_ref()))
+        .show_leaf_collapse_buttons(false)
+        .show(ctx, &mut viewer);
 
-    // Status bar
-    render_status_bar(ctx, lab);
+    // Closing a tab from its close button clears the matching toggle.
+    if !lab.analysis_detached {
+        lab.show_analysis_panel = dock.find_tab(&LabTab::Analysis).is_some();
+    }
+    lab.show_logs_panel = dock.find_tab(&LabTab::Logs).is_some();
+    lab.show_performance_panel = dock.find_tab(&LabTab::Performance).is_some();
+    lab.show_resources_panel = dock.find_tab(&LabTab::Resources).is_some();
+    lab.show_queue_panel = dock.find_tab(&LabTab::Queue).is_some();
+    lab.show_report_panel = dock.find_tab(&LabTab::Report).is_some();
+    lab.dock_state = dock;
 }
 
 // ======================== Minimal Overlay ========================
@@ -70,7 +259,7 @@ fn render_minimal_overlay(
                         );
                         ui.add_space(20.0);
                         ui.label(
-                            egui::RichText::new(format!("Frame: {}", frame))
+                            egui::RichText::new(trf("overlay.frame", &[("frame", &frame)]))
                                 .monospace()
                                 .size(15.0)
                                 .color(egui::Color32::from_rgb(120, 220, 160)),
@@ -83,7 +272,7 @@ fn render_minimal_overlay(
                         );
                         ui.add_space(12.0);
                         ui.label(
-                            egui::RichText::new(format!("FPS: {:.0}", fps))
+                            egui::RichText::new(trf("overlay.fps", &[("fps", &format!("{:.0}", fps))]))
                                 .monospace()
                                 .size(15.0)
                                 .color(egui::Color32::from_rgb(255, 200, 100)),
@@ -91,7 +280,7 @@ fn render_minimal_overlay(
                         if params.paused {
                             ui.add_space(12.0);
                             ui.label(
-                                egui::RichText::new("⏸ PAUSED")
+                                egui::RichText::new(tr("common.paused"))
                                     .size(15.0)
                                     .strong()
                                     .color(egui::Color32::from_rgb(255, 120, 100)),
@@ -101,7 +290,7 @@ fn render_minimal_overlay(
                     
                     ui.add_space(6.0);
                     ui.label(
-                        egui::RichText::new("F1 → Research Lab  •  Space → Pause  •  WASD → Pan  •  Q/E → Zoom")
+                        egui::RichText::new(tr("overlay.hint"))
                             .size(12.0)
                             .color(egui::Color32::from_rgb(130, 140, 160)),
                     );
@@ -109,69 +298,154 @@ fn render_minimal_overlay(
         });
 }
 
-// ======================== Left Panel ========================
+// ======================== Controls Tab ========================
 
-fn render_left_panel(
-    ctx: &egui::Context,
+fn render_controls_tab(
+    ui: &mut egui::Ui,
     params: &mut SimulationParams,
     lab: &mut LabState,
+    camera: &mut CameraState,
 ) {
-    egui::SidePanel::left("lab_panel")
-        .default_width(320.0)
-        .min_width(280.0)
-        .max_width(450.0)
-        .show(ctx, |ui| {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.add_space(8.0);
-                ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new("🔬").size(28.0));
-                    ui.add_space(8.0);
-                    ui.vertical(|ui| {
-                        ui.label(egui::RichText::new("EvoLenia").size(22.0).strong().color(egui::Color32::from_rgb(100, 200, 255)));
-                        ui.label(egui::RichText::new("Research Lab v2.0").size(13.0).color(egui::Color32::from_rgb(150, 150, 170)));
-                    });
-                });
-                ui.add_space(8.0);
-                ui.separator();
-
-                render_control_section(ui, params, lab);
-                ui.separator();
-                render_params_section(ui, params, lab);
-                ui.separator();
-                render_perturbation_section(ui, params, lab);
-                ui.separator();
-                render_visualization_section(ui, params);
-                ui.separator();
-                render_experiment_section(ui, params, lab);
-                ui.separator();
-                render_capture_section(ui, params, lab);
-                ui.separator();
-                render_view_toggles(ui, lab);
-
-                ui.add_space(10.0);
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("🔬").size(28.0));
+            ui.add_space(8.0);
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("EvoLenia").size(22.0).strong().color(egui::Color32::from_rgb(100, 200, 255)));
+                ui.label(egui::RichText::new(tr("lab.subtitle")).size(13.0).color(egui::Color32::from_rgb(150, 150, 170)));
+            });
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let center = &mut lab.notifications;
+                let bell = if center.unread > 0 { format!("🔔 {}", center.unread) } else { "🔔".to_string() };
+                if ui.button(bell).on_hover_text(tr("notify.history")).clicked() {
+                    if center.show_history {
+                        center.show_history = false;
+                    } else {
+                        center.open_history();
+                    }
+                }
             });
         });
+        ui.add_space(8.0);
+        ui.separator();
+
+        render_control_section(ui, params, lab);
+        ui.separator();
+        render_lock_section(ui, params, lab);
+        ui.separator();
+        render_params_section(ui, params, lab);
+        ui.separator();
+        render_schedule_section(ui, params, lab);
+        ui.separator();
+        render_perturbation_section(ui, params, lab);
+        ui.separator();
+        render_particle_section(ui, params, lab);
+        ui.separator();
+        render_flow_section(ui, params, lab);
+        ui.separator();
+        render_reserve_section(ui, params, lab);
+        ui.separator();
+        render_pattern_section(ui, lab);
+        ui.separator();
+        render_transplant_section(ui, lab);
+        ui.separator();
+        render_invasion_section(ui, lab);
+        ui.separator();
+        render_ltee_section(ui, params, lab);
+        ui.separator();
+        render_arena_section(ui, lab);
+        ui.separator();
+        render_portrait_section(ui, lab);
+        ui.separator();
+        render_visualization_section(ui, params, lab);
+        ui.separator();
+        render_experiment_section(ui, params, lab);
+        ui.separator();
+        render_capture_section(ui, params, lab);
+        ui.separator();
+        render_view_toggles(ui, lab, camera);
+
+        ui.add_space(10.0);
+    });
 }
 
 // ======================== Control Section ========================
 
+/// Safety mode and per-parameter locks. Lock changes go to LabState, not
+/// to the parameters, so the guard never blocks them.
+fn render_lock_section(ui: &mut egui::Ui, params: &SimulationParams, lab: &mut LabState) {
+    let run_active = lab.run_active;
+    let locks = &mut lab.param_locks;
+    let header = if locks.is_engaged(run_active) { format!("🔒 {}", tr("lock.title")) } else { tr("lock.title").to_string() };
+    let changes = egui::CollapsingHeader::new(header).id_salt("param_locks").show(ui, |ui| {
+        let mut changes = Vec::new();
+        if ui.checkbox(&mut locks.safety_mode, tr("lock.safety_mode")).on_hover_text(tr("lock.safety_mode_hint")).changed() {
+            changes.push(format!("safety_mode={}", locks.safety_mode));
+        }
+        if locks.safety_mode {
+            if !run_active {
+                ui.label(egui::RichText::new(tr("lock.no_run")).small().color(egui::Color32::GRAY));
+            } else if locks.unlocked {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(tr("lock.unlocked")).small().color(egui::Color32::from_rgb(255, 200, 80)));
+                    if ui.small_button(tr("lock.relock")).clicked() {
+                        locks.unlocked = false;
+                        changes.push(String::from("unlocked=false"));
+                    }
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(tr("lock.engaged")).small().color(egui::Color32::from_rgb(120, 200, 120)));
+                    if ui.small_button(tr("lock.unlock")).on_hover_text(tr("lock.unlock_hint")).clicked() {
+                        locks.unlocked = true;
+                        changes.push(String::from("unlocked=true"));
+                    }
+                });
+            }
+        }
+        ui.add_space(4.0);
+        ui.label(egui::RichText::new(tr("lock.fields_hint")).small().color(egui::Color32::GRAY));
+        egui::Grid::new("param_lock_grid").num_columns(2).show(ui, |ui| {
+            for (i, field) in param_lock::field_names(params).into_iter().enumerate() {
+                let mut locked = locks.locked.contains(&field);
+                if ui.checkbox(&mut locked, egui::RichText::new(&field).small().monospace()).changed() {
+                    changes.push(format!("{}={}", field, locked));
+                    if locked {
+                        locks.locked.insert(field);
+                    } else {
+                        locks.locked.remove(&field);
+                    }
+                }
+                if i % 2 == 1 {
+                    ui.end_row();
+                }
+            }
+        });
+        changes
+    });
+    for change in changes.body_returned.unwrap_or_default() {
+        lab.log_event(lab.current_frame, "PARAM_LOCK", &change);
+    }
+}
+
 fn render_control_section(
     ui: &mut egui::Ui,
     params: &mut SimulationParams,
     lab: &mut LabState,
 ) {
-    ui.collapsing("▶ Control", |ui| {
+    egui::CollapsingHeader::new(tr("control.title")).id_salt("control").show(ui, |ui| {
         ui.horizontal(|ui| {
-            let play_label = if params.paused { "▶ Play" } else { "⏸ Pause" };
+            let play_label = if params.paused { tr("control.play") } else { tr("control.pause") };
             if ui.button(play_label).clicked() {
                 params.paused = !params.paused;
-                lab.log_event(0, "CONTROL", if params.paused { "Paused" } else { "Resumed" });
+                lab.log_event(lab.current_frame, "CONTROL", if params.paused { "Paused" } else { "Resumed" });
             }
-            if ui.button("⏭ Step").clicked() {
+            if ui.button(tr("control.step")).clicked() {
                 lab.step_requested = true;
                 params.paused = true;
             }
-            if ui.button("🔄 Restart").clicked() {
+            if ui.button(tr("control.restart")).clicked() {
                 lab.restart_requested = true;
             }
         });
@@ -179,31 +453,107 @@ fn render_control_section(
         ui.add_space(4.0);
 
         ui.horizontal(|ui| {
-            ui.label("Speed:");
-            if ui.add(egui::Slider::new(&mut params.simulation_speed, 1..=20).suffix("x")).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("speed={}", params.simulation_speed));
+            ui.label(tr("control.speed"));
+            let speed = egui::Slider::new(&mut params.simulation_speed, 1..=20).suffix("x");
+            if ui.add_enabled(!params.step_budget.enabled, speed).changed() {
+                lab.log_param_change(lab.current_frame, "speed", &params.simulation_speed.to_string());
+            }
+        });
+
+        // Wall-clock step budget: steps per frame follow a target UI frame rate
+        ui.horizontal(|ui| {
+            let budget = &mut params.step_budget;
+            if ui.checkbox(&mut budget.enabled, tr("control.step_budget")).on_hover_text(tr("control.step_budget_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "step_budget", &budget.enabled.to_string());
+            }
+            let target = egui::Slider::new(&mut budget.target_fps, 10.0..=120.0).step_by(5.0).suffix(" FPS");
+            if ui.add_enabled(budget.enabled, target).changed() {
+                lab.log_param_change(lab.current_frame, "target_fps", &format!("{:.0}", budget.target_fps));
+            }
+        });
+        if params.step_budget.enabled {
+            let status = match lab.gpu_step_ms {
+                Some(ms) => trf("control.step_budget_gpu", &[("steps", &lab.steps_per_frame), ("ms", &format!("{:.3}", ms))]),
+                None => trf("control.step_budget_frame", &[("steps", &lab.steps_per_frame)]),
+            };
+            ui.label(egui::RichText::new(status).small().color(egui::Color32::from_rgb(150, 200, 150)));
+        }
+
+        // Speed guard: steps per frame drop while the UI is slow to respond
+        ui.horizontal(|ui| {
+            let guard = &mut params.speed_guard;
+            ui.add_enabled_ui(!params.step_budget.enabled, |ui| {
+                if ui.checkbox(&mut guard.enabled, tr("control.speed_guard")).on_hover_text(tr("control.speed_guard_hint")).changed() {
+                    lab.log_param_change(lab.current_frame, "speed_guard", &guard.enabled.to_string());
+                }
+                let max_ms = egui::DragValue::new(&mut guard.max_frame_ms).range(5.0..=500.0).speed(1.0).suffix(" ms");
+                if ui.add_enabled(guard.enabled, max_ms).changed() {
+                    lab.log_param_change(lab.current_frame, "speed_guard_ms", &format!("{:.0}", guard.max_frame_ms));
+                }
+            });
+        });
+        if let Some(steps) = lab.speed_limit.filter(|_| !params.step_budget.enabled) {
+            let status = trf("control.speed_guard_active", &[("steps", &steps), ("speed", &params.simulation_speed)]);
+            ui.label(egui::RichText::new(status).small().color(egui::Color32::from_rgb(230, 180, 80)));
+        }
+
+        // Render cadence: frames in between only simulate
+        ui.horizontal(|ui| {
+            let cadence = &mut params.render_cadence;
+            ui.label(tr("control.render_every")).on_hover_text(tr("control.render_every_hint"));
+            let every = egui::DragValue::new(&mut cadence.every_n_steps).range(1..=1000).suffix(tr("control.render_every_suffix"));
+            if ui.add(every).changed() {
+                lab.log_param_change(lab.current_frame, "render_every_n_steps", &cadence.every_n_steps.to_string());
+            }
+            ui.label(tr("control.render_max_fps")).on_hover_text(tr("control.render_max_fps_hint"));
+            let max_fps = egui::DragValue::new(&mut cadence.max_fps)
+                .range(0.0..=240.0)
+                .speed(1.0)
+                .suffix(" FPS");
+            if ui.add(max_fps).changed() {
+                lab.log_param_change(lab.current_frame, "render_max_fps", &format!("{:.0}", cadence.max_fps));
             }
         });
 
+        // Low-power mode while the window is unfocused or minimized
         ui.horizontal(|ui| {
-            ui.label("Time Step:");
+            let throttle = &mut lab.background_throttle;
+            ui.checkbox(&mut throttle.enabled, tr("control.background_throttle"))
+                .on_hover_text(tr("control.background_throttle_hint"));
+            let rate = egui::DragValue::new(&mut throttle.steps_per_sec).range(0..=1000).suffix(tr("control.background_steps_suffix"));
+            ui.add_enabled(throttle.enabled, rate);
+        });
+
+        // Debug: unfused velocity pass, for comparing against the fused evolution pass
+        if ui.checkbox(&mut params.separate_velocity_pass, tr("control.separate_velocity"))
+            .on_hover_text(tr("control.separate_velocity_hint"))
+            .changed()
+        {
+            lab.log_param_change(lab.current_frame, "separate_velocity", &params.separate_velocity_pass.to_string());
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("control.time_step"));
             if ui.add(egui::Slider::new(&mut params.time_step, 0.1..=2.0).step_by(0.05)).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("time_step={:.2}", params.time_step));
+                lab.log_param_change(lab.current_frame, "time_step", &format!("{:.2}", params.time_step));
             }
         });
+        schedule_overlay(ui, params, "time_step", lab.current_frame);
 
         ui.horizontal(|ui| {
-            ui.label("Diag interval:");
+            ui.label(tr("control.diag_interval"));
             ui.add(egui::DragValue::new(&mut lab.metrics_sample_interval).range(10..=5000));
         });
 
         // Effective values
         ui.add_space(2.0);
         ui.label(
-            egui::RichText::new(format!(
-                "Effective dt: {:.4}  |  Steps/frame: {}",
-                0.1 * params.time_step,
-                params.simulation_speed,
+            egui::RichText::new(trf(
+                "control.effective",
+                &[
+                    ("dt", &format!("{:.4}", 0.1 * params.time_step)),
+                    ("steps", &if params.step_budget.enabled { lab.steps_per_frame } else { lab.speed_limit.unwrap_or(params.simulation_speed) }),
+                ],
             ))
             .small()
             .color(egui::Color32::from_rgb(150, 200, 150)),
@@ -218,491 +568,3552 @@ fn render_params_section(
     params: &mut SimulationParams,
     lab: &mut LabState,
 ) {
-    ui.collapsing("🧬 Simulation Parameters", |ui| {
+    let world = lab.world_size;
+    egui::CollapsingHeader::new(tr("params.title")).id_salt("params").show(ui, |ui| {
         ui.group(|ui| {
-            ui.label(egui::RichText::new("Evolution / Mutation").strong());
+            ui.label(egui::RichText::new(tr("params.evolution")).strong());
             if ui.add(
                 egui::Slider::new(&mut params.mutation_rate, 0.1..=5.0)
-                    .text("Mutation Rate")
+                    .text(tr("params.mutation_rate"))
                     .step_by(0.1),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("mutation_rate={:.1}", params.mutation_rate));
+                lab.log_param_change(lab.current_frame, "mutation_rate", &format!("{:.1}", params.mutation_rate));
+            }
+            schedule_overlay(ui, params, "mutation_rate", lab.current_frame);
+            let mutation = &mut params.mutation;
+            let before = mutation.operator;
+            egui::ComboBox::new("mutation_operator", tr("mutation.operator"))
+                .selected_text(tr(mutation.operator.label_key()))
+                .show_ui(ui, |ui| {
+                    for op in MutationOperator::all() {
+                        ui.selectable_value(&mut mutation.operator, *op, tr(op.label_key()));
+                    }
+                })
+                .response
+                .on_hover_text(tr("mutation.operator_hint"));
+            if mutation.operator != before {
+                lab.log_param_change(lab.current_frame, "mutation_operator", &format!("{:?}", mutation.operator));
+            }
+            if mutation.operator == MutationOperator::Jump && ui.add(
+                egui::Slider::new(&mut mutation.jump_size, 1.0..=100.0)
+                    .logarithmic(true)
+                    .text(tr("mutation.jump_size")),
+            ).on_hover_text(tr("mutation.jump_size_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "mutation_jump_size", &format!("{:.1}", mutation.jump_size));
+            }
+            if mutation.operator == MutationOperator::Correlated && ui.add(
+                egui::Slider::new(&mut mutation.correlation, 0.0..=1.0)
+                    .text(tr("mutation.correlation"))
+                    .step_by(0.05),
+            ).on_hover_text(tr("mutation.correlation_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "mutation_correlation", &format!("{:.2}", mutation.correlation));
+            }
+            ui.label(egui::RichText::new(tr("mutation.gene_rates")).small());
+            for (rate, key) in mutation.gene_rates.iter_mut().zip(GENE_KEYS) {
+                if ui.add(egui::Slider::new(rate, 0.0..=4.0).text(tr(key)).step_by(0.05)).changed() {
+                    lab.log_param_change(lab.current_frame, &format!("{}_rate", &key["mutation.gene_".len()..]), &format!("{:.2}", rate));
+                }
+            }
+        });
+
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(tr("genes.title")).strong());
+                if ui.small_button(tr("genes.reset")).clicked() {
+                    params.gene_constraints = GeneConstraints::default();
+                    lab.log_param_change(lab.current_frame, "gene_constraints", "reset");
+                }
+            });
+            ui.label(egui::RichText::new(tr("genes.hint")).small().color(egui::Color32::GRAY));
+            let constraints = &mut params.gene_constraints;
+            egui::Grid::new("gene_constraints").num_columns(4).striped(true).show(ui, |ui| {
+                ui.label("");
+                ui.label(tr("genes.min"));
+                ui.label(tr("genes.max"));
+                ui.label(tr("genes.lock"));
+                ui.end_row();
+                for (g, key) in GENE_KEYS.iter().enumerate() {
+                    let (lo, hi) = GENE_LIMITS[g];
+                    let speed = (hi - lo) / 200.0;
+                    let name = &key["mutation.gene_".len()..];
+                    ui.label(tr(key));
+                    let max = constraints.max[g];
+                    if ui.add(egui::DragValue::new(&mut constraints.min[g]).range(lo..=max).speed(speed)).changed() {
+                        lab.log_param_change(lab.current_frame, &format!("{name}_min"), &format!("{:.3}", constraints.min[g]));
+                    }
+                    let min = constraints.min[g];
+                    if ui.add(egui::DragValue::new(&mut constraints.max[g]).range(min..=hi).speed(speed)).changed() {
+                        lab.log_param_change(lab.current_frame, &format!("{name}_max"), &format!("{:.3}", constraints.max[g]));
+                    }
+                    if ui.checkbox(&mut constraints.locked[g], "").on_hover_text(tr("genes.lock_hint")).changed() {
+                        lab.log_param_change(lab.current_frame, &format!("{name}_locked"), &constraints.locked[g].to_string());
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("params.convolution")).strong());
+            let conv = &mut params.convolution;
+            if ui.checkbox(&mut conv.separable, tr("params.separable")).on_hover_text(tr("params.separable_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "separable", &conv.separable.to_string());
+            }
+            ui.add_enabled_ui(conv.separable, |ui| {
+                if ui.checkbox(&mut conv.compare_exact, tr("params.conv_compare")).changed() {
+                    lab.log_param_change(lab.current_frame, "conv_compare", &conv.compare_exact.to_string());
+                }
+            });
+            if let Some(error) = lab.convolution_error {
+                ui.label(trf("params.conv_error", &[("error", &format!("{:.5}", error))]));
+            }
+            if ui.checkbox(&mut conv.kernel_lut, tr("params.kernel_lut")).on_hover_text(tr("params.kernel_lut_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "kernel_lut", &conv.kernel_lut.to_string());
+            }
+            if conv.kernel_lut && ui.add(
+                egui::Slider::new(&mut conv.lut_bins, 8..=KERNEL_LUT_MAX_BINS)
+                    .text(tr("params.lut_bins")),
+            ).changed() {
+                lab.log_param_change(lab.current_frame, "lut_bins", &conv.lut_bins.to_string());
             }
         });
 
         ui.group(|ui| {
-            ui.label(egui::RichText::new("Predation").strong());
+            ui.label(egui::RichText::new(tr("params.predation")).strong());
             if ui.add(
                 egui::Slider::new(&mut params.predation_factor, 0.0..=3.0)
-                    .text("Predation Factor")
+                    .text(tr("params.predation_factor"))
                     .step_by(0.1),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("predation={:.1}", params.predation_factor));
+                lab.log_param_change(lab.current_frame, "predation", &format!("{:.1}", params.predation_factor));
             }
+            schedule_overlay(ui, params, "predation_factor", lab.current_frame);
         });
 
         ui.group(|ui| {
-            ui.label(egui::RichText::new("Resources (Gray-Scott)").strong());
+            ui.label(egui::RichText::new(tr("params.resources")).strong());
             if ui.add(
                 egui::Slider::new(&mut params.resource_diffusion, 0.0..=0.5)
-                    .text("Diffusion")
+                    .text(tr("params.diffusion"))
                     .step_by(0.01),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("diffusion={:.3}", params.resource_diffusion));
+                lab.log_param_change(lab.current_frame, "diffusion", &format!("{:.3}", params.resource_diffusion));
             }
+            schedule_overlay(ui, params, "resource_diffusion", lab.current_frame);
             if ui.add(
                 egui::Slider::new(&mut params.resource_feed_rate, 0.0..=0.1)
-                    .text("Feed Rate")
+                    .text(tr("params.feed_rate"))
                     .step_by(0.001),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("feed_rate={:.4}", params.resource_feed_rate));
+                lab.log_param_change(lab.current_frame, "feed_rate", &format!("{:.4}", params.resource_feed_rate));
             }
+            schedule_overlay(ui, params, "resource_feed_rate", lab.current_frame);
             if ui.add(
                 egui::Slider::new(&mut params.resource_consumption, 0.0..=0.3)
-                    .text("Consumption")
+                    .text(tr("params.consumption"))
                     .step_by(0.01),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("consumption={:.3}", params.resource_consumption));
+                lab.log_param_change(lab.current_frame, "consumption", &format!("{:.3}", params.resource_consumption));
+            }
+            schedule_overlay(ui, params, "resource_consumption", lab.current_frame);
+        });
+
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("params.transport")).strong());
+            if ui.add(
+                egui::Slider::new(&mut params.anisotropy, ANISOTROPY_RANGE.0..=ANISOTROPY_RANGE.1)
+                    .text(tr("params.anisotropy"))
+                    .logarithmic(true),
+            ).on_hover_text(tr("params.anisotropy_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "anisotropy", &format!("{:.2}", params.anisotropy));
             }
+            schedule_overlay(ui, params, "anisotropy", lab.current_frame);
+            let rotation = &mut params.rotation;
+            if ui.checkbox(&mut rotation.enabled, tr("params.rotation")).on_hover_text(tr("params.rotation_hint")).changed() {
+                lab.log_param_change(lab.current_frame, "rotation", &rotation.enabled.to_string());
+            }
+            ui.add_enabled_ui(rotation.enabled, |ui| {
+                if ui.add(
+                    egui::Slider::new(&mut rotation.omega, -OMEGA_RANGE..=OMEGA_RANGE)
+                        .text(tr("params.rotation_omega"))
+                        .step_by(0.005),
+                ).changed() {
+                    lab.log_param_change(lab.current_frame, "rotation_omega", &format!("{:.3}", rotation.omega));
+                }
+                ui.add(egui::Slider::new(&mut rotation.center[0], 0.0..=1.0).text(tr("params.rotation_center_x")));
+                ui.add(egui::Slider::new(&mut rotation.center[1], 0.0..=1.0).text(tr("params.rotation_center_y")));
+            });
         });
 
         ui.group(|ui| {
-            ui.label(egui::RichText::new("Mass Normalization").strong());
-            if ui.checkbox(&mut params.mass_normalization_enabled, "Enabled").changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("norm_enabled={}", params.mass_normalization_enabled));
+            ui.label(egui::RichText::new(tr("params.normalization")).strong());
+            if ui.checkbox(&mut params.mass_normalization_enabled, tr("common.enabled")).changed() {
+                lab.log_param_change(lab.current_frame, "norm_enabled", &params.mass_normalization_enabled.to_string());
             }
             if params.mass_normalization_enabled {
                 if ui.add(
                     egui::Slider::new(&mut params.mass_damping, 0.05..=1.0)
-                        .text("Damping")
+                        .text(tr("params.damping"))
                         .step_by(0.05),
                 ).changed() {
-                    lab.log_event(0, "PARAM_CHANGE", &format!("damping={:.2}", params.mass_damping));
+                    lab.log_param_change(lab.current_frame, "damping", &format!("{:.2}", params.mass_damping));
                 }
+                schedule_overlay(ui, params, "mass_damping", lab.current_frame);
                 if ui.add(
                     egui::Slider::new(&mut params.target_mass_multiplier, 0.1..=3.0)
-                        .text("Target Mass ×")
+                        .text(tr("params.target_mass_mult"))
                         .step_by(0.1),
                 ).changed() {
-                    lab.log_event(0, "PARAM_CHANGE", &format!("target_mass_mult={:.1}", params.target_mass_multiplier));
+                    lab.log_param_change(lab.current_frame, "target_mass_mult", &format!("{:.1}", params.target_mass_multiplier));
+                }
+                schedule_overlay(ui, params, "target_mass_multiplier", lab.current_frame);
+                ui.label(
+                    egui::RichText::new(trf(
+                        "params.target",
+                        &[("mass", &format!("{:.0}", target_mass_of(world) * params.target_mass_multiplier))],
+                    ))
+                    .small()
+                    .color(egui::Color32::from_rgb(150, 200, 150)),
+                );
+                let cadence = &mut params.normalization_cadence;
+                if ui.add(
+                    egui::Slider::new(&mut cadence.sum_interval, 1..=MAX_SUM_INTERVAL)
+                        .text(tr("params.sum_interval")),
+                ).on_hover_text(tr("params.sum_interval_hint")).changed() {
+                    lab.log_param_change(lab.current_frame, "sum_interval", &cadence.sum_interval.to_string());
+                }
+                if ui.add(
+                    egui::Slider::new(&mut cadence.drift_threshold, 0.0..=0.05)
+                        .text(tr("params.drift_threshold"))
+                        .step_by(0.001),
+                ).on_hover_text(tr("params.drift_threshold_hint")).changed() {
+                    lab.log_param_change(lab.current_frame, "drift_threshold", &format!("{:.3}", cadence.drift_threshold));
+                }
+            }
+        });
+
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("params.noise")).strong());
+            let noise = &mut params.demographic_noise;
+            if ui.checkbox(&mut noise.enabled, tr("common.enabled"))
+                .on_hover_text(tr("params.noise_hint"))
+                .changed()
+            {
+                lab.log_param_change(lab.current_frame, "noise_enabled", &noise.enabled.to_string());
+            }
+            ui.add_enabled_ui(noise.enabled, |ui| {
+                if ui.add(
+                    egui::Slider::new(&mut noise.amplitude, 0.0..=NOISE_AMPLITUDE_MAX)
+                        .text(tr("params.noise_amplitude"))
+                        .step_by(0.005),
+                ).on_hover_text(tr("params.noise_amplitude_hint")).changed() {
+                    lab.log_param_change(lab.current_frame, "noise_amplitude", &format!("{:.3}", noise.amplitude));
+                }
+                ui.horizontal(|ui| {
+                    if ui.add(egui::DragValue::new(&mut noise.seed).prefix(tr("params.noise_seed"))).changed() {
+                        lab.log_param_change(lab.current_frame, "noise_seed", &noise.seed.to_string());
+                    }
+                    if ui.button("🎲").on_hover_text(tr("params.noise_reseed")).clicked() {
+                        noise.seed = rand::random();
+                        lab.log_param_change(lab.current_frame, "noise_seed", &noise.seed.to_string());
+                    }
+                });
+            });
+        });
+
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("params.capacity")).strong());
+            let capacity = &mut params.carrying_capacity;
+            if ui.checkbox(&mut capacity.enabled, tr("common.enabled"))
+                .on_hover_text(tr("params.capacity_hint"))
+                .changed()
+            {
+                lab.log_param_change(lab.current_frame, "capacity_enabled", &capacity.enabled.to_string());
+            }
+            ui.add_enabled_ui(capacity.enabled, |ui| {
+                if ui.add(
+                    egui::Slider::new(&mut capacity.base, CAPACITY_MIN..=1.0)
+                        .text(tr("params.capacity_base")),
+                ).changed() {
+                    lab.log_param_change(lab.current_frame, "capacity_base", &format!("{:.2}", capacity.base));
+                }
+                if ui.add(
+                    egui::Slider::new(&mut capacity.per_resource, 0.0..=2.0)
+                        .text(tr("params.capacity_per_resource")),
+                ).on_hover_text(tr("params.capacity_per_resource_hint")).changed() {
+                    lab.log_param_change(lab.current_frame, "capacity_per_resource", &format!("{:.2}", capacity.per_resource));
                 }
                 ui.label(
-                    egui::RichText::new(format!(
-                        "Target: {:.0}",
-                        target_total_mass() * params.target_mass_multiplier
+                    egui::RichText::new(trf(
+                        "params.capacity_range",
+                        &[
+                            ("min", &format!("{:.2}", capacity.capacity(0.0))),
+                            ("max", &format!("{:.2}", capacity.capacity(1.0))),
+                        ],
                     ))
                     .small()
                     .color(egui::Color32::from_rgb(150, 200, 150)),
                 );
+                if ui.checkbox(&mut capacity.keep_normalization, tr("params.capacity_keep_normalization"))
+                    .on_hover_text(tr("params.capacity_keep_normalization_hint"))
+                    .changed()
+                {
+                    lab.log_param_change(lab.current_frame, "capacity_keep_normalization", &capacity.keep_normalization.to_string());
+                }
+            });
+            if capacity.replaces_normalization() && params.mass_normalization_enabled {
+                ui.label(
+                    egui::RichText::new(tr("params.capacity_replaces"))
+                        .small()
+                        .color(egui::Color32::from_rgb(255, 200, 50)),
+                );
             }
         });
 
         ui.group(|ui| {
-            ui.label(egui::RichText::new("⚖ Non-Linear Trade-offs").strong());
+            ui.label(egui::RichText::new(tr("params.tradeoffs")).strong());
             if ui.add(
                 egui::Slider::new(&mut params.radius_cost_exponent, 1.0..=3.0)
-                    .text("Radius Cost Exp")
+                    .text(tr("params.radius_cost"))
                     .step_by(0.1),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("radius_cost_exp={:.1}", params.radius_cost_exponent));
+                lab.log_param_change(lab.current_frame, "radius_cost_exp", &format!("{:.1}", params.radius_cost_exponent));
             }
+            schedule_overlay(ui, params, "radius_cost_exponent", lab.current_frame);
             ui.label(
-                egui::RichText::new("Higher = larger radii penalized more")
+                egui::RichText::new(tr("params.radius_cost_hint"))
                     .small()
                     .color(egui::Color32::GRAY),
             );
 
             if ui.add(
                 egui::Slider::new(&mut params.agg_mobility_tradeoff, 0.0..=1.0)
-                    .text("Agg↔Mobility")
+                    .text(tr("params.agg_mobility"))
                     .step_by(0.05),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("agg_mobility={:.2}", params.agg_mobility_tradeoff));
+                lab.log_param_change(lab.current_frame, "agg_mobility", &format!("{:.2}", params.agg_mobility_tradeoff));
             }
+            schedule_overlay(ui, params, "agg_mobility_tradeoff", lab.current_frame);
             ui.label(
-                egui::RichText::new("Aggressive organisms move slower")
+                egui::RichText::new(tr("params.agg_mobility_hint"))
                     .small()
                     .color(egui::Color32::GRAY),
             );
 
             if ui.add(
                 egui::Slider::new(&mut params.starvation_severity, 0.01..=0.2)
-                    .text("Starvation")
+                    .text(tr("params.starvation"))
                     .step_by(0.005),
             ).changed() {
-                lab.log_event(0, "PARAM_CHANGE", &format!("starvation={:.3}", params.starvation_severity));
+                lab.log_param_change(lab.current_frame, "starvation", &format!("{:.3}", params.starvation_severity));
             }
+            schedule_overlay(ui, params, "starvation_severity", lab.current_frame);
         });
 
         ui.group(|ui| {
-            ui.label(egui::RichText::new("Initial Conditions (on restart)").strong());
+            ui.label(egui::RichText::new(tr("params.initial")).strong());
             ui.add(
                 egui::Slider::new(&mut params.num_seed_clusters, 5..=100)
-                    .text("Seed Clusters"),
+                    .text(tr("params.seed_clusters")),
             );
             ui.add(
                 egui::Slider::new(&mut params.seed_cluster_size, 0.5..=3.0)
-                    .text("Cluster Scale")
+                    .text(tr("params.cluster_scale"))
                     .step_by(0.1),
             );
             ui.add(
                 egui::Slider::new(&mut params.initial_mass_fill, 0.05..=0.5)
-                    .text("Mass Fill %")
+                    .text(tr("params.mass_fill"))
                     .step_by(0.01),
             );
+            egui::ComboBox::new("grid_type", tr("params.grid"))
+                .selected_text(tr(params.grid.label_key()))
+                .show_ui(ui, |ui| {
+                    for g in GridType::all().iter().filter(|g| g.supported(world[0], world[1])) {
+                        ui.selectable_value(&mut params.grid, *g, tr(g.label_key()));
+                    }
+                })
+                .response
+                .on_hover_text(tr("params.grid_hint"));
         });
     });
 }
 
-// ======================== Perturbation Section ========================
+// ======================== Particle Section ========================
 
-fn render_perturbation_section(
-    ui: &mut egui::Ui,
-    params: &mut SimulationParams,
-    lab: &mut LabState,
-) {
-    ui.collapsing("🌊 Perturbations", |ui| {
+fn render_particle_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("particles.title")).id_salt("particles").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("particles.subtitle")).small().color(egui::Color32::GRAY));
+        let settings = &mut params.particles;
+        if ui.checkbox(&mut settings.enabled, tr("common.enabled")).changed() {
+            lab.log_param_change(lab.current_frame, "particles", &settings.enabled.to_string());
+            if !settings.enabled {
+                lab.particles = None;
+            }
+        }
+        ui.add_enabled_ui(settings.enabled, |ui| {
+            if ui.add(egui::Slider::new(&mut settings.count, 0..=MAX_PARTICLES).text(tr("particles.count"))).changed() {
+                lab.log_param_change(lab.current_frame, "particle_count", &settings.count.to_string());
+            }
+            ui.add(egui::Slider::new(&mut settings.speed, 0.1..=2.0).text(tr("particles.speed")));
+            ui.add(egui::Slider::new(&mut settings.graze_rate, 0.0..=0.5).text(tr("particles.graze_rate")))
+                .on_hover_text(tr("particles.graze_rate_hint"));
+            ui.add(egui::Slider::new(&mut settings.capacity, 0.05..=1.0).text(tr("particles.capacity")));
+            ui.add(egui::Slider::new(&mut settings.drop_rate, 0.01..=1.0).text(tr("particles.drop_rate")))
+                .on_hover_text(tr("particles.drop_rate_hint"));
+            ui.add(egui::Slider::new(&mut settings.metabolism, 0.0..=0.01).text(tr("particles.metabolism")).step_by(0.0005));
+            ui.add(egui::Slider::new(&mut settings.opacity, 0.0..=1.0).text(tr("vis.opacity")));
+        });
+
+        let Some(stats) = &lab.particles else {
+            if settings.enabled {
+                ui.label(egui::RichText::new(tr("particles.waiting")).small().color(egui::Color32::GRAY));
+            }
+            return;
+        };
         ui.label(
-            egui::RichText::new("Apply ecological disturbances")
-                .small()
-                .color(egui::Color32::GRAY),
+            egui::RichText::new(trf(
+                "particles.summary",
+                &[
+                    ("count", &stats.count),
+                    ("energy", &format!("{:.2}", stats.mean_energy)),
+                    ("loaded", &format!("{:.0}%", stats.loaded_fraction * 100.0)),
+                ],
+            ))
+            .small(),
+        );
+        ui.label(
+            egui::RichText::new(trf(
+                "particles.flow",
+                &[
+                    ("cargo", &format!("{:.2}", stats.cargo)),
+                    ("grazed", &format!("{:.1}", stats.grazed)),
+                    ("deposited", &format!("{:.1}", stats.deposited)),
+                ],
+            ))
+            .small(),
         );
+    });
+}
 
-        // Perturbation type selector
-        egui::ComboBox::from_label("Type")
-            .selected_text(params.perturbation_type.name())
+// ======================== Flow Field Section ========================
+
+fn render_flow_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("flow.title")).id_salt("flow").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("flow.subtitle")).small().color(egui::Color32::GRAY));
+        let settings = &mut params.flow;
+        if ui.checkbox(&mut settings.enabled, tr("common.enabled")).changed() {
+            lab.log_param_change(lab.current_frame, "flow", &settings.enabled.to_string());
+        }
+        ui.add_enabled_ui(settings.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut settings.strength, 0.0..=1.0).text(tr("flow.strength")))
+                .on_hover_text(tr("flow.strength_hint"));
+            ui.add(egui::Slider::new(&mut settings.resource_transport, 0.0..=2.0).text(tr("flow.resource_transport")))
+                .on_hover_text(tr("flow.resource_transport_hint"));
+        });
+        ui.add(egui::Slider::new(&mut settings.overlay_opacity, 0.0..=1.0).text(tr("flow.overlay")))
+            .on_hover_text(tr("flow.overlay_hint"));
+
+        ui.add_space(4.0);
+        egui::ComboBox::new("flow_preset", tr("flow.preset"))
+            .selected_text(tr(lab.flow_preset.label_key()))
             .show_ui(ui, |ui| {
-                for pt in PerturbationType::all() {
-                    ui.selectable_value(&mut params.perturbation_type, pt.clone(), pt.name());
+                for p in FlowPreset::all() {
+                    ui.selectable_value(&mut lab.flow_preset, *p, tr(p.label_key()));
                 }
             });
-
-        // Intensity
-        ui.add(
-            egui::Slider::new(&mut params.perturbation_intensity, 0.0..=1.0)
-                .text("Intensity")
-                .step_by(0.05),
-        );
-
-        // Radius
-        ui.add(
-            egui::Slider::new(&mut params.perturbation_radius, 0.05..=0.5)
-                .text("Radius")
-                .step_by(0.01),
-        );
-
-        // Center
+        if lab.flow_preset == FlowPreset::Curl {
+            ui.add(egui::DragValue::new(&mut lab.flow_seed).prefix(tr("flow.seed")));
+        } else {
+            ui.add(egui::Slider::new(&mut lab.flow_angle, -180.0..=180.0).text(tr("flow.angle")).suffix("°"))
+                .on_hover_text(tr("flow.angle_hint"));
+        }
         ui.horizontal(|ui| {
-            ui.label("Center:");
-            ui.add(egui::DragValue::new(&mut params.perturbation_center_x)
-                .range(0.0..=1.0).speed(0.01).prefix("x="));
-            ui.add(egui::DragValue::new(&mut params.perturbation_center_y)
-                .range(0.0..=1.0).speed(0.01).prefix("y="));
+            if ui.button(tr("flow.generate")).clicked() {
+                lab.flow_generate_requested = true;
+            }
+            if ui.button(tr("common.clear")).clicked() {
+                lab.flow_clear_requested = true;
+            }
         });
 
         ui.add_space(4.0);
+        ui.checkbox(&mut lab.flow_brush_active, tr("flow.brush")).on_hover_text(tr("flow.brush_hint"));
+        ui.add_enabled_ui(lab.flow_brush_active, |ui| {
+            ui.add(egui::Slider::new(&mut lab.flow_brush_radius, 2.0..=64.0).text(tr("flow.brush_radius")));
+            ui.checkbox(&mut lab.flow_brush_erase, tr("flow.brush_erase"));
+        });
+    });
+}
 
-        // Description of current perturbation
-        let desc = match params.perturbation_type {
-            PerturbationType::None => "No perturbation selected",
-            PerturbationType::Drought => "Destroys resources in affected area",
-            PerturbationType::NutrientPulse => "Injects extra resources",
-            PerturbationType::MassStorm => "Kills organisms (mass → 0)",
-            PerturbationType::MutationBurst => "Randomizes DNA in affected area",
-        };
-        ui.label(
-            egui::RichText::new(desc)
-                .small()
-                .italics()
-                .color(egui::Color32::from_rgb(200, 200, 150)),
-        );
+// ======================== Reserve Zones ========================
 
-        ui.add_space(4.0);
+fn render_reserve_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("reserve.title")).id_salt("reserves").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("reserve.subtitle")).small().color(egui::Color32::GRAY));
 
-        let can_apply = params.perturbation_type != PerturbationType::None;
-        ui.add_enabled_ui(can_apply, |ui| {
-            if ui.button("⚡ Apply Perturbation").clicked() {
-                params.perturbation_active = true;
-                lab.log_event(
-                    0,
-                    "PERTURBATION",
-                    &format!(
-                        "{} intensity={:.2} radius={:.2} center=({:.2},{:.2})",
-                        params.perturbation_type.name(),
-                        params.perturbation_intensity,
-                        params.perturbation_radius,
-                        params.perturbation_center_x,
-                        params.perturbation_center_y,
-                    ),
-                );
-            }
-        });
+        let mut removed = None;
+        for (k, zone) in params.reserves.iter_mut().enumerate() {
+            ui.push_id(k, |ui| {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut zone.enabled, "");
+                    ui.add(egui::TextEdit::singleline(&mut zone.name).desired_width(120.0));
+                    if ui.small_button("🗑").on_hover_text(tr("reserve.remove")).clicked() {
+                        removed = Some(k);
+                    }
+                });
 
-        if params.perturbation_active {
-            ui.label(
-                egui::RichText::new("● Pending…")
-                    .color(egui::Color32::from_rgb(255, 200, 50)),
-            );
+                let is_circle = matches!(zone.shape, ReserveShape::Circle { .. });
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(is_circle, tr("reserve.circle")).clicked() && !is_circle {
+                        zone.shape = ReserveShape::Circle { center: [0.5, 0.5], radius: 0.15 };
+                    }
+                    if ui.selectable_label(!is_circle, tr("reserve.polygon")).clicked() && is_circle {
+                        zone.shape = ReserveShape::Polygon { points: Vec::new() };
+                    }
+                    let mut picking = lab.reserve_pick == Some(k);
+                    let hint = if is_circle { "reserve.pick_circle_hint" } else { "reserve.pick_polygon_hint" };
+                    if ui.toggle_value(&mut picking, tr("reserve.pick")).on_hover_text(tr(hint)).changed() {
+                        lab.reserve_pick = picking.then_some(k);
+                    }
+                });
+                match &mut zone.shape {
+                    ReserveShape::Circle { center, radius } => {
+                        ui.horizontal(|ui| {
+                            ui.label(tr("perturb.center"));
+                            ui.add(egui::DragValue::new(&mut center[0]).range(0.0..=1.0).speed(0.01).prefix("x="));
+                            ui.add(egui::DragValue::new(&mut center[1]).range(0.0..=1.0).speed(0.01).prefix("y="));
+                        });
+                        ui.add(egui::Slider::new(radius, 0.01..=0.5).text(tr("perturb.radius")));
+                    }
+                    ReserveShape::Polygon { points } => {
+                        ui.horizontal(|ui| {
+                            ui.label(trf("reserve.vertices", &[("count", &points.len())]));
+                            if ui.add_enabled(!points.is_empty(), egui::Button::new(tr("reserve.undo_vertex"))).clicked() {
+                                points.pop();
+                            }
+                        });
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(tr("reserve.resident"));
+                    let [r, mu, sigma, agg] = &mut zone.resident;
+                    ui.add(egui::DragValue::new(r).range(3.0..=15.0).speed(0.1).prefix("r="));
+                    ui.add(egui::DragValue::new(mu).range(0.05..=0.35).speed(0.005).prefix("μ="));
+                    ui.add(egui::DragValue::new(sigma).range(0.005..=0.08).speed(0.001).prefix("σ="));
+                    ui.add(egui::DragValue::new(agg).range(0.0..=1.0).speed(0.01).prefix("a="));
+                });
+                if ui.button(tr("reserve.capture")).on_hover_text(tr("reserve.capture_hint")).clicked() {
+                    lab.reserve_capture_requested = Some(k);
+                }
+                ui.add(egui::Slider::new(&mut zone.tolerance, 0.01..=0.5).text(tr("reserve.tolerance")))
+                    .on_hover_text(tr("reserve.tolerance_hint"));
+                ui.checkbox(&mut zone.no_predation, tr("reserve.no_predation"))
+                    .on_hover_text(tr("reserve.no_predation_hint"));
+                ui.add(
+                    egui::Slider::new(&mut zone.mutation_mult, 0.0..=5.0)
+                        .text(tr("reserve.mutation_mult")),
+                );
+
+                if let Some(stats) = lab.reserve_stats.get(k).filter(|_| zone.enabled) {
+                    ui.label(
+                        egui::RichText::new(trf(
+                            "reserve.stats",
+                            &[
+                                ("inside", &format!("{:.1}", stats.inside)),
+                                ("outside", &format!("{:.1}", stats.outside)),
+                                ("cells", &stats.cells),
+                            ],
+                        ))
+                        .small(),
+                    )
+                    .on_hover_text(tr("reserve.stats_hint"));
+                }
+            });
+        }
+        if let Some(k) = removed {
+            let name = params.reserves.remove(k).name;
+            lab.reserve_pick = None;
+            lab.log_param_change(lab.current_frame, "reserves", &format!("removed {}", name));
+        }
+
+        ui.add_space(4.0);
+        let full = params.reserves.len() >= MAX_RESERVES;
+        if ui.add_enabled(!full, egui::Button::new(tr("reserve.add")))
+            .on_disabled_hover_text(trf("reserve.full", &[("max", &MAX_RESERVES)]))
+            .clicked()
+        {
+            let name = format!("reserve {}", params.reserves.len() + 1);
+            lab.log_param_change(lab.current_frame, "reserves", &format!("added {}", name));
+            params.reserves.push(ReserveZone { name, ..Default::default() });
         }
     });
 }
 
-// ======================== Visualization Section ========================
+// ======================== Schedules Section ========================
 
-fn render_visualization_section(ui: &mut egui::Ui, params: &mut SimulationParams) {
-    ui.collapsing("🎨 Visualization", |ui| {
-        for mode in 0..VIS_MODE_COUNT {
-            let name = visualization_mode_name(mode);
-            if ui.radio_value(&mut params.visualization_mode, mode, name).clicked() {
-                log::info!("Visualization mode: {}", name);
-            }
-        }
-        ui.add_space(4.0);
-        ui.checkbox(&mut params.vsync, "VSync");
+/// Samples of a schedule's curve drawn under its parameter's slider.
+const SCHEDULE_OVERLAY_POINTS: u32 = 100;
 
-        ui.label(
-            egui::RichText::new(format!("World: {}×{}", WORLD_WIDTH, WORLD_HEIGHT))
-                .small()
-                .color(egui::Color32::GRAY),
-        );
+fn render_schedule_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("schedule.title")).id_salt("schedules").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("schedule.hint")).small().color(egui::Color32::GRAY));
+        let mut remove = None;
+        for (i, schedule) in params.schedules.iter_mut().enumerate() {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut schedule.enabled, "");
+                    egui::ComboBox::from_id_salt(("schedule_param", i))
+                        .selected_text(&schedule.param)
+                        .width(170.0)
+                        .show_ui(ui, |ui| {
+                            for name in SCHEDULABLE {
+                                ui.selectable_value(&mut schedule.param, name.to_string(), *name);
+                            }
+                        });
+                    if ui.small_button("🗑").on_hover_text(tr("schedule.remove")).clicked() {
+                        remove = Some(i);
+                    }
+                });
+                let formula = matches!(schedule.curve, ScheduleCurve::Formula(_));
+                ui.horizontal(|ui| {
+                    if ui.radio(!formula, tr("schedule.keyframes")).clicked() && formula {
+                        schedule.curve = ParamSchedule::default().curve;
+                    }
+                    if ui.radio(formula, tr("schedule.formula")).clicked() && !formula {
+                        schedule.curve = ScheduleCurve::Formula(String::from("0.02 - 0.015 * min(frame / 50000, 1)"));
+                    }
+                });
+                match &mut schedule.curve {
+                    ScheduleCurve::Keyframes(keys) => {
+                        let mut drop = None;
+                        for (k, key) in keys.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut key.frame).speed(100.0).prefix(tr("schedule.frame")));
+                                ui.add(egui::DragValue::new(&mut key.value).speed(0.001).max_decimals(4));
+                                if ui.small_button("✖").clicked() {
+                                    drop = Some(k);
+                                }
+                            });
+                        }
+                        if let Some(k) = drop {
+                            keys.remove(k);
+                        }
+                        if ui.small_button(tr("schedule.add_keyframe")).clicked() {
+                            let last = keys.iter().max_by_key(|k| k.frame).copied();
+                            keys.push(last.map_or(Keyframe { frame: 0, value: 0.0 }, |k| Keyframe { frame: k.frame + 10_000, ..k }));
+                        }
+                    }
+                    ScheduleCurve::Formula(source) => {
+                        ui.add(egui::TextEdit::singleline(source).hint_text("frame").desired_width(f32::INFINITY));
+                    }
+                }
+                let (status, color) = match schedule.check() {
+                    Err(e) => (trf("schedule.invalid", &[("error", &e)]), egui::Color32::from_rgb(255, 120, 100)),
+                    Ok(()) if !schedule.enabled => (tr("schedule.disabled").to_string(), egui::Color32::GRAY),
+                    Ok(()) => {
+                        let value = schedule.value_at(lab.current_frame).map_or("—".to_string(), |v| format!("{v:.4}"));
+                        (trf("schedule.now", &[("value", &value)]), egui::Color32::GRAY)
+                    }
+                };
+                ui.label(egui::RichText::new(status).small().color(color));
+            });
+        }
+        if let Some(i) = remove {
+            let removed = params.schedules.remove(i);
+            lab.log_param_change(lab.current_frame, "schedule_removed", &removed.param);
+        }
+        if ui.button(tr("schedule.add")).clicked() {
+            params.schedules.push(ParamSchedule::default());
+            lab.log_param_change(lab.current_frame, "schedule_added", &params.schedules.len().to_string());
+        }
     });
 }
 
-// ======================== Experiment Section ========================
+/// Curve of the schedule driving `param` under its slider, with the current
+/// frame marked; nothing when the parameter is not scheduled.
+fn schedule_overlay(ui: &mut egui::Ui, params: &SimulationParams, param: &str, frame: u32) {
+    let Some(schedule) = active_schedule(params, param) else {
+        return;
+    };
+    let (start, end) = schedule.span(frame);
+    let frames: Vec<u32> = (0..=SCHEDULE_OVERLAY_POINTS)
+        .map(|i| start + ((end - start) as u64 * i as u64 / SCHEDULE_OVERLAY_POINTS as u64) as u32)
+        .collect();
+    let points: Vec<[f64; 2]> = frames
+        .iter()
+        .zip(schedule.values(&frames))
+        .filter_map(|(&f, v)| Some([f as f64, v? as f64]))
+        .collect();
+    Plot::new(format!("schedule_overlay_{param}"))
+        .height(40.0)
+        .show_axes([false, true])
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_zoom(false)
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(PlotPoints::new(points)).color(egui::Color32::from_rgb(120, 200, 255)));
+            plot_ui.vline(VLine::new(frame as f64).color(egui::Color32::from_rgb(255, 200, 80)));
+        });
+    ui.label(egui::RichText::new(tr("schedule.overlay")).small().color(egui::Color32::GRAY));
+}
 
-fn render_experiment_section(
+// ======================== Perturbation Section ========================
+
+fn render_perturbation_section(
     ui: &mut egui::Ui,
     params: &mut SimulationParams,
     lab: &mut LabState,
 ) {
-    ui.collapsing("🧪 Experiments", |ui| {
-        // Seed control
-        ui.group(|ui| {
-            ui.label(egui::RichText::new("Reproducibility").strong());
-            ui.checkbox(&mut params.use_fixed_seed, "Use fixed seed");
-            if params.use_fixed_seed {
-                ui.horizontal(|ui| {
-                    ui.label("Seed:");
-                    ui.add(egui::DragValue::new(&mut params.fixed_seed_value).range(0..=u64::MAX));
-                });
-            }
-            if let Some(seed) = params.effective_seed() {
-                ui.label(
-                    egui::RichText::new(format!("Active seed: {}", seed))
-                        .small()
-                        .color(egui::Color32::from_rgb(150, 200, 150)),
-                );
-            }
-        });
+    egui::CollapsingHeader::new(tr("perturb.title")).id_salt("perturbations").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(tr("perturb.subtitle"))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
 
-        // Run management
-        ui.group(|ui| {
-            ui.label(egui::RichText::new("Run Management").strong());
-            ui.label(format!("Run ID: {}", lab.run_id));
+        // Perturbation type selector
+        egui::ComboBox::new("perturbation_type", tr("perturb.type"))
+            .selected_text(tr(params.perturbation_type.label_key()))
+            .show_ui(ui, |ui| {
+                for pt in PerturbationType::all() {
+                    ui.selectable_value(&mut params.perturbation_type, pt.clone(), tr(pt.label_key()));
+                }
+            });
 
+        let barrier = params.perturbation_type.plugin().barrier();
+        if barrier {
+            // Wall position and lifetime; the walls span the full height
+            ui.add(
+                egui::Slider::new(&mut params.barrier_frames, 100..=20_000)
+                    .logarithmic(true)
+                    .text(tr("perturb.barrier_frames")),
+            );
             ui.horizontal(|ui| {
-                if ui.button("📁 Start Run").clicked() {
-                    lab.start_run(params);
-                }
-                if ui.button("⏹ Finalize Run").clicked() {
-                    lab.finalize_run(params);
-                }
+                ui.label(tr("perturb.barrier_x"));
+                ui.add(egui::DragValue::new(&mut params.perturbation_center_x)
+                    .range(0.0..=1.0).speed(0.01).prefix("x="));
             });
+        } else {
+            // Intensity
+            ui.add(
+                egui::Slider::new(&mut params.perturbation_intensity, 0.0..=1.0)
+                    .text(tr("common.intensity"))
+                    .step_by(0.05),
+            );
+            schedule_overlay(ui, params, "perturbation_intensity", lab.current_frame);
 
-            if lab.run_active {
-                ui.label(
-                    egui::RichText::new("● Recording")
-                        .color(egui::Color32::from_rgb(100, 255, 100)),
+            // Radius
+            ui.add(
+                egui::Slider::new(&mut params.perturbation_radius, 0.05..=0.5)
+                    .text(tr("perturb.radius"))
+                    .step_by(0.01),
+            );
+
+            // Center
+            ui.horizontal(|ui| {
+                ui.label(tr("perturb.center"));
+                ui.add(egui::DragValue::new(&mut params.perturbation_center_x)
+                    .range(0.0..=1.0).speed(0.01).prefix("x="));
+                ui.add(egui::DragValue::new(&mut params.perturbation_center_y)
+                    .range(0.0..=1.0).speed(0.01).prefix("y="));
+            });
+        }
+
+        ui.add_space(4.0);
+
+        // Description of current perturbation
+        let desc = tr(params.perturbation_type.plugin().description_key());
+        ui.label(
+            egui::RichText::new(desc)
+                .small()
+                .italics()
+                .color(egui::Color32::from_rgb(200, 200, 150)),
+        );
+
+        ui.add_space(4.0);
+
+        let can_apply = params.perturbation_type != PerturbationType::None;
+        ui.add_enabled_ui(can_apply, |ui| {
+            if ui.button(tr("perturb.apply")).clicked() {
+                params.perturbation_active = true;
+                lab.log_event_with_payload(
+                    lab.current_frame,
+                    EventSeverity::Info,
+                    "PERTURBATION",
+                    &format!(
+                        "{} intensity={:.2} radius={:.2} center=({:.2},{:.2})",
+                        params.perturbation_type.name(),
+                        params.perturbation_intensity,
+                        params.perturbation_radius,
+                        params.perturbation_center_x,
+                        params.perturbation_center_y,
+                    ),
+                    serde_json::json!({
+                        "kind": params.perturbation_type.name(),
+                        "intensity": params.perturbation_intensity,
+                        "radius": params.perturbation_radius,
+                        "center": [params.perturbation_center_x, params.perturbation_center_y],
+                    }),
                 );
             }
-
-            ui.label(format!("Metrics: {} samples", lab.metrics_history.len()));
         });
 
-        // Presets
-        ui.group(|ui| {
-            ui.label(egui::RichText::new("Presets").strong());
+        if ui.add_enabled(!barrier, egui::Checkbox::new(&mut params.perturbation_continuous, tr("perturb.continuous")))
+            .on_hover_text(tr("perturb.continuous_hint"))
+            .changed()
+        {
+            lab.log_param_change(lab.current_frame, "perturbation_continuous", &params.perturbation_continuous.to_string());
+        }
 
-            ui.horizontal(|ui| {
-                ui.text_edit_singleline(&mut lab.preset_name);
-                if ui.button("Save").clicked() {
-                    save_preset(&lab.preset_name, params);
-                    lab.set_status(format!("Preset '{}' saved", lab.preset_name));
-                }
+        if params.perturbation_active {
+            ui.label(
+                egui::RichText::new(tr("perturb.pending"))
+                    .color(egui::Color32::from_rgb(255, 200, 50)),
+            );
+        }
+
+        if lab.barrier_split.is_some() {
+            render_barrier_status(ui, lab);
+        }
+    });
+}
+
+/// Walls countdown and the divergence between the two halves of the split.
+fn render_barrier_status(ui: &mut egui::Ui, lab: &LabState) {
+    ui.separator();
+    let state = if lab.barrier_frames_left > 0 {
+        trf("perturb.barrier_up", &[("frames", &lab.barrier_frames_left)])
+    } else {
+        tr("perturb.barrier_down").to_string()
+    };
+    ui.label(egui::RichText::new(state).strong());
+
+    let Some(d) = lab.barrier_divergence else {
+        ui.label(egui::RichText::new(tr("perturb.barrier_waiting")).small().color(egui::Color32::GRAY));
+        return;
+    };
+    egui::Grid::new("barrier_halves").num_columns(3).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.label(tr("perturb.half_a"));
+        ui.label(tr("perturb.half_b"));
+        ui.end_row();
+        ui.label(tr("perturb.half_mass"));
+        ui.label(format!("{:.1}", d.mass[0]));
+        ui.label(format!("{:.1}", d.mass[1]));
+        ui.end_row();
+        ui.label(tr("perturb.half_species"));
+        ui.label(d.species[0].to_string());
+        ui.label(d.species[1].to_string());
+        ui.end_row();
+        ui.label(tr("perturb.half_within"));
+        ui.label(format!("{:.4}", d.within[0]));
+        ui.label(format!("{:.4}", d.within[1]));
+        ui.end_row();
+    });
+    ui.label(trf("perturb.between", &[("value", &format!("{:.4}", d.between))]));
+    let index = d.index().map_or("—".to_string(), |i| format!("{i:.3}"));
+    ui.label(trf("perturb.divergence_index", &[("value", &index)]))
+        .on_hover_text(tr("perturb.divergence_index_hint"));
+
+    if lab.barrier_history.len() > 1 {
+        Plot::new("plot_barrier_divergence")
+            .height(90.0)
+            .show_axes(true)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .show(ui, |plot_ui| {
+                let points = PlotPoints::new(lab.barrier_history.clone());
+                plot_ui.line(Line::new(points).color(egui::Color32::from_rgb(255, 180, 90)));
             });
-            if ui.button("Load preset…").clicked() {
-                if let Some(loaded) = load_preset(&lab.preset_name) {
-                    *params = loaded;
-                    lab.set_status(format!("Preset '{}' loaded", lab.preset_name));
-                }
+    }
+}
+
+// ======================== Pattern Section ========================
+
+fn render_pattern_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("pattern.title")).id_salt("patterns").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(trf("pattern.subtitle", &[("dir", &PATTERN_DIR)]))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+
+        ui.horizontal(|ui| {
+            ui.label(tr("pattern.name"));
+            ui.text_edit_singleline(&mut lab.pattern_name);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("perturb.center"));
+            ui.add(egui::DragValue::new(&mut lab.pattern_center[0])
+                .range(0.0..=1.0).speed(0.01).prefix("x="));
+            ui.add(egui::DragValue::new(&mut lab.pattern_center[1])
+                .range(0.0..=1.0).speed(0.01).prefix("y="));
+        });
+
+        ui.add(
+            egui::Slider::new(&mut lab.pattern_extent, 8..=128)
+                .text(tr("pattern.extent")),
+        )
+        .on_hover_text(tr("pattern.extent_hint"));
+
+        ui.horizontal(|ui| {
+            if ui.button(tr("pattern.import")).on_hover_text(tr("pattern.import_hint")).clicked() {
+                lab.pattern_import_requested = true;
             }
-            if ui.button("Reset to defaults").clicked() {
-                let vis = params.visualization_mode;
-                *params = SimulationParams::default();
-                params.visualization_mode = vis;
-                lab.set_status("Parameters reset to defaults".to_string());
+            if ui.button(tr("pattern.export")).on_hover_text(tr("pattern.export_hint")).clicked() {
+                lab.pattern_export_requested = true;
             }
         });
     });
 }
 
-// ======================== Capture Section ========================
+// ======================== Translocation ========================
+
+fn render_transplant_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("transplant.title")).id_salt("transplant").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(tr("transplant.subtitle"))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
 
-fn render_capture_section(
-    ui: &mut egui::Ui,
-    params: &SimulationParams,
-    lab: &mut LabState,
-) {
-    ui.collapsing("📸 Capture", |ui| {
         ui.horizontal(|ui| {
-            if ui.button("📷 Screenshot (F12)").clicked() {
-                lab.screenshot_requested = true;
+            ui.label(tr("transplant.from"));
+            ui.add(egui::DragValue::new(&mut lab.transplant_from[0])
+                .range(0.0..=1.0).speed(0.01).prefix("x="));
+            ui.add(egui::DragValue::new(&mut lab.transplant_from[1])
+                .range(0.0..=1.0).speed(0.01).prefix("y="));
+            if ui.toggle_value(&mut lab.transplant_pick_from, tr("transplant.pick"))
+                .on_hover_text(tr("transplant.pick_hint"))
+                .changed()
+            {
+                lab.transplant_pick_to = false;
             }
-            if ui.button("💾 Snapshot").clicked() {
-                lab.snapshot_requested = true;
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("transplant.to"));
+            ui.add(egui::DragValue::new(&mut lab.transplant_to[0])
+                .range(0.0..=1.0).speed(0.01).prefix("x="));
+            ui.add(egui::DragValue::new(&mut lab.transplant_to[1])
+                .range(0.0..=1.0).speed(0.01).prefix("y="));
+            if ui.toggle_value(&mut lab.transplant_pick_to, tr("transplant.pick"))
+                .on_hover_text(tr("transplant.pick_hint"))
+                .changed()
+            {
+                lab.transplant_pick_from = false;
             }
         });
 
-        if ui.button("📊 Export Metrics CSV").clicked() {
-            match lab.export_metrics_csv() {
-                Ok(path) => lab.set_status(format!("Exported to {:?}", path)),
-                Err(e) => lab.set_status(format!("Export failed: {}", e)),
-            }
-        }
+        ui.add(
+            egui::Slider::new(&mut lab.transplant_half, 4..=128)
+                .text(tr("transplant.extent")),
+        )
+        .on_hover_text(tr("transplant.extent_hint"));
 
-        if ui.button("📝 Export Report").clicked() {
-            match lab.export_report(params) {
-                Ok(path) => lab.set_status(format!("Report saved to {:?}", path)),
-                Err(e) => lab.set_status(format!("Report failed: {}", e)),
+        ui.horizontal(|ui| {
+            if ui.button(tr("transplant.apply")).on_hover_text(tr("transplant.apply_hint")).clicked() {
+                lab.transplant_requested = true;
             }
-        }
+            let undo = trf("transplant.undo", &[("count", &lab.transplants.len())]);
+            if ui.add_enabled(!lab.transplants.is_empty(), egui::Button::new(undo))
+                .on_hover_text(tr("transplant.undo_hint"))
+                .clicked()
+            {
+                lab.transplant_undo_requested = true;
+            }
+        });
     });
 }
 
-// ======================== View Toggles ========================
+// ======================== Invasion Assay ========================
+
+fn render_invasion_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("invasion.title")).id_salt("invasion").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(tr("invasion.subtitle"))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+        let resident = lab.metrics_history.last().map(|m| [m.avg_radius, m.avg_mu, m.avg_sigma, m.avg_aggressivity]);
+        let assay = &mut lab.invasion;
+        let settings = &mut assay.settings;
+        ui.horizontal(|ui| {
+            ui.label(tr("invasion.mutant"));
+            if ui.add_enabled(resident.is_some(), egui::Button::new(tr("invasion.from_mean")).small())
+                .on_hover_text(tr("invasion.from_mean_hint"))
+                .clicked()
+            {
+                settings.mutant = resident.unwrap_or(settings.mutant);
+            }
+        });
+        ui.horizontal(|ui| {
+            let [r, mu, sigma, agg] = &mut settings.mutant;
+            ui.add(egui::DragValue::new(r).range(3.0..=15.0).speed(0.1).prefix("r="));
+            ui.add(egui::DragValue::new(mu).range(0.05..=0.35).speed(0.005).prefix("μ="));
+            ui.add(egui::DragValue::new(sigma).range(0.005..=0.08).speed(0.001).prefix("σ="));
+            ui.add(egui::DragValue::new(agg).range(0.0..=1.0).speed(0.01).prefix("a="));
+        });
+        ui.add(egui::Slider::new(&mut settings.density, 0.001..=0.2).logarithmic(true).text(tr("invasion.density")))
+            .on_hover_text(tr("invasion.density_hint"));
+        ui.add(egui::Slider::new(&mut settings.tolerance, 0.005..=0.2).logarithmic(true).text(tr("invasion.tolerance")))
+            .on_hover_text(tr("invasion.tolerance_hint"));
+        ui.add(egui::Slider::new(&mut settings.steps, 100..=20000).logarithmic(true).text(tr("invasion.steps")));
+        ui.add(egui::Slider::new(&mut settings.sample_interval, 10..=1000).logarithmic(true).text(tr("invasion.interval")));
+        ui.horizontal(|ui| {
+            ui.label(tr("invasion.seed"));
+            ui.add(egui::DragValue::new(&mut settings.seed));
+        });
 
-fn render_view_toggles(ui: &mut egui::Ui, lab: &mut LabState) {
-    ui.collapsing("📊 View", |ui| {
-        ui.checkbox(&mut lab.show_analysis_panel, "Analysis panel (F9)");
-        ui.checkbox(&mut lab.show_logs_panel, "Logs panel");
+        ui.horizontal(|ui| {
+            if assay.is_running() {
+                if ui.button(tr("invasion.cancel")).on_hover_text(tr("invasion.cancel_hint")).clicked() {
+                    assay.cancel();
+                }
+                ui.add(egui::ProgressBar::new(assay.progress.unwrap_or(0.0)).show_percentage());
+            } else {
+                if ui.button(tr("invasion.run")).on_hover_text(tr("invasion.run_hint")).clicked() {
+                    assay.requested = true;
+                }
+                if assay.result.is_some() && ui.button(tr("invasion.show_results")).clicked() {
+                    assay.show_results = true;
+                }
+            }
+        });
     });
 }
 
-// ======================== Right Analysis Panel ========================
-
-fn render_right_analysis_panel(ctx: &egui::Context, lab: &mut LabState) {
-    egui::SidePanel::right("analysis_panel")
-        .default_width(380.0)
-        .min_width(300.0)
-        .max_width(550.0)
-        .show(ctx, |ui| {
-            ui.add_space(8.0);
-            ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("📈").size(24.0));
-                ui.add_space(8.0);
-                ui.label(egui::RichText::new("Live Analysis").size(20.0).strong().color(egui::Color32::from_rgb(150, 220, 150)));
-            });
-            ui.add_space(8.0);
-            ui.separator();
+// ======================== Long-Term Evolution ========================
 
-            if lab.metrics_history.is_empty() {
-                ui.label("No metrics data yet. Wait for diagnostics readback.");
-                return;
+fn render_ltee_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("ltee.title")).id_salt("ltee").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(tr("ltee.subtitle"))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+        let ltee = &mut params.ltee;
+        if ui.checkbox(&mut ltee.enabled, tr("common.enabled")).changed() {
+            lab.log_param_change(lab.current_frame, "ltee_enabled", &ltee.enabled.to_string());
+        }
+        ui.add_enabled_ui(ltee.enabled, |ui| {
+            if ui.add(egui::Slider::new(&mut ltee.interval, 100..=100_000).logarithmic(true).text(tr("ltee.interval")))
+                .on_hover_text(tr("ltee.interval_hint"))
+                .changed()
+            {
+                lab.log_param_change(lab.current_frame, "ltee_interval", &ltee.interval.to_string());
             }
-
-            // Live stats table
-            if let Some(last) = lab.metrics_history.last() {
-                egui::Grid::new("live_stats")
-                    .num_columns(2)
-                    .striped(true)
-                    .show(ui, |ui| {
-                        stat_row(ui, "Frame", &format!("{}", last.frame));
-                        stat_row(ui, "FPS", &format!("{:.0}", last.fps));
-                        stat_row(ui, "Total Mass", &format!("{:.0}", last.total_mass));
-                        stat_row(ui, "Avg Energy", &format!("{:.4}", last.avg_energy));
-                        stat_row(ui, "Entropy", &format!("{:.2} bits", last.entropy));
-                        stat_row(ui, "Species", &format!("{}", last.species));
-                        stat_row(ui, "Live Pixels", &format!("{} ({:.1}%)", last.live_pixels, last.live_fraction * 100.0));
-                        stat_row(ui, "Predators", &format!("{:.1}%", last.predator_fraction * 100.0));
-                        stat_row(ui, "Avg Resource", &format!("{:.3}", last.avg_resource));
-                        stat_row(ui, "Mass StdDev", &format!("{:.4}", last.mass_std_dev));
-                        // Phase 1 eco metrics
-                        stat_row(ui, "Prey %", &format!("{:.1}%", last.prey_fraction * 100.0));
-                        stat_row(ui, "Opportunist %", &format!("{:.1}%", last.opportunist_fraction * 100.0));
-                        stat_row(ui, "Eff. Diversity", &format!("{:.2}", last.effective_diversity));
-                        stat_row(ui, "Genome Var", &format!("{:.4}", last.genome_variance));
-                        stat_row(ui, "Total Energy", &format!("{:.0}", last.total_energy));
-                        stat_row(ui, "Energy Flux", &format!("{:.4}", last.energy_flux));
-                    });
+            if ui.add(egui::Slider::new(&mut ltee.sample_size, 10..=5000).logarithmic(true).text(tr("ltee.sample_size")))
+                .on_hover_text(tr("ltee.sample_size_hint"))
+                .changed()
+            {
+                lab.log_param_change(lab.current_frame, "ltee_sample_size", &ltee.sample_size.to_string());
             }
-            ui.separator();
-
-            // Time-series plots
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                render_plot(ui, "Total Mass", &lab.metrics_history, |m| m.total_mass as f64);
-                render_plot(ui, "Avg Energy", &lab.metrics_history, |m| m.avg_energy as f64);
-                render_plot(ui, "Genetic Entropy", &lab.metrics_history, |m| m.entropy as f64);
-                render_plot(ui, "Species Count", &lab.metrics_history, |m| m.species as f64);
-                render_plot(ui, "Live Pixels", &lab.metrics_history, |m| m.live_pixels as f64);
-                render_plot(ui, "FPS", &lab.metrics_history, |m| m.fps as f64);
+            if ui.add(egui::Slider::new(&mut ltee.keep, 0.001..=1.0).logarithmic(true).text(tr("ltee.keep")))
+                .on_hover_text(tr("ltee.keep_hint"))
+                .changed()
+            {
+                lab.log_param_change(lab.current_frame, "ltee_keep", &format!("{:.3}", ltee.keep));
+            }
+            let next = (lab.current_frame / ltee.interval.max(1) + 1) * ltee.interval.max(1);
+            ui.label(egui::RichText::new(trf("ltee.next", &[("frame", &next)])).small().color(egui::Color32::GRAY));
+        });
 
-                // Phase 1 eco plots
-                render_plot(ui, "Effective Diversity", &lab.metrics_history, |m| m.effective_diversity as f64);
-                render_plot(ui, "Energy Flux", &lab.metrics_history, |m| m.energy_flux as f64);
-                render_plot(ui, "Genome Variance", &lab.metrics_history, |m| m.genome_variance as f64);
+        ui.separator();
+        let fossils = &mut lab.fossils;
+        ui.horizontal(|ui| {
+            ui.label(trf("ltee.archived", &[("count", &fossils.archive.len())]));
+            if ui.small_button("⟳").on_hover_text(tr("ltee.refresh")).clicked() {
+                fossils.archive = list_samples(&lab.run_dir);
+            }
+        });
+        let name = |path: &Path| path.file_stem().map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+        let selected = fossils.selected.as_deref().map_or_else(|| String::from("–"), name);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("ltee_sample").selected_text(selected).show_ui(ui, |ui| {
+                for path in &fossils.archive {
+                    ui.selectable_value(&mut fossils.selected, Some(path.clone()), name(path));
+                }
+            });
+            if ui.button(tr("ltee.open")).on_hover_text(tr("ltee.open_hint")).clicked() {
+                if let Some(path) = file_dialog::pick_fossil_sample(&lab.run_dir.join(ARCHIVE_DIR)) {
+                    fossils.selected = Some(path);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("perturb.center"));
+            ui.add(egui::DragValue::new(&mut fossils.spawn_center[0]).range(0.0..=1.0).speed(0.01).prefix("x="));
+            ui.add(egui::DragValue::new(&mut fossils.spawn_center[1]).range(0.0..=1.0).speed(0.01).prefix("y="));
+            ui.toggle_value(&mut fossils.spawn_pick, tr("transplant.pick"))
+                .on_hover_text(tr("ltee.pick_hint"));
+        });
+        ui.add(egui::Slider::new(&mut fossils.spawn_radius, 2..=64).text(tr("perturb.radius")));
+        if ui.add_enabled(fossils.selected.is_some(), egui::Button::new(tr("ltee.spawn")))
+            .on_hover_text(tr("ltee.spawn_hint"))
+            .clicked()
+        {
+            fossils.spawn_requested = true;
+        }
+    });
+}
+
+/// Results dialog of the last invasion assay: verdict, invasion fitness and
+/// the mutant share over the run.
+fn render_invasion_results(ctx: &egui::Context, lab: &mut LabState) {
+    let assay = &mut lab.invasion;
+    let Some(result) = assay.result.as_ref().filter(|_| assay.show_results) else {
+        return;
+    };
+    let mut open = true;
+    egui::Window::new(tr("invasion.results"))
+        .id(egui::Id::new("invasion_results"))
+        .open(&mut open)
+        .default_size([360.0, 300.0])
+        .show(ctx, |ui| {
+            let verdict = match result.outcome() {
+                Some(outcome) => tr(outcome.label_key()),
+                None => "–",
+            };
+            ui.label(egui::RichText::new(verdict).strong().size(16.0));
+            if result.cancelled {
+                ui.label(egui::RichText::new(tr("invasion.partial")).small().color(egui::Color32::GRAY));
+            }
+            let fitness = result.fitness().map_or(String::from("–"), |f| format!("{f:+.3}"));
+            let share = |s: Option<&InvasionSample>| s.map_or(String::from("–"), |s| format!("{:.2}%", s.share() * 100.0));
+            egui::Grid::new("invasion_summary").num_columns(2).show(ui, |ui| {
+                ui.label(tr("invasion.fitness"));
+                ui.label(fitness).on_hover_text(tr("invasion.fitness_hint"));
+                ui.end_row();
+                ui.label(tr("invasion.initial_share"));
+                ui.label(share(result.samples.first()));
+                ui.end_row();
+                ui.label(tr("invasion.final_share"));
+                ui.label(share(result.samples.last()));
+                ui.end_row();
+                ui.label(tr("invasion.injected"));
+                ui.label(trf("invasion.injected_value", &[("count", &result.injected), ("frame", &result.frame)]));
+                ui.end_row();
+            });
+            let points: Vec<[f64; 2]> = result.samples.iter().map(|s| [s.step as f64, s.share() as f64 * 100.0]).collect();
+            Plot::new("plot_invasion")
+                .height(140.0)
+                .allow_drag(false)
+                .allow_scroll(false)
+                .x_axis_label(tr("invasion.step"))
+                .y_axis_label(tr("invasion.share"))
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(PlotPoints::new(points)).color(egui::Color32::from_rgb(255, 170, 80)));
+                });
+            if let Some(path) = &assay.csv_path {
+                ui.label(egui::RichText::new(trf("invasion.csv", &[("path", &path.display())])).small());
+            }
+        });
+    assay.show_results = open;
+}
+
+// ======================== Head-to-Head Arena ========================
+
+fn render_arena_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("arena.title")).id_salt("arena").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(tr("arena.subtitle"))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+        let arena = &mut lab.arena;
+        let library = arena.library.get_or_insert_with(list_library);
+        let name = |path: &Option<PathBuf>| {
+            path.as_deref()
+                .and_then(|p| p.file_stem())
+                .map_or_else(|| String::from("–"), |s| s.to_string_lossy().into_owned())
+        };
+        for (label, salt, side) in [("arena.a", "arena_a", &mut arena.a), ("arena.b", "arena_b", &mut arena.b)] {
+            ui.horizontal(|ui| {
+                ui.label(tr(label));
+                egui::ComboBox::from_id_salt(salt).selected_text(name(side)).width(180.0).show_ui(ui, |ui| {
+                    for path in library.iter() {
+                        let entry = Some(path.clone());
+                        let text = name(&entry);
+                        ui.selectable_value(side, entry, text);
+                    }
+                });
+            });
+        }
+        let contestants = library.len();
+        if ui.small_button(tr("arena.rescan")).on_hover_text(tr("arena.rescan_hint")).clicked() {
+            arena.library = None;
+        }
+
+        let settings = &mut arena.settings;
+        ui.add(egui::Slider::new(&mut settings.seeds, 1..=8).text(tr("arena.seeds")))
+            .on_hover_text(tr("arena.seeds_hint"));
+        ui.add(egui::Slider::new(&mut settings.radius, 4..=32).text(tr("arena.radius")))
+            .on_hover_text(tr("arena.radius_hint"));
+        ui.add(egui::Slider::new(&mut settings.steps, 100..=50_000).logarithmic(true).text(tr("invasion.steps")));
+        ui.add(egui::Slider::new(&mut settings.sample_interval, 10..=1000).logarithmic(true).text(tr("invasion.interval")));
+        ui.horizontal(|ui| {
+            ui.label(tr("invasion.seed"));
+            ui.add(egui::DragValue::new(&mut settings.seed));
+        });
+
+        ui.horizontal(|ui| {
+            if arena.is_running() {
+                if ui.button(tr("invasion.cancel")).on_hover_text(tr("arena.cancel_hint")).clicked() {
+                    arena.cancel();
+                }
+                ui.add(egui::ProgressBar::new(arena.progress.unwrap_or(0.0)).show_percentage());
+            } else {
+                if ui.add_enabled(arena.a.is_some() && arena.b.is_some(), egui::Button::new(tr("arena.match")))
+                    .on_hover_text(tr("arena.match_hint"))
+                    .clicked()
+                {
+                    arena.requested = Some(ArenaRequest::Match);
+                }
+                let matches = round_robin(contestants).len();
+                if ui.add_enabled(contestants >= 2, egui::Button::new(tr("arena.tournament")))
+                    .on_hover_text(trf("arena.tournament_hint", &[("count", &contestants), ("matches", &matches)]))
+                    .clicked()
+                {
+                    arena.requested = Some(ArenaRequest::Tournament);
+                }
+                if !arena.results.is_empty() && ui.button(tr("invasion.show_results")).clicked() {
+                    arena.show_results = true;
+                }
+            }
+        });
+    });
+}
+
+/// Results dialog of the last arena run: the tournament table, the matches
+/// and the share of A over the selected match.
+fn render_arena_results(ctx: &egui::Context, lab: &mut LabState) {
+    let arena = &mut lab.arena;
+    if !arena.show_results || arena.results.is_empty() {
+        return;
+    }
+    let mut open = true;
+    egui::Window::new(tr("arena.results"))
+        .id(egui::Id::new("arena_results"))
+        .open(&mut open)
+        .default_size([420.0, 380.0])
+        .show(ctx, |ui| {
+            if arena.results.len() > 1 {
+                egui::Grid::new("arena_standings").num_columns(5).striped(true).show(ui, |ui| {
+                    for header in ["arena.creature", "arena.wins", "arena.draws", "arena.losses", "arena.points"] {
+                        ui.label(egui::RichText::new(tr(header)).strong());
+                    }
+                    ui.end_row();
+                    for row in standings(&arena.results) {
+                        ui.label(&row.name);
+                        ui.label(row.wins.to_string());
+                        ui.label(row.draws.to_string());
+                        ui.label(row.losses.to_string());
+                        ui.label(format!("{:.1}", row.points()));
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+            }
+            egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                for (k, result) in arena.results.iter().enumerate() {
+                    let verdict = match result.outcome() {
+                        Some(MatchOutcome::WinA) => trf("arena.wins_match", &[("name", &result.a)]),
+                        Some(MatchOutcome::WinB) => trf("arena.wins_match", &[("name", &result.b)]),
+                        Some(MatchOutcome::Draw) => tr("arena.draw").to_string(),
+                        None => String::from("–"),
+                    };
+                    let text = format!("{} vs {} — {}", result.a, result.b, verdict);
+                    ui.selectable_value(&mut arena.selected, k, text);
+                }
+            });
+            let Some(result) = arena.results.get(arena.selected) else {
+                return;
+            };
+            if result.cancelled {
+                ui.label(egui::RichText::new(tr("invasion.partial")).small().color(egui::Color32::GRAY));
+            }
+            let points: Vec<[f64; 2]> = result.samples.iter().map(|s| [s.step as f64, s.share_a() as f64 * 100.0]).collect();
+            Plot::new("plot_arena")
+                .height(140.0)
+                .allow_drag(false)
+                .allow_scroll(false)
+                .include_y(0.0)
+                .include_y(100.0)
+                .x_axis_label(tr("invasion.step"))
+                .y_axis_label(trf("arena.share", &[("name", &result.a)]))
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(PlotPoints::new(points)).color(egui::Color32::from_rgb(120, 200, 255)));
+                });
+            if let Some(path) = &arena.csv_path {
+                ui.label(egui::RichText::new(trf("invasion.csv", &[("path", &path.display())])).small());
+            }
+        });
+    arena.show_results = open;
+}
+
+// ======================== Creature Portraits ========================
+
+/// Gallery card size of a portrait (its first frame).
+const PORTRAIT_CARD: f32 = 64.0;
+
+fn render_portrait_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("portrait.title")).id_salt("portraits").show(ui, |ui| {
+        ui.label(
+            egui::RichText::new(trf("portrait.subtitle", &[("dir", &CREATURES_DIR)]))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+
+        ui.horizontal(|ui| {
+            ui.label(tr("perturb.center"));
+            ui.add(egui::DragValue::new(&mut lab.portrait_center[0])
+                .range(0.0..=1.0).speed(0.01).prefix("x="));
+            ui.add(egui::DragValue::new(&mut lab.portrait_center[1])
+                .range(0.0..=1.0).speed(0.01).prefix("y="));
+            ui.toggle_value(&mut lab.portrait_pick_active, tr("portrait.pick"))
+                .on_hover_text(tr("portrait.pick_hint"));
+        });
+        ui.add(egui::Slider::new(&mut lab.portrait.frames, 8..=120).text(tr("portrait.frames")));
+        ui.add(egui::Slider::new(&mut lab.portrait.spacing, 1..=32).text(tr("portrait.spacing")))
+            .on_hover_text(tr("portrait.spacing_hint"));
+        ui.add(egui::Slider::new(&mut lab.portrait.half, 8..=64).text(tr("portrait.window")))
+            .on_hover_text(tr("portrait.window_hint"));
+        ui.add(egui::Slider::new(&mut lab.portrait.delay_ms, 20..=200).text(tr("portrait.delay")));
+
+        match &lab.portrait_capture {
+            Some(capture) => {
+                let (done, total) = (capture.captured(), capture.settings.frames);
+                let mut cancel = false;
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                            .desired_width(160.0)
+                            .text(trf("portrait.capturing", &[("done", &done), ("total", &total)])),
+                    );
+                    cancel = ui.button(tr("portrait.cancel")).clicked();
+                });
+                if cancel {
+                    lab.portrait_capture = None;
+                }
+            }
+            None => {
+                if ui.button(tr("portrait.capture")).on_hover_text(tr("portrait.capture_hint")).clicked() {
+                    lab.portrait_requested = true;
+                }
+            }
+        }
+
+        let count = lab.creatures.get_or_insert_with(|| list_creatures(Path::new(CREATURES_DIR))).len();
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(trf("portrait.gallery", &[("count", &count)])).strong());
+            if ui.small_button("🔄").on_hover_text(tr("common.refresh")).clicked() {
+                lab.creatures = None;
+            }
+        });
+        let Some(creatures) = &lab.creatures else {
+            return;
+        };
+        ui.horizontal_wrapped(|ui| {
+            for entry in creatures {
+                let [r, mu, sigma, aggressivity] = entry.info.genome;
+                let details = trf(
+                    "portrait.details",
+                    &[
+                        ("name", &entry.info.name),
+                        ("frames", &format!("{}–{}", entry.info.start_frame, entry.info.end_frame)),
+                        ("genome", &format!("r {:.1} · μ {:.3} · σ {:.4} · agg {:.2}", r, mu, sigma, aggressivity)),
+                    ],
+                );
+                match cached_texture(ui.ctx(), &mut lab.thumbnails, &entry.gif) {
+                    Some(texture) => ui.image((texture.id(), egui::vec2(PORTRAIT_CARD, PORTRAIT_CARD))),
+                    None => ui.label(&entry.info.name),
+                }
+                .on_hover_text(details);
+            }
+        });
+    });
+}
+
+// ======================== Visualization Section ========================
+
+fn render_visualization_section(
+    ui: &mut egui::Ui,
+    params: &mut SimulationParams,
+    lab: &mut LabState,
+) {
+    egui::CollapsingHeader::new(tr("vis.title")).id_salt("visualization").show(ui, |ui| {
+        for mode in 0..VIS_MODE_COUNT {
+            let label = tr(visualization_mode_key(mode));
+            if ui.radio_value(&mut params.visualization_mode, mode, label).clicked() {
+                log::info!("Visualization mode: {}", visualization_mode_name(mode));
+            }
+        }
+        if !lab.user_modes.is_empty() {
+            ui.label(egui::RichText::new(tr("vis.user_modes")).strong());
+        }
+        for (mode, name) in (VIS_MODE_COUNT..).zip(&lab.user_modes) {
+            if ui.radio_value(&mut params.visualization_mode, mode, name).clicked() {
+                log::info!("Visualization mode: {} (user)", name);
+            }
+        }
+        if ui
+            .button(tr("vis.reload_user_modes"))
+            .on_hover_text(trf("vis.reload_user_modes_hint", &[("dir", &USER_MODES_DIR)]))
+            .clicked()
+        {
+            lab.user_modes_reload_requested = true;
+        }
+        ui.add_space(4.0);
+
+        let can_auto = params.visualization_mode != display::ADVECTION_FLUX_MODE;
+        let adjust = params.current_display_adjust_mut();
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("vis.display_adjust")).strong());
+            ui.add(
+                egui::Slider::new(&mut adjust.exposure, 0.1..=10.0)
+                    .text(tr("vis.exposure"))
+                    .logarithmic(true),
+            );
+            ui.add(egui::Slider::new(&mut adjust.gamma, 0.2..=5.0).text(tr("vis.gamma")));
+            egui::ComboBox::new("display_transform", tr("vis.transform"))
+                .selected_text(tr(adjust.transform.label_key()))
+                .show_ui(ui, |ui| {
+                    for t in DisplayTransform::all() {
+                        ui.selectable_value(&mut adjust.transform, *t, tr(t.label_key()));
+                    }
+                })
+                .response
+                .on_hover_text(tr("vis.transform_hint"));
+            ui.horizontal(|ui| {
+                ui.label(tr("vis.range"));
+                ui.add(egui::DragValue::new(&mut adjust.range_min).speed(0.01).prefix(tr("vis.min")));
+                ui.add(egui::DragValue::new(&mut adjust.range_max).speed(0.01).prefix(tr("vis.max")));
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(can_auto, egui::Button::new(tr("vis.auto_range")))
+                    .on_hover_text(tr("vis.auto_range_hint"))
+                    .clicked()
+                {
+                    lab.auto_normalize_requested = true;
+                }
+                ui.add_enabled(can_auto, egui::Checkbox::new(&mut adjust.auto_normalize, tr("vis.auto_every")));
+                if ui.button(tr("common.reset")).clicked() {
+                    *adjust = DisplayAdjust::default();
+                }
+            });
+        });
+        ui.add_space(4.0);
+
+        ui.group(|ui| {
+            ui.checkbox(&mut params.bloom.enabled, tr("vis.bloom"))
+                .on_hover_text(tr("vis.bloom_hint"));
+            ui.add_enabled_ui(params.bloom.enabled, |ui| {
+                ui.add(egui::Slider::new(&mut params.bloom.emission, 0.0..=5.0).text(tr("vis.emission")));
+                ui.add(egui::Slider::new(&mut params.bloom.threshold, 0.2..=2.0).text(tr("vis.threshold")));
+                ui.add(egui::Slider::new(&mut params.bloom.intensity, 0.0..=3.0).text(tr("common.intensity")));
+            });
+        });
+        ui.add_space(4.0);
+
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .checkbox(&mut params.trail.enabled, tr("vis.trails"))
+                    .on_hover_text(tr("vis.trails_hint"))
+                    .changed()
+                {
+                    lab.trail_clear_requested = true;
+                }
+                if ui.button(tr("common.clear")).clicked() {
+                    lab.trail_clear_requested = true;
+                }
+            });
+            ui.add_enabled_ui(params.trail.enabled, |ui| {
+                ui.add(egui::Slider::new(&mut params.trail.decay, 0.8..=0.999).text(tr("vis.persistence")));
+                ui.add(egui::Slider::new(&mut params.trail.gain, 1.0..=100.0).text(tr("vis.gain")).logarithmic(true));
+                ui.add(egui::Slider::new(&mut params.trail.opacity, 0.0..=2.0).text(tr("vis.opacity")));
+            });
+        });
+        ui.add_space(4.0);
+
+        render_diff_overlay_group(ui, params, lab);
+        ui.add_space(4.0);
+        render_territory_group(ui, params, lab);
+        ui.add_space(4.0);
+        render_species_map_group(ui, params);
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut params.legend.enabled, tr("vis.legend")).on_hover_text(tr("vis.legend_hint"));
+            ui.add_enabled(
+                params.legend.enabled,
+                egui::Checkbox::new(&mut params.legend.in_screenshots, tr("vis.legend_screenshots")),
+            );
+        });
+        ui.horizontal(|ui| {
+            let histogram = &mut params.histogram;
+            ui.checkbox(&mut histogram.enabled, tr("vis.histogram")).on_hover_text(tr("vis.histogram_hint"));
+            ui.add_enabled_ui(histogram.enabled, |ui| {
+                egui::ComboBox::new("histogram_field", "")
+                    .selected_text(tr(histogram.field.label_key()))
+                    .show_ui(ui, |ui| {
+                        for f in HistogramField::all() {
+                            ui.selectable_value(&mut histogram.field, *f, tr(f.label_key()));
+                        }
+                    });
+                ui.checkbox(&mut histogram.log_scale, tr("vis.histogram_log"));
+            });
+        });
+        ui.checkbox(&mut params.vsync, tr("vis.vsync"));
+
+        render_world_resize(ui, lab);
+    });
+}
+
+/// Current world size and the background resize to another one.
+fn render_world_resize(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.label(
+        egui::RichText::new(trf("vis.world", &[("width", &lab.world_size[0]), ("height", &lab.world_size[1])]))
+            .small()
+            .color(egui::Color32::GRAY),
+    );
+    let world = lab.world_size;
+    let resize = &mut lab.resize;
+    ui.horizontal(|ui| {
+        let edges = MIN_WORLD_EDGE..=MAX_WORLD_EDGE;
+        ui.add(egui::DragValue::new(&mut resize.target[0]).range(edges.clone()).speed(64.0));
+        ui.label("×");
+        ui.add(egui::DragValue::new(&mut resize.target[1]).range(edges).speed(64.0));
+        let ready = !resize.is_running() && resize.target != world;
+        if ui.add_enabled(ready, egui::Button::new(tr("vis.resize"))).on_hover_text(tr("vis.resize_hint")).clicked() {
+            resize.requested = true;
+        }
+        if resize.is_running() {
+            ui.spinner();
+            ui.label(egui::RichText::new(tr("vis.resize_building")).small());
+        }
+    });
+}
+
+/// Mass difference against a reference snapshot (file or captured frame).
+fn render_diff_overlay_group(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.group(|ui| {
+        ui.checkbox(&mut params.diff_overlay.enabled, tr("vis.diff_overlay"))
+            .on_hover_text(tr("vis.diff_overlay_hint"));
+        ui.horizontal(|ui| {
+            ui.label(tr("vis.diff_reference"));
+            ui.text_edit_singleline(&mut lab.diff_reference_path)
+                .on_hover_text(tr("vis.diff_path_hint"));
+        });
+        ui.horizontal(|ui| {
+            let has_path = !lab.diff_reference_path.trim().is_empty();
+            if ui.add_enabled(has_path, egui::Button::new(tr("vis.diff_load"))).clicked() {
+                lab.diff_load_requested = true;
+            }
+            if ui.button(tr("vis.diff_capture")).on_hover_text(tr("vis.diff_capture_hint")).clicked() {
+                lab.diff_capture_requested = true;
+            }
+            if ui.add_enabled(lab.diff_reference.is_some(), egui::Button::new(tr("common.clear"))).clicked() {
+                lab.diff_reference = None;
+                lab.diff_divergence = None;
+            }
+        });
+        let status = match (&lab.diff_reference, lab.diff_divergence) {
+            (None, _) => tr("vis.diff_none").to_string(),
+            (Some(r), None) => trf("vis.diff_loaded", &[("name", &r.label)]),
+            (Some(r), Some(d)) => trf("vis.diff_divergence", &[("name", &r.label), ("value", &format!("{:.4}", d))]),
+        };
+        ui.label(egui::RichText::new(status).small().color(egui::Color32::GRAY));
+        ui.add_enabled_ui(params.diff_overlay.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut params.diff_overlay.range, 0.01..=1.0).text(tr("vis.diff_range")).logarithmic(true));
+            ui.add(egui::Slider::new(&mut params.diff_overlay.opacity, 0.0..=1.0).text(tr("vis.opacity")));
+        });
+    });
+}
+
+/// Species territories over the last samples, with a legend of the largest.
+fn render_territory_group(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut params.territory.enabled, tr("vis.territory"))
+                .on_hover_text(tr("vis.territory_hint"));
+            if ui.button(tr("common.clear")).clicked() {
+                lab.territory.clear();
+                lab.territory_areas.clear();
+            }
+        });
+        ui.add_enabled_ui(params.territory.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut params.territory.window, 1..=64).text(tr("vis.territory_window")))
+                .on_hover_text(tr("vis.territory_window_hint"));
+            ui.add(egui::Slider::new(&mut params.territory.opacity, 0.0..=1.0).text(tr("vis.opacity")));
+        });
+        ui.label(
+            egui::RichText::new(trf(
+                "vis.territory_status",
+                &[("species", &lab.territory.species_count()), ("samples", &lab.territory.samples())],
+            ))
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+        let wheel = lab.palette.uniforms().cyclic;
+        for &(species, cells) in lab.territory_areas.iter().take(5) {
+            let [r, g, b] = species_color(species, &wheel);
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("■").color(egui::Color32::from_rgb(r, g, b)));
+                ui.label(
+                    egui::RichText::new(trf("vis.territory_entry", &[("id", &species), ("cells", &cells)])).small(),
+                );
+            });
+        }
+    });
+}
+
+/// GPU species map: territory labels and per-species colors.
+fn render_species_map_group(ui: &mut egui::Ui, params: &mut SimulationParams) {
+    ui.group(|ui| {
+        ui.checkbox(&mut params.species_map.enabled, tr("vis.species_map"))
+            .on_hover_text(tr("vis.species_map_hint"));
+        ui.add_enabled_ui(params.species_map.enabled, |ui| {
+            ui.checkbox(&mut params.species_map.colors, tr("vis.species_colors"))
+                .on_hover_text(tr("vis.species_colors_hint"));
+        });
+    });
+}
+
+// ======================== Experiment Section ========================
+
+fn render_experiment_section(
+    ui: &mut egui::Ui,
+    params: &mut SimulationParams,
+    lab: &mut LabState,
+) {
+    egui::CollapsingHeader::new(tr("exp.title")).id_salt("experiments").show(ui, |ui| {
+        // Seed control
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("exp.reproducibility")).strong());
+            ui.checkbox(&mut params.use_fixed_seed, tr("exp.fixed_seed"));
+            if params.use_fixed_seed {
+                ui.horizontal(|ui| {
+                    ui.label(tr("exp.seed"));
+                    ui.add(egui::DragValue::new(&mut params.fixed_seed_value).range(0..=u64::MAX));
+                });
+            }
+            if let Some(seed) = params.effective_seed() {
+                ui.label(
+                    egui::RichText::new(trf("exp.active_seed", &[("seed", &seed)]))
+                        .small()
+                        .color(egui::Color32::from_rgb(150, 200, 150)),
+                );
+            }
+        });
+
+        render_metadata_group(ui, &mut lab.metadata);
+
+        // Run management
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("exp.run_management")).strong());
+            ui.label(trf("exp.run_id", &[("id", &lab.run_id)]));
+            ui.horizontal(|ui| {
+                ui.label(trf("exp.output_dir", &[("path", &lab.output_root.display())]));
+                if ui.button(tr("exp.choose_output_dir")).on_hover_text(tr("exp.output_dir_hint")).clicked() {
+                    if let Some(dir) = file_dialog::pick_directory(&lab.output_root) {
+                        lab.output_root = dir;
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button(tr("exp.start_run")).clicked() {
+                    lab.start_run(params, None);
+                }
+                if ui.button(tr("exp.finalize_run")).clicked() {
+                    lab.finalize_run(params);
+                }
+            });
+
+            if lab.run_active {
+                ui.label(
+                    egui::RichText::new(tr("exp.recording"))
+                        .color(egui::Color32::from_rgb(100, 255, 100)),
+                );
+            }
+
+            ui.label(trf("exp.metrics_count", &[("count", &lab.metrics_history.len())]));
+            let tensorboard = ui
+                .checkbox(&mut lab.tensorboard_enabled, tr("exp.tensorboard"))
+                .on_hover_text(tr("exp.tensorboard_hint"));
+            if tensorboard.changed() && !lab.tensorboard_enabled {
+                lab.tensorboard = None;
+            }
+            let level = egui::ComboBox::new("session_log_level", tr("exp.session_log"))
+                .selected_text(tr(lab.session_log_level.label_key()))
+                .show_ui(ui, |ui| {
+                    for l in SessionLogLevel::all() {
+                        ui.selectable_value(&mut lab.session_log_level, *l, tr(l.label_key()));
+                    }
+                });
+            level.response.on_hover_text(tr("exp.session_log_hint"));
+            if level.inner.is_some() {
+                session_log::set_level(lab.session_log_level);
+            }
+            ui.checkbox(&mut lab.gpu_validation, tr("exp.gpu_validation"))
+                .on_hover_text(tr("exp.gpu_validation_hint"));
+            if lab.gpu_validation != lab.gpu_validation_active {
+                ui.label(egui::RichText::new(tr("exp.gpu_validation_restart")).small().weak());
+            }
+            if lab.gpu_validation_active {
+                let (distinct, total) = lab.gpu_errors;
+                ui.label(
+                    egui::RichText::new(trf("exp.gpu_errors", &[("distinct", &distinct), ("total", &total)])).small(),
+                );
+            }
+        });
+
+        // Presets
+        ui.group(|ui| {
+            ui.label(egui::RichText::new(tr("exp.presets")).strong());
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut lab.preset_name);
+                if ui.button(tr("common.save")).clicked() {
+                    save_preset(&lab.preset_name, params);
+                    lab.set_status(trf("status.preset_saved", &[("name", &lab.preset_name)]));
+                }
+            });
+            if ui.button(tr("exp.load_preset")).clicked() {
+                if let Some(path) = file_dialog::pick_preset(Path::new(PRESETS_DIR)) {
+                    match load_preset(&path) {
+                        Ok(loaded) => {
+                            if let Some(stem) = path.file_stem() {
+                                lab.preset_name = stem.to_string_lossy().into_owned();
+                            }
+                            let diff = PresetDiff::new(&lab.preset_name, params, &loaded);
+                            if diff.is_empty() {
+                                lab.set_status(trf("status.preset_identical", &[("name", &lab.preset_name)]));
+                            } else {
+                                lab.preset_diff = Some(diff);
+                            }
+                        }
+                        Err(e) => lab.set_warning(trf("status.preset_failed", &[("error", &e)])),
+                    }
+                }
+            }
+            if ui.button(tr("exp.reset_defaults")).clicked() {
+                let vis = params.visualization_mode;
+                *params = SimulationParams::default();
+                params.visualization_mode = vis;
+                lab.set_status(tr("status.params_reset").to_string());
+            }
+        });
+    });
+}
+
+/// Diff of the preset being loaded against the current parameters: the
+/// changed fields, each with its checkbox, and the nested values that differ.
+/// Benchmark result from the first launch (or a rerun) with the settings it
+/// suggests; closing the window keeps the current settings.
+fn render_startup_recommendation(
+    ctx: &egui::Context,
+    params: &mut SimulationParams,
+    lab: &mut LabState,
+    camera: &mut CameraState,
+) {
+    let world = lab.world_size;
+    let Some((bench, rec)) = lab.startup_pending.as_ref() else {
+        return;
+    };
+    let mut open = true;
+    let mut decision = None;
+    egui::Window::new(tr("startup.title"))
+        .id(egui::Id::new("startup_recommendation"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(trf("startup.measured", &[("gpu", &bench.gpu), ("ms", &format!("{:.3}", bench.ms_per_step))]));
+            if bench.integrated {
+                ui.label(egui::RichText::new(tr("startup.integrated")).small().weak());
+            }
+            ui.separator();
+            egui::Grid::new("startup_recommendation_grid").num_columns(3).spacing([20.0, 4.0]).show(ui, |ui| {
+                ui.label("");
+                ui.label(egui::RichText::new(tr("startup.current")).strong());
+                ui.label(egui::RichText::new(tr("startup.recommended")).strong());
+                ui.end_row();
+                let on_off = |v: bool| if v { tr("startup.on") } else { tr("startup.off") };
+                for (key, current, recommended) in [
+                    ("startup.speed", params.simulation_speed.to_string(), rec.simulation_speed.to_string()),
+                    ("startup.vsync", on_off(params.vsync).to_string(), on_off(rec.vsync).to_string()),
+                    (
+                        "startup.render_scale",
+                        format!("{:.0}%", camera.render_scale * 100.0),
+                        format!("{:.0}%", rec.render_scale * 100.0),
+                    ),
+                    ("startup.world_size", format!("{}×{}", world[0], world[1]), format!("{0}×{0}", rec.world_edge)),
+                ] {
+                    ui.label(tr(key));
+                    ui.label(current);
+                    ui.label(recommended);
+                    ui.end_row();
+                }
+            });
+            ui.label(egui::RichText::new(tr("startup.world_size_hint")).small().weak());
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(tr("startup.apply")).clicked() {
+                    decision = Some(true);
+                }
+                if ui.button(tr("startup.keep")).clicked() {
+                    decision = Some(false);
+                }
+            });
+        });
+    if !open {
+        decision = Some(false);
+    }
+    let Some(applied) = decision else {
+        return;
+    };
+    let (bench, recommendation) = lab.startup_pending.take().expect("checked above");
+    if applied {
+        params.simulation_speed = recommendation.simulation_speed;
+        params.vsync = recommendation.vsync;
+        camera.render_scale = recommendation.render_scale;
+    }
+    let details = format!(
+        "{} ({:.3} ms/step): {}",
+        bench.gpu,
+        bench.ms_per_step,
+        if applied { "recommended settings applied" } else { "current settings kept" }
+    );
+    lab.log_event(lab.current_frame, "STARTUP_BENCH", &details);
+    lab.startup_choice = Some(StartupChoice { bench, recommendation, applied });
+}
+
+fn render_preset_diff(ctx: &egui::Context, params: &mut SimulationParams, lab: &mut LabState) {
+    let Some(diff) = lab.preset_diff.as_mut() else {
+        return;
+    };
+    let mut open = true;
+    let mut apply = false;
+    egui::Window::new(trf("preset_diff.title", &[("name", &diff.name)]))
+        .id(egui::Id::new("preset_diff"))
+        .open(&mut open)
+        .default_size([480.0, 360.0])
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new(trf("preset_diff.summary", &[("count", &diff.changes.len())]))
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+            ui.horizontal(|ui| {
+                if ui.small_button(tr("preset_diff.select_all")).clicked() {
+                    diff.select_all(true);
+                }
+                if ui.small_button(tr("preset_diff.select_none")).clicked() {
+                    diff.select_all(false);
+                }
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(280.0).show(ui, |ui| {
+                let changed = egui::Color32::from_rgb(255, 200, 80);
+                for change in &mut diff.changes {
+                    let details = change.details();
+                    ui.checkbox(&mut change.apply, egui::RichText::new(&change.field).strong().color(changed));
+                    for (path, current, preset) in details {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.add_space(24.0);
+                            ui.label(egui::RichText::new(path).small().monospace());
+                            ui.label(egui::RichText::new(current).small().color(egui::Color32::GRAY));
+                            ui.label(egui::RichText::new("→").small());
+                            ui.label(egui::RichText::new(preset).small().color(changed));
+                        });
+                    }
+                }
+            });
+            ui.separator();
+            let count = diff.selected().len();
+            ui.add_enabled_ui(count > 0, |ui| {
+                if ui.button(trf("preset_diff.apply", &[("count", &count)])).clicked() {
+                    apply = true;
+                }
+            });
+        });
+    if apply {
+        let Some(diff) = lab.preset_diff.take() else {
+            return;
+        };
+        match diff.merged(params) {
+            Ok(merged) => {
+                let fields = diff.selected().join(", ");
+                *params = merged;
+                lab.log_event_with_payload(
+                    lab.current_frame,
+                    EventSeverity::Info,
+                    "PRESET",
+                    &format!("Applied preset {}: {}", diff.name, fields),
+                    serde_json::json!({ "preset": diff.name, "fields": diff.selected() }),
+                );
+                lab.set_status(trf("status.preset_loaded", &[("name", &diff.name)]));
+            }
+            Err(e) => lab.set_warning(trf("status.preset_failed", &[("error", &e)])),
+        }
+    } else if !open {
+        lab.preset_diff = None;
+    }
+}
+
+/// Optional metadata written into each new run's config.json, .zenodo.json
+/// and report.
+fn render_metadata_group(ui: &mut egui::Ui, metadata: &mut ExperimentMetadata) {
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(tr("exp.metadata")).strong())
+            .on_hover_text(tr("exp.metadata_hint"));
+        egui::Grid::new("experiment_metadata").num_columns(2).show(ui, |ui| {
+            ui.label(tr("exp.author"));
+            ui.add(egui::TextEdit::singleline(&mut metadata.author).hint_text(tr("exp.author_hint")));
+            ui.end_row();
+            ui.label(tr("exp.tags"));
+            ui.add(egui::TextEdit::singleline(&mut metadata.tags).hint_text(tr("exp.tags_hint")));
+            ui.end_row();
+            ui.label(tr("exp.license"));
+            ui.add(egui::TextEdit::singleline(&mut metadata.license).hint_text("CC-BY-4.0"));
+            ui.end_row();
+        });
+        ui.label(tr("exp.description"));
+        ui.add(egui::TextEdit::multiline(&mut metadata.description).desired_rows(2).desired_width(f32::INFINITY));
+    });
+}
+
+// ======================== Capture Section ========================
+
+fn render_capture_section(
+    ui: &mut egui::Ui,
+    params: &SimulationParams,
+    lab: &mut LabState,
+) {
+    egui::CollapsingHeader::new(tr("capture.title")).id_salt("capture").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            if ui.button(tr("capture.screenshot")).clicked() {
+                lab.screenshot_requested = true;
+            }
+            if ui.button(tr("capture.snapshot")).clicked() {
+                lab.snapshot_requested = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button(tr("capture.save_snapshot_as")).clicked() {
+                let name = format!("snapshot_frame{:06}.{}", lab.current_frame, SNAPSHOT_EXTENSION);
+                if let Some(path) = file_dialog::save_snapshot(&lab.run_dir, &name) {
+                    lab.snapshot_save_path = Some(path);
+                    lab.snapshot_requested = true;
+                }
+            }
+            if ui.button(tr("capture.load_snapshot")).clicked() {
+                lab.snapshot_load_path = file_dialog::pick_snapshot(&lab.run_dir);
+            }
+        });
+
+        if ui.button(tr("capture.export_csv")).clicked() {
+            match lab.export_metrics_csv() {
+                Ok(path) => lab.set_status(trf("status.exported", &[("path", &format!("{:?}", path))])),
+                Err(e) => {
+                    lab.log_event_with_severity(lab.current_frame, EventSeverity::Warn, "EXPORT", &e);
+                    lab.set_warning(trf("status.export_failed", &[("error", &e)]));
+                }
+            }
+        }
+
+        if ui.button(tr("capture.preview_report")).on_hover_text(tr("capture.preview_report_hint")).clicked() {
+            regenerate_report(params, lab);
+            lab.show_report_panel = true;
+        }
+        if ui.button(tr("capture.export_report")).clicked() {
+            match lab.export_report(params) {
+                Ok(path) => lab.set_status(trf("status.report_saved", &[("path", &format!("{:?}", path))])),
+                Err(e) => {
+                    lab.log_event_with_severity(lab.current_frame, EventSeverity::Warn, "EXPORT", &e);
+                    lab.set_warning(trf("status.report_failed", &[("error", &e)]));
+                }
+            }
+        }
+
+        ui.add_space(4.0);
+        render_population_export(ui, lab);
+        ui.add_space(4.0);
+        render_tape_recording(ui, lab);
+        ui.add_space(4.0);
+        render_tape_playback(ui, lab);
+    });
+}
+
+/// Per-cell genome table (population genetics), now or at a chosen frame.
+fn render_population_export(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(tr("capture.population")).strong())
+            .on_hover_text(tr("capture.population_hint"));
+        ui.horizontal(|ui| {
+            for format in PopgenFormat::all() {
+                ui.radio_value(&mut lab.popgen_format, format, format.extension().to_uppercase());
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button(tr("capture.population_now")).clicked() {
+                lab.popgen_export_requested = true;
+            }
+            ui.checkbox(&mut lab.popgen_scheduled, tr("capture.population_at"))
+                .on_hover_text(tr("capture.population_at_hint"));
+            ui.add_enabled(
+                lab.popgen_scheduled,
+                egui::DragValue::new(&mut lab.popgen_frame).range(0..=u32::MAX).speed(100.0),
+            );
+        });
+    });
+}
+
+/// Simulation tape: downsampled channels + metrics every few steps.
+fn render_tape_recording(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(tr("tape.title")).strong()).on_hover_text(tr("tape.hint"));
+        let world = lab.world_size;
+        let tape = &mut lab.tape;
+        ui.add_enabled_ui(!tape.is_recording(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("tape.downsample"));
+                ui.add(egui::DragValue::new(&mut tape.settings.downsample).range(1..=16).prefix("1/"));
+                let [w, h] = tape_size(world, tape.settings.downsample);
+                ui.label(egui::RichText::new(format!("{w}×{h}")).small().color(egui::Color32::GRAY));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("tape.interval"));
+                ui.add(egui::DragValue::new(&mut tape.settings.interval).range(1..=10_000).suffix(tr("tape.steps")));
+            });
+        });
+        match &tape.writer {
+            Some(writer) => {
+                ui.horizontal(|ui| {
+                    if ui.button(tr("tape.stop")).clicked() {
+                        tape.stop_requested = true;
+                    }
+                    let size = format_bytes(writer.bytes);
+                    ui.label(egui::RichText::new(trf("tape.recording", &[("frames", &writer.frames), ("size", &size)])).color(egui::Color32::LIGHT_RED));
+                });
+            }
+            None => {
+                let button = ui.add_enabled(lab.playback.player.is_none(), egui::Button::new(tr("tape.record")));
+                if button.clicked() {
+                    tape.start_requested = true;
+                }
+            }
+        }
+        if let Some(path) = &tape.last_path {
+            ui.label(egui::RichText::new(trf("tape.last", &[("path", &path.display())])).small());
+        }
+    });
+}
+
+/// Tape playback: transport, frame metrics and sequence or video export.
+fn render_tape_playback(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(tr("playback.title")).strong()).on_hover_text(tr("playback.hint"));
+        let Some(player) = lab.playback.player.as_mut() else {
+            let open = ui.add_enabled(!lab.tape.is_recording(), egui::Button::new(tr("playback.open")));
+            if open.clicked() {
+                lab.playback.open_path = file_dialog::pick_tape(&lab.run_dir);
+            }
+            return;
+        };
+        let header = &player.reader.header;
+        let name = player.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        ui.label(egui::RichText::new(trf(
+            "playback.info",
+            &[
+                ("name", &name),
+                ("frames", &player.reader.len()),
+                ("size", &format!("{}×{}", header.size[0], header.size[1])),
+                ("interval", &header.interval),
+            ],
+        ))
+        .small());
+
+        let last = player.reader.len() - 1;
+        if let Some(export) = &player.export {
+            let total = export.end + 1 + export.written as usize - export.next;
+            ui.add(egui::ProgressBar::new(export.written as f32 / total as f32).text(trf(
+                "playback.exporting",
+                &[("done", &export.written), ("total", &total)],
+            )));
+        } else {
+            ui.horizontal(|ui| {
+                if ui.button("⏮").clicked() {
+                    player.position = 0;
+                }
+                if ui.button("◀").clicked() {
+                    player.position = player.position.saturating_sub(1);
+                }
+                let play = if player.playing { "⏸" } else { "▶" };
+                if ui.button(play).clicked() {
+                    if player.position == last {
+                        player.position = 0;
+                    }
+                    player.playing = !player.playing;
+                }
+                if ui.button("▶|").clicked() {
+                    player.position = (player.position + 1).min(last);
+                }
+                if ui.button("⏭").clicked() {
+                    player.position = last;
+                }
+                ui.add(egui::DragValue::new(&mut player.fps).range(0.5..=60.0).speed(0.5).suffix(tr("playback.fps")));
+            });
+            ui.add(egui::Slider::new(&mut player.position, 0..=last).show_value(false));
+            ui.horizontal(|ui| {
+                ui.label(tr("playback.frame"));
+                let (first, end) = (player.frame_at(0), player.frame_at(last));
+                let mut frame = player.frame_at(player.position);
+                if ui.add(egui::DragValue::new(&mut frame).range(first..=end)).changed() {
+                    player.seek(frame);
+                }
+                ui.label(egui::RichText::new(format!("{}/{}", player.position + 1, last + 1)).small().color(egui::Color32::GRAY));
+            });
+        }
+
+        if let Some(m) = &player.metrics {
+            ui.label(egui::RichText::new(trf(
+                "playback.metrics",
+                &[("species", &m.species), ("mass", &format!("{:.0}", m.total_mass)), ("entropy", &format!("{:.3}", m.entropy))],
+            ))
+            .small());
+        }
+
+        ui.horizontal(|ui| {
+            let idle = player.export.is_none();
+            let export = ui.add_enabled(idle, egui::Button::new(tr("playback.export"))).on_hover_text(tr("playback.export_hint"));
+            if export.clicked() {
+                lab.playback.export_requested = Some((player.position, last));
+            }
+            ui.add_enabled_ui(idle, |ui| {
+                ui.checkbox(&mut lab.playback.export_video, tr("playback.export_video"))
+                    .on_hover_text(tr("playback.export_video_hint"));
+            });
+            if ui.button(tr("playback.close")).clicked() {
+                lab.playback.close_requested = true;
+            }
+        });
+    });
+}
+
+// ======================== View Toggles ========================
+
+fn render_view_toggles(ui: &mut egui::Ui, lab: &mut LabState, camera: &mut CameraState) {
+    egui::CollapsingHeader::new(tr("view.title")).id_salt("view").show(ui, |ui| {
+        ui.checkbox(&mut lab.show_analysis_panel, tr("view.analysis_panel"));
+        ui.checkbox(&mut lab.show_logs_panel, tr("view.logs_panel"));
+        ui.checkbox(&mut lab.show_performance_panel, tr("view.performance_panel"));
+        ui.checkbox(&mut lab.show_resources_panel, tr("view.resources_panel"));
+        ui.checkbox(&mut lab.show_queue_panel, tr("view.queue_panel"));
+        ui.checkbox(&mut lab.show_report_panel, tr("view.report_panel"));
+        ui.add(
+            egui::Slider::new(&mut lab.ui_scale, 0.5..=3.0)
+                .text(tr("view.ui_scale"))
+                .step_by(0.05),
+        )
+        .on_hover_text(tr("view.ui_scale_hint"));
+        egui::ComboBox::new("ui_language", tr("view.language"))
+            .selected_text(lab.language.name())
+            .show_ui(ui, |ui| {
+                for l in Language::all() {
+                    ui.selectable_value(&mut lab.language, *l, l.name());
+                }
+            });
+        egui::ComboBox::new("ui_theme", tr("view.theme"))
+            .selected_text(tr(lab.ui_theme.label_key()))
+            .show_ui(ui, |ui| {
+                for t in UiTheme::all() {
+                    ui.selectable_value(&mut lab.ui_theme, *t, tr(t.label_key()));
+                }
+            });
+        egui::ComboBox::new("render_palette", tr("view.palette"))
+            .selected_text(tr(lab.palette.label_key()))
+            .show_ui(ui, |ui| {
+                for p in ColorPalette::all() {
+                    ui.selectable_value(&mut lab.palette, *p, tr(p.label_key()));
+                }
+            })
+            .response
+            .on_hover_text(tr("view.palette_hint"));
+        ui.checkbox(&mut camera.pixel_grid, tr("view.pixel_grid"));
+        ui.add_enabled(
+            lab.smooth_sampling_supported,
+            egui::Checkbox::new(&mut camera.smooth_sampling, tr("view.smooth_sampling")),
+        )
+        .on_hover_text(tr("view.smooth_sampling_hint"))
+        .on_disabled_hover_text(tr("view.smooth_sampling_unsupported"));
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut camera.snap_zoom, tr("view.integer_zoom"))
+                .on_hover_text(tr("view.integer_zoom_hint"))
+                .changed()
+                && camera.snap_zoom
+            {
+                lab.zoom_snap_requested = true;
+            }
+            if ui.button(tr("view.snap")).clicked() {
+                lab.zoom_snap_requested = true;
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut camera.render_scale, RENDER_SCALE_RANGE.0..=RENDER_SCALE_RANGE.1)
+                .text(tr("view.render_scale"))
+                .step_by(0.25)
+                .suffix("×"),
+        )
+        .on_hover_text(tr("view.render_scale_hint"));
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("world_fit", tr("view.fit"))
+                .selected_text(tr(camera.fit.label_key()))
+                .show_ui(ui, |ui| {
+                    for f in WorldFit::all() {
+                        ui.selectable_value(&mut camera.fit, *f, tr(f.label_key()));
+                    }
+                })
+                .response
+                .on_hover_text(tr("view.fit_hint"));
+            ui.color_edit_button_rgb(&mut camera.letterbox).on_hover_text(tr("view.letterbox"));
+        });
+        render_surface_view_controls(ui, camera);
+    });
+}
+
+/// 3D surface view toggle and orbit camera settings.
+fn render_surface_view_controls(ui: &mut egui::Ui, camera: &mut CameraState) {
+    ui.separator();
+    ui.checkbox(&mut camera.surface_view, tr("view.surface"))
+        .on_hover_text(tr("view.surface_hint"));
+    ui.add_enabled_ui(camera.surface_view, |ui| {
+        ui.add(
+            egui::Slider::new(&mut camera.orbit.height_scale, 0.0..=0.5)
+                .text(tr("view.surface_height"))
+                .step_by(0.01),
+        );
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut camera.orbit.auto_rotate, tr("view.surface_rotate"));
+            if ui.button(tr("view.surface_reset")).clicked() {
+                camera.orbit = OrbitCamera { height_scale: camera.orbit.height_scale, ..OrbitCamera::default() };
+            }
+        });
+    });
+}
+
+// ======================== Analysis Tab ========================
+
+fn render_analysis_tab(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("📈").size(24.0));
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new(tr("analysis.title")).size(20.0).strong().color(egui::Color32::from_rgb(150, 220, 150)));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let (label, hint) = if lab.analysis_detached {
+                (tr("analysis.dock"), tr("analysis.dock_hint"))
+            } else {
+                (tr("analysis.detach"), tr("analysis.detach_hint"))
+            };
+            if ui.button(label).on_hover_text(hint).clicked() {
+                lab.analysis_detached = !lab.analysis_detached;
+            }
+        });
+    });
+    ui.add_space(8.0);
+    ui.separator();
+
+    if lab.metrics_history.is_empty() {
+        ui.label(tr("analysis.no_data"));
+    }
+
+    // Live stats table; each value, the table or the whole sample can be copied
+    if let Some(last) = lab.metrics_history.last() {
+        let rows = [
+            (tr("stat.frame"), format!("{}", last.frame)),
+            (tr("stat.fps"), format!("{:.0}", last.fps)),
+            (tr("stat.total_mass"), format!("{:.0}", last.total_mass)),
+            (tr("stat.avg_energy"), format!("{:.4}", last.avg_energy)),
+            (tr("stat.entropy"), format!("{:.2} bits", last.entropy)),
+            (tr("stat.species"), format!("{}", last.species)),
+            (tr("stat.live_pixels"), format!("{} ({:.1}%)", last.live_pixels, last.live_fraction * 100.0)),
+            (tr("stat.predators"), format!("{:.1}%", last.predator_fraction * 100.0)),
+            (tr("stat.avg_resource"), format!("{:.3}", last.avg_resource)),
+            (tr("stat.mass_std"), format!("{:.4}", last.mass_std_dev)),
+            // Phase 1 eco metrics
+            (tr("stat.prey"), format!("{:.1}%", last.prey_fraction * 100.0)),
+            (tr("stat.opportunist"), format!("{:.1}%", last.opportunist_fraction * 100.0)),
+            (tr("stat.eff_diversity"), format!("{:.2}", last.effective_diversity)),
+            (tr("stat.genome_var"), format!("{:.4}", last.genome_variance)),
+            (tr("stat.total_energy"), format!("{:.0}", last.total_energy)),
+            (tr("stat.energy_flux"), format!("{:.4}", last.energy_flux)),
+        ];
+        let mut copied = None;
+        egui::Grid::new("live_stats")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (label, value) in &rows {
+                    ui.label(egui::RichText::new(*label).color(egui::Color32::from_rgb(180, 180, 200)));
+                    ui.label(egui::RichText::new(value).monospace().strong().color(egui::Color32::from_rgb(220, 220, 240)));
+                    if ui.small_button("📋").on_hover_text(tr("stat.copy_value")).clicked() {
+                        copied = Some(value.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button(tr("stat.copy_table")).on_hover_text(tr("stat.copy_table_hint")).clicked() {
+                copied = Some(rows.iter().map(|(label, value)| format!("{}\t{}\n", label, value)).collect());
+            }
+            if ui.button(tr("stat.copy_sample")).on_hover_text(tr("stat.copy_sample_hint")).clicked() {
+                copied = Some(metrics_tsv(last, &lab.custom_metrics));
+            }
+        });
+        if let Some(text) = copied {
+            ui.ctx().copy_text(text);
+            lab.set_status(tr("status.copied").to_string());
+        }
+    }
+    ui.separator();
+
+    // Time-series plots; any chart's export button fills `export`
+    let mut export = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        export = render_profile_section(ui, lab);
+        ui.separator();
+        render_interaction_section(ui, params, lab);
+        ui.separator();
+        render_speciation_section(ui, params, lab);
+        ui.separator();
+        render_forensics_section(ui, params, lab);
+        ui.separator();
+        render_averages_section(ui, params, lab);
+        ui.separator();
+        render_velocity_export_section(ui, params, lab);
+        ui.separator();
+        render_replay_section(ui, lab);
+        ui.separator();
+        render_landscape_section(ui, lab);
+        ui.separator();
+        render_custom_metrics_section(ui, params, lab);
+        ui.separator();
+        render_alert_rules_section(ui, params, lab);
+        ui.separator();
+        render_conditions_section(ui, lab);
+        ui.separator();
+
+        render_plot_export_settings(ui, lab);
+        // Frame marked from the event log, and timeline event markers
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut lab.show_event_markers, tr("analysis.event_markers"))
+                .on_hover_text(tr("analysis.event_markers_hint"));
+            if let Some(frame) = lab.marked_frame {
+                ui.label(egui::RichText::new(trf("analysis.marked_frame", &[("frame", &frame)])).color(MARKER_COLOR));
+                if ui.small_button(tr("common.clear")).clicked() {
+                    lab.marked_frame = None;
+                }
+            }
+        });
+        let markers = PlotMarkers {
+            frame: lab.marked_frame,
+            events: lab.timeline_events(),
+            conditions: &lab.conditions.spans,
+            now: lab.current_frame,
+        };
+
+        if !lab.metrics_history.is_empty() {
+            let series: [(&str, MetricFn); 9] = [
+                (tr("stat.total_mass"), |m| m.total_mass as f64),
+                (tr("stat.avg_energy"), |m| m.avg_energy as f64),
+                (tr("plot.entropy"), |m| m.entropy as f64),
+                (tr("plot.species"), |m| m.species as f64),
+                (tr("stat.live_pixels"), |m| m.live_pixels as f64),
+                (tr("stat.fps"), |m| m.fps as f64),
+                // Phase 1 eco plots
+                (tr("plot.eff_diversity"), |m| m.effective_diversity as f64),
+                (tr("stat.energy_flux"), |m| m.energy_flux as f64),
+                (tr("plot.genome_var"), |m| m.genome_variance as f64),
+            ];
+            for (title, value_fn) in series {
+                export = render_plot(ui, title, &lab.metrics_history, &markers, value_fn).or(export.take());
+            }
+            for (i, name) in plugin_fields().into_iter().enumerate() {
+                let value_fn = |m: &crate::lab::MetricsRecord| m.plugin(i);
+                export = render_plot(ui, name, &lab.metrics_history, &markers, value_fn).or(export.take());
+            }
+            for metric in &lab.custom_metrics {
+                let value_fn = |m: &crate::lab::MetricsRecord| metric.eval(m);
+                export = render_plot(ui, &metric.name, &lab.metrics_history, &markers, value_fn).or(export.take());
+            }
+        }
+
+        // Gallery and comparison of completed runs
+        if !lab.completed_runs.is_empty() {
+            ui.separator();
+            ui.heading(tr("analysis.gallery"));
+            render_run_gallery(ui, params, lab);
+            ui.separator();
+            ui.heading(tr("analysis.comparison"));
+            export = render_comparison_ui(ui, lab).or(export.take());
+        }
+    });
+    if let Some(figure) = export {
+        lab.export_plot(figure, params);
+    }
+}
+
+/// Named conditions of the run: begin one (ending the current), end it, or
+/// delete a span. Beginning and ending are logged as CONDITION events.
+fn render_conditions_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    egui::CollapsingHeader::new(tr("condition.title")).id_salt("conditions").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("condition.hint")).small().color(egui::Color32::GRAY));
+        let frame = lab.current_frame;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut lab.condition_name)
+                    .hint_text(tr("condition.name_hint"))
+                    .desired_width(140.0),
+            );
+            let name = lab.condition_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new(tr("condition.begin"))).clicked() {
+                lab.conditions.begin(&name, frame);
+                lab.log_event_with_payload(
+                    frame,
+                    EventSeverity::Info,
+                    "CONDITION",
+                    &format!("Condition '{}' begins", name),
+                    serde_json::json!({ "name": name, "start": frame }),
+                );
+                lab.condition_name.clear();
+            }
+            if ui.add_enabled(lab.conditions.current().is_some(), egui::Button::new(tr("condition.end"))).clicked() {
+                if let Some(ended) = lab.conditions.end(frame).cloned() {
+                    lab.log_event_with_payload(
+                        frame,
+                        EventSeverity::Info,
+                        "CONDITION",
+                        &format!("Condition '{}' ends", ended.name),
+                        serde_json::json!({ "name": ended.name, "start": ended.start, "end": ended.end }),
+                    );
+                }
+            }
+        });
+        let mut remove = None;
+        for (i, condition) in lab.conditions.spans.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let swatch = condition_color(&condition.name).to_opaque();
+                ui.label(egui::RichText::new("■").color(swatch));
+                ui.label(egui::RichText::new(&condition.name).strong());
+                let range = match condition.end {
+                    Some(end) => format!("{} – {}", condition.start, end),
+                    None => trf("condition.in_progress", &[("start", &condition.start)]),
+                };
+                ui.label(egui::RichText::new(range).small().monospace().color(egui::Color32::GRAY));
+                if ui.small_button("🗑").on_hover_text(tr("condition.remove")).clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            lab.conditions.spans.remove(i);
+        }
+        ui.separator();
+        render_condition_stats(ui, lab);
+    });
+}
+
+/// Comparison of two conditions (this run's or a completed run's) over the
+/// selected metrics.
+fn render_condition_stats(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("condition.stats")).strong())
+        .on_hover_text(tr("condition.stats_hint"));
+    let groups = available_groups(lab);
+    if groups.is_empty() {
+        ui.label(egui::RichText::new(tr("condition.stats_none")).small().color(egui::Color32::GRAY));
+        return;
+    }
+    let metric_names: Vec<String> = MetricsRecord::field_names()
+        .into_iter()
+        .map(String::from)
+        .chain(lab.custom_metrics.iter().map(|m| m.name.clone()))
+        .collect();
+    let stats = &mut lab.condition_stats;
+    for (slot, label, salt) in [(&mut stats.a, "A", "condition_a"), (&mut stats.b, "B", "condition_b")] {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            let selected = slot.as_ref().map_or_else(|| tr("condition.pick").to_string(), GroupRef::label);
+            egui::ComboBox::from_id_salt(salt).selected_text(selected).width(240.0).show_ui(ui, |ui| {
+                for group in &groups {
+                    if ui.selectable_label(slot.as_ref() == Some(group), group.label()).clicked() {
+                        *slot = Some(group.clone());
+                    }
+                }
+            });
+        });
+    }
+    let mut compare = false;
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("condition_metrics")
+            .selected_text(trf("condition.metrics", &[("count", &stats.metrics.len())]))
+            .width(160.0)
+            .show_ui(ui, |ui| {
+                for name in &metric_names {
+                    let mut on = stats.metrics.contains(name);
+                    if ui.checkbox(&mut on, name).changed() {
+                        if on {
+                            stats.metrics.push(name.clone());
+                        } else {
+                            stats.metrics.retain(|m| m != name);
+                        }
+                    }
+                }
+            });
+        let ready = stats.a.is_some() && stats.b.is_some() && !stats.metrics.is_empty();
+        compare = ui.add_enabled(ready, egui::Button::new(tr("condition.compare"))).clicked();
+    });
+    if compare {
+        lab.condition_stats.results = Some(compare_groups(lab, &lab.condition_stats));
+    }
+    match &lab.condition_stats.results {
+        Some(Ok(results)) => {
+            egui::Grid::new("condition_stats_grid").striped(true).num_columns(5).show(ui, |ui| {
+                for header in [tr("condition.metric"), "A", "B", "d", "p"] {
+                    ui.label(egui::RichText::new(header).small().strong());
+                }
+                ui.end_row();
+                for r in results {
+                    ui.label(egui::RichText::new(&r.metric).small().monospace());
+                    ui.label(egui::RichText::new(summary_cell(r.a.as_ref())).small());
+                    ui.label(egui::RichText::new(summary_cell(r.b.as_ref())).small());
+                    ui.label(egui::RichText::new(r.effect_size.map_or_else(|| String::from("—"), |d| format!("{:.2}", d))).small());
+                    match r.mann_whitney {
+                        Some(m) => {
+                            let color = if m.p < 0.05 { egui::Color32::from_rgb(120, 220, 120) } else { egui::Color32::GRAY };
+                            ui.label(egui::RichText::new(format_p(m.p)).small().color(color))
+                                .on_hover_text(format!("U = {:.0}, z = {:.2}", m.u, m.z));
+                        }
+                        None => {
+                            ui.label("—");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui.small_button(tr("condition.copy_table")).clicked() {
+                ui.ctx().copy_text(comparison_table(results));
+                lab.set_status(tr("status.copied").to_string());
+            }
+        }
+        Some(Err(e)) => {
+            ui.label(egui::RichText::new(e).small().color(egui::Color32::from_rgb(255, 120, 100)));
+        }
+        None => {}
+    }
+}
+
+/// Editor for derived metrics; valid ones are plotted after the built-ins
+/// and exported with them.
+fn render_custom_metrics_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &LabState) {
+    egui::CollapsingHeader::new(tr("custom.title")).id_salt("custom_metrics").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("custom.hint")).small().color(egui::Color32::GRAY));
+        let mut remove = None;
+        for (i, def) in params.custom_metrics.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut def.name).hint_text(tr("custom.name")).desired_width(110.0));
+                ui.label("=");
+                ui.add(
+                    egui::TextEdit::singleline(&mut def.expression)
+                        .hint_text("total_energy / total_mass")
+                        .code_editor()
+                        .desired_width(220.0),
+                );
+                if ui.small_button("🗑").on_hover_text(tr("custom.remove")).clicked() {
+                    remove = Some(i);
+                }
+            });
+            let status = match compile_metric(def) {
+                Err(e) => egui::RichText::new(trf("custom.error", &[("error", &e)])).color(egui::Color32::from_rgb(255, 120, 100)),
+                Ok(metric) => match lab.metrics_history.last() {
+                    Some(last) => egui::RichText::new(trf("custom.latest", &[("value", &format!("{:.6}", metric.eval(last)))]))
+                        .color(egui::Color32::GRAY),
+                    None => egui::RichText::new(tr("custom.ok")).color(egui::Color32::GRAY),
+                },
+            };
+            ui.label(status.small());
+        }
+        if let Some(i) = remove {
+            params.custom_metrics.remove(i);
+        }
+        if ui.button(tr("custom.add")).clicked() {
+            params.custom_metrics.push(CustomMetric::default());
+        }
+        ui.collapsing(tr("custom.reference"), |ui| {
+            ui.label(egui::RichText::new(MetricsRecord::field_names().join(", ")).small().monospace());
+            let functions: Vec<&str> = Func::all().iter().map(|f| f.name()).collect();
+            ui.label(egui::RichText::new(format!("+ - * / ^ ( )   {}", functions.join(" "))).small().monospace());
+        });
+    });
+}
+
+/// Alert rules: metric, comparator, threshold, sustained samples, actions.
+fn render_alert_rules_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &LabState) {
+    egui::CollapsingHeader::new(tr("alert.title")).id_salt("alert_rules").show(ui, |ui| {
+        ui.label(egui::RichText::new(tr("alert.hint")).small().color(egui::Color32::GRAY));
+        let metrics: Vec<&str> = MetricsRecord::field_names()
+            .into_iter()
+            .chain(lab.custom_metrics.iter().map(|m| m.name.as_str()))
+            .collect();
+        let mut remove = None;
+        for (i, rule) in params.alert_rules.iter_mut().enumerate() {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut rule.enabled, "");
+                    egui::ComboBox::from_id_salt(("alert_metric", i))
+                        .selected_text(&rule.metric)
+                        .width(140.0)
+                        .show_ui(ui, |ui| {
+                            for name in &metrics {
+                                ui.selectable_value(&mut rule.metric, name.to_string(), *name);
+                            }
+                        });
+                    egui::ComboBox::from_id_salt(("alert_comparator", i))
+                        .selected_text(tr(rule.comparator.label_key()))
+                        .show_ui(ui, |ui| {
+                            for c in Comparator::all() {
+                                ui.selectable_value(&mut rule.comparator, c, tr(c.label_key()));
+                            }
+                        });
+                    let suffix = if rule.comparator.is_relative() { " %" } else { "" };
+                    ui.add(egui::DragValue::new(&mut rule.threshold).speed(0.1).suffix(suffix));
+                    if ui.small_button("🗑").on_hover_text(tr("alert.remove")).clicked() {
+                        remove = Some(i);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if rule.comparator.is_relative() {
+                        ui.label(tr("alert.within"));
+                        ui.add(egui::DragValue::new(&mut rule.window_frames).range(1..=u32::MAX).speed(10.0).suffix(" f"));
+                    }
+                    ui.label(tr("alert.sustained"));
+                    ui.add(egui::DragValue::new(&mut rule.sustained).range(1..=1000));
+                    ui.checkbox(&mut rule.pause, tr("alert.pause"));
+                    ui.checkbox(&mut rule.snapshot, tr("alert.snapshot"));
+                });
+                let (status, color) = if !rule.enabled {
+                    (tr("alert.disabled").to_string(), egui::Color32::GRAY)
+                } else if !metrics.contains(&rule.metric.as_str()) {
+                    (trf("alert.unknown_metric", &[("metric", &rule.metric)]), egui::Color32::from_rgb(255, 120, 100))
+                } else if lab.alerts.is_firing(i) {
+                    (tr("alert.firing").to_string(), egui::Color32::from_rgb(255, 90, 90))
+                } else {
+                    let streak = lab.alerts.streak(i);
+                    (trf("alert.armed", &[("streak", &streak), ("needed", &rule.sustained.max(1))]), egui::Color32::GRAY)
+                };
+                ui.label(egui::RichText::new(status).small().color(color));
+            });
+        }
+        if let Some(i) = remove {
+            params.alert_rules.remove(i);
+        }
+        if ui.button(tr("alert.add")).clicked() {
+            params.alert_rules.push(AlertRule::default());
+        }
+    });
+}
+
+/// Format and size of charts exported with their 💾 button.
+fn render_plot_export_settings(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.horizontal(|ui| {
+        ui.label(tr("plot.export_format")).on_hover_text(tr("plot.export_hint"));
+        for format in PlotFormat::all() {
+            ui.radio_value(&mut lab.plot_export.format, format, format.extension().to_uppercase());
+        }
+        ui.add(egui::DragValue::new(&mut lab.plot_export.width).range(MIN_SIZE..=MAX_SIZE).suffix(" px"));
+        ui.label("×");
+        ui.add(egui::DragValue::new(&mut lab.plot_export.height).range(MIN_SIZE..=MAX_SIZE).suffix(" px"));
+    });
+}
+
+/// Title row under a chart, with its export button.
+fn plot_caption(ui: &mut egui::Ui, caption: &str) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(caption).small().strong());
+        ui.small_button("💾").on_hover_text(tr("plot.export_hint")).clicked()
+    })
+    .inner
+}
+
+fn stat_row(ui: &mut egui::Ui, label: &str, value: &str) {
+    ui.label(egui::RichText::new(label).color(egui::Color32::from_rgb(180, 180, 200)));
+    ui.label(egui::RichText::new(value).monospace().strong().color(egui::Color32::from_rgb(220, 220, 240)));
+    ui.end_row();
+}
+
+/// Value of one metric in a history record (one plot).
+type MetricFn = fn(&crate::lab::MetricsRecord) -> f64;
+
+/// Frame marker color on the analysis plots.
+const MARKER_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 200, 60);
+/// Pointer distance (points) within which an event marker shows its tooltip.
+const EVENT_HOVER_DISTANCE: f64 = 4.0;
+
+/// Vertical markers shared by the analysis plots.
+#[derive(Default)]
+struct PlotMarkers<'a> {
+    frame: Option<u32>,         // jumped to from the event log
+    events: Vec<&'a LabEvent>, // timeline events, hover for details
+    conditions: &'a [Condition], // shaded bands; one in progress runs to `now`
+    now: u32,
+}
+
+/// Fill of a condition band: a hue per name, so a condition that comes
+/// back has the same color.
+fn condition_color(name: &str) -> egui::Color32 {
+    let hash = name.bytes().fold(2166136261u32, |h, b| (h ^ b as u32).wrapping_mul(16777619));
+    egui::ecolor::Hsva::new((hash % 360) as f32 / 360.0, 0.6, 0.9, 0.12).into()
+}
+
+/// Time series of one metric; returns the figure when its export is clicked.
+fn render_plot<F>(
+    ui: &mut egui::Ui,
+    title: &str,
+    history: &[crate::lab::MetricsRecord],
+    markers: &PlotMarkers,
+    value_fn: F,
+) -> Option<PlotFigure>
+where
+    F: Fn(&crate::lab::MetricsRecord) -> f64,
+{
+    let values: Vec<[f64; 2]> = history.iter().map(|m| [m.frame as f64, value_fn(m)]).collect();
+    let (low, high) = values
+        .iter()
+        .map(|p| p[1])
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let points = PlotPoints::from(values);
+
+    let response = Plot::new(format!("plot_{}", title))
+        .height(100.0)
+        .show_axes(true)
+        .show_grid(true)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            if low <= high {
+                for condition in markers.conditions {
+                    let (x0, x1) = (condition.start as f64, condition.end.unwrap_or(markers.now) as f64);
+                    let band = vec![[x0, low], [x1, low], [x1, high], [x0, high]];
+                    plot_ui.polygon(
+                        Polygon::new(PlotPoints::from(band))
+                            .fill_color(condition_color(&condition.name))
+                            .stroke(egui::Stroke::NONE)
+                            .name(&condition.name),
+                    );
+                }
+            }
+            plot_ui.line(Line::new(points).name(title));
+            for event in &markers.events {
+                plot_ui.vline(
+                    VLine::new(event.frame as f64)
+                        .color(event_color(event).gamma_multiply(0.6))
+                        .style(egui_plot::LineStyle::dashed_dense()),
+                );
+            }
+            if let Some(frame) = markers.frame {
+                plot_ui.vline(VLine::new(frame as f64).color(MARKER_COLOR));
+            }
+            match plot_ui.pointer_coordinate() {
+                Some(pointer) if plot_ui.response().hovered() => {
+                    let tolerance = EVENT_HOVER_DISTANCE * plot_ui.transform().dvalue_dpos()[0].abs();
+                    events_near(&markers.events, pointer.x, tolerance)
+                }
+                _ => Vec::new(),
+            }
+        });
+    let hovered = response.inner;
+    if !hovered.is_empty() {
+        response.response.on_hover_ui_at_pointer(|ui| {
+            for event in hovered {
+                ui.label(
+                    egui::RichText::new(format!("{} · {} — {}", event.frame, event.event_type, event.details))
+                        .small()
+                        .color(event_color(event)),
+                );
+            }
+        });
+    }
+    let clicked = plot_caption(ui, title);
+    ui.add_space(4.0);
+    clicked.then(|| PlotFigure {
+        title: title.to_string(),
+        x_label: tr("plot.frame").to_string(),
+        y_label: title.to_string(),
+        series: vec![PlotSeries {
+            name: String::new(), // single series: the axis label names it
+            points: history.iter().map(|m| [m.frame as f64, value_fn(m)]).collect(),
+        }],
+        markers: markers.events.iter().map(|e| e.frame as f64).chain(markers.frame.map(|f| f as f64)).collect(),
+        ..Default::default()
+    })
+}
+
+// ======================== Cross-section Profile ========================
+
+fn render_profile_section(ui: &mut egui::Ui, lab: &mut LabState) -> Option<PlotFigure> {
+    ui.label(egui::RichText::new(tr("profile.title")).strong());
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut lab.profile_tool_active, tr("profile.line_tool"));
+        if ui.button(tr("common.refresh")).clicked() && lab.profile_line.is_some() {
+            lab.profile_refresh_requested = true;
+        }
+        if ui.button(tr("common.clear")).clicked() {
+            lab.profile_line = None;
+            lab.profile_samples.clear();
+        }
+    });
+
+    if lab.profile_samples.is_empty() {
+        ui.label(
+            egui::RichText::new(tr("profile.hint"))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+        return None;
+    }
+
+    let points = |f: fn(&crate::profile::ProfileSample) -> f32| -> Vec<[f64; 2]> {
+        lab.profile_samples
+            .iter()
+            .map(|s| [s.distance as f64, f(s) as f64])
+            .collect()
+    };
+    let series = |f| PlotPoints::new(points(f));
+
+    Plot::new("plot_profile")
+        .height(140.0)
+        .show_axes(true)
+        .show_grid(true)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .legend(egui_plot::Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(series(|s| s.mass)).name(tr("profile.mass")).color(egui::Color32::from_rgb(220, 220, 240)));
+            plot_ui.line(Line::new(series(|s| s.energy)).name(tr("profile.energy")).color(egui::Color32::from_rgb(255, 150, 100)));
+            plot_ui.line(Line::new(series(|s| s.resource)).name(tr("profile.resource")).color(egui::Color32::from_rgb(120, 220, 120)));
+        });
+    let clicked = plot_caption(ui, &trf("profile.samples", &[("count", &lab.profile_samples.len())]));
+    ui.add_space(4.0);
+    clicked.then(|| PlotFigure {
+        title: tr("profile.figure").to_string(),
+        x_label: tr("profile.distance").to_string(),
+        y_label: tr("profile.value").to_string(),
+        series: vec![
+            PlotSeries { name: tr("profile.mass").to_string(), points: points(|s| s.mass) },
+            PlotSeries { name: tr("profile.energy").to_string(), points: points(|s| s.energy) },
+            PlotSeries { name: tr("profile.resource").to_string(), points: points(|s| s.resource) },
+        ],
+        ..Default::default()
+    })
+}
+
+// ======================== Interaction Matrix ========================
+
+fn render_interaction_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("interact.title")).strong());
+    if ui
+        .checkbox(&mut params.interaction_matrix, tr("interact.track"))
+        .on_hover_text(tr("interact.track_hint"))
+        .changed()
+    {
+        lab.log_param_change(lab.current_frame, "interaction_matrix", &params.interaction_matrix.to_string());
+        if !params.interaction_matrix {
+            lab.interactions = None;
+        }
+    }
+
+    let Some(matrix) = &lab.interactions else {
+        let hint = if params.interaction_matrix { "interact.waiting" } else { "interact.hint" };
+        ui.label(egui::RichText::new(tr(hint)).small().color(egui::Color32::GRAY));
+        return;
+    };
+    render_interaction_heatmap(ui, matrix);
+    let upward = matrix.upward_fraction().map_or(String::from("–"), |f| format!("{:.0}%", f * 100.0));
+    ui.label(
+        egui::RichText::new(trf("interact.summary", &[("total", &matrix.total()), ("upward", &upward)]))
+            .small()
+            .strong(),
+    );
+    ui.add_space(4.0);
+}
+
+/// Attacker rows × defender columns, cell brightness by count (square root
+/// scale so rare interactions stay visible).
+fn render_interaction_heatmap(ui: &mut egui::Ui, matrix: &InteractionMatrix) {
+    const CELL: f32 = 34.0;
+    const LABEL: f32 = 56.0;
+    let n = AGG_CLASSES as f32;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(LABEL + CELL * n, LABEL * 0.5 + CELL * n), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let font = egui::FontId::proportional(10.0);
+    let text_color = egui::Color32::from_rgb(180, 180, 200);
+    let origin = rect.min + egui::vec2(LABEL, LABEL * 0.5);
+    let max = matrix.max().max(1) as f32;
+
+    for class in 0..AGG_CLASSES {
+        let label = InteractionMatrix::class_label(class);
+        let offset = (class as f32 + 0.5) * CELL;
+        painter.text(origin + egui::vec2(offset, -4.0), egui::Align2::CENTER_BOTTOM, &label, font.clone(), text_color);
+        painter.text(origin + egui::vec2(-4.0, offset), egui::Align2::RIGHT_CENTER, &label, font.clone(), text_color);
+    }
+
+    let mut hovered = None;
+    for (a, row) in matrix.counts.iter().enumerate() {
+        for (d, &count) in row.iter().enumerate() {
+            let min = origin + egui::vec2(d as f32 * CELL, a as f32 * CELL);
+            let cell = egui::Rect::from_min_size(min, egui::vec2(CELL, CELL)).shrink(1.0);
+            let t = (count as f32 / max).sqrt();
+            let color = egui::Color32::from_rgb((30.0 + 225.0 * t) as u8, (30.0 + 110.0 * t) as u8, (45.0 - 15.0 * t) as u8);
+            painter.rect_filled(cell, 2.0, color);
+            if response.hover_pos().is_some_and(|p| cell.contains(p)) {
+                hovered = Some((a, d, count));
+            }
+        }
+    }
+
+    if let Some((a, d, count)) = hovered {
+        let share = count as f64 / matrix.total().max(1) as f64 * 100.0;
+        response.on_hover_text(trf(
+            "interact.cell",
+            &[
+                ("attacker", &InteractionMatrix::class_label(a)),
+                ("defender", &InteractionMatrix::class_label(d)),
+                ("count", &count),
+                ("share", &format!("{:.1}", share)),
+            ],
+        ));
+    }
+    ui.label(egui::RichText::new(tr("interact.axes")).small().color(egui::Color32::GRAY));
+}
+
+// ======================== Speciation ========================
+
+fn render_speciation_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("speciation.title")).strong());
+    let settings = &mut params.speciation;
+    if ui.checkbox(&mut settings.enabled, tr("speciation.track"))
+        .on_hover_text(tr("speciation.track_hint"))
+        .changed()
+    {
+        lab.log_param_change(lab.current_frame, "speciation_enabled", &settings.enabled.to_string());
+        if !settings.enabled {
+            lab.speciation.reset();
+        }
+    }
+    if !settings.enabled {
+        ui.label(egui::RichText::new(tr("speciation.hint")).small().color(egui::Color32::GRAY));
+        return;
+    }
+    ui.add(egui::Slider::new(&mut settings.persistence, 1..=30).text(tr("speciation.persistence")))
+        .on_hover_text(tr("speciation.persistence_hint"));
+    ui.add(egui::Slider::new(&mut settings.min_share, 0.01..=0.3).text(tr("speciation.min_share")))
+        .on_hover_text(tr("speciation.min_share_hint"));
+    ui.checkbox(&mut settings.capture, tr("speciation.capture"))
+        .on_hover_text(tr("speciation.capture_hint"));
+
+    let tracker = &lab.speciation;
+    ui.label(
+        egui::RichText::new(trf("speciation.summary", &[("lineages", &tracker.lineages.len()), ("events", &tracker.detected)]))
+            .small()
+            .strong(),
+    );
+    egui::Grid::new("speciation_lineages").num_columns(3).striped(true).show(ui, |ui| {
+        for lineage in &tracker.lineages {
+            let text = egui::RichText::new(lineage.label()).monospace();
+            ui.label(if lineage.confirmed { text } else { text.italics().color(egui::Color32::GRAY) })
+                .on_hover_text(trf("speciation.born", &[("frame", &lineage.born), ("age", &lineage.age)]));
+            ui.label(format!("{:.1}%", lineage.share * 100.0));
+            let [r, mu, sigma, agg] = lineage.centroid;
+            ui.label(egui::RichText::new(format!("r={r:.1} μ={mu:.3} σ={sigma:.3} a={agg:.2}")).small());
+            ui.end_row();
+        }
+    });
+    ui.add_space(4.0);
+}
+
+// ======================== Extinction Forensics ========================
+
+fn render_forensics_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("forensics.title")).strong());
+    let settings = &mut params.forensics;
+    if ui.checkbox(&mut settings.enabled, tr("forensics.enabled"))
+        .on_hover_text(tr("forensics.enabled_hint"))
+        .changed()
+    {
+        lab.log_param_change(lab.current_frame, "forensics_enabled", &settings.enabled.to_string());
+        if !settings.enabled {
+            lab.rewind.clear();
+        }
+    }
+    ui.add_enabled_ui(settings.enabled, |ui| {
+        ui.add(egui::Slider::new(&mut settings.history, 5..=500).logarithmic(true).text(tr("forensics.history")))
+            .on_hover_text(tr("forensics.history_hint"));
+        ui.add(egui::Slider::new(&mut settings.rewind_depth, 1..=8).text(tr("forensics.rewind")))
+            .on_hover_text(tr("forensics.rewind_hint"));
+        ui.add_enabled(params.speciation.enabled, egui::Checkbox::new(&mut settings.lineages, tr("forensics.lineages")))
+            .on_hover_text(tr("forensics.lineages_hint"))
+            .on_disabled_hover_text(tr("forensics.lineages_disabled"));
+        let buffer = if lab.rewind.is_empty() {
+            tr("forensics.buffer_empty").to_string()
+        } else {
+            trf("forensics.buffer", &[("count", &lab.rewind.len()), ("size", &format_bytes(lab.rewind.bytes()))])
+        };
+        ui.label(egui::RichText::new(buffer).small().color(egui::Color32::GRAY));
+    });
+    if let Some(dir) = &lab.last_extinction_report {
+        ui.label(egui::RichText::new(trf("forensics.last", &[("path", &dir.display())])).small());
+    }
+    ui.add_space(4.0);
+}
+
+// ======================== Time-Averaged Fields ========================
+
+fn render_averages_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("averages.title")).strong());
+    let settings = &mut params.averages;
+    if ui.checkbox(&mut settings.enabled, tr("averages.enabled"))
+        .on_hover_text(tr("averages.enabled_hint"))
+        .changed()
+    {
+        lab.log_param_change(lab.current_frame, "averages_enabled", &settings.enabled.to_string());
+    }
+    ui.add_enabled_ui(settings.enabled, |ui| {
+        if ui.add(egui::Slider::new(&mut settings.window, 10..=100_000).logarithmic(true).text(tr("averages.window")))
+            .on_hover_text(tr("averages.window_hint"))
+            .changed()
+        {
+            lab.log_param_change(lab.current_frame, "averages_window", &settings.window.to_string());
+        }
+        let samples = lab.average_samples.min(settings.window);
+        ui.label(egui::RichText::new(trf("averages.samples", &[("count", &samples), ("window", &settings.window)])).small().color(egui::Color32::GRAY));
+    });
+    ui.horizontal(|ui| {
+        if ui.add_enabled(lab.average_samples > 0, egui::Button::new(tr("averages.export"))).on_hover_text(tr("averages.export_hint")).clicked() {
+            lab.average_export_requested = true;
+        }
+        if ui.add_enabled(lab.average_samples > 0, egui::Button::new(tr("averages.reset"))).clicked() {
+            lab.average_reset_requested = true;
+        }
+    });
+    if let Some(dir) = &lab.last_average_export {
+        ui.label(egui::RichText::new(trf("averages.last", &[("path", &dir.display())])).small());
+    }
+    ui.add_space(4.0);
+}
+
+// ======================== Velocity Field Export ========================
+
+fn render_velocity_export_section(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("velocity.title")).strong());
+    let settings = &mut params.velocity_export;
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut settings.npy, tr("velocity.npy")).on_hover_text(tr("velocity.npy_hint"));
+        ui.checkbox(&mut settings.quiver, tr("velocity.quiver")).on_hover_text(tr("velocity.quiver_hint"));
+    });
+    ui.add_enabled(settings.quiver, egui::Slider::new(&mut settings.stride, 4..=64).text(tr("velocity.stride")))
+        .on_hover_text(tr("velocity.stride_hint"));
+    ui.horizontal(|ui| {
+        if ui.checkbox(&mut settings.enabled, tr("velocity.periodic")).on_hover_text(tr("velocity.periodic_hint")).changed() {
+            lab.velocity_samples = 0;
+            lab.log_param_change(lab.current_frame, "velocity_export", &settings.enabled.to_string());
+        }
+        ui.add_enabled(settings.enabled, egui::DragValue::new(&mut settings.every).range(1..=1000).prefix("1/"));
+    });
+    if ui.button(tr("velocity.export")).clicked() {
+        lab.velocity_export_requested = true;
+    }
+    if let Some(stem) = &lab.last_velocity_export {
+        ui.label(egui::RichText::new(trf("velocity.last", &[("path", &stem.display())])).small());
+    }
+    ui.add_space(4.0);
+}
+
+// ======================== Replay Check ========================
+
+fn render_replay_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("replay.title")).strong());
+    ui.label(egui::RichText::new(tr("replay.subtitle")).small().color(egui::Color32::GRAY));
+    let check = &mut lab.replay;
+    ui.horizontal(|ui| {
+        if ui.button(tr("replay.pick_tape")).clicked() {
+            if let Some(path) = file_dialog::pick_tape(&lab.run_dir) {
+                check.source = Some(ReplaySource::Tape(path));
+            }
+        }
+        if ui.button(tr("replay.pick_snapshots")).on_hover_text(tr("replay.pick_snapshots_hint")).clicked() {
+            if let Some(dir) = file_dialog::pick_directory(&lab.run_dir) {
+                check.source = Some(ReplaySource::Snapshots(dir));
+            }
+        }
+    });
+    if let Some(source) = &check.source {
+        ui.label(egui::RichText::new(source.path().display().to_string()).small());
+    }
+    ui.horizontal(|ui| {
+        ui.label(tr("replay.tolerance"));
+        ui.add(egui::DragValue::new(&mut check.settings.tolerance).range(0.0..=1.0).speed(1e-6).max_decimals(7))
+            .on_hover_text(tr("replay.tolerance_hint"));
+    });
+    ui.checkbox(&mut check.settings.stop_at_divergence, tr("replay.stop_at_divergence"));
+    ui.horizontal(|ui| {
+        if check.is_running() {
+            if ui.button(tr("replay.cancel")).clicked() {
+                check.cancel();
+            }
+            ui.add(egui::ProgressBar::new(check.progress.unwrap_or(0.0)).show_percentage());
+        } else if ui.add_enabled(check.source.is_some(), egui::Button::new(tr("replay.run"))).clicked() {
+            check.requested = true;
+        }
+    });
+
+    if let Some(result) = &check.result {
+        match result.first_divergence() {
+            Some(checkpoint) => {
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 120, 80),
+                    trf("replay.diverged", &[("frame", &checkpoint.frame), ("start", &result.start_frame)]),
+                );
+                egui::Grid::new("replay_divergence").striped(true).show(ui, |ui| {
+                    for diff in checkpoint.channels.iter().filter(|d| d.differing > 0) {
+                        ui.label(diff.name);
+                        ui.label(format!("{:.3e}", diff.max_diff));
+                        ui.label(trf("replay.differing", &[("count", &diff.differing)]));
+                        if let Some((x, y)) = diff.first_cell.map(|cell| result.cell_xy(cell)) {
+                            ui.label(format!("({x}, {y})"));
+                        }
+                        ui.end_row();
+                    }
+                });
+            }
+            None => {
+                let key = if result.cancelled { "replay.matched_partial" } else { "replay.matched" };
+                ui.colored_label(egui::Color32::from_rgb(120, 200, 120), trf(key, &[("count", &result.checkpoints.len())]));
+            }
+        }
+        if let Some(path) = &check.csv_path {
+            ui.label(egui::RichText::new(path.display().to_string()).small().color(egui::Color32::GRAY));
+        }
+    }
+    ui.add_space(4.0);
+}
+
+// ======================== Fitness Landscape ========================
+
+fn render_landscape_section(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("landscape.title")).strong());
+    ui.label(egui::RichText::new(tr("landscape.subtitle")).small().color(egui::Color32::GRAY));
+    let probe = &mut lab.landscape;
+    let settings = &mut probe.settings;
+    ui.horizontal(|ui| {
+        ui.label(tr("landscape.center"));
+        ui.add(egui::DragValue::new(&mut settings.center[0]).range(0.0..=1.0).speed(0.01).prefix("x="));
+        ui.add(egui::DragValue::new(&mut settings.center[1]).range(0.0..=1.0).speed(0.01).prefix("y="));
+        ui.toggle_value(&mut probe.pick, tr("transplant.pick")).on_hover_text(tr("landscape.pick_hint"));
+    });
+    sweep_axis_row(ui, "landscape_x", tr("landscape.x_gene"), &mut settings.x);
+    let mut two_genes = settings.y.is_some();
+    if ui.checkbox(&mut two_genes, tr("landscape.two_genes")).changed() {
+        let other = (0..GENE_KEYS.len()).find(|&g| g != settings.x.gene).unwrap_or(0);
+        settings.y = two_genes.then(|| SweepAxis::full(other));
+    }
+    if let Some(y) = &mut settings.y {
+        sweep_axis_row(ui, "landscape_y", tr("landscape.y_gene"), y);
+    }
+    ui.add(egui::Slider::new(&mut settings.resolution, 2..=LANDSCAPE_MAX_RESOLUTION).text(tr("landscape.resolution")));
+    ui.add(egui::Slider::new(&mut settings.steps, 20..=1000).logarithmic(true).text(tr("landscape.steps")))
+        .on_hover_text(tr("landscape.steps_hint"));
+
+    ui.horizontal(|ui| {
+        if probe.is_running() {
+            if ui.button(tr("landscape.cancel")).clicked() {
+                probe.cancel();
+            }
+            ui.add(egui::ProgressBar::new(probe.progress.unwrap_or(0.0)).show_percentage());
+        } else if ui.button(tr("landscape.run")).on_hover_text(tr("landscape.run_hint")).clicked() {
+            probe.requested = true;
+        }
+    });
+
+    let Some(landscape) = &probe.result else {
+        return;
+    };
+    if landscape.settings.y.is_some() {
+        render_landscape_heatmap(ui, landscape);
+    } else {
+        let x = landscape.settings.x;
+        let n = landscape.settings.resolution;
+        let points: Vec<[f64; 2]> = (0..n).map(|k| [x.value(k, n) as f64, landscape.at(k, 0) as f64]).collect();
+        Plot::new("plot_landscape")
+            .height(120.0)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .x_axis_label(tr(x.label_key()))
+            .y_axis_label(tr("landscape.growth"))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::new(points)).color(egui::Color32::from_rgb(255, 170, 80)));
+            });
+    }
+    let base = GENE_KEYS
+        .iter()
+        .zip(landscape.base_genome)
+        .map(|(key, g)| format!("{}={:.3}", tr(key), g))
+        .collect::<Vec<_>>()
+        .join(" ");
+    ui.label(egui::RichText::new(trf("landscape.base", &[("genes", &base)])).small());
+    if let Some((point, growth)) = landscape.best() {
+        let best = landscape.describe(point);
+        ui.label(
+            egui::RichText::new(trf("landscape.best", &[("genes", &best), ("growth", &format!("{growth:.2}"))]))
+                .small()
+                .strong(),
+        );
+    }
+    ui.add_space(4.0);
+}
+
+/// Gene and value range of one sweep axis.
+fn sweep_axis_row(ui: &mut egui::Ui, id: &str, label: &str, axis: &mut SweepAxis) {
+    ui.horizontal(|ui| {
+        let before = axis.gene;
+        egui::ComboBox::new(id, label)
+            .selected_text(tr(axis.label_key()))
+            .show_ui(ui, |ui| {
+                for (g, key) in GENE_KEYS.iter().enumerate() {
+                    ui.selectable_value(&mut axis.gene, g, tr(key));
+                }
+            });
+        if axis.gene != before {
+            *axis = SweepAxis::full(axis.gene);
+        }
+        let (lo, hi) = GENE_LIMITS[axis.gene];
+        let speed = (hi - lo) / 200.0;
+        let max = axis.range.1;
+        ui.add(egui::DragValue::new(&mut axis.range.0).range(lo..=max).speed(speed));
+        let min = axis.range.0;
+        ui.add(egui::DragValue::new(&mut axis.range.1).range(min..=hi).speed(speed));
+    });
+}
+
+/// Growth per grid point, x gene left to right and y gene bottom to top:
+/// blue where the clone shrinks, orange where it grows.
+fn render_landscape_heatmap(ui: &mut egui::Ui, landscape: &FitnessLandscape) {
+    const SIZE: f32 = 200.0;
+    let n = landscape.settings.resolution;
+    let cell = SIZE / n as f32;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(SIZE, SIZE), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let max = landscape.max_growth().max(1.0 + 1e-3);
+
+    let mut hovered = None;
+    for ky in 0..n {
+        for kx in 0..n {
+            let min = rect.min + egui::vec2(kx as f32 * cell, (n - 1 - ky) as f32 * cell);
+            let square = egui::Rect::from_min_size(min, egui::vec2(cell, cell));
+            let g = landscape.at(kx, ky);
+            let color = if g < 1.0 {
+                let t = g.clamp(0.0, 1.0);
+                egui::Color32::from_rgb((20.0 + 60.0 * t) as u8, (30.0 + 60.0 * t) as u8, (70.0 + 40.0 * t) as u8)
+            } else {
+                let t = ((g - 1.0) / (max - 1.0)).clamp(0.0, 1.0);
+                egui::Color32::from_rgb((80.0 + 175.0 * t) as u8, (90.0 + 80.0 * t) as u8, (110.0 - 80.0 * t) as u8)
+            };
+            painter.rect_filled(square, 0.0, color);
+            if response.hover_pos().is_some_and(|p| square.contains(p)) {
+                hovered = Some((kx, ky, g));
+            }
+        }
+    }
+
+    if let Some((kx, ky, g)) = hovered {
+        response.on_hover_text(trf(
+            "landscape.cell",
+            &[("genes", &landscape.describe((kx, ky))), ("growth", &format!("{g:.3}"))],
+        ));
+    }
+    let y = landscape.settings.y.map_or("", |y| tr(y.label_key()));
+    ui.label(
+        egui::RichText::new(trf("landscape.axes", &[("x", &tr(landscape.settings.x.label_key())), ("y", &y)]))
+            .small()
+            .color(egui::Color32::GRAY),
+    );
+}
+
+/// Draw the profile line over the simulation, beneath the UI panels.
+fn render_profile_overlay(ctx: &egui::Context, lab: &LabState, camera: &CameraState) {
+    let Some(line) = lab.profile_line.filter(|_| !camera.surface_view) else {
+        return;
+    };
+    let rect = ctx.screen_rect();
+    let physical = rect.size() * ctx.pixels_per_point();
+    let to_screen = |uv: [f32; 2]| {
+        let s = camera.world_to_screen(uv, physical.x.round().max(1.0) as u32, physical.y.round().max(1.0) as u32, lab.world_size);
+        rect.min + egui::vec2(s[0] * rect.width(), s[1] * rect.height())
+    };
+    let a = to_screen(line.start);
+    let b = to_screen(line.end);
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("profile_overlay"),
+    ));
+    let color = egui::Color32::from_rgb(255, 220, 80);
+    painter.line_segment([a, b], egui::Stroke::new(2.0, color));
+    painter.circle_filled(a, 4.0, color);
+    painter.circle_stroke(b, 4.0, egui::Stroke::new(2.0, color));
+}
+
+/// Legend of the active visualization mode, in the bottom-left corner.
+fn render_legend_overlay(ctx: &egui::Context, params: &SimulationParams, lab: &LabState) {
+    if !params.legend.enabled {
+        return;
+    }
+    let palette = lab.palette.uniforms();
+    let Some(legend) = legend_for(params, &palette, &lab.territory_areas, lab.territory.species_count()) else {
+        return;
+    };
+    let rgb = |c: [u8; 3]| egui::Color32::from_rgb(c[0], c[1], c[2]);
+    egui::Area::new(egui::Id::new("legend_overlay"))
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(12.0, -12.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::new()
+                .fill(egui::Color32::from_black_alpha(160))
+                .corner_radius(6)
+                .inner_margin(egui::Margin::same(8))
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new(legend.title()).small().color(egui::Color32::WHITE));
+                    match &legend {
+                        ModeLegend::Scale { stops, ticks, .. } => {
+                            let (rect, _) = ui.allocate_exact_size(egui::vec2(220.0, 12.0), egui::Sense::hover());
+                            let painter = ui.painter();
+                            let steps = 64;
+                            for i in 0..steps {
+                                let t0 = i as f32 / steps as f32;
+                                let x0 = rect.left() + t0 * rect.width();
+                                let x1 = rect.left() + (i + 1) as f32 / steps as f32 * rect.width();
+                                let color = rgb(scale_color(stops, t0 + 0.5 / steps as f32));
+                                painter.rect_filled(
+                                    egui::Rect::from_x_y_ranges(x0..=x1, rect.y_range()),
+                                    0.0,
+                                    color,
+                                );
+                            }
+                            if !ticks.is_empty() {
+                                let (row, _) = ui.allocate_exact_size(egui::vec2(220.0, 14.0), egui::Sense::hover());
+                                for (p, label) in ticks {
+                                    let align = match *p {
+                                        p if p <= 0.0 => egui::Align2::LEFT_TOP,
+                                        p if p >= 1.0 => egui::Align2::RIGHT_TOP,
+                                        _ => egui::Align2::CENTER_TOP,
+                                    };
+                                    ui.painter().text(
+                                        egui::pos2(row.left() + p * row.width(), row.top()),
+                                        align,
+                                        label,
+                                        egui::FontId::proportional(11.0),
+                                        egui::Color32::WHITE,
+                                    );
+                                }
+                            }
+                        }
+                        ModeLegend::Swatches { entries, .. } => {
+                            for (color, label) in entries {
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("■").color(rgb(*color)));
+                                    ui.label(egui::RichText::new(label).small().color(egui::Color32::WHITE));
+                                });
+                            }
+                        }
+                    }
+                });
+        });
+}
+
+/// Live histogram of the visualized field, in the bottom-right corner. For
+/// mass the display window of the active mode is marked, with the share of
+/// cells above it; otherwise the share in the last bin.
+fn render_histogram_overlay(ctx: &egui::Context, params: &SimulationParams, lab: &LabState) {
+    if !params.histogram.enabled {
+        return;
+    }
+    let Some(histogram) = &lab.histogram else {
+        return;
+    };
+    let adjust = params.current_display_adjust();
+    let window = (histogram.field == HistogramField::Mass).then(|| {
+        let exposure = adjust.exposure.max(1e-6);
+        (adjust.range_min / exposure, adjust.range_max / exposure)
+    });
+    egui::Area::new(egui::Id::new("histogram_overlay"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::new()
+                .fill(egui::Color32::from_black_alpha(160))
+                .corner_radius(6)
+                .inner_margin(egui::Margin::same(8))
+                .show(ui, |ui| {
+                    let title = trf(
+                        "histogram.summary",
+                        &[("field", &tr(histogram.field.label_key())), ("cells", &histogram.total())],
+                    );
+                    ui.label(egui::RichText::new(title).small().color(egui::Color32::WHITE));
+
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(192.0, 64.0), egui::Sense::hover());
+                    let painter = ui.painter();
+                    let heights = histogram.heights(params.histogram.log_scale);
+                    let bar = rect.width() / heights.len().max(1) as f32;
+                    let last = heights.len().saturating_sub(1);
+                    for (i, h) in heights.iter().enumerate() {
+                        let x = rect.left() + i as f32 * bar;
+                        let color = if i == last {
+                            egui::Color32::from_rgb(255, 120, 80) // overflow bin
+                        } else {
+                            egui::Color32::from_gray(200)
+                        };
+                        painter.rect_filled(
+                            egui::Rect::from_x_y_ranges(x..=x + bar.max(1.0), rect.bottom() - h * rect.height()..=rect.bottom()),
+                            0.0,
+                            color,
+                        );
+                    }
+                    let to_x = |v: f32| rect.left() + (v / histogram.max_value).clamp(0.0, 1.0) * rect.width();
+                    if let Some((lo, hi)) = window {
+                        let stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 220, 80));
+                        for v in [lo, hi] {
+                            painter.vline(to_x(v), rect.y_range(), stroke);
+                        }
+                    }
+                    let (row, _) = ui.allocate_exact_size(egui::vec2(rect.width(), 14.0), egui::Sense::hover());
+                    for (v, align) in [(0.0, egui::Align2::LEFT_TOP), (histogram.max_value, egui::Align2::RIGHT_TOP)] {
+                        ui.painter().text(
+                            egui::pos2(to_x(v), row.top()),
+                            align,
+                            format!("{:.2}", v),
+                            egui::FontId::proportional(11.0),
+                            egui::Color32::GRAY,
+                        );
+                    }
 
-                // Comparison section
-                if !lab.completed_runs.is_empty() {
-                    ui.separator();
-                    ui.heading("🔀 Run Comparison");
-                    render_comparison_ui(ui, lab);
-                }
-            });
+                    let note = match window {
+                        Some((_, hi)) => trf(
+                            "histogram.above_window",
+                            &[("share", &format!("{:.1}", histogram.share_above(hi) * 100.0))],
+                        ),
+                        None => trf(
+                            "histogram.saturated",
+                            &[("share", &format!("{:.1}", histogram.saturated_share() * 100.0))],
+                        ),
+                    };
+                    ui.label(egui::RichText::new(note).small().color(egui::Color32::WHITE));
+                });
         });
 }
 
-fn stat_row(ui: &mut egui::Ui, label: &str, value: &str) {
-    ui.label(egui::RichText::new(label).color(egui::Color32::from_rgb(180, 180, 200)));
-    ui.label(egui::RichText::new(value).monospace().strong().color(egui::Color32::from_rgb(220, 220, 240)));
-    ui.end_row();
-}
+// ======================== Run Gallery ========================
 
-fn render_plot<F>(
-    ui: &mut egui::Ui,
-    title: &str,
-    history: &[crate::lab::MetricsRecord],
-    value_fn: F,
-) where
-    F: Fn(&crate::lab::MetricsRecord) -> f64,
-{
-    let points: PlotPoints = history
-        .iter()
-        .map(|m| [m.frame as f64, value_fn(m)])
-        .collect();
+/// Texture of an image file, loaded once per window and cached in `cache`
+/// (textures belong to the egui context of the window that loaded them).
+fn cached_texture(
+    ctx: &egui::Context,
+    cache: &mut HashMap<(egui::ViewportId, PathBuf), Option<egui::TextureHandle>>,
+    path: &Path,
+) -> Option<egui::TextureHandle> {
+    cache
+        .entry((ctx.viewport_id(), path.to_path_buf()))
+        .or_insert_with(|| match crate::thumbnail::load_color_image(path) {
+            Ok(image) => Some(ctx.load_texture(path.display().to_string(), image, egui::TextureOptions::NEAREST)),
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
+        })
+        .clone()
+}
 
-    Plot::new(format!("plot_{}", title))
-        .height(100.0)
-        .show_axes(true)
-        .show_grid(true)
-        .allow_drag(false)
-        .allow_scroll(false)
-        .show(ui, |plot_ui| {
-            plot_ui.line(Line::new(points).name(title));
-        });
-    ui.label(egui::RichText::new(title).small().strong());
-    ui.add_space(4.0);
+/// Completed runs as thumbnails with their final metrics; each card sets the
+/// comparison runs or reloads the run's parameters.
+fn render_run_gallery(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    let size = egui::vec2(THUMBNAIL_WIDTH as f32, THUMBNAIL_WIDTH as f32 * lab.world_size[1] as f32 / lab.world_size[0] as f32);
+    let mut reload = None;
+    ui.horizontal_wrapped(|ui| {
+        for i in 0..lab.completed_runs.len() {
+            let run = lab.completed_runs[i].clone();
+            ui.group(|ui| {
+                ui.set_width(size.x);
+                ui.vertical(|ui| {
+                    match run.thumbnail.as_deref().and_then(|p| cached_texture(ui.ctx(), &mut lab.thumbnails, p)) {
+                        Some(texture) => {
+                            ui.image((texture.id(), size));
+                        }
+                        None => {
+                            ui.add_sized(size, egui::Label::new(egui::RichText::new(tr("gallery.no_thumbnail")).weak()));
+                        }
+                    }
+                    ui.label(egui::RichText::new(&run.run_id).small().strong()).on_hover_text(run.run_dir.display().to_string());
+                    let frames = trf("gallery.frames", &[("frames", &run.total_frames)]);
+                    ui.label(egui::RichText::new(frames).small());
+                    if let Some(m) = &run.final_metrics {
+                        let metrics = format!(
+                            "{} {:.0} · {} {:.2} · {} {}",
+                            tr("profile.mass"),
+                            m.total_mass,
+                            tr("stat.entropy"),
+                            m.entropy,
+                            tr("stat.species"),
+                            m.species
+                        );
+                        ui.label(egui::RichText::new(metrics).small());
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(lab.comparison_a == Some(i), "A").on_hover_text(tr("compare.run_a")).clicked() {
+                            lab.comparison_a = Some(i);
+                        }
+                        if ui.selectable_label(lab.comparison_b == Some(i), "B").on_hover_text(tr("compare.run_b")).clicked() {
+                            lab.comparison_b = Some(i);
+                        }
+                        if ui.button(tr("gallery.reload")).on_hover_text(tr("gallery.reload_hint")).clicked() {
+                            reload = Some(i);
+                        }
+                    });
+                });
+            });
+        }
+    });
+    if let Some(i) = reload {
+        lab.reload_run(i, params);
+    }
 }
 
 // ======================== Comparison UI ========================
 
-fn render_comparison_ui(ui: &mut egui::Ui, lab: &mut LabState) {
+fn render_comparison_ui(ui: &mut egui::Ui, lab: &mut LabState) -> Option<PlotFigure> {
     ui.horizontal(|ui| {
-        ui.label("Run A:");
+        ui.label(tr("compare.run_a"));
         egui::ComboBox::from_id_salt("comp_a")
             .selected_text(
                 lab.comparison_a
                     .and_then(|i| lab.completed_runs.get(i))
-                    .map_or("Select…".to_string(), |r| r.run_id.clone()),
+                    .map_or(tr("common.select").to_string(), |r| r.run_id.clone()),
             )
             .show_ui(ui, |ui| {
                 for (i, run) in lab.completed_runs.iter().enumerate() {
@@ -712,12 +4123,12 @@ fn render_comparison_ui(ui: &mut egui::Ui, lab: &mut LabState) {
     });
 
     ui.horizontal(|ui| {
-        ui.label("Run B:");
+        ui.label(tr("compare.run_b"));
         egui::ComboBox::from_id_salt("comp_b")
             .selected_text(
                 lab.comparison_b
                     .and_then(|i| lab.completed_runs.get(i))
-                    .map_or("Select…".to_string(), |r| r.run_id.clone()),
+                    .map_or(tr("common.select").to_string(), |r| r.run_id.clone()),
             )
             .show_ui(ui, |ui| {
                 for (i, run) in lab.completed_runs.iter().enumerate() {
@@ -740,30 +4151,43 @@ fn render_comparison_ui(ui: &mut egui::Ui, lab: &mut LabState) {
                     LabState::load_comparison_metrics(&csv_b),
                 ) {
                     (Ok(metrics_a), Ok(metrics_b)) => {
-                        render_comparison_plot(ui, "Mass", &metrics_a, &metrics_b, |m| m.total_mass as f64);
-                        render_comparison_plot(ui, "Entropy", &metrics_a, &metrics_b, |m| m.entropy as f64);
-                        render_comparison_plot(ui, "Species", &metrics_a, &metrics_b, |m| m.species as f64);
+                        let runs = [(run_a.run_id.as_str(), &metrics_a[..]), (run_b.run_id.as_str(), &metrics_b[..])];
+                        let series: [(&str, MetricFn); 3] = [
+                            (tr("profile.mass"), |m| m.total_mass as f64),
+                            (tr("stat.entropy"), |m| m.entropy as f64),
+                            (tr("stat.species"), |m| m.species as f64),
+                        ];
+                        let mut export = None;
+                        for (title, value_fn) in series {
+                            export = render_comparison_plot(ui, title, runs, value_fn).or(export.take());
+                        }
+                        return export;
                     }
                     _ => {
-                        ui.label("Could not load comparison data.");
+                        ui.label(tr("compare.load_failed"));
                     }
                 }
             }
         }
     }
+    None
 }
 
+/// One metric of two runs (id, history); returns the figure when its export is clicked.
 fn render_comparison_plot<F>(
     ui: &mut egui::Ui,
     title: &str,
-    a: &[crate::lab::MetricsRecord],
-    b: &[crate::lab::MetricsRecord],
+    runs: [(&str, &[crate::lab::MetricsRecord]); 2],
     value_fn: F,
-) where
+) -> Option<PlotFigure>
+where
     F: Fn(&crate::lab::MetricsRecord) -> f64,
 {
-    let points_a: PlotPoints = a.iter().map(|m| [m.frame as f64, value_fn(m)]).collect();
-    let points_b: PlotPoints = b.iter().map(|m| [m.frame as f64, value_fn(m)]).collect();
+    let points = |history: &[crate::lab::MetricsRecord]| -> Vec<[f64; 2]> {
+        history.iter().map(|m| [m.frame as f64, value_fn(m)]).collect()
+    };
+    let points_a = PlotPoints::new(points(runs[0].1));
+    let points_b = PlotPoints::new(points(runs[1].1));
 
     Plot::new(format!("comp_{}", title))
         .height(100.0)
@@ -771,76 +4195,779 @@ fn render_comparison_plot<F>(
         .allow_drag(false)
         .allow_scroll(false)
         .show(ui, |plot_ui| {
-            plot_ui.line(Line::new(points_a).name("Run A").color(egui::Color32::from_rgb(100, 200, 255)));
-            plot_ui.line(Line::new(points_b).name("Run B").color(egui::Color32::from_rgb(255, 150, 100)));
+            plot_ui.line(Line::new(points_a).name(tr("compare.series_a")).color(egui::Color32::from_rgb(100, 200, 255)));
+            plot_ui.line(Line::new(points_b).name(tr("compare.series_b")).color(egui::Color32::from_rgb(255, 150, 100)));
         });
-    ui.label(egui::RichText::new(format!("{} (A vs B)", title)).small().strong());
+    let clicked = plot_caption(ui, &trf("compare.plot_title", &[("title", &title)]));
     ui.add_space(4.0);
+    clicked.then(|| PlotFigure {
+        title: trf("compare.plot_title", &[("title", &title)]),
+        x_label: tr("plot.frame").to_string(),
+        y_label: title.to_string(),
+        series: runs.iter().map(|&(id, history)| PlotSeries { name: id.to_string(), points: points(history) }).collect(),
+        ..Default::default()
+    })
 }
 
-// ======================== Bottom Logs Panel ========================
+// ======================== Performance Tab ========================
 
-fn render_bottom_logs_panel(ctx: &egui::Context, lab: &mut LabState) {
-    egui::TopBottomPanel::bottom("logs_panel")
-        .default_height(120.0)
-        .min_height(60.0)
-        .max_height(300.0)
-        .show(ctx, |ui| {
+/// Frame time percentiles and a per-frame cost breakdown, to tell simulation
+/// cost from UI cost when the frame rate drops.
+fn render_performance_tab(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(tr("perf.title")).strong());
+        ui.label(trf("perf.window", &[("count", &lab.frame_stats.samples().len())]));
+        if ui.small_button(tr("common.clear")).clicked() {
+            lab.frame_stats.clear();
+        }
+    });
+    ui.horizontal(|ui| {
+        match &lab.startup_choice {
+            Some(choice) => ui.label(trf(
+                "perf.startup_bench",
+                &[("gpu", &choice.bench.gpu), ("ms", &format!("{:.3}", choice.bench.ms_per_step))],
+            )),
+            None => ui.label(tr("perf.startup_bench_none")),
+        };
+        let idle = lab.startup_pending.is_none() && !lab.startup_bench_requested;
+        if ui.add_enabled(idle, egui::Button::new(tr("perf.startup_bench_rerun")).small()).clicked() {
+            lab.startup_bench_requested = true;
+        }
+    });
+    let stats = &lab.frame_stats;
+    let Some(pct) = stats.frame_percentiles() else {
+        ui.label(tr("perf.no_data"));
+        return;
+    };
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        let ms = |v: f32| format!("{:.2} ms", v);
+        egui::Grid::new("perf_percentiles").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+            stat_row(ui, "p50", &format!("{}  ({:.0} FPS)", ms(pct.p50), 1000.0 / pct.p50.max(0.001)));
+            stat_row(ui, "p95", &ms(pct.p95));
+            stat_row(ui, "p99", &ms(pct.p99));
+            stat_row(ui, tr("perf.max"), &ms(pct.max));
+        });
+
+        // Mean cost per frame, by source
+        ui.separator();
+        ui.label(egui::RichText::new(tr("perf.breakdown")).strong());
+        let frame_mean = stats.mean(|t| Some(t.frame_ms)).unwrap_or(0.0).max(0.001);
+        egui::Grid::new("perf_breakdown").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+            for (key, _, value) in PERF_SERIES {
+                let text = match stats.mean(value) {
+                    Some(v) => format!("{}  ({:.0}%)", ms(v), 100.0 * v / frame_mean),
+                    None => tr("perf.unavailable").to_string(),
+                };
+                stat_row(ui, tr(key), &text);
+            }
+            let stalls = stats.readback_stalls();
+            stat_row(
+                ui,
+                tr("perf.readback_stalls"),
+                &trf("perf.stalls_value", &[("count", &stalls), ("ms", &format!("{:.0}", FrameStats::STALL_MS))]),
+            );
+        });
+        ui.label(egui::RichText::new(tr("perf.gpu_hint")).small().weak());
+
+        // Rolling charts (x = frames ago, newest at 0)
+        ui.separator();
+        let n = stats.samples().len();
+        Plot::new("perf_plot")
+            .height(180.0)
+            .legend(Legend::default())
+            .show_grid(true)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .x_axis_label(tr("perf.frames_ago"))
+            .y_axis_label("ms")
+            .show(ui, |plot_ui| {
+                let series = std::iter::once(("perf.frame", egui::Color32::from_rgb(220, 220, 240), frame_time as PerfValue))
+                    .chain(PERF_SERIES.iter().copied());
+                for (key, color, value) in series {
+                    let points: PlotPoints = stats
+                        .samples()
+                        .enumerate()
+                        .filter_map(|(i, t)| value(t).map(|v| [i as f64 - n as f64 + 1.0, v as f64]))
+                        .collect();
+                    plot_ui.line(Line::new(points).name(tr(key)).color(color));
+                }
+            });
+    });
+}
+
+type PerfValue = fn(&FrameTiming) -> Option<f32>;
+
+fn frame_time(t: &FrameTiming) -> Option<f32> {
+    Some(t.frame_ms)
+}
+
+/// Cost sources shown in the breakdown and charts: label key, color, value.
+const PERF_SERIES: [(&str, egui::Color32, PerfValue); 4] = [
+    ("perf.encode", egui::Color32::from_rgb(100, 180, 255), |t| Some(t.encode_ms)),
+    ("perf.gpu_sim", egui::Color32::from_rgb(120, 220, 120), |t| t.gpu_sim_ms),
+    ("perf.egui", egui::Color32::from_rgb(255, 170, 60), |t| Some(t.egui_ms)),
+    ("perf.readback", egui::Color32::from_rgb(230, 90, 90), |t| Some(t.readback_ms)),
+];
+
+// ======================== Resources Tab ========================
+
+/// GPU buffer sizes of the current world, CPU-side history, device limits,
+/// and projections for larger worlds.
+fn render_resources_tab(ui: &mut egui::Ui, lab: &mut LabState) {
+    let Some(report) = &lab.memory_report else {
+        ui.label(tr("resources.no_data"));
+        return;
+    };
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.label(egui::RichText::new(trf(
+            "resources.world",
+            &[("w", &report.width), ("h", &report.height), ("total", &format_bytes(report.gpu_total()))],
+        )).strong());
+
+        for warning in report.warnings() {
+            let text = trf("resources.warning", &[
+                ("buffer", &warning.buffer),
+                ("size", &format_bytes(warning.bytes)),
+                ("percent", &format!("{:.0}", warning.fraction() * 100.0)),
+                ("limit", &tr(warning.limit_key)),
+            ]);
+            ui.label(egui::RichText::new(format!("⚠ {}", text)).color(severity_color(warning.severity())));
+        }
+
+        // Buffers grouped by kind: per buffer, copies, and total
+        for kind in BufferKind::all() {
+            ui.separator();
+            ui.label(egui::RichText::new(trf(
+                "resources.kind_total",
+                &[("kind", &tr(kind.label_key())), ("total", &format_bytes(report.total(*kind)))],
+            )).strong());
+            egui::Grid::new(("resources_buffers", kind.label_key())).num_columns(2).spacing([20.0, 2.0]).show(ui, |ui| {
+                for b in report.buffers.iter().filter(|b| b.kind == *kind) {
+                    let size = if b.copies > 1 {
+                        format!("{} × {} = {}", b.copies, format_bytes(b.bytes), format_bytes(b.total()))
+                    } else {
+                        format_bytes(b.bytes)
+                    };
+                    stat_row(ui, b.name, &size);
+                }
+            });
+        }
+
+        ui.separator();
+        ui.label(egui::RichText::new(tr("resources.cpu")).strong());
+        egui::Grid::new("resources_cpu").num_columns(2).spacing([20.0, 2.0]).show(ui, |ui| {
+            stat_row(
+                ui,
+                tr("resources.metrics_history"),
+                &trf("resources.records", &[("count", &lab.metrics_history.len()), ("size", &format_bytes(report.history_bytes))]),
+            );
+            stat_row(
+                ui,
+                tr("resources.event_log"),
+                &trf("resources.records", &[("count", &lab.events.len()), ("size", &format_bytes(report.events_bytes))]),
+            );
+        });
+
+        ui.separator();
+        ui.label(egui::RichText::new(tr("resources.limits")).strong());
+        egui::Grid::new("resources_limits").num_columns(2).spacing([20.0, 2.0]).show(ui, |ui| {
+            stat_row(ui, tr("resources.limit_binding"), &format_bytes(report.max_storage_binding));
+            stat_row(ui, tr("resources.limit_buffer"), &format_bytes(report.max_buffer_size));
+        });
+
+        // What a larger world would need on this device
+        ui.separator();
+        ui.label(egui::RichText::new(tr("resources.projection")).strong());
+        egui::Grid::new("resources_projection").num_columns(4).spacing([20.0, 2.0]).show(ui, |ui| {
+            for size in PROJECTED_SIZES {
+                let projected = report.resized(size, size);
+                let largest = projected.largest_buffer().map_or(0, |b| b.bytes);
+                ui.label(format!("{}²", size));
+                ui.label(egui::RichText::new(trf(
+                    "resources.projected_total",
+                    &[("total", &format_bytes(projected.gpu_total())), ("largest", &format_bytes(largest))],
+                )).monospace());
+                let worst = projected.warnings().iter().map(|w| w.severity()).max();
+                let (text, color) = match worst {
+                    None => (tr("resources.fits"), egui::Color32::from_rgb(120, 220, 120)),
+                    Some(s) if s == EventSeverity::Warn => (tr("resources.near_limit"), severity_color(s)),
+                    Some(s) => (tr("resources.over_limit"), severity_color(s)),
+                };
+                ui.label(egui::RichText::new(text).color(color));
+                let tiling = match projected.tile_plan() {
+                    Some(plan) if plan.tiles == 1 => tr("resources.tiles_one").to_string(),
+                    Some(plan) => trf("resources.tiles", &[
+                        ("tiles", &plan.tiles),
+                        ("rows", &plan.rows),
+                        ("halo", &plan.halo),
+                        ("size", &format_bytes(plan.tile_bytes)),
+                    ]),
+                    None => tr("resources.tiles_none").to_string(),
+                };
+                ui.label(tiling).on_hover_text(tr("resources.tiles_hint"));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+// ======================== Experiment Queue Tab ========================
+
+fn render_queue_tab(ui: &mut egui::Ui, params: &mut SimulationParams, lab: &mut LabState) {
+    let (finished, total) = lab.experiment_queue.counts();
+    ui.horizontal(|ui| {
+        let queue = &mut lab.experiment_queue;
+        let toggle = if queue.running { tr("queue.pause") } else { tr("queue.start") };
+        if ui.button(toggle).on_hover_text(tr("queue.pause_hint")).clicked() {
+            queue.running = !queue.running;
+        }
+        if let Some(index) = queue.active() {
+            if ui.button(tr("queue.cancel_current")).clicked() {
+                match queue.entries[index].protocol.mode {
+                    QueueMode::Visible => lab.finalize_run(params), // picked up as cancelled
+                    QueueMode::Background => queue.cancel_background(),
+                }
+            }
+        }
+        if ui.button(tr("queue.clear_finished")).clicked() {
+            lab.experiment_queue.clear_finished();
+        }
+    });
+    ui.add(
+        egui::ProgressBar::new(lab.experiment_queue.fraction())
+            .text(trf("queue.progress", &[("done", &finished), ("total", &total)])),
+    );
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        if let Some(figure) = render_background_run(ui, lab) {
+            lab.export_plot(figure, params);
+        }
+        render_queue_entries(ui, lab);
+        ui.separator();
+        render_protocol_form(ui, params, lab);
+    });
+}
+
+/// Live progress and metrics of the current (or last) background run;
+/// returns a plot's figure when its export is clicked.
+fn render_background_run(ui: &mut egui::Ui, lab: &LabState) -> Option<PlotFigure> {
+    let queue = &lab.experiment_queue;
+    let Some(name) = &queue.background_name else {
+        return None;
+    };
+    let active = queue.active().filter(|&i| queue.entries[i].protocol.mode == QueueMode::Background);
+    let title = if active.is_some() { "queue.background_title" } else { "queue.background_last" };
+    ui.label(egui::RichText::new(trf(title, &[("name", name)])).strong());
+    if let Some(i) = active {
+        if let EntryStatus::Running { frames_done } = queue.entries[i].status {
+            let frames = queue.entries[i].protocol.frames.max(1);
+            ui.add(egui::ProgressBar::new(frames_done as f32 / frames as f32).text(format!("{}/{}", frames_done, frames)));
+        }
+    }
+    let Some(last) = queue.background_metrics.last() else {
+        ui.label(egui::RichText::new(tr("queue.background_waiting")).small().color(egui::Color32::GRAY));
+        ui.separator();
+        return None;
+    };
+    egui::Grid::new("background_stats").num_columns(2).striped(true).show(ui, |ui| {
+        stat_row(ui, tr("stat.frame"), &format!("{}", last.frame));
+        stat_row(ui, tr("queue.steps_per_sec"), &format!("{:.0}", last.fps));
+        stat_row(ui, tr("stat.total_mass"), &format!("{:.0}", last.total_mass));
+        stat_row(ui, tr("stat.species"), &format!("{}", last.species));
+        stat_row(ui, tr("stat.entropy"), &format!("{:.2} bits", last.entropy));
+        stat_row(ui, tr("stat.live_pixels"), &format!("{} ({:.1}%)", last.live_pixels, last.live_fraction * 100.0));
+    });
+    let markers = PlotMarkers::default();
+    let mass = render_plot(ui, tr("queue.plot_mass"), &queue.background_metrics, &markers, |m| m.total_mass as f64);
+    let species = render_plot(ui, tr("queue.plot_species"), &queue.background_metrics, &markers, |m| m.species as f64);
+    ui.separator();
+    mass.or(species)
+}
+
+fn render_queue_entries(ui: &mut egui::Ui, lab: &mut LabState) {
+    let queue = &mut lab.experiment_queue;
+    if queue.entries.is_empty() {
+        ui.label(tr("queue.empty"));
+        return;
+    }
+    let mut remove = None;
+    let mut move_up = None;
+    egui::Grid::new("queue_entries").num_columns(5).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+        for (i, entry) in queue.entries.iter().enumerate() {
+            let p = &entry.protocol;
+            ui.label(format!("{}. {}", i + 1, p.name));
+            ui.label(tr(p.mode.label_key()));
+            ui.label(match p.seed {
+                Some(seed) => trf("queue.frames_seed", &[("frames", &p.frames), ("seed", &seed)]),
+                None => trf("queue.frames_random", &[("frames", &p.frames)]),
+            });
+            match &entry.status {
+                EntryStatus::Running { frames_done } => {
+                    ui.add(
+                        egui::ProgressBar::new(*frames_done as f32 / p.frames.max(1) as f32)
+                            .desired_width(120.0)
+                            .text(format!("{}/{}", frames_done, p.frames)),
+                    );
+                }
+                status => {
+                    let (text, color) = match status {
+                        EntryStatus::Done => (tr("queue.status_done").to_string(), egui::Color32::from_rgb(120, 220, 120)),
+                        EntryStatus::Cancelled => (tr("queue.status_cancelled").to_string(), egui::Color32::GRAY),
+                        EntryStatus::Failed(e) => {
+                            (trf("queue.status_failed", &[("error", e)]), severity_color(EventSeverity::Warn))
+                        }
+                        _ => (tr("queue.status_pending").to_string(), ui.visuals().text_color()),
+                    };
+                    let label = ui.label(egui::RichText::new(text).color(color));
+                    if let Some(dir) = &entry.run_dir {
+                        label.on_hover_text(dir.display().to_string());
+                    }
+                }
+            }
             ui.horizontal(|ui| {
-                ui.label(egui::RichText::new("📋 Events Log").strong());
-                ui.label(format!("({} events)", lab.events.len()));
-                if ui.button("Clear").clicked() {
-                    lab.events.clear();
+                if ui.add_enabled(i > 0, egui::Button::new("⬆").small()).on_hover_text(tr("queue.move_up")).clicked() {
+                    move_up = Some(i);
+                }
+                let running = matches!(entry.status, EntryStatus::Running { .. });
+                if ui.add_enabled(!running, egui::Button::new("✖").small()).on_hover_text(tr("queue.remove")).clicked() {
+                    remove = Some(i);
+                }
+            });
+            ui.end_row();
+        }
+    });
+    if let Some(i) = move_up {
+        queue.move_up(i);
+    }
+    if let Some(i) = remove {
+        queue.remove(i);
+    }
+}
+
+fn render_protocol_form(ui: &mut egui::Ui, params: &SimulationParams, lab: &mut LabState) {
+    ui.label(egui::RichText::new(tr("queue.new_protocol")).strong());
+    let draft = &mut lab.experiment_queue.draft;
+    egui::Grid::new("queue_protocol_form").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+        ui.label(tr("queue.name"));
+        ui.text_edit_singleline(&mut draft.name);
+        ui.end_row();
+
+        ui.label(tr("queue.frames"));
+        ui.add(egui::DragValue::new(&mut draft.frames).range(1..=u32::MAX).speed(100.0));
+        ui.end_row();
+
+        ui.checkbox(&mut draft.fixed_seed, tr("queue.fixed_seed"));
+        ui.add_enabled(draft.fixed_seed, egui::DragValue::new(&mut draft.seed));
+        ui.end_row();
+
+        ui.label(tr("queue.mode"));
+        egui::ComboBox::from_id_salt("queue_mode")
+            .selected_text(tr(draft.mode.label_key()))
+            .show_ui(ui, |ui| {
+                for mode in QueueMode::all() {
+                    ui.selectable_value(&mut draft.mode, mode, tr(mode.label_key()));
+                }
+            })
+            .response
+            .on_hover_text(tr("queue.mode_hint"));
+        ui.end_row();
+
+        ui.label(tr("queue.preset"));
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut draft.preset).on_hover_text(tr("queue.preset_hint"));
+            if ui.button("📂").on_hover_text(tr("exp.load_preset")).clicked() {
+                if let Some(path) = file_dialog::pick_preset(Path::new(PRESETS_DIR)) {
+                    draft.preset = path.display().to_string();
+                }
+            }
+        });
+        ui.end_row();
+    });
+
+    ui.horizontal(|ui| {
+        let enqueue = ui.button(tr("queue.enqueue")).clicked();
+        let run_next = ui.button(tr("queue.run_next")).on_hover_text(tr("queue.run_next_hint")).clicked();
+        if !enqueue && !run_next {
+            return;
+        }
+        let draft = &lab.experiment_queue.draft;
+        let preset = draft.preset.trim().to_string();
+        let source = if preset.is_empty() { Some(params.clone()) } else { load_preset(&preset_path(&preset)).ok() };
+        let Some(source) = source else {
+            lab.set_warning(trf("status.preset_missing", &[("name", &preset)]));
+            return;
+        };
+        let mut protocol = draft.build(source);
+        lab.set_status(trf("status.queue_enqueued", &[("name", &protocol.name)]));
+        if run_next {
+            protocol.mode = QueueMode::Background;
+            lab.experiment_queue.enqueue_next(protocol);
+        } else {
+            lab.experiment_queue.enqueue(protocol);
+        }
+    });
+}
+
+// ======================== Report Tab ========================
+
+/// Generate the current run's report into the preview (discarding edits).
+fn regenerate_report(params: &SimulationParams, lab: &mut LabState) {
+    match lab.generate_report(params) {
+        Ok(markdown) => {
+            let run_dir = lab.run_dir.clone();
+            lab.report.set(markdown, &run_dir);
+        }
+        Err(e) => lab.set_warning(trf("status.report_failed", &[("error", &e)])),
+    }
+}
+
+/// Report preview: rendered markdown with its plots, or a text editor to
+/// tweak it before saving report.md.
+fn render_report_tab(ui: &mut egui::Ui, params: &SimulationParams, lab: &mut LabState) {
+    ui.horizontal(|ui| {
+        if ui.button(tr("report.regenerate")).on_hover_text(tr("report.regenerate_hint")).clicked() {
+            regenerate_report(params, lab);
+        }
+        ui.toggle_value(&mut lab.report.editing, tr("report.edit"));
+        let can_save = !lab.report.markdown.is_empty();
+        if ui.add_enabled(can_save, egui::Button::new(tr("common.save"))).clicked() {
+            match lab.save_report(&lab.report.markdown) {
+                Ok(path) => {
+                    lab.report.modified = false;
+                    lab.set_status(trf("status.report_saved", &[("path", &format!("{:?}", path))]));
+                }
+                Err(e) => lab.set_warning(trf("status.report_failed", &[("error", &e)])),
+            }
+        }
+        if lab.report.modified {
+            ui.label(egui::RichText::new(tr("report.modified")).small().color(egui::Color32::from_rgb(255, 200, 100)));
+        }
+    });
+    render_report_template_row(ui, lab);
+    ui.separator();
+
+    if lab.report.markdown.is_empty() {
+        ui.label(egui::RichText::new(tr("report.empty")).weak());
+        return;
+    }
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        if lab.report.editing {
+            let editor = egui::TextEdit::multiline(&mut lab.report.markdown)
+                .code_editor()
+                .desired_width(f32::INFINITY);
+            if ui.add(editor).changed() {
+                lab.report.modified = true;
+            }
+        } else {
+            render_markdown(ui, &mut lab.report);
+        }
+    });
+}
+
+/// Template used for generated reports: a file path, or empty for the
+/// built-in layout (which can be saved as a starting point).
+fn render_report_template_row(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.horizontal(|ui| {
+        ui.label(tr("report.template"));
+        ui.add(
+            egui::TextEdit::singleline(&mut lab.report_template)
+                .hint_text(tr("report.template_builtin"))
+                .desired_width(220.0),
+        )
+        .on_hover_text(tr("report.template_hint"));
+        if ui.button("📂").on_hover_text(tr("dialog.load_template")).clicked() {
+            if let Some(path) = file_dialog::pick_template(Path::new(TEMPLATES_DIR)) {
+                lab.report_template = path.display().to_string();
+            }
+        }
+        if ui.button(tr("report.save_builtin")).on_hover_text(tr("report.save_builtin_hint")).clicked() {
+            if let Some(path) = file_dialog::save_template(Path::new(TEMPLATES_DIR), "my_report.md.hbs") {
+                match std::fs::write(&path, DEFAULT_TEMPLATE) {
+                    Ok(()) => {
+                        lab.report_template = path.display().to_string();
+                        lab.set_status(trf("status.template_saved", &[("path", &format!("{:?}", path))]));
+                    }
+                    Err(e) => lab.set_warning(trf("status.report_failed", &[("error", &e)])),
+                }
+            }
+        }
+    });
+}
+
+/// Text with `**bold**` runs, wrapped to the panel width.
+fn markdown_inline(ui: &mut egui::Ui, prefix: Option<&str>, text: &str) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        if let Some(prefix) = prefix {
+            ui.label(prefix);
+        }
+        for (span, bold) in inline_spans(text) {
+            let rich = egui::RichText::new(span);
+            ui.label(if bold { rich.strong() } else { rich });
+        }
+    });
+}
+
+fn render_markdown(ui: &mut egui::Ui, report: &mut ReportPreview) {
+    for (i, block) in parse_markdown(&report.markdown).into_iter().enumerate() {
+        match block {
+            Block::Heading(level, text) => {
+                let size = match level {
+                    1 => 22.0,
+                    2 => 18.0,
+                    _ => 15.0,
+                };
+                ui.add_space(6.0);
+                ui.label(egui::RichText::new(text).size(size).strong());
+            }
+            Block::Paragraph(text) => markdown_inline(ui, None, &text),
+            Block::Bullet(text) => markdown_inline(ui, Some("• "), &text),
+            Block::Table(rows) => {
+                egui::Grid::new(("report_table", i)).striped(true).show(ui, |ui| {
+                    for (r, row) in rows.iter().enumerate() {
+                        for cell in row {
+                            let text = egui::RichText::new(cell);
+                            ui.label(if r == 0 { text.strong() } else { text });
+                        }
+                        ui.end_row();
+                    }
+                });
+            }
+            Block::Code(code) => {
+                let show = |ui: &mut egui::Ui| {
+                    ui.label(egui::RichText::new(&code).monospace().small());
+                };
+                if code.lines().count() > 12 {
+                    egui::CollapsingHeader::new(tr("report.code")).id_salt(("report_code", i)).show(ui, show);
+                } else {
+                    show(ui);
                 }
-                if ui.button("Export").clicked() {
-                    match lab.export_events_log() {
-                        Ok(path) => lab.set_status(format!("Exported events to {:?}", path)),
-                        Err(e) => lab.set_status(format!("Export failed: {}", e)),
+            }
+            Block::Image { alt, path } => {
+                let full = report.run_dir.join(&path);
+                let texture = if path.ends_with(".svg") { None } else { cached_texture(ui.ctx(), &mut report.images, &full) };
+                match texture {
+                    Some(texture) => {
+                        let size = texture.size_vec2();
+                        let scale = (ui.available_width() / size.x).min(1.0);
+                        ui.image((texture.id(), size * scale)).on_hover_text(&path);
                     }
+                    None => {
+                        ui.label(egui::RichText::new(trf("report.image_unavailable", &[("path", &path)])).weak());
+                    }
+                }
+                ui.label(egui::RichText::new(alt).small().weak());
+            }
+        }
+    }
+}
+
+// ======================== Logs Tab ========================
+
+fn render_logs_tab(ui: &mut egui::Ui, lab: &mut LabState) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(tr("logs.title")).strong());
+        if lab.event_filter.is_active() {
+            let shown = lab.events.iter().filter(|e| lab.event_filter.matches(e)).count();
+            ui.label(trf("logs.filtered", &[("shown", &shown), ("count", &lab.events.len())]));
+        } else {
+            ui.label(trf("logs.count", &[("count", &lab.events.len())]));
+        }
+        if ui.button(tr("common.clear")).clicked() {
+            lab.events.clear();
+        }
+        if ui.button(tr("common.export")).clicked() {
+            match lab.export_events_log() {
+                Ok(path) => lab.set_status(trf("status.events_exported", &[("path", &format!("{:?}", path))])),
+                Err(e) => {
+                    lab.log_event_with_severity(lab.current_frame, EventSeverity::Warn, "EXPORT", &e);
+                    lab.set_warning(trf("status.export_failed", &[("error", &e)]));
+                }
+            }
+        }
+    });
+
+    // Filters: minimum severity, event type, free-text search
+    let types: std::collections::BTreeSet<String> =
+        lab.events.iter().map(|e| e.event_type.clone()).collect();
+    ui.horizontal(|ui| {
+        let filter = &mut lab.event_filter;
+        egui::ComboBox::from_id_salt("log_severity")
+            .selected_text(tr(filter.min_severity.label_key()))
+            .show_ui(ui, |ui| {
+                for s in EventSeverity::all() {
+                    ui.selectable_value(&mut filter.min_severity, *s, tr(s.label_key()));
+                }
+            })
+            .response
+            .on_hover_text(tr("logs.severity_hint"));
+        egui::ComboBox::from_id_salt("log_type")
+            .selected_text(filter.event_type.as_deref().unwrap_or(tr("logs.all_types")))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut filter.event_type, None, tr("logs.all_types"));
+                for t in &types {
+                    ui.selectable_value(&mut filter.event_type, Some(t.clone()), t);
                 }
             });
-            ui.separator();
-            egui::ScrollArea::vertical()
-                .auto_shrink([false, false])
-                .stick_to_bottom(true)
-                .show(ui, |ui| {
-                    for event in lab.events.iter().rev().take(100) {
-                        let color = match event.event_type.as_str() {
-                            "PARAM_CHANGE" => egui::Color32::from_rgb(255, 200, 100),
-                            "RUN_START" | "RUN_END" => egui::Color32::from_rgb(100, 255, 100),
-                            "CONTROL" => egui::Color32::from_rgb(150, 200, 255),
-                            "SCREENSHOT" | "SNAPSHOT" => egui::Color32::from_rgb(200, 150, 255),
-                            _ => egui::Color32::from_rgb(180, 180, 180),
-                        };
-                        ui.label(egui::RichText::new(event.to_log_line()).small().color(color).monospace());
+        ui.add(
+            egui::TextEdit::singleline(&mut filter.search)
+                .hint_text(tr("logs.search_hint"))
+                .desired_width(160.0),
+        );
+        if filter.is_active() && ui.button("✖").on_hover_text(tr("logs.reset_filter")).clicked() {
+            *filter = EventFilter::default();
+        }
+    });
+    ui.separator();
+
+    // Newest first; rows are virtualized so long sessions stay responsive
+    let matching: Vec<usize> = (0..lab.events.len())
+        .rev()
+        .filter(|&i| lab.event_filter.matches(&lab.events[i]))
+        .collect();
+    let row_height = ui.text_style_height(&egui::TextStyle::Small) + ui.spacing().item_spacing.y;
+    let mut jump_to = None;
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show_rows(ui, row_height, matching.len(), |ui, range| {
+            for &i in &matching[range] {
+                let event = &lab.events[i];
+                let color = event_color(event);
+                ui.horizontal(|ui| {
+                    let link = ui
+                        .link(egui::RichText::new(format!("⏵{}", event.frame)).small().monospace())
+                        .on_hover_text(trf("logs.jump_hint", &[("frame", &event.frame)]));
+                    if link.clicked() {
+                        jump_to = Some(event.frame);
                     }
+                    ui.label(egui::RichText::new(event.to_log_line()).small().color(color).monospace());
                 });
+            }
         });
+
+    // Jump: mark the frame on the analysis plots and make sure they are visible
+    if let Some(frame) = jump_to {
+        lab.marked_frame = Some(frame);
+        lab.show_analysis_panel = true;
+    }
+}
+
+// ======================== Notifications ========================
+
+/// Text color of an event in the log (also its marker on the plots).
+fn event_color(event: &LabEvent) -> egui::Color32 {
+    match event.severity {
+        EventSeverity::Alert => egui::Color32::from_rgb(255, 90, 90),
+        EventSeverity::Warn => egui::Color32::from_rgb(255, 170, 60),
+        EventSeverity::Info => match event.event_type.as_str() {
+            "PARAM_CHANGE" => egui::Color32::from_rgb(255, 200, 100),
+            "RUN_START" | "RUN_END" => egui::Color32::from_rgb(100, 255, 100),
+            "CONTROL" => egui::Color32::from_rgb(150, 200, 255),
+            "SCREENSHOT" | "SNAPSHOT" => egui::Color32::from_rgb(200, 150, 255),
+            "PROFILE" => egui::Color32::from_rgb(255, 220, 80),
+            "PERTURBATION" | "RESTART" | "SEED" => egui::Color32::from_rgb(120, 220, 255),
+            _ => egui::Color32::from_rgb(180, 180, 180),
+        },
+    }
 }
 
-// ======================== Status Bar ========================
+fn severity_color(severity: EventSeverity) -> egui::Color32 {
+    match severity {
+        EventSeverity::Info => egui::Color32::from_rgba_premultiplied(30, 80, 30, 220),
+        EventSeverity::Warn => egui::Color32::from_rgba_premultiplied(110, 70, 10, 220),
+        EventSeverity::Alert => egui::Color32::from_rgba_premultiplied(120, 25, 25, 220),
+    }
+}
 
-fn render_status_bar(ctx: &egui::Context, lab: &mut LabState) {
-    if let Some(msg) = lab.current_status() {
-        let msg = msg.to_string();
-        egui::Area::new(egui::Id::new("status_bar"))
-            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))
+/// Toast stack (bottom right): running jobs with progress bars, then recent
+/// notifications. Plus the notification history window.
+fn render_notifications(ctx: &egui::Context, lab: &mut LabState) {
+    let center = &mut lab.notifications;
+    let has_toasts = center.toasts().next().is_some();
+    if !center.jobs.is_empty() || has_toasts {
+        egui::Area::new(egui::Id::new("notification_toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .order(egui::Order::Foreground)
             .show(ctx, |ui| {
-                egui::Frame::default()
-                    .fill(egui::Color32::from_rgba_premultiplied(30, 80, 30, 220))
-                    .corner_radius(egui::CornerRadius::same(4))
-                    .inner_margin(egui::Margin::symmetric(12, 6))
-                    .show(ui, |ui| {
-                        ui.label(egui::RichText::new(msg).color(egui::Color32::WHITE));
-                    });
+                ui.set_max_width(320.0);
+                for job in &center.jobs {
+                    egui::Frame::default()
+                        .fill(egui::Color32::from_rgba_premultiplied(25, 45, 80, 230))
+                        .corner_radius(egui::CornerRadius::same(4))
+                        .inner_margin(egui::Margin::symmetric(12, 6))
+                        .show(ui, |ui| {
+                            let elapsed = job.started.elapsed().as_secs_f32();
+                            ui.label(
+                                egui::RichText::new(trf("notify.job_running", &[("title", &job.title), ("secs", &format!("{:.0}", elapsed))]))
+                                    .color(egui::Color32::WHITE),
+                            );
+                            let bar = match job.progress {
+                                Some(p) => egui::ProgressBar::new(p).show_percentage(),
+                                None => egui::ProgressBar::new(0.0).animate(true),
+                            };
+                            ui.add(bar.desired_width(296.0));
+                        });
+                    ui.add_space(4.0);
+                }
+                for note in center.toasts() {
+                    egui::Frame::default()
+                        .fill(severity_color(note.severity))
+                        .corner_radius(egui::CornerRadius::same(4))
+                        .inner_margin(egui::Margin::symmetric(12, 6))
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(&note.text).color(egui::Color32::WHITE));
+                        });
+                    ui.add_space(4.0);
+                }
             });
+        // Keep progress bars animating and toasts expiring while idle
+        ctx.request_repaint();
     }
+
+    let mut open = center.show_history;
+    egui::Window::new(tr("notify.history"))
+        .id(egui::Id::new("notification_history"))
+        .open(&mut open)
+        .default_size([360.0, 280.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(trf("notify.count", &[("count", &center.history.len())]));
+                if ui.button(tr("common.clear")).clicked() {
+                    center.history.clear();
+                }
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                if center.history.is_empty() {
+                    ui.label(egui::RichText::new(tr("notify.empty")).weak());
+                }
+                for note in center.history.iter().rev() {
+                    ui.horizontal_wrapped(|ui| {
+                        let icon = match note.severity {
+                            EventSeverity::Info => "ℹ",
+                            EventSeverity::Warn => "⚠",
+                            EventSeverity::Alert => "🚨",
+                        };
+                        ui.label(egui::RichText::new(format!("{} {}", note.wall_time, icon)).small().monospace());
+                        ui.label(&note.text);
+                    });
+                }
+            });
+        });
+    center.show_history = open;
 }
 
 // ======================== Preset Save/Load ========================
 
+/// Preset directory, relative to the working directory.
+const PRESETS_DIR: &str = "presets";
+
 fn save_preset(name: &str, params: &SimulationParams) {
-    let dir = std::path::PathBuf::from("presets");
+    let dir = std::path::PathBuf::from(PRESETS_DIR);
     if let Err(e) = std::fs::create_dir_all(&dir) {
         log::error!("Failed to create presets dir: {}", e);
         return;
@@ -858,17 +4985,25 @@ fn save_preset(name: &str, params: &SimulationParams) {
     }
 }
 
-fn load_preset(name: &str) -> Option<SimulationParams> {
-    let path = std::path::PathBuf::from(format!("presets/{}.json", name));
-    let content = std::fs::read_to_string(&path).ok()?;
+/// A preset name resolves to `presets/<name>.json`; a `.json` path is used as is.
+fn preset_path(name: &str) -> std::path::PathBuf {
+    if name.ends_with(".json") {
+        std::path::PathBuf::from(name)
+    } else {
+        Path::new(PRESETS_DIR).join(format!("{}.json", name))
+    }
+}
+
+fn load_preset(path: &Path) -> Result<SimulationParams, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
     match serde_json::from_str::<SimulationParams>(&content) {
         Ok(params) => {
             log::info!("Loaded preset from {:?}", path);
-            Some(params)
+            Ok(params)
         }
         Err(e) => {
             log::error!("Failed to parse preset {:?}: {}", path, e);
-            None
+            Err(format!("{:?}: {}", path, e))
         }
     }
 }
@@ -0,0 +1,331 @@
+// ============================================================================
+// landscape.rs — EvoLenia v2 Research Lab
+// Fitness landscape probe: clone the population around a chosen location
+// into a small toroidal micro-world, give it a uniform genome, and step it on
+// the CPU for a few hundred frames. Sweeping one or two genes over a grid of
+// values — one micro-world per grid point — maps the growth each genome
+// achieves from the same starting pattern: a local fitness landscape.
+//
+// The micro-world runs the Lenia core of compute_evolution.wgsl (exact
+// radius-blended convolution, Gaussian growth) without metabolism,
+// advection or mutation, so it measures how well a genome sustains the local
+// pattern, not the whole ecology.
+// ============================================================================
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+
+use crate::config::{GENE_KEYS, GENE_LIMITS};
+use crate::i18n::tr;
+use crate::kernel::kernel_taps;
+use crate::world::{BufferSnapshot, DT};
+
+/// Side of the micro-world, in cells.
+pub const PROBE_SIZE: usize = 64;
+/// Most grid points per swept gene.
+pub const MAX_RESOLUTION: usize = 16;
+
+/// Cells lighter than this count as empty when averaging the genome.
+const LIVING_MASS: f32 = 0.01;
+/// Genome of an empty clone (the world's initial defaults).
+const FALLBACK_GENOME: [f32; 4] = [8.0, 0.15, 0.03, 0.2];
+
+// ======================== Micro-world ========================
+
+/// A PROBE_SIZE² torus of mass cut from the world, with one genome for all
+/// of its cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MicroWorld {
+    pub mass: Vec<f32>, // row-major
+    pub genome: [f32; 4],
+}
+
+impl MicroWorld {
+    /// Clone the PROBE_SIZE² square centered on `center` (pixels), with the
+    /// mass-weighted mean genome of its living cells.
+    pub fn clone_from(snap: &BufferSnapshot, center: [u32; 2]) -> Self {
+        let half = PROBE_SIZE / 2;
+        let mut mass = Vec::with_capacity(PROBE_SIZE * PROBE_SIZE);
+        let mut sums = [0.0f64; 4];
+        let mut total = 0.0f64;
+        let [width, height] = snap.size.map(|edge| edge as usize);
+        for y in 0..PROBE_SIZE {
+            for x in 0..PROBE_SIZE {
+                let wx = (center[0] as usize + width + x - half) % width;
+                let wy = (center[1] as usize + height + y - half) % height;
+                let i = wy * width + wx;
+                let m = snap.mass[i];
+                mass.push(m);
+                if m >= LIVING_MASS {
+                    for (s, g) in sums.iter_mut().zip(&snap.genome_a[i * 4..i * 4 + 4]) {
+                        *s += (*g * m) as f64;
+                    }
+                    total += m as f64;
+                }
+            }
+        }
+        let genome = if total > 0.0 { sums.map(|s| (s / total) as f32) } else { FALLBACK_GENOME };
+        Self { mass, genome }
+    }
+
+    pub fn total_mass(&self) -> f32 {
+        self.mass.iter().sum()
+    }
+
+    /// One Lenia step: m ← clamp(m + dt · (2·G(U; μ, σ) − 1), 0, 1).
+    pub fn step(&mut self, taps: &[(i32, i32, f32)]) {
+        let n = PROBE_SIZE as i32;
+        let mu = self.genome[1];
+        let sigma = self.genome[2].max(0.005);
+        let next: Vec<f32> = (0..n * n)
+            .map(|i| {
+                let (x, y) = (i % n, i / n);
+                let u: f32 = taps
+                    .iter()
+                    .map(|&(dx, dy, w)| w * self.mass[((y + dy).rem_euclid(n) * n + (x + dx).rem_euclid(n)) as usize])
+                    .sum();
+                let growth = (-((u - mu) * (u - mu)) / (2.0 * sigma * sigma)).exp();
+                (self.mass[i as usize] + DT * (2.0 * growth - 1.0)).clamp(0.0, 1.0)
+            })
+            .collect();
+        self.mass = next;
+    }
+
+    /// Growth of the clone under `genome` after `steps`: final over initial
+    /// total mass (0 = extinct, 1 = unchanged).
+    pub fn growth(&self, genome: [f32; 4], steps: u32) -> f32 {
+        let initial = self.total_mass();
+        if initial <= 0.0 {
+            return 0.0;
+        }
+        let taps = kernel_taps(genome[0]);
+        let mut world = MicroWorld { mass: self.mass.clone(), genome };
+        for _ in 0..steps {
+            world.step(&taps);
+        }
+        world.total_mass() / initial
+    }
+}
+
+// ======================== Sweeps ========================
+
+/// One swept gene and its range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepAxis {
+    pub gene: usize, // index into the genome (GENE_KEYS)
+    pub range: (f32, f32),
+}
+
+impl SweepAxis {
+    /// The whole range of `gene`.
+    pub fn full(gene: usize) -> Self {
+        Self { gene, range: GENE_LIMITS[gene] }
+    }
+
+    /// Gene value at grid point `k` of `resolution`.
+    pub fn value(&self, k: usize, resolution: usize) -> f32 {
+        let (lo, hi) = self.range;
+        if resolution < 2 {
+            return (lo + hi) * 0.5;
+        }
+        lo + (hi - lo) * k as f32 / (resolution - 1) as f32
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        GENE_KEYS[self.gene]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbeSettings {
+    pub center: [f32; 2], // world UV
+    pub x: SweepAxis,
+    pub y: Option<SweepAxis>, // None = a one-gene sweep
+    pub resolution: usize,    // grid points per axis
+    pub steps: u32,
+}
+
+impl Default for ProbeSettings {
+    fn default() -> Self {
+        Self {
+            center: [0.5, 0.5],
+            x: SweepAxis::full(1),
+            y: Some(SweepAxis::full(2)),
+            resolution: 8,
+            steps: 200,
+        }
+    }
+}
+
+impl ProbeSettings {
+    /// Genome of grid point (`kx`, `ky`): `base` with the swept genes set.
+    pub fn genome_at(&self, base: [f32; 4], kx: usize, ky: usize) -> [f32; 4] {
+        let mut genome = base;
+        genome[self.x.gene] = self.x.value(kx, self.resolution);
+        if let Some(y) = &self.y {
+            genome[y.gene] = y.value(ky, self.resolution);
+        }
+        genome
+    }
+
+    /// Grid points along y (1 for a one-gene sweep).
+    pub fn rows(&self) -> usize {
+        if self.y.is_some() {
+            self.resolution
+        } else {
+            1
+        }
+    }
+}
+
+/// Growth per grid point of a finished probe, row-major (y rows of x).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FitnessLandscape {
+    pub settings: ProbeSettings,
+    pub base_genome: [f32; 4], // the clone's own genome
+    pub initial_mass: f32,
+    pub growth: Vec<f32>,
+}
+
+impl FitnessLandscape {
+    pub fn at(&self, kx: usize, ky: usize) -> f32 {
+        self.growth[ky * self.settings.resolution + kx]
+    }
+
+    /// Grid point with the highest growth, and that growth.
+    pub fn best(&self) -> Option<((usize, usize), f32)> {
+        let n = self.settings.resolution;
+        self.growth
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, &g)| ((i % n, i / n), g))
+    }
+
+    /// Swept gene values of grid point (`kx`, `ky`), e.g. "μ=0.150 σ=0.030".
+    pub fn describe(&self, (kx, ky): (usize, usize)) -> String {
+        let genome = self.settings.genome_at(self.base_genome, kx, ky);
+        let axes = std::iter::once(self.settings.x).chain(self.settings.y);
+        axes.map(|axis| format!("{}={:.3}", tr(axis.label_key()), genome[axis.gene])).collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn max_growth(&self) -> f32 {
+        self.growth.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// Step one micro-world per grid point, spread over the available cores.
+/// `progress` gets the number of grid points done; stops early (None) when
+/// `cancel` is set.
+pub fn sweep(
+    world: &MicroWorld,
+    settings: &ProbeSettings,
+    cancel: &AtomicBool,
+    progress: &(dyn Fn(usize) + Sync),
+) -> Option<FitnessLandscape> {
+    let n = settings.resolution.clamp(1, MAX_RESOLUTION);
+    let settings = ProbeSettings { resolution: n, ..*settings };
+    let points = n * settings.rows();
+    let mut growth = vec![0.0; points];
+    let done = AtomicUsize::new(0);
+    let threads = std::thread::available_parallelism().map_or(1, |t| t.get()).min(points);
+    let chunk = points.div_ceil(threads);
+    std::thread::scope(|scope| {
+        for (c, out) in growth.chunks_mut(chunk).enumerate() {
+            let done = &done;
+            scope.spawn(move || {
+                for (k, g) in out.iter_mut().enumerate() {
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let i = c * chunk + k;
+                    *g = world.growth(settings.genome_at(world.genome, i % n, i / n), settings.steps);
+                    progress(done.fetch_add(1, Ordering::Relaxed) + 1);
+                }
+            });
+        }
+    });
+    (!cancel.load(Ordering::Relaxed)).then(|| FitnessLandscape {
+        settings,
+        base_genome: world.genome,
+        initial_mass: world.total_mass(),
+        growth,
+    })
+}
+
+// ======================== Probe State ========================
+
+enum ProbeUpdate {
+    Progress(f32),
+    Finished(Option<FitnessLandscape>), // None if cancelled
+}
+
+struct ProbeWorker {
+    updates: Receiver<ProbeUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// The probe tool of the Lab: its settings, the sweep in flight and the last
+/// landscape.
+#[derive(Default)]
+pub struct LandscapeProbe {
+    pub settings: ProbeSettings,
+    pub pick: bool,      // next click on the world sets the center
+    pub requested: bool, // clone the world at `settings.center` and sweep
+    pub progress: Option<f32>,
+    pub result: Option<FitnessLandscape>,
+    worker: Option<ProbeWorker>,
+}
+
+impl LandscapeProbe {
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Sweep `world` on a background thread.
+    pub fn start(&mut self, world: MicroWorld) {
+        let settings = self.settings;
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        std::thread::spawn(move || {
+            let total = (settings.resolution.clamp(1, MAX_RESOLUTION) * settings.rows()) as f32;
+            let progress_tx = tx.clone();
+            let report = move |done: usize| {
+                let _ = progress_tx.send(ProbeUpdate::Progress(done as f32 / total));
+            };
+            let result = sweep(&world, &settings, &flag, &report);
+            let _ = tx.send(ProbeUpdate::Finished(result));
+        });
+        self.progress = Some(0.0);
+        self.worker = Some(ProbeWorker { updates: rx, cancel });
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Apply the worker's updates. Returns the landscape of a sweep that just
+    /// completed (Some(None) if it was cancelled).
+    pub fn poll(&mut self) -> Option<Option<&FitnessLandscape>> {
+        let updates: Vec<ProbeUpdate> = self.worker.as_ref()?.updates.try_iter().collect();
+        let mut finished = None;
+        for update in updates {
+            match update {
+                ProbeUpdate::Progress(fraction) => self.progress = Some(fraction),
+                ProbeUpdate::Finished(result) => finished = Some(result),
+            }
+        }
+        let result = finished?;
+        self.worker = None;
+        self.progress = None;
+        let completed = result.is_some();
+        if completed {
+            self.result = result;
+        }
+        Some(self.result.as_ref().filter(|_| completed))
+    }
+}
@@ -0,0 +1,251 @@
+// ============================================================================
+// legend.rs — EvoLenia v2 Research Lab
+// Legend of the active visualization mode: a color scale with numeric ticks
+// for continuous modes (mass density with the display adjustment inverted,
+// energy, aggressivity, flow direction), swatches for categorical ones
+// (genome channels, trophic roles, species map ids). The lab UI draws it as
+// an overlay; screenshots are taken before the UI is painted, so when asked
+// the legend is composited into the captured frame on the CPU instead.
+// ============================================================================
+
+use ab_glyph::FontRef;
+
+use crate::config::{visualization_mode_key, DisplayAdjust, DisplayTransform, SimulationParams, VIS_MODE_COUNT};
+use crate::i18n::{tr, trf};
+use crate::plot_export::{format_tick, text_mask, FONT_NAME};
+use crate::territory::species_color;
+use crate::theme::PaletteUniforms;
+
+pub type Rgb = [u8; 3];
+
+/// Ticks on a color scale.
+const SCALE_TICKS: usize = 5;
+/// Species listed at most.
+const MAX_SWATCHES: usize = 8;
+/// log1p strength of the Log display transform (LOG_STRENGTH in render.wgsl).
+const LOG_STRENGTH: f32 = 100.0;
+/// Energy at which Metabolic Stress shows a cell fully healthy (render.wgsl).
+const STRESS_ENERGY: f32 = 0.3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModeLegend {
+    /// Colors evenly spaced along the scale; ticks at positions in [0, 1].
+    Scale { title: String, stops: Vec<Rgb>, ticks: Vec<(f32, String)> },
+    Swatches { title: String, entries: Vec<(Rgb, String)> },
+}
+
+impl ModeLegend {
+    pub fn title(&self) -> &str {
+        match self {
+            ModeLegend::Scale { title, .. } | ModeLegend::Swatches { title, .. } => title,
+        }
+    }
+}
+
+/// Scale color at `t` in [0, 1], linear between the stops.
+pub fn scale_color(stops: &[Rgb], t: f32) -> Rgb {
+    let Some(&last) = stops.last() else {
+        return [0; 3];
+    };
+    let x = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = (x.floor() as usize).min(stops.len() - 1);
+    let (a, b, f) = (stops[i], stops.get(i + 1).copied().unwrap_or(last), x - i as f32);
+    std::array::from_fn(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f).round() as u8)
+}
+
+fn rgb(c: [f32; 4]) -> Rgb {
+    std::array::from_fn(|i| (c[i].clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// Field value shown at display intensity `d` (render.wgsl display_adjust
+/// inverted), None under histogram equalization, which has no closed form.
+pub fn field_at(d: f32, adjust: &DisplayAdjust) -> Option<f32> {
+    let t = d.clamp(0.0, 1.0).powf(adjust.gamma.max(0.01));
+    let t = match adjust.transform {
+        DisplayTransform::Linear => t,
+        DisplayTransform::Log => ((1.0 + LOG_STRENGTH).powf(t) - 1.0) / LOG_STRENGTH,
+        DisplayTransform::Equalize => return None,
+    };
+    let span = (adjust.range_max - adjust.range_min).max(1e-6);
+    Some((adjust.range_min + t * span) / adjust.exposure.max(1e-6))
+}
+
+/// Evenly spaced ticks labelled with `value(position)`.
+fn ticks(value: impl Fn(f32) -> f32) -> Vec<(f32, String)> {
+    let positions: Vec<f32> = (0..SCALE_TICKS).map(|i| i as f32 / (SCALE_TICKS - 1) as f32).collect();
+    let values: Vec<f32> = positions.iter().map(|&p| value(p)).collect();
+    let step = values.windows(2).map(|w| (w[1] - w[0]).abs()).filter(|s| *s > 0.0).fold(f32::INFINITY, f32::min);
+    let step = if step.is_finite() { step as f64 / 10.0 } else { 1.0 };
+    positions.into_iter().zip(values).map(|(p, v)| (p, format_tick(v as f64, step))).collect()
+}
+
+/// Legend of the active mode; None for user modes. `areas` are the species
+/// holding the most ground, largest first, and `species` the clusters known.
+pub fn legend_for(
+    params: &SimulationParams,
+    palette: &PaletteUniforms,
+    areas: &[(u16, usize)],
+    species: usize,
+) -> Option<ModeLegend> {
+    let mode = params.visualization_mode;
+    if mode >= VIS_MODE_COUNT {
+        return None;
+    }
+    let title = tr(visualization_mode_key(mode)).to_string();
+    let adjust = params.current_display_adjust();
+    let unit_ticks = || ticks(|p| p);
+    Some(match mode {
+        0 if params.species_map.enabled && params.species_map.colors => {
+            let ids: Vec<u16> = if areas.is_empty() {
+                (0..species.min(MAX_SWATCHES) as u16).collect()
+            } else {
+                areas.iter().take(MAX_SWATCHES).map(|&(id, _)| id).collect()
+            };
+            let entries = ids
+                .into_iter()
+                .map(|id| (species_color(id, &palette.cyclic), trf("legend.species", &[("id", &id)])))
+                .collect();
+            ModeLegend::Swatches { title, entries }
+        }
+        0 => ModeLegend::Swatches {
+            title,
+            entries: vec![
+                (rgb(palette.species[0]), tr("legend.radius").to_string()),
+                (rgb(palette.species[1]), tr("legend.mu").to_string()),
+                (rgb(palette.species[2]), tr("legend.sigma").to_string()),
+                (rgb(palette.predator_glow), tr("legend.predator_glow").to_string()),
+            ],
+        },
+        1 => ModeLegend::Scale {
+            title: trf("legend.colored_by", &[("mode", &title), ("field", &tr("legend.energy"))]),
+            stops: vec![rgb(palette.heat_low), rgb(palette.heat_high)],
+            ticks: unit_ticks(),
+        },
+        2 => ModeLegend::Scale {
+            title: trf("legend.colored_by", &[("mode", &title), ("field", &tr("legend.mass"))]),
+            stops: vec![[0; 3], [255; 3]],
+            ticks: match field_at(0.0, adjust) {
+                Some(_) => ticks(|p| field_at(p, adjust).unwrap_or(0.0)),
+                None => (0..SCALE_TICKS)
+                    .map(|i| {
+                        let p = i as f32 / (SCALE_TICKS - 1) as f32;
+                        (p, trf("legend.percentile", &[("p", &((p * 100.0).round() as u32))]))
+                    })
+                    .collect(),
+            },
+        },
+        3 => ModeLegend::Scale {
+            title: trf("legend.colored_by", &[("mode", &title), ("field", &tr("legend.genome_hash"))]),
+            stops: palette.cyclic.iter().chain(&palette.cyclic[..1]).map(|&c| rgb(c)).collect(),
+            ticks: Vec::new(),
+        },
+        4 => ModeLegend::Scale {
+            title: trf("legend.colored_by", &[("mode", &title), ("field", &tr("legend.aggressivity"))]),
+            stops: vec![rgb(palette.prey), rgb(palette.predator)],
+            ticks: unit_ticks(),
+        },
+        5 => ModeLegend::Scale {
+            title: trf("legend.colored_by", &[("mode", &title), ("field", &tr("legend.energy"))]),
+            stops: vec![rgb(palette.healthy), rgb(palette.starving)],
+            ticks: ticks(|p| STRESS_ENERGY * (1.0 - p)),
+        },
+        6 => ModeLegend::Scale {
+            title: trf("legend.colored_by", &[("mode", &title), ("field", &tr("legend.direction"))]),
+            stops: palette.cyclic.iter().chain(&palette.cyclic[..1]).map(|&c| rgb(c)).collect(),
+            ticks: ticks(|p| p * 360.0 - 180.0).into_iter().map(|(p, v)| (p, format!("{v}°"))).collect(),
+        },
+        _ => ModeLegend::Swatches {
+            title,
+            entries: vec![
+                (rgb(palette.trophic[0]), tr("legend.prey").to_string()),
+                (rgb(mix(palette.trophic[1], palette.trophic[2], 0.5)), tr("legend.opportunist").to_string()),
+                (rgb(mix(palette.trophic[3], palette.trophic[4], 0.5)), tr("legend.predator").to_string()),
+            ],
+        },
+    })
+}
+
+// ======================== Screenshot compositing ========================
+
+/// Blend `color` at `alpha` into the RGBA8 pixel at (x, y), if inside.
+fn blend(rgba: &mut [u8], width: u32, height: u32, x: i32, y: i32, color: Rgb, alpha: f32) {
+    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+        return;
+    }
+    let i = (y as usize * width as usize + x as usize) * 4;
+    for c in 0..3 {
+        rgba[i + c] = (rgba[i + c] as f32 + (color[c] as f32 - rgba[i + c] as f32) * alpha).round() as u8;
+    }
+}
+
+fn fill_rect(rgba: &mut [u8], width: u32, height: u32, min: [i32; 2], size: [i32; 2], color: Rgb, alpha: f32) {
+    for y in min[1]..min[1] + size[1] {
+        for x in min[0]..min[0] + size[0] {
+            blend(rgba, width, height, x, y, color, alpha);
+        }
+    }
+}
+
+/// Draw `text` in white with its top-left corner at `pos`; returns its width.
+fn draw_text(rgba: &mut [u8], width: u32, height: u32, font: &FontRef, pos: [i32; 2], text: &str, size: f32) -> i32 {
+    let (w, h, mask) = text_mask(font, text, size);
+    for my in 0..h {
+        for mx in 0..w {
+            let c = mask[my * w + mx];
+            if c > 0.0 {
+                blend(rgba, width, height, pos[0] + mx as i32, pos[1] + my as i32, [255; 3], c);
+            }
+        }
+    }
+    w as i32
+}
+
+/// Composite the legend into the bottom-left corner of an RGBA8 frame,
+/// sized with the frame height.
+pub fn composite(rgba: &mut [u8], width: u32, height: u32, legend: &ModeLegend) -> Result<(), String> {
+    if rgba.len() != width as usize * height as usize * 4 {
+        return Err(format!("Frame of {} bytes is not {}×{} RGBA", rgba.len(), width, height));
+    }
+    let fonts = egui::FontDefinitions::default();
+    let data = fonts.font_data.get(FONT_NAME).ok_or_else(|| format!("Font {} unavailable", FONT_NAME))?;
+    let font = FontRef::try_from_slice_and_index(&data.font, data.index).map_err(|e| e.to_string())?;
+
+    let s = (height as f32 / 720.0).max(1.0);
+    let px = |v: f32| (v * s).round() as i32;
+    let (text, line, pad) = (13.0 * s, px(18.0), px(8.0));
+    let (inner_w, inner_h) = match legend {
+        ModeLegend::Scale { ticks, .. } => (px(220.0), line + px(12.0) + if ticks.is_empty() { 0 } else { line }),
+        ModeLegend::Swatches { entries, .. } => (px(220.0), line * (entries.len() as i32 + 1)),
+    };
+    let origin = [px(12.0), height as i32 - px(12.0) - inner_h - 2 * pad];
+    fill_rect(rgba, width, height, origin, [inner_w + 2 * pad, inner_h + 2 * pad], [0; 3], 0.6);
+    let (x0, mut y) = (origin[0] + pad, origin[1] + pad);
+    draw_text(rgba, width, height, &font, [x0, y], legend.title(), text);
+    y += line;
+    match legend {
+        ModeLegend::Scale { stops, ticks, .. } => {
+            for x in 0..inner_w {
+                let color = scale_color(stops, x as f32 / (inner_w - 1).max(1) as f32);
+                fill_rect(rgba, width, height, [x0 + x, y], [1, px(12.0)], color, 1.0);
+            }
+            y += px(12.0) + px(2.0);
+            for (p, label) in ticks {
+                let (w, _, _) = text_mask(&font, label, text);
+                let x = x0 + (p * inner_w as f32) as i32 - w as i32 / 2;
+                draw_text(rgba, width, height, &font, [x.clamp(x0 - pad, x0 + inner_w + pad - w as i32), y], label, text);
+            }
+        }
+        ModeLegend::Swatches { entries, .. } => {
+            for (color, label) in entries {
+                fill_rect(rgba, width, height, [x0, y + px(3.0)], [px(12.0), px(12.0)], *color, 1.0);
+                draw_text(rgba, width, height, &font, [x0 + px(18.0), y], label, text);
+                y += line;
+            }
+        }
+    }
+    Ok(())
+}
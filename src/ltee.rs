@@ -0,0 +1,195 @@
+// ============================================================================
+// ltee.rs — EvoLenia v2 Research Lab
+// Long-term evolution experiment (LTEE) mode, after serial-transfer
+// experiments: every K frames a sample of the population's genomes is
+// frozen to an archive file in the run directory, and the population can go
+// through a transfer bottleneck (only a random fraction of the living cells
+// survive) before the run continues. Archived samples can be spawned back
+// into a world later, e.g. to let an ancestor compete with its descendants
+// (a "frozen fossil record" competition).
+// ============================================================================
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::world::BufferSnapshot;
+
+/// Archive directory inside a run directory.
+pub const ARCHIVE_DIR: &str = "ltee";
+/// Version of the archive file format.
+pub const FOSSIL_SCHEMA_VERSION: u32 = 1;
+
+/// Cells lighter than this are not alive (never sampled, never kept).
+const LIVING_MASS: f32 = 0.01;
+/// Mass of a cell spawned from an archived sample.
+const SPAWN_MASS: f32 = 0.5;
+/// Energy of a spawned cell (the initial world energy).
+const SPAWN_ENERGY: f32 = 0.5;
+
+/// One frozen organism: a living cell's genome.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FossilCell {
+    pub genome: [f32; 4], // r, mu, sigma, aggressivity
+    pub mutation_rate: f32,
+    pub mass: f32,
+}
+
+/// The genomes frozen at one transfer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FossilSample {
+    pub version: u32,
+    pub run_id: String,
+    pub frame: u32,
+    pub transfer: u32, // 1 for the first transfer of the run
+    pub living_cells: usize,
+    pub cells: Vec<FossilCell>,
+}
+
+impl FossilSample {
+    /// Freeze `size` living cells of `snap`, drawn with replacement in
+    /// proportion to their mass. None when nothing is alive.
+    pub fn freeze(snap: &BufferSnapshot, size: usize, rng: &mut impl Rng) -> Option<Self> {
+        let living: Vec<usize> = (0..snap.mass.len()).filter(|&i| snap.mass[i] >= LIVING_MASS).collect();
+        let weights = WeightedIndex::new(living.iter().map(|&i| snap.mass[i])).ok()?;
+        let cells = (0..size)
+            .map(|_| {
+                let i = living[weights.sample(rng)];
+                let mut genome = [0.0; 4];
+                genome.copy_from_slice(&snap.genome_a[i * 4..i * 4 + 4]);
+                FossilCell { genome, mutation_rate: snap.genome_b[i], mass: snap.mass[i] }
+            })
+            .collect();
+        Some(Self { version: FOSSIL_SCHEMA_VERSION, run_id: String::new(), frame: 0, transfer: 0, living_cells: living.len(), cells })
+    }
+
+    /// Mean genome of the sample.
+    pub fn mean_genome(&self) -> [f32; 4] {
+        let n = self.cells.len().max(1) as f32;
+        let mut mean = [0.0; 4];
+        for cell in &self.cells {
+            for (m, g) in mean.iter_mut().zip(cell.genome) {
+                *m += g / n;
+            }
+        }
+        mean
+    }
+}
+
+/// Transfer bottleneck: keep each living cell of `snap` with probability
+/// `keep`, clear the others. Returns the number of cells kept and removed.
+pub fn dilute(snap: &mut BufferSnapshot, keep: f32, rng: &mut impl Rng) -> (usize, usize) {
+    let (mut kept, mut removed) = (0, 0);
+    for i in 0..snap.mass.len() {
+        if snap.mass[i] < LIVING_MASS {
+            continue;
+        }
+        if rng.gen::<f32>() < keep {
+            kept += 1;
+        } else {
+            snap.mass[i] = 0.0;
+            snap.energy[i] = 0.0;
+            removed += 1;
+        }
+    }
+    (kept, removed)
+}
+
+/// Seed a disc of `radius` cells around `center` (pixels, wrapping) with
+/// genomes drawn from `sample`. Returns the number of cells spawned.
+pub fn spawn_sample(snap: &mut BufferSnapshot, sample: &FossilSample, center: [u32; 2], radius: u32, rng: &mut impl Rng) -> usize {
+    if sample.cells.is_empty() {
+        return 0;
+    }
+    let r = radius as i64;
+    let mut spawned = 0;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            if dx * dx + dy * dy > r * r {
+                continue;
+            }
+            let x = (center[0] as i64 + dx).rem_euclid(snap.size[0] as i64) as usize;
+            let y = (center[1] as i64 + dy).rem_euclid(snap.size[1] as i64) as usize;
+            let i = y * snap.size[0] as usize + x;
+            let cell = &sample.cells[rng.gen_range(0..sample.cells.len())];
+            snap.mass[i] = SPAWN_MASS;
+            snap.energy[i] = SPAWN_ENERGY;
+            snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&cell.genome);
+            snap.genome_b[i] = cell.mutation_rate;
+            spawned += 1;
+        }
+    }
+    spawned
+}
+
+/// Sampling stream of the transfer at `frame` (reproducible per frame).
+pub fn transfer_rng(frame: u32) -> StdRng {
+    StdRng::seed_from_u64(frame as u64)
+}
+
+// ======================== Archive ========================
+
+/// Archive file of transfer `transfer` at `frame`, e.g.
+/// `<run>/ltee/transfer_003_f15000.json`.
+pub fn sample_path(run_dir: &Path, transfer: u32, frame: u32) -> PathBuf {
+    run_dir.join(ARCHIVE_DIR).join(format!("transfer_{transfer:03}_f{frame}.json"))
+}
+
+pub fn save_sample(path: &Path, sample: &FossilSample) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let json = serde_json::to_string(sample).map_err(|e| format!("Failed to serialize fossil sample: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+pub fn load_sample(path: &Path) -> Result<FossilSample, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let sample: FossilSample = serde_json::from_str(&json).map_err(|e| format!("Invalid fossil sample {:?}: {}", path, e))?;
+    if sample.version > FOSSIL_SCHEMA_VERSION {
+        return Err(format!("{:?} was written by a newer version (schema {})", path, sample.version));
+    }
+    Ok(sample)
+}
+
+/// Archived samples of a run, oldest transfer first.
+pub fn list_samples(run_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(run_dir.join(ARCHIVE_DIR)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+// ======================== Lab State ========================
+
+/// LTEE bookkeeping of the Lab: the run's archive and the fossil spawner.
+pub struct FossilRecord {
+    pub archive: Vec<PathBuf>,    // samples of the current run (list_samples)
+    pub selected: Option<PathBuf>, // sample to spawn
+    pub spawn_center: [f32; 2],   // world UV
+    pub spawn_radius: u32,
+    pub spawn_pick: bool, // next click on the world sets the spawn center
+    pub spawn_requested: bool,
+}
+
+impl Default for FossilRecord {
+    fn default() -> Self {
+        Self {
+            archive: Vec::new(),
+            selected: None,
+            spawn_center: [0.5, 0.5],
+            spawn_radius: 12,
+            spawn_pick: false,
+            spawn_requested: false,
+        }
+    }
+}
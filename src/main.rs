@@ -3,41 +3,146 @@
 // Entry point. Initializes logging and starts the event loop.
 // ============================================================================
 
+mod alerts;
+mod analysis_window;
 mod app;
+mod arena;
+mod averages;
 mod camera;
+mod capture;
+mod channels;
+mod condition_stats;
+mod conditions;
 mod config;
+mod crash;
+mod creature;
+mod display;
+mod dock;
+mod expr;
+mod file_dialog;
+mod flow;
+mod forensics;
+mod gpu_debug;
 mod headless;
+mod histogram;
+mod i18n;
 mod input;
+mod invasion;
+mod kernel;
 mod lab;
 mod lab_ui;
+mod landscape;
+mod legend;
+mod ltee;
+mod metadata;
+mod metric_plugins;
 mod metrics;
+mod monitor;
+mod notify;
+mod pacing;
+mod param_lock;
+mod particles;
+mod pattern;
+mod perturbation;
 mod pipeline;
+mod plot_export;
+mod popgen;
+mod postfx;
+mod preset_diff;
+mod profile;
+mod queue;
 mod renderer;
+mod replay;
+mod report;
+mod reserves;
+mod resize;
+mod resources;
+mod schedule;
+mod session_log;
+mod settings;
+mod speciation;
+mod startup;
 mod state_io;
+mod stream;
+mod tape;
+mod tensorboard;
+mod territory;
+mod theme;
+mod thumbnail;
+mod transplant;
+mod user_modes;
+mod vectors;
+mod viewer;
 mod world;
 
 #[cfg(test)]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::manual_is_multiple_of,
+    clippy::manual_range_contains,
+    clippy::same_item_push
+)]
 mod tests;
 
 use app::{App, AppConfig};
-use headless::{run_headless, HeadlessConfig};
+use headless::{bench_report, run_bench, run_headless, BenchConfig, HeadlessConfig, ProgressMode};
 use winit::event_loop::EventLoop;
+use world::{check_world_size, parse_world_size, CellLayout, GridType, DEFAULT_WORLD_SIZE};
 
 fn main() {
-    env_logger::init();
+    session_log::init();
+    crash::install();
 
     let cli = CliOptions::from_args(std::env::args().collect());
 
+    if cli.bench {
+        match run_bench(&BenchConfig { frames: cli.frames, layouts: cli.layouts, world_size: cli.world_size }) {
+            Ok(results) => println!("{}", bench_report(&results, cli.world_size)),
+            Err(err) => {
+                eprintln!("Benchmark failed: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(addr) = &cli.view_addr {
+        if let Err(err) = viewer::run_viewer(addr) {
+            eprintln!("Viewer failed: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     if cli.headless || cli.headless_then_gui {
         let headless_cfg = HeadlessConfig {
             frames: cli.frames,
             load_state_path: cli.load_state_path.clone(),
             save_state_path: Some(cli.save_state_path.clone()),
             progress_interval: cli.progress_interval,
+            progress_mode: cli.progress_mode,
+            tensorboard_dir: cli.tensorboard_dir.clone(),
+            metrics_addr: cli.metrics_addr.clone(),
+            diag_interval: cli.diag_interval,
+            population_path: cli.population_path.clone(),
+            population_frame: cli.population_frame,
+            grid: cli.grid,
+            world_size: cli.world_size,
+            stream_addr: cli.stream_addr.clone(),
+            stream_interval: cli.stream_interval,
+            stream_width: cli.stream_width,
+            record_path: cli.record_path.clone(),
+            record_interval: cli.record_interval,
+            record_width: cli.record_width,
         };
-        if let Err(err) = run_headless(&headless_cfg) {
-            eprintln!("Headless run failed: {err}");
-            std::process::exit(1);
+        match run_headless(&headless_cfg) {
+            // Interrupted runs are saved; exit with the usual SIGINT status
+            Ok(outcome) if outcome.interrupted => std::process::exit(130),
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Headless run failed: {err}");
+                std::process::exit(1);
+            }
         }
         if !cli.headless_then_gui {
             return;
@@ -54,6 +159,9 @@ fn main() {
             cli.load_state_path
         },
         diag_interval: cli.diag_interval,
+        grid: cli.grid,
+        world_size: cli.world_size,
+        gpu_validation: cli.gpu_validation,
     });
     event_loop.run_app(&mut app).unwrap();
 }
@@ -67,6 +175,23 @@ struct CliOptions {
     save_state_path: String,
     diag_interval: u32,
     progress_interval: u32,
+    progress_mode: ProgressMode,
+    tensorboard_dir: Option<String>, // headless TensorBoard log directory (`--tensorboard`)
+    metrics_addr: Option<String>,    // headless Prometheus endpoint address (`--metrics-addr`)
+    population_path: Option<String>, // headless live-cell genome table (`--population`)
+    population_frame: Option<u32>,   // frame of that export (`--population-frame`), default end of run
+    bench: bool,              // `bench` subcommand: time the simulation per cell layout
+    layouts: Vec<CellLayout>, // layouts to benchmark (`--layout`)
+    world_size: [u32; 2],     // world width × height in cells (`--world`)
+    grid: GridType,           // lattice of the initial world (`--grid`)
+    stream_addr: Option<String>, // headless frame stream address (`--stream-addr`)
+    stream_interval: u32,        // steps between streamed frames (`--stream-interval`)
+    stream_width: u32,           // streamed frame width (`--stream-width`)
+    record_path: Option<String>, // headless PNG sequence directory or video file (`--record`)
+    record_interval: u32,        // steps between recorded frames (`--record-interval`)
+    record_width: u32,           // recorded frame width (`--record-width`)
+    view_addr: Option<String>,   // `view <host:port>` subcommand: watch a streamed run
+    gpu_validation: bool,        // wgpu validation and error scopes (`--gpu-validation`)
 }
 
 impl Default for CliOptions {
@@ -79,6 +204,23 @@ impl Default for CliOptions {
             save_state_path: String::from("/tmp/evolenia_final.snap"),
             diag_interval: 300,
             progress_interval: 1000,
+            progress_mode: ProgressMode::Auto,
+            tensorboard_dir: None,
+            metrics_addr: None,
+            population_path: None,
+            population_frame: None,
+            bench: false,
+            layouts: CellLayout::all().to_vec(),
+            world_size: DEFAULT_WORLD_SIZE,
+            grid: GridType::Square,
+            stream_addr: None,
+            stream_interval: 30,
+            stream_width: 256,
+            record_path: None,
+            record_interval: 30,
+            record_width: 512,
+            view_addr: None,
+            gpu_validation: false,
         }
     }
 }
@@ -89,48 +231,120 @@ impl CliOptions {
         let mut i = 1usize;
         while i < args.len() {
             match args[i].as_str() {
+                "bench" if i == 1 => options.bench = true,
+                "view" if i == 1 && i + 1 < args.len() => {
+                    options.view_addr = Some(args[i + 1].clone());
+                    i += 1;
+                }
                 "--headless" => options.headless = true,
                 "--headless-then-gui" => options.headless_then_gui = true,
-                "--frames" => {
-                    if i + 1 < args.len() {
-                        if let Ok(v) = args[i + 1].parse::<u32>() {
-                            options.frames = v.max(1);
-                        }
-                        i += 1;
+                "--gpu-validation" => options.gpu_validation = true,
+                "--frames" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.frames = v.max(1);
+                    }
+                    i += 1;
+                }
+                "--load" if i + 1 < args.len() => {
+                    options.load_state_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--save" if i + 1 < args.len() => {
+                    options.save_state_path = args[i + 1].clone();
+                    i += 1;
+                }
+                "--diag-interval" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.diag_interval = v.max(1);
                     }
+                    i += 1;
                 }
-                "--load" => {
-                    if i + 1 < args.len() {
-                        options.load_state_path = Some(args[i + 1].clone());
-                        i += 1;
+                "--tensorboard" if i + 1 < args.len() => {
+                    options.tensorboard_dir = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--metrics-addr" if i + 1 < args.len() => {
+                    options.metrics_addr = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--stream-addr" if i + 1 < args.len() => {
+                    options.stream_addr = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--stream-interval" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.stream_interval = v.max(1);
+                    }
+                    i += 1;
+                }
+                "--stream-width" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.stream_width = v.max(1);
                     }
+                    i += 1;
+                }
+                "--record" if i + 1 < args.len() => {
+                    options.record_path = Some(args[i + 1].clone());
+                    i += 1;
                 }
-                "--save" => {
-                    if i + 1 < args.len() {
-                        options.save_state_path = args[i + 1].clone();
-                        i += 1;
+                "--record-interval" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.record_interval = v.max(1);
                     }
+                    i += 1;
                 }
-                "--diag-interval" => {
-                    if i + 1 < args.len() {
-                        if let Ok(v) = args[i + 1].parse::<u32>() {
-                            options.diag_interval = v.max(1);
-                        }
-                        i += 1;
+                "--record-width" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.record_width = v.max(1);
                     }
+                    i += 1;
+                }
+                "--population" if i + 1 < args.len() => {
+                    options.population_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--population-frame" if i + 1 < args.len() => {
+                    options.population_frame = args[i + 1].parse::<u32>().ok();
+                    i += 1;
                 }
-                "--progress-interval" => {
-                    if i + 1 < args.len() {
-                        if let Ok(v) = args[i + 1].parse::<u32>() {
-                            options.progress_interval = v.max(1);
-                        }
-                        i += 1;
+                "--layout" if i + 1 < args.len() => {
+                    if let Some(layout) = CellLayout::parse(&args[i + 1]) {
+                        options.layouts = vec![layout];
                     }
+                    i += 1;
+                }
+                "--grid" if i + 1 < args.len() => {
+                    if let Some(grid) = GridType::parse(&args[i + 1]) {
+                        options.grid = grid;
+                    }
+                    i += 1;
+                }
+                "--world" if i + 1 < args.len() => {
+                    match parse_world_size(&args[i + 1]).map(|size| check_world_size(size).map(|_| size)) {
+                        Some(Ok(size)) => options.world_size = size,
+                        Some(Err(e)) => eprintln!("Ignoring --world {}: {}", args[i + 1], e),
+                        None => eprintln!("Ignoring --world {}: expected WIDTHxHEIGHT", args[i + 1]),
+                    }
+                    i += 1;
+                }
+                "--quiet" => options.progress_mode = ProgressMode::Quiet,
+                "--json-progress" => options.progress_mode = ProgressMode::Json,
+                "--progress-interval" if i + 1 < args.len() => {
+                    if let Ok(v) = args[i + 1].parse::<u32>() {
+                        options.progress_interval = v.max(1);
+                    }
+                    i += 1;
                 }
                 _ => {}
             }
             i += 1;
         }
+        // Layouts and grids depend on the world size, which may come last
+        let [width, height] = options.world_size;
+        options.layouts.retain(|l| l.supported(width, height));
+        if !options.grid.supported(width, height) {
+            options.grid = GridType::Square;
+        }
         options
     }
 }
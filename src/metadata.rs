@@ -0,0 +1,85 @@
+// ============================================================================
+// metadata.rs — EvoLenia v2 Research Lab
+// Optional experiment metadata (author, description, tags, license) entered
+// in the UI. It is embedded in each run's config.json and report.md, and
+// written as <run>/.zenodo.json in the format Zenodo reads when a run
+// directory is deposited for a DOI.
+// ============================================================================
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Zenodo deposit metadata file inside the run directory.
+pub const ZENODO_FILE: &str = ".zenodo.json";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExperimentMetadata {
+    pub author: String, // "Family, Given"; several separated by ';'
+    pub description: String,
+    pub tags: String,    // comma-separated, as typed
+    pub license: String, // SPDX identifier, e.g. "CC-BY-4.0"
+}
+
+impl ExperimentMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.authors().is_empty()
+            && self.tag_list().is_empty()
+            && self.description.trim().is_empty()
+            && self.license.trim().is_empty()
+    }
+
+    /// Authors, trimmed, empty entries dropped.
+    pub fn authors(&self) -> Vec<String> {
+        split_list(&self.author, ';')
+    }
+
+    /// Tags, trimmed, empty entries dropped.
+    pub fn tag_list(&self) -> Vec<String> {
+        split_list(&self.tags, ',')
+    }
+
+    /// The `metadata` object of config.json.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "authors": self.authors(),
+            "description": self.description.trim(),
+            "tags": self.tag_list(),
+            "license": self.license.trim(),
+        })
+    }
+
+    /// Zenodo deposit metadata for a run (`title` is the run id).
+    pub fn zenodo_json(&self, title: &str) -> serde_json::Value {
+        let mut zenodo = serde_json::json!({
+            "title": title,
+            "upload_type": "dataset",
+            "creators": self.authors().into_iter().map(|name| serde_json::json!({ "name": name })).collect::<Vec<_>>(),
+            "keywords": self.tag_list(),
+        });
+        if !self.description.trim().is_empty() {
+            zenodo["description"] = self.description.trim().into();
+        }
+        if !self.license.trim().is_empty() {
+            zenodo["license"] = self.license.trim().into();
+        }
+        zenodo
+    }
+
+    /// Write `<run_dir>/.zenodo.json`; nothing when no field is filled in.
+    pub fn save_zenodo(&self, run_dir: &Path, title: &str) -> Result<(), String> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let path = run_dir.join(ZENODO_FILE);
+        let json = serde_json::to_string_pretty(&self.zenodo_json(title))
+            .map_err(|e| format!("Failed to serialize {:?}: {}", path, e))?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+    }
+}
+
+fn split_list(text: &str, separator: char) -> Vec<String> {
+    text.split(separator).map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
+}
@@ -0,0 +1,103 @@
+// ============================================================================
+// metric_plugins.rs — EvoLenia v2 Research Lab
+// Metric providers: metrics computed from a readback snapshot by independent
+// modules. Each provider in PROVIDERS names its metrics and computes them;
+// the values ride along in `MetricsRecord::plugins` and appear next to the
+// built-in fields everywhere: metrics.csv columns, custom metric and alert
+// rule fields, analysis and report plots, the report's final metrics and
+// `plugin/<name>` TensorBoard scalars. A new metric is a provider added to
+// PROVIDERS, not a new record field and an edit to every serializer.
+// ============================================================================
+
+use crate::world::BufferSnapshot;
+
+/// A source of metrics computed from the world state.
+pub trait MetricProvider: Sync {
+    /// Names of the metrics, as `compute` returns them. Fixed for a
+    /// provider: they are the metrics.csv column headers.
+    fn names(&self) -> &'static [&'static str];
+
+    /// Metric values for one readback.
+    fn compute(&self, snapshot: &BufferSnapshot) -> Vec<(&'static str, f64)>;
+}
+
+/// Registered providers; their metrics follow the built-in fields in this order.
+pub static PROVIDERS: &[&dyn MetricProvider] = &[&PatchMetrics];
+
+/// Names of every provider metric, in record order.
+pub fn plugin_fields() -> Vec<&'static str> {
+    PROVIDERS.iter().flat_map(|p| p.names().iter().copied()).collect()
+}
+
+/// Every provider metric for `snapshot`, in `plugin_fields` order (NaN for
+/// a name its provider did not return).
+pub fn compute_plugins(snapshot: &BufferSnapshot) -> Vec<f64> {
+    PROVIDERS
+        .iter()
+        .flat_map(|p| {
+            let values = p.compute(snapshot);
+            p.names().iter().map(move |name| values.iter().find(|(n, _)| n == name).map_or(f64::NAN, |&(_, v)| v))
+        })
+        .collect()
+}
+
+// ======================== Patches ========================
+
+/// Mass above which a cell counts as part of a patch (as `live_pixels`).
+const PATCH_MASS: f32 = 0.01;
+
+/// Connected live regions (4-neighbour, wrapping at the edges): how many
+/// separate patches the population forms, and how much of the live area
+/// the largest one holds (1 = one connected mat, near 0 = scattered
+/// creatures).
+pub struct PatchMetrics;
+
+impl MetricProvider for PatchMetrics {
+    fn names(&self) -> &'static [&'static str] {
+        &["patch_count", "largest_patch_fraction"]
+    }
+
+    fn compute(&self, snapshot: &BufferSnapshot) -> Vec<(&'static str, f64)> {
+        let sizes = patch_sizes(&snapshot.mass, snapshot.size[0] as usize, snapshot.size[1] as usize);
+        let live: usize = sizes.iter().sum();
+        let largest = sizes.iter().max().copied().unwrap_or(0);
+        vec![
+            ("patch_count", sizes.len() as f64),
+            ("largest_patch_fraction", if live > 0 { largest as f64 / live as f64 } else { 0.0 }),
+        ]
+    }
+}
+
+/// Cell counts of the connected live regions of a row-major `width` ×
+/// `height` toroidal mass field.
+pub fn patch_sizes(mass: &[f32], width: usize, height: usize) -> Vec<usize> {
+    let mut parent: Vec<usize> = (0..mass.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let live = |i: usize| mass[i] > PATCH_MASS;
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            if !live(i) {
+                continue;
+            }
+            for j in [y * width + (x + 1) % width, (y + 1) % height * width + x] {
+                if live(j) {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    parent[a] = b;
+                }
+            }
+        }
+    }
+    let mut sizes = vec![0usize; mass.len()];
+    for i in (0..mass.len()).filter(|&i| live(i)) {
+        sizes[root(&mut parent, i)] += 1;
+    }
+    sizes.retain(|&s| s > 0);
+    sizes
+}
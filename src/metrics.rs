@@ -263,6 +263,85 @@ impl SimDiagnostics {
             self.energy_flux,
         );
     }
+
+    /// Every diagnostic as a named scalar, grouped as "section/name"
+    /// (TensorBoard tags, Prometheus metric names).
+    pub fn named_scalars(&self) -> Vec<(&'static str, f32)> {
+        let genome = &self.genome_stats;
+        vec![
+            ("population/total_mass", self.total_mass),
+            ("population/live_pixels", self.live_pixels as f32),
+            ("population/live_fraction", self.live_fraction),
+            ("population/mass_std_dev", self.mass_std_dev),
+            ("energy/avg_energy", self.avg_energy),
+            ("energy/total_energy", self.total_energy),
+            ("energy/energy_flux", self.energy_flux),
+            ("energy/starving_fraction", self.starving_fraction),
+            ("resources/avg_resource", self.avg_resource),
+            ("resources/depleted_fraction", self.depleted_fraction),
+            ("diversity/genetic_entropy", self.genetic_entropy),
+            ("diversity/species_count", self.species_count as f32),
+            ("diversity/effective_diversity", self.effective_diversity),
+            ("diversity/genome_variance", self.genome_variance),
+            ("trophic/prey_fraction", self.prey_fraction),
+            ("trophic/opportunist_fraction", self.opportunist_fraction),
+            ("trophic/predator_fraction", self.predator_fraction_strict),
+            ("genome/avg_radius", genome.avg_radius),
+            ("genome/avg_mu", genome.avg_mu),
+            ("genome/avg_sigma", genome.avg_sigma),
+            ("genome/avg_aggressivity", genome.avg_aggressivity),
+            ("genome/avg_mutation_rate", genome.avg_mutation_rate),
+        ]
+    }
+}
+
+// ======================== Interaction Matrix ========================
+
+/// Aggressivity classes of the interaction matrix: equal-width bins of
+/// [0, 1] (AGG_CLASSES in compute_evolution.wgsl).
+pub const AGG_CLASSES: usize = 5;
+
+/// Who eats whom between two diagnostics samples: `counts[a][d]` is the
+/// number of living cells of aggressivity class `d` whose genome was
+/// replaced by an incoming class-`a` genome.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InteractionMatrix {
+    pub counts: [[u32; AGG_CLASSES]; AGG_CLASSES],
+}
+
+impl InteractionMatrix {
+    /// From the GPU counter buffer, attacker-major.
+    pub fn from_counts(counts: &[u32]) -> Self {
+        let mut matrix = Self::default();
+        for (i, &count) in counts.iter().take(AGG_CLASSES * AGG_CLASSES).enumerate() {
+            matrix.counts[i / AGG_CLASSES][i % AGG_CLASSES] = count;
+        }
+        matrix
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().flatten().map(|&c| c as u64).sum()
+    }
+
+    pub fn max(&self) -> u32 {
+        self.counts.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// Share of takeovers by more aggressive genomes (above the diagonal).
+    pub fn upward_fraction(&self) -> Option<f32> {
+        let total = self.total();
+        let upward: u64 = (0..AGG_CLASSES)
+            .flat_map(|a| (0..a).map(move |d| (a, d)))
+            .map(|(a, d)| self.counts[a][d] as u64)
+            .sum();
+        (total > 0).then(|| upward as f32 / total as f32)
+    }
+
+    /// "0.0–0.2" style label of a class.
+    pub fn class_label(class: usize) -> String {
+        let width = 1.0 / AGG_CLASSES as f32;
+        format!("{:.1}–{:.1}", class as f32 * width, (class + 1) as f32 * width)
+    }
 }
 
 // ======================== Genetic Entropy ========================
@@ -320,6 +399,9 @@ pub fn compute_genetic_entropy(genome_a: &[f32], mass: &[f32], bins: usize) -> f
 
 // ======================== Species Detection (k-means) ========================
 
+/// Genomes closer than this (see genome_distance) are the same species.
+pub const SPECIES_THRESHOLD: f32 = 0.15;
+
 /// Simple k-means clustering on genome space to detect distinct species.
 /// Returns the number of clusters (species) found.
 ///
@@ -352,7 +434,7 @@ pub fn detect_species(genome_a: &[f32], mass: &[f32], max_species: usize) -> usi
     // Simple heuristic: count distinct genome clusters by variance threshold
     // Real k-means would be better but requires iterative optimization
     let mut unique_genomes: Vec<(f32, f32, f32, f32)> = Vec::new();
-    let threshold = 0.15; // Genomes closer than this are considered same species
+    let threshold = SPECIES_THRESHOLD;
 
     for genome in genomes {
         let mut is_unique = true;
@@ -374,8 +456,66 @@ pub fn detect_species(genome_a: &[f32], mass: &[f32], max_species: usize) -> usi
     unique_genomes.len()
 }
 
+/// Greedy genome clustering: a genome joins the first representative within
+/// SPECIES_THRESHOLD, otherwise it founds a new cluster. Past `max_clusters`,
+/// genomes join the nearest representative. Ids are stable for as long as
+/// the clusters are kept, so one instance can label successive frames.
+#[derive(Clone, Debug)]
+pub struct GenomeClusters {
+    representatives: Vec<(f32, f32, f32, f32)>,
+    max_clusters: usize,
+}
+
+impl GenomeClusters {
+    pub fn new(max_clusters: usize) -> Self {
+        Self { representatives: Vec::new(), max_clusters: max_clusters.max(1) }
+    }
+
+    /// Clusters founded so far.
+    pub fn count(&self) -> usize {
+        self.representatives.len()
+    }
+
+    /// Representative genome of each cluster, by id.
+    pub fn representatives(&self) -> &[(f32, f32, f32, f32)] {
+        &self.representatives
+    }
+
+    /// Move cluster `id` to `genome`, keeping its id (a drifting species).
+    pub fn recenter(&mut self, id: usize, genome: (f32, f32, f32, f32)) {
+        if let Some(rep) = self.representatives.get_mut(id) {
+            *rep = genome;
+        }
+    }
+
+    /// Whether new genomes can only join existing clusters.
+    pub fn is_full(&self) -> bool {
+        self.representatives.len() >= self.max_clusters
+    }
+
+    pub fn assign(&mut self, genome: (f32, f32, f32, f32)) -> u32 {
+        let mut nearest: Option<(usize, f32)> = None;
+        for (id, &rep) in self.representatives.iter().enumerate() {
+            let d = genome_distance(genome, rep);
+            if d < SPECIES_THRESHOLD {
+                return id as u32;
+            }
+            if nearest.is_none_or(|(_, best)| d < best) {
+                nearest = Some((id, d));
+            }
+        }
+        match nearest {
+            Some((id, _)) if self.representatives.len() >= self.max_clusters => id as u32,
+            _ => {
+                self.representatives.push(genome);
+                (self.representatives.len() - 1) as u32
+            }
+        }
+    }
+}
+
 /// Euclidean distance in normalized genome space
-fn genome_distance(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> f32 {
+pub fn genome_distance(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> f32 {
     let dr = (a.0 / 16.0 - b.0 / 16.0).powi(2);
     let dmu = (a.1 - b.1).powi(2);
     let dsigma = (a.2 / 0.3 - b.2 / 0.3).powi(2);
@@ -447,3 +587,75 @@ pub fn compute_genome_stats(
         predator_fraction: predator_mass / total_mass,
     }
 }
+
+// ======================== Split Divergence ========================
+
+/// How far the two halves of a split world (see PerturbationType::Barrier)
+/// have drifted apart genetically. Half 0 spans the half-world east of the
+/// split column, half 1 the rest.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SplitDivergence {
+    pub mass: [f32; 2],
+    pub species: [usize; 2],
+    pub between: f32,     // genome distance between the halves' mean genomes
+    pub within: [f32; 2], // mass-weighted mean distance to the half's own mean genome
+}
+
+impl SplitDivergence {
+    /// Between-half distance relative to the within-half spread: near 0 for
+    /// one mixed population, above 1 once the halves form distinct clusters.
+    /// None while either half is empty.
+    pub fn index(&self) -> Option<f32> {
+        if self.mass.iter().any(|&m| m <= 0.0) {
+            return None;
+        }
+        let spread = (self.within[0] + self.within[1]) * 0.5;
+        Some(self.between / spread.max(1e-3))
+    }
+}
+
+/// Compare the halves of the world split at column `split_x` (pixels) and
+/// half a world away.
+pub fn split_divergence(snap: &BufferSnapshot, width: u32, split_x: f32) -> SplitDivergence {
+    let half_of = |i: usize| {
+        let offset = ((i as u32 % width) as f32 + 0.5 - split_x).rem_euclid(width as f32);
+        usize::from(offset >= width as f32 * 0.5)
+    };
+    let genome = |i: usize| {
+        let g = &snap.genome_a[i * 4..i * 4 + 4];
+        (g[0], g[1], g[2], g[3])
+    };
+    let live = |i: usize| snap.mass[i] >= 0.01;
+
+    let mut mass = [0.0f32; 2];
+    let mut sums = [[0.0f32; 4]; 2];
+    for i in (0..snap.mass.len()).filter(|&i| live(i)) {
+        let (h, m) = (half_of(i), snap.mass[i]);
+        mass[h] += m;
+        for (s, g) in sums[h].iter_mut().zip(&snap.genome_a[i * 4..i * 4 + 4]) {
+            *s += g * m;
+        }
+    }
+    let means = [0, 1].map(|h| {
+        let s = sums[h].map(|v| if mass[h] > 0.0 { v / mass[h] } else { 0.0 });
+        (s[0], s[1], s[2], s[3])
+    });
+
+    let mut within = [0.0f32; 2];
+    for i in (0..snap.mass.len()).filter(|&i| live(i)) {
+        let h = half_of(i);
+        within[h] += genome_distance(genome(i), means[h]) * snap.mass[i];
+    }
+    let species = [0, 1].map(|h| {
+        let masked: Vec<f32> =
+            snap.mass.iter().enumerate().map(|(i, &m)| if half_of(i) == h { m } else { 0.0 }).collect();
+        detect_species(&snap.genome_a, &masked, 20)
+    });
+
+    SplitDivergence {
+        mass,
+        species,
+        between: if mass.iter().all(|&m| m > 0.0) { genome_distance(means[0], means[1]) } else { 0.0 },
+        within: [0, 1].map(|h| if mass[h] > 0.0 { within[h] / mass[h] } else { 0.0 }),
+    }
+}
@@ -0,0 +1,212 @@
+// ============================================================================
+// monitor.rs — EvoLenia v2
+// Optional HTTP endpoint for long headless runs (`--metrics-addr`):
+//   GET /metrics  current frame, steps/sec and diagnostics in Prometheus text
+//                 exposition format, for existing monitoring/alerting stacks
+//   GET /health   liveness check (JSON)
+//
+// Served from a background thread with std::net only; the simulation thread
+// just updates a shared snapshot.
+// ============================================================================
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Prefix of every exported metric name.
+const METRIC_PREFIX: &str = "evolenia";
+/// How often the accept loop checks for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Slow or idle clients are dropped after this long.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What the endpoint reports, updated by the run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MonitorState {
+    pub frame: u32,
+    pub frames_total: u32,
+    pub steps_per_sec: f64,
+    pub diagnostics_frame: Option<u32>, // frame of the last diagnostics sample
+    pub diagnostics: Vec<(&'static str, f32)>, // SimDiagnostics::named_scalars
+}
+
+impl MonitorState {
+    /// Prometheus text exposition format (version 0.0.4).
+    pub fn prometheus_text(&self) -> String {
+        let mut text = String::new();
+        let mut gauge = |name: &str, help: &str, value: f64| {
+            let name = format!("{}_{}", METRIC_PREFIX, name);
+            text += &format!("# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n");
+        };
+        gauge("frame", "Simulation steps completed.", self.frame as f64);
+        gauge("frames_total", "Simulation steps requested for this run.", self.frames_total as f64);
+        gauge("steps_per_second", "Simulation throughput over the last second.", self.steps_per_sec);
+        if let Some(frame) = self.diagnostics_frame {
+            gauge("diagnostics_frame", "Frame of the last diagnostics sample.", frame as f64);
+        }
+        for &(tag, value) in &self.diagnostics {
+            gauge(&metric_name(tag), &format!("Diagnostic {}.", tag), value as f64);
+        }
+        text
+    }
+
+    fn health_json(&self) -> String {
+        serde_json::json!({
+            "status": "ok",
+            "frame": self.frame,
+            "frames_total": self.frames_total,
+        })
+        .to_string()
+    }
+}
+
+/// Diagnostic tag ("population/total_mass") to a Prometheus metric name
+/// suffix ("population_total_mass").
+pub fn metric_name(tag: &str) -> String {
+    tag.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// Status line, content type and body for a request path.
+pub fn route(path: &str, state: &MonitorState) -> (&'static str, &'static str, String) {
+    match path.split('?').next().unwrap_or("") {
+        "/metrics" => ("200 OK", "text/plain; version=0.0.4; charset=utf-8", state.prometheus_text()),
+        "/health" | "/" => ("200 OK", "application/json", state.health_json()),
+        _ => ("404 Not Found", "text/plain; charset=utf-8", String::from("not found\n")),
+    }
+}
+
+/// Steps/sec over windows of at least one second.
+pub(crate) struct StepRate {
+    since: Instant,
+    start_frame: u32,
+}
+
+impl StepRate {
+    pub(crate) fn new() -> Self {
+        Self { since: Instant::now(), start_frame: 0 }
+    }
+
+    /// Throughput since the last update once a second has passed, else None.
+    pub(crate) fn update(&mut self, frame: u32) -> Option<f64> {
+        let elapsed = self.since.elapsed().as_secs_f64();
+        if elapsed < 1.0 {
+            return None;
+        }
+        let rate = frame.saturating_sub(self.start_frame) as f64 / elapsed;
+        *self = Self { since: Instant::now(), start_frame: frame };
+        Some(rate)
+    }
+}
+
+// ======================== Server ========================
+
+/// Background HTTP server; stops when dropped.
+pub struct MetricsServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MonitorState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    rate: StepRate,
+}
+
+impl MetricsServer {
+    /// Bind `addr` (e.g. "0.0.0.0:9898"; port 0 picks a free port).
+    pub fn start(addr: &str, frames_total: u32) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MonitorState { frames_total, ..Default::default() }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || serve(listener, &state, &stop))
+        };
+        Ok(Self { addr, state, stop, thread: Some(thread), rate: StepRate::new() })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Record progress; steps/sec is refreshed at most once per second.
+    pub fn set_frame(&mut self, frame: u32) {
+        let rate = self.rate.update(frame);
+        let mut state = self.lock();
+        state.frame = frame;
+        if let Some(rate) = rate {
+            state.steps_per_sec = rate;
+        }
+    }
+
+    /// Publish a diagnostics sample taken at `frame`.
+    pub fn set_diagnostics(&mut self, frame: u32, diagnostics: Vec<(&'static str, f32)>) {
+        let mut state = self.lock();
+        state.diagnostics_frame = Some(frame);
+        state.diagnostics = diagnostics;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MonitorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(listener: TcpListener, state: &Mutex<MonitorState>, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = handle_client(stream, state) {
+                    log::debug!("Metrics request failed: {}", e);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                log::warn!("Metrics endpoint accept failed: {}", e);
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+fn handle_client(stream: TcpStream, state: &Mutex<MonitorState>) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    // "GET /metrics HTTP/1.1"; headers are read and ignored
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) => {
+            let state = state.lock().unwrap_or_else(|e| e.into_inner());
+            route(path, &state)
+        }
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", String::from("only GET is supported\n")),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
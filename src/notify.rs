@@ -0,0 +1,178 @@
+// ============================================================================
+// notify.rs — EvoLenia v2 Research Lab
+// Notification center: short-lived toasts for finished operations, a history
+// of past notifications, and progress for background jobs. Worker threads
+// report through a channel (JobHandle) that the UI thread drains each frame.
+// ============================================================================
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Instant;
+
+use chrono::Local;
+
+use crate::lab::EventSeverity;
+
+/// How long a notification stays on screen as a toast.
+pub const TOAST_SECS: f32 = 5.0;
+
+/// Notifications kept in the history (oldest are dropped first).
+pub const HISTORY_LIMIT: usize = 200;
+
+// ======================== Jobs ========================
+
+/// Message from a background job to the notification center.
+#[derive(Debug)]
+pub enum JobUpdate {
+    Progress { id: u64, fraction: f32 },
+    Finished { id: u64, result: Result<String, String> },
+}
+
+/// Reporting end of a background job; move it into the worker thread.
+/// Dropping it without `finish` reports the job as interrupted.
+pub struct JobHandle {
+    id: u64,
+    tx: Sender<JobUpdate>,
+    finished: bool,
+}
+
+impl JobHandle {
+    /// Report progress in [0, 1].
+    pub fn progress(&self, fraction: f32) {
+        let _ = self.tx.send(JobUpdate::Progress { id: self.id, fraction: fraction.clamp(0.0, 1.0) });
+    }
+
+    /// Report completion: a success or failure message.
+    pub fn finish(mut self, result: Result<String, String>) {
+        self.finished = true;
+        let _ = self.tx.send(JobUpdate::Finished { id: self.id, result });
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.tx.send(JobUpdate::Finished { id: self.id, result: Err("interrupted".into()) });
+        }
+    }
+}
+
+/// A job still running.
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub id: u64,
+    pub title: String,
+    pub event_type: &'static str, // events log type used if the job fails
+    pub progress: Option<f32>,    // None until the job reports any (indeterminate)
+    pub started: Instant,
+}
+
+/// A job that completed since the last poll.
+#[derive(Clone, Debug)]
+pub struct FinishedJob {
+    pub event_type: &'static str,
+    pub result: Result<String, String>,
+}
+
+// ======================== Notifications ========================
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub severity: EventSeverity,
+    pub text: String,
+    pub wall_time: String, // HH:MM:SS, for the history list
+    pub created: Instant,
+}
+
+impl Notification {
+    pub fn is_toast(&self) -> bool {
+        self.created.elapsed().as_secs_f32() < TOAST_SECS
+    }
+}
+
+pub struct NotificationCenter {
+    tx: Sender<JobUpdate>,
+    rx: Receiver<JobUpdate>,
+    next_id: u64,
+    pub jobs: Vec<Job>,
+    pub history: VecDeque<Notification>, // oldest first
+    pub show_history: bool,
+    pub unread: usize, // notifications since the history was last opened
+}
+
+impl Default for NotificationCenter {
+    fn default() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            tx,
+            rx,
+            next_id: 0,
+            jobs: Vec::new(),
+            history: VecDeque::new(),
+            show_history: false,
+            unread: 0,
+        }
+    }
+}
+
+impl NotificationCenter {
+    /// Post a notification (shown as a toast, then kept in the history).
+    pub fn notify(&mut self, severity: EventSeverity, text: String) {
+        if self.history.len() >= HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(Notification {
+            severity,
+            text,
+            wall_time: Local::now().format("%H:%M:%S").to_string(),
+            created: Instant::now(),
+        });
+        if !self.show_history {
+            self.unread += 1;
+        }
+    }
+
+    /// Register a background job and get the handle its worker reports to.
+    pub fn start_job(&mut self, title: String, event_type: &'static str) -> JobHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(Job { id, title, event_type, progress: None, started: Instant::now() });
+        JobHandle { id, tx: self.tx.clone(), finished: false }
+    }
+
+    /// Apply pending job updates. Finished jobs become notifications and are
+    /// returned so the caller can log them.
+    pub fn poll(&mut self) -> Vec<FinishedJob> {
+        let mut finished = Vec::new();
+        while let Ok(update) = self.rx.try_recv() {
+            match update {
+                JobUpdate::Progress { id, fraction } => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        job.progress = Some(fraction);
+                    }
+                }
+                JobUpdate::Finished { id, result } => {
+                    let Some(pos) = self.jobs.iter().position(|j| j.id == id) else { continue };
+                    let job = self.jobs.remove(pos);
+                    let (severity, text) = match &result {
+                        Ok(msg) => (EventSeverity::Info, msg.clone()),
+                        Err(e) => (EventSeverity::Warn, format!("{}: {}", job.title, e)),
+                    };
+                    self.notify(severity, text);
+                    finished.push(FinishedJob { event_type: job.event_type, result });
+                }
+            }
+        }
+        finished
+    }
+
+    /// Notifications young enough to show as toasts, newest last.
+    pub fn toasts(&self) -> impl Iterator<Item = &Notification> {
+        self.history.iter().filter(|n| n.is_toast())
+    }
+
+    pub fn open_history(&mut self) {
+        self.show_history = true;
+        self.unread = 0;
+    }
+}
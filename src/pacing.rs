@@ -0,0 +1,454 @@
+// ============================================================================
+// pacing.rs — EvoLenia v2
+// Simulation pacing: GPU timing of the per-frame simulation batch (timestamp
+// queries, when the adapter supports them), the wall-clock step budget that
+// turns a target UI frame rate into a steps-per-frame count, the speed guard
+// that lowers steps per frame while the UI is slow to respond, background
+// throttling while the window is unfocused or minimized, the render cadence
+// (which loop iterations render and which only simulate), and the rolling
+// frame cost statistics behind the performance panel.
+// ============================================================================
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{RenderCadenceSettings, SpeedGuardSettings, StepBudgetSettings};
+
+// ======================== GPU Step Timer ========================
+
+const READBACK_IDLE: u8 = 0;
+const READBACK_PENDING: u8 = 1;
+const READBACK_READY: u8 = 2;
+
+/// Measures GPU time of one simulation batch per readback round trip.
+/// Timestamps are written by empty compute passes at the start of the first
+/// step and the end of the last step, so the simulation passes themselves
+/// are untouched. Batches submitted while a readback is in flight are not
+/// timed.
+pub struct GpuStepTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    period_ns: f32,
+    readback: Arc<AtomicU8>,
+    timed_steps: u32,
+    recording: bool,
+    pub last_step_ms: Option<f32>,
+}
+
+impl GpuStepTimer {
+    /// None when the device lacks `TIMESTAMP_QUERY`.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("step_timer_queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let size = 2 * std::mem::size_of::<u64>() as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("step_timer_resolve"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("step_timer_readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period_ns: queue.get_timestamp_period(),
+            readback: Arc::new(AtomicU8::new(READBACK_IDLE)),
+            timed_steps: 0,
+            recording: false,
+            last_step_ms: None,
+        })
+    }
+
+    /// Collect a finished readback, if any (non-blocking).
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        if self.readback.load(Ordering::Acquire) == READBACK_PENDING {
+            device.poll(wgpu::Maintain::Poll);
+        }
+        if self.readback.load(Ordering::Acquire) != READBACK_READY {
+            return;
+        }
+        {
+            let data = self.readback_buffer.slice(..).get_mapped_range();
+            let ticks: &[u64] = bytemuck::cast_slice(&data);
+            let elapsed_ns = ticks[1].saturating_sub(ticks[0]) as f64 * self.period_ns as f64;
+            if elapsed_ns > 0.0 && self.timed_steps > 0 {
+                self.last_step_ms = Some((elapsed_ns / 1e6 / self.timed_steps as f64) as f32);
+            }
+        }
+        self.readback_buffer.unmap();
+        self.readback.store(READBACK_IDLE, Ordering::Release);
+    }
+
+    /// Start timing a batch in its first encoder. Returns false (and records
+    /// nothing) while the previous measurement is still being read back.
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder) -> bool {
+        self.recording = self.readback.load(Ordering::Acquire) == READBACK_IDLE;
+        if self.recording {
+            self.timestamp_pass(encoder, Some(0), None);
+        }
+        self.recording
+    }
+
+    /// Finish timing in the batch's last encoder (before it is submitted).
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder, steps: u32) {
+        if !self.recording {
+            return;
+        }
+        self.timestamp_pass(encoder, None, Some(1));
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, self.resolve_buffer.size());
+        self.timed_steps = steps;
+    }
+
+    /// Request the readback once the batch's last encoder was submitted.
+    pub fn after_submit(&mut self) {
+        if !self.recording {
+            return;
+        }
+        self.recording = false;
+        self.readback.store(READBACK_PENDING, Ordering::Release);
+        let readback = self.readback.clone();
+        self.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let next = if result.is_ok() { READBACK_READY } else { READBACK_IDLE };
+            readback.store(next, Ordering::Release);
+        });
+    }
+
+    fn timestamp_pass(&self, encoder: &mut wgpu::CommandEncoder, begin: Option<u32>, end: Option<u32>) {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("step_timer_pass"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: begin,
+                end_of_pass_write_index: end,
+            }),
+        });
+    }
+}
+
+// ======================== Step Budget ========================
+
+/// Chooses steps per frame so whole frames take about 1/target_fps.
+/// With a GPU step time, steps = share × frame budget / step time, where the
+/// share of the frame given to simulation adapts to the measured frame time
+/// (render and UI take the rest). Without one, steps scale directly with the
+/// ratio of target to measured frame time.
+#[derive(Clone, Debug)]
+pub struct StepBudget {
+    steps: f32,      // smoothed, fractional
+    sim_share: f32,  // fraction of the frame budget given to simulation
+    frame_ms: Option<f32>, // smoothed frame time
+}
+
+impl Default for StepBudget {
+    fn default() -> Self {
+        Self { steps: 1.0, sim_share: 0.75, frame_ms: None }
+    }
+}
+
+impl StepBudget {
+    const MIN_SHARE: f32 = 0.1;
+    const MAX_SHARE: f32 = 0.95;
+    const SMOOTHING: f32 = 0.2;
+
+    pub fn steps(&self) -> u32 {
+        self.steps.round().max(1.0) as u32
+    }
+
+    /// Feed the last frame time (and GPU step time, if measured); returns
+    /// the step count for the next frame.
+    pub fn update(&mut self, settings: &StepBudgetSettings, frame_ms: f32, gpu_step_ms: Option<f32>) -> u32 {
+        let frame_ms = match self.frame_ms {
+            Some(prev) => prev + (frame_ms - prev) * Self::SMOOTHING,
+            None => frame_ms,
+        }
+        .max(0.01);
+        self.frame_ms = Some(frame_ms);
+
+        let target_ms = 1000.0 / settings.target_fps.max(1.0);
+        if frame_ms > target_ms * 1.05 {
+            self.sim_share = (self.sim_share * 0.95).max(Self::MIN_SHARE);
+        } else if frame_ms < target_ms * 0.95 {
+            self.sim_share = (self.sim_share * 1.02).min(Self::MAX_SHARE);
+        }
+
+        let ideal = match gpu_step_ms {
+            Some(step_ms) if step_ms > 0.0 => target_ms * self.sim_share / step_ms,
+            _ => self.steps * target_ms / frame_ms,
+        };
+        let max_steps = settings.max_steps.max(1) as f32;
+        self.steps = (self.steps + (ideal.clamp(1.0, max_steps) - self.steps) * Self::SMOOTHING).clamp(1.0, max_steps);
+        self.steps()
+    }
+}
+
+// ======================== Speed Guard ========================
+
+/// Caps steps per frame below the requested speed while event loop turns
+/// are slow: the cap drops by a quarter when the smoothed turn time exceeds
+/// the target and grows back one step at a time when turns take under
+/// HEADROOM of it. Changes are spaced HOLD turns apart so each one shows
+/// in the measurements before the next.
+#[derive(Clone, Debug, Default)]
+pub struct SpeedGuard {
+    limit: Option<u32>,    // None: full speed
+    turn_ms: Option<f32>,  // smoothed event loop turn time
+    hold: u32,             // turns left before the next change
+}
+
+impl SpeedGuard {
+    const SMOOTHING: f32 = 0.2;
+    const HEADROOM: f32 = 0.7;
+    const HOLD: u32 = 10;
+
+    /// Steps to run for the requested `speed`.
+    pub fn steps(&self, speed: u32) -> u32 {
+        self.limit.map_or(speed, |limit| limit.min(speed)).max(1)
+    }
+
+    /// The cap, when it is below `speed`.
+    pub fn limited(&self, speed: u32) -> Option<u32> {
+        self.limit.filter(|&limit| limit < speed)
+    }
+
+    /// Feed the duration of the last event loop turn that simulated.
+    pub fn observe(&mut self, settings: &SpeedGuardSettings, turn_ms: f32, speed: u32) {
+        if !settings.enabled {
+            *self = Self::default();
+            return;
+        }
+        let turn_ms = match self.turn_ms {
+            Some(prev) => prev + (turn_ms - prev) * Self::SMOOTHING,
+            None => turn_ms,
+        };
+        self.turn_ms = Some(turn_ms);
+        if self.hold > 0 {
+            self.hold -= 1;
+            return;
+        }
+        let current = self.steps(speed);
+        if turn_ms > settings.max_frame_ms && current > 1 {
+            self.limit = Some((current * 3 / 4).clamp(1, current - 1));
+            self.hold = Self::HOLD;
+        } else if turn_ms < settings.max_frame_ms * Self::HEADROOM && self.limit.is_some() {
+            self.limit = Some(current + 1).filter(|&limit| limit < speed);
+            self.hold = Self::HOLD;
+        }
+    }
+}
+
+// ======================== Background Throttling ========================
+
+/// Low-power mode for windows left in the background: no rendering, and the
+/// simulation advances at a fixed, low step rate.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundThrottle {
+    pub enabled: bool,
+    pub steps_per_sec: u32, // 0 = pause while in the background
+}
+
+impl Default for BackgroundThrottle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            steps_per_sec: 10,
+        }
+    }
+}
+
+/// Converts elapsed wall time into whole steps at a fixed rate, carrying the
+/// remainder between wake-ups.
+#[derive(Clone, Debug)]
+pub struct BackgroundClock {
+    last: Instant,
+    carry: f64, // fractional steps owed
+}
+
+impl BackgroundClock {
+    /// Longest gap credited at once (e.g. after the machine slept).
+    const MAX_CATCH_UP_SECS: f64 = 1.0;
+    /// Shortest sleep between wake-ups; higher rates run in batches.
+    const MIN_WAKE: Duration = Duration::from_millis(20);
+
+    pub fn new(now: Instant) -> Self {
+        Self { last: now, carry: 0.0 }
+    }
+
+    /// Restart timing (when entering the background).
+    pub fn reset(&mut self, now: Instant) {
+        self.last = now;
+        self.carry = 0.0;
+    }
+
+    /// Steps to run now for `steps_per_sec`.
+    pub fn steps_due(&mut self, now: Instant, steps_per_sec: u32) -> u32 {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64().min(Self::MAX_CATCH_UP_SECS);
+        self.last = now;
+        self.carry += elapsed * steps_per_sec as f64;
+        let steps = self.carry.floor();
+        self.carry -= steps;
+        steps as u32
+    }
+
+    /// When to wake up next.
+    pub fn next_wake(&self, now: Instant, steps_per_sec: u32) -> Instant {
+        let period = Duration::from_secs_f64(1.0 / steps_per_sec.max(1) as f64);
+        now + period.max(Self::MIN_WAKE)
+    }
+}
+
+// ======================== Render Cadence ========================
+
+/// What the next loop iteration does under the render cadence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderDue {
+    /// Render (and run the UI) this iteration.
+    Now,
+    /// Only simulate; render later.
+    Simulate,
+    /// Nothing to simulate: sleep until the next render.
+    WaitUntil(Instant),
+}
+
+/// Tracks steps and time since the last rendered frame.
+#[derive(Clone, Debug)]
+pub struct RenderClock {
+    last_render: Instant,
+    steps_since_render: u32,
+}
+
+impl RenderClock {
+    /// Longest time without rendering under the step rule, so the UI stays
+    /// responsive when the simulation is slow.
+    pub const MAX_RENDER_GAP: Duration = Duration::from_millis(250);
+
+    pub fn new(now: Instant) -> Self {
+        Self { last_render: now, steps_since_render: 0 }
+    }
+
+    /// Count simulated steps.
+    pub fn add_steps(&mut self, steps: u32) {
+        self.steps_since_render = self.steps_since_render.saturating_add(steps);
+    }
+
+    /// A frame was rendered at `now`.
+    pub fn rendered(&mut self, now: Instant) {
+        self.last_render = now;
+        self.steps_since_render = 0;
+    }
+
+    /// Whether to render now. While the simulation is stopped (`simulating`
+    /// false) the step rule cannot apply and only the frame rate cap holds.
+    pub fn due(&self, settings: &RenderCadenceSettings, now: Instant, simulating: bool) -> RenderDue {
+        let since = now.saturating_duration_since(self.last_render);
+        if settings.max_fps > 0.0 {
+            let period = Duration::from_secs_f64(1.0 / settings.max_fps as f64);
+            if since < period {
+                return if simulating { RenderDue::Simulate } else { RenderDue::WaitUntil(self.last_render + period) };
+            }
+        }
+        let waiting_for_steps = settings.every_n_steps > 1 && self.steps_since_render < settings.every_n_steps;
+        if simulating && waiting_for_steps && since < Self::MAX_RENDER_GAP {
+            return RenderDue::Simulate;
+        }
+        RenderDue::Now
+    }
+}
+
+// ======================== Frame Statistics ========================
+
+/// Cost breakdown of one rendered frame, in milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTiming {
+    pub frame_ms: f32,           // wall time since the previous frame
+    pub encode_ms: f32,          // CPU: simulation + render command encoding and submission
+    pub egui_ms: f32,            // CPU: UI build, tessellation and egui encoding
+    pub readback_ms: f32,        // CPU blocked on GPU→CPU readbacks (diagnostics, captures)
+    pub gpu_sim_ms: Option<f32>, // GPU: simulation batch (timestamp queries)
+}
+
+/// Nearest-rank percentiles of the frame time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FramePercentiles {
+    pub p50: f32,
+    pub p95: f32,
+    pub p99: f32,
+    pub max: f32,
+}
+
+/// Rolling window of the most recent frame timings.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+    samples: VecDeque<FrameTiming>, // oldest first
+    capacity: usize,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl FrameStats {
+    /// About ten seconds at 60 FPS.
+    pub const DEFAULT_CAPACITY: usize = 600;
+    /// Readbacks longer than this count as stalls.
+    pub const STALL_MS: f32 = 2.0;
+
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn push(&mut self, timing: FrameTiming) {
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(timing);
+    }
+
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &FrameTiming> {
+        self.samples.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn frame_percentiles(&self) -> Option<FramePercentiles> {
+        let mut times: Vec<f32> = self.samples.iter().map(|t| t.frame_ms).collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_by(f32::total_cmp);
+        let rank = |p: f32| times[((p * times.len() as f32).ceil() as usize).clamp(1, times.len()) - 1];
+        Some(FramePercentiles { p50: rank(0.5), p95: rank(0.95), p99: rank(0.99), max: times[times.len() - 1] })
+    }
+
+    /// Mean of `value` over the frames where it is defined.
+    pub fn mean<F: Fn(&FrameTiming) -> Option<f32>>(&self, value: F) -> Option<f32> {
+        let (sum, n) = self.samples.iter().filter_map(value).fold((0.0, 0), |(s, n), v| (s + v, n + 1));
+        (n > 0).then(|| sum / n as f32)
+    }
+
+    /// Frames whose readbacks exceeded `STALL_MS`.
+    pub fn readback_stalls(&self) -> usize {
+        self.samples.iter().filter(|t| t.readback_ms > Self::STALL_MS).count()
+    }
+}
@@ -0,0 +1,109 @@
+// ============================================================================
+// param_lock.rs — EvoLenia v2 Research Lab
+// Parameter locks and the recording safety mode. A lock pins one top-level
+// field of SimulationParams (a settings group such as `mutation` is one
+// field); safety mode pins every field that changes the dynamics while a run
+// is recording, until it is explicitly unlocked for that run. Display,
+// pacing and analysis settings stay free. The lab UI edits the parameters in
+// place, so the guard compares them before and after the UI pass and puts
+// blocked fields back; the attempt is logged instead of the change.
+// ============================================================================
+
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+
+use crate::config::SimulationParams;
+use crate::preset_diff::PresetDiff;
+
+/// Fields safety mode leaves editable: they change what is shown, how fast
+/// it runs or what is measured, not what the world does.
+pub const FREE_FIELDS: &[&str] = &[
+    "paused",
+    "simulation_speed",
+    "vsync",
+    "step_budget",
+    "render_cadence",
+    "speed_guard",
+    "visualization_mode",
+    "show_extended_ui",
+    "display_adjust",
+    "bloom",
+    "trail",
+    "diff_overlay",
+    "territory",
+    "species_map",
+    "legend",
+    "histogram",
+    "custom_metrics",
+    "alert_rules",
+    "speciation",
+    "forensics",
+    "averages",
+    "velocity_export",
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct ParamLocks {
+    pub locked: BTreeSet<String>, // top-level fields pinned by the user
+    pub safety_mode: bool,        // pin the dynamics while a run is active
+    pub unlocked: bool,           // safety mode lifted for the current run
+}
+
+impl ParamLocks {
+    /// Whether safety mode currently pins the dynamics.
+    pub fn safety_engaged(&self, run_active: bool) -> bool {
+        self.safety_mode && run_active && !self.unlocked
+    }
+
+    /// Whether an edit of `field` is blocked.
+    pub fn blocks(&self, field: &str, run_active: bool) -> bool {
+        self.locked.contains(field) || (self.safety_engaged(run_active) && !FREE_FIELDS.contains(&field))
+    }
+
+    /// Whether any edit can be blocked (the guard is skipped otherwise).
+    pub fn is_engaged(&self, run_active: bool) -> bool {
+        !self.locked.is_empty() || self.safety_engaged(run_active)
+    }
+}
+
+/// Edits the guard put back.
+#[derive(Clone, Debug, Default)]
+pub struct Blocked {
+    pub attempted: Map<String, Value>, // field -> value the edit would have set
+    pub all: bool,                     // every edit of the pass was blocked
+}
+
+/// Put back the fields of `params` changed since `before` that `locks`
+/// block. None when nothing was blocked.
+pub fn enforce(
+    locks: &ParamLocks,
+    run_active: bool,
+    before: &SimulationParams,
+    params: &mut SimulationParams,
+) -> Option<Blocked> {
+    let mut diff = PresetDiff::new("", params, before);
+    for change in &mut diff.changes {
+        change.apply = locks.blocks(&change.field, run_active);
+    }
+    let attempted: Map<String, Value> = diff
+        .changes
+        .iter()
+        .filter(|c| c.apply)
+        .map(|c| (c.field.clone(), c.current.clone()))
+        .collect();
+    if attempted.is_empty() {
+        return None;
+    }
+    let all = attempted.len() == diff.changes.len();
+    *params = diff.merged(params).unwrap_or_else(|_| before.clone());
+    Some(Blocked { attempted, all })
+}
+
+/// Top-level fields of the parameters, by name.
+pub fn field_names(params: &SimulationParams) -> Vec<String> {
+    match serde_json::to_value(params) {
+        Ok(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
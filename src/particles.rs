@@ -0,0 +1,105 @@
+// ============================================================================
+// particles.rs — EvoLenia v2
+// Grazer / disperser particles: a fixed pool of GPU agents moving in
+// continuous space over the field (compute_particles.wgsl). A hungry particle
+// climbs the mass gradient and grazes the cell under it, keeping the genome
+// of what it ate; once loaded it heads for empty ground and drops its cargo
+// there, seeding the carried genome far from where it was taken.
+// ============================================================================
+
+use bytemuck::{Pod, Zeroable};
+use rand::Rng;
+
+use crate::config::ParticleSettings;
+
+/// Size of the particle pool; `ParticleSettings::count` selects how many run.
+pub const MAX_PARTICLES: u32 = 10_000;
+
+/// One particle, mirrored by `Particle` in compute_particles.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Particle {
+    pub pos: [f32; 2], // cells, in [0, width) × [0, height) of the world
+    pub vel: [f32; 2], // unit heading
+    pub genome_a: [f32; 4], // genome of the last cell grazed
+    pub genome_b: f32,
+    pub cargo: f32,     // mass carried
+    pub energy: f32,    // 0 = starved, respawns elsewhere
+    pub age: f32,       // steps since (re)spawn
+    pub grazed: f32,    // lifetime totals, kept across respawns
+    pub deposited: f32,
+    pub _pad: [f32; 2],
+}
+
+/// Uniform of both particle passes.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ParticleParams {
+    pub count: u32,
+    pub frame: u32,
+    pub speed: f32,
+    pub graze_rate: f32,
+    pub capacity: f32,
+    pub drop_rate: f32,
+    pub metabolism: f32,
+    pub _pad: u32,
+}
+
+impl ParticleParams {
+    pub fn new(settings: &ParticleSettings, frame: u32) -> Self {
+        Self {
+            count: settings.active_count(),
+            frame,
+            speed: settings.speed,
+            graze_rate: settings.graze_rate,
+            capacity: settings.capacity,
+            drop_rate: settings.drop_rate,
+            metabolism: settings.metabolism,
+            _pad: 0,
+        }
+    }
+}
+
+/// The whole pool scattered over a `size` world with random headings.
+pub fn initial_particles(rng: &mut impl Rng, [width, height]: [u32; 2]) -> Vec<Particle> {
+    (0..MAX_PARTICLES)
+        .map(|_| {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            Particle {
+                pos: [rng.gen_range(0.0..width as f32), rng.gen_range(0.0..height as f32)],
+                vel: [angle.cos(), angle.sin()],
+                energy: 1.0,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Summary of the active particles at a diagnostics sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParticleStats {
+    pub count: usize,
+    pub mean_energy: f32,
+    pub loaded_fraction: f32, // carrying more than half their capacity
+    pub cargo: f32,           // mass in transit
+    pub grazed: f32,          // lifetime totals of the pool
+    pub deposited: f32,
+}
+
+impl ParticleStats {
+    pub fn from_particles(particles: &[Particle], capacity: f32) -> Self {
+        let count = particles.len();
+        if count == 0 {
+            return Self::default();
+        }
+        let loaded = particles.iter().filter(|p| p.cargo > 0.5 * capacity).count();
+        Self {
+            count,
+            mean_energy: particles.iter().map(|p| p.energy).sum::<f32>() / count as f32,
+            loaded_fraction: loaded as f32 / count as f32,
+            cargo: particles.iter().map(|p| p.cargo).sum(),
+            grazed: particles.iter().map(|p| p.grazed).sum(),
+            deposited: particles.iter().map(|p| p.deposited).sum(),
+        }
+    }
+}
@@ -0,0 +1,434 @@
+// ============================================================================
+// pattern.rs — EvoLenia v2 Research Lab
+// Creature exchange with the Lenia community. Import reads the published
+// Lenia pattern format (JSON with `params` R/T/b/m/s/kn/gn and `cells` as RLE
+// text or a 2D array) wherever it maps onto a single EvoLenia genome, noting
+// each approximation made. Export writes a region of the world in the same
+// format, plus an `evolenia` block carrying the genes Lenia has no notion of
+// (schema in README, "Lenia Patterns").
+// ============================================================================
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::GENOME_RADIUS_RANGE;
+use crate::world::{BufferSnapshot, DT};
+
+/// Directory of the pattern files named in the Lab UI.
+pub const PATTERN_DIR: &str = "patterns";
+/// Growth center and width clamps of the mutation step (compute_evolution.wgsl).
+pub const GENOME_MU_RANGE: (f32, f32) = (0.05, 0.35);
+pub const GENOME_SIGMA_RANGE: (f32, f32) = (0.005, 0.08);
+/// Version of the `evolenia` block written on export.
+pub const PATTERN_SCHEMA_VERSION: u32 = 1;
+
+/// Genes Lenia patterns lack get the world's initial defaults.
+const DEFAULT_AGGRESSIVITY: f32 = 0.0;
+const DEFAULT_MUTATION_RATE: f32 = 0.003;
+/// Energy of imported cells (the initial world energy).
+const IMPORT_ENERGY: f32 = 0.5;
+/// Cells that round to an empty RLE cell are left out of exports.
+const EXPORT_MIN_MASS: f32 = 0.5 / 255.0;
+/// Lenia kernel core and growth function codes (1-based, as in Lenia.py).
+const LENIA_POLYNOMIAL: u32 = 1;
+const LENIA_EXPONENTIAL: u32 = 2;
+
+/// A creature: one genome over a patch of mass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+    pub genome: [f32; 4], // r, mu, sigma, aggressivity
+    pub mutation_rate: f32,
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<f32>, // mass, row-major
+}
+
+// ======================== File Format ========================
+
+/// One pattern entry: Lenia's fields, plus the EvoLenia block on export.
+#[derive(Serialize, Deserialize)]
+struct PatternFile {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    code: String,
+    params: LeniaParams,
+    cells: LeniaCells,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    evolenia: Option<EvoLeniaGenes>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LeniaParams {
+    #[serde(rename = "R")]
+    radius: f32,
+    #[serde(rename = "T", default = "default_time_scale")]
+    time_scale: f32,
+    #[serde(default = "default_rings")]
+    b: String, // ring peak heights, e.g. "1" or "1,1/2"
+    m: f32,
+    s: f32,
+    #[serde(default = "default_function")]
+    kn: u32,
+    #[serde(default = "default_function")]
+    gn: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LeniaCells {
+    Rle(String),
+    Grid(Vec<Vec<f32>>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct EvoLeniaGenes {
+    version: u32,
+    radius: f32, // exact genome radius (params.R is rounded)
+    aggressivity: f32,
+    mutation_rate: f32,
+}
+
+fn default_time_scale() -> f32 {
+    1.0 / DT
+}
+
+fn default_rings() -> String {
+    "1".to_string()
+}
+
+fn default_function() -> u32 {
+    LENIA_POLYNOMIAL
+}
+
+// ======================== Import ========================
+
+/// Parse a pattern file: one pattern, or a list (such as Lenia's
+/// animals.json) of which the first pattern is taken. Returns the pattern
+/// and the approximations made mapping it onto EvoLenia.
+pub fn parse_pattern(json: &str) -> Result<(Pattern, Vec<String>), String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut notes = Vec::new();
+    let entry = match value {
+        serde_json::Value::Array(list) => {
+            // Lists interleave section headers (name only) with patterns
+            let mut patterns: Vec<_> =
+                list.into_iter().filter(|e| e.get("params").is_some() && e.get("cells").is_some()).collect();
+            if patterns.is_empty() {
+                return Err("the list holds no pattern".to_string());
+            }
+            if patterns.len() > 1 {
+                notes.push(format!("the list holds {} patterns; imported the first", patterns.len()));
+            }
+            patterns.swap_remove(0)
+        }
+        entry => entry,
+    };
+    let file: PatternFile = serde_json::from_value(entry).map_err(|e| e.to_string())?;
+    let pattern = file.into_pattern(&mut notes)?;
+    Ok((pattern, notes))
+}
+
+impl PatternFile {
+    fn into_pattern(self, notes: &mut Vec<String>) -> Result<Pattern, String> {
+        let p = &self.params;
+        if p.b.split(',').count() != 1 {
+            return Err(format!("multi-ring kernel (b={}) has no EvoLenia equivalent", p.b));
+        }
+        if !matches!(p.kn, LENIA_POLYNOMIAL | LENIA_EXPONENTIAL) {
+            notes.push(format!("kernel core kn={} approximated by the Gaussian ring", p.kn));
+        }
+        let sigma = match p.gn {
+            LENIA_EXPONENTIAL => p.s,
+            // (1 - x²/9s²)⁴ ≈ exp(-4x²/9s²): a Gaussian of width 3s/2√2
+            LENIA_POLYNOMIAL => {
+                notes.push("polynomial growth (gn=1) approximated by a Gaussian".to_string());
+                p.s * 1.5 / std::f32::consts::SQRT_2
+            }
+            gn => {
+                notes.push(format!("growth function gn={} approximated by a Gaussian", gn));
+                p.s
+            }
+        };
+        if (p.time_scale * DT - 1.0).abs() > 1e-3 {
+            notes.push(format!("time scale T={} ignored (EvoLenia steps with dt={})", p.time_scale, DT));
+        }
+
+        let (width, height, cells) = match self.cells {
+            LeniaCells::Rle(text) => decode_rle(&text)?,
+            LeniaCells::Grid(rows) => grid_cells(rows)?,
+        };
+
+        // The cells are drawn for their own radius: rescale them to the clamped one
+        let (aggressivity, mutation_rate, drawn_radius) = match &self.evolenia {
+            Some(genes) => (genes.aggressivity, genes.mutation_rate, genes.radius),
+            None => (DEFAULT_AGGRESSIVITY, DEFAULT_MUTATION_RATE, p.radius),
+        };
+        let radius = clamped(drawn_radius, GENOME_RADIUS_RANGE, "radius R", notes);
+        let scale = radius / drawn_radius;
+        let (width, height, cells) = if (scale - 1.0).abs() > 1e-3 {
+            notes.push(format!("cells rescaled by {:.2} to match the radius", scale));
+            resample(width, height, &cells, scale)
+        } else {
+            (width, height, cells)
+        };
+
+        Ok(Pattern {
+            name: if self.name.is_empty() { self.code } else { self.name },
+            genome: [
+                radius,
+                clamped(p.m, GENOME_MU_RANGE, "growth center m", notes),
+                clamped(sigma, GENOME_SIGMA_RANGE, "growth width s", notes),
+                aggressivity.clamp(0.0, 1.0),
+            ],
+            mutation_rate,
+            width,
+            height,
+            cells,
+        })
+    }
+}
+
+fn clamped(value: f32, (lo, hi): (f32, f32), what: &str, notes: &mut Vec<String>) -> f32 {
+    let c = value.clamp(lo, hi);
+    if c != value {
+        notes.push(format!("{} {} clamped to {}", what, value, c));
+    }
+    c
+}
+
+/// Decode Lenia's RLE: runs of `[count]state`, where a state is `.` or `b`
+/// (empty), `o` (full), `A`–`X` (levels 1–24 of 255) or one of `p`–`y`
+/// followed by `A`–`X` (levels 25–255); `$` ends a row and `!` the pattern.
+pub fn decode_rle(text: &str) -> Result<(usize, usize, Vec<f32>), String> {
+    let mut rows: Vec<Vec<f32>> = vec![Vec::new()];
+    let mut count = 0usize;
+    let mut prefix: Option<char> = None;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if let Some(digit) = c.to_digit(10).filter(|_| prefix.is_none()) {
+            count = count * 10 + digit as usize;
+            continue;
+        }
+        let run = count.max(1);
+        count = 0;
+        match (prefix.take(), c) {
+            (None, '!') => break,
+            (None, '$') => rows.extend((0..run).map(|_| Vec::new())),
+            (None, '%' | '#' | '@') => {
+                return Err("patterns with more than two dimensions are not supported".to_string())
+            }
+            (None, 'p'..='y') => {
+                prefix = Some(c);
+                count = run;
+            }
+            (prefix, c) => {
+                let level = match (prefix, c) {
+                    (None, '.' | 'b') => 0,
+                    (None, 'o') => 255,
+                    (None, 'A'..='X') => c as u32 - 'A' as u32 + 1,
+                    (Some(p), 'A'..='X') => (p as u32 - 'p' as u32) * 24 + (c as u32 - 'A' as u32) + 25,
+                    _ => return Err(format!("invalid RLE cell '{}'", c)),
+                };
+                let row = rows.last_mut().expect("rows starts non-empty");
+                row.extend(std::iter::repeat_n(level as f32 / 255.0, run));
+            }
+        }
+    }
+    grid_cells(rows)
+}
+
+/// Pad rows to a rectangle, dropping trailing empty rows.
+fn grid_cells(mut rows: Vec<Vec<f32>>) -> Result<(usize, usize, Vec<f32>), String> {
+    while rows.last().is_some_and(|r| r.is_empty()) {
+        rows.pop();
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width == 0 {
+        return Err("pattern has no cells".to_string());
+    }
+    let cells = rows
+        .iter()
+        .flat_map(|row| (0..width).map(|x| row.get(x).copied().unwrap_or(0.0).clamp(0.0, 1.0)))
+        .collect();
+    Ok((width, rows.len(), cells))
+}
+
+/// Nearest-neighbor resampling by `scale`.
+fn resample(width: usize, height: usize, cells: &[f32], scale: f32) -> (usize, usize, Vec<f32>) {
+    let w = ((width as f32 * scale).round() as usize).max(1);
+    let h = ((height as f32 * scale).round() as usize).max(1);
+    let src = |i: usize, n: usize| ((i as f32 / scale) as usize).min(n - 1);
+    let out = (0..h)
+        .flat_map(|y| (0..w).map(move |x| (x, y)))
+        .map(|(x, y)| cells[src(y, height) * width + src(x, width)])
+        .collect();
+    (w, h, out)
+}
+
+// ======================== Export ========================
+
+/// Encode cells as Lenia RLE, quantized to 255 levels.
+pub fn encode_rle(width: usize, cells: &[f32]) -> String {
+    let symbol = |level: u32| match level {
+        0 => ".".to_string(),
+        1..=24 => char::from_u32('A' as u32 + level - 1).unwrap().to_string(),
+        _ => {
+            let (hi, lo) = ((level - 25) / 24, (level - 25) % 24);
+            format!("{}{}", char::from_u32('p' as u32 + hi).unwrap(), char::from_u32('A' as u32 + lo).unwrap())
+        }
+    };
+    let mut out = String::new();
+    for (y, row) in cells.chunks(width).enumerate() {
+        if y > 0 {
+            out.push('$');
+        }
+        let levels: Vec<u32> = row.iter().map(|&m| (m.clamp(0.0, 1.0) * 255.0).round() as u32).collect();
+        let end = levels.iter().rposition(|&l| l > 0).map_or(0, |i| i + 1);
+        let mut x = 0;
+        while x < end {
+            let run = levels[x..end].iter().take_while(|&&l| l == levels[x]).count();
+            if run > 1 {
+                out.push_str(&run.to_string());
+            }
+            out.push_str(&symbol(levels[x]));
+            x += run;
+        }
+    }
+    out.push('!');
+    out
+}
+
+impl Pattern {
+    /// The creature in the `2 * half + 1` square around `center` (pixels,
+    /// wrapping), cropped to its living cells, with the mass-weighted mean
+    /// genome. None when the square holds no living cell.
+    pub fn extract(snap: &BufferSnapshot, center: [u32; 2], half: u32, name: &str) -> Option<Self> {
+        let [width, height] = snap.size;
+        let side = (2 * half + 1).min(width).min(height) as usize;
+        let origin = |c: u32, size: u32| (c + size - (side as u32 / 2) % size) % size;
+        let (x0, y0) = (origin(center[0], width), origin(center[1], height));
+        let pixel = |x: usize, y: usize| {
+            let wx = (x0 as usize + x) % width as usize;
+            let wy = (y0 as usize + y) % height as usize;
+            wy * width as usize + wx
+        };
+
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (side, side, 0, 0);
+        let mut genome = [0.0f64; 4];
+        let mut mutation_rate = 0.0f64;
+        let mut total = 0.0f64;
+        for y in 0..side {
+            for x in 0..side {
+                let i = pixel(x, y);
+                let m = snap.mass[i];
+                if m <= EXPORT_MIN_MASS {
+                    continue;
+                }
+                (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+                for (k, g) in genome.iter_mut().enumerate() {
+                    *g += (snap.genome_a[i * 4 + k] * m) as f64;
+                }
+                mutation_rate += (snap.genome_b[i] * m) as f64;
+                total += m as f64;
+            }
+        }
+        if total == 0.0 {
+            return None;
+        }
+
+        let (width, height) = (max_x - min_x + 1, max_y - min_y + 1);
+        let cells = (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+            .map(|(x, y)| snap.mass[pixel(x, y)])
+            .collect();
+        Some(Self {
+            name: name.to_string(),
+            genome: genome.map(|g| (g / total) as f32),
+            mutation_rate: (mutation_rate / total) as f32,
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Whether the pattern fits in a `size` world without overlapping
+    /// itself.
+    pub fn fits(&self, [width, height]: [u32; 2]) -> Result<(), String> {
+        if self.width > width as usize || self.height > height as usize {
+            return Err(format!("pattern {}x{} does not fit the {}x{} world", self.width, self.height, width, height));
+        }
+        Ok(())
+    }
+
+    /// Write the pattern centered on `center` (pixels, wrapping): its box is
+    /// cleared, then living cells get its mass, genome and fresh energy.
+    pub fn stamp(&self, snap: &mut BufferSnapshot, center: [u32; 2]) {
+        let [width, height] = snap.size.map(|edge| edge as usize);
+        let x0 = center[0] as usize + width - self.width / 2;
+        let y0 = center[1] as usize + height - self.height / 2;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let wx = (x0 + x) % width;
+                let wy = (y0 + y) % height;
+                let i = wy * width + wx;
+                let m = self.cells[y * self.width + x];
+                snap.mass[i] = m;
+                if m > 0.0 {
+                    snap.energy[i] = IMPORT_ENERGY;
+                    snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&self.genome);
+                    snap.genome_b[i] = self.mutation_rate;
+                }
+            }
+        }
+    }
+
+    /// The pattern in Lenia's format, with the `evolenia` block.
+    pub fn to_json(&self) -> String {
+        let [radius, mu, sigma, aggressivity] = self.genome;
+        let file = PatternFile {
+            name: self.name.clone(),
+            code: String::new(),
+            params: LeniaParams {
+                radius: radius.round(),
+                time_scale: default_time_scale().round(),
+                b: default_rings(),
+                m: mu,
+                s: sigma,
+                kn: LENIA_EXPONENTIAL,
+                gn: LENIA_EXPONENTIAL,
+            },
+            cells: LeniaCells::Rle(encode_rle(self.width, &self.cells)),
+            evolenia: Some(EvoLeniaGenes {
+                version: PATTERN_SCHEMA_VERSION,
+                radius,
+                aggressivity,
+                mutation_rate: self.mutation_rate,
+            }),
+        };
+        serde_json::to_string_pretty(&file).expect("pattern serializes")
+    }
+}
+
+// ======================== Files ========================
+
+/// Path of the pattern file `name` in PATTERN_DIR.
+pub fn pattern_path(name: &str) -> PathBuf {
+    Path::new(PATTERN_DIR).join(format!("{}.json", name))
+}
+
+pub fn load_pattern(path: &Path) -> Result<(Pattern, Vec<String>), String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_pattern(&json)
+}
+
+pub fn save_pattern(path: &Path, pattern: &Pattern) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, pattern.to_json()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,258 @@
+// ============================================================================
+// perturbation.rs — EvoLenia v2
+// Perturbation types and their behaviour. Each type is a Perturbation
+// implementation: its names, the uniform of one application and the WGSL
+// statements the perturbation pass runs on a cell. compute_perturbation.wgsl
+// only locates the cell; `with_perturbations` appends one function per type
+// and the switch on `params.kind` that calls them, numbered in
+// `PerturbationType::all()` order. A new perturbation is a variant, an impl
+// and its locale keys here, not an edit to the config, app, world and shader.
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SimulationParams;
+use crate::world::PerturbationParams;
+
+/// Perturbation types for ecological experiments.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PerturbationType {
+    None,
+    Drought,         // reduces resources in area
+    NutrientPulse,   // boosts resources in area
+    MassStorm,       // randomizes mass/energy in area (catastrophe)
+    MutationBurst,   // locally amplifies mutation rate
+    Barrier,         // temporary walls splitting the world into two isolated halves
+}
+
+impl PerturbationType {
+    pub fn all() -> &'static [PerturbationType] {
+        &[
+            PerturbationType::None,
+            PerturbationType::Drought,
+            PerturbationType::NutrientPulse,
+            PerturbationType::MassStorm,
+            PerturbationType::MutationBurst,
+            PerturbationType::Barrier,
+        ]
+    }
+
+    /// Implementation of this type.
+    pub fn plugin(&self) -> &'static dyn Perturbation {
+        match self {
+            PerturbationType::None => &NoPerturbation,
+            PerturbationType::Drought => &Drought,
+            PerturbationType::NutrientPulse => &NutrientPulse,
+            PerturbationType::MassStorm => &MassStorm,
+            PerturbationType::MutationBurst => &MutationBurst,
+            PerturbationType::Barrier => &Walls,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.plugin().name()
+    }
+
+    /// Translation key for the UI label; `name()` stays English for logs.
+    pub fn label_key(&self) -> &'static str {
+        self.plugin().label_key()
+    }
+
+    /// Kind code read by the perturbation pass: the position in `all()`.
+    pub fn shader_kind(&self) -> u32 {
+        Self::all().iter().position(|t| t == self).unwrap_or(0) as u32
+    }
+}
+
+/// Behaviour of a perturbation type.
+pub trait Perturbation: Sync {
+    /// English name, for logs and events.
+    fn name(&self) -> &'static str;
+
+    /// Translation keys of the UI label and of the description under it.
+    fn label_key(&self) -> &'static str;
+    fn description_key(&self) -> &'static str;
+
+    /// Uniform of one application at `intensity` to a `size` world; by
+    /// default the configured disc, in pixels. `extra` carries type-specific
+    /// values.
+    fn uniform(&self, params: &SimulationParams, intensity: f32, size: [u32; 2]) -> PerturbationParams {
+        PerturbationParams::disc(params, intensity, size)
+    }
+
+    /// WGSL statements applied to every cell, or None for no effect. They
+    /// see `i` (cell index), `dx`, `dy` (wrapped offset from the center, in
+    /// pixels), `params`, `cells` and `resource_map`; `disc_dose(dx, dy)`
+    /// is the intensity with linear falloff, 0 outside the radius.
+    fn wgsl(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether it can run every step at a fraction of its intensity.
+    fn continuous(&self) -> bool {
+        self.wgsl().is_some()
+    }
+
+    /// Whether Apply raises standing walls (world.rs Barrier) that the pass
+    /// repeats every step until they lift, instead of applying once.
+    fn barrier(&self) -> bool {
+        false
+    }
+}
+
+struct NoPerturbation;
+
+impl Perturbation for NoPerturbation {
+    fn name(&self) -> &'static str {
+        "None"
+    }
+    fn label_key(&self) -> &'static str {
+        "perturb.none"
+    }
+    fn description_key(&self) -> &'static str {
+        "perturb.desc.none"
+    }
+}
+
+struct Drought;
+
+impl Perturbation for Drought {
+    fn name(&self) -> &'static str {
+        "Drought"
+    }
+    fn label_key(&self) -> &'static str {
+        "perturb.drought"
+    }
+    fn description_key(&self) -> &'static str {
+        "perturb.desc.drought"
+    }
+    fn wgsl(&self) -> Option<&'static str> {
+        Some(
+            "let dose = disc_dose(dx, dy);
+    if (dose > 0.0) {
+        resource_map[i] = max(resource_map[i] * (1.0 - dose * 0.8), 0.01);
+    }",
+        )
+    }
+}
+
+struct NutrientPulse;
+
+impl Perturbation for NutrientPulse {
+    fn name(&self) -> &'static str {
+        "Nutrient Pulse"
+    }
+    fn label_key(&self) -> &'static str {
+        "perturb.nutrient_pulse"
+    }
+    fn description_key(&self) -> &'static str {
+        "perturb.desc.nutrient_pulse"
+    }
+    fn wgsl(&self) -> Option<&'static str> {
+        Some(
+            "let dose = disc_dose(dx, dy);
+    if (dose > 0.0) {
+        resource_map[i] = min(resource_map[i] + dose * 0.5, 1.0);
+    }",
+        )
+    }
+}
+
+struct MassStorm;
+
+impl Perturbation for MassStorm {
+    fn name(&self) -> &'static str {
+        "Mass Storm"
+    }
+    fn label_key(&self) -> &'static str {
+        "perturb.mass_storm"
+    }
+    fn description_key(&self) -> &'static str {
+        "perturb.desc.mass_storm"
+    }
+    fn wgsl(&self) -> Option<&'static str> {
+        // Catastrophe: reduce mass and energy
+        Some(
+            "let kill = disc_dose(dx, dy) * 0.7;
+    cells[i].mass *= 1.0 - kill;
+    cells[i].energy *= 1.0 - kill * 0.5;",
+        )
+    }
+}
+
+struct MutationBurst;
+
+impl Perturbation for MutationBurst {
+    fn name(&self) -> &'static str {
+        "Mutation Burst"
+    }
+    fn label_key(&self) -> &'static str {
+        "perturb.mutation_burst"
+    }
+    fn description_key(&self) -> &'static str {
+        "perturb.desc.mutation_burst"
+    }
+    fn wgsl(&self) -> Option<&'static str> {
+        // Amplify the mutation rate of live cells (genome B)
+        Some(
+            "let dose = disc_dose(dx, dy);
+    if (dose > 0.0 && cells[i].mass > 0.01) {
+        cells[i].mutation_rate = min(cells[i].mutation_rate + dose * 0.005, 0.01);
+    }",
+        )
+    }
+}
+
+/// Two full-height walls of half-width `radius`, at column center_x and
+/// half a world away.
+struct Walls;
+
+impl Perturbation for Walls {
+    fn name(&self) -> &'static str {
+        "Barrier"
+    }
+    fn label_key(&self) -> &'static str {
+        "perturb.barrier"
+    }
+    fn description_key(&self) -> &'static str {
+        "perturb.desc.barrier"
+    }
+    fn uniform(&self, params: &SimulationParams, _intensity: f32, size: [u32; 2]) -> PerturbationParams {
+        PerturbationParams::barrier(params.perturbation_center_x * size[0] as f32)
+    }
+    fn wgsl(&self) -> Option<&'static str> {
+        Some(
+            "let far = wrapped(dx + f32(WORLD_WIDTH) * 0.5, f32(WORLD_WIDTH));
+    if (min(abs(dx), abs(far)) < params.radius) {
+        cells[i].mass = 0.0;
+        cells[i].energy = 0.0;
+    }",
+        )
+    }
+    fn continuous(&self) -> bool {
+        false
+    }
+    fn barrier(&self) -> bool {
+        true
+    }
+}
+
+/// `source` (compute_perturbation.wgsl) with a `perturb_<kind>` function per
+/// type that has WGSL and the `apply_perturbation` dispatch over them.
+pub fn with_perturbations(source: &str) -> String {
+    let mut functions = String::new();
+    let mut cases = String::new();
+    for kind in PerturbationType::all() {
+        if let Some(body) = kind.plugin().wgsl() {
+            let code = kind.shader_kind();
+            functions.push_str(&format!(
+                "\n// {}\nfn perturb_{code}(i: u32, dx: f32, dy: f32) {{\n    {body}\n}}\n",
+                kind.name()
+            ));
+            cases.push_str(&format!("        case {code}u: {{ perturb_{code}(i, dx, dy); }}\n"));
+        }
+    }
+    format!(
+        "{source}{functions}\nfn apply_perturbation(i: u32, dx: f32, dy: f32) {{\n    switch params.kind {{\n{cases}        default: {{}}\n    }}\n}}\n"
+    )
+}
@@ -1,12 +1,28 @@
 // ============================================================================
 // pipeline.rs — EvoLenia v2
 // GPU pipeline creation (compute & render) and bind-group-layout helpers.
+// The cell state (mass, energy, genome B) is one interleaved storage buffer
+// per ping-pong side, read-only where a pass only reads it; genome A is
+// bound as a sampled texture when read and as a storage texture when
+// written (see world.rs). Every shader addresses the cell buffers, so all of
+// them get the shared indexing.wgsl prelude for the world's cell layout, and
+// every compute shader gets specialization.wgsl:
+// world size and workgroup dimensions are pipeline-override constants. The
+// velocity and evolution shaders share the velocity rule (velocity_field.wgsl).
 // ============================================================================
 
+use std::collections::HashMap;
+
 use wgpu::util::DeviceExt;
 
 use crate::camera::CameraUniforms;
-use crate::world::WorldState;
+use crate::channels::{layout_entries, with_channels, ChannelPass};
+use crate::perturbation::with_perturbations;
+use crate::user_modes::with_user_mode;
+use crate::world::{
+    CellLayout, GridType, WorldState, CONV_FIELD_FORMAT, GENOME_FIELD_FORMAT, WORKGROUP_LINEAR, WORKGROUP_X,
+    WORKGROUP_Y,
+};
 
 // ======================== Pipelines ========================
 
@@ -15,6 +31,11 @@ pub struct Pipelines {
     pub velocity_pipeline: wgpu::ComputePipeline,
     pub velocity_bind_groups: [wgpu::BindGroup; 2],
 
+    pub separable_h_pipeline: wgpu::ComputePipeline,
+    pub separable_h_bind_groups: [wgpu::BindGroup; 2],
+    pub separable_v_pipeline: wgpu::ComputePipeline,
+    pub separable_v_bind_group: wgpu::BindGroup,
+
     pub evolution_pipeline: wgpu::ComputePipeline,
     pub evolution_bind_groups: [wgpu::BindGroup; 2],
 
@@ -25,25 +46,72 @@ pub struct Pipelines {
     pub normalize_pipeline: wgpu::ComputePipeline,
     pub normalize_bind_groups: [wgpu::BindGroup; 2],
 
+    pub trail_pipeline: wgpu::ComputePipeline,
+    pub trail_bind_groups: [wgpu::BindGroup; 2],
+    pub species_pipeline: wgpu::ComputePipeline,
+    pub species_bind_groups: [wgpu::BindGroup; 2],
+    pub histogram_pipeline: wgpu::ComputePipeline,
+    pub histogram_bind_groups: [wgpu::BindGroup; 2],
+
+    pub average_pipeline: wgpu::ComputePipeline,
+    pub average_bind_groups: [wgpu::BindGroup; 2],
+
+    pub perturbation_pipeline: wgpu::ComputePipeline,
+    pub perturbation_bind_groups: [wgpu::BindGroup; 2],
+    pub barrier_bind_groups: [wgpu::BindGroup; 2],
+
+    pub particle_apply_pipeline: wgpu::ComputePipeline,
+    pub particle_apply_bind_groups: [wgpu::BindGroup; 2],
+    pub particle_update_pipeline: wgpu::ComputePipeline,
+    pub particle_update_bind_groups: [wgpu::BindGroup; 2],
+
     pub render_pipeline: wgpu::RenderPipeline,
+    pub surface_pipeline: wgpu::RenderPipeline, // 3D surface view, same bindings
     pub render_bind_groups: [wgpu::BindGroup; 2],
+    render_layout: wgpu::PipelineLayout,
+    render_source: String, // composed render shader, base of the user mode variants
+    color_format: wgpu::TextureFormat,
 
     pub camera_buffer: wgpu::Buffer,
 }
 
+impl Pipelines {
+    /// Flat and 3D surface pipelines of the render shader with its user
+    /// mode stub replaced by `mode_source` (user_modes.rs); they share the
+    /// render bind groups.
+    pub fn create_user_views(&self, device: &wgpu::Device, mode_source: &str) -> [wgpu::RenderPipeline; 2] {
+        let shader = load_shader(device, "render_user_mode", &with_user_mode(&self.render_source, mode_source));
+        [("user_mode_pipeline", "fs_main"), ("user_mode_surface_pipeline", "fs_surface")]
+            .map(|(label, fs_entry)| view_pipeline(device, &self.render_layout, &shader, self.color_format, label, fs_entry))
+    }
+}
+
 // ======================== Pipeline Creation ========================
 
 pub fn create_pipelines(
     device: &wgpu::Device,
     world: &WorldState,
-    surface_format: wgpu::TextureFormat,
+    color_format: wgpu::TextureFormat,
 ) -> Pipelines {
     // ---- Load shaders ----
-    let velocity_shader = load_shader(device, "compute_velocity", include_str!("shaders/compute_velocity.wgsl"));
-    let evolution_shader = load_shader(device, "compute_evolution", include_str!("shaders/compute_evolution.wgsl"));
-    let resources_shader = load_shader(device, "compute_resources", include_str!("shaders/compute_resources.wgsl"));
-    let normalize_shader = load_shader(device, "normalize_mass", include_str!("shaders/normalize_mass.wgsl"));
-    let render_shader = load_shader(device, "render", include_str!("shaders/render.wgsl"));
+    let indexed = |source: &str| with_indexing(world.layout, world.grid, source);
+    let compute = |source: &str| with_specialization(&indexed(source));
+    let velocity_shader = load_shader(device, "compute_velocity", &compute(&with_velocity_field(include_str!("shaders/compute_velocity.wgsl"))));
+    let separable_shader = load_shader(device, "compute_separable", &compute(include_str!("shaders/compute_separable.wgsl")));
+    let evolution_shader = load_shader(device, "compute_evolution", &compute(&with_channels(ChannelPass::Evolution, &with_velocity_field(include_str!("shaders/compute_evolution.wgsl")))));
+    let resources_shader = load_shader(device, "compute_resources", &compute(include_str!("shaders/compute_resources.wgsl")));
+    let normalize_shader = load_shader(device, "normalize_mass", &compute(include_str!("shaders/normalize_mass.wgsl")));
+    let trail_shader = load_shader(device, "compute_trail", &compute(&with_channels(ChannelPass::Trail, include_str!("shaders/compute_trail.wgsl"))));
+    let species_shader = load_shader(device, "compute_species", &compute(&with_channels(ChannelPass::Species, include_str!("shaders/compute_species.wgsl"))));
+    let histogram_shader = load_shader(device, "compute_histogram", &compute(include_str!("shaders/compute_histogram.wgsl")));
+    let average_shader = load_shader(device, "compute_average", &compute(include_str!("shaders/compute_average.wgsl")));
+    let perturbation_shader = load_shader(device, "compute_perturbation", &compute(&with_perturbations(include_str!("shaders/compute_perturbation.wgsl"))));
+    let particles_shader = load_shader(device, "compute_particles", &compute(include_str!("shaders/compute_particles.wgsl")));
+    let render_source = indexed(&with_channels(ChannelPass::Render, include_str!("shaders/render.wgsl")));
+    let render_shader = load_shader(device, "render", &render_source);
+
+    let fields = FieldViews::new(world);
+    let constants = specialization_constants(world.size[0], world.size[1]);
 
     // ================================================================
     // VELOCITY PIPELINE
@@ -53,12 +121,13 @@ pub fn create_pipelines(
         entries: &[
             bgl_uniform(0),
             bgl_storage_ro(1),
-            bgl_storage_ro(2),
+            bgl_field(2, false),
             bgl_storage_rw(3),
+            bgl_field(4, false),
         ],
     });
 
-    let velocity_pipeline = create_compute_pipeline(device, "velocity", &velocity_bgl, &velocity_shader, "main");
+    let velocity_pipeline = create_compute_pipeline(device, &constants, "velocity", &velocity_bgl, &velocity_shader, "main");
 
     let velocity_bind_groups = [
         device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -66,9 +135,10 @@ pub fn create_pipelines(
             layout: &velocity_bgl,
             entries: &[
                 bg_buffer(0, &world.velocity_params_buffer),
-                bg_buffer(1, &world.mass[0]),
-                bg_buffer(2, &world.genome_a[0]),
+                bg_buffer(1, &world.cells[0]),
+                bg_view(2, &fields.genome_a[0]),
                 bg_buffer(3, &world.velocity),
+                bg_view(4, &fields.flow_field),
             ],
         }),
         device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -76,73 +146,124 @@ pub fn create_pipelines(
             layout: &velocity_bgl,
             entries: &[
                 bg_buffer(0, &world.velocity_params_buffer),
-                bg_buffer(1, &world.mass[1]),
-                bg_buffer(2, &world.genome_a[1]),
+                bg_buffer(1, &world.cells[1]),
+                bg_view(2, &fields.genome_a[1]),
                 bg_buffer(3, &world.velocity),
+                bg_view(4, &fields.flow_field),
             ],
         }),
     ];
 
     // ================================================================
-    // EVOLUTION PIPELINE
+    // SEPARABLE CONVOLUTION PIPELINES
     // ================================================================
-    let evolution_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("evolution_bgl"),
+    let separable_h_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("separable_h_bgl"),
         entries: &[
-            bgl_uniform(0),
-            bgl_storage_ro(1),
+            bgl_uniform(1),
             bgl_storage_ro(2),
-            bgl_storage_ro(3),
-            bgl_storage_ro(4),
-            bgl_storage_ro(5),
-            bgl_storage_ro(6),
-            bgl_storage_rw(7),
-            bgl_storage_rw(8),
-            bgl_storage_rw(9),
-            bgl_storage_rw(10),
+            bgl_field_out(3, CONV_FIELD_FORMAT),
+            bgl_field_out(4, CONV_FIELD_FORMAT),
+            bgl_field_out(5, CONV_FIELD_FORMAT),
+        ],
+    });
+    let separable_v_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("separable_v_bgl"),
+        entries: &[
+            bgl_uniform(1),
+            bgl_field(6, false),
+            bgl_field(7, false),
+            bgl_field(8, false),
+            bgl_field_out(9, CONV_FIELD_FORMAT),
         ],
     });
 
-    let evolution_pipeline = create_compute_pipeline(device, "evolution", &evolution_bgl, &evolution_shader, "main");
+    let separable_h_pipeline = create_compute_pipeline(device, &constants, "separable_h", &separable_h_bgl, &separable_shader, "blur_h");
+    let separable_v_pipeline = create_compute_pipeline(device, &constants, "separable_v", &separable_v_bgl, &separable_shader, "blur_v");
 
-    let evolution_bind_groups = [
-        // cur=0: read [0], write [1]
+    let separable_h_bind_groups = [0, 1].map(|cur| {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("evolution_bg_0"),
-            layout: &evolution_bgl,
+            label: Some(&format!("separable_h_bg_{cur}")),
+            layout: &separable_h_bgl,
             entries: &[
-                bg_buffer(0, &world.sim_params_buffer),
-                bg_buffer(1, &world.mass[0]),
-                bg_buffer(2, &world.energy[0]),
-                bg_buffer(3, &world.genome_a[0]),
-                bg_buffer(4, &world.genome_b[0]),
-                bg_buffer(5, &world.resource_map),
-                bg_buffer(6, &world.velocity),
-                bg_buffer(7, &world.mass[1]),
-                bg_buffer(8, &world.energy[1]),
-                bg_buffer(9, &world.genome_a[1]),
-                bg_buffer(10, &world.genome_b[1]),
+                bg_buffer(1, &world.separable_kernel_buffer),
+                bg_buffer(2, &world.cells[cur]),
+                bg_view(3, &fields.conv_terms[0]),
+                bg_view(4, &fields.conv_terms[1]),
+                bg_view(5, &fields.conv_terms[2]),
             ],
-        }),
-        // cur=1: read [1], write [0]
+        })
+    });
+    let separable_v_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("separable_v_bg"),
+        layout: &separable_v_bgl,
+        entries: &[
+            bg_buffer(1, &world.separable_kernel_buffer),
+            bg_view(6, &fields.conv_terms[0]),
+            bg_view(7, &fields.conv_terms[1]),
+            bg_view(8, &fields.conv_terms[2]),
+            bg_view(9, &fields.conv_potential),
+        ],
+    });
+
+    // ================================================================
+    // EVOLUTION PIPELINE
+    // ================================================================
+    let evolution_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("evolution_bgl"),
+        entries: &[
+            vec![
+                bgl_uniform(0),
+                bgl_storage_ro(1),
+                bgl_field(2, false),
+                bgl_storage_ro(3),
+                bgl_storage_rw(4),
+                bgl_storage_rw(5),
+                bgl_field_out(6, GENOME_FIELD_FORMAT),
+                bgl_field(7, false),
+                bgl_uniform(8),
+                bgl_storage_rw(9),
+                bgl_storage_ro(10),
+                bgl_storage_rw(11),
+                bgl_field(12, false),
+                bgl_field(13, false),
+                bgl_uniform(14),
+            ],
+            layout_entries(ChannelPass::Evolution),
+        ]
+        .concat(),
+    });
+
+    let evolution_pipeline = create_compute_pipeline(device, &constants, "evolution", &evolution_bgl, &evolution_shader, "main");
+
+    // cur=0: read [0], write [1]; cur=1: read [1], write [0]
+    let evolution_bind_groups = [0, 1].map(|cur| {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("evolution_bg_1"),
+            label: Some(&format!("evolution_bg_{cur}")),
             layout: &evolution_bgl,
             entries: &[
-                bg_buffer(0, &world.sim_params_buffer),
-                bg_buffer(1, &world.mass[1]),
-                bg_buffer(2, &world.energy[1]),
-                bg_buffer(3, &world.genome_a[1]),
-                bg_buffer(4, &world.genome_b[1]),
-                bg_buffer(5, &world.resource_map),
-                bg_buffer(6, &world.velocity),
-                bg_buffer(7, &world.mass[0]),
-                bg_buffer(8, &world.energy[0]),
-                bg_buffer(9, &world.genome_a[0]),
-                bg_buffer(10, &world.genome_b[0]),
-            ],
-        }),
-    ];
+                vec![
+                    bg_buffer(0, &world.sim_params_buffer),
+                    bg_buffer(1, &world.cells[cur]),
+                    bg_view(2, &fields.genome_a[cur]),
+                    bg_buffer(3, &world.resource_map),
+                    bg_buffer(4, &world.velocity),
+                    bg_buffer(5, &world.cells[1 - cur]),
+                    bg_view(6, &fields.genome_a[1 - cur]),
+                    bg_view(7, &fields.conv_potential),
+                    bg_buffer(8, &world.separable_kernel_buffer),
+                    bg_buffer(9, &world.conv_error),
+                    bg_buffer(10, &world.kernel_lut),
+                    bg_buffer(11, &world.interaction_counts),
+                    bg_view(12, &fields.flow_field),
+                    bg_view(13, &fields.reserve_mask),
+                    bg_buffer(14, &world.reserve_params_buffer),
+                ],
+                world.channels.bind_entries(ChannelPass::Evolution, cur),
+            ]
+            .concat(),
+        })
+    });
 
     // ================================================================
     // RESOURCES PIPELINE
@@ -153,10 +274,11 @@ pub fn create_pipelines(
             bgl_uniform(0),
             bgl_storage_ro(1),
             bgl_storage_rw(2),
+            bgl_field(3, false),
         ],
     });
 
-    let resources_pipeline = create_compute_pipeline(device, "resources", &resources_bgl, &resources_shader, "main");
+    let resources_pipeline = create_compute_pipeline(device, &constants, "resources", &resources_bgl, &resources_shader, "main");
 
     // After evolution, the "next" buffer has new mass.
     // cur=0 → evolution wrote to [1], so resources reads [1]
@@ -166,8 +288,9 @@ pub fn create_pipelines(
             layout: &resources_bgl,
             entries: &[
                 bg_buffer(0, &world.resource_params_buffer),
-                bg_buffer(1, &world.mass[1]),
+                bg_buffer(1, &world.cells[1]),
                 bg_buffer(2, &world.resource_map),
+                bg_view(3, &fields.flow_field),
             ],
         }),
         device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -175,8 +298,9 @@ pub fn create_pipelines(
             layout: &resources_bgl,
             entries: &[
                 bg_buffer(0, &world.resource_params_buffer),
-                bg_buffer(1, &world.mass[0]),
+                bg_buffer(1, &world.cells[0]),
                 bg_buffer(2, &world.resource_map),
+                bg_view(3, &fields.flow_field),
             ],
         }),
     ];
@@ -204,7 +328,7 @@ pub fn create_pipelines(
         layout: Some(&normalize_layout),
         module: &normalize_shader,
         entry_point: Some("sum_mass"),
-        compilation_options: Default::default(),
+        compilation_options: specialized(&constants),
         cache: None,
     });
 
@@ -213,7 +337,7 @@ pub fn create_pipelines(
         layout: Some(&normalize_layout),
         module: &normalize_shader,
         entry_point: Some("normalize"),
-        compilation_options: Default::default(),
+        compilation_options: specialized(&constants),
         cache: None,
     });
 
@@ -224,7 +348,7 @@ pub fn create_pipelines(
             layout: &normalize_bgl,
             entries: &[
                 bg_buffer(0, &world.normalize_params_buffer),
-                bg_buffer(1, &world.mass[1]),
+                bg_buffer(1, &world.cells[1]),
                 bg_buffer(2, &world.mass_sum),
             ],
         }),
@@ -233,62 +357,289 @@ pub fn create_pipelines(
             layout: &normalize_bgl,
             entries: &[
                 bg_buffer(0, &world.normalize_params_buffer),
-                bg_buffer(1, &world.mass[0]),
+                bg_buffer(1, &world.cells[0]),
                 bg_buffer(2, &world.mass_sum),
             ],
         }),
     ];
 
     // ================================================================
-    // RENDER PIPELINE
+    // TRAIL PIPELINE (visualization only)
     // ================================================================
-    let render_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("render_bgl"),
+    let trail_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("trail_bgl"),
+        entries: &[
+            vec![bgl_uniform(0), bgl_storage_ro(1), bgl_storage_ro(2)],
+            layout_entries(ChannelPass::Trail),
+        ]
+        .concat(),
+    });
+
+    let trail_pipeline = create_compute_pipeline(device, &constants, "trail", &trail_bgl, &trail_shader, "main");
+
+    // Runs after a step with the given `cur`, so it imprints that step's output mass
+    let trail_bind_groups = [
+        // cur=0: step wrote cells[1]
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("trail_bg_0"),
+            layout: &trail_bgl,
+            entries: &[
+                vec![
+                    bg_buffer(0, &world.trail_params_buffer),
+                    bg_buffer(1, &world.cells[1]),
+                    bg_buffer(2, &world.velocity),
+                ],
+                world.channels.bind_entries(ChannelPass::Trail, 0),
+            ]
+            .concat(),
+        }),
+        // cur=1: step wrote cells[0]
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("trail_bg_1"),
+            layout: &trail_bgl,
+            entries: &[
+                vec![
+                    bg_buffer(0, &world.trail_params_buffer),
+                    bg_buffer(1, &world.cells[0]),
+                    bg_buffer(2, &world.velocity),
+                ],
+                world.channels.bind_entries(ChannelPass::Trail, 1),
+            ]
+            .concat(),
+        }),
+    ];
+
+    // ================================================================
+    // SPECIES MAP PIPELINE (visualization and analysis only)
+    // ================================================================
+    let species_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("species_bgl"),
+        entries: &[
+            vec![bgl_uniform(0), bgl_storage_ro(1), bgl_field(2, false), bgl_storage_ro(3)],
+            layout_entries(ChannelPass::Species),
+        ]
+        .concat(),
+    });
+
+    let species_pipeline = create_compute_pipeline(device, &constants, "species", &species_bgl, &species_shader, "main");
+
+    // Indexed like the render bind groups: labels the state they draw
+    let species_bind_groups = [0, 1].map(|side| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("species_bg_{side}")),
+            layout: &species_bgl,
+            entries: &[
+                vec![
+                    bg_buffer(0, &world.species_params_buffer),
+                    bg_buffer(1, &world.cells[1 - side]),
+                    bg_view(2, &fields.genome_a[1 - side]),
+                    bg_buffer(3, &world.species_centroids),
+                ],
+                world.channels.bind_entries(ChannelPass::Species, side),
+            ]
+            .concat(),
+        })
+    });
+
+    // ================================================================
+    // HISTOGRAM PIPELINE (visualization only)
+    // ================================================================
+    let histogram_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("histogram_bgl"),
+        entries: &[bgl_uniform(0), bgl_storage_ro(1), bgl_storage_ro(2), bgl_storage_rw(3)],
+    });
+
+    let histogram_pipeline = create_compute_pipeline(device, &constants, "histogram", &histogram_bgl, &histogram_shader, "main");
+
+    // Indexed like the render bind groups: bins the state they draw
+    let histogram_bind_groups = [0, 1].map(|side| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("histogram_bg_{side}")),
+            layout: &histogram_bgl,
+            entries: &[
+                bg_buffer(0, &world.histogram_params_buffer),
+                bg_buffer(1, &world.cells[1 - side]),
+                bg_buffer(2, &world.resource_map),
+                bg_buffer(3, &world.histogram_bins),
+            ],
+        })
+    });
+
+    // ================================================================
+    // AVERAGING PIPELINE (analysis only)
+    // ================================================================
+    let average_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("average_bgl"),
         entries: &[
             bgl_uniform(0),
             bgl_storage_ro(1),
             bgl_storage_ro(2),
             bgl_storage_ro(3),
-            bgl_uniform(4),
+            bgl_storage_rw(4),
+        ],
+    });
+
+    let average_pipeline = create_compute_pipeline(device, &constants, "average", &average_bgl, &average_shader, "main");
+
+    // Like the trail: runs after a step with the given `cur`, on its output
+    let average_bind_groups = [0, 1].map(|cur| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("average_bg_{cur}")),
+            layout: &average_bgl,
+            entries: &[
+                bg_buffer(0, &world.average_params_buffer),
+                bg_buffer(1, &world.cells[1 - cur]),
+                bg_buffer(2, &world.resource_map),
+                bg_buffer(3, &world.velocity),
+                bg_buffer(4, &world.field_average),
+            ],
+        })
+    });
+
+    // ================================================================
+    // PERTURBATION PIPELINE (edits the current state in place)
+    // ================================================================
+    let perturbation_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("perturbation_bgl"),
+        entries: &[
+            bgl_uniform(0),
+            bgl_storage_rw(1),
+            bgl_storage_rw(2),
+        ],
+    });
+
+    let perturbation_pipeline =
+        create_compute_pipeline(device, &constants, "perturbation", &perturbation_bgl, &perturbation_shader, "main");
+
+    // Barriers share the pipeline but keep their own uniform, so they can run
+    // in the same step as a continuous perturbation
+    let perturbation_groups = |name: &str, params: &wgpu::Buffer| [0, 1].map(|cur| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{name}_bg_{cur}")),
+            layout: &perturbation_bgl,
+            entries: &[
+                bg_buffer(0, params),
+                bg_buffer(1, &world.cells[cur]),
+                bg_buffer(2, &world.resource_map),
+            ],
+        })
+    });
+    let perturbation_bind_groups = perturbation_groups("perturbation", &world.perturbation_params_buffer);
+    let barrier_bind_groups = perturbation_groups("barrier", &world.barrier_params_buffer);
+
+    // ================================================================
+    // PARTICLE PIPELINES (apply last step's flux, then move the particles;
+    // both on the current state, in place)
+    // ================================================================
+    let particle_apply_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("particle_apply_bgl"),
+        entries: &[
+            bgl_uniform(0),
+            bgl_storage_rw(1),
+            bgl_storage_rw(2),
+            bgl_storage_rw(3),
+            bgl_storage_rw(4),
+            bgl_storage_rw(7),
+            bgl_field_out(8, GENOME_FIELD_FORMAT),
+        ],
+    });
+    let particle_update_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("particle_update_bgl"),
+        entries: &[
+            bgl_uniform(0),
+            bgl_storage_rw(1),
+            bgl_storage_rw(2),
+            bgl_storage_rw(3),
+            bgl_storage_rw(4),
             bgl_storage_ro(5),
-            bgl_storage_ro(6),
+            bgl_field(6, false),
         ],
     });
 
+    let particle_apply_pipeline =
+        create_compute_pipeline(device, &constants, "particle_apply", &particle_apply_bgl, &particles_shader, "apply");
+    let particle_update_pipeline =
+        create_compute_pipeline(device, &constants, "particle_update", &particle_update_bgl, &particles_shader, "update");
+
+    let particle_buffers = [
+        bg_buffer(0, &world.particle_params_buffer),
+        bg_buffer(1, &world.particles),
+        bg_buffer(2, &world.particle_flux),
+        bg_buffer(3, &world.particle_claims),
+        bg_buffer(4, &world.particle_markers),
+    ];
+    let particle_apply_bind_groups = [0, 1].map(|cur| {
+        let mut entries = particle_buffers.to_vec();
+        entries.extend([
+            bg_buffer(7, &world.cells[cur]),
+            bg_view(8, &fields.genome_a[cur]),
+        ]);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("particle_apply_bg_{cur}")),
+            layout: &particle_apply_bgl,
+            entries: &entries,
+        })
+    });
+    let particle_update_bind_groups = [0, 1].map(|cur| {
+        let mut entries = particle_buffers.to_vec();
+        entries.extend([
+            bg_buffer(5, &world.cells[cur]),
+            bg_view(6, &fields.genome_a[cur]),
+        ]);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("particle_update_bg_{cur}")),
+            layout: &particle_update_bgl,
+            entries: &entries,
+        })
+    });
+
+    // ================================================================
+    // RENDER PIPELINE
+    // ================================================================
+    // Bilinear field sampling needs filterable 32-bit float textures; without
+    // the feature, the sampler stays nearest-neighbor.
+    let filterable = smooth_sampling_supported(device);
+    let render_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("render_bgl"),
+        entries: &[
+            vec![
+                bgl_uniform(0),
+                bgl_storage_ro(1),
+                bgl_field(2, filterable),
+                bgl_uniform(3),
+                bgl_storage_ro(4),
+                bgl_storage_ro(5),
+                bgl_storage_ro(6),
+                bgl_uniform(7),
+                bgl_sampler(8, filterable),
+                bgl_field(9, filterable),
+                bgl_field(10, filterable),
+                bgl_storage_ro(11),
+                bgl_field(12, filterable),
+                bgl_storage_ro(13),
+            ],
+            layout_entries(ChannelPass::Render),
+        ]
+        .concat(),
+    });
+    let filter = if filterable { wgpu::FilterMode::Linear } else { wgpu::FilterMode::Nearest };
+    let field_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("field_sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat, // the world is toroidal
+        address_mode_v: wgpu::AddressMode::Repeat,
+        mag_filter: filter,
+        min_filter: filter,
+        ..Default::default()
+    });
+
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("render_pipeline_layout"),
         bind_group_layouts: &[&render_bgl],
         push_constant_ranges: &[],
     });
 
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("render_pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &render_shader,
-            entry_point: Some("vs_main"),
-            buffers: &[],
-            compilation_options: Default::default(),
-        },
-        fragment: Some(wgpu::FragmentState {
-            module: &render_shader,
-            entry_point: Some("fs_main"),
-            targets: &[Some(wgpu::ColorTargetState {
-                format: surface_format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-            compilation_options: Default::default(),
-        }),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            ..Default::default()
-        },
-        depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
-        multiview: None,
-        cache: None,
-    });
+    let render_pipeline = view_pipeline(device, &render_pipeline_layout, &render_shader, color_format, "render_pipeline", "fs_main");
+    let surface_pipeline = view_pipeline(device, &render_pipeline_layout, &render_shader, color_format, "surface_pipeline", "fs_surface");
 
     // Camera uniform buffer
     let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -303,33 +654,59 @@ pub fn create_pipelines(
             label: Some("render_bg_0"),
             layout: &render_bgl,
             entries: &[
-                bg_buffer(0, &world.render_params_buffer),
-                bg_buffer(1, &world.mass[1]),
-                bg_buffer(2, &world.energy[1]),
-                bg_buffer(3, &world.genome_a[1]),
-                bg_buffer(4, &camera_buffer),
-                bg_buffer(5, &world.velocity),
-                bg_buffer(6, &world.resource_map),
-            ],
+                vec![
+                    bg_buffer(0, &world.render_params_buffer),
+                    bg_buffer(1, &world.cells[1]),
+                    bg_view(2, &fields.genome_a[1]),
+                    bg_buffer(3, &camera_buffer),
+                    bg_buffer(4, &world.velocity),
+                    bg_buffer(5, &world.resource_map),
+                    bg_buffer(6, &world.display_lut),
+                    bg_buffer(7, &world.palette_buffer),
+                    bg_sampler(8, &field_sampler),
+                    bg_view(9, &fields.reference_mass),
+                    bg_view(10, &fields.territory),
+                    bg_buffer(11, &world.particle_markers),
+                    bg_view(12, &fields.flow_field),
+                    bg_buffer(13, &world.species_palette),
+                ],
+                world.channels.bind_entries(ChannelPass::Render, 0),
+            ]
+            .concat(),
         }),
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("render_bg_1"),
             layout: &render_bgl,
             entries: &[
-                bg_buffer(0, &world.render_params_buffer),
-                bg_buffer(1, &world.mass[0]),
-                bg_buffer(2, &world.energy[0]),
-                bg_buffer(3, &world.genome_a[0]),
-                bg_buffer(4, &camera_buffer),
-                bg_buffer(5, &world.velocity),
-                bg_buffer(6, &world.resource_map),
-            ],
+                vec![
+                    bg_buffer(0, &world.render_params_buffer),
+                    bg_buffer(1, &world.cells[0]),
+                    bg_view(2, &fields.genome_a[0]),
+                    bg_buffer(3, &camera_buffer),
+                    bg_buffer(4, &world.velocity),
+                    bg_buffer(5, &world.resource_map),
+                    bg_buffer(6, &world.display_lut),
+                    bg_buffer(7, &world.palette_buffer),
+                    bg_sampler(8, &field_sampler),
+                    bg_view(9, &fields.reference_mass),
+                    bg_view(10, &fields.territory),
+                    bg_buffer(11, &world.particle_markers),
+                    bg_view(12, &fields.flow_field),
+                    bg_buffer(13, &world.species_palette),
+                ],
+                world.channels.bind_entries(ChannelPass::Render, 1),
+            ]
+            .concat(),
         }),
     ];
 
     Pipelines {
         velocity_pipeline,
         velocity_bind_groups,
+        separable_h_pipeline,
+        separable_h_bind_groups,
+        separable_v_pipeline,
+        separable_v_bind_group,
         evolution_pipeline,
         evolution_bind_groups,
         resources_pipeline,
@@ -337,23 +714,148 @@ pub fn create_pipelines(
         sum_mass_pipeline,
         normalize_pipeline,
         normalize_bind_groups,
+        trail_pipeline,
+        trail_bind_groups,
+        species_pipeline,
+        species_bind_groups,
+        histogram_pipeline,
+        histogram_bind_groups,
+        average_pipeline,
+        average_bind_groups,
+        perturbation_pipeline,
+        perturbation_bind_groups,
+        barrier_bind_groups,
+        particle_apply_pipeline,
+        particle_apply_bind_groups,
+        particle_update_pipeline,
+        particle_update_bind_groups,
         render_pipeline,
+        surface_pipeline,
         render_bind_groups,
+        render_layout: render_pipeline_layout,
+        render_source,
+        color_format,
         camera_buffer,
     }
 }
 
+/// Whether the device can filter the f32 field textures (bilinear render).
+pub fn smooth_sampling_supported(device: &wgpu::Device) -> bool {
+    device.features().contains(wgpu::Features::FLOAT32_FILTERABLE)
+}
+
+/// Default views of the world's field textures, by ping-pong index.
+struct FieldViews {
+    genome_a: [wgpu::TextureView; 2],
+    conv_terms: [wgpu::TextureView; 3],
+    conv_potential: wgpu::TextureView,
+    reference_mass: wgpu::TextureView,
+    territory: wgpu::TextureView,
+    flow_field: wgpu::TextureView,
+    reserve_mask: wgpu::TextureView,
+}
+
+impl FieldViews {
+    fn new(world: &WorldState) -> Self {
+        let views = |pair: &[wgpu::Texture; 2]| pair.each_ref().map(|t| t.create_view(&Default::default()));
+        Self {
+            genome_a: views(&world.genome_a),
+            conv_terms: world.conv_terms.each_ref().map(|t| t.create_view(&Default::default())),
+            conv_potential: world.conv_potential.create_view(&Default::default()),
+            reference_mass: world.reference_mass.create_view(&Default::default()),
+            territory: world.territory.create_view(&Default::default()),
+            flow_field: world.flow_field.create_view(&Default::default()),
+            reserve_mask: world.reserve_mask.create_view(&Default::default()),
+        }
+    }
+}
+
 // ======================== Helpers ========================
 
-fn load_shader(device: &wgpu::Device, label: &str, source: &str) -> wgpu::ShaderModule {
+pub(crate) fn load_shader(device: &wgpu::Device, label: &str, source: &str) -> wgpu::ShaderModule {
     device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some(label),
         source: wgpu::ShaderSource::Wgsl(source.into()),
     })
 }
 
+/// Prepend the advection velocity rule (velocity_field.wgsl).
+fn with_velocity_field(source: &str) -> String {
+    format!("{}\n{}", include_str!("shaders/velocity_field.wgsl"), source)
+}
+
+/// Prepend the override declarations (specialization.wgsl) of a compute shader.
+fn with_specialization(source: &str) -> String {
+    format!("{}\n{}", include_str!("shaders/specialization.wgsl"), source)
+}
+
+/// Override values for the compute shaders of a `width` × `height` world.
+pub fn specialization_constants(width: u32, height: u32) -> HashMap<String, f64> {
+    [
+        ("WORLD_WIDTH", width),
+        ("WORLD_HEIGHT", height),
+        ("WORKGROUP_X", WORKGROUP_X),
+        ("WORKGROUP_Y", WORKGROUP_Y),
+        ("WORKGROUP_LINEAR", WORKGROUP_LINEAR),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value as f64))
+    .collect()
+}
+
+fn specialized(constants: &HashMap<String, f64>) -> wgpu::PipelineCompilationOptions<'_> {
+    wgpu::PipelineCompilationOptions { constants, ..Default::default() }
+}
+
+/// Prepend the cell-index and lattice helpers (indexing.wgsl) configured for
+/// `layout` and `grid`.
+fn with_indexing(layout: CellLayout, grid: GridType, source: &str) -> String {
+    format!("{}\n{}\n{}\n{}", layout.wgsl_const(), grid.wgsl_const(), include_str!("shaders/indexing.wgsl"), source)
+}
+
+/// Full-screen view pipeline of the render shader; the flat and 3D surface
+/// views differ only in the fragment entry point.
+fn view_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    color_format: wgpu::TextureFormat,
+    label: &str,
+    fs_entry: &str,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fs_entry),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 fn create_compute_pipeline(
     device: &wgpu::Device,
+    constants: &HashMap<String, f64>,
     name: &str,
     bgl: &wgpu::BindGroupLayout,
     module: &wgpu::ShaderModule,
@@ -369,12 +871,12 @@ fn create_compute_pipeline(
         layout: Some(&layout),
         module,
         entry_point: Some(entry_point),
-        compilation_options: Default::default(),
+        compilation_options: specialized(constants),
         cache: None,
     })
 }
 
-fn bgl_uniform(binding: u32) -> wgpu::BindGroupLayoutEntry {
+pub(crate) fn bgl_uniform(binding: u32) -> wgpu::BindGroupLayoutEntry {
     wgpu::BindGroupLayoutEntry {
         binding,
         visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::VERTEX_FRAGMENT,
@@ -387,7 +889,7 @@ fn bgl_uniform(binding: u32) -> wgpu::BindGroupLayoutEntry {
     }
 }
 
-fn bgl_storage_ro(binding: u32) -> wgpu::BindGroupLayoutEntry {
+pub(crate) fn bgl_storage_ro(binding: u32) -> wgpu::BindGroupLayoutEntry {
     wgpu::BindGroupLayoutEntry {
         binding,
         visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::VERTEX_FRAGMENT,
@@ -400,7 +902,7 @@ fn bgl_storage_ro(binding: u32) -> wgpu::BindGroupLayoutEntry {
     }
 }
 
-fn bgl_storage_rw(binding: u32) -> wgpu::BindGroupLayoutEntry {
+pub(crate) fn bgl_storage_rw(binding: u32) -> wgpu::BindGroupLayoutEntry {
     wgpu::BindGroupLayoutEntry {
         binding,
         visibility: wgpu::ShaderStages::COMPUTE,
@@ -413,7 +915,66 @@ fn bgl_storage_rw(binding: u32) -> wgpu::BindGroupLayoutEntry {
     }
 }
 
-fn bg_buffer(binding: u32, buffer: &wgpu::Buffer) -> wgpu::BindGroupEntry<'_> {
+/// Field texture read with textureLoad (or sampled, when `filterable`).
+fn bgl_field(binding: u32, filterable: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn bgl_field_out(binding: u32, format: wgpu::TextureFormat) -> wgpu::BindGroupLayoutEntry {
+    bgl_storage_texture(binding, format, wgpu::StorageTextureAccess::WriteOnly)
+}
+
+fn bgl_storage_texture(
+    binding: u32,
+    format: wgpu::TextureFormat,
+    access: wgpu::StorageTextureAccess,
+) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access,
+            format,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        count: None,
+    }
+}
+
+fn bgl_sampler(binding: u32, filtering: bool) -> wgpu::BindGroupLayoutEntry {
+    let ty = if filtering { wgpu::SamplerBindingType::Filtering } else { wgpu::SamplerBindingType::NonFiltering };
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(ty),
+        count: None,
+    }
+}
+
+fn bg_view(binding: u32, view: &wgpu::TextureView) -> wgpu::BindGroupEntry<'_> {
+    wgpu::BindGroupEntry {
+        binding,
+        resource: wgpu::BindingResource::TextureView(view),
+    }
+}
+
+fn bg_sampler(binding: u32, sampler: &wgpu::Sampler) -> wgpu::BindGroupEntry<'_> {
+    wgpu::BindGroupEntry {
+        binding,
+        resource: wgpu::BindingResource::Sampler(sampler),
+    }
+}
+
+pub(crate) fn bg_buffer(binding: u32, buffer: &wgpu::Buffer) -> wgpu::BindGroupEntry<'_> {
     wgpu::BindGroupEntry {
         binding,
         resource: buffer.as_entire_binding(),
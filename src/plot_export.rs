@@ -0,0 +1,507 @@
+// ============================================================================
+// plot_export.rs — EvoLenia v2 Research Lab
+// Analysis charts as publication figures: white background, labeled axes
+// with rounded ticks, legend, and metadata (run, frame, seed, parameters).
+// A figure is laid out once into shapes, then written as SVG text or
+// rasterized to PNG (tiny-skia paths, ab_glyph text with egui's bundled
+// font). PNG metadata goes into iTXt chunks, SVG metadata into <metadata>.
+// ============================================================================
+
+use std::path::{Path, PathBuf};
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::ImageEncoder;
+use serde::{Deserialize, Serialize};
+
+type Rgb = [u8; 3];
+
+/// Okabe–Ito colors, readable in print and for color-blind readers.
+const SERIES_COLORS: [Rgb; 6] = [
+    [0, 114, 178],
+    [213, 94, 0],
+    [0, 158, 115],
+    [204, 121, 167],
+    [230, 159, 0],
+    [86, 180, 233],
+];
+const INK: Rgb = [30, 30, 30];
+const GRID: Rgb = [225, 225, 225];
+const MARKER: Rgb = [140, 140, 140];
+/// Font of the PNG text (egui's default proportional font).
+pub(crate) const FONT_NAME: &str = "Ubuntu-Light";
+/// Approximate advance of a character, in font sizes (legend box sizing).
+const CHAR_WIDTH: f32 = 0.55;
+/// Export size bounds, in pixels.
+pub const MIN_SIZE: u32 = 200;
+pub const MAX_SIZE: u32 = 8000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlotFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl PlotFormat {
+    pub fn all() -> [PlotFormat; 2] {
+        [PlotFormat::Svg, PlotFormat::Png]
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PlotFormat::Svg => "svg",
+            PlotFormat::Png => "png",
+        }
+    }
+}
+
+/// Output format and size of exported plots.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlotExportSettings {
+    pub format: PlotFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for PlotExportSettings {
+    fn default() -> Self {
+        Self { format: PlotFormat::Svg, width: 1200, height: 800 }
+    }
+}
+
+/// One named line of a figure.
+#[derive(Clone, Debug)]
+pub struct PlotSeries {
+    pub name: String,
+    pub points: Vec<[f64; 2]>,
+}
+
+/// A chart as drawn in the analysis panel, ready to export.
+#[derive(Clone, Debug, Default)]
+pub struct PlotFigure {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub series: Vec<PlotSeries>,
+    pub markers: Vec<f64>,               // x positions of event markers (dashed)
+    pub metadata: Vec<(String, String)>, // embedded in the file, not drawn
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Drawing primitives shared by the SVG and PNG writers (pixel coordinates).
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+    Line { points: Vec<[f32; 2]>, color: Rgb, width: f32, dashed: bool },
+    Rect { min: [f32; 2], size: [f32; 2], fill: Option<Rgb>, stroke: Option<Rgb> },
+    // Centered vertically on `pos`; vertical text reads bottom to top
+    Text { pos: [f32; 2], text: String, size: f32, anchor: Anchor, vertical: bool },
+}
+
+/// Round tick positions covering [min, max], about `target` of them.
+pub fn nice_ticks(min: f64, max: f64, target: usize) -> Vec<f64> {
+    let step = tick_step(min, max, target);
+    if !step.is_finite() || step <= 0.0 {
+        return vec![min];
+    }
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+/// 1, 2 or 5 × 10^k, so that the range spans about `target` steps.
+fn tick_step(min: f64, max: f64, target: usize) -> f64 {
+    let raw = (max - min) / target.max(1) as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let base = match raw / magnitude {
+        r if r < 1.5 => 1.0,
+        r if r < 3.0 => 2.0,
+        r if r < 7.0 => 5.0,
+        _ => 10.0,
+    };
+    base * magnitude
+}
+
+/// Tick label with just enough decimals for the step.
+pub fn format_tick(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).clamp(0.0, 6.0) as usize;
+    let text = format!("{:.*}", decimals, value);
+    // Avoid "-0" / "-0.00" from rounding
+    if text.trim_start_matches('-').chars().all(|c| c == '0' || c == '.') {
+        text.trim_start_matches('-').to_string()
+    } else {
+        text
+    }
+}
+
+/// Data bounds: exact x range, y range padded by 5%; degenerate ranges widened.
+fn bounds(figure: &PlotFigure) -> ([f64; 2], [f64; 2]) {
+    let mut x = [f64::INFINITY, f64::NEG_INFINITY];
+    let mut y = [f64::INFINITY, f64::NEG_INFINITY];
+    for p in figure.series.iter().flat_map(|s| &s.points).filter(|p| p[0].is_finite() && p[1].is_finite()) {
+        x = [x[0].min(p[0]), x[1].max(p[0])];
+        y = [y[0].min(p[1]), y[1].max(p[1])];
+    }
+    let widen = |r: [f64; 2], pad: f64| {
+        if !r[0].is_finite() {
+            [0.0, 1.0]
+        } else if r[1] - r[0] <= f64::EPSILON * r[0].abs().max(1.0) {
+            let d = (r[0].abs() * 0.1).max(1.0);
+            [r[0] - d, r[1] + d]
+        } else {
+            let d = (r[1] - r[0]) * pad;
+            [r[0] - d, r[1] + d]
+        }
+    };
+    (widen(x, 0.0), widen(y, 0.05))
+}
+
+/// Lay the figure out at the given pixel size.
+fn layout(figure: &PlotFigure, width: u32, height: u32) -> Vec<Shape> {
+    let (w, h) = (width as f32, height as f32);
+    let s = (w.min(h) / 600.0).max(0.4);
+    let font = 15.0 * s;
+    let (left, right, top, bottom) = (95.0 * s, 30.0 * s, 55.0 * s, 75.0 * s);
+    let (pw, ph) = ((w - left - right).max(1.0), (h - top - bottom).max(1.0));
+    let (xr, yr) = bounds(figure);
+    let px = |x: f64| left + ((x - xr[0]) / (xr[1] - xr[0])) as f32 * pw;
+    let py = |y: f64| top + ph - ((y - yr[0]) / (yr[1] - yr[0])) as f32 * ph;
+    let text = |pos: [f32; 2], text: &str, size: f32, anchor: Anchor, vertical: bool| Shape::Text {
+        pos,
+        text: text.to_string(),
+        size,
+        anchor,
+        vertical,
+    };
+    let line = |points: Vec<[f32; 2]>, color: Rgb, width: f32, dashed: bool| Shape::Line { points, color, width, dashed };
+
+    let mut shapes = Vec::new();
+    // Grid and tick labels
+    let (x_step, y_step) = (tick_step(xr[0], xr[1], 6), tick_step(yr[0], yr[1], 6));
+    for x in nice_ticks(xr[0], xr[1], 6) {
+        let x_px = px(x);
+        shapes.push(line(vec![[x_px, top], [x_px, top + ph]], GRID, s, false));
+        shapes.push(line(vec![[x_px, top + ph], [x_px, top + ph + 5.0 * s]], INK, s, false));
+        shapes.push(text([x_px, top + ph + 18.0 * s], &format_tick(x, x_step), font * 0.85, Anchor::Middle, false));
+    }
+    for y in nice_ticks(yr[0], yr[1], 6) {
+        let y_px = py(y);
+        shapes.push(line(vec![[left, y_px], [left + pw, y_px]], GRID, s, false));
+        shapes.push(line(vec![[left - 5.0 * s, y_px], [left, y_px]], INK, s, false));
+        shapes.push(text([left - 8.0 * s, y_px], &format_tick(y, y_step), font * 0.85, Anchor::End, false));
+    }
+
+    // Event markers, then data on top
+    for &x in figure.markers.iter().filter(|&&x| x >= xr[0] && x <= xr[1]) {
+        shapes.push(line(vec![[px(x), top], [px(x), top + ph]], MARKER, s, true));
+    }
+    for (i, series) in figure.series.iter().enumerate() {
+        let points: Vec<[f32; 2]> = series
+            .points
+            .iter()
+            .filter(|p| p[0].is_finite() && p[1].is_finite())
+            .map(|p| [px(p[0]), py(p[1])])
+            .collect();
+        if !points.is_empty() {
+            shapes.push(line(points, SERIES_COLORS[i % SERIES_COLORS.len()], 2.0 * s, false));
+        }
+    }
+    shapes.push(Shape::Rect { min: [left, top], size: [pw, ph], fill: None, stroke: Some(INK) });
+
+    // Title and axis labels
+    shapes.push(text([left + pw / 2.0, top / 2.0], &figure.title, font * 1.3, Anchor::Middle, false));
+    shapes.push(text([left + pw / 2.0, h - 22.0 * s], &figure.x_label, font, Anchor::Middle, false));
+    shapes.push(text([24.0 * s, top + ph / 2.0], &figure.y_label, font, Anchor::Middle, true));
+
+    // Legend, top right inside the plot area
+    let named: Vec<(usize, &PlotSeries)> = figure.series.iter().enumerate().filter(|(_, s)| !s.name.is_empty()).collect();
+    if !named.is_empty() {
+        let row = font * 1.4;
+        let swatch = 24.0 * s;
+        let longest = named.iter().map(|(_, s)| s.name.chars().count()).max().unwrap_or(0) as f32;
+        let (bw, bh) = (swatch + 18.0 * s + longest * font * CHAR_WIDTH, row * named.len() as f32 + 8.0 * s);
+        let (bx, by) = (left + pw - bw - 10.0 * s, top + 10.0 * s);
+        shapes.push(Shape::Rect { min: [bx, by], size: [bw, bh], fill: Some([255, 255, 255]), stroke: Some(GRID) });
+        for (row_index, (i, series)) in named.iter().enumerate() {
+            let y = by + 4.0 * s + row * (row_index as f32 + 0.5);
+            let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+            shapes.push(line(vec![[bx + 6.0 * s, y], [bx + 6.0 * s + swatch, y]], color, 2.0 * s, false));
+            shapes.push(text([bx + 12.0 * s + swatch, y], &series.name, font * 0.9, Anchor::Start, false));
+        }
+    }
+    shapes
+}
+
+// ======================== SVG ========================
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn hex(c: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+/// The figure as a standalone SVG document.
+pub fn render_svg(figure: &PlotFigure, width: u32, height: u32) -> String {
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+         font-family=\"Helvetica, Arial, sans-serif\">\n<title>{}</title>\n",
+        xml_escape(&figure.title),
+        w = width,
+        h = height,
+    );
+    let metadata: serde_json::Map<String, serde_json::Value> =
+        figure.metadata.iter().map(|(k, v)| (k.clone(), v.clone().into())).collect();
+    svg += &format!("<metadata>{}</metadata>\n", xml_escape(&serde_json::Value::Object(metadata).to_string()));
+    svg += &format!("<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n", width, height);
+    for shape in layout(figure, width, height) {
+        match shape {
+            Shape::Line { points, color, width, dashed } => {
+                let coords: Vec<String> = points.iter().map(|p| format!("{:.2},{:.2}", p[0], p[1])).collect();
+                let dash = if dashed { format!(" stroke-dasharray=\"{:.1} {:.1}\"", 4.0 * width, 3.0 * width) } else { String::new() };
+                svg += &format!(
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\" stroke-linejoin=\"round\"{}/>\n",
+                    coords.join(" "),
+                    hex(color),
+                    width,
+                    dash
+                );
+            }
+            Shape::Rect { min, size, fill, stroke } => {
+                svg += &format!(
+                    "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"{}\"/>\n",
+                    min[0],
+                    min[1],
+                    size[0],
+                    size[1],
+                    fill.map_or("none".to_string(), hex),
+                    stroke.map_or("none".to_string(), hex)
+                );
+            }
+            Shape::Text { pos, text, size, anchor, vertical } => {
+                let anchor = match anchor {
+                    Anchor::Start => "start",
+                    Anchor::Middle => "middle",
+                    Anchor::End => "end",
+                };
+                let rotate = if vertical { format!(" transform=\"rotate(-90 {:.2} {:.2})\"", pos[0], pos[1]) } else { String::new() };
+                svg += &format!(
+                    "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{:.1}\" text-anchor=\"{}\" dominant-baseline=\"central\" fill=\"{}\"{}>{}</text>\n",
+                    pos[0],
+                    pos[1],
+                    size,
+                    anchor,
+                    hex(INK),
+                    rotate,
+                    xml_escape(&text)
+                );
+            }
+        }
+    }
+    svg + "</svg>\n"
+}
+
+// ======================== PNG ========================
+
+/// One line of text as a coverage mask: (width, height, coverage).
+pub(crate) fn text_mask(font: &FontRef, text: &str, size: f32) -> (usize, usize, Vec<f32>) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut x = 0.0;
+    let mut glyphs = Vec::new();
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            x += scaled.kern(prev, id);
+        }
+        glyphs.push(id.with_scale_and_position(size, ab_glyph::point(x, scaled.ascent())));
+        x += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    let (w, h) = (x.ceil().max(1.0) as usize, (scaled.ascent() - scaled.descent()).ceil().max(1.0) as usize);
+    let mut mask = vec![0.0; w * h];
+    for glyph in glyphs {
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let b = outline.px_bounds();
+        outline.draw(|gx, gy, c| {
+            let (mx, my) = (b.min.x as i32 + gx as i32, b.min.y as i32 + gy as i32);
+            if mx >= 0 && my >= 0 && (mx as usize) < w && (my as usize) < h {
+                let m = &mut mask[my as usize * w + mx as usize];
+                *m = (*m + c).min(1.0);
+            }
+        });
+    }
+    (w, h, mask)
+}
+
+/// Blend a text mask into the (opaque) pixmap in `INK`.
+fn draw_text(pixmap: &mut tiny_skia::Pixmap, font: &FontRef, pos: [f32; 2], text: &str, size: f32, anchor: Anchor, vertical: bool) {
+    let (w, h, mask) = text_mask(font, text, size);
+    let offset = match anchor {
+        Anchor::Start => 0.0,
+        Anchor::Middle => w as f32 / 2.0,
+        Anchor::End => w as f32,
+    };
+    let (pw, ph) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    for my in 0..h {
+        for mx in 0..w {
+            let c = mask[my * w + mx];
+            if c <= 0.0 {
+                continue;
+            }
+            // Along the text: mx - offset; across: my - h/2
+            let (along, across) = (mx as f32 - offset, my as f32 - h as f32 / 2.0);
+            let (x, y) = if vertical { (pos[0] + across, pos[1] - along) } else { (pos[0] + along, pos[1] + across) };
+            let (x, y) = (x.round() as i32, y.round() as i32);
+            if x < 0 || y < 0 || x >= pw || y >= ph {
+                continue;
+            }
+            let p = &mut pixels[(y * pw + x) as usize];
+            let mix = |dst: u8, src: u8| (dst as f32 + (src as f32 - dst as f32) * c).round() as u8;
+            if let Some(blended) =
+                tiny_skia::PremultipliedColorU8::from_rgba(mix(p.red(), INK[0]), mix(p.green(), INK[1]), mix(p.blue(), INK[2]), 255)
+            {
+                *p = blended;
+            }
+        }
+    }
+}
+
+/// The figure rasterized to RGBA8 (opaque), row-major.
+pub fn render_rgba(figure: &PlotFigure, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let fonts = egui::FontDefinitions::default();
+    let data = fonts.font_data.get(FONT_NAME).ok_or_else(|| format!("Font {} unavailable", FONT_NAME))?;
+    let font = FontRef::try_from_slice_and_index(&data.font, data.index).map_err(|e| e.to_string())?;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or_else(|| format!("Invalid size {}×{}", width, height))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    let paint = |c: Rgb| {
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color_rgba8(c[0], c[1], c[2], 255);
+        paint.anti_alias = true;
+        paint
+    };
+    let identity = tiny_skia::Transform::identity();
+    for shape in layout(figure, width, height) {
+        match shape {
+            Shape::Line { points, color, width, dashed } => {
+                let mut path = tiny_skia::PathBuilder::new();
+                path.move_to(points[0][0], points[0][1]);
+                for p in &points[1..] {
+                    path.line_to(p[0], p[1]);
+                }
+                if points.len() == 1 {
+                    path.line_to(points[0][0] + 0.01, points[0][1]);
+                }
+                let Some(path) = path.finish() else {
+                    continue;
+                };
+                let stroke = tiny_skia::Stroke {
+                    width,
+                    line_join: tiny_skia::LineJoin::Round,
+                    dash: if dashed { tiny_skia::StrokeDash::new(vec![4.0 * width, 3.0 * width], 0.0) } else { None },
+                    ..Default::default()
+                };
+                pixmap.stroke_path(&path, &paint(color), &stroke, identity, None);
+            }
+            Shape::Rect { min, size, fill, stroke } => {
+                let Some(rect) = tiny_skia::Rect::from_xywh(min[0], min[1], size[0], size[1]) else {
+                    continue;
+                };
+                if let Some(fill) = fill {
+                    pixmap.fill_rect(rect, &paint(fill), identity, None);
+                }
+                if let Some(stroke) = stroke {
+                    let path = tiny_skia::PathBuilder::from_rect(rect);
+                    pixmap.stroke_path(&path, &paint(stroke), &tiny_skia::Stroke::default(), identity, None);
+                }
+            }
+            Shape::Text { pos, text, size, anchor, vertical } => {
+                draw_text(&mut pixmap, &font, pos, &text, size, anchor, vertical);
+            }
+        }
+    }
+    // Opaque, so premultiplied equals straight alpha
+    Ok(pixmap.take())
+}
+
+/// PNG chunk: length, type, data, CRC of type and data.
+fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(&crc.finalize().to_be_bytes());
+    chunk
+}
+
+/// Insert uncompressed UTF-8 text (iTXt) chunks right after IHDR.
+pub fn with_text_chunks(png: &[u8], entries: &[(String, String)]) -> Vec<u8> {
+    // 8-byte signature, then IHDR: length + type + 13 bytes + CRC
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+    let mut out = png[..IHDR_END].to_vec();
+    for (key, value) in entries {
+        // Keywords are 1–79 Latin-1 characters
+        let key: String = key.chars().filter(|c| c.is_ascii_graphic() || *c == ' ').take(79).collect();
+        if key.is_empty() {
+            continue;
+        }
+        let mut data = key.into_bytes();
+        data.extend_from_slice(&[0, 0, 0, 0, 0]); // no compression, no language tag or translation
+        data.extend_from_slice(value.as_bytes());
+        out.extend(png_chunk(b"iTXt", &data));
+    }
+    out.extend_from_slice(&png[IHDR_END..]);
+    out
+}
+
+/// The figure as a PNG file with its metadata embedded.
+pub fn render_png(figure: &PlotFigure, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let rgba = render_rgba(figure, width, height)?;
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(&rgba, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    Ok(with_text_chunks(&png, &figure.metadata))
+}
+
+/// `<dir>/plots/<title>_f<frame>.<ext>`, the title reduced to a file-safe slug.
+pub fn plot_path(dir: &Path, title: &str, frame: u32, format: PlotFormat) -> PathBuf {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    let slug = slug.trim_end_matches('_');
+    let slug = if slug.is_empty() { "plot" } else { slug };
+    dir.join("plots").join(format!("{}_f{}.{}", slug, frame, format.extension()))
+}
+
+/// Render and write the figure; the size is clamped to [MIN_SIZE, MAX_SIZE].
+pub fn export_plot(figure: &PlotFigure, settings: PlotExportSettings, path: &Path) -> Result<(), String> {
+    let (w, h) = (settings.width.clamp(MIN_SIZE, MAX_SIZE), settings.height.clamp(MIN_SIZE, MAX_SIZE));
+    let bytes = match settings.format {
+        PlotFormat::Svg => render_svg(figure, w, h).into_bytes(),
+        PlotFormat::Png => render_png(figure, w, h)?,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    std::fs::write(path, bytes).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
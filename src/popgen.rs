@@ -0,0 +1,357 @@
+// ============================================================================
+// popgen.rs — EvoLenia v2 Research Lab
+// Population genetics export: one row per live cell at a chosen frame,
+//   x, y, mass, r, mu, sigma, agg, mut_rate, lineage_id
+// as CSV or Parquet, for spatial analyses (isolation by distance, clines,
+// local diversity) that aggregate metrics cannot support.
+//
+// The simulation does not track ancestry, so `lineage_id` is a genome
+// cluster: cells whose genomes lie within the species-detection threshold
+// of the same representative (see metrics.rs) share an id.
+//
+// Parquet is written by hand (one row group, one uncompressed PLAIN page per
+// column, Thrift compact metadata) to avoid an Arrow dependency.
+// ============================================================================
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::metrics::GenomeClusters;
+use crate::tensorboard::put_varint;
+use crate::world::BufferSnapshot;
+
+/// Cells below this mass are empty space (same cutoff as live_pixels).
+pub const LIVE_MASS_THRESHOLD: f32 = 0.01;
+/// Cluster representatives kept; later genomes join the nearest one.
+const MAX_LINEAGES: usize = 1024;
+
+// ======================== Cell Table ========================
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellRecord {
+    pub x: u32,
+    pub y: u32,
+    pub mass: f32,
+    pub r: f32,
+    pub mu: f32,
+    pub sigma: f32,
+    pub agg: f32,
+    pub mut_rate: f32,
+    pub lineage_id: u32,
+}
+
+impl CellRecord {
+    pub fn csv_header() -> &'static str {
+        "x,y,mass,r,mu,sigma,agg,mut_rate,lineage_id"
+    }
+
+    pub fn to_csv_line(self) -> String {
+        format!(
+            "{},{},{:.6},{:.4},{:.5},{:.5},{:.4},{:.6},{}",
+            self.x, self.y, self.mass, self.r, self.mu, self.sigma, self.agg, self.mut_rate, self.lineage_id
+        )
+    }
+
+    fn genome(self) -> (f32, f32, f32, f32) {
+        (self.r, self.mu, self.sigma, self.agg)
+    }
+}
+
+/// Live cells of a row-major snapshot `width` cells wide, with lineage ids.
+pub fn live_cells(snap: &BufferSnapshot, width: u32) -> Vec<CellRecord> {
+    let mut cells: Vec<CellRecord> = snap
+        .mass
+        .iter()
+        .enumerate()
+        .filter(|&(_, &m)| m > LIVE_MASS_THRESHOLD)
+        .map(|(i, &mass)| {
+            let g = &snap.genome_a[i * 4..i * 4 + 4];
+            CellRecord {
+                x: i as u32 % width,
+                y: i as u32 / width,
+                mass,
+                r: g[0],
+                mu: g[1],
+                sigma: g[2],
+                agg: g[3],
+                mut_rate: snap.genome_b[i],
+                lineage_id: 0,
+            }
+        })
+        .collect();
+    assign_lineages(&mut cells);
+    cells
+}
+
+/// Genome clusters in scan order (see GenomeClusters).
+fn assign_lineages(cells: &mut [CellRecord]) {
+    let mut clusters = GenomeClusters::new(MAX_LINEAGES);
+    for cell in cells {
+        cell.lineage_id = clusters.assign(cell.genome());
+    }
+}
+
+// ======================== Export ========================
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PopgenFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+impl PopgenFormat {
+    pub fn all() -> [Self; 2] {
+        [Self::Csv, Self::Parquet]
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+
+    /// Format from a file name's extension (CSV unless ".parquet").
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => Self::Parquet,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// Default file name for an export taken at `frame`.
+pub fn population_path(dir: &Path, frame: u32, format: PopgenFormat) -> PathBuf {
+    dir.join(format!("population_frame{:06}.{}", frame, format.extension()))
+}
+
+/// Write the live-cell table of `snap` to `path`; returns the row count.
+pub fn export_population(snap: &BufferSnapshot, width: u32, path: &Path) -> Result<usize, String> {
+    let cells = live_cells(snap, width);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    let result = match PopgenFormat::from_path(path) {
+        PopgenFormat::Csv => write_csv(path, &cells),
+        PopgenFormat::Parquet => write_parquet(path, &cells),
+    };
+    result.map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(cells.len())
+}
+
+pub fn write_csv(path: &Path, cells: &[CellRecord]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{}", CellRecord::csv_header())?;
+    for cell in cells {
+        writeln!(file, "{}", cell.to_csv_line())?;
+    }
+    file.flush()
+}
+
+pub fn write_parquet(path: &Path, cells: &[CellRecord]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(&encode_parquet(cells))?;
+    file.flush()
+}
+
+// ======================== Parquet Encoding ========================
+
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+// parquet.thrift enum values
+const TYPE_INT32: i32 = 1;
+const TYPE_FLOAT: i32 = 4;
+const REPETITION_REQUIRED: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+/// Column name, physical type and PLAIN-encoded values.
+fn parquet_columns(cells: &[CellRecord]) -> Vec<(&'static str, i32, Vec<u8>)> {
+    let int = |f: fn(&CellRecord) -> u32| -> Vec<u8> { cells.iter().flat_map(|c| f(c).to_le_bytes()).collect() };
+    let float = |f: fn(&CellRecord) -> f32| -> Vec<u8> { cells.iter().flat_map(|c| f(c).to_le_bytes()).collect() };
+    vec![
+        ("x", TYPE_INT32, int(|c| c.x)),
+        ("y", TYPE_INT32, int(|c| c.y)),
+        ("mass", TYPE_FLOAT, float(|c| c.mass)),
+        ("r", TYPE_FLOAT, float(|c| c.r)),
+        ("mu", TYPE_FLOAT, float(|c| c.mu)),
+        ("sigma", TYPE_FLOAT, float(|c| c.sigma)),
+        ("agg", TYPE_FLOAT, float(|c| c.agg)),
+        ("mut_rate", TYPE_FLOAT, float(|c| c.mut_rate)),
+        ("lineage_id", TYPE_INT32, int(|c| c.lineage_id)),
+    ]
+}
+
+/// Whole Parquet file: magic, one column chunk per column, footer.
+pub fn encode_parquet(cells: &[CellRecord]) -> Vec<u8> {
+    let rows = cells.len() as i64;
+    let columns = parquet_columns(cells);
+    let mut out = PARQUET_MAGIC.to_vec();
+
+    // Column chunks: (name, type, offset, size)
+    let mut chunks = Vec::with_capacity(columns.len());
+    for (name, ty, values) in &columns {
+        let mut header = Thrift::default();
+        header.i32(1, PAGE_DATA);
+        header.i32(2, values.len() as i32);
+        header.i32(3, values.len() as i32);
+        header.begin_struct(5); // DataPageHeader
+        header.i32(1, rows as i32);
+        header.i32(2, ENCODING_PLAIN);
+        header.i32(3, ENCODING_RLE);
+        header.i32(4, ENCODING_RLE);
+        header.end_struct();
+        header.stop();
+
+        let offset = out.len() as i64;
+        out.extend_from_slice(&header.buf);
+        out.extend_from_slice(values);
+        chunks.push((*name, *ty, offset, out.len() as i64 - offset));
+    }
+
+    let mut meta = Thrift::default();
+    meta.i32(1, 1); // version
+    meta.list(2, Thrift::STRUCT, columns.len() + 1);
+    meta.element(|s| {
+        s.binary(4, b"schema");
+        s.i32(5, columns.len() as i32);
+    });
+    for &(name, ty, _, _) in &chunks {
+        meta.element(|s| {
+            s.i32(1, ty);
+            s.i32(3, REPETITION_REQUIRED);
+            s.binary(4, name.as_bytes());
+        });
+    }
+    meta.i64(3, rows);
+    meta.list(4, Thrift::STRUCT, 1);
+    meta.element(|group| {
+        group.list(1, Thrift::STRUCT, chunks.len());
+        for &(name, ty, offset, size) in &chunks {
+            group.element(|chunk| {
+                chunk.i64(2, offset);
+                chunk.begin_struct(3); // ColumnMetaData
+                chunk.i32(1, ty);
+                chunk.list(2, Thrift::I32, 2);
+                chunk.list_i32(&[ENCODING_PLAIN, ENCODING_RLE]);
+                chunk.list(3, Thrift::BINARY, 1);
+                chunk.list_binary(name.as_bytes());
+                chunk.i32(4, CODEC_UNCOMPRESSED);
+                chunk.i64(5, rows);
+                chunk.i64(6, size);
+                chunk.i64(7, size);
+                chunk.i64(9, offset);
+                chunk.end_struct();
+            });
+        }
+        group.i64(2, chunks.iter().map(|c| c.3).sum());
+        group.i64(3, rows);
+    });
+    meta.binary(6, format!("evolenia {}", env!("CARGO_PKG_VERSION")).as_bytes());
+    meta.stop();
+
+    out.extend_from_slice(&meta.buf);
+    out.extend_from_slice(&(meta.buf.len() as u32).to_le_bytes());
+    out.extend_from_slice(PARQUET_MAGIC);
+    out
+}
+
+/// Thrift compact protocol writer, covering the types Parquet metadata uses.
+#[derive(Default)]
+struct Thrift {
+    buf: Vec<u8>,
+    last_field: Vec<i16>, // last field id per open struct (outermost first)
+}
+
+impl Thrift {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field(&mut self, id: i16, ty: u8) {
+        if self.last_field.is_empty() {
+            self.last_field.push(0);
+        }
+        let last = self.last_field.last_mut().expect("open struct");
+        let delta = id - *last;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | ty);
+        } else {
+            self.buf.push(ty);
+            put_varint(&mut self.buf, zigzag(id as i64));
+        }
+        *last = id;
+    }
+
+    fn i32(&mut self, id: i16, v: i32) {
+        self.field(id, Self::I32);
+        put_varint(&mut self.buf, zigzag(v as i64));
+    }
+
+    fn i64(&mut self, id: i16, v: i64) {
+        self.field(id, Self::I64);
+        put_varint(&mut self.buf, zigzag(v));
+    }
+
+    fn binary(&mut self, id: i16, bytes: &[u8]) {
+        self.field(id, Self::BINARY);
+        self.raw_binary(bytes);
+    }
+
+    fn raw_binary(&mut self, bytes: &[u8]) {
+        put_varint(&mut self.buf, bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, Self::STRUCT);
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buf.push(0);
+        self.last_field.pop();
+    }
+
+    /// Field stop of the outermost struct.
+    fn stop(&mut self) {
+        self.buf.push(0);
+    }
+
+    /// List field header; elements follow without field headers.
+    fn list(&mut self, id: i16, elem: u8, len: usize) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | elem);
+        } else {
+            self.buf.push(0xF0 | elem);
+            put_varint(&mut self.buf, len as u64);
+        }
+    }
+
+    fn element(&mut self, write: impl FnOnce(&mut Self)) {
+        self.last_field.push(0);
+        write(self);
+        self.end_struct();
+    }
+
+    fn list_i32(&mut self, values: &[i32]) {
+        for &v in values {
+            put_varint(&mut self.buf, zigzag(v as i64));
+        }
+    }
+
+    fn list_binary(&mut self, bytes: &[u8]) {
+        self.raw_binary(bytes);
+    }
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
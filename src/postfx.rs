@@ -0,0 +1,296 @@
+// ============================================================================
+// postfx.rs — EvoLenia v2
+// HDR post-processing stage: the simulation is rendered into an Rgba16Float
+// offscreen target, then bright-pass → separable blur (half resolution) →
+// bloom composite + soft tonemap onto the swapchain.
+// ============================================================================
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::config::BloomSettings;
+use crate::pipeline::{bg_buffer, bgl_uniform, load_shader};
+
+/// Format of the offscreen scene and bloom targets.
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Soft-tonemap knee: composite output below this is left untouched.
+const TONEMAP_KNEE: f32 = 0.8;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct PostParams {
+    threshold: f32,
+    intensity: f32,
+    knee: f32,
+    tonemap: u32,
+    texel: [f32; 2],
+    _pad0: f32,
+    _pad1: f32,
+}
+
+/// Size-dependent targets and the bind groups that reference them.
+struct PostTargets {
+    hdr_view: wgpu::TextureView,
+    bright_bg: wgpu::BindGroup,
+    blur_h_bg: wgpu::BindGroup,
+    blur_v_bg: wgpu::BindGroup,
+    composite_bg: wgpu::BindGroup,
+    bloom_a_view: wgpu::TextureView,
+    bloom_b_view: wgpu::TextureView,
+    bloom_size: [u32; 2],
+    size: [u32; 2],
+}
+
+pub struct PostFx {
+    bgl: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params_buffer: wgpu::Buffer,
+    bright_pipeline: wgpu::RenderPipeline,
+    blur_h_pipeline: wgpu::RenderPipeline,
+    blur_v_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    targets: PostTargets,
+    bloom_enabled: bool,
+}
+
+impl PostFx {
+    pub fn new(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let shader = load_shader(device, "postfx", include_str!("shaders/postfx.wgsl"));
+
+        let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("postfx_bgl"),
+            entries: &[
+                bgl_uniform(0),
+                bgl_texture(1),
+                bgl_texture(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("postfx_pipeline_layout"),
+            bind_group_layouts: &[&bgl],
+            push_constant_ranges: &[],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("postfx_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("postfx_params"),
+            contents: bytemuck::bytes_of(&PostParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bright_pipeline = create_fullscreen_pipeline(device, "postfx_bright", &layout, &shader, "fs_bright", HDR_FORMAT);
+        let blur_h_pipeline = create_fullscreen_pipeline(device, "postfx_blur_h", &layout, &shader, "fs_blur_h", HDR_FORMAT);
+        let blur_v_pipeline = create_fullscreen_pipeline(device, "postfx_blur_v", &layout, &shader, "fs_blur_v", HDR_FORMAT);
+        let composite_pipeline = create_fullscreen_pipeline(device, "postfx_composite", &layout, &shader, "fs_composite", surface_format);
+
+        let targets = create_targets(device, &bgl, &sampler, &params_buffer, width, height);
+
+        Self {
+            bgl,
+            sampler,
+            params_buffer,
+            bright_pipeline,
+            blur_h_pipeline,
+            blur_v_pipeline,
+            composite_pipeline,
+            targets,
+            bloom_enabled: false,
+        }
+    }
+
+    /// View the simulation render pass should draw into.
+    pub fn hdr_view(&self) -> &wgpu::TextureView {
+        &self.targets.hdr_view
+    }
+
+    /// Recreate the offscreen targets at a new size (no-op at the current
+    /// one). The scene target need not match the window: the composite
+    /// resamples it onto the swapchain.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.targets.size != [width.max(1), height.max(1)] {
+            self.targets = create_targets(device, &self.bgl, &self.sampler, &self.params_buffer, width, height);
+        }
+    }
+
+    /// Upload bloom settings for this frame.
+    pub fn update(&mut self, queue: &wgpu::Queue, bloom: &BloomSettings) {
+        let [bw, bh] = self.targets.bloom_size;
+        let params = PostParams {
+            threshold: bloom.threshold,
+            intensity: if bloom.enabled { bloom.intensity } else { 0.0 },
+            knee: TONEMAP_KNEE,
+            tonemap: bloom.enabled as u32,
+            texel: [1.0 / bw as f32, 1.0 / bh as f32],
+            _pad0: 0.0,
+            _pad1: 0.0,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+        self.bloom_enabled = bloom.enabled;
+    }
+
+    /// Encode the bloom chain (if enabled) and the final composite into `target`.
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        if self.bloom_enabled {
+            fullscreen_pass(encoder, "postfx_bright_pass", &self.targets.bloom_a_view, &self.bright_pipeline, &self.targets.bright_bg);
+            fullscreen_pass(encoder, "postfx_blur_h_pass", &self.targets.bloom_b_view, &self.blur_h_pipeline, &self.targets.blur_h_bg);
+            fullscreen_pass(encoder, "postfx_blur_v_pass", &self.targets.bloom_a_view, &self.blur_v_pipeline, &self.targets.blur_v_bg);
+        }
+        fullscreen_pass(encoder, "postfx_composite_pass", target, &self.composite_pipeline, &self.targets.composite_bg);
+    }
+}
+
+// ======================== Helpers ========================
+
+fn create_targets(
+    device: &wgpu::Device,
+    bgl: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    params_buffer: &wgpu::Buffer,
+    width: u32,
+    height: u32,
+) -> PostTargets {
+    let width = width.max(1);
+    let height = height.max(1);
+    let bloom_size = [width.div_ceil(2), height.div_ceil(2)];
+
+    let hdr_view = create_hdr_texture(device, "postfx_hdr", width, height);
+    let bloom_a_view = create_hdr_texture(device, "postfx_bloom_a", bloom_size[0], bloom_size[1]);
+    let bloom_b_view = create_hdr_texture(device, "postfx_bloom_b", bloom_size[0], bloom_size[1]);
+
+    let make_bg = |label: &str, a: &wgpu::TextureView, b: &wgpu::TextureView| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: bgl,
+            entries: &[
+                bg_buffer(0, params_buffer),
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(a) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(b) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+        })
+    };
+
+    PostTargets {
+        bright_bg: make_bg("postfx_bright_bg", &hdr_view, &hdr_view),
+        blur_h_bg: make_bg("postfx_blur_h_bg", &bloom_a_view, &bloom_a_view),
+        blur_v_bg: make_bg("postfx_blur_v_bg", &bloom_b_view, &bloom_b_view),
+        composite_bg: make_bg("postfx_composite_bg", &hdr_view, &bloom_a_view),
+        hdr_view,
+        bloom_a_view,
+        bloom_b_view,
+        bloom_size,
+        size: [width, height],
+    }
+}
+
+fn create_hdr_texture(device: &wgpu::Device, label: &str, width: u32, height: u32) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn create_fullscreen_pipeline(
+    device: &wgpu::Device,
+    name: &str,
+    layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    fs_entry: &str,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(name),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point: Some(fs_entry),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn fullscreen_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    label: &str,
+    target: &wgpu::TextureView,
+    pipeline: &wgpu::RenderPipeline,
+    bind_group: &wgpu::BindGroup,
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.draw(0..6, 0..1);
+}
+
+fn bgl_texture(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
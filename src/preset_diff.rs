@@ -0,0 +1,134 @@
+// ============================================================================
+// preset_diff.rs — EvoLenia v2 Research Lab
+// Preset diff and merge: a preset being loaded is compared with the current
+// parameters field by field (the top-level fields of SimulationParams; a
+// settings group such as `bloom` or `mutation` is one field), and only the
+// fields the user keeps selected are taken from it. The merge goes through
+// the JSON form of both, so a field is applied exactly as the preset file
+// has it.
+// ============================================================================
+
+use serde_json::{Map, Value};
+
+use crate::config::SimulationParams;
+
+/// Longest value shown inline in the diff before it is shortened.
+const SUMMARY_CHARS: usize = 48;
+
+/// One top-level field that differs between the current parameters and a preset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub current: Value,
+    pub preset: Value,
+    pub apply: bool,
+}
+
+impl FieldChange {
+    /// Dotted paths inside the field whose values differ, with the current
+    /// and preset values (the field itself for a plain value).
+    pub fn details(&self) -> Vec<(String, String, String)> {
+        let (mut current, mut preset) = (Vec::new(), Vec::new());
+        flatten(&self.field, &self.current, &mut current);
+        flatten(&self.field, &self.preset, &mut preset);
+        let mut paths: Vec<&String> = current.iter().chain(&preset).map(|(p, _)| p).collect();
+        paths.sort();
+        paths.dedup();
+        let find = |rows: &[(String, Value)], path: &str| rows.iter().find(|(p, _)| p == path).map(|(_, v)| v.clone());
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let (a, b) = (find(&current, path), find(&preset, path));
+                (a != b).then(|| {
+                    let show = |v: Option<Value>| v.map_or_else(|| String::from("—"), |v| summarize(&v));
+                    (path.clone(), show(a), show(b))
+                })
+            })
+            .collect()
+    }
+}
+
+/// A preset waiting to be applied, with its differences to the current parameters.
+#[derive(Clone, Debug)]
+pub struct PresetDiff {
+    pub name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+impl PresetDiff {
+    /// Fields of `preset` that differ from `current`, all selected.
+    pub fn new(name: &str, current: &SimulationParams, preset: &SimulationParams) -> Self {
+        let (a, b) = (to_map(current), to_map(preset));
+        let changes = b
+            .iter()
+            .filter(|(field, value)| a.get(*field) != Some(*value))
+            .map(|(field, value)| FieldChange {
+                field: field.clone(),
+                current: a.get(field).cloned().unwrap_or(Value::Null),
+                preset: value.clone(),
+                apply: true,
+            })
+            .collect();
+        Self { name: name.to_string(), changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn select_all(&mut self, apply: bool) {
+        for change in &mut self.changes {
+            change.apply = apply;
+        }
+    }
+
+    /// Names of the selected fields.
+    pub fn selected(&self) -> Vec<&str> {
+        self.changes.iter().filter(|c| c.apply).map(|c| c.field.as_str()).collect()
+    }
+
+    /// `current` with the selected fields taken from the preset.
+    pub fn merged(&self, current: &SimulationParams) -> Result<SimulationParams, String> {
+        let mut map = to_map(current);
+        for change in self.changes.iter().filter(|c| c.apply) {
+            map.insert(change.field.clone(), change.preset.clone());
+        }
+        serde_json::from_value(Value::Object(map)).map_err(|e| e.to_string())
+    }
+}
+
+fn to_map(params: &SimulationParams) -> Map<String, Value> {
+    match serde_json::to_value(params) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Leaf values of `value` by dotted path (arrays count as leaves).
+fn flatten(prefix: &str, value: &Value, rows: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                flatten(&format!("{}.{}", prefix, key), v, rows);
+            }
+        }
+        other => rows.push((prefix.to_string(), other.clone())),
+    }
+}
+
+/// Compact one-line form of a value, shortened past SUMMARY_CHARS.
+pub fn summarize(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => match n.as_f64() {
+            Some(x) if n.is_f64() => format!("{}", (x * 1e6).round() / 1e6),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    };
+    if text.chars().count() > SUMMARY_CHARS {
+        format!("{}…", text.chars().take(SUMMARY_CHARS).collect::<String>())
+    } else {
+        text
+    }
+}
@@ -0,0 +1,64 @@
+// ============================================================================
+// profile.rs — EvoLenia v2 Research Lab
+// Cross-section line profiles: sample channel values along a user-drawn
+// segment of the world for quantitative inspection of fronts and gradients.
+// ============================================================================
+
+use crate::world::BufferSnapshot;
+
+/// One sample of the cross-section, taken at the nearest pixel to the line.
+#[derive(Clone, Debug)]
+pub struct ProfileSample {
+    pub distance: f32, // distance from line start, in pixels
+    pub mass: f32,
+    pub energy: f32,
+    pub resource: f32,
+}
+
+/// A line segment in world-UV space ([0,1]², origin top-left).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProfileLine {
+    pub start: [f32; 2],
+    pub end: [f32; 2],
+}
+
+impl ProfileLine {
+    /// Length of the segment in pixels of a `size` world.
+    pub fn length_px(&self, [width, height]: [u32; 2]) -> f32 {
+        let dx = (self.end[0] - self.start[0]) * width as f32;
+        let dy = (self.end[1] - self.start[1]) * height as f32;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// Sample mass, energy, and resource along a line (nearest-neighbor, one
+/// sample per pixel step). Endpoints outside the world are clamped to its edge.
+pub fn sample_line_profile(snap: &BufferSnapshot, line: &ProfileLine) -> Vec<ProfileSample> {
+    let [width, height] = snap.size;
+    let w = width as f32;
+    let h = height as f32;
+    let x0 = line.start[0].clamp(0.0, 1.0) * w;
+    let y0 = line.start[1].clamp(0.0, 1.0) * h;
+    let x1 = line.end[0].clamp(0.0, 1.0) * w;
+    let y1 = line.end[1].clamp(0.0, 1.0) * h;
+
+    let dx = x1 - x0;
+    let dy = y1 - y0;
+    let length = (dx * dx + dy * dy).sqrt();
+    let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+
+    let mut samples = Vec::with_capacity(steps + 1);
+    for s in 0..=steps {
+        let t = s as f32 / steps as f32;
+        let px = ((x0 + dx * t) as u32).min(width - 1);
+        let py = ((y0 + dy * t) as u32).min(height - 1);
+        let idx = (py * width + px) as usize;
+        samples.push(ProfileSample {
+            distance: length * t,
+            mass: snap.mass[idx],
+            energy: snap.energy[idx],
+            resource: snap.resource[idx],
+        });
+    }
+    samples
+}
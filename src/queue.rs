@@ -0,0 +1,403 @@
+// ============================================================================
+// queue.rs — EvoLenia v2 Research Lab
+// Experiment queue: protocols (frames, parameters, seed) run one after the
+// other, either in the visible world or on a background world sharing the
+// GPU. Each protocol files its results into its own run directory.
+// ============================================================================
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::app::step_world;
+use crate::conditions::Conditions;
+use crate::config::SimulationParams;
+use crate::expr::compile_metrics;
+use crate::lab::{run_config_json, write_metrics_csv, MetricsRecord};
+use crate::metadata::ExperimentMetadata;
+use crate::metrics::SimDiagnostics;
+use crate::pipeline::create_pipelines;
+use crate::state_io;
+use crate::world::WorldState;
+
+/// Steps between progress reports (and GPU syncs) of a background run.
+const BACKGROUND_PROGRESS_STEPS: u32 = 100;
+
+// ======================== Protocols ========================
+
+/// Where a queued protocol runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueueMode {
+    /// Drives the on-screen world, like a manual run.
+    #[default]
+    Visible,
+    /// Separate world on the same GPU; the view stays free to explore.
+    Background,
+}
+
+impl QueueMode {
+    pub fn all() -> [Self; 2] {
+        [Self::Visible, Self::Background]
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Self::Visible => "queue.mode_visible",
+            Self::Background => "queue.mode_background",
+        }
+    }
+}
+
+/// One experiment: run `params` from a fresh world for `frames` steps.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExperimentProtocol {
+    pub name: String,
+    pub frames: u32,
+    pub seed: Option<u64>, // None: random initial world
+    pub mode: QueueMode,
+    pub params: SimulationParams,
+}
+
+impl ExperimentProtocol {
+    /// Parameters to run with: the protocol seed applied, not paused.
+    pub fn run_params(&self) -> SimulationParams {
+        let mut params = self.params.clone();
+        params.use_fixed_seed = self.seed.is_some();
+        params.fixed_seed_value = self.seed.unwrap_or(params.fixed_seed_value);
+        params.seed = None;
+        params.paused = false;
+        params
+    }
+
+    /// Suffix of the run id: queue position and the name, filename-safe.
+    pub fn run_label(&self, index: usize) -> String {
+        let name: String = self
+            .name
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        format!("q{:02}_{}", index + 1, name)
+    }
+
+    /// Write protocol.json into the run directory.
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize protocol: {}", e))?;
+        fs::write(dir.join("protocol.json"), json).map_err(|e| format!("Failed to write protocol.json: {}", e))
+    }
+}
+
+/// Protocol being edited in the queue panel.
+#[derive(Clone, Debug)]
+pub struct ProtocolDraft {
+    pub name: String,
+    pub frames: u32,
+    pub fixed_seed: bool,
+    pub seed: u64,
+    pub mode: QueueMode,
+    pub preset: String, // empty: the current controls
+}
+
+impl Default for ProtocolDraft {
+    fn default() -> Self {
+        Self {
+            name: String::from("experiment"),
+            frames: 10_000,
+            fixed_seed: true,
+            seed: 42,
+            mode: QueueMode::Visible,
+            preset: String::new(),
+        }
+    }
+}
+
+impl ProtocolDraft {
+    pub fn build(&self, params: SimulationParams) -> ExperimentProtocol {
+        ExperimentProtocol {
+            name: self.name.clone(),
+            frames: self.frames.max(1),
+            seed: self.fixed_seed.then_some(self.seed),
+            mode: self.mode,
+            params,
+        }
+    }
+}
+
+// ======================== Queue ========================
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntryStatus {
+    Pending,
+    Running { frames_done: u32 },
+    Done,
+    Cancelled,
+    Failed(String),
+}
+
+impl EntryStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Done | Self::Cancelled | Self::Failed(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QueueEntry {
+    pub protocol: ExperimentProtocol,
+    pub status: EntryStatus,
+    pub run_dir: Option<PathBuf>, // set when the protocol starts
+}
+
+/// What a background run sends back to the UI thread.
+enum WorkerUpdate {
+    Progress(u32),
+    Sample(MetricsRecord),
+    Finished(Result<bool, String>), // Ok(cancelled)
+}
+
+/// Background run in flight. The queue has at most one running entry, so
+/// updates always apply to `ExperimentQueue::active`.
+struct BackgroundRun {
+    updates: Receiver<WorkerUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Output location, sampling and world size of a background run.
+pub struct BackgroundJob {
+    pub protocol: ExperimentProtocol,
+    pub run_id: String,
+    pub run_dir: PathBuf,
+    pub metrics_interval: u32,
+    pub metadata: ExperimentMetadata,
+    pub size: [u32; 2], // the visible world's when the run starts
+}
+
+#[derive(Default)]
+pub struct ExperimentQueue {
+    pub entries: Vec<QueueEntry>,
+    pub running: bool, // start pending protocols as the previous one finishes
+    pub draft: ProtocolDraft,
+    pub background_name: Option<String>,        // protocol of the current or last background run
+    pub background_metrics: Vec<MetricsRecord>, // its samples so far, shown live in the panel
+    worker: Option<BackgroundRun>,
+}
+
+impl ExperimentQueue {
+    pub fn enqueue(&mut self, protocol: ExperimentProtocol) {
+        self.entries.push(QueueEntry { protocol, status: EntryStatus::Pending, run_dir: None });
+    }
+
+    /// Queue a protocol ahead of every pending one and start the queue, so it
+    /// runs as soon as the current protocol ends.
+    pub fn enqueue_next(&mut self, protocol: ExperimentProtocol) {
+        let at = self.entries.iter().position(|e| e.status == EntryStatus::Pending).unwrap_or(self.entries.len());
+        self.entries.insert(at, QueueEntry { protocol, status: EntryStatus::Pending, run_dir: None });
+        self.running = true;
+    }
+
+    /// Index of the running protocol.
+    pub fn active(&self) -> Option<usize> {
+        self.entries.iter().position(|e| matches!(e.status, EntryStatus::Running { .. }))
+    }
+
+    /// Protocol to start now: the first pending one, once nothing is running.
+    pub fn next_to_start(&self) -> Option<usize> {
+        if !self.running || self.active().is_some() {
+            return None;
+        }
+        self.entries.iter().position(|e| e.status == EntryStatus::Pending)
+    }
+
+    /// Stop the queue once every protocol has finished; true if it just did.
+    pub fn stop_if_complete(&mut self) -> bool {
+        let complete = self.running && self.active().is_none() && self.entries.iter().all(|e| e.status.is_finished());
+        if complete {
+            self.running = false;
+        }
+        complete
+    }
+
+    pub fn begin(&mut self, index: usize, run_dir: PathBuf) {
+        let entry = &mut self.entries[index];
+        entry.status = EntryStatus::Running { frames_done: 0 };
+        entry.run_dir = Some(run_dir);
+    }
+
+    pub fn set_progress(&mut self, index: usize, frames_done: u32) {
+        self.entries[index].status = EntryStatus::Running { frames_done };
+    }
+
+    pub fn finish(&mut self, index: usize, status: EntryStatus) {
+        self.entries[index].status = status;
+    }
+
+    /// Remove a protocol that is not running.
+    pub fn remove(&mut self, index: usize) {
+        if self.active() != Some(index) {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn move_up(&mut self, index: usize) {
+        if index > 0 {
+            self.entries.swap(index - 1, index);
+        }
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.entries.retain(|e| !e.status.is_finished());
+    }
+
+    /// (finished, total) protocols.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.entries.iter().filter(|e| e.status.is_finished()).count(), self.entries.len())
+    }
+
+    /// Overall progress in [0, 1], weighted by frames.
+    pub fn fraction(&self) -> f32 {
+        let total: u64 = self.entries.iter().map(|e| e.protocol.frames as u64).sum();
+        let done: u64 = self
+            .entries
+            .iter()
+            .map(|e| match e.status {
+                EntryStatus::Pending => 0,
+                EntryStatus::Running { frames_done } => (frames_done as u64).min(e.protocol.frames as u64),
+                _ => e.protocol.frames as u64,
+            })
+            .sum();
+        if total == 0 {
+            0.0
+        } else {
+            done as f32 / total as f32
+        }
+    }
+
+    /// Run `job` (the protocol at `index`) on a background world.
+    pub fn start_background(&mut self, index: usize, device: wgpu::Device, queue: wgpu::Queue, job: BackgroundJob) {
+        self.begin(index, job.run_dir.clone());
+        self.background_name = Some(job.protocol.name.clone());
+        self.background_metrics.clear();
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        std::thread::spawn(move || {
+            let result = run_background(&device, &queue, &job, &flag, &tx);
+            let _ = tx.send(WorkerUpdate::Finished(result));
+        });
+        self.worker = Some(BackgroundRun { updates: rx, cancel });
+    }
+
+    /// Ask the background run to stop; it still writes what it has.
+    pub fn cancel_background(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Apply background progress. Returns the index and final status of a
+    /// background run that just ended.
+    pub fn poll_background(&mut self) -> Option<(usize, EntryStatus)> {
+        let updates: Vec<WorkerUpdate> = self.worker.as_ref()?.updates.try_iter().collect();
+        let mut finished = None;
+        for update in updates {
+            match update {
+                WorkerUpdate::Progress(frames_done) => {
+                    if let Some(index) = self.active() {
+                        self.set_progress(index, frames_done);
+                    }
+                }
+                WorkerUpdate::Sample(record) => self.background_metrics.push(record),
+                WorkerUpdate::Finished(result) => {
+                    finished = Some(match result {
+                        Ok(false) => EntryStatus::Done,
+                        Ok(true) => EntryStatus::Cancelled,
+                        Err(e) => EntryStatus::Failed(e),
+                    });
+                }
+            }
+        }
+        let status = finished?;
+        self.worker = None;
+        let index = self.active()?;
+        self.finish(index, status.clone());
+        Some((index, status))
+    }
+}
+
+// ======================== Background Runs ========================
+
+/// Run a protocol to completion (or cancellation) on its own world and write
+/// config.json, protocol.json, metrics.csv and final.snap. Ok(true) if it was
+/// cancelled.
+fn run_background(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    job: &BackgroundJob,
+    cancel: &AtomicBool,
+    updates: &Sender<WorkerUpdate>,
+) -> Result<bool, String> {
+    let dir = &job.run_dir;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create run directory {:?}: {}", dir, e))?;
+    let params = job.protocol.run_params();
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let config = run_config_json(&job.run_id, &timestamp, &params, &job.metadata, job.size);
+    let json = serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(dir.join("config.json"), json).map_err(|e| format!("Failed to write config.json: {}", e))?;
+    job.metadata.save_zenodo(dir, &job.run_id)?;
+    job.protocol.save(dir)?;
+
+    let mut world = WorldState::new_with_grid(device, queue, job.protocol.seed, params.grid, job.size);
+    let pipelines = create_pipelines(device, &world, wgpu::TextureFormat::Rgba8Unorm);
+    let interval = job.metrics_interval.max(1);
+    let started = Instant::now();
+    let mut last_sample = (started, 0u32);
+    let mut records = Vec::new();
+    let mut cancelled = false;
+
+    while world.frame < job.protocol.frames {
+        if cancel.load(Ordering::SeqCst) {
+            cancelled = true;
+            break;
+        }
+        step_world(device, queue, &mut world, &pipelines, &params);
+
+        if world.frame.is_multiple_of(interval) {
+            let snapshot = world
+                .readback_snapshot(device, queue)
+                .ok_or_else(|| format!("GPU readback failed at frame {}", world.frame))?;
+            let diag = SimDiagnostics::from_snapshot(&snapshot);
+            let steps_per_sec = (world.frame - last_sample.1) as f32 / last_sample.0.elapsed().as_secs_f32().max(1e-6);
+            let time_ms = started.elapsed().as_secs_f64() * 1000.0;
+            let record = MetricsRecord::from_diagnostics(&diag, world.frame, time_ms, steps_per_sec).with_plugins(&snapshot);
+            let _ = updates.send(WorkerUpdate::Sample(record.clone()));
+            records.push(record);
+            last_sample = (Instant::now(), world.frame);
+        }
+        if world.frame.is_multiple_of(BACKGROUND_PROGRESS_STEPS) {
+            // Keeps submissions from running far ahead of the GPU
+            device.poll(wgpu::Maintain::Wait);
+            let _ = updates.send(WorkerUpdate::Progress(world.frame));
+        }
+    }
+    let _ = updates.send(WorkerUpdate::Progress(world.frame));
+
+    write_metrics_csv(
+        &dir.join("metrics.csv"),
+        &records,
+        &compile_metrics(&params.custom_metrics),
+        &Conditions::default(),
+    )?;
+    let snapshot = world
+        .readback_snapshot(device, queue)
+        .ok_or_else(|| String::from("GPU readback failed at end of run"))?;
+    let path = dir.join("final.snap");
+    state_io::save_snapshot(&path.to_string_lossy(), &snapshot)
+        .map_err(|e| format!("Failed to save {:?}: {}", path, e))?;
+    log::info!("Queued run {} finished at frame {} (cancelled: {})", job.run_id, world.frame, cancelled);
+    Ok(cancelled)
+}
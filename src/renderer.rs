@@ -9,8 +9,9 @@ use glyphon::{
     Viewport as GlyphViewport,
 };
 
-use crate::config::{visualization_mode_name, SimulationParams};
-use crate::world::{target_total_mass, WORLD_HEIGHT, WORLD_WIDTH};
+use crate::config::{visualization_mode_key, SimulationParams};
+use crate::i18n::{tr, trf};
+use crate::world::target_mass_of;
 
 /// All glyphon resources needed for HUD text rendering.
 pub struct HudRenderer {
@@ -55,11 +56,13 @@ impl HudRenderer {
     }
 
     /// Prepare HUD text for the current frame.
+    #[allow(clippy::too_many_arguments)]
     pub fn prepare(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         params: &SimulationParams,
+        world: [u32; 2],
         frame: u32,
         fps: f32,
         camera_zoom: f32,
@@ -74,7 +77,7 @@ impl HudRenderer {
             },
         );
 
-        let hud_text = build_hud_text(params, frame, fps, camera_zoom);
+        let hud_text = build_hud_text(params, world, frame, fps, camera_zoom);
 
         // Larger font for better readability (was 14.0/18.0)
         let mut text_buf = TextBuffer::new(&mut self.font_system, Metrics::new(18.0, 24.0));
@@ -128,51 +131,35 @@ impl HudRenderer {
 
 // ======================== HUD Text Builder ========================
 
-fn build_hud_text(params: &SimulationParams, frame: u32, fps: f32, camera_zoom: f32) -> String {
-    let pause_status = if params.paused { " [PAUSED]" } else { "" };
+fn build_hud_text(params: &SimulationParams, world: [u32; 2], frame: u32, fps: f32, camera_zoom: f32) -> String {
+    let paused = if params.paused { tr("hud.paused") } else { "" };
+    let fps = format!("{:.0}", fps);
+    let zoom = format!("{:.2}", camera_zoom);
+    let mode = tr(visualization_mode_key(params.visualization_mode));
 
     if params.show_extended_ui {
-        format!(
-            "━━━ EvoLenia v2.0 — Extended HUD ━━━\n\
-             Frame: {}   FPS: {:.0}{}  |  Zoom: {:.2}x\n\
-             \n\
-             VISUALIZATION (1-5 / Tab):\n\
-             • Current: {} (<)✓(>)\n\
-             • 1: Species Color  2: Energy  3: Mass  4: Diversity  5: Predator/Prey\n\
-             \n\
-             SIMULATION CONTROL:\n\
-             • Space: {}  |  R: Restart  |  H: Toggle HUD  |  ESC: Quit\n\
-             • Speed: {}x (←/→ to adjust)  |  TimeStep: {:.2}x (↑/↓)\n\
-             • Mutation Rate: {:.2}x ([/] to adjust)\n\
-             \n\
-             CAMERA:\n\
-             • Pan: WASD  |  Zoom: Q/E or Mouse Wheel\n\
-             • VSync: {} (V to toggle)\n\
-             \n\
-             WORLD: {}×{}  |  Target Mass: {:.0}",
-            frame,
-            fps,
-            pause_status,
-            camera_zoom,
-            visualization_mode_name(params.visualization_mode),
-            if params.paused { "Resume" } else { "Pause" },
-            params.simulation_speed,
-            params.time_step,
-            params.mutation_rate,
-            if params.vsync { "ON" } else { "OFF" },
-            WORLD_WIDTH,
-            WORLD_HEIGHT,
-            target_total_mass()
+        trf(
+            "hud.extended",
+            &[
+                ("frame", &frame),
+                ("fps", &fps),
+                ("paused", &paused),
+                ("zoom", &zoom),
+                ("mode", &mode),
+                ("space", &tr(if params.paused { "hud.resume" } else { "hud.pause" })),
+                ("speed", &params.simulation_speed),
+                ("time_step", &format!("{:.2}", params.time_step)),
+                ("mutation", &format!("{:.2}", params.mutation_rate)),
+                ("vsync", &tr(if params.vsync { "hud.on" } else { "hud.off" })),
+                ("width", &world[0]),
+                ("height", &world[1]),
+                ("target", &format!("{:.0}", target_mass_of(world))),
+            ],
         )
     } else {
-        format!(
-            "Frame: {}   FPS: {:.0}{}   Zoom: {:.2}x\n\
-             Mode: {} (1-5/Tab) | Space: Pause | R: Restart | H: Help",
-            frame,
-            fps,
-            pause_status,
-            camera_zoom,
-            visualization_mode_name(params.visualization_mode),
+        trf(
+            "hud.compact",
+            &[("frame", &frame), ("fps", &fps), ("paused", &paused), ("zoom", &zoom), ("mode", &mode)],
         )
     }
 }
@@ -0,0 +1,388 @@
+// ============================================================================
+// replay.rs — EvoLenia v2 Research Lab
+// Deterministic replay check: re-simulate a recorded run from its start
+// state and compare the replay with the recording at every checkpoint, to
+// catch nondeterminism introduced by code changes. A recording is either a
+// simulation tape (tape.rs), replayed from the full-resolution snapshot and
+// parameters saved when it started, or a folder of snapshots named after
+// their frames, replayed from the earliest with the current parameters.
+//
+// The replay runs on a background world sharing the GPU, as the invasion
+// assay does, with its frame counter set to the start frame so the per-step
+// random streams line up. Snapshots are compared value for value; tape frames
+// are downsampled and quantized, so each tape channel allows its own
+// quantization step on top of the tolerance. The first checkpoint that
+// differs is the reported divergence; every checkpoint goes to a CSV.
+// ============================================================================
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use crate::app::step_world;
+use crate::config::SimulationParams;
+use crate::pipeline::create_pipelines;
+use crate::state_io::{self, SNAPSHOT_EXTENSION};
+use crate::tape::{self, TapeReader, TAPE_CHANNELS};
+use crate::world::{check_world_size, BufferSnapshot, WorldState};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplaySettings {
+    pub tolerance: f32,            // absolute difference still counted as equal
+    pub stop_at_divergence: bool, // end the replay at the first divergent checkpoint
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self { tolerance: 0.0, stop_at_divergence: true }
+    }
+}
+
+/// What to replay.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplaySource {
+    Tape(PathBuf),
+    Snapshots(PathBuf), // folder
+}
+
+impl ReplaySource {
+    pub fn path(&self) -> &Path {
+        match self {
+            ReplaySource::Tape(path) | ReplaySource::Snapshots(path) => path,
+        }
+    }
+}
+
+// ======================== Comparison ========================
+
+/// Differences of one channel at a checkpoint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelDiff {
+    pub name: &'static str,
+    pub max_diff: f32,
+    pub differing: usize,          // values beyond the tolerance
+    pub first_cell: Option<usize>, // row-major cell of the first of them
+}
+
+/// One compared frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub frame: u32,
+    pub channels: Vec<ChannelDiff>,
+}
+
+impl Checkpoint {
+    pub fn diverged(&self) -> bool {
+        self.channels.iter().any(|c| c.differing > 0)
+    }
+}
+
+fn channels(snap: &BufferSnapshot) -> [&[f32]; 5] {
+    [&snap.mass, &snap.energy, &snap.genome_a, &snap.genome_b, &snap.resource]
+}
+
+/// Compare a replayed state with a recorded one, channel by channel, each
+/// with its own tolerance. NaNs compare equal only to NaNs.
+pub fn compare(recorded: &BufferSnapshot, replayed: &BufferSnapshot, tolerance: [f32; 5]) -> Vec<ChannelDiff> {
+    channels(recorded)
+        .into_iter()
+        .zip(channels(replayed))
+        .zip(TAPE_CHANNELS)
+        .zip(tolerance)
+        .map(|(((expected, actual), (name, components)), tolerance)| {
+            let mut diff = ChannelDiff { name, max_diff: 0.0, differing: 0, first_cell: None };
+            for (i, (a, b)) in expected.iter().zip(actual).enumerate() {
+                let d = if a.is_nan() && b.is_nan() { 0.0 } else { (a - b).abs() };
+                let d = if d.is_nan() { f32::INFINITY } else { d };
+                diff.max_diff = diff.max_diff.max(d);
+                if d > tolerance {
+                    diff.differing += 1;
+                    diff.first_cell.get_or_insert(i / components);
+                }
+            }
+            if expected.len() != actual.len() {
+                diff.differing += expected.len().abs_diff(actual.len());
+                diff.max_diff = f32::INFINITY;
+            }
+            diff
+        })
+        .collect()
+}
+
+/// Quantization step of a decoded tape channel: its range over 65535.
+pub fn quantization_step(values: &[f32]) -> f32 {
+    let lo = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let hi = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if hi > lo { (hi - lo) / u16::MAX as f32 } else { 0.0 }
+}
+
+/// Snapshot files of `dir` with the frame in their names (the last run of
+/// digits of the stem), sorted by frame; one file per frame.
+pub fn snapshot_sequence(dir: &Path) -> Result<Vec<(u32, PathBuf)>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {:?}: {}", dir, e))?;
+    let mut files: Vec<(u32, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == SNAPSHOT_EXTENSION))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let digits: String = stem.chars().rev().skip_while(|c| !c.is_ascii_digit()).take_while(|c| c.is_ascii_digit()).collect();
+            let frame = digits.chars().rev().collect::<String>().parse().ok()?;
+            Some((frame, path))
+        })
+        .collect();
+    files.sort();
+    files.dedup_by_key(|(frame, _)| *frame);
+    Ok(files)
+}
+
+// ======================== Result ========================
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayResult {
+    pub source: ReplaySource,
+    pub start_frame: u32,
+    pub size: [u32; 2],  // of the replayed world, that of its start state
+    pub downsample: u32, // world cells per compared cell, per axis (tapes)
+    pub checkpoints: Vec<Checkpoint>,
+    pub cancelled: bool,
+}
+
+impl ReplayResult {
+    /// The first checkpoint where the replay left the recording.
+    pub fn first_divergence(&self) -> Option<&Checkpoint> {
+        self.checkpoints.iter().find(|c| c.diverged())
+    }
+
+    /// World cell (x, y) of a compared cell (the corner of its block on
+    /// a tape).
+    pub fn cell_xy(&self, cell: usize) -> (u32, u32) {
+        let d = self.downsample.max(1);
+        let width = self.size[0].div_ceil(d) as usize;
+        ((cell % width) as u32 * d, (cell / width) as u32 * d)
+    }
+
+    /// One line per checkpoint: frame, then each channel's largest
+    /// difference and count of differing values.
+    pub fn write_csv(&self, path: &Path) -> Result<(), String> {
+        let mut file = fs::File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+        let columns: Vec<String> = TAPE_CHANNELS.iter().map(|(name, _)| format!("{name}_max_diff,{name}_differing")).collect();
+        writeln!(file, "frame,{},diverged", columns.join(",")).map_err(|e| format!("Write error: {}", e))?;
+        for c in &self.checkpoints {
+            let values: Vec<String> = c.channels.iter().map(|d| format!("{:e},{}", d.max_diff, d.differing)).collect();
+            writeln!(file, "{},{},{}", c.frame, values.join(","), c.diverged() as u8).map_err(|e| format!("Write error: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Report file of a replay of `source` in `run_dir`.
+pub fn report_path(run_dir: &Path, source: &ReplaySource) -> PathBuf {
+    let stem = source.path().file_stem().map_or_else(|| "replay".into(), |s| s.to_string_lossy());
+    run_dir.join(format!("replay_check_{stem}.csv"))
+}
+
+// ======================== Background Replay ========================
+
+/// A recording opened for replay, its checkpoints read one at a time.
+enum Recording {
+    Tape(TapeReader),
+    Snapshots(Vec<(u32, PathBuf)>),
+}
+
+/// Where a replay starts and what it checks.
+struct Opened {
+    recording: Recording,
+    start: BufferSnapshot,
+    start_frame: u32,
+    params: SimulationParams,
+    frames: Vec<u32>, // checkpoint frames, increasing
+}
+
+impl Recording {
+    fn downsample(&self) -> u32 {
+        match self {
+            Recording::Tape(reader) => reader.header.downsample,
+            Recording::Snapshots(_) => 1,
+        }
+    }
+
+    fn open(source: &ReplaySource, current: &SimulationParams) -> Result<Opened, String> {
+        match source {
+            ReplaySource::Tape(path) => {
+                let reader = TapeReader::open(path).map_err(|e| e.to_string())?;
+                let start_path = tape::start_snapshot_path(path);
+                let start = state_io::load_snapshot(&start_path.to_string_lossy())
+                    .map_err(|e| format!("no start snapshot {:?}: {}", start_path, e))?;
+                let params = match fs::read_to_string(tape::start_params_path(path)) {
+                    Ok(json) => serde_json::from_str(&json).map_err(|e| format!("invalid tape parameters: {e}"))?,
+                    Err(_) => current.clone(),
+                };
+                let frames: Vec<u32> = reader.index.iter().map(|e| e.0).collect();
+                let start_frame = *frames.first().ok_or("the tape has no complete frame")?;
+                Ok(Opened { recording: Recording::Tape(reader), start, start_frame, params, frames })
+            }
+            ReplaySource::Snapshots(dir) => {
+                let files = snapshot_sequence(dir)?;
+                if files.len() < 2 {
+                    return Err(format!("{:?} has fewer than two snapshots named after their frames", dir));
+                }
+                let start = state_io::load_snapshot(&files[0].1.to_string_lossy()).map_err(|e| e.to_string())?;
+                let (start_frame, frames) = (files[0].0, files[1..].iter().map(|f| f.0).collect());
+                Ok(Opened { recording: Recording::Snapshots(files), start, start_frame, params: current.clone(), frames })
+            }
+        }
+    }
+
+    /// The recorded state of checkpoint `k` and the tolerance of each
+    /// channel, with the replayed state brought to the recording's form.
+    fn checkpoint(&mut self, k: usize, replayed: BufferSnapshot, tolerance: f32) -> Result<(BufferSnapshot, BufferSnapshot, [f32; 5]), String> {
+        match self {
+            Recording::Tape(reader) => {
+                let frame = reader.read(k).map_err(|e| e.to_string())?;
+                let steps = channels(&frame.snapshot).map(|values| quantization_step(values) + tolerance);
+                Ok((frame.snapshot, tape::downsample(&replayed, reader.header.downsample), steps))
+            }
+            Recording::Snapshots(files) => {
+                let snap = state_io::load_snapshot(&files[k + 1].1.to_string_lossy()).map_err(|e| e.to_string())?;
+                Ok((snap, replayed, [tolerance; 5]))
+            }
+        }
+    }
+}
+
+/// A replay ready to run.
+pub struct ReplayJob {
+    pub source: ReplaySource,
+    pub params: SimulationParams, // used when the recording has none
+    pub settings: ReplaySettings,
+    pub csv_path: PathBuf,
+}
+
+enum ReplayUpdate {
+    Progress(f32),
+    Finished(Result<ReplayResult, String>),
+}
+
+struct ReplayWorker {
+    updates: Receiver<ReplayUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Step a world from the start state through every checkpoint, then write
+/// the CSV.
+fn run_replay(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    job: &ReplayJob,
+    cancel: &AtomicBool,
+    updates: &Sender<ReplayUpdate>,
+) -> Result<ReplayResult, String> {
+    let Opened { mut recording, start, start_frame, params, frames } = Recording::open(&job.source, &job.params)?;
+    check_world_size(start.size)?;
+    let mut world = WorldState::new_with_grid(device, queue, None, params.grid, start.size);
+    if !world.apply_snapshot(queue, &start) {
+        return Err(String::from("the start snapshot does not fit a new world"));
+    }
+    world.frame = start_frame;
+    let pipelines = create_pipelines(device, &world, wgpu::TextureFormat::Rgba8Unorm);
+    let mut result = ReplayResult {
+        source: job.source.clone(),
+        start_frame,
+        size: world.size,
+        downsample: recording.downsample(),
+        checkpoints: Vec::new(),
+        cancelled: false,
+    };
+    let end = frames.last().copied().unwrap_or(start_frame);
+
+    for (k, &frame) in frames.iter().enumerate() {
+        while world.frame < frame {
+            if cancel.load(Ordering::SeqCst) {
+                result.cancelled = true;
+                break;
+            }
+            step_world(device, queue, &mut world, &pipelines, &params);
+        }
+        if result.cancelled {
+            break;
+        }
+        let replayed = world
+            .readback_snapshot(device, queue)
+            .ok_or_else(|| format!("GPU readback failed at frame {}", frame))?;
+        let (recorded, replayed, tolerance) = recording.checkpoint(k, replayed, job.settings.tolerance)?;
+        let checkpoint = Checkpoint { frame, channels: compare(&recorded, &replayed, tolerance) };
+        let diverged = checkpoint.diverged();
+        result.checkpoints.push(checkpoint);
+        let done = (frame - start_frame) as f32 / (end - start_frame).max(1) as f32;
+        let _ = updates.send(ReplayUpdate::Progress(done));
+        if diverged && job.settings.stop_at_divergence {
+            break;
+        }
+    }
+
+    if let Some(dir) = job.csv_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    result.write_csv(&job.csv_path)?;
+    Ok(result)
+}
+
+/// The replay checker of the Lab: settings, the source picked, the run in
+/// flight and the last result.
+#[derive(Default)]
+pub struct ReplayCheck {
+    pub settings: ReplaySettings,
+    pub source: Option<ReplaySource>,
+    pub requested: bool,
+    pub progress: Option<f32>,
+    pub result: Option<ReplayResult>,
+    pub csv_path: Option<PathBuf>, // of the last result
+    worker: Option<ReplayWorker>,
+}
+
+impl ReplayCheck {
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Run `job` on a background world sharing the GPU.
+    pub fn start(&mut self, device: wgpu::Device, queue: wgpu::Queue, job: ReplayJob) {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        self.csv_path = Some(job.csv_path.clone());
+        std::thread::spawn(move || {
+            let result = run_replay(&device, &queue, &job, &flag, &tx);
+            let _ = tx.send(ReplayUpdate::Finished(result));
+        });
+        self.progress = Some(0.0);
+        self.worker = Some(ReplayWorker { updates: rx, cancel });
+    }
+
+    /// Stop the replay early; the checkpoints so far are still reported.
+    pub fn cancel(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Apply the worker's updates. Returns the outcome of a replay that
+    /// just ended: the result (also kept in `result`) or an error.
+    pub fn poll(&mut self) -> Option<Result<&ReplayResult, String>> {
+        let updates: Vec<ReplayUpdate> = self.worker.as_ref()?.updates.try_iter().collect();
+        let mut finished = None;
+        for update in updates {
+            match update {
+                ReplayUpdate::Progress(fraction) => self.progress = Some(fraction),
+                ReplayUpdate::Finished(result) => finished = Some(result),
+            }
+        }
+        let result = finished?;
+        self.worker = None;
+        self.progress = None;
+        Some(result.map(|result| &*self.result.insert(result)))
+    }
+}
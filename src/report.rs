@@ -0,0 +1,424 @@
+// ============================================================================
+// report.rs — EvoLenia v2 Research Lab
+// Experiment report pieces and the in-app preview. The report is markdown
+// (report.md in the run directory); its plots are written as PNG files in
+// <run>/plots and embedded with relative image links, so the file reads the
+// same in the app, on a code host or in any markdown viewer. The preview
+// understands the subset the report uses: headings, paragraphs and bullets
+// with **bold**, pipe tables, fenced code and image lines.
+//
+// The layout comes from a Handlebars template (templates/report.md.hbs is
+// built in): the report's data and its prebuilt sections are the template
+// context, so a lab can match its notebook format without code changes.
+// ============================================================================
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use handlebars::{handlebars_helper, no_escape, Handlebars};
+use serde::Serialize;
+
+use crate::lab::{LabEvent, MetricsRecord};
+use crate::metric_plugins::plugin_fields;
+use crate::plot_export::{export_plot, PlotFigure, PlotFormat, PlotExportSettings, PlotSeries};
+
+/// File name inside the run directory.
+pub const REPORT_FILE: &str = "report.md";
+
+/// Where user report templates are kept, relative to the working directory.
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Built-in report layout (also the starting point for custom templates).
+pub const DEFAULT_TEMPLATE: &str = include_str!("../templates/report.md.hbs");
+
+/// Size of the plots generated for the report.
+pub const REPORT_PLOT_SIZE: (u32, u32) = (800, 360);
+
+/// Prefix of generated plot files (rewritten on each regeneration).
+const REPORT_PLOT_PREFIX: &str = "report_";
+
+type MetricFn = fn(&MetricsRecord) -> f64;
+
+/// Metrics plotted in every report: (file slug, title, value).
+const REPORT_PLOTS: [(&str, &str, MetricFn); 3] = [
+    ("mass", "Total mass", |m| m.total_mass as f64),
+    ("entropy", "Genetic entropy (bits)", |m| m.entropy as f64),
+    ("species", "Species", |m| m.species as f64),
+];
+
+/// Escape a value for a pipe-table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Time series of the report metrics and the provider metrics, with
+/// timeline events as markers.
+pub fn report_figures(history: &[MetricsRecord], events: &[LabEvent]) -> Vec<(String, PlotFigure)> {
+    let markers: Vec<f64> = events.iter().filter(|e| e.on_timeline()).map(|e| e.frame as f64).collect();
+    let figure = |slug: &str, title: &str, value: &dyn Fn(&MetricsRecord) -> f64| {
+        let figure = PlotFigure {
+            title: title.to_string(),
+            x_label: String::from("Frame"),
+            y_label: title.to_string(),
+            series: vec![PlotSeries {
+                name: title.to_string(),
+                points: history.iter().map(|m| [m.frame as f64, value(m)]).collect(),
+            }],
+            markers: markers.clone(),
+            metadata: Vec::new(),
+        };
+        (format!("{}{}.png", REPORT_PLOT_PREFIX, slug), figure)
+    };
+    // Provider metrics the records lack (e.g. loaded from an older CSV) are left out
+    let plugins = plugin_fields()
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| history.iter().any(|m| m.plugin(i).is_finite()))
+        .map(|(i, name)| figure(name, name, &|m| m.plugin(i)));
+    REPORT_PLOTS.iter().map(|(slug, title, value)| figure(slug, title, value)).chain(plugins).collect()
+}
+
+/// Write the report figures to `<run_dir>/plots/`.
+pub fn write_report_plots(run_dir: &Path, figures: &[(String, PlotFigure)]) -> Result<(), String> {
+    let (width, height) = REPORT_PLOT_SIZE;
+    let settings = PlotExportSettings { format: PlotFormat::Png, width, height };
+    for (file, figure) in figures {
+        export_plot(figure, settings, &run_dir.join("plots").join(file))?;
+    }
+    Ok(())
+}
+
+/// "## Plots": the final-state thumbnail and every image in `<run_dir>/plots`
+/// (generated report plots first, then plots exported from the analysis panel).
+pub fn plots_section(run_dir: &Path) -> String {
+    let mut out = String::from("## Plots\n\n");
+    if run_dir.join(crate::thumbnail::THUMBNAIL_FILE).is_file() {
+        out.push_str(&format!("![Final state]({})\n\n", crate::thumbnail::THUMBNAIL_FILE));
+    }
+    let files = plot_files(run_dir);
+    if files.is_empty() {
+        out.push_str("No plots.\n");
+    }
+    for (name, path) in files {
+        out.push_str(&format!("![{}]({})\n\n", name, path));
+    }
+    out
+}
+
+/// "## Perturbation Timeline": perturbations, parameter changes, restarts,
+/// pattern loads, queue protocols and alerts, in frame order.
+pub fn timeline_section(events: &[LabEvent]) -> String {
+    let mut out = String::from("## Perturbation Timeline\n\n");
+    let mut timeline: Vec<&LabEvent> = events.iter().filter(|e| e.on_timeline()).collect();
+    if timeline.is_empty() {
+        out.push_str("No perturbations, parameter changes or alerts recorded.\n");
+        return out;
+    }
+    timeline.sort_by_key(|e| e.frame);
+    out.push_str("| Frame | Event | Details |\n|-------|-------|---------|\n");
+    for e in timeline {
+        out.push_str(&format!("| {} | {} | {} |\n", e.frame, cell(&e.event_type), cell(&e.details)));
+    }
+    out
+}
+
+/// Final metrics as a two-column table.
+pub fn final_metrics_section(last: Option<&MetricsRecord>) -> String {
+    let Some(m) = last else {
+        return String::from("No metrics collected.");
+    };
+    let mut table = format!(
+        "| Metric | Value |\n|--------|-------|\n\
+         | Total Mass | {:.1} |\n\
+         | Avg Energy | {:.4} |\n\
+         | Entropy | {:.3} bits |\n\
+         | Species | {} |\n\
+         | Live Pixels | {} ({:.1}%) |\n\
+         | Predator % | {:.1}% |\n\
+         | FPS | {:.0} |",
+        m.total_mass,
+        m.avg_energy,
+        m.entropy,
+        m.species,
+        m.live_pixels,
+        m.live_fraction * 100.0,
+        m.predator_fraction * 100.0,
+        m.fps,
+    );
+    for (i, name) in plugin_fields().into_iter().enumerate().filter(|&(i, _)| m.plugin(i).is_finite()) {
+        table += &format!("\n| {} | {:.4} |", cell(name), m.plugin(i));
+    }
+    table
+}
+
+/// Species count and diversity over a run.
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeciesSummary {
+    pub r#final: usize,
+    pub peak: usize,
+    pub peak_frame: u32,
+    pub min: usize,
+    pub min_frame: u32,
+    pub mean: f64,
+    pub entropy_start: f32,
+    pub entropy_end: f32,
+    pub effective_diversity: f32,
+    pub predator_fraction: f32,
+}
+
+impl SpeciesSummary {
+    /// None without metrics.
+    pub fn from_history(history: &[MetricsRecord]) -> Option<Self> {
+        let (first, last) = (history.first()?, history.last()?);
+        let peak = history.iter().max_by_key(|m| m.species).unwrap_or(last);
+        let low = history.iter().min_by_key(|m| m.species).unwrap_or(last);
+        Some(Self {
+            r#final: last.species,
+            peak: peak.species,
+            peak_frame: peak.frame,
+            min: low.species,
+            min_frame: low.frame,
+            mean: history.iter().map(|m| m.species as f64).sum::<f64>() / history.len() as f64,
+            entropy_start: first.entropy,
+            entropy_end: last.entropy,
+            effective_diversity: last.effective_diversity,
+            predator_fraction: last.predator_fraction,
+        })
+    }
+}
+
+/// Largest territories: (species, cells, percent of owned cells).
+pub fn territory_shares(areas: &[(u16, usize)], count: usize) -> Vec<(u16, usize, f64)> {
+    let total: usize = areas.iter().map(|(_, n)| n).sum();
+    areas
+        .iter()
+        .take(count)
+        .map(|&(species, cells)| (species, cells, cells as f64 / total.max(1) as f64 * 100.0))
+        .collect()
+}
+
+/// "## Species Summary": species count and diversity over the run, and the
+/// largest territories when the territory overlay was sampled.
+pub fn species_section(history: &[MetricsRecord], areas: &[(u16, usize)]) -> String {
+    let mut out = String::from("## Species Summary\n\n");
+    let Some(s) = SpeciesSummary::from_history(history) else {
+        out.push_str("No metrics collected.\n");
+        return out;
+    };
+    out.push_str(&format!(
+        "- **Final species**: {}\n\
+         - **Peak**: {} (frame {})\n\
+         - **Minimum**: {} (frame {})\n\
+         - **Mean**: {:.1}\n\
+         - **Entropy**: {:.3} → {:.3} bits\n\
+         - **Effective diversity**: {:.2}\n\
+         - **Predators**: {:.1}%\n",
+        s.r#final,
+        s.peak,
+        s.peak_frame,
+        s.min,
+        s.min_frame,
+        s.mean,
+        s.entropy_start,
+        s.entropy_end,
+        s.effective_diversity,
+        s.predator_fraction * 100.0,
+    ));
+    let shares = territory_shares(areas, 5);
+    if !shares.is_empty() {
+        out.push_str("\n| Territory | Cells | Share |\n|-----------|-------|-------|\n");
+        for (species, cells, share) in shares {
+            out.push_str(&format!("| #{} | {} | {:.1}% |\n", species, cells, share));
+        }
+    }
+    out
+}
+
+/// Images in `<run_dir>/plots` as (name, path relative to the run directory),
+/// generated report plots first.
+pub fn plot_files(run_dir: &Path) -> Vec<(String, String)> {
+    let mut files: Vec<String> = std::fs::read_dir(run_dir.join("plots"))
+        .map(|dir| {
+            dir.filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.ends_with(".png") || name.ends_with(".svg"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|name| (!name.starts_with(REPORT_PLOT_PREFIX), name.clone()));
+    files
+        .into_iter()
+        .map(|name| {
+            let stem = name.rsplit_once('.').map_or(name.as_str(), |(stem, _)| stem);
+            (stem.trim_start_matches(REPORT_PLOT_PREFIX).to_string(), format!("plots/{}", name))
+        })
+        .collect()
+}
+
+/// Markdown table of every parameter, nested fields as dotted names.
+pub fn param_table(params: &serde_json::Value) -> String {
+    fn flatten(prefix: &str, value: &serde_json::Value, rows: &mut Vec<(String, String)>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, v) in map {
+                    let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    flatten(&name, v, rows);
+                }
+            }
+            serde_json::Value::String(s) => rows.push((prefix.to_string(), s.clone())),
+            other => rows.push((prefix.to_string(), other.to_string())),
+        }
+    }
+    let mut rows = Vec::new();
+    flatten("", params, &mut rows);
+    let mut out = String::from("| Parameter | Value |\n|-----------|-------|\n");
+    for (name, value) in rows {
+        out.push_str(&format!("| {} | {} |\n", cell(&name), cell(&value)));
+    }
+    out
+}
+
+// ======================== Templates ========================
+
+handlebars_helper!(fixed: |value: f64, digits: u64| format!("{:.*}", digits as usize, value));
+handlebars_helper!(percent: |fraction: f64, digits: u64| format!("{:.*}%", digits as usize, fraction * 100.0));
+
+/// Template text at `path`, or the built-in one when `path` is empty.
+pub fn load_template(path: &str) -> Result<String, String> {
+    if path.trim().is_empty() {
+        return Ok(DEFAULT_TEMPLATE.to_string());
+    }
+    std::fs::read_to_string(path.trim()).map_err(|e| format!("Failed to read template {}: {}", path.trim(), e))
+}
+
+/// Render a report template. Markdown is not HTML-escaped; unknown
+/// placeholders render empty.
+pub fn render_template(template: &str, context: &serde_json::Value) -> Result<String, String> {
+    let mut registry = Handlebars::new();
+    registry.register_escape_fn(no_escape);
+    registry.register_helper("fixed", Box::new(fixed));
+    registry.register_helper("percent", Box::new(percent));
+    registry.render_template(template, context).map_err(|e| format!("Report template: {}", e))
+}
+
+// ======================== Markdown Preview ========================
+
+/// Block of the markdown subset used by reports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Bullet(String),
+    Table(Vec<Vec<String>>), // first row is the header
+    Code(String),
+    Image { alt: String, path: String },
+}
+
+/// `![alt](path)` on its own line.
+fn image_line(line: &str) -> Option<Block> {
+    let rest = line.strip_prefix("![")?;
+    let (alt, rest) = rest.split_once("](")?;
+    let path = rest.strip_suffix(')')?;
+    Some(Block::Image { alt: alt.to_string(), path: path.to_string() })
+}
+
+fn table_row(line: &str) -> Vec<String> {
+    let inner = line.trim().trim_start_matches('|').trim_end_matches('|');
+    let mut cells = vec![String::new()];
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+fn is_table_separator(line: &str) -> bool {
+    line.contains('-') && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+pub fn parse_markdown(source: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = source.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim_start().starts_with("```") {
+                    break;
+                }
+                code.push(line);
+            }
+            blocks.push(Block::Code(code.join("\n")));
+        } else if let Some(image) = image_line(trimmed) {
+            blocks.push(image);
+        } else if trimmed.starts_with('#') {
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            blocks.push(Block::Heading(level, trimmed[level..].trim().to_string()));
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            blocks.push(Block::Bullet(item.to_string()));
+        } else if trimmed.starts_with('|') {
+            let mut rows = vec![table_row(trimmed)];
+            while let Some(next) = lines.peek().map(|l| l.trim()).filter(|l| l.starts_with('|')) {
+                if !is_table_separator(next) {
+                    rows.push(table_row(next));
+                }
+                lines.next();
+            }
+            blocks.push(Block::Table(rows));
+        } else {
+            let mut text = trimmed.to_string();
+            while let Some(next) = lines.peek().map(|l| l.trim()) {
+                let starts_block = next.is_empty()
+                    || ["#", "- ", "* ", "|", "```", "!["].iter().any(|p| next.starts_with(p));
+                if starts_block {
+                    break;
+                }
+                text.push(' ');
+                text.push_str(next);
+                lines.next();
+            }
+            blocks.push(Block::Paragraph(text));
+        }
+    }
+    blocks
+}
+
+/// Split `**bold**` runs: (text, bold) pieces in order.
+pub fn inline_spans(text: &str) -> Vec<(String, bool)> {
+    text.split("**")
+        .enumerate()
+        .filter(|(_, s)| !s.is_empty())
+        .map(|(i, s)| (s.to_string(), i % 2 == 1))
+        .collect()
+}
+
+/// Report tab state: the markdown being reviewed, for the current run.
+#[derive(Default)]
+pub struct ReportPreview {
+    pub markdown: String,
+    pub run_dir: PathBuf, // image links resolve against it
+    pub editing: bool,
+    pub modified: bool, // edited since generated or saved
+    pub images: HashMap<(egui::ViewportId, PathBuf), Option<egui::TextureHandle>>, // per window; None: unreadable (or SVG)
+}
+
+impl ReportPreview {
+    /// Show freshly generated markdown; cached images may have been rewritten.
+    pub fn set(&mut self, markdown: String, run_dir: &Path) {
+        self.markdown = markdown;
+        self.run_dir = run_dir.to_path_buf();
+        self.modified = false;
+        self.images.clear();
+    }
+}
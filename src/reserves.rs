@@ -0,0 +1,185 @@
+// ============================================================================
+// reserves.rs — EvoLenia v2 Research Lab
+// Protected reserve zones for source-sink experiments. A reserve is a circle
+// or polygon of the world with a resident lineage: the cells whose genome is
+// within `tolerance` of the reserve's resident genome. Inside the zone,
+// residents can be shielded from takeover by other lineages (predation) and
+// mutate at a scaled rate. The zones are rasterized here into a mask of zone
+// ids (0 = none, k = the k-th zone) that compute_evolution.wgsl reads beside
+// one uniform entry per zone; the mask is rebuilt only when a zone changes.
+// ============================================================================
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::genome_distance;
+use crate::world::BufferSnapshot;
+
+/// Zones uploaded to the GPU; later ones are ignored (MAX_RESERVES in
+/// compute_evolution.wgsl).
+pub const MAX_RESERVES: usize = 8;
+
+/// Zone outline, in world UV.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ReserveShape {
+    Circle { center: [f32; 2], radius: f32 }, // radius as a fraction of the shorter world side
+    Polygon { points: Vec<[f32; 2]> },        // closed, even-odd fill
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReserveZone {
+    pub name: String,
+    pub enabled: bool,
+    pub shape: ReserveShape,
+    pub resident: [f32; 4], // genome of the protected lineage (r, mu, sigma, aggressivity)
+    pub tolerance: f32,     // genome distance (metrics::genome_distance) still counted as resident
+    pub no_predation: bool, // residents cannot be taken over by other lineages
+    pub mutation_mult: f32, // scales the residents' mutation rate (1 = unchanged)
+}
+
+impl Default for ReserveZone {
+    fn default() -> Self {
+        Self {
+            name: String::from("reserve"),
+            enabled: true,
+            shape: ReserveShape::Circle { center: [0.5, 0.5], radius: 0.15 },
+            resident: [8.0, 0.15, 0.03, 0.2],
+            tolerance: 0.1,
+            no_predation: true,
+            mutation_mult: 1.0,
+        }
+    }
+}
+
+impl ReserveZone {
+    /// Whether the center of cell (`x`, `y`) of a `size` world lies in the
+    /// zone. Circles wrap around the torus; polygons are drawn as given.
+    pub fn contains(&self, x: u32, y: u32, [width, height]: [u32; 2]) -> bool {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        match &self.shape {
+            ReserveShape::Circle { center, radius } => {
+                let wrapped = |d: f32, size: f32| d - size * (d / size).round();
+                let dx = wrapped(px - center[0] * width as f32, width as f32);
+                let dy = wrapped(py - center[1] * height as f32, height as f32);
+                let r = radius * width.min(height) as f32;
+                dx * dx + dy * dy <= r * r
+            }
+            ReserveShape::Polygon { points } => {
+                let (u, v) = (px / width as f32, py / height as f32);
+                let mut inside = false;
+                for (k, a) in points.iter().enumerate() {
+                    let b = points[(k + 1) % points.len()];
+                    if (a[1] > v) != (b[1] > v) && u < a[0] + (v - a[1]) / (b[1] - a[1]) * (b[0] - a[0]) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    /// Whether a genome belongs to the zone's resident lineage.
+    pub fn is_resident(&self, genome: &[f32]) -> bool {
+        let tuple = |g: &[f32]| (g[0], g[1], g[2], g[3]);
+        genome_distance(tuple(genome), tuple(&self.resident)) <= self.tolerance
+    }
+}
+
+/// Zone id of every cell of a `size` world, row-major: the first enabled
+/// zone containing it (1-based), 0 outside all of them.
+pub fn reserve_mask(zones: &[ReserveZone], size: [u32; 2]) -> Vec<f32> {
+    let active: Vec<(usize, &ReserveZone)> =
+        zones.iter().take(MAX_RESERVES).enumerate().filter(|(_, z)| z.enabled).collect();
+    let [width, height] = size;
+    let mut mask = vec![0.0f32; (width * height) as usize];
+    if active.is_empty() {
+        return mask;
+    }
+    for y in 0..height {
+        for x in 0..width {
+            if let Some((k, _)) = active.iter().find(|(_, z)| z.contains(x, y, size)) {
+                mask[(y * width + x) as usize] = (*k + 1) as f32;
+            }
+        }
+    }
+    mask
+}
+
+/// One zone as read by compute_evolution.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct ReserveUniform {
+    pub resident: [f32; 4],
+    pub tolerance: f32,
+    pub no_predation: u32,
+    pub mutation_mult: f32,
+    pub _pad: u32,
+}
+
+/// The uniform array for `zones`, indexed like the mask (zone id − 1).
+pub fn reserve_uniforms(zones: &[ReserveZone]) -> [ReserveUniform; MAX_RESERVES] {
+    let mut uniforms = [ReserveUniform::default(); MAX_RESERVES];
+    for (k, zone) in zones.iter().enumerate().take(MAX_RESERVES) {
+        uniforms[k] = ReserveUniform {
+            resident: zone.resident,
+            tolerance: zone.tolerance,
+            no_predation: zone.no_predation as u32,
+            mutation_mult: zone.mutation_mult,
+            _pad: 0,
+        };
+    }
+    uniforms
+}
+
+/// Resident mass of a zone inside it and elsewhere in the world: a reserve
+/// acting as a source exports residents beyond its edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReserveStats {
+    pub inside: f32,
+    pub outside: f32,
+    pub cells: usize, // zone area
+}
+
+/// Resident mass for each zone of `zones`, measured on `snap` with `mask`.
+pub fn reserve_stats(snap: &BufferSnapshot, mask: &[f32], zones: &[ReserveZone]) -> Vec<ReserveStats> {
+    let mut stats = vec![ReserveStats::default(); zones.len()];
+    for (i, &id) in mask.iter().enumerate() {
+        if id > 0.0 {
+            stats[id as usize - 1].cells += 1;
+        }
+        let m = snap.mass[i];
+        if m < 0.01 {
+            continue;
+        }
+        let genome = &snap.genome_a[i * 4..i * 4 + 4];
+        for (k, (zone, s)) in zones.iter().zip(stats.iter_mut()).enumerate() {
+            if zone.enabled && zone.is_resident(genome) {
+                if id as usize == k + 1 {
+                    s.inside += m;
+                } else {
+                    s.outside += m;
+                }
+            }
+        }
+    }
+    stats
+}
+
+/// Mass-weighted mean genome of the living cells in zone `id` (1-based), to
+/// make the current occupants the resident lineage. None if it is empty.
+pub fn zone_mean_genome(snap: &BufferSnapshot, mask: &[f32], id: usize) -> Option<[f32; 4]> {
+    let mut sums = [0.0f64; 4];
+    let mut total = 0.0f64;
+    for (i, _) in mask.iter().enumerate().filter(|(_, &z)| z as usize == id) {
+        let m = snap.mass[i];
+        if m < 0.01 {
+            continue;
+        }
+        for (s, g) in sums.iter_mut().zip(&snap.genome_a[i * 4..i * 4 + 4]) {
+            *s += (*g * m) as f64;
+        }
+        total += m as f64;
+    }
+    (total > 0.0).then(|| sums.map(|s| (s / total) as f32))
+}
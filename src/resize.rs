@@ -0,0 +1,146 @@
+// ============================================================================
+// resize.rs — EvoLenia v2 Research Lab
+// Runtime world resize. The world at the new size and its pipelines are
+// built on a background thread sharing the GPU, as the replay check builds
+// its world, so the frame loop keeps running meanwhile. Once they are ready
+// the app carries the live world over — fields resampled to the new size
+// (state_io.rs), particles scaled with it, the frame counter kept — and
+// swaps the new world and pipelines in. Sizes the adapter cannot hold are
+// refused before anything is built, from the memory report (resources.rs).
+// ============================================================================
+
+use std::sync::mpsc::{self, Receiver};
+
+use crate::lab::EventSeverity;
+use crate::particles::{Particle, MAX_PARTICLES};
+use crate::pipeline::{create_pipelines, Pipelines};
+use crate::postfx::HDR_FORMAT;
+use crate::resources::MemoryReport;
+use crate::state_io;
+use crate::world::{check_world_size, CellLayout, GridType, WorldState, DEFAULT_WORLD_SIZE};
+
+/// What to build: the current world's lattice and cell order at a new size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResizeJob {
+    pub size: [u32; 2],
+    pub layout: CellLayout,
+    pub grid: GridType,
+}
+
+/// A world and its pipelines built at the size of a job, ready to swap in.
+pub struct ResizedWorld {
+    pub world: WorldState,
+    pub pipelines: Pipelines,
+}
+
+/// Why the world cannot become `size` with `layout` and `grid`, if it
+/// cannot: the size itself, the cell order or lattice at that size, a field
+/// texture over `max_texture_edge`, or a buffer of `report` (the current
+/// world's, resized) over a binding or allocation limit.
+pub fn check_resize(
+    size: [u32; 2],
+    layout: CellLayout,
+    grid: GridType,
+    report: &MemoryReport,
+    max_texture_edge: u32,
+) -> Result<(), String> {
+    check_world_size(size)?;
+    let [width, height] = size;
+    if !layout.supported(width, height) {
+        return Err(format!("the {} cell layout does not support a {}×{} world", layout.name(), width, height));
+    }
+    if !grid.supported(width, height) {
+        return Err(format!("the {} grid does not support a {}×{} world", grid.name(), width, height));
+    }
+    if width.max(height) > max_texture_edge {
+        return Err(format!("the adapter's textures are at most {} cells wide", max_texture_edge));
+    }
+    let projected = report.resized(width, height);
+    if let Some(over) = projected.warnings().into_iter().find(|w| w.severity() == EventSeverity::Alert) {
+        return Err(format!("the {} buffer would exceed a GPU limit ({} > {} bytes)", over.buffer, over.bytes, over.limit));
+    }
+    Ok(())
+}
+
+/// Why the GPU behind `limits` cannot hold a world of `size` on `grid`, if
+/// it cannot: the startup check of the size asked for with `--world`.
+pub fn check_startup_size(size: [u32; 2], grid: GridType, limits: &wgpu::Limits) -> Result<(), String> {
+    let report = MemoryReport::projected(size[0], size[1], limits);
+    check_resize(size, CellLayout::RowMajor, grid, &report, limits.max_texture_dimension_2d)
+}
+
+/// Particle positions of a `from` world stretched over a `to` world.
+pub fn scale_particles(particles: &mut [Particle], from: [u32; 2], to: [u32; 2]) {
+    let scale = [to[0] as f32 / from[0] as f32, to[1] as f32 / from[1] as f32];
+    for particle in particles {
+        for axis in 0..2 {
+            // Keep the position inside the world after rounding
+            particle.pos[axis] = (particle.pos[axis] * scale[axis]).min(to[axis] as f32 - 0.5);
+        }
+    }
+}
+
+/// Move the content of `old` into `new`, a world of another size: the
+/// fields resampled, the particles scaled and the frame counter.
+pub fn carry_over(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    old: &WorldState,
+    new: &mut WorldState,
+) -> Result<(), String> {
+    let snapshot = old.readback_snapshot(device, queue).ok_or("Failed to read back the world")?;
+    if !new.apply_snapshot(queue, &state_io::resample_snapshot(&snapshot, new.size)) {
+        return Err(String::from("The resampled world does not fit the new size"));
+    }
+    let mut particles = old.read_particles(device, queue, MAX_PARTICLES).ok_or("Failed to read back the particles")?;
+    scale_particles(&mut particles, old.size, new.size);
+    queue.write_buffer(&new.particles, 0, bytemuck::cast_slice(&particles));
+    new.frame = old.frame;
+    Ok(())
+}
+
+/// The world resize of the Lab: the size asked for and the build in flight.
+pub struct WorldResize {
+    pub target: [u32; 2],
+    pub requested: bool,
+    worker: Option<Receiver<ResizedWorld>>,
+}
+
+impl Default for WorldResize {
+    fn default() -> Self {
+        Self { target: DEFAULT_WORLD_SIZE, requested: false, worker: None }
+    }
+}
+
+impl WorldResize {
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Build the world of `job` and its pipelines on a background thread.
+    pub fn start(&mut self, device: wgpu::Device, queue: wgpu::Queue, job: ResizeJob) {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let world = WorldState {
+                grid: job.grid,
+                ..WorldState::new_with_layout(&device, &queue, None, job.layout, job.size)
+            };
+            let pipelines = create_pipelines(&device, &world, HDR_FORMAT);
+            // Uploads of the new world land before it is swapped in
+            device.poll(wgpu::Maintain::Wait);
+            let _ = tx.send(ResizedWorld { world, pipelines });
+        });
+        self.worker = Some(rx);
+    }
+
+    /// The world once it is built; Err when the build thread died.
+    pub fn poll(&mut self) -> Option<Result<ResizedWorld, String>> {
+        let result = match self.worker.as_ref()?.try_recv() {
+            Ok(resized) => Ok(resized),
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => Err(String::from("The resize thread stopped")),
+        };
+        self.worker = None;
+        Some(result)
+    }
+}
@@ -0,0 +1,320 @@
+// ============================================================================
+// resources.rs — EvoLenia v2 Research Lab
+// Memory accounting for the Resources panel: GPU buffer sizes of the world
+// (per channel, staging, uniforms), CPU-side history, and warnings when a
+// buffer approaches the device's binding or allocation limits. Projections
+// for larger worlds use the same per-pixel layout as WorldState, and come
+// with the row-band tiling such a world would need to stay under the limits
+// (planning only: the simulation still binds each channel whole).
+// ============================================================================
+
+use crate::channels::CHANNELS;
+use crate::kernel::KERNEL_MAX_R;
+use crate::lab::{EventSeverity, LabEvent, LabState, MetricsRecord};
+use crate::world::WorldState;
+
+/// Buffers above this fraction of a limit are reported.
+pub const LIMIT_WARN_FRACTION: f64 = 0.8;
+
+/// World sizes offered as projections in the Resources panel.
+pub const PROJECTED_SIZES: [u32; 4] = [1024, 2048, 4096, 8192];
+
+/// Rows a tile borrows from each neighbouring tile: the widest kernel reach.
+pub const TILE_HALO: u32 = KERNEL_MAX_R as u32;
+
+// ======================== Buffer Inventory ========================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferKind {
+    Storage, // simulation state, bound to the compute shaders
+    Staging, // CPU readback (diagnostics, snapshots)
+    Uniform, // parameters and lookup tables
+}
+
+impl BufferKind {
+    pub fn all() -> &'static [BufferKind] {
+        &[BufferKind::Storage, BufferKind::Staging, BufferKind::Uniform]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            BufferKind::Storage => "resources.kind_storage",
+            BufferKind::Staging => "resources.kind_staging",
+            BufferKind::Uniform => "resources.kind_uniform",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BufferEntry {
+    pub name: &'static str,
+    pub kind: BufferKind,
+    pub bytes: u64,  // size of one buffer
+    pub copies: u32, // 2 for ping-pong pairs
+}
+
+impl BufferEntry {
+    pub fn total(&self) -> u64 {
+        self.bytes * self.copies as u64
+    }
+}
+
+/// Per-pixel world buffers: name, kind, f32 values per pixel, copies.
+/// Registry channels (channels.rs) follow.
+const PIXEL_BUFFERS: [(&str, BufferKind, u64, u32); 14] = [
+    ("cells", BufferKind::Storage, 3, 2), // mass, energy, genome B
+    ("genome_a", BufferKind::Storage, 4, 2),
+    ("resource_map", BufferKind::Storage, 1, 1),
+    ("velocity", BufferKind::Storage, 2, 1),
+    ("field_average", BufferKind::Storage, 3, 1),
+    ("reference_mass", BufferKind::Storage, 1, 1),
+    ("territory", BufferKind::Storage, 1, 1), // RGBA8
+    ("flow_field", BufferKind::Storage, 2, 1),
+    ("reserve_mask", BufferKind::Storage, 1, 1),
+    ("conv_terms", BufferKind::Storage, 4, 3),
+    ("conv_potential", BufferKind::Storage, 4, 1),
+    ("staging_cells", BufferKind::Staging, 3, 1),
+    ("staging_genome_a", BufferKind::Staging, 4, 1),
+    ("staging_resource", BufferKind::Staging, 1, 1),
+];
+
+// ======================== Limit Warnings ========================
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitWarning {
+    pub buffer: &'static str,
+    pub limit_key: &'static str, // translation key naming the limit
+    pub bytes: u64,
+    pub limit: u64,
+}
+
+impl LimitWarning {
+    /// Alert when the buffer no longer fits, Warn when it is close.
+    pub fn severity(&self) -> EventSeverity {
+        if self.bytes > self.limit {
+            EventSeverity::Alert
+        } else {
+            EventSeverity::Warn
+        }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.bytes as f64 / self.limit.max(1) as f64
+    }
+}
+
+// ======================== Tiling Plan ========================
+
+/// Split of a world into horizontal bands such that every per-pixel buffer
+/// of a band, halo rows above and below included, fits in one binding.
+/// Only planned: the simulation does not run in tiles, worlds over the
+/// limits are refused (see `resize::check_resize`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TilePlan {
+    pub tiles: u32,
+    pub rows: u32,       // interior rows per tile (the last one may have fewer)
+    pub halo: u32,       // rows exchanged with each neighbour, 0 for one tile
+    pub tile_bytes: u64, // largest per-tile buffer, halos included
+}
+
+// ======================== Memory Report ========================
+
+#[derive(Clone, Debug)]
+pub struct MemoryReport {
+    pub width: u32,
+    pub height: u32,
+    pub buffers: Vec<BufferEntry>,
+    pub max_storage_binding: u64, // device limits the report is checked against
+    pub max_buffer_size: u64,
+    pub history_bytes: u64, // CPU: metrics history (allocated capacity)
+    pub events_bytes: u64,  // CPU: event log, including strings
+}
+
+impl MemoryReport {
+    /// Sizes of the buffers actually allocated for `world`.
+    pub fn for_world(world: &WorldState, limits: &wgpu::Limits) -> Self {
+        let entry = |name, kind, buffer: &wgpu::Buffer, copies| BufferEntry { name, kind, bytes: buffer.size(), copies };
+        let field = |name, texture: &wgpu::Texture, copies| BufferEntry {
+            name,
+            kind: BufferKind::Storage,
+            bytes: texture_bytes(texture),
+            copies,
+        };
+        let storage = BufferKind::Storage;
+        let staging = BufferKind::Staging;
+        let uniform = BufferKind::Uniform;
+        let mut buffers = vec![
+            entry("cells", storage, &world.cells[0], 2),
+            field("genome_a", &world.genome_a[0], 2),
+            entry("resource_map", storage, &world.resource_map, 1),
+            entry("velocity", storage, &world.velocity, 1),
+            entry("field_average", storage, &world.field_average, 1),
+            field("reference_mass", &world.reference_mass, 1),
+            field("territory", &world.territory, 1),
+            field("flow_field", &world.flow_field, 1),
+            field("reserve_mask", &world.reserve_mask, 1),
+            field("conv_terms", &world.conv_terms[0], 3),
+            field("conv_potential", &world.conv_potential, 1),
+            entry("conv_error", storage, &world.conv_error, 1),
+            entry("interaction_counts", storage, &world.interaction_counts, 1),
+            entry("histogram_bins", storage, &world.histogram_bins, 1),
+            entry("kernel_lut", storage, &world.kernel_lut, 1),
+            entry("mass_sum", storage, &world.mass_sum, 1),
+            entry("staging_cells", staging, &world.staging_cells, 1),
+            entry("staging_genome_a", staging, &world.staging_genome_a, 1),
+            entry("staging_resource", staging, &world.staging_resource, 1),
+            entry("staging_conv_error", staging, &world.staging_conv_error, 1),
+            entry("staging_interactions", staging, &world.staging_interactions, 1),
+            entry("staging_histogram", staging, &world.staging_histogram, 1),
+            entry("sim_params", uniform, &world.sim_params_buffer, 1),
+            entry("velocity_params", uniform, &world.velocity_params_buffer, 1),
+            entry("resource_params", uniform, &world.resource_params_buffer, 1),
+            entry("normalize_params", uniform, &world.normalize_params_buffer, 1),
+            entry("separable_kernel", uniform, &world.separable_kernel_buffer, 1),
+            entry("trail_params", uniform, &world.trail_params_buffer, 1),
+            entry("average_params", uniform, &world.average_params_buffer, 1),
+            entry("perturbation_params", uniform, &world.perturbation_params_buffer, 1),
+            entry("reserve_params", uniform, &world.reserve_params_buffer, 1),
+            entry("render_params", uniform, &world.render_params_buffer, 1),
+            entry("display_lut", uniform, &world.display_lut, 1),
+            entry("palette", uniform, &world.palette_buffer, 1),
+        ];
+        for (name, buffer, copies, staging_buffer) in world.channels.entries() {
+            buffers.push(entry(name, storage, buffer, copies));
+            buffers.extend(staging_buffer.map(|b| entry(name, staging, b, 1)));
+        }
+        Self {
+            width: world.size[0],
+            height: world.size[1],
+            buffers,
+            max_storage_binding: limits.max_storage_buffer_binding_size as u64,
+            max_buffer_size: limits.max_buffer_size,
+            history_bytes: 0,
+            events_bytes: 0,
+        }
+    }
+
+    /// Per-pixel buffers a world of `width` × `height` would allocate
+    /// before any world exists, checked against `limits`.
+    pub fn projected(width: u32, height: u32, limits: &wgpu::Limits) -> Self {
+        Self {
+            width,
+            height,
+            buffers: pixel_buffers(width, height),
+            max_storage_binding: limits.max_storage_buffer_binding_size as u64,
+            max_buffer_size: limits.max_buffer_size,
+            history_bytes: 0,
+            events_bytes: 0,
+        }
+    }
+
+    /// Per-pixel buffers a world of `width` × `height` would allocate,
+    /// checked against the same limits (uniforms are size-independent and
+    /// left out).
+    pub fn resized(&self, width: u32, height: u32) -> Self {
+        Self { width, height, buffers: pixel_buffers(width, height), history_bytes: 0, events_bytes: 0, ..*self }
+    }
+
+    /// Add the lab's CPU-side history to the report.
+    pub fn with_cpu_usage(mut self, lab: &LabState) -> Self {
+        self.history_bytes = (lab.metrics_history.capacity() * std::mem::size_of::<MetricsRecord>()) as u64;
+        self.events_bytes = lab
+            .events
+            .iter()
+            .map(|e| e.wall_time.capacity() + e.event_type.capacity() + e.details.capacity())
+            .sum::<usize>() as u64
+            + (lab.events.capacity() * std::mem::size_of::<LabEvent>()) as u64;
+        self
+    }
+
+    pub fn total(&self, kind: BufferKind) -> u64 {
+        self.buffers.iter().filter(|b| b.kind == kind).map(BufferEntry::total).sum()
+    }
+
+    pub fn gpu_total(&self) -> u64 {
+        self.buffers.iter().map(BufferEntry::total).sum()
+    }
+
+    pub fn largest_buffer(&self) -> Option<&BufferEntry> {
+        self.buffers.iter().max_by_key(|b| b.bytes)
+    }
+
+    /// Row bands needed to keep the largest buffer under both the storage
+    /// binding and the buffer size limit. None when even a single row plus
+    /// its halos does not fit.
+    pub fn tile_plan(&self) -> Option<TilePlan> {
+        let largest = self.largest_buffer().map_or(0, |b| b.bytes);
+        let limit = self.max_storage_binding.min(self.max_buffer_size);
+        if largest <= limit {
+            return Some(TilePlan { tiles: 1, rows: self.height, halo: 0, tile_bytes: largest });
+        }
+        let row_bytes = largest / self.height.max(1) as u64;
+        let max_rows = (limit / row_bytes.max(1)).min(u32::MAX as u64) as u32;
+        let rows = max_rows.checked_sub(2 * TILE_HALO).filter(|&r| r > 0)?;
+        let tiles = self.height.div_ceil(rows);
+        let rows = self.height.div_ceil(tiles); // balance the bands
+        Some(TilePlan { tiles, rows, halo: TILE_HALO, tile_bytes: (rows + 2 * TILE_HALO) as u64 * row_bytes })
+    }
+
+    /// Buffers near or over the storage binding size (storage buffers) or
+    /// the maximum buffer size (all buffers).
+    pub fn warnings(&self) -> Vec<LimitWarning> {
+        let near = |bytes: u64, limit: u64| bytes as f64 >= limit as f64 * LIMIT_WARN_FRACTION;
+        let mut warnings = Vec::new();
+        for b in &self.buffers {
+            if b.kind == BufferKind::Storage && near(b.bytes, self.max_storage_binding) {
+                warnings.push(LimitWarning {
+                    buffer: b.name,
+                    limit_key: "resources.limit_binding",
+                    bytes: b.bytes,
+                    limit: self.max_storage_binding,
+                });
+            } else if near(b.bytes, self.max_buffer_size) {
+                warnings.push(LimitWarning {
+                    buffer: b.name,
+                    limit_key: "resources.limit_buffer",
+                    bytes: b.bytes,
+                    limit: self.max_buffer_size,
+                });
+            }
+        }
+        warnings
+    }
+}
+
+/// Size of a field texture's texel data.
+fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let texel = texture.format().block_copy_size(None).unwrap_or(0) as u64;
+    texture.width() as u64 * texture.height() as u64 * texel
+}
+
+fn pixel_buffers(width: u32, height: u32) -> Vec<BufferEntry> {
+    let pixel_bytes = width as u64 * height as u64 * std::mem::size_of::<f32>() as u64;
+    let channels = CHANNELS.iter().flat_map(|spec| {
+        let floats = spec.components as u64;
+        let storage = (spec.name, BufferKind::Storage, floats, if spec.ping_pong { 2 } else { 1 });
+        std::iter::once(storage).chain(spec.staging.then_some((spec.name, BufferKind::Staging, floats, 1)))
+    });
+    PIXEL_BUFFERS
+        .into_iter()
+        .chain(channels)
+        .map(|(name, kind, floats, copies)| BufferEntry { name, kind, bytes: pixel_bytes * floats, copies })
+        .collect()
+}
+
+/// Human-readable size in binary units (B, KiB, MiB, GiB).
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
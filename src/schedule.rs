@@ -0,0 +1,170 @@
+// ============================================================================
+// schedule.rs — EvoLenia v2 Research Lab
+// Parameter schedules: a scalar parameter driven by the frame number, either
+// through keyframes (linear in between, held before the first and after the
+// last) or a formula in `frame` (expr.rs syntax), e.g.
+// `max(0.005, 0.02 - 0.015 * frame / 50000)`. Schedules are part of the
+// parameters, so presets, configs and protocols carry them; every world that
+// steps (the view, queued and background runs, replays) sets the scheduled
+// values before each step.
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SimulationParams;
+use crate::expr::Expr;
+
+/// Parameters a schedule can drive (keys of `param_mut`).
+pub const SCHEDULABLE: &[&str] = &[
+    "time_step",
+    "mutation_rate",
+    "predation_factor",
+    "resource_diffusion",
+    "resource_feed_rate",
+    "resource_consumption",
+    "anisotropy",
+    "mass_damping",
+    "target_mass_multiplier",
+    "radius_cost_exponent",
+    "agg_mobility_tradeoff",
+    "starvation_severity",
+    "perturbation_intensity",
+];
+
+/// Variable of schedule formulas.
+pub const FRAME_VARIABLE: &str = "frame";
+
+/// The parameter a schedule key names.
+pub fn param_mut<'a>(params: &'a mut SimulationParams, name: &str) -> Option<&'a mut f32> {
+    Some(match name {
+        "time_step" => &mut params.time_step,
+        "mutation_rate" => &mut params.mutation_rate,
+        "predation_factor" => &mut params.predation_factor,
+        "resource_diffusion" => &mut params.resource_diffusion,
+        "resource_feed_rate" => &mut params.resource_feed_rate,
+        "resource_consumption" => &mut params.resource_consumption,
+        "anisotropy" => &mut params.anisotropy,
+        "mass_damping" => &mut params.mass_damping,
+        "target_mass_multiplier" => &mut params.target_mass_multiplier,
+        "radius_cost_exponent" => &mut params.radius_cost_exponent,
+        "agg_mobility_tradeoff" => &mut params.agg_mobility_tradeoff,
+        "starvation_severity" => &mut params.starvation_severity,
+        "perturbation_intensity" => &mut params.perturbation_intensity,
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub frame: u32,
+    pub value: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScheduleCurve {
+    Keyframes(Vec<Keyframe>), // sorted by frame when evaluated
+    Formula(String),          // in FRAME_VARIABLE
+}
+
+/// A parameter driven by the frame number.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParamSchedule {
+    pub param: String, // one of SCHEDULABLE
+    pub enabled: bool,
+    pub curve: ScheduleCurve,
+}
+
+impl Default for ParamSchedule {
+    fn default() -> Self {
+        Self {
+            param: "resource_feed_rate".to_string(),
+            enabled: true,
+            curve: ScheduleCurve::Keyframes(vec![
+                Keyframe { frame: 0, value: 0.02 },
+                Keyframe { frame: 50_000, value: 0.005 },
+            ]),
+        }
+    }
+}
+
+impl ParamSchedule {
+    /// Why the schedule cannot be evaluated, if it cannot.
+    pub fn check(&self) -> Result<(), String> {
+        if !SCHEDULABLE.contains(&self.param.as_str()) {
+            return Err(format!("unknown parameter '{}'", self.param));
+        }
+        match &self.curve {
+            ScheduleCurve::Keyframes(keys) if keys.is_empty() => Err(String::from("no keyframes")),
+            ScheduleCurve::Keyframes(_) => Ok(()),
+            ScheduleCurve::Formula(source) => Expr::parse(source, &[FRAME_VARIABLE]).map(|_| ()),
+        }
+    }
+
+    /// Value at `frame`, or None when the schedule is invalid or the
+    /// formula is undefined there.
+    pub fn value_at(&self, frame: u32) -> Option<f32> {
+        self.values(&[frame]).pop().flatten()
+    }
+
+    /// Values at each of `frames` (the formula is parsed once).
+    pub fn values(&self, frames: &[u32]) -> Vec<Option<f32>> {
+        match &self.curve {
+            ScheduleCurve::Keyframes(keys) => {
+                let mut keys = keys.clone();
+                keys.sort_by_key(|k| k.frame);
+                frames.iter().map(|&f| interpolate(&keys, f)).collect()
+            }
+            ScheduleCurve::Formula(source) => match Expr::parse(source, &[FRAME_VARIABLE]) {
+                Ok(expr) => frames
+                    .iter()
+                    .map(|&f| Some(expr.eval(&[f as f64]) as f32).filter(|v| v.is_finite()))
+                    .collect(),
+                Err(_) => vec![None; frames.len()],
+            },
+        }
+    }
+
+    /// Frames the schedule changes over: its keyframes, or, for a formula,
+    /// from 0 to well past `frame`.
+    pub fn span(&self, frame: u32) -> (u32, u32) {
+        let end = match &self.curve {
+            ScheduleCurve::Keyframes(keys) => keys.iter().map(|k| k.frame).max().unwrap_or(0),
+            ScheduleCurve::Formula(_) => frame.saturating_mul(2),
+        };
+        (0, end.max(frame).max(1000))
+    }
+}
+
+/// Piecewise-linear value of sorted `keys` at `frame`.
+fn interpolate(keys: &[Keyframe], frame: u32) -> Option<f32> {
+    let after = keys.iter().position(|k| k.frame > frame);
+    match after {
+        Some(0) => keys.first().map(|k| k.value),
+        Some(i) => {
+            let (a, b) = (keys[i - 1], keys[i]);
+            let t = (frame - a.frame) as f32 / (b.frame - a.frame) as f32;
+            Some(a.value + (b.value - a.value) * t)
+        }
+        None => keys.last().map(|k| k.value),
+    }
+}
+
+/// Set every enabled, valid schedule's parameter to its value at `frame`.
+pub fn apply_schedules(params: &mut SimulationParams, frame: u32) {
+    let values: Vec<(String, f32)> = params
+        .schedules
+        .iter()
+        .filter(|s| s.enabled)
+        .filter_map(|s| Some((s.param.clone(), s.value_at(frame)?)))
+        .collect();
+    for (name, value) in values {
+        if let Some(slot) = param_mut(params, &name) {
+            *slot = value;
+        }
+    }
+}
+
+/// The enabled schedule driving `param`, if any (the last one wins).
+pub fn active_schedule<'a>(params: &'a SimulationParams, param: &str) -> Option<&'a ParamSchedule> {
+    params.schedules.iter().rev().find(|s| s.enabled && s.param == param)
+}
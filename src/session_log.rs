@@ -0,0 +1,205 @@
+// ============================================================================
+// session_log.rs — EvoLenia v2 Research Lab
+// Console log capture: the process logger forwards every record to the usual
+// env_logger console output (RUST_LOG filtering unchanged) and, at or above
+// the level chosen in the UI, appends it to <run>/session.log so GPU
+// warnings and driver messages are archived with the experiment data.
+// Records logged before the first run (adapter selection, device limits)
+// are kept in a bounded buffer and copied at the top of each session log.
+// ============================================================================
+
+use std::fmt;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+
+/// File name inside the run directory.
+pub const SESSION_LOG_FILE: &str = "session.log";
+
+/// Startup lines kept for the session log header.
+pub const STARTUP_LINES: usize = 1000;
+
+/// Minimum level written to the session log.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionLogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl SessionLogLevel {
+    pub fn all() -> &'static [SessionLogLevel] {
+        &[
+            SessionLogLevel::Off,
+            SessionLogLevel::Error,
+            SessionLogLevel::Warn,
+            SessionLogLevel::Info,
+            SessionLogLevel::Debug,
+            SessionLogLevel::Trace,
+        ]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            SessionLogLevel::Off => "log_level.off",
+            SessionLogLevel::Error => "log_level.error",
+            SessionLogLevel::Warn => "log_level.warn",
+            SessionLogLevel::Info => "log_level.info",
+            SessionLogLevel::Debug => "log_level.debug",
+            SessionLogLevel::Trace => "log_level.trace",
+        }
+    }
+
+    pub fn filter(self) -> LevelFilter {
+        match self {
+            SessionLogLevel::Off => LevelFilter::Off,
+            SessionLogLevel::Error => LevelFilter::Error,
+            SessionLogLevel::Warn => LevelFilter::Warn,
+            SessionLogLevel::Info => LevelFilter::Info,
+            SessionLogLevel::Debug => LevelFilter::Debug,
+            SessionLogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// One session log line: `2024-05-01 12:00:00.123 WARN  wgpu_core::device: message`.
+pub fn format_line(time: &str, level: Level, target: &str, message: &fmt::Arguments) -> String {
+    format!("{} {:<5} {}: {}", time, level, target, message)
+}
+
+// ======================== Capture ========================
+
+#[derive(Default)]
+struct Sink {
+    file: Option<LineWriter<File>>,
+    startup: Vec<String>,
+    startup_done: bool, // a session log was opened: stop buffering
+}
+
+static SINK: Mutex<Sink> = Mutex::new(Sink { file: None, startup: Vec::new(), startup_done: false });
+static FILE_LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Info as usize);
+static CONSOLE_FILTER: OnceLock<LevelFilter> = OnceLock::new();
+
+fn file_level() -> LevelFilter {
+    match FILE_LEVEL.load(Ordering::Relaxed) {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Whether a record at `level` goes to the session log.
+pub fn captures(level: Level) -> bool {
+    level <= file_level()
+}
+
+/// Set the session log level. Raises the global max level when the file
+/// wants more than the console so those records reach the logger at all.
+pub fn set_level(level: SessionLogLevel) {
+    FILE_LEVEL.store(level.filter() as usize, Ordering::Relaxed);
+    if let Some(console) = CONSOLE_FILTER.get() {
+        log::set_max_level((*console).max(level.filter()));
+    }
+}
+
+/// Append a record to the open session log, or to the startup buffer
+/// before the first run.
+pub fn capture(level: Level, target: &str, message: &fmt::Arguments) {
+    if !captures(level) {
+        return;
+    }
+    let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+    let line = format_line(&time, level, target, message);
+    let Ok(mut sink) = SINK.lock() else {
+        return;
+    };
+    if let Some(file) = &mut sink.file {
+        // Nowhere to report a failed write without recursing into the logger
+        let _ = writeln!(file, "{}", line);
+    } else if !sink.startup_done && sink.startup.len() < STARTUP_LINES {
+        sink.startup.push(line);
+    }
+}
+
+/// Start `<run_dir>/session.log` (replacing any open one), beginning with
+/// the buffered startup lines.
+pub fn open(run_dir: &Path, run_id: &str) -> Result<(), String> {
+    let path = run_dir.join(SESSION_LOG_FILE);
+    let file = File::create(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+    let mut file = LineWriter::new(file);
+    let mut sink = SINK.lock().map_err(|_| String::from("session log lock poisoned"))?;
+    let mut header = format!("# EvoLenia session log — run {} (level {})\n", run_id, file_level());
+    if !sink.startup.is_empty() {
+        header.push_str("# --- startup ---\n");
+        for line in &sink.startup {
+            header.push_str(line);
+            header.push('\n');
+        }
+        header.push_str("# --- run ---\n");
+    }
+    file.write_all(header.as_bytes()).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    sink.file = Some(file);
+    sink.startup_done = true;
+    Ok(())
+}
+
+/// Stop writing to the current session log (run finalized).
+pub fn close() {
+    if let Ok(mut sink) = SINK.lock() {
+        if let Some(mut file) = sink.file.take() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// ======================== Logger ========================
+
+struct SessionLogger {
+    console: env_logger::Logger,
+}
+
+impl Log for SessionLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata) || captures(metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.console.matches(record) {
+            self.console.log(record);
+        }
+        capture(record.level(), record.target(), record.args());
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Ok(mut sink) = SINK.lock() {
+            if let Some(file) = &mut sink.file {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// Install the process logger (replaces `env_logger::init()`).
+pub fn init() {
+    let console = env_logger::Builder::from_default_env().build();
+    let console_filter = console.filter();
+    let logger = SessionLogger { console };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        let _ = CONSOLE_FILTER.set(console_filter);
+        log::set_max_level(console_filter.max(file_level()));
+    }
+}
@@ -0,0 +1,147 @@
+// ============================================================================
+// settings.rs — EvoLenia v2
+// Per-user session settings persisted between launches: UI scale, language,
+// theme and palette, panel visibility and dock layout, window geometry,
+// camera state, background throttling, the session log level, the GPU
+// validation option, the run output directory, the report template, the
+// experiment metadata, and the first-launch benchmark result (startup.rs).
+// ============================================================================
+
+use std::path::PathBuf;
+
+use egui_dock::DockState;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::CameraState;
+use crate::dock::{self, LabTab};
+use crate::i18n::Language;
+use crate::lab::LabState;
+use crate::metadata::ExperimentMetadata;
+use crate::startup::StartupChoice;
+use crate::pacing::BackgroundThrottle;
+use crate::session_log::SessionLogLevel;
+use crate::theme::{ColorPalette, UiTheme};
+
+/// Settings file, relative to the working directory (like `presets/`).
+pub const SETTINGS_PATH: &str = "evolenia_settings.json";
+
+/// Window size and position in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub width: u32,
+    pub height: u32,
+    pub position: Option<[i32; 2]>, // unavailable on some platforms (Wayland)
+    pub maximized: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    pub ui_scale: f32, // multiplier on top of the OS scale factor
+    pub ui_theme: UiTheme,
+    pub language: Language,
+    pub palette: ColorPalette,
+    pub show_lab_ui: bool,
+    pub show_analysis_panel: bool,
+    pub analysis_detached: bool,
+    pub analysis_window: Option<WindowGeometry>,
+    pub show_logs_panel: bool,
+    pub show_performance_panel: bool,
+    pub show_resources_panel: bool,
+    pub show_queue_panel: bool,
+    pub show_report_panel: bool,
+    pub dock: Option<DockState<LabTab>>, // None: keep the default layout
+    pub window: Option<WindowGeometry>,
+    pub camera: CameraState,
+    pub background_throttle: BackgroundThrottle,
+    pub session_log_level: SessionLogLevel,
+    pub gpu_validation: bool, // read at startup (gpu_debug.rs)
+    pub output_root: PathBuf,
+    pub report_template: String,
+    pub metadata: ExperimentMetadata,
+    pub startup: Option<StartupChoice>, // None: the first-launch benchmark has not run
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        let lab = LabState::default();
+        Self::capture(&lab, &CameraState::default(), None)
+    }
+}
+
+impl UiSettings {
+    /// Snapshot the current session layout.
+    pub fn capture(lab: &LabState, camera: &CameraState, window: Option<WindowGeometry>) -> Self {
+        Self {
+            ui_scale: lab.ui_scale,
+            ui_theme: lab.ui_theme,
+            language: lab.language,
+            palette: lab.palette,
+            show_lab_ui: lab.show_lab_ui,
+            show_analysis_panel: lab.show_analysis_panel,
+            analysis_detached: lab.analysis_detached,
+            analysis_window: lab.analysis_window,
+            show_logs_panel: lab.show_logs_panel,
+            show_performance_panel: lab.show_performance_panel,
+            show_resources_panel: lab.show_resources_panel,
+            show_queue_panel: lab.show_queue_panel,
+            show_report_panel: lab.show_report_panel,
+            dock: Some(dock::persistable(&lab.dock_state)),
+            window,
+            camera: camera.clone(),
+            background_throttle: lab.background_throttle,
+            session_log_level: lab.session_log_level,
+            gpu_validation: lab.gpu_validation,
+            output_root: lab.output_root.clone(),
+            report_template: lab.report_template.clone(),
+            metadata: lab.metadata.clone(),
+            startup: lab.startup_choice.clone(),
+        }
+    }
+
+    /// Restore layout and camera into a fresh session.
+    pub fn apply(&self, lab: &mut LabState, camera: &mut CameraState) {
+        lab.ui_scale = self.ui_scale.clamp(0.5, 3.0);
+        lab.ui_theme = self.ui_theme;
+        lab.language = self.language;
+        lab.palette = self.palette;
+        lab.show_lab_ui = self.show_lab_ui;
+        lab.show_analysis_panel = self.show_analysis_panel;
+        lab.analysis_detached = self.analysis_detached;
+        lab.analysis_window = self.analysis_window;
+        lab.show_logs_panel = self.show_logs_panel;
+        lab.show_performance_panel = self.show_performance_panel;
+        lab.show_resources_panel = self.show_resources_panel;
+        lab.show_queue_panel = self.show_queue_panel;
+        lab.show_report_panel = self.show_report_panel;
+        if let Some(dock) = &self.dock {
+            lab.dock_state = dock.clone();
+        }
+        *camera = self.camera.clone();
+        lab.background_throttle = self.background_throttle;
+        lab.session_log_level = self.session_log_level;
+        lab.gpu_validation = self.gpu_validation;
+        lab.output_root = self.output_root.clone();
+        lab.report_template = self.report_template.clone();
+        lab.metadata = self.metadata.clone();
+        lab.startup_choice = self.startup.clone();
+    }
+
+    /// Load settings; `None` if the file does not exist or cannot be parsed.
+    pub fn load(path: &str) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        match serde_json::from_str::<UiSettings>(&content) {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                log::warn!("Ignoring unreadable settings file {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+}
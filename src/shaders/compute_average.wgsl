@@ -0,0 +1,36 @@
+// ============================================================================
+// compute_average.wgsl — EvoLenia v2
+// Time-averaged fields: each step the running means of mass, resource and
+// flow speed move towards the step's values by `weight` (1/n over the first
+// n steps, then 1/window). Three planes of one value per cell: mass,
+// resource, speed. Analysis only — never read back by the simulation.
+// ============================================================================
+
+struct Params {
+    width: u32,
+    height: u32,
+    weight: f32,  // of the newest step (1 = restart the mean)
+    _pad: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
+@group(0) @binding(2) var<storage, read> resource_map: array<f32>;
+@group(0) @binding(3) var<storage, read> velocity: array<vec2<f32>>;
+@group(0) @binding(4) var<storage, read_write> average: array<f32>;
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+
+    let n = WORLD_WIDTH * WORLD_HEIGHT;
+    let i = cell_index(gid.x, gid.y, WORLD_WIDTH);
+    let m = cells[i].mass;
+    let values = vec3<f32>(m, resource_map[i], length(velocity[i]));
+    for (var k = 0u; k < 3u; k++) {
+        let j = k * n + i;
+        average[j] = mix(average[j], values[k], params.weight);
+    }
+}
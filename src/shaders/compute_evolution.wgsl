@@ -8,8 +8,41 @@
 // advection ensures mass is transferred (never duplicated). DNA travels
 // with mass via stochastic segregation — the colonizer's genome replaces
 // the receiver's with probability proportional to the flux.
+//
+// The convolution is either the exact 27×27 loop or, in separable mode, a
+// blend of the per-tier sums precomputed by compute_separable.wgsl. The exact
+// loop reads its weights from the kernel LUT (kernel.rs) when one is loaded.
+//
+// Optionally, genome takeovers of living cells are counted by attacker and
+// defender aggressivity class (the predation interaction matrix).
+//
+// Advection velocities are computed here from the mass and genome fields
+// (velocity_field.wgsl), turned by the world's rotation, plus the global flow
+// field, and each cell's own velocity is written for the trail
+// and render passes, unless the separate velocity pass (debug) produced them.
+//
+// Reserve zones (reserves.rs) protect a resident lineage — genomes within a
+// tolerance of the zone's resident genome — from takeover by other lineages,
+// and scale its mutation rate, inside the zone.
+//
+// Mutation steps are uniform by default; the other operators (Gaussian,
+// Cauchy, discrete jumps, correlated across genes) draw their own deviates.
+// Mutated genes are clamped to the per-gene bounds; locked genes never mutate.
 // ============================================================================
 
+const CONV_EXACT: u32 = 0u;
+const CONV_COMPARE: u32 = 2u; // separable, plus error accumulation vs exact
+const LUT_MAX_D2: i32 = 169;  // max_r²
+const LUT_STRIDE: u32 = 171u; // weights for d² = 0..=169, then the kernel sum
+const CAPACITY_MIN: f32 = 0.01; // see config::CAPACITY_MIN
+const MUTATION_UNIFORM: u32 = 0u; // config::MutationOperator
+const MUTATION_GAUSSIAN: u32 = 1u;
+const MUTATION_CAUCHY: u32 = 2u;
+const MUTATION_JUMP: u32 = 3u;
+const MUTATION_CORRELATED: u32 = 4u;
+const CAUCHY_CLAMP: f32 = 50.0; // keeps a Cauchy step finite
+const GENE_STEP: vec4<f32> = vec4<f32>(3.0, 0.15, 0.08, 0.3); // mutation step per gene, in gene units
+
 struct Params {
     width: u32,
     height: u32,
@@ -20,22 +53,81 @@ struct Params {
     radius_cost_exp: f32,      // exponent for radius metabolic cost
     agg_mobility: f32,         // aggressivity-mobility tradeoff strength
     starvation_severity: f32,  // mass decay multiplier when starving
-    _pad1: u32,
-    _pad2: u32,
-    _pad3: u32,
+    convolution: u32,          // 0 = exact, 1 = separable, 2 = separable + comparison
+    kernel_lut_bins: u32,      // radius bins in kernel_lut, 0 = evaluate exp()
+    fused_velocity: u32,       // 1 = compute velocities here, 0 = read the velocity pass output
+    interactions: u32,         // 1 = count genome takeovers into `interactions`
+    anisotropy: f32,           // horizontal / vertical advection strength
+    flow_strength: f32,        // scale of flow_field in the advection velocity (0 = off)
+    rotation: f32,             // Coriolis angular velocity ω (0 = no rotation)
+    rotation_center: vec2<f32>, // pole, in cells
+    capacity: u32,              // 1 = logistic growth against the local carrying capacity
+    capacity_base: f32,         // K = base + per_resource · resource
+    capacity_per_resource: f32,
+    demographic_noise: f32,     // amplitude of the growth noise (0 = off)
+    noise_seed: u32,            // stream of the demographic noise
+    mutation_operator: u32,     // config::MutationOperator::shader_id
+    gene_rates: vec4<f32>,      // per-gene mutation step multiplier
+    mutation_jump: f32,         // Jump: jump size, taken with probability 1 / size
+    mutation_correlation: f32,  // Correlated: variance share common to all genes
+    gene_lock: u32,             // bit g = gene g does not mutate
+    _pad9: u32,
+    gene_min: vec4<f32>,        // mutations keep genes in [gene_min, gene_max]
+    gene_max: vec4<f32>,
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
-@group(0) @binding(1) var<storage, read> mass_in: array<f32>;
-@group(0) @binding(2) var<storage, read> energy_in: array<f32>;
-@group(0) @binding(3) var<storage, read> genome_a_in: array<vec4<f32>>;
-@group(0) @binding(4) var<storage, read> genome_b_in: array<f32>;
-@group(0) @binding(5) var<storage, read> resource_map: array<f32>;
-@group(0) @binding(6) var<storage, read> velocity: array<vec2<f32>>;
-@group(0) @binding(7) var<storage, read_write> mass_out: array<f32>;
-@group(0) @binding(8) var<storage, read_write> energy_out: array<f32>;
-@group(0) @binding(9) var<storage, read_write> genome_a_out: array<vec4<f32>>;
-@group(0) @binding(10) var<storage, read_write> genome_b_out: array<f32>;
+@group(0) @binding(1) var<storage, read> cells_in: array<Cell>; // mass, energy, genome B
+@group(0) @binding(2) var genome_a_in: texture_2d<f32>;
+@group(0) @binding(3) var<storage, read> resource_map: array<f32>;
+@group(0) @binding(4) var<storage, read_write> velocity: array<vec2<f32>>;
+@group(0) @binding(5) var<storage, read_write> cells_out: array<Cell>;
+@group(0) @binding(6) var genome_a_out: texture_storage_2d<rgba32float, write>;
+
+struct SeparableKernel {
+    horizontal: array<vec4<f32>, 81>,
+    vertical: array<vec4<f32>, 81>,
+    sums: vec4<f32>, // total weight per tier (small, mid, large, xlarge)
+}
+
+@group(0) @binding(7) var potential: texture_2d<f32>; // Σ K·m per tier
+@group(0) @binding(8) var<uniform> separable: SeparableKernel;
+// conv_error[0] = Σ |U_separable - U_exact| * 1e5, conv_error[1] = cells compared
+@group(0) @binding(9) var<storage, read_write> conv_error: array<atomic<u32>>;
+@group(0) @binding(10) var<storage, read> kernel_lut: array<f32>;
+// interactions[a * AGG_CLASSES + d] = living cells of aggressivity class d
+// whose genome was replaced by an incoming class-a genome (who eats whom)
+@group(0) @binding(11) var<storage, read_write> interactions: array<atomic<u32>>;
+@group(0) @binding(12) var flow_field: texture_2d<f32>; // global wind, |v| ≤ 1 (flow.rs)
+
+const MAX_RESERVES: u32 = 8u; // see reserves.rs
+
+struct Reserve {
+    resident: vec4<f32>, // genome of the protected lineage
+    tolerance: f32,      // genome distance still counted as resident
+    no_predation: u32,   // 1 = residents cannot be taken over by other lineages
+    mutation_mult: f32,  // residents' mutation rate scale
+    _pad: u32,
+}
+
+@group(0) @binding(13) var reserve_mask: texture_2d<f32>; // zone id per cell, 0 = none
+@group(0) @binding(14) var<uniform> reserves: array<Reserve, MAX_RESERVES>;
+
+const AGG_CLASSES: u32 = 5u; // equal-width aggressivity bins, see metrics.rs
+
+fn agg_class(agg: f32) -> u32 {
+    return min(u32(clamp(agg, 0.0, 1.0) * f32(AGG_CLASSES)), AGG_CLASSES - 1u);
+}
+
+// Same metric as metrics::genome_distance
+fn genome_distance(a: vec4<f32>, b: vec4<f32>) -> f32 {
+    let d = (a - b) * vec4<f32>(1.0 / 16.0, 1.0, 1.0 / 0.3, 1.0);
+    return length(d);
+}
+
+fn is_resident(genome: vec4<f32>, zone: u32) -> bool {
+    return zone > 0u && genome_distance(genome, reserves[zone - 1u].resident) <= reserves[zone - 1u].tolerance;
+}
 
 // ======================== PRNG ========================
 // PCG hash-based pseudo-random number generator (no global state)
@@ -55,11 +147,109 @@ fn rand_signed(seed: u32) -> f32 {
     return rand01(seed) * 2.0 - 1.0;
 }
 
-// Toroidal indexing
-fn idx(x: i32, y: i32) -> u32 {
-    let wx = ((x % i32(params.width)) + i32(params.width)) % i32(params.width);
-    let wy = ((y % i32(params.height)) + i32(params.height)) % i32(params.height);
-    return u32(wy) * params.width + u32(wx);
+// Standard normal deviate (Box-Muller) from two hashes of `seed`
+fn rand_normal(seed: u32) -> f32 {
+    let u1 = max(rand01(seed), 1e-7);
+    let u2 = rand01(pcg_hash(seed + 1u));
+    return sqrt(-2.0 * log(u1)) * cos(6.2831853 * u2);
+}
+
+// One mutation deviate per gene for the non-uniform operators
+fn mutation_deviates(seed: u32) -> vec4<f32> {
+    var d = vec4<f32>(0.0);
+    var s = seed;
+    for (var g = 0u; g < 4u; g++) {
+        s = pcg_hash(s + g);
+        let u = rand01(s);
+        switch params.mutation_operator {
+            case MUTATION_CAUCHY: {
+                d[g] = clamp(tan(3.14159265 * (u - 0.5)), -CAUCHY_CLAMP, CAUCHY_CLAMP);
+            }
+            case MUTATION_JUMP: {
+                let k = params.mutation_jump;
+                if (rand01(pcg_hash(s + 7u)) * k < 1.0) {
+                    d[g] = select(-k, k, u < 0.5);
+                }
+            }
+            default: {
+                d[g] = rand_normal(s);
+            }
+        }
+    }
+    if (params.mutation_operator == MUTATION_CORRELATED) {
+        let c = params.mutation_correlation;
+        d = sqrt(1.0 - c) * d + sqrt(c) * rand_normal(pcg_hash(s + 13u));
+    }
+    return d;
+}
+
+// Genes locked by the gene constraints
+fn gene_locked() -> vec4<bool> {
+    let l = params.gene_lock;
+    return vec4<bool>((l & 1u) != 0u, (l & 2u) != 0u, (l & 4u) != 0u, (l & 8u) != 0u);
+}
+
+// Toroidal wrap to texel coordinates
+fn wrap(x: i32, y: i32) -> vec2<i32> {
+    let wx = ((x % i32(WORLD_WIDTH)) + i32(WORLD_WIDTH)) % i32(WORLD_WIDTH);
+    let wy = ((y % i32(WORLD_HEIGHT)) + i32(WORLD_HEIGHT)) % i32(WORLD_HEIGHT);
+    return vec2<i32>(wx, wy);
+}
+
+// Wrapped hex neighbor k of cell c (HEX_GRID only)
+fn hex_cell(c: vec2<i32>, k: u32) -> vec2<i32> {
+    let n = hex_neighbor(c.x, c.y, k);
+    return wrap(n.x, n.y);
+}
+
+// Flat index of a wrapped cell (buffer-backed fields)
+fn index(c: vec2<i32>) -> u32 {
+    return cell_index(u32(c.x), u32(c.y), WORLD_WIDTH);
+}
+
+fn load_mass(c: vec2<i32>) -> f32 {
+    return cells_in[index(c)].mass;
+}
+
+fn load_genome_a(c: vec2<i32>) -> vec4<f32> {
+    return textureLoad(genome_a_in, c, 0);
+}
+
+fn load_genome_b(c: vec2<i32>) -> f32 {
+    return cells_in[index(c)].mutation_rate;
+}
+
+// Advection velocity of a wrapped cell: recomputed when fused, else the
+// velocity pass output
+fn velocity_at(c: vec2<i32>) -> vec2<f32> {
+    if (params.fused_velocity == 0u) {
+        return velocity[index(c)];
+    }
+    var vel: vec2<f32>;
+    if HEX_GRID {
+        // The two upper and two lower neighbors stand in for up/down
+        vel = advection_velocity(
+            load_mass(c),
+            load_mass(hex_cell(c, 0u)),
+            load_mass(hex_cell(c, 3u)),
+            0.5 * (load_mass(hex_cell(c, 1u)) + load_mass(hex_cell(c, 2u))),
+            0.5 * (load_mass(hex_cell(c, 4u)) + load_mass(hex_cell(c, 5u))),
+            load_genome_a(c).w,
+            params.anisotropy,
+        );
+    } else {
+        vel = advection_velocity(
+            load_mass(c),
+            load_mass(wrap(c.x + 1, c.y)),
+            load_mass(wrap(c.x - 1, c.y)),
+            load_mass(wrap(c.x, c.y - 1)),
+            load_mass(wrap(c.x, c.y + 1)),
+            load_genome_a(c).w,
+            params.anisotropy,
+        );
+    }
+    vel = coriolis(vel, vec2<f32>(c), params.rotation, params.rotation_center);
+    return with_flow(vel, textureLoad(flow_field, c, 0).xy, params.flow_strength);
 }
 
 // ======================== LENIA RING KERNEL ========================
@@ -71,67 +261,45 @@ fn kernel_weight(dist: f32, radius: f32) -> f32 {
     return exp(-(diff * diff) / (2.0 * 0.15 * 0.15));
 }
 
-@compute @workgroup_size(16, 16)
-fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
-    let x = i32(gid.x);
-    let y = i32(gid.y);
-
-    if (gid.x >= params.width || gid.y >= params.height) {
-        return;
+// ================== LENIA CONVOLUTION ==================
+// Same kernel as exact_potential, with weights interpolated between the two
+// LUT radius bins around r (genome radii span 3..15).
+fn lut_potential(x: i32, y: i32, r: f32) -> f32 {
+    let bin = clamp((r - 3.0) / 12.0, 0.0, 1.0) * f32(params.kernel_lut_bins - 1u);
+    let b0 = min(u32(bin), params.kernel_lut_bins - 2u);
+    let f = bin - f32(b0);
+    let row0 = b0 * LUT_STRIDE;
+    let row1 = row0 + LUT_STRIDE;
+    let max_r = 13;
+
+    var U = 0.0;
+    for (var dy = -max_r; dy <= max_r; dy = dy + 1) {
+        for (var dx = -max_r; dx <= max_r; dx = dx + 1) {
+            let d2 = dx * dx + dy * dy;
+            if (d2 > LUT_MAX_D2) {
+                continue;
+            }
+            let w = mix(kernel_lut[row0 + u32(d2)], kernel_lut[row1 + u32(d2)], f);
+            if (w > 0.0) {
+                U += w * load_mass(wrap(x + dx, y + dy));
+            }
+        }
     }
 
-    let i = idx(x, y);
-    let m = mass_in[i];
-    let e = energy_in[i];
-    let ga = genome_a_in[i]; // r, mu, sigma, aggressivity
-    let gb = genome_b_in[i]; // mutation_rate
-
-    let r      = ga.x; // perception radius
-    let mu     = ga.y; // growth center (ecological niche)
-    // Guard: sigma must be > 0 to avoid division by zero in growth function
-    // exp(-x²/(2·0²)) = exp(-∞) or exp(NaN) — catastrophic for the simulation
-    let sigma  = max(ga.z, 0.005); // growth width (tolerance), minimum 0.005
-    let agg    = ga.w; // aggressivity
-
-    // Base seed for PRNG — unique per pixel per frame
-    let base_seed = (gid.y * params.width + gid.x) ^ params.frame ^ 0xDEADBEEFu;
-
-    // ================== EARLY EXIT FOR EMPTY REGIONS ==================
-    // Skip the expensive convolution for dead pixels with no living
-    // neighbors within kernel range. With ~85% fill=0 this skips most work.
-    if (m < 0.001) {
-        let max_r_i = 14; // must be >= max_r used in convolution
-        let hr = max_r_i / 2;
-        // Sparse 16-point check: cardinal at max_r, diagonals at half, immediate neighbors, mid-ring
-        let has_life =
-            mass_in[idx(x + max_r_i, y)] > 0.001 ||
-            mass_in[idx(x - max_r_i, y)] > 0.001 ||
-            mass_in[idx(x, y + max_r_i)] > 0.001 ||
-            mass_in[idx(x, y - max_r_i)] > 0.001 ||
-            mass_in[idx(x + hr, y + hr)] > 0.001 ||
-            mass_in[idx(x - hr, y + hr)] > 0.001 ||
-            mass_in[idx(x + hr, y - hr)] > 0.001 ||
-            mass_in[idx(x - hr, y - hr)] > 0.001 ||
-            mass_in[idx(x + hr, y)] > 0.001 ||
-            mass_in[idx(x - hr, y)] > 0.001 ||
-            mass_in[idx(x, y + hr)] > 0.001 ||
-            mass_in[idx(x, y - hr)] > 0.001 ||
-            mass_in[idx(x + 1, y)] > 0.001 ||
-            mass_in[idx(x - 1, y)] > 0.001 ||
-            mass_in[idx(x, y + 1)] > 0.001 ||
-            mass_in[idx(x, y - 1)] > 0.001;
-
-        if (!has_life) {
-            mass_out[i] = 0.0;
-            energy_out[i] = e;
-            genome_a_out[i] = ga;
-            genome_b_out[i] = gb;
-            return;
-        }
+    let kernel_sum = mix(kernel_lut[row0 + LUT_STRIDE - 1u], kernel_lut[row1 + LUT_STRIDE - 1u], f);
+    if (kernel_sum > 0.0) {
+        U = U / kernel_sum;
     }
+    return U;
+}
 
-    // ================== LENIA CONVOLUTION ==================
-    // Four-tier kernel interpolation supporting radii from 3 to 15.
+// Four-tier kernel interpolation supporting radii from 3 to 15.
+fn exact_potential(x: i32, y: i32, r: f32) -> f32 {
+    // The LUT bins integer squared distances, which only exist on the square
+    // lattice; hex worlds never upload one (see WorldState::convolution)
+    if (params.kernel_lut_bins > 0u && !HEX_GRID) {
+        return lut_potential(x, y, r);
+    }
     // max_r=13 enables proper Lenia patterns (orbium, geminium, etc.)
     // which require effective radii of 10-15 pixels.
     // 27×27 = 729 samples per pixel — fast enough on modern GPUs.
@@ -140,14 +308,16 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let r_large  = 10.0;
     let r_xlarge = 15.0;
     let max_r    = 13;  // 27×27 convolution — required for Lenia creatures
+    // Hex rows are only √3/2 apart, so the same radius spans more of them
+    let max_dy   = select(max_r, 15, HEX_GRID);
 
     var U = 0.0; // Perceived density (convolution result)
     var kernel_sum = 0.0;
 
     // Sample the neighborhood up to max kernel radius
-    for (var dy = -max_r; dy <= max_r; dy = dy + 1) {
+    for (var dy = -max_dy; dy <= max_dy; dy = dy + 1) {
         for (var dx = -max_r; dx <= max_r; dx = dx + 1) {
-            let dist = sqrt(f32(dx * dx + dy * dy));
+            let dist = length(lattice_offset(y, dx, dy));
             if (dist < 0.5 || dist > f32(max_r)) {
                 continue;
             }
@@ -180,8 +350,7 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
             }
 
             if (w > 0.001) {
-                let ni = idx(x + dx, y + dy);
-                U += w * mass_in[ni];
+                U += w * load_mass(wrap(x + dx, y + dy));
                 kernel_sum += w;
             }
         }
@@ -191,13 +360,132 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     if (kernel_sum > 0.0) {
         U = U / kernel_sum;
     }
+    return U;
+}
+
+// Tier sums from the separable passes, blended like the exact kernels
+fn separable_potential(c: vec2<i32>, r: f32) -> f32 {
+    let p = textureLoad(potential, c, 0);
+    let s = separable.sums;
+    var u: f32;
+    if (r <= 3.0) {
+        u = p.x / s.x;
+    } else if (r <= 6.0) {
+        let t = (r - 3.0) / 3.0;
+        u = mix(p.x, p.y, t) / mix(s.x, s.y, t);
+    } else if (r <= 10.0) {
+        let t = (r - 6.0) / 4.0;
+        u = mix(p.y, p.z, t) / mix(s.y, s.z, t);
+    } else {
+        let t = clamp((r - 10.0) / 5.0, 0.0, 1.0);
+        u = mix(p.z, p.w, t) / mix(s.z, s.w, t);
+    }
+    return clamp(u, 0.0, 1.0);
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let x = i32(gid.x);
+    let y = i32(gid.y);
+
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+
+    let c = vec2<i32>(x, y);
+    let i = index(c);
+    let cell = cells_in[i];
+    let m = cell.mass;
+    let e = cell.energy;
+    let ga = load_genome_a(c); // r, mu, sigma, aggressivity
+    let gb = cell.mutation_rate;
+
+    let r      = ga.x; // perception radius
+    let mu     = ga.y; // growth center (ecological niche)
+    // Guard: sigma must be > 0 to avoid division by zero in growth function
+    // exp(-x²/(2·0²)) = exp(-∞) or exp(NaN) — catastrophic for the simulation
+    let sigma  = max(ga.z, 0.005); // growth width (tolerance), minimum 0.005
+    let agg    = ga.w; // aggressivity
+
+    // Own velocity, published for the trail and render passes when fused
+    let vel = velocity_at(c);
+    if (params.fused_velocity != 0u) {
+        velocity[i] = vel;
+    }
+
+    // Base seed for PRNG — unique per pixel per frame
+    let base_seed = (gid.y * WORLD_WIDTH + gid.x) ^ params.frame ^ 0xDEADBEEFu;
+
+    // ================== EARLY EXIT FOR EMPTY REGIONS ==================
+    // Skip the expensive convolution for dead pixels with no living
+    // neighbors within kernel range. With ~85% fill=0 this skips most work.
+    if (m < 0.001) {
+        let max_r_i = 14; // must be >= max_r used in convolution
+        let hr = max_r_i / 2;
+        // Sparse 16-point check: cardinal at max_r, diagonals at half, immediate neighbors, mid-ring
+        let has_life =
+            load_mass(wrap(x + max_r_i, y)) > 0.001 ||
+            load_mass(wrap(x - max_r_i, y)) > 0.001 ||
+            load_mass(wrap(x, y + max_r_i)) > 0.001 ||
+            load_mass(wrap(x, y - max_r_i)) > 0.001 ||
+            load_mass(wrap(x + hr, y + hr)) > 0.001 ||
+            load_mass(wrap(x - hr, y + hr)) > 0.001 ||
+            load_mass(wrap(x + hr, y - hr)) > 0.001 ||
+            load_mass(wrap(x - hr, y - hr)) > 0.001 ||
+            load_mass(wrap(x + hr, y)) > 0.001 ||
+            load_mass(wrap(x - hr, y)) > 0.001 ||
+            load_mass(wrap(x, y + hr)) > 0.001 ||
+            load_mass(wrap(x, y - hr)) > 0.001 ||
+            load_mass(wrap(x + 1, y)) > 0.001 ||
+            load_mass(wrap(x - 1, y)) > 0.001 ||
+            load_mass(wrap(x, y + 1)) > 0.001 ||
+            load_mass(wrap(x, y - 1)) > 0.001;
+
+        if (!has_life) {
+            write_outputs(c, 0.0, e, ga, gb);
+            return;
+        }
+    }
+
+    // ================== LENIA CONVOLUTION ==================
+    var U: f32; // Perceived density (convolution result)
+    if (params.convolution == CONV_EXACT) {
+        U = exact_potential(x, y, r);
+    } else {
+        U = separable_potential(c, r);
+        if (params.convolution == CONV_COMPARE) {
+            let err = abs(U - exact_potential(x, y, r));
+            atomicAdd(&conv_error[0], u32(err * 1e5));
+            atomicAdd(&conv_error[1], 1u);
+        }
+    }
 
     // ================== GROWTH FUNCTION ==================
     // Gaussian bell: G(U; μ, σ) = exp(-((U - μ)² / (2σ²)))
     // Biologically: organisms thrive at density μ, tolerate ±σ
     let growth_raw = exp(-((U - mu) * (U - mu)) / (2.0 * sigma * sigma));
     let dM = 2.0 * growth_raw - 1.0; // ∈ [-1, +1]
-    var mass_candidate = clamp(m + params.dt * dM, 0.0, 1.0);
+    var growth = params.dt * dM;
+
+    // ================== CARRYING CAPACITY ==================
+    // Logistic saturation against the cell's capacity, set by its resources:
+    // growth slows to zero at K, and turns into decay above it.
+    if (params.capacity != 0u) {
+        let k = clamp(params.capacity_base + params.capacity_per_resource * resource_map[i], CAPACITY_MIN, 1.0);
+        if (growth > 0.0) {
+            growth *= 1.0 - m / k;
+        }
+    }
+
+    // ================== DEMOGRAPHIC NOISE ==================
+    // Multiplicative noise with a standard deviation of amplitude / √m: small
+    // populations drift, and can go extinct, where large ones stay smooth.
+    // Its stream depends only on the noise seed, the cell and the frame.
+    if (params.demographic_noise > 0.0 && m > 0.001) {
+        let noise_seed = pcg_hash(pcg_hash(i ^ (params.noise_seed * 0x9E3779B9u)) ^ params.frame);
+        growth *= 1.0 + params.demographic_noise * rand_normal(noise_seed) / sqrt(m);
+    }
+    var mass_candidate = clamp(m + growth, 0.0, 1.0);
 
     // ================== METABOLISM ==================
     // Cost scales with genomic complexity (Darwinian parsimony)
@@ -222,30 +510,49 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
 
     // ================== MASS-CONSERVATIVE ADVECTION ==================
     // Mass is TRANSFERRED, never copied. Conservation: flux_in = flux_out
-    let vel = velocity[i];
+    var vel_right = vec2<f32>(0.0);
+    var vel_left = vec2<f32>(0.0);
+    var vel_down = vec2<f32>(0.0);
+    var vel_up = vec2<f32>(0.0);
+    var hex_vel: array<vec2<f32>, 6>;
 
-    // Cardinal direction vectors
     var total_flux_out = 0.0;
     var total_flux_in = 0.0;
 
-    // Flux limiters — unrolled (WGSL requires constant indices for local arrays)
-    // Cap per direction = mass/8 (not /4): prevents >50% total outflow per step
-    // right
-    { let fc = dot(vel, vec2<f32>(1.0, 0.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
-      let ni = idx(x + 1, y); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = dot(vn, vec2<f32>(-1.0, 0.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
-    // left
-    { let fc = dot(vel, vec2<f32>(-1.0, 0.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
-      let ni = idx(x - 1, y); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = dot(vn, vec2<f32>(1.0, 0.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
-    // down
-    { let fc = dot(vel, vec2<f32>(0.0, 1.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
-      let ni = idx(x, y + 1); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = dot(vn, vec2<f32>(0.0, -1.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
-    // up
-    { let fc = dot(vel, vec2<f32>(0.0, -1.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
-      let ni = idx(x, y - 1); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = dot(vn, vec2<f32>(0.0, 1.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
+    if HEX_GRID {
+        // Six directions: cap per direction = mass/12, same 50% total bound
+        for (var k = 0u; k < 6u; k++) {
+            let d = hex_dir(k);
+            let nc = hex_cell(c, k);
+            hex_vel[k] = velocity_at(nc);
+            total_flux_out += clamp(dot(vel, d), 0.0, mass_candidate / 12.0);
+            total_flux_in += clamp(dot(hex_vel[k], -d), 0.0, load_mass(nc) / 12.0);
+        }
+    } else {
+        vel_right = velocity_at(wrap(x + 1, y));
+        vel_left = velocity_at(wrap(x - 1, y));
+        vel_down = velocity_at(wrap(x, y + 1));
+        vel_up = velocity_at(wrap(x, y - 1));
+
+        // Flux limiters — unrolled (WGSL requires constant indices for local arrays)
+        // Cap per direction = mass/8 (not /4): prevents >50% total outflow per step
+        // right
+        { let fc = dot(vel, vec2<f32>(1.0, 0.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
+          let nc = wrap(x + 1, y); let vn = vel_right; let mn = load_mass(nc);
+          let fi = dot(vn, vec2<f32>(-1.0, 0.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
+        // left
+        { let fc = dot(vel, vec2<f32>(-1.0, 0.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
+          let nc = wrap(x - 1, y); let vn = vel_left; let mn = load_mass(nc);
+          let fi = dot(vn, vec2<f32>(1.0, 0.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
+        // down
+        { let fc = dot(vel, vec2<f32>(0.0, 1.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
+          let nc = wrap(x, y + 1); let vn = vel_down; let mn = load_mass(nc);
+          let fi = dot(vn, vec2<f32>(0.0, -1.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
+        // up
+        { let fc = dot(vel, vec2<f32>(0.0, -1.0)); total_flux_out += clamp(fc, 0.0, mass_candidate / 8.0);
+          let nc = wrap(x, y - 1); let vn = vel_up; let mn = load_mass(nc);
+          let fi = dot(vn, vec2<f32>(0.0, 1.0)); total_flux_in += clamp(fi, 0.0, mn / 8.0); }
+    }
 
     var mass_new = mass_candidate + total_flux_in - total_flux_out;
     mass_new = clamp(mass_new, 0.0, 1.0);
@@ -258,32 +565,57 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     var genome_b_new = gb;
 
     var seed = base_seed;
-    // Genome advection — unrolled
-    // right
-    { let ni = idx(x + 1, y); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = clamp(dot(vn, vec2<f32>(-1.0, 0.0)), 0.0, mn / 4.0);
-      if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 1u);
-        if (rand01(seed) < p) { genome_a_new = genome_a_in[ni]; genome_b_new = genome_b_in[ni]; } } }
-    // left
-    { let ni = idx(x - 1, y); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = clamp(dot(vn, vec2<f32>(1.0, 0.0)), 0.0, mn / 4.0);
-      if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 2u);
-        if (rand01(seed) < p) { genome_a_new = genome_a_in[ni]; genome_b_new = genome_b_in[ni]; } } }
-    // down
-    { let ni = idx(x, y + 1); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = clamp(dot(vn, vec2<f32>(0.0, -1.0)), 0.0, mn / 4.0);
-      if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 3u);
-        if (rand01(seed) < p) { genome_a_new = genome_a_in[ni]; genome_b_new = genome_b_in[ni]; } } }
-    // up
-    { let ni = idx(x, y - 1); let vn = velocity[ni]; let mn = mass_in[ni];
-      let fi = clamp(dot(vn, vec2<f32>(0.0, 1.0)), 0.0, mn / 4.0);
-      if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 4u);
-        if (rand01(seed) < p) { genome_a_new = genome_a_in[ni]; genome_b_new = genome_b_in[ni]; } } }
+    if HEX_GRID {
+        for (var k = 0u; k < 6u; k++) {
+            let nc = hex_cell(c, k); let mn = load_mass(nc);
+            let fi = clamp(dot(hex_vel[k], -hex_dir(k)), 0.0, mn / 6.0);
+            if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 1u + k);
+              if (rand01(seed) < p) { genome_a_new = load_genome_a(nc); genome_b_new = load_genome_b(nc); } }
+        }
+    } else {
+        // Genome advection — unrolled
+        // right
+        { let nc = wrap(x + 1, y); let vn = vel_right; let mn = load_mass(nc);
+          let fi = clamp(dot(vn, vec2<f32>(-1.0, 0.0)), 0.0, mn / 4.0);
+          if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 1u);
+            if (rand01(seed) < p) { genome_a_new = load_genome_a(nc); genome_b_new = load_genome_b(nc); } } }
+        // left
+        { let nc = wrap(x - 1, y); let vn = vel_left; let mn = load_mass(nc);
+          let fi = clamp(dot(vn, vec2<f32>(1.0, 0.0)), 0.0, mn / 4.0);
+          if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 2u);
+            if (rand01(seed) < p) { genome_a_new = load_genome_a(nc); genome_b_new = load_genome_b(nc); } } }
+        // down
+        { let nc = wrap(x, y + 1); let vn = vel_down; let mn = load_mass(nc);
+          let fi = clamp(dot(vn, vec2<f32>(0.0, -1.0)), 0.0, mn / 4.0);
+          if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 3u);
+            if (rand01(seed) < p) { genome_a_new = load_genome_a(nc); genome_b_new = load_genome_b(nc); } } }
+        // up
+        { let nc = wrap(x, y - 1); let vn = vel_up; let mn = load_mass(nc);
+          let fi = clamp(dot(vn, vec2<f32>(0.0, 1.0)), 0.0, mn / 4.0);
+          if (fi > 0.001) { let p = fi / (mass_new + 0.001); seed = pcg_hash(seed + 4u);
+            if (rand01(seed) < p) { genome_a_new = load_genome_a(nc); genome_b_new = load_genome_b(nc); } } }
+    }
+
+    // Reserves: a resident keeps its genome against other lineages
+    let zone = u32(textureLoad(reserve_mask, c, 0).x);
+    if (zone > 0u && reserves[zone - 1u].no_predation != 0u && m > 0.01
+        && is_resident(ga, zone) && !is_resident(genome_a_new, zone)) {
+        genome_a_new = ga;
+        genome_b_new = gb;
+    }
+
+    // Predation bookkeeping: a living cell taken over by a neighbor's genome
+    if (params.interactions != 0u && m > 0.01 && any(genome_a_new != ga)) {
+        atomicAdd(&interactions[agg_class(genome_a_new.w) * AGG_CLASSES + agg_class(ga.w)], 1u);
+    }
 
     // ================== MUTATIONS ==================
     // Only living cells mutate (dead cells are inert)
     if (mass_new > 0.01) {
-        let mut_rate = genome_b_new;
+        var mut_rate = genome_b_new;
+        if (is_resident(genome_a_new, zone)) {
+            mut_rate *= reserves[zone - 1u].mutation_mult;
+        }
 
         // Independent noise per gene channel
         seed = pcg_hash(seed + 100u);
@@ -297,12 +629,18 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
         seed = pcg_hash(seed + 104u);
         let noise_mut = rand_signed(seed);
 
-        // Mutate each gene with rate-scaled noise — smaller steps to preserve Lenia patterns
+        // The selected operator replaces the uniform deviates
+        var noise = vec4<f32>(noise_r, noise_mu, noise_sigma, noise_agg);
+        if (params.mutation_operator != MUTATION_UNIFORM) {
+            noise = mutation_deviates(pcg_hash(seed + 105u));
+        }
+
+        // Mutate each gene with rate-scaled noise — smaller steps to preserve Lenia patterns —
+        // within the gene constraints; locked genes keep their value
         let mm = params.mutation_rate_mult;
-        genome_a_new.x = clamp(genome_a_new.x + noise_r     * mut_rate * mm * 3.0,  3.0, 15.0);
-        genome_a_new.y = clamp(genome_a_new.y + noise_mu    * mut_rate * mm * 0.15, 0.05, 0.35);
-        genome_a_new.z = clamp(genome_a_new.z + noise_sigma * mut_rate * mm * 0.08, 0.005, 0.08);
-        genome_a_new.w = clamp(genome_a_new.w + noise_agg   * mut_rate * mm * 0.3,  0.0, 1.0);
+        let step = noise * params.gene_rates * mut_rate * mm * GENE_STEP;
+        let mutated = clamp(genome_a_new + step, params.gene_min, params.gene_max);
+        genome_a_new = select(mutated, genome_a_new, gene_locked());
 
         // Meta-mutation: mutation rate evolves too (smaller step)
         // Beta-prior prevents drift to 0 or 1
@@ -320,21 +658,31 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
         var neighbor_genome_b = 0.0;
         var neighbor_weight = 0.0;
 
-        // 4-connected neighbors, weighted by their mass
-        let nr = idx(x + 1, y); let mr = mass_in[nr];
-        let nl = idx(x - 1, y); let ml = mass_in[nl];
-        let nd = idx(x, y + 1); let md = mass_in[nd];
-        let nu = idx(x, y - 1); let mu_n = mass_in[nu];
-
-        neighbor_genome_a += genome_a_in[nr] * mr;
-        neighbor_genome_a += genome_a_in[nl] * ml;
-        neighbor_genome_a += genome_a_in[nd] * md;
-        neighbor_genome_a += genome_a_in[nu] * mu_n;
-        neighbor_genome_b += genome_b_in[nr] * mr;
-        neighbor_genome_b += genome_b_in[nl] * ml;
-        neighbor_genome_b += genome_b_in[nd] * md;
-        neighbor_genome_b += genome_b_in[nu] * mu_n;
-        neighbor_weight = mr + ml + md + mu_n;
+        if HEX_GRID {
+            // All six hex neighbors, weighted by their mass
+            for (var k = 0u; k < 6u; k++) {
+                let nc = hex_cell(c, k); let mn = load_mass(nc);
+                neighbor_genome_a += load_genome_a(nc) * mn;
+                neighbor_genome_b += load_genome_b(nc) * mn;
+                neighbor_weight += mn;
+            }
+        } else {
+            // 4-connected neighbors, weighted by their mass
+            let nr = wrap(x + 1, y); let mr = load_mass(nr);
+            let nl = wrap(x - 1, y); let ml = load_mass(nl);
+            let nd = wrap(x, y + 1); let md = load_mass(nd);
+            let nu = wrap(x, y - 1); let mu_n = load_mass(nu);
+
+            neighbor_genome_a += load_genome_a(nr) * mr;
+            neighbor_genome_a += load_genome_a(nl) * ml;
+            neighbor_genome_a += load_genome_a(nd) * md;
+            neighbor_genome_a += load_genome_a(nu) * mu_n;
+            neighbor_genome_b += load_genome_b(nr) * mr;
+            neighbor_genome_b += load_genome_b(nl) * ml;
+            neighbor_genome_b += load_genome_b(nd) * md;
+            neighbor_genome_b += load_genome_b(nu) * mu_n;
+            neighbor_weight = mr + ml + md + mu_n;
+        }
 
         if (neighbor_weight > 0.01) {
             let avg_ga = neighbor_genome_a / neighbor_weight;
@@ -345,8 +693,10 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     }
 
     // ================== WRITE OUTPUTS ==================
-    mass_out[i] = mass_new;
-    energy_out[i] = energy_new;
-    genome_a_out[i] = genome_a_new;
-    genome_b_out[i] = genome_b_new;
+    write_outputs(c, mass_new, energy_new, genome_a_new, genome_b_new);
+}
+
+fn write_outputs(c: vec2<i32>, m: f32, e: f32, ga: vec4<f32>, gb: f32) {
+    cells_out[index(c)] = Cell(m, e, gb);
+    textureStore(genome_a_out, c, ga);
 }
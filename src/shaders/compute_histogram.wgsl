@@ -0,0 +1,41 @@
+// ============================================================================
+// compute_histogram.wgsl — EvoLenia v2
+// Histogram of one field (mass, energy or resource) over [0, max_value]
+// for the viewport overlay (histogram.rs). Values at or above max_value
+// fall into the last bin, so a tall last bar means the field saturates.
+// Mass and energy count live cells only; resource counts every cell.
+// Visualization only — never read by the simulation.
+// ============================================================================
+
+struct Params {
+    field: u32,          // 0 = mass, 1 = energy, 2 = resource
+    bins: u32,           // histogram::HISTOGRAM_BINS
+    max_value: f32,      // upper edge of the last regular bin
+    live_mass: f32,      // lighter cells are empty (mass and energy)
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
+@group(0) @binding(2) var<storage, read> resource_map: array<f32>;
+@group(0) @binding(3) var<storage, read_write> bins: array<atomic<u32>>;
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+
+    let i = cell_index(gid.x, gid.y, WORLD_WIDTH);
+    var v = resource_map[i];
+    if (params.field < 2u) {
+        let cell = cells[i];
+        if (cell.mass <= params.live_mass) {
+            return;
+        }
+        v = select(cell.mass, cell.energy, params.field == 1u);
+    }
+
+    let t = max(v, 0.0) / max(params.max_value, 1e-6);
+    let bin = min(u32(t * f32(params.bins)), params.bins - 1u);
+    atomicAdd(&bins[bin], 1u);
+}
@@ -0,0 +1,178 @@
+// ============================================================================
+// compute_particles.wgsl — EvoLenia v2
+// Grazer / disperser particles over the continuous field (see particles.rs).
+//
+// Two passes run on the current state before each simulation step:
+//   apply  — per cell: fold the grazing / deposit flux recorded by the last
+//            update into the fields, and clear the marker layer
+//   update — per particle: steer up the mass gradient while hungry and away
+//            from it while loaded, graze the cell underneath, drop cargo
+//            (with the carried genome) on empty ground
+// Particles never write the fields themselves, so any number of them can
+// share a cell: their flux is summed with fixed-point atomics.
+// ============================================================================
+
+struct Particle {
+    pos: vec2<f32>,      // cells
+    vel: vec2<f32>,      // unit heading
+    genome_a: vec4<f32>, // genome of the last cell grazed
+    genome_b: f32,
+    cargo: f32,
+    energy: f32,
+    age: f32,
+    grazed: f32,
+    deposited: f32,
+    _pad0: f32,
+    _pad1: f32,
+}
+
+struct Params {
+    count: u32,
+    frame: u32,
+    speed: f32,      // cells per step
+    graze_rate: f32, // share of the cell's mass taken per step
+    capacity: f32,   // mass a particle can carry
+    drop_rate: f32,  // share of the cargo dropped per step
+    metabolism: f32, // energy spent per step
+    _pad: u32,
+}
+
+const FLUX_SCALE: f32 = 1048576.0; // fixed point: 2^20 per unit of mass
+const EMPTY: f32 = 0.05;           // below this mass a cell counts as empty ground
+const WANDER: f32 = 1.2;           // max random turn per step (radians)
+const SEEK: f32 = 8.0;             // weight of the mass gradient against the heading
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read_write> flux: array<atomic<i32>>;  // mass change per cell
+@group(0) @binding(3) var<storage, read_write> claims: array<atomic<u32>>; // depositor id + 1
+@group(0) @binding(4) var<storage, read_write> markers: array<u32>;        // 1 = particle here (render)
+
+// update
+@group(0) @binding(5) var<storage, read> cells_in: array<Cell>;
+@group(0) @binding(6) var genome_a_in: texture_2d<f32>;
+
+// apply
+@group(0) @binding(7) var<storage, read_write> cells: array<Cell>;
+@group(0) @binding(8) var genome_a_out: texture_storage_2d<rgba32float, write>;
+
+fn pcg_hash(inp: u32) -> u32 {
+    var state = inp * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn rand01(seed: u32) -> f32 {
+    return f32(pcg_hash(seed)) / 4294967295.0;
+}
+
+fn wrap(x: i32, y: i32) -> vec2<i32> {
+    let wx = ((x % i32(WORLD_WIDTH)) + i32(WORLD_WIDTH)) % i32(WORLD_WIDTH);
+    let wy = ((y % i32(WORLD_HEIGHT)) + i32(WORLD_HEIGHT)) % i32(WORLD_HEIGHT);
+    return vec2<i32>(wx, wy);
+}
+
+fn mass_at(x: i32, y: i32) -> f32 {
+    let c = wrap(x, y);
+    return cells_in[cell_index(u32(c.x), u32(c.y), WORLD_WIDTH)].mass;
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn apply(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+    let i = cell_index(gid.x, gid.y, WORLD_WIDTH);
+    let c = vec2<i32>(gid.xy);
+    markers[i] = 0u;
+
+    let delta = f32(atomicExchange(&flux[i], 0)) / FLUX_SCALE;
+    let claim = atomicExchange(&claims[i], 0u);
+    if (delta == 0.0 && claim == 0u) {
+        return;
+    }
+    // Several grazers on one cell can overdraw it; the clamp absorbs that and
+    // mass normalization the resulting drift
+    var cell = cells[i];
+    cell.mass = clamp(cell.mass + delta, 0.0, 1.0);
+    if (claim > 0u) {
+        // Seeded ground: the depositor's genome, with enough energy to settle
+        let p = particles[claim - 1u];
+        textureStore(genome_a_out, c, p.genome_a);
+        cell.mutation_rate = p.genome_b;
+        cell.energy = max(cell.energy, 0.5);
+    }
+    cells[i] = cell;
+}
+
+@compute @workgroup_size(WORKGROUP_LINEAR)
+fn update(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let id = gid.x;
+    if (id >= params.count) {
+        return;
+    }
+    var p = particles[id];
+    var seed = pcg_hash(id * 1664525u + params.frame * 22695477u + 1u);
+    let size = vec2<f32>(f32(WORLD_WIDTH), f32(WORLD_HEIGHT));
+
+    // ---- Steering: wander, plus the mass gradient (hungry) or against it (loaded) ----
+    let here = vec2<i32>(floor(p.pos));
+    let grad = vec2<f32>(
+        mass_at(here.x + 1, here.y) - mass_at(here.x - 1, here.y),
+        mass_at(here.x, here.y + 1) - mass_at(here.x, here.y - 1),
+    ) * 0.5;
+    let loaded = p.cargo > 0.5 * params.capacity;
+    let turn = (rand01(seed) - 0.5) * WANDER;
+    seed = pcg_hash(seed);
+    let heading = vec2<f32>(
+        p.vel.x * cos(turn) - p.vel.y * sin(turn),
+        p.vel.x * sin(turn) + p.vel.y * cos(turn),
+    );
+    var dir = heading + select(grad, -grad, loaded) * SEEK;
+    if (dot(dir, dir) < 1e-8) {
+        dir = vec2<f32>(1.0, 0.0);
+    }
+    p.vel = normalize(dir);
+    p.pos = (p.pos + p.vel * params.speed + size) % size;
+
+    // ---- Feeding / dispersal at the new cell ----
+    let c = wrap(i32(floor(p.pos.x)), i32(floor(p.pos.y)));
+    let i = cell_index(u32(c.x), u32(c.y), WORLD_WIDTH);
+    let cell = cells_in[i];
+    let m = cell.mass;
+    markers[i] = 1u;
+    p.energy -= params.metabolism;
+    p.age += 1.0;
+
+    if (!loaded && m > EMPTY) {
+        let take = min(m * params.graze_rate, params.capacity - p.cargo);
+        if (take > 0.0) {
+            atomicAdd(&flux[i], -i32(take * FLUX_SCALE));
+            p.cargo += take;
+            p.grazed += take;
+            p.energy = min(p.energy + take, 1.0);
+            p.genome_a = textureLoad(genome_a_in, c, 0);
+            p.genome_b = cell.mutation_rate;
+        }
+    } else if (m < EMPTY && p.cargo > 0.0) {
+        // Drop a share of the cargo; the last crumbs all at once
+        let drop = select(p.cargo * params.drop_rate, p.cargo, p.cargo < 0.01);
+        atomicAdd(&flux[i], i32(drop * FLUX_SCALE));
+        atomicMax(&claims[i], id + 1u);
+        p.cargo -= drop;
+        p.deposited += drop;
+    }
+
+    // ---- Starvation: the cargo falls where it is, a fresh particle spawns elsewhere ----
+    if (p.energy <= 0.0) {
+        if (p.cargo > 0.0) {
+            atomicAdd(&flux[i], i32(p.cargo * FLUX_SCALE));
+            p.deposited += p.cargo;
+            p.cargo = 0.0;
+        }
+        p.pos = vec2<f32>(rand01(seed), rand01(seed + 1u)) * size;
+        p.energy = 1.0;
+        p.age = 0.0;
+    }
+    particles[id] = p;
+}
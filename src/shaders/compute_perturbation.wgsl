@@ -0,0 +1,56 @@
+// ============================================================================
+// compute_perturbation.wgsl — EvoLenia v2
+// Ecological perturbation applied in place to the current state.
+//
+// Runs between steps, either once (Apply) or every step at a fraction of the
+// intensity (continuous), so no field ever round-trips through the CPU.
+// This pass only locates the cell; what happens to it is the WGSL of the
+// perturbation type (perturbation.rs), appended as `apply_perturbation`.
+// Most types act on a disc of toroidal radius `radius` around `center`
+// (pixels) with linear falloff (`disc_dose`); a barrier clears two
+// full-height walls every step while it stands.
+// ============================================================================
+
+struct Params {
+    kind: u32,       // PerturbationType::shader_kind (0 = none)
+    center_x: f32,   // pixels
+    center_y: f32,
+    radius: f32,     // pixels
+    intensity: f32,  // amplitude of this application
+    extra0: f32,     // type-specific (Perturbation::uniform)
+    extra1: f32,
+    extra2: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> cells: array<Cell>;
+@group(0) @binding(2) var<storage, read_write> resource_map: array<f32>;
+
+// Shortest signed offset on a ring of the given size
+fn wrapped(d: f32, size: f32) -> f32 {
+    if (d > size * 0.5) {
+        return d - size;
+    }
+    if (d < -size * 0.5) {
+        return d + size;
+    }
+    return d;
+}
+
+// Intensity at offset (dx, dy) from the center: linear falloff to 0 at `radius`
+fn disc_dose(dx: f32, dy: f32) -> f32 {
+    let dist = sqrt(dx * dx + dy * dy);
+    return params.intensity * max(1.0 - dist / params.radius, 0.0);
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+
+    let i = cell_index(gid.x, gid.y, WORLD_WIDTH);
+    let dx = wrapped(f32(gid.x) - params.center_x, f32(WORLD_WIDTH));
+    let dy = wrapped(f32(gid.y) - params.center_y, f32(WORLD_HEIGHT));
+    apply_perturbation(i, dx, dy);
+}
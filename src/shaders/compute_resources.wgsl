@@ -6,6 +6,9 @@
 // and are consumed by organisms. This creates spatial selection pressure:
 // areas depleted by organisms become deserts, pushing evolution to
 // disperse or become more efficient.
+//
+// The global flow field (flow.rs) carries nutrients downstream (first-order
+// upwind advection), so colonies deplete the ground downwind of them.
 // ============================================================================
 
 struct Params {
@@ -14,42 +17,67 @@ struct Params {
     diffusion: f32,
     feed_rate: f32,
     consumption: f32,
-    _pad1: u32,
-    _pad2: u32,
+    anisotropy: f32, // horizontal / vertical diffusion strength
+    flow_transport: f32, // scale of flow_field moving the resources (0 = off)
     _pad3: u32,
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
-@group(0) @binding(1) var<storage, read> mass: array<f32>;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
 @group(0) @binding(2) var<storage, read_write> resource_map: array<f32>;
+@group(0) @binding(3) var flow_field: texture_2d<f32>;
 
 // Toroidal indexing
 fn idx(x: i32, y: i32) -> u32 {
-    let wx = ((x % i32(params.width)) + i32(params.width)) % i32(params.width);
-    let wy = ((y % i32(params.height)) + i32(params.height)) % i32(params.height);
-    return u32(wy) * params.width + u32(wx);
+    let wx = ((x % i32(WORLD_WIDTH)) + i32(WORLD_WIDTH)) % i32(WORLD_WIDTH);
+    let wy = ((y % i32(WORLD_HEIGHT)) + i32(WORLD_HEIGHT)) % i32(WORLD_HEIGHT);
+    return cell_index(u32(wx), u32(wy), WORLD_WIDTH);
 }
 
-@compute @workgroup_size(16, 16)
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
 fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let x = i32(gid.x);
     let y = i32(gid.y);
 
-    if (gid.x >= params.width || gid.y >= params.height) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
         return;
     }
 
     let i = idx(x, y);
     let r = resource_map[i];
-    let m = mass[i];
+    let m = cells[i].mass;
 
-    // Discrete Laplacian for diffusion (5-point stencil)
+    // Discrete Laplacian for diffusion (5-point stencil, 7-point on hex)
     let r_right = resource_map[idx(x + 1, y)];
     let r_left  = resource_map[idx(x - 1, y)];
-    let r_up    = resource_map[idx(x, y - 1)];
-    let r_down  = resource_map[idx(x, y + 1)];
 
-    let laplacian = (r_right + r_left + r_up + r_down - 4.0 * r) / 4.0;
+    // Axis weights (√a, 1/√a) as for advection: a = 1 is the plain stencil
+    let s = sqrt(max(params.anisotropy, 1e-3));
+    var laplacian = 0.0;
+    var r_up = 0.0;
+    var r_down = 0.0;
+    if HEX_GRID {
+        // The four diagonal neighbors lie at ±60°, weighted s·cos² + sin²/s
+        let w = 0.25 * s + 0.75 / s;
+        var diagonal = 0.0;
+        for (var k = 1u; k < 6u; k++) {
+            if (k == 3u) { continue; }
+            let n = hex_neighbor(x, y, k);
+            diagonal += resource_map[idx(n.x, n.y)] - r;
+        }
+        // As for the velocity, the upper and lower pairs stand in for up/down
+        let n1 = hex_neighbor(x, y, 1u);
+        let n2 = hex_neighbor(x, y, 2u);
+        let n4 = hex_neighbor(x, y, 4u);
+        let n5 = hex_neighbor(x, y, 5u);
+        r_up = 0.5 * (resource_map[idx(n1.x, n1.y)] + resource_map[idx(n2.x, n2.y)]);
+        r_down = 0.5 * (resource_map[idx(n4.x, n4.y)] + resource_map[idx(n5.x, n5.y)]);
+        laplacian = (s * (r_right + r_left - 2.0 * r) + w * diagonal) / 6.0;
+    } else {
+        r_up   = resource_map[idx(x, y - 1)];
+        r_down = resource_map[idx(x, y + 1)];
+        laplacian = (s * (r_right + r_left - 2.0 * r) + (r_up + r_down - 2.0 * r) / s) / 4.0;
+    }
 
     // Gray-Scott dynamics (parameterized via uniforms):
     // - Diffusion: nutrients spread spatially
@@ -59,7 +87,17 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let feed          = params.feed_rate * (1.0 - r);
     let consumed      = r * m * params.consumption;
 
-    let r_new = clamp(r + diffusion + feed - consumed, 0.0, 1.0);
+    // Upwind transport along the flow: take the difference on the side the
+    // flow comes from
+    var transported = 0.0;
+    if (params.flow_transport != 0.0) {
+        let u = textureLoad(flow_field, vec2<i32>(x, y), 0).xy * params.flow_transport;
+        let dr_dx = select(r - r_left, r_right - r, u.x < 0.0);
+        let dr_dy = select(r - r_up, r_down - r, u.y < 0.0);
+        transported = -(u.x * dr_dx + u.y * dr_dy);
+    }
+
+    let r_new = clamp(r + diffusion + feed - consumed + transported, 0.0, 1.0);
 
     resource_map[i] = r_new;
 }
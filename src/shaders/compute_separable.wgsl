@@ -0,0 +1,86 @@
+// ============================================================================
+// compute_separable.wgsl — EvoLenia v2
+// Separable approximation of the Lenia convolution (see kernel.rs).
+//
+// blur_h: for each tier and separable term k, filter the mass of the cell
+//   state along x with the term's taps → three rgba textures (one per term, one channel
+//   per tier).
+// blur_v: filter those along y with the scaled taps and sum the terms →
+//   one rgba texture holding Σ K·m for the four kernel tiers.
+//
+// compute_evolution.wgsl blends the tiers by genome radius. Two 27-tap passes
+// replace the 27×27 neighborhood loop of the exact mode.
+// ============================================================================
+
+const MAX_R: i32 = 13;
+const RANK: i32 = 3;
+
+struct SeparableKernel {
+    horizontal: array<vec4<f32>, 81>, // [tap * RANK + k], one channel per tier
+    vertical: array<vec4<f32>, 81>,
+    sums: vec4<f32>,
+}
+
+@group(0) @binding(1) var<uniform> weights: SeparableKernel;
+
+// blur_h
+@group(0) @binding(2) var<storage, read> cells: array<Cell>;
+@group(0) @binding(3) var term0_out: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(4) var term1_out: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(5) var term2_out: texture_storage_2d<rgba32float, write>;
+
+// blur_v
+@group(0) @binding(6) var term0: texture_2d<f32>;
+@group(0) @binding(7) var term1: texture_2d<f32>;
+@group(0) @binding(8) var term2: texture_2d<f32>;
+@group(0) @binding(9) var potential_out: texture_storage_2d<rgba32float, write>;
+
+fn wrap(x: i32, y: i32) -> vec2<i32> {
+    let wx = ((x % i32(WORLD_WIDTH)) + i32(WORLD_WIDTH)) % i32(WORLD_WIDTH);
+    let wy = ((y % i32(WORLD_HEIGHT)) + i32(WORLD_HEIGHT)) % i32(WORLD_HEIGHT);
+    return vec2<i32>(wx, wy);
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn blur_h(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+    let x = i32(gid.x);
+    let y = i32(gid.y);
+
+    var h0 = vec4<f32>(0.0);
+    var h1 = vec4<f32>(0.0);
+    var h2 = vec4<f32>(0.0);
+    for (var dx = -MAX_R; dx <= MAX_R; dx = dx + 1) {
+        let n = wrap(x + dx, y);
+        let m = cells[cell_index(u32(n.x), u32(n.y), WORLD_WIDTH)].mass;
+        let tap = (dx + MAX_R) * RANK;
+        h0 += m * weights.horizontal[tap];
+        h1 += m * weights.horizontal[tap + 1];
+        h2 += m * weights.horizontal[tap + 2];
+    }
+    let c = vec2<i32>(x, y);
+    textureStore(term0_out, c, h0);
+    textureStore(term1_out, c, h1);
+    textureStore(term2_out, c, h2);
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn blur_v(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+    let x = i32(gid.x);
+    let y = i32(gid.y);
+
+    var acc = vec4<f32>(0.0);
+    for (var dy = -MAX_R; dy <= MAX_R; dy = dy + 1) {
+        let c = wrap(x, y + dy);
+        let tap = (dy + MAX_R) * RANK;
+        acc += textureLoad(term0, c, 0) * weights.vertical[tap];
+        acc += textureLoad(term1, c, 0) * weights.vertical[tap + 1];
+        acc += textureLoad(term2, c, 0) * weights.vertical[tap + 2];
+    }
+    textureStore(potential_out, vec2<i32>(x, y), acc);
+}
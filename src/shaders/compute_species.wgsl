@@ -0,0 +1,57 @@
+// ============================================================================
+// compute_species.wgsl — EvoLenia v2
+// Species map: labels each live cell with its genome cluster, from the
+// cluster representatives uploaded by the CPU (territory.rs). The rule is
+// GenomeClusters::assign without founding: the first cluster closer than the
+// threshold, else the nearest one once the table is full, else UNASSIGNED
+// (the CPU founds a cluster for it at the next diagnostics sample).
+// Visualization and analysis only — never read by the simulation.
+// ============================================================================
+
+const MAX_SPECIES: u32 = 64u;
+const EMPTY: f32 = -1.0;
+const UNASSIGNED: f32 = -2.0;
+// Genome space normalization of metrics::genome_distance (r, mu, sigma, aggressivity)
+const GENOME_SCALE: vec4<f32> = vec4<f32>(1.0 / 16.0, 1.0, 1.0 / 0.3, 1.0);
+
+struct Params {
+    count: u32,          // clusters in `centroids`
+    full: u32,           // 1 = no new cluster can be founded
+    threshold: f32,      // metrics::SPECIES_THRESHOLD
+    live_mass: f32,      // popgen::LIVE_MASS_THRESHOLD
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
+@group(0) @binding(2) var genome_a: texture_2d<f32>;
+@group(0) @binding(3) var<storage, read> centroids: array<vec4<f32>, MAX_SPECIES>;
+// `species` is a registry channel (channels.rs), declared when composed
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+
+    let i = cell_index(gid.x, gid.y, WORLD_WIDTH);
+    if (cells[i].mass <= params.live_mass) {
+        species[i] = EMPTY;
+        return;
+    }
+
+    let genome = textureLoad(genome_a, vec2<i32>(gid.xy), 0) * GENOME_SCALE;
+    var nearest = 0u;
+    var best = 1e30;
+    for (var id = 0u; id < min(params.count, MAX_SPECIES); id++) {
+        let d = length(genome - centroids[id] * GENOME_SCALE);
+        if (d < params.threshold) {
+            species[i] = f32(id);
+            return;
+        }
+        if (d < best) {
+            best = d;
+            nearest = id;
+        }
+    }
+    species[i] = select(UNASSIGNED, f32(nearest), params.full == 1u && params.count > 0u);
+}
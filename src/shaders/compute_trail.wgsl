@@ -0,0 +1,30 @@
+// ============================================================================
+// compute_trail.wgsl — EvoLenia v2
+// Afterimage layer: each step the trail decays and is re-imprinted with the
+// local mass flux (mass × flow speed), so paths of fast creatures persist.
+// Visualization only — never read back by the simulation.
+// ============================================================================
+
+struct Params {
+    width: u32,
+    height: u32,
+    decay: f32,   // per-step retention factor (0.9 = short, 0.999 = long trails)
+    gain: f32,    // imprint scale for mass × speed
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
+@group(0) @binding(2) var<storage, read> velocity: array<vec2<f32>>;
+// `trail` is a registry channel (channels.rs), declared when composed
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
+        return;
+    }
+
+    let i = cell_index(gid.x, gid.y, WORLD_WIDTH);
+    let m = cells[i].mass;
+    let imprint = clamp(m * length(velocity[i]) * params.gain, 0.0, 1.0);
+    trail[i] = max(trail[i] * params.decay, imprint);
+}
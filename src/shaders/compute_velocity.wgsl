@@ -1,81 +1,80 @@
 // ============================================================================
 // compute_velocity.wgsl — EvoLenia v2
-// Computes the advection velocity field from mass gradients and predation.
-//
-// Biology: Predators (high aggressivity) orient their mass flow toward
-// prey (lower mass neighbors), creating predator-prey spatial dynamics.
+// Computes the advection velocity field from mass gradients and predation
+// (velocity_field.wgsl), turned by the world's rotation, plus the global
+// flow field. Only used when the velocity pass runs separately;
+// by default compute_evolution.wgsl fuses it.
 // ============================================================================
 
 struct Params {
     width: u32,
     height: u32,
     frame: u32,
-    _pad: u32,
+    anisotropy: f32,
+    flow_strength: f32, // scale of flow_field (0 = off)
+    rotation: f32,      // Coriolis angular velocity ω (0 = no rotation)
+    rotation_center: vec2<f32>, // pole, in cells
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
-@group(0) @binding(1) var<storage, read> mass: array<f32>;
-@group(0) @binding(2) var<storage, read> genome_a: array<vec4<f32>>;
+@group(0) @binding(1) var<storage, read> cells: array<Cell>;
+@group(0) @binding(2) var genome_a: texture_2d<f32>;
 @group(0) @binding(3) var<storage, read_write> velocity: array<vec2<f32>>;
+@group(0) @binding(4) var flow_field: texture_2d<f32>;
 
 // Toroidal indexing — wraps around edges for a borderless world
 fn idx(x: i32, y: i32) -> u32 {
-    let wx = ((x % i32(params.width)) + i32(params.width)) % i32(params.width);
-    let wy = ((y % i32(params.height)) + i32(params.height)) % i32(params.height);
-    return u32(wy) * params.width + u32(wx);
+    let wx = ((x % i32(WORLD_WIDTH)) + i32(WORLD_WIDTH)) % i32(WORLD_WIDTH);
+    let wy = ((y % i32(WORLD_HEIGHT)) + i32(WORLD_HEIGHT)) % i32(WORLD_HEIGHT);
+    return cell_index(u32(wx), u32(wy), WORLD_WIDTH);
 }
 
-@compute @workgroup_size(16, 16)
+fn mass_at(x: i32, y: i32) -> f32 {
+    return cells[idx(x, y)].mass;
+}
+
+// Mass of hex neighbor k of (x, y)
+fn hex_mass(x: i32, y: i32, k: u32) -> f32 {
+    let n = hex_neighbor(x, y, k);
+    return mass_at(n.x, n.y);
+}
+
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
 fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let x = i32(gid.x);
     let y = i32(gid.y);
 
-    if (gid.x >= params.width || gid.y >= params.height) {
+    if (gid.x >= WORLD_WIDTH || gid.y >= WORLD_HEIGHT) {
         return;
     }
 
     let i = idx(x, y);
-    let m_center = mass[i];
-    let agg = genome_a[i].w; // aggressivity channel
-
-    // Central differences for mass gradient ∇M
-    let m_right = mass[idx(x + 1, y)];
-    let m_left  = mass[idx(x - 1, y)];
-    let m_up    = mass[idx(x, y - 1)];
-    let m_down  = mass[idx(x, y + 1)];
-
-    // Gradient of mass field (points toward higher mass)
-    let grad_m = vec2<f32>(
-        (m_right - m_left) * 0.5,
-        (m_down - m_up) * 0.5
-    );
+    let agg = textureLoad(genome_a, vec2<i32>(x, y), 0).w; // aggressivity channel
 
-    // Base velocity: mass flows along gradient, modulated by aggressivity
-    // Predators (agg > 0.5) move TOWARD higher mass (prey detection)
-    var vel = grad_m * agg;
-
-    // Predation flux component: predators push mass toward weaker neighbors
-    if (agg > 0.5 && m_center > 0.01) {
-        var predation_vel = vec2<f32>(0.0, 0.0);
-
-        // right
-        let diff0 = m_center - m_right;
-        if (diff0 > 0.0) { predation_vel += vec2<f32>(1.0, 0.0) * agg * diff0 * 0.008; }
-        // left
-        let diff1 = m_center - m_left;
-        if (diff1 > 0.0) { predation_vel += vec2<f32>(-1.0, 0.0) * agg * diff1 * 0.008; }
-        // up
-        let diff2 = m_center - m_up;
-        if (diff2 > 0.0) { predation_vel += vec2<f32>(0.0, -1.0) * agg * diff2 * 0.008; }
-        // down
-        let diff3 = m_center - m_down;
-        if (diff3 > 0.0) { predation_vel += vec2<f32>(0.0, 1.0) * agg * diff3 * 0.008; }
-
-        vel += predation_vel;
+    var vel: vec2<f32>;
+    if HEX_GRID {
+        // The two upper and two lower neighbors stand in for up/down
+        vel = advection_velocity(
+            mass_at(x, y),
+            hex_mass(x, y, 0u),
+            hex_mass(x, y, 3u),
+            0.5 * (hex_mass(x, y, 1u) + hex_mass(x, y, 2u)),
+            0.5 * (hex_mass(x, y, 4u) + hex_mass(x, y, 5u)),
+            agg,
+            params.anisotropy,
+        );
+    } else {
+        // Central differences for mass gradient ∇M (see velocity_field.wgsl)
+        vel = advection_velocity(
+            mass_at(x, y),
+            mass_at(x + 1, y),
+            mass_at(x - 1, y),
+            mass_at(x, y - 1),
+            mass_at(x, y + 1),
+            agg,
+            params.anisotropy,
+        );
     }
-
-    // Clamp velocity to prevent instability
-    vel = clamp(vel, vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0));
-
-    velocity[i] = vel;
+    vel = coriolis(vel, vec2<f32>(f32(x), f32(y)), params.rotation, params.rotation_center);
+    velocity[i] = with_flow(vel, textureLoad(flow_field, vec2<i32>(x, y), 0).xy, params.flow_strength);
 }
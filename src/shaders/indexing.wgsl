@@ -0,0 +1,86 @@
+// ============================================================================
+// indexing.wgsl — EvoLenia v2
+// Cell index translation shared by the shaders that address the flat
+// per-pixel buffers (cell state, resource map, velocity, trail). pipeline.rs
+// prepends it to those shaders together with `const MORTON_LAYOUT: bool`,
+// which selects row-major or Morton (Z-order) cell order (see CellLayout in
+// world.rs).
+// Morton order keeps 2D neighbors close in memory; it requires a square,
+// power-of-two world.
+// It also carries the lattice helpers selected by `const HEX_GRID: bool`
+// (see GridType in world.rs): on the hex grid odd rows are shifted half a
+// cell to the right and rows are √3/2 apart (odd-r offset coordinates).
+// ============================================================================
+
+// Interleaved per-cell state (CellState in world.rs): one struct per cell
+// in the ping-pong cell buffers, read and written as a whole
+struct Cell {
+    mass: f32,
+    energy: f32,
+    mutation_rate: f32, // genome B
+}
+
+// Spread the low 16 bits of v to the even bit positions
+fn morton_spread(v: u32) -> u32 {
+    var b = v & 0x0000ffffu;
+    b = (b | (b << 8u)) & 0x00ff00ffu;
+    b = (b | (b << 4u)) & 0x0f0f0f0fu;
+    b = (b | (b << 2u)) & 0x33333333u;
+    b = (b | (b << 1u)) & 0x55555555u;
+    return b;
+}
+
+// Flat buffer index of the (already wrapped) cell (x, y)
+fn cell_index(x: u32, y: u32, width: u32) -> u32 {
+    if MORTON_LAYOUT {
+        return morton_spread(x) | (morton_spread(y) << 1u);
+    }
+    return y * width + x;
+}
+
+// ======================== Hex Lattice ========================
+
+const HEX_ROW: f32 = 0.8660254; // row spacing in cell widths (√3/2)
+
+// Offset (x, y) → axial (q, r); neighbors differ by a fixed axial step
+fn hex_axial(x: i32, y: i32) -> vec2<i32> {
+    return vec2<i32>(x - (y - (y & 1)) / 2, y);
+}
+
+// Axial (q, r) → offset (x, y)
+fn hex_offset(a: vec2<i32>) -> vec2<i32> {
+    return vec2<i32>(a.x + (a.y - (a.y & 1)) / 2, a.y);
+}
+
+// Axial step to neighbor k, counter-clockwise from +x (y points down)
+fn hex_axial_step(k: u32) -> vec2<i32> {
+    switch k {
+        case 0u: { return vec2<i32>(1, 0); }
+        case 1u: { return vec2<i32>(1, -1); }
+        case 2u: { return vec2<i32>(0, -1); }
+        case 3u: { return vec2<i32>(-1, 0); }
+        case 4u: { return vec2<i32>(-1, 1); }
+        default: { return vec2<i32>(0, 1); }
+    }
+}
+
+// Unwrapped coordinates of hex neighbor k of (x, y)
+fn hex_neighbor(x: i32, y: i32, k: u32) -> vec2<i32> {
+    return hex_offset(hex_axial(x, y) + hex_axial_step(k));
+}
+
+// Unit vector toward hex neighbor k
+fn hex_dir(k: u32) -> vec2<f32> {
+    let a = f32(k) * 1.0471976; // k · 60°
+    return vec2<f32>(cos(a), -sin(a));
+}
+
+// Physical offset from a cell in row y to the cell (dx, dy) away, in cell
+// widths — what kernel distances are measured on
+fn lattice_offset(y: i32, dx: i32, dy: i32) -> vec2<f32> {
+    if HEX_GRID {
+        let shift = 0.5 * f32(((y + dy) & 1) - (y & 1));
+        return vec2<f32>(f32(dx) + shift, f32(dy) * HEX_ROW);
+    }
+    return vec2<f32>(f32(dx), f32(dy));
+}
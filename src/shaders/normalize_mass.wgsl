@@ -6,6 +6,12 @@
 //   into a global atomic counter.
 // Pass B (normalize_mass): A single correction factor is applied globally
 //   so that total mass returns to the target value.
+// Both passes dispatch 1D over the cell buffer; neither depends on its order.
+//
+// The sum pass may run only every few steps (NormalizationCadence). Sums
+// alternate between the two mass_sum slots; in between, Pass B replays the
+// damped corrections applied since the latest sum, with the per-step drift
+// measured between the last two sums, to estimate the current total.
 //
 // Biology: This enforces the conservation law — mass is neither created
 // nor destroyed, only redistributed. This is the thermodynamic foundation
@@ -20,48 +26,84 @@ struct Params {
     target_mass_x1000: u32, // target mass * 1000, encoded as u32
     damping_x1000: u32,    // damping factor * 1000
     enabled: u32,          // 0 = disabled, 1 = enabled
-    _pad1: u32,
-    _pad2: u32,
-    _pad3: u32,
+    sum_slot: u32,         // mass_sum slot of the latest sum
+    steps_since_sum: u32,  // 0 = the sum pass ran this step
+    drift_interval: u32,   // steps between the last two sums, 0 = unknown
 }
 
 @group(0) @binding(0) var<uniform> params: Params;
-@group(0) @binding(1) var<storage, read_write> mass: array<f32>;
+@group(0) @binding(1) var<storage, read_write> cells: array<Cell>;
 @group(0) @binding(2) var<storage, read_write> mass_sum: array<atomic<u32>>;
-// mass_sum[0] = accumulated total mass * 1000 (integer atomics)
-// mass_sum[1] = pixel count (for normalization)
+// mass_sum[sum_slot] = accumulated total mass * 1000 (integer atomics)
+// mass_sum[1 - sum_slot] = the previous sum
 
-@compute @workgroup_size(256)
+@compute @workgroup_size(WORKGROUP_LINEAR)
 fn sum_mass(@builtin(global_invocation_id) gid: vec3<u32>) {
-    let total_pixels = params.width * params.height;
+    let total_pixels = WORLD_WIDTH * WORLD_HEIGHT;
     if (gid.x >= total_pixels) {
         return;
     }
 
     // Atomically add mass * 1000 (integer representation for atomics)
-    let m = mass[gid.x];
+    let m = cells[gid.x].mass;
     let m_int = u32(m * 1000.0);
-    atomicAdd(&mass_sum[0], m_int);
+    atomicAdd(&mass_sum[params.sum_slot], m_int);
 }
 
 // --- Pass B: Apply correction factor to all pixels ---
 
-@compute @workgroup_size(256)
+// Soft correction: blend toward target with damping factor (parameterized)
+fn damped_correction(actual_total: f32, target_total: f32, damping: f32) -> f32 {
+    let raw_correction = target_total / actual_total;
+    return 1.0 + (raw_correction - 1.0) * damping;
+}
+
+// Total after one normalization step (no correction near an empty world)
+fn corrected_total(total: f32, target_total: f32, damping: f32) -> f32 {
+    if (total > 0.001) {
+        return total * damped_correction(total, target_total, damping);
+    }
+    return total;
+}
+
+// Current total mass: the fresh sum, or an extrapolation from the last two
+fn estimated_total(target_total: f32, damping: f32) -> f32 {
+    let measured = f32(atomicLoad(&mass_sum[params.sum_slot])) / 1000.0;
+    if (params.steps_since_sum == 0u) {
+        return measured;
+    }
+
+    // Per-step drift: the latest sum vs what the corrections alone predicted
+    var growth = 1.0;
+    let previous = f32(atomicLoad(&mass_sum[1u - params.sum_slot])) / 1000.0;
+    if (params.drift_interval > 0u && previous > 0.001) {
+        var predicted = previous;
+        for (var i = 0u; i < params.drift_interval; i = i + 1u) {
+            predicted = corrected_total(predicted, target_total, damping);
+        }
+        growth = clamp(pow(measured / max(predicted, 0.001), 1.0 / f32(params.drift_interval)), 0.5, 1.5);
+    }
+
+    var total = measured;
+    for (var i = 0u; i < params.steps_since_sum; i = i + 1u) {
+        total = corrected_total(total, target_total, damping) * growth;
+    }
+    return total;
+}
+
+@compute @workgroup_size(WORKGROUP_LINEAR)
 fn normalize(@builtin(global_invocation_id) gid: vec3<u32>) {
-    let total_pixels = params.width * params.height;
+    let total_pixels = WORLD_WIDTH * WORLD_HEIGHT;
     if (gid.x >= total_pixels) {
         return;
     }
 
-    let actual_total = f32(atomicLoad(&mass_sum[0])) / 1000.0;
     let target_total = f32(params.target_mass_x1000) / 1000.0;
+    let damping = f32(params.damping_x1000) / 1000.0;
+    let actual_total = estimated_total(target_total, damping);
 
     if (params.enabled > 0u && actual_total > 0.001) {
-        let raw_correction = target_total / actual_total;
-        // Soft correction: blend toward target with damping factor (parameterized)
-        let damping = f32(params.damping_x1000) / 1000.0;
-        let correction = 1.0 + (raw_correction - 1.0) * damping;
-        let corrected = clamp(mass[gid.x] * correction, 0.0, 1.0);
-        mass[gid.x] = corrected;
+        let correction = damped_correction(actual_total, target_total, damping);
+        cells[gid.x].mass = clamp(cells[gid.x].mass * correction, 0.0, 1.0);
     }
 }
@@ -0,0 +1,109 @@
+// ============================================================================
+// postfx.wgsl — EvoLenia v2
+// HDR post-processing: bright-pass extraction, separable Gaussian blur at
+// half resolution, and bloom composite + soft tonemap onto the swapchain.
+//
+// Passes:
+//   fs_bright    : HDR scene → bloom A (only the part above the threshold)
+//   fs_blur_h    : bloom A → bloom B (horizontal 9-tap Gaussian)
+//   fs_blur_v    : bloom B → bloom A (vertical 9-tap Gaussian)
+//   fs_composite : HDR scene + bloom A → swapchain
+// ============================================================================
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct PostParams {
+    threshold: f32,     // luminance above which pixels feed the bloom
+    intensity: f32,     // bloom strength in the composite (0 = off)
+    knee: f32,          // tonemap: values below pass through unchanged
+    tonemap: u32,       // 0 = clamp, 1 = soft shoulder above the knee
+    texel: vec2<f32>,   // 1 / bloom target size
+    _pad0: f32,
+    _pad1: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: PostParams;
+@group(0) @binding(1) var src_a: texture_2d<f32>;
+@group(0) @binding(2) var src_b: texture_2d<f32>;
+@group(0) @binding(3) var src_sampler: sampler;
+
+// Full-screen triangle pair, same layout as render.wgsl
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    var pos: vec2<f32>;
+    var uv: vec2<f32>;
+
+    switch vertex_index {
+        case 0u: { pos = vec2<f32>(-1.0, -1.0); uv = vec2<f32>(0.0, 1.0); }
+        case 1u: { pos = vec2<f32>( 1.0, -1.0); uv = vec2<f32>(1.0, 1.0); }
+        case 2u: { pos = vec2<f32>(-1.0,  1.0); uv = vec2<f32>(0.0, 0.0); }
+        case 3u: { pos = vec2<f32>(-1.0,  1.0); uv = vec2<f32>(0.0, 0.0); }
+        case 4u: { pos = vec2<f32>( 1.0, -1.0); uv = vec2<f32>(1.0, 1.0); }
+        case 5u: { pos = vec2<f32>( 1.0,  1.0); uv = vec2<f32>(1.0, 0.0); }
+        default: { pos = vec2<f32>(0.0, 0.0); uv = vec2<f32>(0.0, 0.0); }
+    }
+
+    out.position = vec4<f32>(pos, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn luminance(c: vec3<f32>) -> f32 {
+    return dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let c = textureSample(src_a, src_sampler, in.uv).rgb;
+    let l = luminance(c);
+    // Keep only the excess above the threshold, preserving hue
+    let excess = max(l - params.threshold, 0.0) / max(l, 1e-4);
+    return vec4<f32>(c * excess, 1.0);
+}
+
+// 9-tap Gaussian (σ ≈ 2 texels), weights sum to 1
+const BLUR_WEIGHTS = array<f32, 5>(0.2270, 0.1946, 0.1216, 0.0541, 0.0162);
+
+fn blur(uv: vec2<f32>, dir: vec2<f32>) -> vec4<f32> {
+    var acc = textureSample(src_a, src_sampler, uv).rgb * BLUR_WEIGHTS[0];
+    for (var i = 1; i < 5; i++) {
+        let off = dir * params.texel * f32(i);
+        acc += textureSample(src_a, src_sampler, uv + off).rgb * BLUR_WEIGHTS[i];
+        acc += textureSample(src_a, src_sampler, uv - off).rgb * BLUR_WEIGHTS[i];
+    }
+    return vec4<f32>(acc, 1.0);
+}
+
+@fragment
+fn fs_blur_h(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_v(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
+
+// Identity below the knee, exponential roll-off towards 1.0 above it, so the
+// regular (LDR) palette is untouched and only glowing regions are compressed.
+fn soft_shoulder(x: vec3<f32>) -> vec3<f32> {
+    let k = clamp(params.knee, 0.0, 0.99);
+    let w = 1.0 - k;
+    let rolled = k + w * (vec3<f32>(1.0) - exp(-(x - vec3<f32>(k)) / w));
+    return select(x, rolled, x > vec3<f32>(k));
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(src_a, src_sampler, in.uv).rgb;
+    let bloom = textureSample(src_b, src_sampler, in.uv).rgb;
+    let hdr = scene + bloom * params.intensity;
+    if params.tonemap == 1u {
+        return vec4<f32>(soft_shoulder(hdr), 1.0);
+    }
+    return vec4<f32>(clamp(hdr, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
@@ -11,6 +11,29 @@
 //   5 = Metabolic Stress: Shows energy deficit — cyan=healthy, magenta=starving
 //   6 = Advection Flux: Velocity field magnitude — blue=still, yellow=fast
 //   7 = Trophic Roles: Prey(green) / Opportunist(blue) / Predator(red)
+//
+// Each mode's primary intensity field (mass, or flow speed for mode 6) passes
+// through display_adjust(): exposure → min/max window → tone transform
+// (linear / log / histogram-equalized) → gamma.
+//
+// Output goes to an HDR target (see postfx.wgsl). With hdr_emission > 0,
+// energetic predators and dense blooms are pushed above 1.0 so they glow.
+// The optional trail layer (compute_trail.wgsl) is added on top of any mode.
+// With species colors on, mode 0 colors every species map cluster from the
+// species palette (territory.rs), so a species keeps its color as it drifts.
+// The optional territory overlay (territory.rs) tints cells with the color
+// of the species that held them most over recent samples.
+// The optional diff overlay tints cells by mass minus a reference snapshot:
+// warm where mass was gained, blue where it was lost.
+// Cells are sampled nearest-neighbor by default; smooth sampling filters the
+// cell state and genome bilinearly instead. At high zoom an anti-aliased pixel grid
+// marks cell boundaries.
+// Mode colors come from the palette table (theme.rs); the colors listed above
+// are the Classic palette, alternatives are colorblind-safe.
+//
+// fs_surface is the 3D surface view: the displayed mass intensity becomes a
+// height field, ray-marched from the orbit camera (camera.rs OrbitCamera) and
+// colored by the same mode and overlays as the flat view.
 // ============================================================================
 
 struct VertexOutput {
@@ -46,7 +69,19 @@ struct RenderParams {
     width: u32,
     height: u32,
     visualization_mode: u32,
-    _pad: u32,
+    diff_range: f32,        // |mass - reference| shown at full overlay color
+    exposure: f32,
+    gamma: f32,
+    range_min: f32,
+    range_max: f32,
+    transform: u32,         // 0 = linear, 1 = log, 2 = histogram-equalized
+    hdr_emission: f32,      // HDR boost of energetic regions (0 = LDR output)
+    trail_opacity: f32,     // afterimage layer strength (0 = hidden)
+    diff_opacity: f32,      // reference diff overlay strength (0 = hidden)
+    territory_opacity: f32, // territory overlay strength (0 = hidden)
+    particle_opacity: f32,  // grazer marker strength (0 = hidden)
+    flow_opacity: f32,      // flow streamline preview strength (0 = hidden)
+    species_colors: u32,    // 1 = mode 0 colors by species map id (compute_species.wgsl)
 }
 
 struct CameraUniforms {
@@ -54,42 +89,107 @@ struct CameraUniforms {
     zoom: f32,              // zoom factor (>1 = zoomed in)
     aspect_ratio: f32,      // window aspect ratio
     world_aspect: f32,      // world aspect ratio
+    grid_alpha: f32,        // pixel-grid overlay opacity (0 = hidden)
+    cell_px: f32,           // on-screen size of one world cell, in pixels
+    smooth_sampling: f32,   // 1 = bilinear field sampling, 0 = nearest cell
+    // 3D surface view: x across the world width, z down its height, y up
+    eye: vec3<f32>,
+    height_scale: f32,      // surface height at full display intensity
+    right: vec3<f32>,
+    tan_half_fov: f32,      // vertical
+    up: vec3<f32>,
+    _pad0: f32,
+    forward: vec3<f32>,
     _pad1: f32,
+    letterbox: vec3<f32>,   // color around the world
     _pad2: f32,
-    _pad3: f32,
+}
+
+struct Palette {
+    species: array<vec4<f32>, 3>, // basis for genome r / mu / sigma
+    predator_glow: vec4<f32>,
+    heat_low: vec4<f32>,
+    heat_high: vec4<f32>,
+    prey: vec4<f32>,
+    predator: vec4<f32>,
+    healthy: vec4<f32>,
+    starving: vec4<f32>,
+    trophic: array<vec4<f32>, 5>, // prey, opportunist lo/hi, predator lo/hi
+    cyclic: array<vec4<f32>, 6>,  // hue wheel stops
 }
 
 @group(0) @binding(0) var<uniform> render_params: RenderParams;
-@group(0) @binding(1) var<storage, read> mass: array<f32>;
-@group(0) @binding(2) var<storage, read> energy: array<f32>;
-@group(0) @binding(3) var<storage, read> genome_a: array<vec4<f32>>;
-@group(0) @binding(4) var<uniform> camera: CameraUniforms;
-@group(0) @binding(5) var<storage, read> velocity: array<vec2<f32>>;
-@group(0) @binding(6) var<storage, read> resource_map: array<f32>;
-
-// HSV to RGB conversion for diversity visualization
-fn hsv2rgb(h: f32, s: f32, v: f32) -> vec3<f32> {
-    let c = v * s;
-    let h6 = h * 6.0;
-    let x = c * (1.0 - abs((h6 % 2.0) - 1.0));
-    
-    var rgb = vec3<f32>(0.0);
-    if h6 < 1.0 {
-        rgb = vec3<f32>(c, x, 0.0);
-    } else if h6 < 2.0 {
-        rgb = vec3<f32>(x, c, 0.0);
-    } else if h6 < 3.0 {
-        rgb = vec3<f32>(0.0, c, x);
-    } else if h6 < 4.0 {
-        rgb = vec3<f32>(0.0, x, c);
-    } else if h6 < 5.0 {
-        rgb = vec3<f32>(x, 0.0, c);
-    } else {
-        rgb = vec3<f32>(c, 0.0, x);
+@group(0) @binding(1) var<storage, read> cells: array<Cell>; // mass, energy, genome B
+@group(0) @binding(2) var genome_a: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> camera: CameraUniforms;
+@group(0) @binding(4) var<storage, read> velocity: array<vec2<f32>>;
+@group(0) @binding(5) var<storage, read> resource_map: array<f32>;
+@group(0) @binding(6) var<storage, read> display_lut: array<f32>; // equalization CDF
+@group(0) @binding(7) var<uniform> palette: Palette;
+@group(0) @binding(8) var field_sampler: sampler;
+@group(0) @binding(9) var reference_mass: texture_2d<f32>; // diff overlay baseline
+@group(0) @binding(10) var territory: texture_2d<f32>;      // owner color, alpha = hold
+@group(0) @binding(11) var<storage, read> particle_markers: array<u32>; // 1 = grazer on the cell
+@group(0) @binding(12) var flow_field: texture_2d<f32>;                  // global wind (flow.rs)
+@group(0) @binding(13) var<storage, read> species_palette: array<vec4<f32>>; // color per species id
+// `trail` (mass-flux afterimage) and `species` (cluster id per cell) are
+// registry channels (channels.rs)
+
+// State fields at one screen pixel
+struct PixelState {
+    mass: f32,
+    energy: f32,
+    genome_a: vec4<f32>, // r, mu, sigma, aggressivity
+}
+
+// Cell state of a texel, wrapped around the toroidal world
+fn load_cell(texel: vec2<i32>) -> Cell {
+    let size = vec2<i32>(i32(render_params.width), i32(render_params.height));
+    let t = ((texel % size) + size) % size;
+    return cells[cell_index(u32(t.x), u32(t.y), render_params.width)];
+}
+
+// Bilinear sample at `uv` when smooth sampling is on, else the cell texel.
+// The cell buffer is blended by hand, matching the Repeat sampler of the
+// genome texture (texel centers at half-integer coordinates).
+fn sample_cell(uv: vec2<f32>, texel: vec2<i32>) -> PixelState {
+    // Bilinear filtering blends square texels, which hex cells are not
+    if camera.smooth_sampling > 0.5 && !HEX_GRID {
+        let p = uv * vec2<f32>(f32(render_params.width), f32(render_params.height)) - 0.5;
+        let base = vec2<i32>(floor(p));
+        let f = fract(p);
+        let c00 = load_cell(base);
+        let c10 = load_cell(base + vec2<i32>(1, 0));
+        let c01 = load_cell(base + vec2<i32>(0, 1));
+        let c11 = load_cell(base + vec2<i32>(1, 1));
+        let mass = mix(mix(c00.mass, c10.mass, f.x), mix(c01.mass, c11.mass, f.x), f.y);
+        let energy = mix(mix(c00.energy, c10.energy, f.x), mix(c01.energy, c11.energy, f.x), f.y);
+        return PixelState(mass, energy, textureSampleLevel(genome_a, field_sampler, uv, 0.0));
     }
-    
-    let m = v - c;
-    return rgb + vec3<f32>(m);
+    let cell = load_cell(texel);
+    return PixelState(cell.mass, cell.energy, textureLoad(genome_a, texel, 0));
+}
+
+const EQ_BINS: u32 = 256u;
+const LOG_STRENGTH: f32 = 100.0;
+
+// Periodic palette lookup (h in [0, 1) wraps), linear between the six stops
+fn cyclic_color(h: f32) -> vec3<f32> {
+    let t = fract(h) * 6.0;
+    let i = u32(floor(t)) % 6u;
+    return mix(palette.cyclic[i].rgb, palette.cyclic[(i + 1u) % 6u].rgb, t - floor(t));
+}
+
+// Gain/contrast transform for a mode's primary field
+fn display_adjust(v: f32) -> f32 {
+    let span = max(render_params.range_max - render_params.range_min, 1e-6);
+    var t = clamp((v * render_params.exposure - render_params.range_min) / span, 0.0, 1.0);
+    if render_params.transform == 1u {
+        t = log(1.0 + LOG_STRENGTH * t) / log(1.0 + LOG_STRENGTH);
+    } else if render_params.transform == 2u {
+        t = display_lut[min(u32(t * f32(EQ_BINS - 1u)), EQ_BINS - 1u)];
+    }
+    return pow(t, 1.0 / max(render_params.gamma, 0.01));
 }
 
 @fragment
@@ -110,8 +210,8 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     
     let world_uv = corrected / camera.zoom + vec2<f32>(0.5, 0.5) + camera.offset;
 
-    // Outside the [0,1] world bounds: show gray background (no tiling)
-    let outside_bg = vec3<f32>(0.08, 0.08, 0.10);
+    // Outside the [0,1] world bounds: show the letterbox color (no tiling)
+    let outside_bg = camera.letterbox;
     if (world_uv.x < 0.0 || world_uv.x > 1.0 || world_uv.y < 0.0 || world_uv.y > 1.0) {
         return vec4<f32>(outside_bg, 1.0);
     }
@@ -123,39 +223,311 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let px = u32(wx * f32(render_params.width));
     let py = u32(wy * f32(render_params.height));
 
-    let cx = min(px, render_params.width - 1u);
-    let cy = min(py, render_params.height - 1u);
+    var cx = min(px, render_params.width - 1u);
+    var cy = min(py, render_params.height - 1u);
+
+    var hex_edge = 0.0;
+    if HEX_GRID {
+        let h = hex_cell_at(vec2<f32>(wx * f32(render_params.width), wy * f32(render_params.height)));
+        let w = i32(render_params.width);
+        cx = u32(((i32(h.x) % w) + w) % w);
+        cy = u32(clamp(i32(h.y), 0, i32(render_params.height) - 1));
+        hex_edge = h.z;
+    }
+
+    var color = cell_color(world_uv, cx, cy);
+    if camera.grid_alpha > 0.0 {
+        // Distance to the nearest cell edge in screen pixels → ~1px AA line
+        var edge_px = hex_edge * camera.cell_px;
+        if !HEX_GRID {
+            let f = fract(vec2<f32>(wx * f32(render_params.width), wy * f32(render_params.height)));
+            let edge = min(f, vec2<f32>(1.0) - f) * camera.cell_px;
+            edge_px = min(edge.x, edge.y);
+        }
+        let line = 1.0 - smoothstep(0.0, 1.0, edge_px);
+        color = mix(color, vec3<f32>(0.35, 0.35, 0.4), line * camera.grid_alpha * 0.6);
+    }
+    return vec4<f32>(color, 1.0);
+}
 
-    let idx = cy * render_params.width + cx;
-    let m = mass[idx];
-    let e = energy[idx];
-    let ga = genome_a[idx]; // r, mu, sigma, aggressivity
+// Hex cell (column, row) under p, given in cell units, and the distance from
+// p to the cell border in cell widths. Rows keep their one-cell height on
+// screen, so hexes are drawn stretched by 1/HEX_ROW and the world stays a
+// rectangle.
+fn hex_cell_at(p: vec2<f32>) -> vec3<f32> {
+    let row = i32(floor(p.y));
+    var best = vec2<f32>(0.0);
+    var best_d = vec2<f32>(1e9);
+    for (var r = row - 1; r <= row + 1; r++) {
+        let shift = 0.5 * f32(r & 1);
+        let col = floor(p.x - shift);
+        let d = (p - vec2<f32>(col + 0.5 + shift, f32(r) + 0.5)) * vec2<f32>(1.0, HEX_ROW);
+        if (dot(d, d) < dot(best_d, best_d)) {
+            best = vec2<f32>(col, f32(r));
+            best_d = d;
+        }
+    }
+    // Pointy-top hexagon with inradius 0.5: edge normals at 0° and ±60°
+    let a = abs(best_d);
+    return vec3<f32>(best, 0.5 - max(a.x, 0.5 * a.x + HEX_ROW * a.y));
+}
+
+// Mode color with HDR glow and the territory / trail / diff overlays at a cell
+fn cell_color(world_uv: vec2<f32>, cx: u32, cy: u32) -> vec3<f32> {
+    let idx = cell_index(cx, cy, render_params.width);
+    let texel = vec2<i32>(i32(cx), i32(cy));
+    let cell = sample_cell(world_uv, texel);
+    var color = mode_color(idx, cell).rgb;
+    if render_params.hdr_emission > 0.0 {
+        color = color * (1.0 + render_params.hdr_emission * hdr_glow(cell));
+    }
+    if render_params.territory_opacity > 0.0 {
+        let owner = textureLoad(territory, texel, 0);
+        color = mix(color, owner.rgb, owner.a * render_params.territory_opacity);
+    }
+    if render_params.trail_opacity > 0.0 {
+        let trail_col = vec3<f32>(0.6, 0.85, 1.0); // pale cyan afterimage
+        color = color + trail_col * trail[idx] * render_params.trail_opacity;
+    }
+    if render_params.diff_opacity > 0.0 {
+        let delta = load_cell(texel).mass - textureLoad(reference_mass, texel, 0).x;
+        let d = clamp(delta / max(render_params.diff_range, 1e-6), -1.0, 1.0);
+        let gain_col = vec3<f32>(1.0, 0.55, 0.15);
+        let loss_col = vec3<f32>(0.2, 0.5, 1.0);
+        let diff_col = select(loss_col, gain_col, d > 0.0);
+        color = mix(color, diff_col, abs(d) * render_params.diff_opacity);
+    }
+    if render_params.flow_opacity > 0.0 {
+        let p = world_uv * vec2<f32>(f32(render_params.width), f32(render_params.height));
+        let speed = min(length(flow_at(p)), 1.0);
+        if speed > 0.0 {
+            // Pale streaks on a dark wash, fading out where the wind is slack
+            let streak = clamp((flow_streaks(p) - 0.5) * 2.5 + 0.5, 0.0, 1.0);
+            let flow_col = mix(vec3<f32>(0.1, 0.15, 0.25), vec3<f32>(0.85, 0.95, 1.0), streak);
+            color = mix(color, flow_col, render_params.flow_opacity * (0.3 + 0.7 * speed));
+        }
+    }
+    if render_params.particle_opacity > 0.0 && particle_markers[idx] != 0u {
+        let grazer_col = vec3<f32>(1.0, 0.95, 0.55); // pale yellow dots
+        color = mix(color, grazer_col, render_params.particle_opacity);
+    }
+    return color;
+}
+
+// ======================== Flow streamlines ========================
+
+const FLOW_LIC_STEPS: i32 = 12;
+const FLOW_LIC_STEP: f32 = 0.75; // cells
+
+// Flow vector of the cell containing p (cells), wrapping around the torus
+fn flow_at(p: vec2<f32>) -> vec2<f32> {
+    let w = i32(render_params.width);
+    let h = i32(render_params.height);
+    let c = vec2<i32>(((i32(floor(p.x)) % w) + w) % w, ((i32(floor(p.y)) % h) + h) % h);
+    return textureLoad(flow_field, c, 0).xy;
+}
+
+// White noise per cell: the texture the streamlines smear
+fn cell_noise(p: vec2<f32>) -> f32 {
+    var h = (u32(i32(floor(p.x))) * 73856093u) ^ (u32(i32(floor(p.y))) * 19349663u);
+    h = (h ^ (h >> 16u)) * 2246822519u;
+    h = h ^ (h >> 13u);
+    return f32(h & 0xFFFFu) / 65535.0;
+}
+
+// Line integral convolution: the noise averaged along the streamline through
+// p, traced in unit steps both downstream and upstream
+fn flow_streaks(p: vec2<f32>) -> f32 {
+    var sum = cell_noise(p);
+    var samples = 1.0;
+    for (var side = 0; side < 2; side++) {
+        let sign = select(-1.0, 1.0, side == 1);
+        var q = p;
+        for (var i = 0; i < FLOW_LIC_STEPS; i++) {
+            let v = flow_at(q);
+            let len = length(v);
+            if (len < 1e-4) {
+                break;
+            }
+            q += sign * v / len * FLOW_LIC_STEP;
+            sum += cell_noise(q);
+            samples += 1.0;
+        }
+    }
+    return sum / samples;
+}
+
+// ======================== 3D surface view ========================
+
+const SURFACE_MAX_STEPS: i32 = 512;
+const SURFACE_REFINE_STEPS: i32 = 6;
+
+// Displayed mass intensity of a texel, clamped to the world
+fn texel_height(x: i32, y: i32) -> f32 {
+    let texel = clamp(vec2<i32>(x, y), vec2<i32>(0), vec2<i32>(i32(render_params.width) - 1, i32(render_params.height) - 1));
+    return display_adjust(load_cell(texel).mass);
+}
+
+// Surface height at world UV (bilinear between cell centers)
+fn surface_height(uv: vec2<f32>) -> f32 {
+    let p = uv * vec2<f32>(f32(render_params.width), f32(render_params.height)) - vec2<f32>(0.5);
+    let i = vec2<i32>(floor(p));
+    let f = p - floor(p);
+    let top = mix(texel_height(i.x, i.y), texel_height(i.x + 1, i.y), f.x);
+    let bottom = mix(texel_height(i.x, i.y + 1), texel_height(i.x + 1, i.y + 1), f.x);
+    return mix(top, bottom, f.y) * camera.height_scale;
+}
+
+// Surface frame point → world UV
+fn surface_uv(p: vec3<f32>) -> vec2<f32> {
+    return vec2<f32>(p.x / camera.world_aspect + 0.5, p.z + 0.5);
+}
+
+@fragment
+fn fs_surface(in: VertexOutput) -> @location(0) vec4<f32> {
+    let outside_bg = camera.letterbox;
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let dir = normalize(
+        camera.forward
+        + camera.right * ndc.x * camera.tan_half_fov * camera.aspect_ratio
+        + camera.up * ndc.y * camera.tan_half_fov
+    );
+
+    // Clip the ray to the box holding the surface
+    let half_x = camera.world_aspect * 0.5;
+    let box_min = vec3<f32>(-half_x, 0.0, -0.5);
+    let box_max = vec3<f32>(half_x, max(camera.height_scale, 1e-4), 0.5);
+    let inv = 1.0 / select(dir, vec3<f32>(1e-6), abs(dir) < vec3<f32>(1e-6));
+    let t0 = (box_min - camera.eye) * inv;
+    let t1 = (box_max - camera.eye) * inv;
+    let t_near = max(max(min(t0.x, t1.x), min(t0.y, t1.y)), max(min(t0.z, t1.z), 0.0));
+    let t_far = min(min(max(t0.x, t1.x), max(t0.y, t1.y)), max(t0.z, t1.z));
+    if t_near >= t_far {
+        return vec4<f32>(outside_bg, 1.0);
+    }
+
+    // March until the ray dips under the surface, then bisect the crossing
+    let min_step = 0.5 / f32(max(render_params.width, render_params.height));
+    var t = t_near;
+    var prev_t = t_near;
+    var hit = false;
+    for (var i = 0; i < SURFACE_MAX_STEPS; i++) {
+        let p = camera.eye + dir * t;
+        let above = p.y - surface_height(surface_uv(p));
+        if above <= 0.0 {
+            hit = true;
+            break;
+        }
+        prev_t = t;
+        t = t + max(min_step, above * 0.5);
+        if t > t_far {
+            break;
+        }
+    }
+    if !hit {
+        // Last chance where the ray leaves the box (its floor is the ground)
+        let p_far = camera.eye + dir * t_far;
+        if p_far.y > surface_height(surface_uv(p_far)) + 1e-5 {
+            return vec4<f32>(outside_bg, 1.0);
+        }
+        t = t_far;
+    }
+    var lo = prev_t;
+    var hi = t;
+    for (var i = 0; i < SURFACE_REFINE_STEPS; i++) {
+        let mid = (lo + hi) * 0.5;
+        let p = camera.eye + dir * mid;
+        if p.y > surface_height(surface_uv(p)) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let p = camera.eye + dir * hi;
+    let uv = clamp(surface_uv(p), vec2<f32>(0.0), vec2<f32>(1.0));
+
+    // Normal from central differences over one cell (a cell spans
+    // 1 / height in both x and z of the surface frame)
+    let cell = vec2<f32>(1.0 / f32(render_params.width), 1.0 / f32(render_params.height));
+    let dhdx = surface_height(uv + vec2<f32>(cell.x, 0.0)) - surface_height(uv - vec2<f32>(cell.x, 0.0));
+    let dhdz = surface_height(uv + vec2<f32>(0.0, cell.y)) - surface_height(uv - vec2<f32>(0.0, cell.y));
+    let normal = normalize(vec3<f32>(-dhdx, 2.0 * cell.y, -dhdz));
+
+    let cx = min(u32(uv.x * f32(render_params.width)), render_params.width - 1u);
+    let cy = min(u32(uv.y * f32(render_params.height)), render_params.height - 1u);
+    let color = cell_color(uv, cx, cy);
+    let light = normalize(vec3<f32>(-0.4, 0.8, -0.3));
+    let shade = 0.35 + 0.65 * max(dot(normal, light), 0.0);
+    return vec4<f32>(color * shade, 1.0);
+}
+
+// Emission weight: energy × max(aggressivity, displayed mass density)
+fn hdr_glow(cell: PixelState) -> f32 {
+    let e = clamp(cell.energy, 0.0, 1.0);
+    return e * max(cell.genome_a.w, display_adjust(cell.mass));
+}
+
+// Fields of a world pixel handed to a user visualization mode (user_modes.rs)
+struct ModeInput {
+    mass: f32,
+    energy: f32,
+    genome_a: vec4<f32>, // r, mu, sigma, aggressivity
+    mutation_rate: f32,
+    resource_level: f32, // resource map
+    velocity: vec2<f32>,
+    intensity: f32, // display-adjusted mass
+}
+
+// First user mode; modes below it are built in (VIS_MODE_COUNT in config.rs)
+const USER_MODE_FIRST: u32 = 8u;
+
+// USER MODE BEGIN — replaced by the user mode's source in its shader variant
+fn user_mode_color(c: ModeInput) -> vec3<f32> {
+    return vec3<f32>(c.intensity);
+}
+// USER MODE END
+
+// Base (LDR) color of the selected visualization mode at a world pixel
+fn mode_color(idx: u32, cell: PixelState) -> vec4<f32> {
+    let m = cell.mass;
+    let e = cell.energy;
+    let ga = cell.genome_a; // r, mu, sigma, aggressivity
+    let mi = display_adjust(m); // display intensity of mass
 
     let bg = vec3<f32>(0.02, 0.02, 0.05); // Dark background
 
+    // User modes: the loaded mode's function, clamped to displayable colors
+    if render_params.visualization_mode >= USER_MODE_FIRST {
+        let input = ModeInput(m, e, ga, cells[idx].mutation_rate, resource_map[idx], velocity[idx], mi);
+        return vec4<f32>(clamp(user_mode_color(input), vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+    }
+
     // Mode 0: Species Color
     if render_params.visualization_mode == 0u {
-        let species_color = vec3<f32>(
-            clamp(ga.x / 15.0, 0.0, 1.0),   // R = perception radius (max 15)
-            clamp(ga.y * 5.0, 0.0, 1.0),     // G = growth center μ (scaled: 0.15 → 0.75)
-            clamp(ga.z / 0.06, 0.0, 1.0)     // B = growth width σ (scaled for Lenia range)
-        );
-        let predator_glow = step(0.7, ga.w) * vec3<f32>(1.0, 0.5, 0.0);
+        var species_color =
+            clamp(ga.x / 15.0, 0.0, 1.0) * palette.species[0].rgb    // R = perception radius (max 15)
+            + clamp(ga.y * 5.0, 0.0, 1.0) * palette.species[1].rgb  // G = growth center μ (scaled: 0.15 → 0.75)
+            + clamp(ga.z / 0.06, 0.0, 1.0) * palette.species[2].rgb; // B = growth width σ (scaled for Lenia range)
+        let id = species[idx];
+        if render_params.species_colors == 1u && id >= 0.0 {
+            species_color = species_palette[min(u32(id), arrayLength(&species_palette) - 1u)].rgb;
+        }
+        let predator_glow = step(0.7, ga.w) * palette.predator_glow.rgb;
         let final_color = clamp(species_color + predator_glow * 0.3, vec3<f32>(0.0), vec3<f32>(1.0));
-        let color = mix(bg, final_color, m);
+        let color = mix(bg, final_color, mi);
         return vec4<f32>(color, 1.0);
     }
     
     // Mode 1: Energy Heatmap (blue = low, red = high)
     if render_params.visualization_mode == 1u {
-        let heat_color = vec3<f32>(e, 0.2, 1.0 - e); // Blue -> Purple -> Red
-        let color = mix(bg, heat_color, m);
+        let heat_color = mix(palette.heat_low.rgb, palette.heat_high.rgb, e); // Blue -> Purple -> Red
+        let color = mix(bg, heat_color, mi);
         return vec4<f32>(color, 1.0);
     }
     
     // Mode 2: Mass Density (grayscale)
     if render_params.visualization_mode == 2u {
-        let gray = vec3<f32>(m);
+        let gray = vec3<f32>(mi);
         return vec4<f32>(gray, 1.0);
     }
     
@@ -163,17 +535,15 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     if render_params.visualization_mode == 3u {
         // Hash genome to a hue (0-1)
         let genome_hash = fract((ga.x * 0.1 + ga.y * 0.3 + ga.z * 3.0 + ga.w * 0.7) * 43758.5453);
-        let diversity_color = hsv2rgb(genome_hash, 0.8, 0.9);
-        let color = mix(bg, diversity_color, m);
+        let diversity_color = cyclic_color(genome_hash) * 0.9;
+        let color = mix(bg, diversity_color, mi);
         return vec4<f32>(color, 1.0);
     }
     
     // Mode 4: Predator/Prey (red = predator, green = prey)
     if render_params.visualization_mode == 4u {
-        let predator_color = vec3<f32>(1.0, 0.0, 0.0); // Red
-        let prey_color = vec3<f32>(0.0, 1.0, 0.0);     // Green
-        let species_color = mix(prey_color, predator_color, ga.w);
-        let color = mix(bg, species_color, m);
+        let species_color = mix(palette.prey.rgb, palette.predator.rgb, ga.w);
+        let color = mix(bg, species_color, mi);
         return vec4<f32>(color, 1.0);
     }
 
@@ -184,10 +554,8 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         let resource_bg = vec3<f32>(0.02, 0.08 * r_val, 0.02); // dim green for resource base
         if (m > 0.01) {
             let stress = 1.0 - clamp(e / 0.3, 0.0, 1.0); // 0=healthy, 1=starving
-            let healthy_col = vec3<f32>(0.0, 0.9, 0.9);   // cyan
-            let starving_col = vec3<f32>(0.9, 0.0, 0.7);  // magenta
-            let stress_col = mix(healthy_col, starving_col, stress);
-            let color = mix(resource_bg, stress_col, m);
+            let stress_col = mix(palette.healthy.rgb, palette.starving.rgb, stress);
+            let color = mix(resource_bg, stress_col, mi);
             return vec4<f32>(color, 1.0);
         }
         return vec4<f32>(resource_bg, 1.0);
@@ -198,13 +566,13 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     if render_params.visualization_mode == 6u {
         let vel = velocity[idx];
         let speed = length(vel);
-        let norm_speed = clamp(speed * 20.0, 0.0, 1.0); // scale for visibility
+        let norm_speed = display_adjust(speed * 20.0); // scale for visibility
         // Direction-dependent color: hue from atan2
         let angle = atan2(vel.y, vel.x); // -π to π
         let hue = (angle / 6.2832 + 0.5); // 0 to 1
-        let flux_col = hsv2rgb(hue, 0.8, norm_speed);
+        let flux_col = cyclic_color(hue) * norm_speed;
         let still_col = vec3<f32>(0.05, 0.05, 0.15);
-        let color = mix(still_col, flux_col, clamp(norm_speed + m * 0.3, 0.0, 1.0));
+        let color = mix(still_col, flux_col, clamp(norm_speed + mi * 0.3, 0.0, 1.0));
         return vec4<f32>(color, 1.0);
     }
 
@@ -218,19 +586,19 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             var role_col: vec3<f32>;
             if (agg_v < 0.2) {
                 // Prey: green → lime, specialist prey are more saturated
-                role_col = vec3<f32>(0.1, 0.85, 0.15);
+                role_col = palette.trophic[0].rgb;
             } else if (agg_v < 0.5) {
                 // Opportunist: blue-teal, interpolated
                 let t = (agg_v - 0.2) / 0.3;
-                role_col = mix(vec3<f32>(0.1, 0.7, 0.6), vec3<f32>(0.3, 0.3, 0.9), t);
+                role_col = mix(palette.trophic[1].rgb, palette.trophic[2].rgb, t);
             } else {
                 // Predator: orange → red
                 let t = (agg_v - 0.5) / 0.5;
-                role_col = mix(vec3<f32>(1.0, 0.5, 0.0), vec3<f32>(1.0, 0.0, 0.0), t);
+                role_col = mix(palette.trophic[3].rgb, palette.trophic[4].rgb, t);
             }
             let sat = mix(0.5, 1.0, specialization);
             let final_col = mix(vec3<f32>(0.5), role_col, sat);
-            let color = mix(bg, final_col, m);
+            let color = mix(bg, final_col, mi);
             return vec4<f32>(color, 1.0);
         }
         return vec4<f32>(bg, 1.0);
@@ -0,0 +1,15 @@
+// ============================================================================
+// specialization.wgsl — EvoLenia v2
+// Pipeline-overridable constants shared by the compute shaders, set when the
+// pipelines are created (pipeline.rs). World size and workgroup dimensions
+// are compile-time values for the driver, so wrap arithmetic, bounds checks
+// and cell indexing can be strength-reduced, and a resized world only needs
+// its pipelines rebuilt. The width/height fields of the Params uniforms are
+// kept for layout compatibility (the render pass still reads its own).
+// ============================================================================
+
+override WORLD_WIDTH: u32;
+override WORLD_HEIGHT: u32;
+override WORKGROUP_X: u32 = 16u;
+override WORKGROUP_Y: u32 = 16u;
+override WORKGROUP_LINEAR: u32 = 256u; // 1D passes over every pixel
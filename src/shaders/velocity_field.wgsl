@@ -0,0 +1,98 @@
+// ============================================================================
+// velocity_field.wgsl — EvoLenia v2
+// Advection velocity rule shared by compute_velocity.wgsl (separate pass)
+// and compute_evolution.wgsl (fused: each cell recomputes the velocities it
+// needs from the mass and genome fields instead of reading a velocity buffer
+// written by the previous pass). pipeline.rs prepends it to both shaders.
+//
+// Biology: Predators (high aggressivity) orient their mass flow toward
+// prey (lower mass neighbors), creating predator-prey spatial dynamics.
+//
+// Anisotropy a scales horizontal flow by √a and vertical flow by 1/√a, so
+// a > 1 favors transport along x (e.g. "river" worlds) and a = 1 is isotropic.
+//
+// In a rotating world the organisms' velocity is turned by a Coriolis-like
+// deflection. The global flow field (flow.rs) is added on top, after the
+// anisotropy and the rotation: it is an external current, not part of the
+// organisms' own motion.
+// ============================================================================
+
+// Per-axis transport weights for anisotropy a (compute_resources.wgsl weights
+// its diffusion stencil the same way)
+fn anisotropy_weights(a: f32) -> vec2<f32> {
+    let s = sqrt(max(a, 1e-3));
+    return vec2<f32>(s, 1.0 / s);
+}
+
+// Velocity of a cell from its mass, its 4-neighborhood masses, its
+// aggressivity and the world's anisotropy
+fn advection_velocity(
+    m_center: f32,
+    m_right: f32,
+    m_left: f32,
+    m_up: f32,
+    m_down: f32,
+    agg: f32,
+    anisotropy: f32,
+) -> vec2<f32> {
+    // Gradient of mass field (points toward higher mass)
+    let grad_m = vec2<f32>(
+        (m_right - m_left) * 0.5,
+        (m_down - m_up) * 0.5
+    );
+
+    // Base velocity: mass flows along gradient, modulated by aggressivity
+    // Predators (agg > 0.5) move TOWARD higher mass (prey detection)
+    var vel = grad_m * agg;
+
+    // Predation flux component: predators push mass toward weaker neighbors
+    if (agg > 0.5 && m_center > 0.01) {
+        var predation_vel = vec2<f32>(0.0, 0.0);
+
+        // right
+        let diff0 = m_center - m_right;
+        if (diff0 > 0.0) { predation_vel += vec2<f32>(1.0, 0.0) * agg * diff0 * 0.008; }
+        // left
+        let diff1 = m_center - m_left;
+        if (diff1 > 0.0) { predation_vel += vec2<f32>(-1.0, 0.0) * agg * diff1 * 0.008; }
+        // up
+        let diff2 = m_center - m_up;
+        if (diff2 > 0.0) { predation_vel += vec2<f32>(0.0, -1.0) * agg * diff2 * 0.008; }
+        // down
+        let diff3 = m_center - m_down;
+        if (diff3 > 0.0) { predation_vel += vec2<f32>(0.0, 1.0) * agg * diff3 * 0.008; }
+
+        vel += predation_vel;
+    }
+
+    // Faster along x than y for anisotropy > 1
+    vel *= anisotropy_weights(anisotropy);
+
+    // Clamp velocity to prevent instability
+    return clamp(vel, vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0));
+}
+
+// Organism velocity plus the flow field vector of the cell scaled by the
+// flow strength, under the same clamp
+fn with_flow(vel: vec2<f32>, flow: vec2<f32>, strength: f32) -> vec2<f32> {
+    return clamp(vel + flow * strength, vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0));
+}
+
+// Coriolis-like deflection in a world rotating at omega around a pole at
+// `center` (cells): the velocity of the cell at `p` turns by
+// 2ω·cos(π/2 · r / r_max), with r its distance to the pole around the torus
+// and r_max the farthest any cell can be. Like the Coriolis parameter from
+// pole to equator it fades to nothing, so the torus seam opposite the pole
+// stays smooth. ω > 0 turns motion clockwise on screen (y points down).
+fn coriolis(vel: vec2<f32>, p: vec2<f32>, omega: f32, center: vec2<f32>) -> vec2<f32> {
+    if (omega == 0.0) {
+        return vel;
+    }
+    let size = vec2<f32>(f32(WORLD_WIDTH), f32(WORLD_HEIGHT));
+    let d = abs(p - center) % size;
+    let r = length(min(d, size - d));
+    let angle = 2.0 * omega * cos(1.5707964 * r / length(size * 0.5));
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2<f32>(vel.x * c - vel.y * s, vel.x * s + vel.y * c);
+}
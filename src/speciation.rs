@@ -0,0 +1,240 @@
+// ============================================================================
+// speciation.rs — EvoLenia v2 Research Lab
+// Speciation detection: at every metrics sample the living genomes are
+// clustered (greedy seeding at SPECIES_THRESHOLD, then a few mass-weighted
+// k-means passes that merge centroids closer than the threshold), and the
+// clusters are matched to the lineages of the previous sample by centroid.
+// A cluster that no free lineage accounts for, next to a lineage that
+// continues, has split off it. Once the split has lasted a few samples it is
+// reported as a speciation event, and the new lineage keeps its label
+// ("S4 from S1") for as long as it is tracked.
+// ============================================================================
+
+use crate::config::SpeciationSettings;
+use crate::metrics::{genome_distance, SPECIES_THRESHOLD};
+use crate::world::BufferSnapshot;
+
+/// Cells lighter than this are left out of the clustering (as detect_species).
+const CLUSTER_MIN_MASS: f32 = 0.05;
+/// Most clusters per sample.
+pub const MAX_CLUSTERS: usize = 16;
+/// Living cells clustered per sample; larger worlds are subsampled evenly.
+const MAX_SAMPLE_CELLS: usize = 65_536;
+/// k-means passes after the greedy seeding.
+const REFINE_ITERATIONS: usize = 4;
+/// A cluster continues a lineage whose centroid is this close.
+const MATCH_DISTANCE: f32 = 2.0 * SPECIES_THRESHOLD;
+
+fn tuple(g: [f32; 4]) -> (f32, f32, f32, f32) {
+    (g[0], g[1], g[2], g[3])
+}
+
+fn distance(a: [f32; 4], b: [f32; 4]) -> f32 {
+    genome_distance(tuple(a), tuple(b))
+}
+
+fn nearest(centroids: &[[f32; 4]], genome: [f32; 4]) -> usize {
+    (0..centroids.len())
+        .min_by(|&a, &b| distance(centroids[a], genome).total_cmp(&distance(centroids[b], genome)))
+        .unwrap_or(0)
+}
+
+/// One genome cluster of a sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GenomeCluster {
+    pub centroid: [f32; 4], // mass-weighted mean genome
+    pub share: f32,         // of the clustered living mass
+}
+
+/// Cluster the living genomes of `snap`, heaviest cluster first.
+pub fn genome_clusters(snap: &BufferSnapshot) -> Vec<GenomeCluster> {
+    let living: Vec<usize> = (0..snap.mass.len()).filter(|&i| snap.mass[i] >= CLUSTER_MIN_MASS).collect();
+    let stride = (living.len() / MAX_SAMPLE_CELLS).max(1);
+    let cells: Vec<([f32; 4], f32)> = living
+        .iter()
+        .step_by(stride)
+        .map(|&i| {
+            let mut genome = [0.0; 4];
+            genome.copy_from_slice(&snap.genome_a[i * 4..i * 4 + 4]);
+            (genome, snap.mass[i])
+        })
+        .collect();
+
+    let mut centroids: Vec<[f32; 4]> = Vec::new();
+    for &(genome, _) in &cells {
+        if centroids.len() >= MAX_CLUSTERS {
+            break;
+        }
+        if centroids.iter().all(|&c| distance(c, genome) >= SPECIES_THRESHOLD) {
+            centroids.push(genome);
+        }
+    }
+
+    let mut clusters = Vec::new();
+    for pass in 0..=REFINE_ITERATIONS {
+        let mut sums = vec![([0.0f64; 4], 0.0f64); centroids.len()];
+        for &(genome, m) in &cells {
+            let (sum, mass) = &mut sums[nearest(&centroids, genome)];
+            for (s, g) in sum.iter_mut().zip(genome) {
+                *s += (g * m) as f64;
+            }
+            *mass += m as f64;
+        }
+        let total: f64 = sums.iter().map(|(_, m)| m).sum();
+        clusters = sums
+            .iter()
+            .filter(|(_, m)| *m > 0.0)
+            .map(|(sum, m)| GenomeCluster { centroid: sum.map(|s| (s / m) as f32), share: (m / total) as f32 })
+            .collect();
+        clusters.sort_by(|a, b| b.share.total_cmp(&a.share));
+        if pass == REFINE_ITERATIONS {
+            break;
+        }
+        // Merge centroids that drifted together: keep the heavier one.
+        centroids.clear();
+        for cluster in &clusters {
+            if centroids.iter().all(|&c| distance(c, cluster.centroid) >= SPECIES_THRESHOLD) {
+                centroids.push(cluster.centroid);
+            }
+        }
+    }
+    clusters
+}
+
+// ======================== Tracking ========================
+
+/// A cluster followed from sample to sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lineage {
+    pub id: u32,
+    pub centroid: [f32; 4],
+    pub share: f32,
+    pub age: u32,            // consecutive samples tracked
+    pub born: u32,           // frame first seen
+    pub parent: Option<u32>, // lineage it split from
+    pub confirmed: bool,     // false while the split is too recent to report
+}
+
+impl Lineage {
+    /// "S4", or "S4 ← S1" for a lineage that split from S1.
+    pub fn label(&self) -> String {
+        match self.parent {
+            Some(parent) => format!("S{} ← S{}", self.id, parent),
+            None => format!("S{}", self.id),
+        }
+    }
+}
+
+/// A split that persisted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeciationEvent {
+    pub frame: u32,       // detection
+    pub split_frame: u32, // the child was first seen
+    pub parent: u32,
+    pub child: u32,
+    pub centroids: [[f32; 4]; 2], // parent and child, now
+    pub shares: [f32; 2],
+}
+
+#[derive(Default)]
+pub struct SpeciationTracker {
+    pub lineages: Vec<Lineage>,
+    pub lost: Vec<Lineage>, // established lineages gone at the last update
+    pub detected: usize,    // events so far
+    next_id: u32,
+}
+
+impl SpeciationTracker {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Match this sample's `clusters` to the lineages and return the splits
+    /// that just became persistent.
+    pub fn update(&mut self, clusters: &[GenomeCluster], frame: u32, settings: &SpeciationSettings) -> Vec<SpeciationEvent> {
+        let clusters: Vec<&GenomeCluster> = clusters.iter().filter(|c| c.share >= settings.min_share).collect();
+
+        // Nearest pairs first; each lineage continues in at most one cluster.
+        let mut pairs = Vec::new();
+        for (c, cluster) in clusters.iter().enumerate() {
+            for (l, lineage) in self.lineages.iter().enumerate() {
+                let d = distance(cluster.centroid, lineage.centroid);
+                if d < MATCH_DISTANCE {
+                    pairs.push((d, c, l));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut continues: Vec<Option<usize>> = vec![None; clusters.len()];
+        let mut taken = vec![false; self.lineages.len()];
+        for &(_, c, l) in &pairs {
+            if continues[c].is_none() && !taken[l] {
+                continues[c] = Some(l);
+                taken[l] = true;
+            }
+        }
+
+        let mut next = Vec::with_capacity(clusters.len());
+        for (c, cluster) in clusters.iter().enumerate() {
+            match continues[c] {
+                Some(l) => next.push(Lineage {
+                    centroid: cluster.centroid,
+                    share: cluster.share,
+                    age: self.lineages[l].age + 1,
+                    ..self.lineages[l].clone()
+                }),
+                None => {
+                    // Every lineage in range already continues elsewhere:
+                    // the nearest one is the parent.
+                    let parent = pairs.iter().find(|&&(_, pc, _)| pc == c).map(|&(_, _, l)| self.lineages[l].id);
+                    next.push(Lineage {
+                        id: self.next_id,
+                        centroid: cluster.centroid,
+                        share: cluster.share,
+                        age: 1,
+                        born: frame,
+                        parent,
+                        confirmed: parent.is_none(),
+                    });
+                    self.next_id += 1;
+                }
+            }
+        }
+
+        let persistence = settings.persistence.max(1);
+        self.lost = self
+            .lineages
+            .iter()
+            .zip(&taken)
+            .filter(|(lineage, &taken)| !taken && lineage.confirmed && lineage.age >= persistence)
+            .map(|(lineage, _)| lineage.clone())
+            .collect();
+
+        let mut events = Vec::new();
+        for k in 0..next.len() {
+            let (Some(parent), false) = (next[k].parent, next[k].confirmed) else {
+                continue;
+            };
+            let Some(p) = next.iter().position(|l| l.id == parent) else {
+                // The parent is gone: a replacement, not a split.
+                next[k].parent = None;
+                next[k].confirmed = true;
+                continue;
+            };
+            if next[k].age >= persistence {
+                next[k].confirmed = true;
+                events.push(SpeciationEvent {
+                    frame,
+                    split_frame: next[k].born,
+                    parent,
+                    child: next[k].id,
+                    centroids: [next[p].centroid, next[k].centroid],
+                    shares: [next[p].share, next[k].share],
+                });
+            }
+        }
+        self.lineages = next;
+        self.detected += events.len();
+        events
+    }
+}
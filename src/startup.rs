@@ -0,0 +1,87 @@
+// ============================================================================
+// startup.rs — EvoLenia v2 Research Lab
+// First-launch benchmark: about two seconds of simulation steps on the
+// detected GPU, turned into recommended settings (simulation speed, vsync,
+// render scale) that a dialog offers to apply. The world size is chosen at
+// launch (--world) or by a Lab resize, so the dialog only suggests one. The
+// choice is kept in the UI settings: the benchmark does not run again, and
+// applied settings are restored at later launches.
+// ============================================================================
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::RENDER_SCALE_RANGE;
+
+/// Wall time spent timing simulation steps.
+pub const STARTUP_BENCH_DURATION: Duration = Duration::from_secs(2);
+/// Untimed steps first (pipeline warm-up).
+pub const STARTUP_WARMUP_STEPS: u32 = 10;
+/// Frame time the recommendations aim for (30 FPS).
+const TARGET_FRAME_MS: f32 = 33.3;
+/// Share of the frame left to the simulation; rendering and UI take the rest.
+const SIM_SHARE: f32 = 0.6;
+/// Steps at most this long leave room for a 60 Hz display.
+const VSYNC_STEP_MS: f32 = 4.0;
+/// Range of the speed slider.
+const SPEED_RANGE: (u32, u32) = (1, 20);
+/// World edges suggested (powers of two).
+const WORLD_EDGE_RANGE: (u32, u32) = (128, 2048);
+
+/// Measured simulation cost on this machine.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StartupBench {
+    pub gpu: String,
+    pub integrated: bool, // integrated, virtual or software adapter
+    pub ms_per_step: f32,
+}
+
+/// Settings suggested for a benchmark result.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub simulation_speed: u32,
+    pub vsync: bool,
+    pub render_scale: f32,
+    pub world_edge: u32, // suggested world width (--world, Lab resize)
+}
+
+impl Recommendation {
+    /// Settings for a benchmark run on a world `width` cells wide.
+    pub fn for_bench(bench: &StartupBench, width: u32) -> Self {
+        let sim_ms = TARGET_FRAME_MS * SIM_SHARE;
+        let step_ms = bench.ms_per_step.max(1e-3);
+        // Rendering competes with the simulation on slow or shared GPUs
+        let render_scale: f32 = if step_ms > 2.0 * sim_ms {
+            0.5
+        } else if step_ms > sim_ms || bench.integrated {
+            0.75
+        } else {
+            1.0
+        };
+        // Step cost grows with the cell count, i.e. with the square of the edge
+        let edge = width as f32 * (sim_ms / step_ms).sqrt();
+        let world_edge = prev_power_of_two(edge.clamp(WORLD_EDGE_RANGE.0 as f32, WORLD_EDGE_RANGE.1 as f32) as u32);
+        Self {
+            simulation_speed: ((sim_ms / step_ms) as u32).clamp(SPEED_RANGE.0, SPEED_RANGE.1),
+            vsync: step_ms <= VSYNC_STEP_MS,
+            render_scale: render_scale.clamp(RENDER_SCALE_RANGE.0, RENDER_SCALE_RANGE.1),
+            world_edge,
+        }
+    }
+}
+
+fn prev_power_of_two(n: u32) -> u32 {
+    if n == 0 {
+        return 1;
+    }
+    1 << (31 - n.leading_zeros())
+}
+
+/// Result of the first-launch benchmark and what the user chose.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StartupChoice {
+    pub bench: StartupBench,
+    pub recommendation: Recommendation,
+    pub applied: bool, // false: the current settings were kept
+}
@@ -1,26 +1,75 @@
 // ============================================================================
 // state_io.rs — EvoLenia v2
 // Binary snapshot save/load for headless->GUI workflows.
+// Each channel is stored as its length, its f32 values (little-endian), and
+// a CRC-32 of those bytes, so truncated or corrupted files fail to load
+// instead of producing a garbage world. Version 1 files (no checksums) still
+// load unverified. Snapshots keep the size they were saved at; those of
+// another size are resampled into the world they are loaded into
+// (`fit_snapshot`), so an ecosystem carries over to a world of another size
+// (--world, Lab resize). The flow field (flow.rs) follows as an
+// optional trailing channel: files written before it existed end without
+// one and load with no flow field. The snapshot channels of the channel
+// registry (channels.rs) come last, each as its name and a channel, so a
+// file keeps loading after channels are added to or removed from the
+// registry (a channel the file lacks starts zeroed).
 // ============================================================================
 
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, Read, Write};
 
-use crate::world::{BufferSnapshot, WORLD_HEIGHT, WORLD_WIDTH};
+use crate::world::BufferSnapshot;
 
-const MAGIC: &[u8; 8] = b"EVOSNP01";
+/// File extension of snapshot files.
+pub const SNAPSHOT_EXTENSION: &str = "snap";
+
+const MAGIC: &[u8; 8] = b"EVOSNP02";
+const MAGIC_V1: &[u8; 8] = b"EVOSNP01";
+
+/// Snapshot channels in file order, with their f32 values per pixel.
+const CHANNELS: [(&str, usize); 5] = [("mass", 1), ("energy", 1), ("genome_a", 4), ("genome_b", 1), ("resource", 1)];
+
+/// Trailing optional channel: the flow field, saved empty when there is none.
+const FLOW_CHANNEL: (&str, usize) = ("flow", 2);
 
 pub fn save_snapshot(path: &str, snapshot: &BufferSnapshot) -> io::Result<()> {
+    save_snapshot_with_progress(path, snapshot, |_| {})
+}
+
+/// Like `save_snapshot`, reporting the fraction written after each buffer.
+pub fn save_snapshot_with_progress(
+    path: &str,
+    snapshot: &BufferSnapshot,
+    mut progress: impl FnMut(f32),
+) -> io::Result<()> {
     let mut file = File::create(path)?;
     file.write_all(MAGIC)?;
-    file.write_all(&WORLD_WIDTH.to_le_bytes())?;
-    file.write_all(&WORLD_HEIGHT.to_le_bytes())?;
-
-    write_vec_f32(&mut file, &snapshot.mass)?;
-    write_vec_f32(&mut file, &snapshot.energy)?;
-    write_vec_f32(&mut file, &snapshot.genome_a)?;
-    write_vec_f32(&mut file, &snapshot.genome_b)?;
-    write_vec_f32(&mut file, &snapshot.resource)?;
+    file.write_all(&snapshot.size[0].to_le_bytes())?;
+    file.write_all(&snapshot.size[1].to_le_bytes())?;
+
+    let buffers = [
+        &snapshot.mass,
+        &snapshot.energy,
+        &snapshot.genome_a,
+        &snapshot.genome_b,
+        &snapshot.resource,
+        &snapshot.flow,
+    ];
+    let total: usize = buffers.into_iter().chain(snapshot.channels.values()).map(|b| b.len()).sum();
+    let mut written = 0;
+    for buffer in buffers {
+        write_channel(&mut file, buffer)?;
+        written += buffer.len();
+        progress(written as f32 / total.max(1) as f32);
+    }
+    for (name, values) in &snapshot.channels {
+        file.write_all(&(name.len() as u32).to_le_bytes())?;
+        file.write_all(name.as_bytes())?;
+        write_channel(&mut file, values)?;
+        written += values.len();
+        progress(written as f32 / total.max(1) as f32);
+    }
     Ok(())
 }
 
@@ -28,62 +77,159 @@ pub fn load_snapshot(path: &str) -> io::Result<BufferSnapshot> {
     let mut file = File::open(path)?;
 
     let mut magic = [0u8; 8];
-    file.read_exact(&mut magic)?;
-    if &magic != MAGIC {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid snapshot magic"));
-    }
+    read_exact(&mut file, &mut magic, "header")?;
+    let checksums = match &magic {
+        m if m == MAGIC => true,
+        m if m == MAGIC_V1 => false,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid snapshot magic")),
+    };
 
     let width = read_u32(&mut file)?;
     let height = read_u32(&mut file)?;
-    if width != WORLD_WIDTH || height != WORLD_HEIGHT {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!(
-                "snapshot dimensions {}x{} incompatible with current world {}x{}",
-                width, height, WORLD_WIDTH, WORLD_HEIGHT
-            ),
-        ));
-    }
-
-    let mass = read_vec_f32(&mut file)?;
-    let energy = read_vec_f32(&mut file)?;
-    let genome_a = read_vec_f32(&mut file)?;
-    let genome_b = read_vec_f32(&mut file)?;
-    let resource = read_vec_f32(&mut file)?;
+    let pixels = (width as usize).checked_mul(height as usize).filter(|&n| n > 0).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid snapshot dimensions {}x{}", width, height))
+    })?;
+    let mut read = |(name, per_pixel): (&str, usize)| read_channel(&mut file, name, pixels * per_pixel, checksums);
 
     Ok(BufferSnapshot {
-        mass,
-        energy,
-        genome_a,
-        genome_b,
-        resource,
+        size: [width, height],
+        mass: read(CHANNELS[0])?,
+        energy: read(CHANNELS[1])?,
+        genome_a: read(CHANNELS[2])?,
+        genome_b: read(CHANNELS[3])?,
+        resource: read(CHANNELS[4])?,
+        flow: read_optional_channel(&mut file, FLOW_CHANNEL.0, pixels * FLOW_CHANNEL.1, checksums)?,
+        channels: read_named_channels(&mut file, pixels, checksums)?,
     })
 }
 
-fn write_vec_f32(file: &mut File, values: &[f32]) -> io::Result<()> {
-    let len = values.len() as u64;
-    file.write_all(&len.to_le_bytes())?;
-    for value in values {
-        file.write_all(&value.to_le_bytes())?;
+/// `snapshot` as it loads into a `size` world: unchanged at that size,
+/// resampled otherwise.
+pub fn fit_snapshot(snapshot: BufferSnapshot, size: [u32; 2]) -> BufferSnapshot {
+    if snapshot.size == size {
+        return snapshot;
+    }
+    let [from, to] = [snapshot.size, size];
+    log::info!("Resampling a {}x{} snapshot to the {}x{} world", from[0], from[1], to[0], to[1]);
+    resample_snapshot(&snapshot, size)
+}
+
+/// Nearest-cell resample of a snapshot to `to`. Every channel is a per-cell
+/// level, so values are copied as they are: genomes stay whole (species
+/// keep coherent colors) and the total mass follows the world area, like
+/// the target mass of the normalization pass.
+pub fn resample_snapshot(snapshot: &BufferSnapshot, to: [u32; 2]) -> BufferSnapshot {
+    let from = snapshot.size;
+    let axis = |src: u32, dst: u32| -> Vec<usize> {
+        (0..dst as u64).map(|i| ((2 * i + 1) * src as u64 / (2 * dst as u64)) as usize).collect()
+    };
+    let (xs, ys) = (axis(from[0], to[0]), axis(from[1], to[1]));
+    let source: Vec<usize> = ys.iter().flat_map(|&y| xs.iter().map(move |&x| y * from[0] as usize + x)).collect();
+    let pixels = (from[0] as usize * from[1] as usize).max(1);
+    let pick = |values: &[f32], per_pixel: usize| -> Vec<f32> {
+        source.iter().flat_map(|&i| values[i * per_pixel..(i + 1) * per_pixel].iter().copied()).collect()
+    };
+    BufferSnapshot {
+        size: to,
+        mass: pick(&snapshot.mass, 1),
+        energy: pick(&snapshot.energy, 1),
+        genome_a: pick(&snapshot.genome_a, 4),
+        genome_b: pick(&snapshot.genome_b, 1),
+        resource: pick(&snapshot.resource, 1),
+        flow: if snapshot.flow.is_empty() { Vec::new() } else { pick(&snapshot.flow, FLOW_CHANNEL.1) },
+        channels: snapshot
+            .channels
+            .iter()
+            .map(|(name, values)| (name.clone(), pick(values, values.len() / pixels)))
+            .collect(),
     }
-    Ok(())
 }
 
-fn read_vec_f32(file: &mut File) -> io::Result<Vec<f32>> {
+/// Write one channel: length, values, CRC-32 of the value bytes.
+fn write_channel(file: &mut File, values: &[f32]) -> io::Result<()> {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    file.write_all(&(values.len() as u64).to_le_bytes())?;
+    file.write_all(&bytes)?;
+    file.write_all(&crc32fast::hash(&bytes).to_le_bytes())
+}
+
+/// Read one channel of `expected` values, verifying its checksum if present.
+fn read_channel(file: &mut File, name: &str, expected: usize, checksum: bool) -> io::Result<Vec<f32>> {
+    let mut len_buf = [0u8; 8];
+    read_exact(file, &mut len_buf, name)?;
+    read_values(file, name, u64::from_le_bytes(len_buf), expected, checksum)
+}
+
+/// Read a channel older files may end without: empty at the end of the
+/// file or when saved empty, else `expected` values.
+fn read_optional_channel(file: &mut File, name: &str, expected: usize, checksum: bool) -> io::Result<Vec<f32>> {
     let mut len_buf = [0u8; 8];
-    file.read_exact(&mut len_buf)?;
-    let len = u64::from_le_bytes(len_buf) as usize;
-    let mut bytes = vec![0u8; len * std::mem::size_of::<f32>()];
-    file.read_exact(&mut bytes)?;
-    let mut values = Vec::with_capacity(len);
-    for chunk in bytes.chunks_exact(4) {
-        values.push(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    if file.read(&mut len_buf[..1])? == 0 {
+        return Ok(Vec::new());
+    }
+    read_exact(file, &mut len_buf[1..], name)?;
+    let len = u64::from_le_bytes(len_buf);
+    read_values(file, name, len, if len == 0 { 0 } else { expected }, checksum)
+}
+
+/// Registry channels up to the end of the file, by name: each a name
+/// length, the name and a channel of a whole number of values per pixel.
+fn read_named_channels(file: &mut File, pixels: usize, checksum: bool) -> io::Result<BTreeMap<String, Vec<f32>>> {
+    let mut channels = BTreeMap::new();
+    let mut len_buf = [0u8; 4];
+    while file.read(&mut len_buf[..1])? != 0 {
+        read_exact(file, &mut len_buf[1..], "channel name")?;
+        let mut name = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+        read_exact(file, &mut name, "channel name")?;
+        let name = String::from_utf8(name)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "snapshot channel name is not UTF-8"))?;
+        let mut count_buf = [0u8; 8];
+        read_exact(file, &mut count_buf, &name)?;
+        let len = u64::from_le_bytes(count_buf);
+        let per_pixel = (len as usize / pixels).max(1);
+        let values = read_values(file, &name, len, pixels * per_pixel, checksum)?;
+        channels.insert(name, values);
+    }
+    Ok(channels)
+}
+
+/// Values (and checksum) of a channel whose length `len` was just read.
+fn read_values(file: &mut File, name: &str, len: u64, expected: usize, checksum: bool) -> io::Result<Vec<f32>> {
+    if len != expected as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("snapshot {} channel has {} values, expected {} (corrupted file)", name, len, expected),
+        ));
     }
-    Ok(values)
+    let mut bytes = vec![0u8; expected * std::mem::size_of::<f32>()];
+    read_exact(file, &mut bytes, name)?;
+    if checksum {
+        let mut crc_buf = [0u8; 4];
+        read_exact(file, &mut crc_buf, name)?;
+        let stored = u32::from_le_bytes(crc_buf);
+        let actual = crc32fast::hash(&bytes);
+        if stored != actual {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("snapshot {} channel is corrupted (checksum {:08x}, expected {:08x})", name, actual, stored),
+            ));
+        }
+    }
+    Ok(bytes.chunks_exact(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect())
+}
+
+/// `read_exact` that reports a short read as a truncated snapshot.
+fn read_exact(file: &mut File, buf: &mut [u8], section: &str) -> io::Result<()> {
+    file.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => {
+            io::Error::new(io::ErrorKind::InvalidData, format!("snapshot truncated in {}", section))
+        }
+        _ => e,
+    })
 }
 
 fn read_u32(file: &mut File) -> io::Result<u32> {
     let mut bytes = [0u8; 4];
-    file.read_exact(&mut bytes)?;
+    read_exact(file, &mut bytes, "header")?;
     Ok(u32::from_le_bytes(bytes))
 }
@@ -0,0 +1,429 @@
+// ============================================================================
+// stream.rs — EvoLenia v2
+// Frame stream for remote viewing: a headless run (`--stream-addr`) sends
+// downsampled frames and its metrics over TCP to any number of lightweight
+// viewers (`evolenia view <host:port>`, viewer.rs), so a cluster-hosted run
+// can be watched without a GPU window on the simulation host.
+//
+// Wire format: each message is a kind byte, the payload length (u32 LE) and
+// the payload:
+//   Hello    JSON StreamHello, sent first
+//   Frame    frame, width, height (u32 LE each), then sRGB RGBA8 rows
+//   Metrics  JSON StreamMetrics (progress and the last diagnostics sample)
+//
+// Served from background threads with std::net only, like monitor.rs. Each
+// viewer has a short queue; a viewer that falls behind skips messages
+// instead of slowing the simulation.
+// ============================================================================
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::capture::FrameSink;
+use crate::monitor::StepRate;
+
+/// Protocol version in the hello message; viewers refuse other versions.
+pub const STREAM_VERSION: u32 = 1;
+/// Larger payloads are treated as a corrupt stream.
+const MAX_PAYLOAD: u32 = 64 << 20;
+/// Messages queued per viewer before new ones are skipped.
+const CLIENT_QUEUE: usize = 8;
+/// How often the accept loop checks for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Viewers that stop reading are dropped after this long.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Delay between connection attempts of a viewer.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// Diagnostics samples kept by a viewer for its plot.
+pub const HISTORY_LIMIT: usize = 2000;
+
+const KIND_HELLO: u8 = 1;
+const KIND_FRAME: u8 = 2;
+const KIND_METRICS: u8 = 3;
+
+/// First message of a stream.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StreamHello {
+    pub version: u32,
+    pub world: [u32; 2],
+    pub frames_total: u32,
+}
+
+/// Run progress and the last diagnostics sample.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamMetrics {
+    pub frame: u32,
+    pub frames_total: u32,
+    pub steps_per_sec: f64,
+    pub diagnostics_frame: Option<u32>,
+    pub diagnostics: Vec<(String, f32)>, // SimDiagnostics::named_scalars
+}
+
+/// One downsampled frame (thumbnail.rs species colors).
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamFrame {
+    pub frame: u32,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamMessage {
+    Hello(StreamHello),
+    Frame(StreamFrame),
+    Metrics(StreamMetrics),
+}
+
+impl StreamMessage {
+    /// Wire bytes of the message.
+    pub fn encode(&self) -> Vec<u8> {
+        let (kind, payload) = match self {
+            StreamMessage::Hello(hello) => (KIND_HELLO, serde_json::to_vec(hello).unwrap_or_default()),
+            StreamMessage::Metrics(metrics) => (KIND_METRICS, serde_json::to_vec(metrics).unwrap_or_default()),
+            StreamMessage::Frame(frame) => {
+                let mut payload = Vec::with_capacity(12 + frame.rgba.len());
+                for v in [frame.frame, frame.width, frame.height] {
+                    payload.extend_from_slice(&v.to_le_bytes());
+                }
+                payload.extend_from_slice(&frame.rgba);
+                (KIND_FRAME, payload)
+            }
+        };
+        let mut bytes = Vec::with_capacity(5 + payload.len());
+        bytes.push(kind);
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    /// Read one message; InvalidData on an unknown kind or a malformed payload.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+        if len > MAX_PAYLOAD {
+            return Err(invalid(format!("message of {} bytes", len)));
+        }
+        let mut payload = vec![0u8; len as usize];
+        reader.read_exact(&mut payload)?;
+        match header[0] {
+            KIND_HELLO => serde_json::from_slice(&payload).map(StreamMessage::Hello).map_err(|e| invalid(e.to_string())),
+            KIND_METRICS => serde_json::from_slice(&payload).map(StreamMessage::Metrics).map_err(|e| invalid(e.to_string())),
+            KIND_FRAME if payload.len() >= 12 => {
+                let word = |i: usize| u32::from_le_bytes([payload[i], payload[i + 1], payload[i + 2], payload[i + 3]]);
+                let (frame, width, height) = (word(0), word(4), word(8));
+                if (width as u64) * (height as u64) * 4 != (payload.len() - 12) as u64 {
+                    return Err(invalid(format!("{}x{} frame with {} bytes", width, height, payload.len() - 12)));
+                }
+                Ok(StreamMessage::Frame(StreamFrame { frame, width, height, rgba: payload[12..].to_vec() }))
+            }
+            kind => Err(invalid(format!("unknown message kind {}", kind))),
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// ======================== Server ========================
+
+/// State shared with the accept thread.
+struct Shared {
+    hello: Arc<Vec<u8>>,
+    last_frame: Option<Arc<Vec<u8>>>, // replayed to viewers that connect later
+    metrics: StreamMetrics,
+    clients: Vec<SyncSender<Arc<Vec<u8>>>>,
+}
+
+impl Shared {
+    /// Queue `bytes` for every viewer; viewers that are gone are dropped,
+    /// viewers with a full queue skip it.
+    fn broadcast(&mut self, bytes: Arc<Vec<u8>>) {
+        self.clients.retain(|tx| !matches!(tx.try_send(Arc::clone(&bytes)), Err(TrySendError::Disconnected(_))));
+    }
+
+    fn metrics_bytes(&self) -> Arc<Vec<u8>> {
+        Arc::new(StreamMessage::Metrics(self.metrics.clone()).encode())
+    }
+}
+
+/// Background stream server; stops when dropped.
+pub struct StreamServer {
+    addr: SocketAddr,
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    rate: StepRate,
+}
+
+impl StreamServer {
+    /// Bind `addr` (e.g. "0.0.0.0:9899"; port 0 picks a free port).
+    pub fn start(addr: &str, world: [u32; 2], frames_total: u32) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let hello = StreamMessage::Hello(StreamHello { version: STREAM_VERSION, world, frames_total });
+        let shared = Arc::new(Mutex::new(Shared {
+            hello: Arc::new(hello.encode()),
+            last_frame: None,
+            metrics: StreamMetrics { frames_total, ..Default::default() },
+            clients: Vec::new(),
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let shared = Arc::clone(&shared);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || accept_viewers(listener, &shared, &stop))
+        };
+        Ok(Self { addr, shared, stop, thread: Some(thread), rate: StepRate::new() })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Viewers currently connected.
+    pub fn viewer_count(&self) -> usize {
+        self.lock().clients.len()
+    }
+
+    /// Record progress; steps/sec is refreshed at most once per second and
+    /// sent with the next frame.
+    pub fn set_frame(&mut self, frame: u32) {
+        let rate = self.rate.update(frame);
+        let mut shared = self.lock();
+        shared.metrics.frame = frame;
+        if let Some(rate) = rate {
+            shared.metrics.steps_per_sec = rate;
+        }
+    }
+
+    /// Send a downsampled frame, followed by the current progress.
+    pub fn publish_frame(&mut self, frame: StreamFrame) {
+        let bytes = Arc::new(StreamMessage::Frame(frame).encode());
+        let mut shared = self.lock();
+        shared.last_frame = Some(Arc::clone(&bytes));
+        shared.broadcast(bytes);
+        let metrics = shared.metrics_bytes();
+        shared.broadcast(metrics);
+    }
+
+    /// Send a diagnostics sample taken at `frame`.
+    pub fn set_diagnostics(&mut self, frame: u32, diagnostics: &[(&'static str, f32)]) {
+        let mut shared = self.lock();
+        shared.metrics.diagnostics_frame = Some(frame);
+        shared.metrics.diagnostics = diagnostics.iter().map(|&(tag, value)| (tag.to_string(), value)).collect();
+        let metrics = shared.metrics_bytes();
+        shared.broadcast(metrics);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl FrameSink for StreamServer {
+    fn write_frame(&mut self, frame: u32, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+        self.publish_frame(StreamFrame { frame, width, height, rgba: rgba.to_vec() });
+        Ok(())
+    }
+}
+
+impl Drop for StreamServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        // Closing the queues ends the writer threads
+        self.lock().clients.clear();
+    }
+}
+
+fn accept_viewers(listener: TcpListener, shared: &Mutex<Shared>, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Err(e) = add_viewer(stream, shared) {
+                    log::warn!("Stream viewer {} rejected: {}", peer, e);
+                } else {
+                    log::info!("Stream viewer connected from {}", peer);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                log::warn!("Stream accept failed: {}", e);
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Start a writer thread for a new viewer, primed with the hello message,
+/// the last frame and the current metrics.
+fn add_viewer(stream: TcpStream, shared: &Mutex<Shared>) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let (tx, rx) = sync_channel::<Arc<Vec<u8>>>(CLIENT_QUEUE);
+    let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = tx.try_send(Arc::clone(&shared.hello));
+    if let Some(frame) = &shared.last_frame {
+        let _ = tx.try_send(Arc::clone(frame));
+    }
+    let _ = tx.try_send(shared.metrics_bytes());
+    shared.clients.push(tx);
+    std::thread::spawn(move || {
+        let mut stream = stream;
+        for bytes in rx {
+            if let Err(e) = stream.write_all(&bytes) {
+                log::info!("Stream viewer disconnected: {}", e);
+                break;
+            }
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+    Ok(())
+}
+
+// ======================== Viewer Side ========================
+
+/// Connection state of a viewer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum LinkStatus {
+    #[default]
+    Connecting,
+    Connected,
+    /// Lost or refused (reason); the viewer retries.
+    Disconnected(String),
+}
+
+/// What a viewer has received so far.
+#[derive(Clone, Debug, Default)]
+pub struct ViewerFeed {
+    pub status: LinkStatus,
+    pub hello: Option<StreamHello>,
+    pub frame: Option<StreamFrame>,
+    pub frame_serial: u64, // bumped per received frame, to refresh the texture
+    pub metrics: StreamMetrics,
+    pub history: Vec<(u32, Vec<(String, f32)>)>, // diagnostics samples by frame, oldest first
+}
+
+impl ViewerFeed {
+    /// Fold one message into the feed. Errors on a protocol mismatch.
+    pub fn apply(&mut self, message: StreamMessage) -> Result<(), String> {
+        match message {
+            StreamMessage::Hello(hello) => {
+                if hello.version != STREAM_VERSION {
+                    return Err(format!("stream protocol {} (expected {})", hello.version, STREAM_VERSION));
+                }
+                // A new run (or a restarted one): earlier samples no longer apply
+                if self.hello.as_ref() != Some(&hello) {
+                    self.history.clear();
+                }
+                self.hello = Some(hello);
+                self.status = LinkStatus::Connected;
+            }
+            StreamMessage::Frame(frame) => {
+                self.frame = Some(frame);
+                self.frame_serial += 1;
+            }
+            StreamMessage::Metrics(metrics) => {
+                if let Some(frame) = metrics.diagnostics_frame {
+                    if self.history.last().is_none_or(|(last, _)| *last != frame) {
+                        // A rewound run starts a new history
+                        if self.history.last().is_some_and(|(last, _)| *last > frame) {
+                            self.history.clear();
+                        }
+                        self.history.push((frame, metrics.diagnostics.clone()));
+                        if self.history.len() > HISTORY_LIMIT {
+                            self.history.remove(0);
+                        }
+                    }
+                }
+                self.metrics = metrics;
+            }
+        }
+        Ok(())
+    }
+
+    /// Values of diagnostic `tag` over the history, as (frame, value).
+    pub fn series(&self, tag: &str) -> Vec<[f64; 2]> {
+        self.history
+            .iter()
+            .filter_map(|(frame, values)| {
+                values.iter().find(|(t, _)| t == tag).map(|&(_, v)| [*frame as f64, v as f64])
+            })
+            .collect()
+    }
+}
+
+/// Viewer connection, kept open (and reopened) by a background thread;
+/// closes when dropped.
+pub struct StreamClient {
+    addr: String,
+    feed: Arc<Mutex<ViewerFeed>>,
+    stop: Arc<AtomicBool>,
+    socket: Arc<Mutex<Option<TcpStream>>>, // for shutdown: unblocks the reader
+}
+
+impl StreamClient {
+    pub fn connect(addr: &str) -> Self {
+        let feed = Arc::new(Mutex::new(ViewerFeed::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let socket = Arc::new(Mutex::new(None));
+        {
+            let (addr, feed, stop, socket) = (addr.to_string(), Arc::clone(&feed), Arc::clone(&stop), Arc::clone(&socket));
+            std::thread::spawn(move || receive(&addr, &feed, &stop, &socket));
+        }
+        Self { addr: addr.to_string(), feed, stop, socket }
+    }
+
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub fn feed(&self) -> MutexGuard<'_, ViewerFeed> {
+        self.feed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for StreamClient {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(stream) = self.socket.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn receive(addr: &str, feed: &Mutex<ViewerFeed>, stop: &AtomicBool, socket: &Mutex<Option<TcpStream>>) {
+    let set_status = |status: LinkStatus| feed.lock().unwrap_or_else(|e| e.into_inner()).status = status;
+    while !stop.load(Ordering::SeqCst) {
+        let result = TcpStream::connect(addr).and_then(|stream| -> io::Result<()> {
+            *socket.lock().unwrap_or_else(|e| e.into_inner()) = Some(stream.try_clone()?);
+            let mut reader = io::BufReader::new(stream);
+            loop {
+                let message = StreamMessage::read_from(&mut reader)?;
+                feed.lock().unwrap_or_else(|e| e.into_inner()).apply(message).map_err(invalid)?;
+            }
+        });
+        if let Err(e) = result {
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            log::debug!("Stream {} unavailable: {}", addr, e);
+            set_status(LinkStatus::Disconnected(e.to_string()));
+        }
+        std::thread::sleep(RECONNECT_INTERVAL);
+    }
+}
@@ -0,0 +1,527 @@
+// ============================================================================
+// tape.rs — EvoLenia v2 Research Lab
+// Simulation tapes: a recording mode writing one stream file per recording,
+// with every captured frame's channels (downsampled) and its metrics side by
+// side, so a run can be re-watched later with full channel switching.
+//
+// File layout (little-endian):
+//   "EVOTAPE1", u32 header length, JSON TapeHeader
+//   then one chunk per captured frame:
+//   "FRAM", u32 frame, u64 payload length, payload, u32 CRC-32 of payload
+// The payload is a u32 length and the frame's MetricsRecord as JSON, then
+// each channel in TAPE_CHANNELS order: its range (f32 lo, f32 hi) and one
+// u16 per value quantized over that range. Chunks are self-delimiting, so a
+// reader indexes them in one pass and a tape cut short by a crash still
+// plays up to its last complete frame.
+//
+// Scalar channels are box-averaged over each downsample block; genomes are
+// taken from the block's heaviest cell, so species keep coherent colors.
+//
+// Each recording also saves the full-resolution state and the parameters
+// it started from next to the tape, so the replay checker (replay.rs) can
+// re-simulate the run and compare it with the tape.
+//
+// Playback swaps the simulation for the tape: frames are upsampled back to
+// the world size and uploaded into the world buffers, so every render mode
+// and the screenshot path work on them as on a live world. The world state
+// under the tape is kept aside and restored when playback ends.
+// ============================================================================
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::capture::FrameSink;
+use crate::lab::MetricsRecord;
+use crate::state_io::SNAPSHOT_EXTENSION;
+use crate::world::BufferSnapshot;
+
+/// File extension of tapes.
+pub const TAPE_EXTENSION: &str = "tape";
+/// Version of the header and chunk layout.
+pub const TAPE_SCHEMA_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"EVOTAPE1";
+const FRAME_TAG: &[u8; 4] = b"FRAM";
+/// Chunk bytes before the payload: tag, frame, payload length.
+const CHUNK_HEADER: u64 = 4 + 4 + 8;
+
+/// Recorded channels in chunk order, with their values per cell (the
+/// snapshot channels, without the authored flow field).
+pub const TAPE_CHANNELS: [(&str, usize); 5] = [("mass", 1), ("energy", 1), ("genome_a", 4), ("genome_b", 1), ("resource", 1)];
+
+// ======================== Header ========================
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TapeChannel {
+    pub name: String,
+    pub components: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TapeHeader {
+    pub version: u32,
+    pub run_id: String,
+    pub created: String,
+    pub world: [u32; 2],  // cells of the recorded world
+    pub size: [u32; 2],   // cells of each recorded frame
+    pub downsample: u32,  // world cells per recorded cell, per axis
+    pub interval: u32,    // steps between captures
+    pub channels: Vec<TapeChannel>,
+}
+
+impl TapeHeader {
+    pub fn new(run_id: &str, settings: &TapeSettings, world: [u32; 2]) -> Self {
+        Self {
+            version: TAPE_SCHEMA_VERSION,
+            run_id: run_id.to_string(),
+            created: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            world,
+            size: tape_size(world, settings.downsample),
+            downsample: settings.downsample.max(1),
+            interval: settings.interval.max(1),
+            channels: TAPE_CHANNELS.iter().map(|&(name, components)| TapeChannel { name: name.to_string(), components }).collect(),
+        }
+    }
+
+    fn cells(&self) -> usize {
+        self.size[0] as usize * self.size[1] as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TapeSettings {
+    pub downsample: u32, // world cells per recorded cell, per axis
+    pub interval: u32,   // steps between captures
+}
+
+impl Default for TapeSettings {
+    fn default() -> Self {
+        Self { downsample: 4, interval: 10 }
+    }
+}
+
+/// Recorded frame size of a `world` for a downsample factor (at least one
+/// cell).
+pub fn tape_size(world: [u32; 2], downsample: u32) -> [u32; 2] {
+    let d = downsample.max(1);
+    [world[0].div_ceil(d), world[1].div_ceil(d)]
+}
+
+// ======================== Channels ========================
+
+/// Shrink a world snapshot to the tape size: scalars averaged per block,
+/// genomes from the heaviest cell of the block.
+pub fn downsample(snap: &BufferSnapshot, factor: u32) -> BufferSnapshot {
+    let d = factor.max(1);
+    let [w, h] = tape_size(snap.size, d);
+    let n = (w * h) as usize;
+    let mut out = BufferSnapshot {
+        size: [w, h],
+        mass: vec![0.0; n],
+        energy: vec![0.0; n],
+        genome_a: vec![0.0; n * 4],
+        genome_b: vec![0.0; n],
+        resource: vec![0.0; n],
+        flow: Vec::new(),
+        channels: BTreeMap::new(),
+    };
+    let mut counts = vec![0u32; n];
+    let mut heaviest = vec![(f32::NEG_INFINITY, 0usize); n];
+    let [width, height] = snap.size;
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) as usize;
+            let k = ((y / d) * w + x / d) as usize;
+            out.mass[k] += snap.mass[i];
+            out.energy[k] += snap.energy[i];
+            out.resource[k] += snap.resource[i];
+            counts[k] += 1;
+            if snap.mass[i] > heaviest[k].0 {
+                heaviest[k] = (snap.mass[i], i);
+            }
+        }
+    }
+    for k in 0..n {
+        let c = counts[k].max(1) as f32;
+        out.mass[k] /= c;
+        out.energy[k] /= c;
+        out.resource[k] /= c;
+        let i = heaviest[k].1;
+        out.genome_a[k * 4..k * 4 + 4].copy_from_slice(&snap.genome_a[i * 4..i * 4 + 4]);
+        out.genome_b[k] = snap.genome_b[i];
+    }
+    out
+}
+
+fn channels(snap: &BufferSnapshot) -> [&[f32]; 5] {
+    [&snap.mass, &snap.energy, &snap.genome_a, &snap.genome_b, &snap.resource]
+}
+
+/// Range and u16 quantization of one channel.
+fn encode_channel(values: &[f32], out: &mut Vec<u8>) {
+    let lo = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let hi = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let (lo, hi) = if lo.is_finite() && hi.is_finite() { (lo, hi) } else { (0.0, 0.0) };
+    out.extend(lo.to_le_bytes());
+    out.extend(hi.to_le_bytes());
+    let span = hi - lo;
+    for &v in values {
+        let q = if span > 0.0 { ((v - lo) / span * u16::MAX as f32).round() } else { 0.0 };
+        out.extend((q as u16).to_le_bytes());
+    }
+}
+
+/// Inverse of `encode_channel` for `count` values; returns the values and
+/// the bytes consumed.
+fn decode_channel(bytes: &[u8], count: usize) -> io::Result<(Vec<f32>, usize)> {
+    let len = 8 + count * 2;
+    if bytes.len() < len {
+        return Err(invalid("tape frame is shorter than its channels"));
+    }
+    let lo = f32::from_le_bytes(bytes[0..4].try_into().unwrap());
+    let hi = f32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let step = (hi - lo) / u16::MAX as f32;
+    let values = bytes[8..len].chunks_exact(2).map(|q| lo + u16::from_le_bytes([q[0], q[1]]) as f32 * step).collect();
+    Ok((values, len))
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+// ======================== Writer ========================
+
+/// An open tape being recorded.
+pub struct TapeWriter {
+    file: BufWriter<File>,
+    pub path: PathBuf,
+    pub header: TapeHeader,
+    pub frames: u32,
+    pub bytes: u64,
+    pub last_frame: Option<u32>,
+}
+
+impl TapeWriter {
+    pub fn create(path: &Path, header: TapeHeader) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let json = serde_json::to_vec(&header).map_err(|e| invalid(e.to_string()))?;
+        file.write_all(MAGIC)?;
+        file.write_all(&(json.len() as u32).to_le_bytes())?;
+        file.write_all(&json)?;
+        let bytes = (MAGIC.len() + 4 + json.len()) as u64;
+        Ok(Self { file, path: path.to_path_buf(), header, frames: 0, bytes, last_frame: None })
+    }
+
+    /// Append world snapshot `snap` taken at `frame`, with its metrics.
+    pub fn write_frame(&mut self, frame: u32, metrics: &MetricsRecord, snap: &BufferSnapshot) -> io::Result<()> {
+        let small = downsample(snap, self.header.downsample);
+        let json = serde_json::to_vec(metrics).map_err(|e| invalid(e.to_string()))?;
+        let mut payload = Vec::with_capacity(4 + json.len() + self.header.cells() * 16 + 40);
+        payload.extend((json.len() as u32).to_le_bytes());
+        payload.extend(&json);
+        for values in channels(&small) {
+            encode_channel(values, &mut payload);
+        }
+        self.file.write_all(FRAME_TAG)?;
+        self.file.write_all(&frame.to_le_bytes())?;
+        self.file.write_all(&(payload.len() as u64).to_le_bytes())?;
+        self.file.write_all(&payload)?;
+        self.file.write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+        self.file.flush()?;
+        self.frames += 1;
+        self.bytes += CHUNK_HEADER + payload.len() as u64 + 4;
+        self.last_frame = Some(frame);
+        Ok(())
+    }
+
+    /// Flush and close the tape.
+    pub fn finish(mut self) -> io::Result<PathBuf> {
+        self.file.flush()?;
+        Ok(self.path)
+    }
+}
+
+// ======================== Reader ========================
+
+/// One played-back frame.
+#[derive(Clone)]
+pub struct TapeFrame {
+    pub frame: u32,
+    pub metrics: MetricsRecord,
+    pub snapshot: BufferSnapshot, // tape-sized
+}
+
+/// An indexed tape: the header and where each frame's chunk starts.
+pub struct TapeReader {
+    file: File,
+    pub header: TapeHeader,
+    pub index: Vec<(u32, u64)>, // (frame, chunk offset)
+}
+
+impl TapeReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut magic = [0u8; 8];
+        file.read_exact(&mut magic).map_err(|_| invalid("not a tape file"))?;
+        if &magic != MAGIC {
+            return Err(invalid("not a tape file"));
+        }
+        let mut len = [0u8; 4];
+        file.read_exact(&mut len)?;
+        let mut json = vec![0u8; u32::from_le_bytes(len) as usize];
+        file.read_exact(&mut json).map_err(|_| invalid("tape header truncated"))?;
+        let header: TapeHeader = serde_json::from_slice(&json).map_err(|e| invalid(format!("invalid tape header: {e}")))?;
+        if header.version > TAPE_SCHEMA_VERSION {
+            return Err(invalid(format!("tape written by a newer version (schema {})", header.version)));
+        }
+        let expected: Vec<(&str, usize)> = header.channels.iter().map(|c| (c.name.as_str(), c.components)).collect();
+        if expected != TAPE_CHANNELS {
+            return Err(invalid("tape channels do not match this version"));
+        }
+
+        // Index the complete chunks; stop at the first torn one
+        let end = file.metadata()?.len();
+        let mut offset = file.stream_position()?;
+        let mut index = Vec::new();
+        while offset + CHUNK_HEADER <= end {
+            let mut chunk = [0u8; CHUNK_HEADER as usize];
+            file.read_exact(&mut chunk)?;
+            let payload = u64::from_le_bytes(chunk[8..16].try_into().unwrap());
+            if &chunk[0..4] != FRAME_TAG || offset + CHUNK_HEADER + payload + 4 > end {
+                break;
+            }
+            index.push((u32::from_le_bytes(chunk[4..8].try_into().unwrap()), offset));
+            offset += CHUNK_HEADER + payload + 4;
+            file.seek(SeekFrom::Start(offset))?;
+        }
+        Ok(Self { file, header, index })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Read and verify the `k`-th recorded frame.
+    pub fn read(&mut self, k: usize) -> io::Result<TapeFrame> {
+        let &(frame, offset) = self.index.get(k).ok_or_else(|| invalid(format!("no frame {k} on the tape")))?;
+        self.file.seek(SeekFrom::Start(offset + 8))?;
+        let mut len = [0u8; 8];
+        self.file.read_exact(&mut len)?;
+        let mut payload = vec![0u8; u64::from_le_bytes(len) as usize];
+        self.file.read_exact(&mut payload)?;
+        let mut crc = [0u8; 4];
+        self.file.read_exact(&mut crc)?;
+        if u32::from_le_bytes(crc) != crc32fast::hash(&payload) {
+            return Err(invalid(format!("tape frame {frame} is corrupted (checksum mismatch)")));
+        }
+
+        let json_len = u32::from_le_bytes(payload.get(0..4).ok_or_else(|| invalid("tape frame truncated"))?.try_into().unwrap()) as usize;
+        let json = payload.get(4..4 + json_len).ok_or_else(|| invalid("tape frame truncated"))?;
+        let metrics: MetricsRecord = serde_json::from_slice(json).map_err(|e| invalid(format!("invalid tape metrics: {e}")))?;
+        let mut rest = &payload[4 + json_len..];
+        let cells = self.header.cells();
+        let mut read = |components: usize| -> io::Result<Vec<f32>> {
+            let (values, used) = decode_channel(rest, cells * components)?;
+            rest = &rest[used..];
+            Ok(values)
+        };
+        let snapshot = BufferSnapshot {
+            size: self.header.size,
+            mass: read(TAPE_CHANNELS[0].1)?,
+            energy: read(TAPE_CHANNELS[1].1)?,
+            genome_a: read(TAPE_CHANNELS[2].1)?,
+            genome_b: read(TAPE_CHANNELS[3].1)?,
+            resource: read(TAPE_CHANNELS[4].1)?,
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        Ok(TapeFrame { frame, metrics, snapshot })
+    }
+}
+
+/// Nearest-neighbor upsampling of a tape frame back to the world size.
+pub fn upsample(small: &BufferSnapshot, header: &TapeHeader) -> BufferSnapshot {
+    let (w, d) = (header.size[0], header.downsample.max(1));
+    let [width, height] = header.world;
+    let n = (width * height) as usize;
+    let mut out = BufferSnapshot {
+        size: header.world,
+        mass: Vec::with_capacity(n),
+        energy: Vec::with_capacity(n),
+        genome_a: Vec::with_capacity(n * 4),
+        genome_b: Vec::with_capacity(n),
+        resource: Vec::with_capacity(n),
+        flow: Vec::new(),
+        channels: BTreeMap::new(),
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let k = ((y / d) * w + x / d) as usize;
+            out.mass.push(small.mass[k]);
+            out.energy.push(small.energy[k]);
+            out.genome_a.extend_from_slice(&small.genome_a[k * 4..k * 4 + 4]);
+            out.genome_b.push(small.genome_b[k]);
+            out.resource.push(small.resource[k]);
+        }
+    }
+    out
+}
+
+/// Index entry of the last recorded frame at or before `frame` (the first
+/// entry when `frame` precedes the tape).
+pub fn seek_frame(index: &[(u32, u64)], frame: u32) -> usize {
+    index.partition_point(|&(f, _)| f <= frame).saturating_sub(1)
+}
+
+// ======================== Playback ========================
+
+/// The live world put aside while a tape plays.
+pub struct PlaybackResume {
+    pub snapshot: BufferSnapshot,
+    pub frame: u32,
+    pub paused: bool,
+}
+
+/// Export of tape frames `next..=end` to a PNG sequence or a video.
+pub struct SequenceExport {
+    pub path: PathBuf, // sequence directory or video file
+    pub sink: Box<dyn FrameSink>,
+    pub next: usize,
+    pub end: usize,
+    pub written: u32,
+}
+
+/// An open tape: the frame on show and the transport state.
+pub struct TapePlayer {
+    pub reader: TapeReader,
+    pub path: PathBuf,
+    pub position: usize,      // index of the frame to show
+    pub shown: Option<usize>, // index of the frame in the world buffers
+    pub metrics: Option<MetricsRecord>,
+    pub playing: bool,
+    pub fps: f32, // tape frames per second while playing
+    clock: f32,
+    pub resume: PlaybackResume,
+    pub export: Option<SequenceExport>,
+}
+
+impl TapePlayer {
+    pub fn new(reader: TapeReader, path: &Path, resume: PlaybackResume) -> Self {
+        Self {
+            reader,
+            path: path.to_path_buf(),
+            position: 0,
+            shown: None,
+            metrics: None,
+            playing: false,
+            fps: 10.0,
+            clock: 0.0,
+            resume,
+            export: None,
+        }
+    }
+
+    /// Simulation frame of tape entry `k`.
+    pub fn frame_at(&self, k: usize) -> u32 {
+        self.reader.index.get(k).map_or(0, |e| e.0)
+    }
+
+    /// Show the last recorded frame at or before simulation frame `frame`.
+    pub fn seek(&mut self, frame: u32) {
+        self.position = seek_frame(&self.reader.index, frame);
+    }
+
+    /// Advance by `dt` seconds: one frame per image while exporting, else
+    /// at `fps` while playing, stopping at the end of the tape.
+    pub fn tick(&mut self, dt: f32) {
+        if let Some(export) = &self.export {
+            self.position = export.next;
+            return;
+        }
+        let last = self.reader.len().saturating_sub(1);
+        if !self.playing {
+            self.clock = 0.0;
+            return;
+        }
+        self.clock += dt * self.fps.max(0.0);
+        let steps = self.clock.floor();
+        self.clock -= steps;
+        self.position = (self.position + steps as usize).min(last);
+        if self.position == last {
+            self.playing = false;
+        }
+    }
+
+    /// Whether the world buffers are behind the frame to show.
+    pub fn needs_upload(&self) -> bool {
+        self.shown != Some(self.position)
+    }
+}
+
+// ======================== Lab State ========================
+
+/// Tape recording of the Lab: settings, the tape being written, and the
+/// requests of the UI.
+#[derive(Default)]
+pub struct TapeRecorder {
+    pub settings: TapeSettings,
+    pub writer: Option<TapeWriter>,
+    pub next_frame: u32,
+    pub start_requested: bool,
+    pub stop_requested: bool,
+    pub last_path: Option<PathBuf>,
+}
+
+impl TapeRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Whether the frame just reached is to be captured.
+    pub fn due(&self, frame: u32) -> bool {
+        self.writer.is_some() && frame >= self.next_frame
+    }
+}
+
+/// Tape playback of the Lab: the open tape and the requests of the UI.
+#[derive(Default)]
+pub struct TapePlayback {
+    pub player: Option<TapePlayer>,
+    pub open_path: Option<PathBuf>,
+    pub close_requested: bool,
+    pub export_requested: Option<(usize, usize)>, // tape entries, inclusive
+    pub export_video: bool,                       // export to a video instead of PNG images
+}
+
+impl TapePlayback {
+    pub fn is_active(&self) -> bool {
+        self.player.is_some()
+    }
+
+    pub fn exporting(&self) -> bool {
+        self.player.as_ref().is_some_and(|p| p.export.is_some())
+    }
+}
+
+/// Tape file of a recording started at `frame`.
+pub fn tape_path(run_dir: &Path, frame: u32) -> PathBuf {
+    run_dir.join(format!("recording_f{frame:06}.{TAPE_EXTENSION}"))
+}
+
+/// Full-resolution snapshot of the world when the tape started.
+pub fn start_snapshot_path(tape: &Path) -> PathBuf {
+    tape.with_extension(SNAPSHOT_EXTENSION)
+}
+
+/// Simulation parameters (JSON) when the tape started.
+pub fn start_params_path(tape: &Path) -> PathBuf {
+    tape.with_extension("params.json")
+}
@@ -0,0 +1,201 @@
+// ============================================================================
+// tensorboard.rs — EvoLenia v2 Research Lab
+// TensorBoard event files: metrics as scalar summaries and screenshots as
+// image summaries, browsable with `tensorboard --logdir <dir>`.
+//
+// An event file is a sequence of TFRecords (length, masked CRC-32C, payload,
+// masked CRC-32C) whose payloads are `tensorflow.Event` protobufs. Only the
+// handful of fields we write are encoded, by hand, to avoid a protobuf
+// dependency.
+// ============================================================================
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Event file format version TensorBoard expects in the first record.
+const FILE_VERSION: &str = "brain.Event:2";
+/// `Summary.Image.colorspace` for RGBA images.
+const COLORSPACE_RGBA: u64 = 4;
+
+/// Distinguishes event files created within the same second.
+static FILE_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+// ======================== CRC-32C (Castagnoli) ========================
+
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { (c >> 1) ^ 0x82F6_3B78 } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+pub fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc = CRC32C_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// TFRecord checksum: CRC-32C rotated and offset, so records containing
+/// their own CRCs still checksum well.
+pub fn masked_crc32c(data: &[u8]) -> u32 {
+    crc32c(data).rotate_right(15).wrapping_add(0xA282_EAD8)
+}
+
+/// Frame one payload as a TFRecord.
+pub fn encode_record(data: &[u8]) -> Vec<u8> {
+    let len = (data.len() as u64).to_le_bytes();
+    let mut record = Vec::with_capacity(data.len() + 16);
+    record.extend_from_slice(&len);
+    record.extend_from_slice(&masked_crc32c(&len).to_le_bytes());
+    record.extend_from_slice(data);
+    record.extend_from_slice(&masked_crc32c(data).to_le_bytes());
+    record
+}
+
+// ======================== Protobuf Encoding ========================
+
+pub fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn put_key(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    put_varint(buf, ((field as u64) << 3) | wire_type as u64);
+}
+
+fn put_uint(buf: &mut Vec<u8>, field: u32, v: u64) {
+    put_key(buf, field, 0);
+    put_varint(buf, v);
+}
+
+fn put_double(buf: &mut Vec<u8>, field: u32, v: f64) {
+    put_key(buf, field, 1);
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_key(buf, field, 2);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn put_float(buf: &mut Vec<u8>, field: u32, v: f32) {
+    put_key(buf, field, 5);
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+/// `Summary.Value { tag, simple_value }`
+fn scalar_value(tag: &str, value: f32) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_bytes(&mut buf, 1, tag.as_bytes());
+    put_float(&mut buf, 2, value);
+    buf
+}
+
+/// `Summary.Value { tag, image: Image { height, width, colorspace, encoded_image_string } }`
+fn image_value(tag: &str, width: u32, height: u32, png: &[u8]) -> Vec<u8> {
+    let mut image = Vec::with_capacity(png.len() + 16);
+    put_uint(&mut image, 1, height as u64);
+    put_uint(&mut image, 2, width as u64);
+    put_uint(&mut image, 3, COLORSPACE_RGBA);
+    put_bytes(&mut image, 4, png);
+
+    let mut buf = Vec::with_capacity(image.len() + tag.len() + 8);
+    put_bytes(&mut buf, 1, tag.as_bytes());
+    put_bytes(&mut buf, 4, &image);
+    buf
+}
+
+/// `Event { wall_time, step, summary: Summary { value* } }`
+pub fn summary_event(wall_time: f64, step: u32, values: &[Vec<u8>]) -> Vec<u8> {
+    let mut summary = Vec::new();
+    for value in values {
+        put_bytes(&mut summary, 1, value);
+    }
+    let mut event = Vec::with_capacity(summary.len() + 24);
+    put_double(&mut event, 1, wall_time);
+    put_uint(&mut event, 2, step as u64);
+    put_bytes(&mut event, 5, &summary);
+    event
+}
+
+/// `Event { wall_time, file_version }`, the first record of every file.
+fn file_version_event(wall_time: f64) -> Vec<u8> {
+    let mut event = Vec::new();
+    put_double(&mut event, 1, wall_time);
+    put_bytes(&mut event, 3, FILE_VERSION.as_bytes());
+    event
+}
+
+fn wall_time() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64())
+}
+
+// ======================== Event Writer ========================
+
+/// Shared handle on one event file. Clones write to the same file, so the
+/// screenshot worker can log images while the main thread logs scalars.
+#[derive(Clone)]
+pub struct TensorBoardLog {
+    path: PathBuf,
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl TensorBoardLog {
+    /// Create a new event file in `dir` (created if needed).
+    pub fn create(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let path = dir.join(format!(
+            "events.out.tfevents.{}.evolenia.{}.{}",
+            secs,
+            std::process::id(),
+            FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let log = Self { writer: Arc::new(Mutex::new(BufWriter::new(File::create(&path)?))), path };
+        log.write_event(&file_version_event(wall_time()))?;
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Log scalars under their tags at one step.
+    pub fn scalars(&self, step: u32, scalars: &[(&str, f32)]) -> io::Result<()> {
+        let values: Vec<Vec<u8>> = scalars.iter().map(|&(tag, v)| scalar_value(tag, v)).collect();
+        self.write_event(&summary_event(wall_time(), step, &values))
+    }
+
+    /// Log a PNG-encoded RGBA image under `tag` at one step.
+    pub fn image(&self, step: u32, tag: &str, width: u32, height: u32, png: &[u8]) -> io::Result<()> {
+        self.write_event(&summary_event(wall_time(), step, &[image_value(tag, width, height, png)]))
+    }
+
+    /// Flushed per event, so TensorBoard sees a live run as it goes.
+    fn write_event(&self, event: &[u8]) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(&encode_record(event))?;
+        writer.flush()
+    }
+}
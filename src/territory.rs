@@ -0,0 +1,223 @@
+// ============================================================================
+// territory.rs — EvoLenia v2 Research Lab
+// Territory / home-range map: each diagnostics sample labels live cells with
+// a species id (genome cluster, kept stable across samples), and the last
+// `window` label maps are kept. A cell's territory owner is the species that
+// occupied it most often over the window; how often sets the overlay alpha,
+// so contested or recently colonized ground shows faint.
+// With the species map on, the labels come from the GPU pass instead
+// (compute_species.wgsl) and the CPU only founds clusters for the genomes
+// it left unassigned; the representatives are then uploaded back.
+// After each sample a cluster's representative moves to the mass-weighted
+// mean genome of its cells, so a species keeps its id, and with it its
+// color, while its genomes drift; only a population no cluster follows
+// founds a new id and gets a new hue. The id → color table is the same for
+// the overlay, the legend and the render shader (species palette buffer).
+// ============================================================================
+
+use std::collections::VecDeque;
+
+use crate::metrics::GenomeClusters;
+use crate::popgen::LIVE_MASS_THRESHOLD;
+use crate::world::BufferSnapshot;
+
+/// Species tracked before new genomes join the nearest existing one.
+pub const MAX_SPECIES: usize = 64;
+/// Label of an empty cell.
+pub const NO_SPECIES: u16 = u16::MAX;
+/// Species map value of a live cell no cluster is close to yet.
+pub const UNASSIGNED: f32 = -2.0;
+/// Golden-ratio hue step, so consecutive species ids get distant colors.
+const HUE_STEP: f32 = 0.618_034;
+
+/// Per-cell species labels over a sliding window of samples.
+pub struct TerritoryMap {
+    species: GenomeClusters,
+    labels: VecDeque<Vec<u16>>, // oldest first
+}
+
+impl Default for TerritoryMap {
+    fn default() -> Self {
+        Self { species: GenomeClusters::new(MAX_SPECIES), labels: VecDeque::new() }
+    }
+}
+
+impl TerritoryMap {
+    /// Samples currently in the window.
+    pub fn samples(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn species_count(&self) -> usize {
+        self.species.count()
+    }
+
+    /// Forget all samples and species (new run).
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Species clusters the labels refer to.
+    pub fn species(&self) -> &GenomeClusters {
+        &self.species
+    }
+
+    /// Label the snapshot's live cells and add them, keeping `window` samples.
+    pub fn record(&mut self, snap: &BufferSnapshot, window: usize) {
+        let labels = self.label(snap);
+        self.push(labels, window);
+    }
+
+    /// Species of each of the snapshot's cells (NO_SPECIES when empty),
+    /// with the clusters moved onto their cells.
+    pub fn label(&mut self, snap: &BufferSnapshot) -> Vec<u16> {
+        let labels: Vec<u16> = snap
+            .mass
+            .iter()
+            .zip(snap.genome_a.chunks_exact(4))
+            .map(|(&m, g)| {
+                if m > LIVE_MASS_THRESHOLD {
+                    self.species.assign((g[0], g[1], g[2], g[3])) as u16
+                } else {
+                    NO_SPECIES
+                }
+            })
+            .collect();
+        self.follow(&labels, snap);
+        labels
+    }
+
+    /// Labels of the GPU species map (row-major), with the genomes it left
+    /// unassigned given a cluster from the snapshot and the clusters moved
+    /// onto their cells.
+    pub fn resolve(&mut self, map: &[f32], snap: &BufferSnapshot) -> Vec<u16> {
+        let labels: Vec<u16> = map
+            .iter()
+            .zip(snap.genome_a.chunks_exact(4))
+            .map(|(&id, g)| match id {
+                id if id == UNASSIGNED => self.species.assign((g[0], g[1], g[2], g[3])) as u16,
+                id if id < 0.0 => NO_SPECIES,
+                id => id as u16,
+            })
+            .collect();
+        self.follow(&labels, snap);
+        labels
+    }
+
+    /// Move every labelled cluster to the mass-weighted mean genome of its
+    /// cells; clusters without cells keep their representative.
+    fn follow(&mut self, labels: &[u16], snap: &BufferSnapshot) {
+        let mut sums = vec![([0.0f64; 4], 0.0f64); self.species.count()];
+        for (i, &label) in labels.iter().enumerate() {
+            let Some((sum, mass)) = sums.get_mut(label as usize) else {
+                continue; // NO_SPECIES
+            };
+            let m = snap.mass[i] as f64;
+            for (s, &g) in sum.iter_mut().zip(&snap.genome_a[i * 4..i * 4 + 4]) {
+                *s += g as f64 * m;
+            }
+            *mass += m;
+        }
+        for (id, (sum, mass)) in sums.into_iter().enumerate().filter(|(_, (_, mass))| *mass > 0.0) {
+            let [r, mu, sigma, agg] = sum.map(|s| (s / mass) as f32);
+            self.species.recenter(id, (r, mu, sigma, agg));
+        }
+    }
+
+    /// Add a label map, keeping `window` samples.
+    pub fn push(&mut self, labels: Vec<u16>, window: usize) {
+        self.labels.push_back(labels);
+        while self.labels.len() > window.max(1) {
+            self.labels.pop_front();
+        }
+    }
+
+    /// Per cell: the species that held it most often, and the share of the
+    /// window it held it (0 for never-occupied cells).
+    pub fn owners(&self) -> Vec<(u16, f32)> {
+        let Some(first) = self.labels.front() else {
+            return Vec::new();
+        };
+        let window = self.labels.len() as f32;
+        let mut tally: Vec<(u16, u32)> = Vec::with_capacity(4);
+        (0..first.len())
+            .map(|i| {
+                tally.clear();
+                for labels in &self.labels {
+                    let label = labels[i];
+                    if label == NO_SPECIES {
+                        continue;
+                    }
+                    match tally.iter_mut().find(|(s, _)| *s == label) {
+                        Some((_, n)) => *n += 1,
+                        None => tally.push((label, 1)),
+                    }
+                }
+                // Ties go to the species that arrived later
+                tally
+                    .iter()
+                    .max_by_key(|&&(_, n)| n)
+                    .map_or((NO_SPECIES, 0.0), |&(s, n)| (s, n as f32 / window))
+            })
+            .collect()
+    }
+
+    /// Cells owned per species, largest first: (species, cells).
+    pub fn areas(owners: &[(u16, f32)]) -> Vec<(u16, usize)> {
+        let mut areas: Vec<(u16, usize)> = Vec::new();
+        for &(species, _) in owners.iter().filter(|(s, _)| *s != NO_SPECIES) {
+            match areas.iter_mut().find(|(s, _)| *s == species) {
+                Some((_, n)) => *n += 1,
+                None => areas.push((species, 1)),
+            }
+        }
+        areas.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        areas
+    }
+}
+
+/// Cluster representatives as uploaded for the species map pass: one vec4
+/// per id, zero past the last cluster.
+pub fn centroid_table(species: &GenomeClusters) -> [[f32; 4]; MAX_SPECIES] {
+    let mut table = [[0.0; 4]; MAX_SPECIES];
+    for (slot, &(r, mu, sigma, agg)) in table.iter_mut().zip(species.representatives()) {
+        *slot = [r, mu, sigma, agg];
+    }
+    table
+}
+
+/// Overlay texture: species color, alpha = share of the window held.
+pub fn territory_rgba(owners: &[(u16, f32)], wheel: &[[f32; 4]; 6]) -> Vec<u8> {
+    owners
+        .iter()
+        .flat_map(|&(species, share)| {
+            if species == NO_SPECIES {
+                return [0; 4];
+            }
+            let [r, g, b] = species_color(species, wheel);
+            [r, g, b, (share.clamp(0.0, 1.0) * 255.0).round() as u8]
+        })
+        .collect()
+}
+
+/// Color of a species id on the palette's hue wheel.
+pub fn species_color(species: u16, wheel: &[[f32; 4]; 6]) -> [u8; 3] {
+    species_rgb(species, wheel).map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Linear color of a species id: golden-ratio steps around the hue wheel.
+pub fn species_rgb(species: u16, wheel: &[[f32; 4]; 6]) -> [f32; 3] {
+    let t = (species as f32 * HUE_STEP).fract() * 6.0;
+    let i = t.floor() as usize % 6;
+    let f = t - t.floor();
+    let (a, b) = (wheel[i], wheel[(i + 1) % 6]);
+    std::array::from_fn(|c| a[c] + (b[c] - a[c]) * f)
+}
+
+/// Species palette uploaded for the render shader: the color of every id.
+pub fn species_palette(wheel: &[[f32; 4]; 6]) -> [[f32; 4]; MAX_SPECIES] {
+    std::array::from_fn(|id| {
+        let [r, g, b] = species_rgb(id as u16, wheel);
+        [r, g, b, 1.0]
+    })
+}
@@ -10,17 +10,21 @@ mod physics_tests {
     //! Tests for physical conservation laws and bounds.
     //! These are fundamental — violations indicate broken physics.
 
-    use crate::world::{BufferSnapshot, WORLD_HEIGHT, WORLD_WIDTH};
+    use std::collections::BTreeMap;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
     use crate::metrics::SimDiagnostics;
 
     fn create_uniform_snapshot(mass_value: f32, energy_value: f32) -> BufferSnapshot {
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![mass_value; n],
             energy: vec![energy_value; n],
             genome_a: vec![10.0, 0.15, 0.02, 0.1].into_iter().cycle().take(n * 4).collect(),
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         }
     }
 
@@ -54,7 +58,7 @@ mod physics_tests {
     #[test]
     fn total_mass_calculation_is_accurate() {
         // Diagnostic total_mass must equal sum of pixel masses
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let mass_per_pixel = 0.3;
         let mut snap = create_uniform_snapshot(mass_per_pixel, 0.5);
         
@@ -89,7 +93,7 @@ mod physics_tests {
     #[test]
     fn starving_fraction_is_ratio_of_live_pixels() {
         // Starving: energy ≤ 0.01 among LIVE pixels only
-        let _n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let _n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let mut snap = create_uniform_snapshot(0.0, 0.5);
         
         // 3 live pixels, 2 starving
@@ -121,7 +125,8 @@ mod genome_tests {
     //! Tests for genome bounds and validity.
     //! Genome values outside valid ranges cause shader errors.
 
-    use crate::world::{BufferSnapshot, WORLD_HEIGHT, WORLD_WIDTH};
+    use std::collections::BTreeMap;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
     use crate::metrics::compute_genome_stats;
 
     #[test]
@@ -157,13 +162,16 @@ mod genome_tests {
     #[test]
     fn genome_stats_weighted_by_mass() {
         // Genome stats MUST be mass-weighted averages
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.0; n],
             energy: vec![0.5; n],
             genome_a: vec![0.0; n * 4],
             genome_b: vec![0.0; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         // Pixel 0: mass=0.8, r=10
@@ -197,13 +205,16 @@ mod genome_tests {
     #[test]
     fn predator_fraction_uses_correct_threshold() {
         // Predator: aggressivity > 0.7 (NOT >= 0.7)
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.0; n],
             energy: vec![0.5; n],
             genome_a: vec![0.0; n * 4],
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         // Pixel 0: agg=0.7 (NOT predator)
@@ -663,15 +674,18 @@ mod math_tests {
 
 #[cfg(test)]
 mod state_io_tests {
-    //! Tests for snapshot save/load (lossless roundtrip).
+    //! Tests for snapshot save/load (lossless roundtrip, integrity checks,
+    //! resampling across world sizes).
 
-    use crate::state_io::{save_snapshot, load_snapshot};
-    use crate::world::{BufferSnapshot, WORLD_HEIGHT, WORLD_WIDTH};
+    use std::collections::BTreeMap;
+    use crate::state_io::{fit_snapshot, save_snapshot, load_snapshot, resample_snapshot};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
     use std::fs;
 
     fn create_test_snapshot() -> BufferSnapshot {
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: (0..n).map(|i| (i as f32 / n as f32) * 0.9 + 0.05).collect(),
             energy: (0..n).map(|i| 0.5 + 0.3 * ((i as f32 / 100.0).sin())).collect(),
             genome_a: (0..n * 4).map(|i| match i % 4 {
@@ -683,6 +697,8 @@ mod state_io_tests {
             }).collect(),
             genome_b: (0..n).map(|i| 0.001 + (i % 10) as f32 * 0.0005).collect(),
             resource: (0..n).map(|i| 0.5 + 0.5 * ((i as f32 / 50.0).cos())).collect(),
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         }
     }
 
@@ -740,24 +756,132 @@ mod state_io_tests {
         
         assert!(result.is_err(), "Loading file with invalid magic should fail");
     }
+
+    fn load_error(path: &str, bytes: &[u8]) -> String {
+        fs::write(path, bytes).expect("Failed to write test file");
+        let result = load_snapshot(path);
+        let _ = fs::remove_file(path);
+        match result {
+            Ok(_) => panic!("Loading a damaged snapshot should fail"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn truncated_snapshot_reports_channel() {
+        let path = "/tmp/evolenia_truncated.snap";
+        save_snapshot(path, &create_test_snapshot()).expect("Failed to save snapshot");
+        let bytes = fs::read(path).unwrap();
+        // The empty flow channel (length + CRC) closes the file
+        let err = load_error(path, &bytes[..bytes.len() - 12 - 10]);
+        assert!(err.contains("truncated in resource"), "{}", err);
+        let err = load_error(path, &bytes[..bytes.len() - 6]);
+        assert!(err.contains("truncated in flow"), "{}", err);
+    }
+
+    #[test]
+    fn corrupted_snapshot_fails_checksum() {
+        let path = "/tmp/evolenia_corrupted.snap";
+        save_snapshot(path, &create_test_snapshot()).expect("Failed to save snapshot");
+        let mut bytes = fs::read(path).unwrap();
+        // A mass value: after magic, dimensions and the channel length
+        bytes[8 + 8 + 8 + 100] ^= 0x10;
+        let err = load_error(path, &bytes);
+        assert!(err.contains("mass channel is corrupted"), "{}", err);
+    }
+
+    #[test]
+    fn version_1_snapshot_still_loads() {
+        let original = create_test_snapshot();
+        let mut bytes = b"EVOSNP01".to_vec();
+        bytes.extend(DEFAULT_WORLD_SIZE[0].to_le_bytes());
+        bytes.extend(DEFAULT_WORLD_SIZE[1].to_le_bytes());
+        for channel in [&original.mass, &original.energy, &original.genome_a, &original.genome_b, &original.resource] {
+            bytes.extend((channel.len() as u64).to_le_bytes());
+            bytes.extend(channel.iter().flat_map(|v| v.to_le_bytes()));
+        }
+        let path = "/tmp/evolenia_v1.snap";
+        fs::write(path, &bytes).expect("Failed to write test file");
+        let loaded = load_snapshot(path);
+        let _ = fs::remove_file(path);
+        let loaded = loaded.expect("Version 1 snapshot should load");
+        assert_eq!(loaded.genome_a, original.genome_a);
+        assert_eq!(loaded.resource, original.resource);
+    }
+
+    #[test]
+    fn resample_keeps_cells_and_genomes() {
+        // 2×2 world, one distinct value per cell
+        let small = BufferSnapshot {
+            size: [2, 2],
+            mass: vec![0.1, 0.2, 0.3, 0.4],
+            energy: vec![1.0, 2.0, 3.0, 4.0],
+            genome_a: (0..16).map(|i| i as f32).collect(),
+            genome_b: vec![5.0, 6.0, 7.0, 8.0],
+            resource: vec![0.5; 4],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        let big = resample_snapshot(&small, [4, 4]);
+        assert_eq!(big.mass.len(), 16);
+        assert_eq!(big.genome_a.len(), 64);
+        // Each source cell becomes a 2×2 block with the whole genome
+        assert_eq!(&big.mass[..4], &[0.1, 0.1, 0.2, 0.2]);
+        assert_eq!(big.mass[15], 0.4);
+        assert_eq!(&big.genome_a[4 * 5..4 * 6], &[0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(&big.genome_a[4 * 10..4 * 11], &[12.0, 13.0, 14.0, 15.0]);
+
+        let back = resample_snapshot(&big, [2, 2]);
+        assert_eq!(back.mass, small.mass);
+        assert_eq!(back.genome_a, small.genome_a);
+        assert_eq!(back.genome_b, small.genome_b);
+    }
+
+    #[test]
+    fn snapshot_of_another_size_is_resampled_on_load() {
+        let (w, h) = (DEFAULT_WORLD_SIZE[0] / 2, DEFAULT_WORLD_SIZE[1] / 2);
+        let n = (w * h) as usize;
+        let mut bytes = b"EVOSNP01".to_vec();
+        bytes.extend(w.to_le_bytes());
+        bytes.extend(h.to_le_bytes());
+        for (per_pixel, value) in [(1, 0.25f32), (1, 0.5), (4, 0.1), (1, 0.003), (1, 0.75)] {
+            bytes.extend(((n * per_pixel) as u64).to_le_bytes());
+            bytes.extend((0..n * per_pixel).flat_map(|_| value.to_le_bytes()));
+        }
+        let path = "/tmp/evolenia_half_size.snap";
+        fs::write(path, &bytes).expect("Failed to write test file");
+        let loaded = load_snapshot(path);
+        let _ = fs::remove_file(path);
+        let loaded = loaded.expect("Smaller snapshot should load");
+        assert_eq!((loaded.size, loaded.mass.len()), ([w, h], n));
+        let loaded = fit_snapshot(loaded, DEFAULT_WORLD_SIZE);
+        assert_eq!(loaded.size, DEFAULT_WORLD_SIZE);
+        assert_eq!(loaded.mass.len(), (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize);
+        assert!(loaded.mass.iter().all(|&m| m == 0.25));
+        assert!(loaded.resource.iter().all(|&r| r == 0.75));
+    }
 }
 
 #[cfg(test)]
 mod trophic_tests {
     //! Tests for trophic classification (prey/opportunist/predator).
 
-    use crate::world::{BufferSnapshot, WORLD_HEIGHT, WORLD_WIDTH};
+    use std::collections::BTreeMap;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
     use crate::metrics::SimDiagnostics;
 
     fn create_trophic_snapshot(agg_values: &[(f32, f32)]) -> BufferSnapshot {
         // agg_values: [(aggressivity, mass), ...]
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.0; n],
             energy: vec![0.5; n],
             genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         for (i, &(agg, mass)) in agg_values.iter().enumerate() {
@@ -874,19 +998,23 @@ mod trophic_tests {
 mod diversity_tests {
     //! Tests for diversity metrics (effective diversity, genome variance).
 
-    use crate::world::{BufferSnapshot, WORLD_HEIGHT, WORLD_WIDTH};
+    use std::collections::BTreeMap;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
     use crate::metrics::SimDiagnostics;
 
     #[test]
     fn effective_diversity_minimum_is_one() {
         // Hill number N1 = exp(H) ≥ 1 (even for uniform population)
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.5; n],
             energy: vec![0.5; n],
             genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         let diag = SimDiagnostics::from_snapshot(&snap);
@@ -900,15 +1028,18 @@ mod diversity_tests {
 
     #[test]
     fn effective_diversity_increases_with_species() {
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         
         // Snapshot 1: uniform population
         let snap_uniform = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.5; n],
             energy: vec![0.5; n],
             genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         // Snapshot 2: two distinct species (half each)
@@ -921,11 +1052,14 @@ mod diversity_tests {
             }
         }
         let snap_diverse = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.5; n],
             energy: vec![0.5; n],
             genome_a: genome_a_diverse,
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         let diag_uniform = SimDiagnostics::from_snapshot(&snap_uniform);
@@ -940,13 +1074,16 @@ mod diversity_tests {
 
     #[test]
     fn genome_variance_is_non_negative() {
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.5; n],
             energy: vec![0.5; n],
             genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         let diag = SimDiagnostics::from_snapshot(&snap);
@@ -960,13 +1097,16 @@ mod diversity_tests {
 
     #[test]
     fn uniform_population_has_zero_genome_variance() {
-        let n = (WORLD_WIDTH * WORLD_HEIGHT) as usize;
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
         let snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
             mass: vec![0.5; n],
             energy: vec![0.5; n],
             genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
             genome_b: vec![0.003; n],
             resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
         };
         
         let diag = SimDiagnostics::from_snapshot(&snap);
@@ -983,30 +1123,42 @@ mod diversity_tests {
 mod initialization_tests {
     //! Tests for world initialization invariants.
 
-    use crate::world::{WORLD_WIDTH, WORLD_HEIGHT, TARGET_FILL, total_pixels, target_total_mass};
-
-    #[test]
-    fn total_pixels_is_width_times_height() {
-        assert_eq!(
-            total_pixels(),
-            WORLD_WIDTH * WORLD_HEIGHT,
-            "total_pixels() should equal WORLD_WIDTH * WORLD_HEIGHT"
-        );
-    }
+    use crate::world::{
+        check_world_size, parse_world_size, target_mass_of, DEFAULT_WORLD_SIZE, MAX_WORLD_EDGE, TARGET_FILL,
+    };
 
     #[test]
     fn target_mass_is_fill_times_pixels() {
-        let expected = (WORLD_WIDTH * WORLD_HEIGHT) as f32 * TARGET_FILL;
-        let actual = target_total_mass();
+        let [width, height] = DEFAULT_WORLD_SIZE;
+        let expected = (width * height) as f32 * TARGET_FILL;
+        let actual = target_mass_of(DEFAULT_WORLD_SIZE);
         
         let diff = (expected - actual).abs();
         assert!(
             diff < 0.01,
-            "target_total_mass() should be pixels * TARGET_FILL. Expected {}, got {}",
+            "target_mass_of() should be pixels * TARGET_FILL. Expected {}, got {}",
             expected, actual
         );
     }
 
+    #[test]
+    fn world_sizes_parse_and_check() {
+        assert_eq!(parse_world_size("1024x512"), Some([1024, 512]));
+        assert_eq!(parse_world_size(" 256 × 128 "), Some([256, 128]));
+        assert_eq!(parse_world_size("768"), Some([768, 768]));
+        assert_eq!(parse_world_size("1024x"), None);
+        assert_eq!(parse_world_size("wide"), None);
+
+        assert!(check_world_size(DEFAULT_WORLD_SIZE).is_ok());
+        assert!(check_world_size([2048, 512]).is_ok());
+        assert!(check_world_size([96, 64]).is_err(), "width not a multiple of 64");
+        assert!(check_world_size([512, 200]).is_err(), "height not a multiple of 16");
+        assert!(check_world_size([512, 208]).is_ok());
+        assert!(check_world_size([512, 32]).is_err(), "edge too small");
+        assert!(check_world_size([MAX_WORLD_EDGE * 2, 512]).is_err(), "edge too large");
+        assert_eq!(target_mass_of([1024, 512]), 2.0 * target_mass_of([512, 512]));
+    }
+
     #[test]
     fn target_fill_is_reasonable() {
         // TARGET_FILL should be in (0, 1)
@@ -1017,13 +1169,6503 @@ mod initialization_tests {
     #[test]
     fn world_dimensions_are_power_of_two_friendly() {
         // Workgroup size compatibility (16x16)
-        assert!(
-            WORLD_WIDTH % 16 == 0,
-            "WORLD_WIDTH should be divisible by 16 for GPU workgroups"
-        );
-        assert!(
-            WORLD_HEIGHT % 16 == 0,
-            "WORLD_HEIGHT should be divisible by 16 for GPU workgroups"
-        );
+        let [width, height] = DEFAULT_WORLD_SIZE;
+        assert!(width.is_multiple_of(16), "the world width should be divisible by 16 for GPU workgroups");
+        assert!(height.is_multiple_of(16), "the world height should be divisible by 16 for GPU workgroups");
+    }
+}
+
+#[cfg(test)]
+mod camera_tests {
+    //! Tests for screen ↔ world coordinate mapping used by pointer tools and
+    //! for the orbit camera of the 3D surface view.
+
+    use crate::camera::{CameraState, CameraUniforms, OrbitCamera};
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    #[test]
+    fn screen_world_roundtrip_is_identity() {
+        let camera = CameraState { offset: [0.12, -0.07], zoom: 3.5, ..Default::default() };
+        for (w, h) in [(600, 1000), (800, 800), (1800, 1000)] {
+            for uv in [[0.0f32, 0.0], [0.25, 0.75], [0.5, 0.5], [1.0, 0.3]] {
+                let world = camera.screen_to_world(uv, w, h, DEFAULT_WORLD_SIZE);
+                let back = camera.world_to_screen(world, w, h, DEFAULT_WORLD_SIZE);
+                assert!(
+                    (back[0] - uv[0]).abs() < 1e-5 && (back[1] - uv[1]).abs() < 1e-5,
+                    "Roundtrip failed for {:?} at {}x{}: got {:?}",
+                    uv, w, h, back
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn screen_center_maps_to_world_center_at_default_camera() {
+        let camera = CameraState::default();
+        let world = camera.screen_to_world([0.5, 0.5], 1600, 1000, DEFAULT_WORLD_SIZE);
+        assert!((world[0] - 0.5).abs() < 1e-6 && (world[1] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn integer_zoom_snaps_and_steps_whole_pixels() {
+        let (w, h) = (1600, 900);
+        let mut camera = CameraState { zoom: 7.3, ..Default::default() };
+        camera.snap_to_integer_zoom(w, h, DEFAULT_WORLD_SIZE);
+        let px = camera.cell_px(w, h, DEFAULT_WORLD_SIZE);
+        assert!((px - px.round()).abs() < 1e-3, "Snapped cell size {} not integral", px);
+
+        camera.step_integer_zoom(1.0, w, h, DEFAULT_WORLD_SIZE);
+        assert!((camera.cell_px(w, h, DEFAULT_WORLD_SIZE) - (px.round() + 1.0)).abs() < 1e-3);
+        camera.step_integer_zoom(-1.0, w, h, DEFAULT_WORLD_SIZE);
+        assert!((camera.cell_px(w, h, DEFAULT_WORLD_SIZE) - px.round()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pixel_grid_hidden_at_default_zoom() {
+        let mut camera = CameraState::default();
+        assert_eq!(camera.uniforms(1280, 720, DEFAULT_WORLD_SIZE).grid_alpha, 0.0);
+        camera.zoom = 40.0;
+        assert_eq!(camera.uniforms(1280, 720, DEFAULT_WORLD_SIZE).grid_alpha, 1.0);
+        camera.pixel_grid = false;
+        assert_eq!(camera.uniforms(1280, 720, DEFAULT_WORLD_SIZE).grid_alpha, 0.0);
+    }
+
+    #[test]
+    fn smooth_sampling_flag_reaches_uniforms() {
+        let mut camera = CameraState::default();
+        assert_eq!(camera.uniforms(1280, 720, DEFAULT_WORLD_SIZE).smooth_sampling, 0.0);
+        camera.smooth_sampling = true;
+        assert_eq!(camera.uniforms(1280, 720, DEFAULT_WORLD_SIZE).smooth_sampling, 1.0);
+        // Settings saved before the option existed load with it off
+        let old: CameraState = serde_json::from_str(r#"{"offset":[0.0,0.0],"zoom":2.0}"#).unwrap();
+        assert!(!old.smooth_sampling);
+    }
+
+    #[test]
+    fn orbit_basis_is_orthonormal_and_faces_target() {
+        let mut camera = CameraState::default();
+        camera.orbit.orbit(137.0, -42.0);
+        let [right, up, forward] = camera.orbit.basis();
+        let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        for (a, b) in [(right, up), (up, forward), (forward, right)] {
+            assert!(dot(a, b).abs() < 1e-5);
+        }
+        for v in [right, up, forward] {
+            assert!((dot(v, v) - 1.0).abs() < 1e-5);
+        }
+        assert!(up[1] > 0.0, "Camera upside down");
+        let (eye, target) = (camera.orbit.eye(DEFAULT_WORLD_SIZE), camera.orbit.target_point(DEFAULT_WORLD_SIZE));
+        let to_target = [target[0] - eye[0], target[1] - eye[1], target[2] - eye[2]];
+        let d = dot(to_target, to_target).sqrt();
+        assert!((d - camera.orbit.distance).abs() < 1e-4);
+        assert!((dot(to_target, forward) - d).abs() < 1e-4, "Forward does not point at the target");
+    }
+
+    #[test]
+    fn orbit_clamps_pitch_and_distance() {
+        let mut orbit = OrbitCamera::default();
+        orbit.orbit(0.0, 1e6);
+        assert!(orbit.pitch < std::f32::consts::FRAC_PI_2, "Camera flipped over the pole");
+        orbit.orbit(0.0, -1e6);
+        assert!(orbit.pitch > 0.0, "Camera went under the ground");
+        for _ in 0..200 {
+            orbit.apply_scroll(5.0);
+        }
+        let closest = orbit.distance;
+        assert!(closest > 0.0);
+        for _ in 0..200 {
+            orbit.apply_scroll(-5.0);
+        }
+        assert!(orbit.distance > closest && orbit.distance.is_finite());
+    }
+
+    #[test]
+    fn surface_view_settings_default_when_missing() {
+        // Settings saved before the 3D view existed load it off, at the default orbit
+        let camera: CameraState = serde_json::from_str(r#"{"zoom": 2.0}"#).unwrap();
+        assert!(!camera.surface_view);
+        assert_eq!(camera.orbit, OrbitCamera::default());
+        // Matches the std140 layout of CameraUniforms in render.wgsl
+        assert_eq!(std::mem::size_of::<CameraUniforms>(), 112);
+    }
+}
+
+#[cfg(test)]
+mod profile_tests {
+    //! Tests for cross-section line profile sampling.
+
+    use std::collections::BTreeMap;
+    use crate::profile::{sample_line_profile, ProfileLine};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn gradient_snapshot() -> BufferSnapshot {
+        // Mass increases linearly with x; resource decreases with y.
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.5; n],
+            genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
+            genome_b: vec![0.003; n],
+            resource: vec![0.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        for y in 0..DEFAULT_WORLD_SIZE[1] {
+            for x in 0..DEFAULT_WORLD_SIZE[0] {
+                let i = (y * DEFAULT_WORLD_SIZE[0] + x) as usize;
+                snap.mass[i] = x as f32 / DEFAULT_WORLD_SIZE[0] as f32;
+                snap.resource[i] = 1.0 - y as f32 / DEFAULT_WORLD_SIZE[1] as f32;
+            }
+        }
+        snap
+    }
+
+    #[test]
+    fn horizontal_profile_follows_mass_gradient() {
+        let snap = gradient_snapshot();
+        let line = ProfileLine { start: [0.0, 0.5], end: [1.0, 0.5] };
+        let samples = sample_line_profile(&snap, &line);
+
+        assert_eq!(samples.len(), DEFAULT_WORLD_SIZE[0] as usize + 1, "One sample per pixel step");
+        for pair in samples.windows(2) {
+            assert!(pair[1].mass >= pair[0].mass, "Mass should be non-decreasing along +x");
+            assert!(pair[1].distance > pair[0].distance, "Distance must increase monotonically");
+        }
+        let last = samples.last().unwrap();
+        assert!((last.distance - DEFAULT_WORLD_SIZE[0] as f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn out_of_world_endpoints_are_clamped() {
+        let snap = gradient_snapshot();
+        let line = ProfileLine { start: [-0.5, -0.5], end: [1.5, 1.5] };
+        let samples = sample_line_profile(&snap, &line);
+        assert!(!samples.is_empty());
+        assert!(samples[0].resource > 0.99, "Clamped start should land on the top edge");
+    }
+
+    #[test]
+    fn degenerate_line_yields_samples() {
+        let snap = gradient_snapshot();
+        let line = ProfileLine { start: [0.3, 0.3], end: [0.3, 0.3] };
+        let samples = sample_line_profile(&snap, &line);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].mass, samples[1].mass);
+    }
+}
+
+#[cfg(test)]
+mod display_tests {
+    //! Tests for display gain/contrast statistics and their persistence.
+
+    use crate::config::{DisplayAdjust, SimulationParams};
+    use crate::display::auto_range;
+
+    #[test]
+    fn auto_range_ignores_outliers_and_empty_space() {
+        let mut values = vec![0.0; 1000];
+        values.extend((0..1000).map(|i| 0.2 + 0.3 * i as f32 / 999.0));
+        values.extend([50.0; 5]); // a few saturated hot spots
+        let (lo, hi) = auto_range(&values, 1.0);
+        assert!((0.2..0.22).contains(&lo), "lo={}", lo);
+        assert!(hi < 1.0, "Outliers should not set the upper bound, hi={}", hi);
+        assert!(hi > lo);
+    }
+
+    #[test]
+    fn auto_range_defaults_when_nothing_alive() {
+        assert_eq!(auto_range(&[0.0; 64], 2.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn params_without_display_adjust_still_load() {
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("display_adjust");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        let d = DisplayAdjust::default();
+        assert_eq!(params.current_display_adjust().exposure, d.exposure);
+        assert_eq!(params.current_display_adjust().gamma, d.gamma);
+    }
+
+    #[test]
+    fn bundled_presets_still_load() {
+        for entry in std::fs::read_dir("presets").unwrap() {
+            let path = entry.unwrap().path();
+            let json = std::fs::read_to_string(&path).unwrap();
+            let params: SimulationParams = serde_json::from_str(&json)
+                .unwrap_or_else(|e| panic!("{:?} failed to load: {}", path, e));
+            assert!(!params.bloom.enabled, "Bloom should default to off for old presets");
+            assert!(!params.trail.enabled, "Trails should default to off for old presets");
+        }
+    }
+}
+
+#[cfg(test)]
+mod equalization_tests {
+    //! Tests for the histogram-equalization display lookup table.
+
+    use crate::config::DisplayAdjust;
+    use crate::display::{equalization_lut, identity_lut, EQUALIZE_BINS};
+
+    #[test]
+    fn lut_is_monotonic_and_normalized() {
+        // Heavy-tailed field: mostly sparse values plus a dense bloom
+        let mut values: Vec<f32> = (0..5000).map(|i| 0.01 + 0.05 * (i % 100) as f32 / 100.0).collect();
+        values.extend([0.95; 200]);
+        let lut = equalization_lut(&values, &DisplayAdjust::default());
+
+        assert_eq!(lut.len(), EQUALIZE_BINS);
+        for pair in lut.windows(2) {
+            assert!(pair[1] >= pair[0], "CDF must be non-decreasing");
+        }
+        assert_eq!(lut[0], 0.0, "Lowest occupied bin should map to black");
+        assert!((lut[EQUALIZE_BINS - 1] - 1.0).abs() < 1e-6);
+        // Sparse band (t < 0.06) should be spread over most of the output range
+        assert!(lut[(0.05 * (EQUALIZE_BINS - 1) as f32) as usize] > 0.7);
+    }
+
+    #[test]
+    fn empty_field_falls_back_to_identity() {
+        let lut = equalization_lut(&[0.0; 128], &DisplayAdjust::default());
+        assert_eq!(lut, identity_lut());
+    }
+}
+
+#[cfg(test)]
+mod settings_tests {
+    //! Tests for session settings persistence.
+
+    use crate::camera::CameraState;
+    use crate::dock::{sync_tab, LabTab};
+    use crate::i18n::Language;
+    use crate::lab::LabState;
+    use crate::pacing::BackgroundThrottle;
+    use crate::settings::{UiSettings, WindowGeometry};
+    use crate::theme::{ColorPalette, UiTheme};
+
+    #[test]
+    fn settings_roundtrip_restores_layout_and_camera() {
+        let mut lab = LabState {
+            ui_scale: 1.5,
+            background_throttle: BackgroundThrottle { enabled: true, steps_per_sec: 25 },
+            ui_theme: UiTheme::Light,
+            language: Language::French,
+            palette: ColorPalette::OkabeIto,
+            show_analysis_panel: true,
+            ..Default::default()
+        };
+        sync_tab(&mut lab.dock_state, LabTab::Analysis, true);
+        sync_tab(&mut lab.dock_state, LabTab::Logs, false);
+        let camera = CameraState { offset: [0.1, -0.2], zoom: 6.0, snap_zoom: true, ..Default::default() };
+        let window = WindowGeometry { width: 1920, height: 1080, position: Some([40, 30]), maximized: false };
+
+        let path = "/tmp/evolenia_test_settings.json";
+        UiSettings::capture(&lab, &camera, Some(window)).save(path).unwrap();
+        let loaded = UiSettings::load(path).expect("settings should load");
+
+        let mut lab2 = LabState::default();
+        let mut camera2 = CameraState::default();
+        loaded.apply(&mut lab2, &mut camera2);
+        assert_eq!(lab2.ui_scale, 1.5);
+        assert_eq!(lab2.ui_theme, UiTheme::Light);
+        assert_eq!(lab2.language, Language::French);
+        assert_eq!(lab2.palette, ColorPalette::OkabeIto);
+        assert!(lab2.show_analysis_panel);
+        assert_eq!(lab2.background_throttle, BackgroundThrottle { enabled: true, steps_per_sec: 25 });
+        assert!(lab2.dock_state.find_tab(&LabTab::Analysis).is_some());
+        assert!(lab2.dock_state.find_tab(&LabTab::Logs).is_none());
+        assert_eq!(camera2.offset, [0.1, -0.2]);
+        assert_eq!(camera2.zoom, 6.0);
+        assert!(camera2.snap_zoom);
+        assert_eq!(loaded.window, Some(window));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn partial_settings_fill_in_defaults() {
+        let settings: UiSettings = serde_json::from_str(r#"{"ui_scale": 2.0}"#).unwrap();
+        assert_eq!(settings.ui_scale, 2.0);
+        assert!(settings.show_lab_ui);
+        assert!(settings.window.is_none());
+        assert_eq!(settings.camera.zoom, 1.0);
+        assert!(!settings.background_throttle.enabled);
+        assert!(!settings.show_performance_panel);
+        assert!(!settings.show_resources_panel);
+    }
+
+    #[test]
+    fn legacy_panel_sizes_keep_default_dock() {
+        let settings: UiSettings =
+            serde_json::from_str(r#"{"left_panel_width": 400.0, "dock": null}"#).unwrap();
+        let mut lab = LabState::default();
+        settings.apply(&mut lab, &mut CameraState::default());
+        assert!(lab.dock_state.find_tab(&LabTab::Controls).is_some());
+        assert!(lab.dock_state.find_tab(&LabTab::Viewport).is_some());
+    }
+}
+
+#[cfg(test)]
+mod dock_tests {
+    //! Tests for the lab dock layout.
+
+    use crate::dock::{default_dock_state, sync_tab, LabTab};
+
+    #[test]
+    fn default_layout_has_core_tabs() {
+        let dock = default_dock_state();
+        for tab in [LabTab::Viewport, LabTab::Controls, LabTab::Logs] {
+            assert!(dock.find_tab(&tab).is_some(), "{:?} missing", tab);
+        }
+        assert!(dock.find_tab(&LabTab::Analysis).is_none());
+    }
+
+    #[test]
+    fn sync_tab_adds_and_removes_once() {
+        let mut dock = default_dock_state();
+        sync_tab(&mut dock, LabTab::Analysis, true);
+        sync_tab(&mut dock, LabTab::Analysis, true);
+        assert_eq!(dock.iter_all_tabs().filter(|(_, t)| **t == LabTab::Analysis).count(), 1);
+
+        sync_tab(&mut dock, LabTab::Analysis, false);
+        sync_tab(&mut dock, LabTab::Logs, false);
+        assert!(dock.find_tab(&LabTab::Analysis).is_none());
+        assert!(dock.find_tab(&LabTab::Logs).is_none());
+        assert!(dock.find_tab(&LabTab::Viewport).is_some());
+    }
+}
+
+#[cfg(test)]
+mod theme_tests {
+    //! Tests for UI themes and render palettes.
+
+    use crate::theme::{ColorPalette, PaletteUniforms, UiTheme};
+
+    /// CPU mirror of the hue wheel the render shader used before palettes.
+    fn hsv(h: f32, s: f32, v: f32) -> [f32; 3] {
+        let c = v * s;
+        let h6 = h * 6.0;
+        let x = c * (1.0 - ((h6 % 2.0) - 1.0).abs());
+        let m = v - c;
+        let rgb = match h6 as u32 {
+            0 => [c, x, 0.0],
+            1 => [x, c, 0.0],
+            2 => [0.0, c, x],
+            3 => [0.0, x, c],
+            4 => [x, 0.0, c],
+            _ => [c, 0.0, x],
+        };
+        [rgb[0] + m, rgb[1] + m, rgb[2] + m]
+    }
+
+    #[test]
+    fn classic_cyclic_stops_match_hsv_wheel() {
+        let pal = ColorPalette::Classic.uniforms();
+        for (i, stop) in pal.cyclic.iter().enumerate() {
+            let expected = hsv(i as f32 / 6.0, 0.8, 1.0);
+            for ch in 0..3 {
+                assert!((stop[ch] - expected[ch]).abs() < 1e-6, "stop {} channel {}", i, ch);
+            }
+        }
+    }
+
+    #[test]
+    fn palettes_are_valid_colors() {
+        // 21 vec4 slots, matching the WGSL struct
+        assert_eq!(std::mem::size_of::<PaletteUniforms>(), 21 * 16);
+        for palette in ColorPalette::all() {
+            let pal = palette.uniforms();
+            let floats: &[f32] = bytemuck::cast_slice(std::slice::from_ref(&pal));
+            assert!(floats.iter().all(|v| (0.0..=1.0).contains(v)), "{:?}", palette);
+        }
+        assert_ne!(ColorPalette::OkabeIto.uniforms().prey, ColorPalette::Classic.uniforms().prey);
+    }
+
+    #[test]
+    fn themes_map_to_egui_dark_mode() {
+        assert!(UiTheme::Dark.visuals().dark_mode);
+        assert!(!UiTheme::Light.visuals().dark_mode);
+    }
+}
+
+#[cfg(test)]
+mod i18n_tests {
+    //! Tests for UI translation bundles.
+
+    use std::collections::{BTreeSet, HashMap};
+
+    use crate::config::{visualization_mode_key, DisplayTransform, VIS_MODE_COUNT};
+    use crate::perturbation::PerturbationType;
+    use crate::i18n::{parse_bundle, substitute, tr, Language};
+    use crate::lab::EventSeverity;
+    use crate::resources::BufferKind;
+    use crate::theme::{ColorPalette, UiTheme};
+
+    fn bundle(lang: Language) -> HashMap<String, String> {
+        parse_bundle(lang.bundle_source()).expect("bundle should parse")
+    }
+
+    fn placeholders(text: &str) -> BTreeSet<String> {
+        text.split('{').skip(1).filter_map(|s| s.split_once('}').map(|(n, _)| n.to_string())).collect()
+    }
+
+    /// Keys passed as literals to `tr(` / `trf(` in a source file.
+    fn keys_used(source: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        for call in ["tr(", "trf("] {
+            for (i, _) in source.match_indices(call) {
+                let rest = source[i + call.len()..].trim_start();
+                if let Some(lit) = rest.strip_prefix('"') {
+                    keys.push(lit.split('"').next().unwrap().to_string());
+                }
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn bundles_share_keys_and_placeholders() {
+        let en = bundle(Language::English);
+        let fr = bundle(Language::French);
+        let en_keys: BTreeSet<_> = en.keys().collect();
+        let fr_keys: BTreeSet<_> = fr.keys().collect();
+        assert_eq!(en_keys, fr_keys);
+        for (key, text) in &en {
+            assert_eq!(placeholders(text), placeholders(&fr[key]), "placeholders differ for {}", key);
+        }
+    }
+
+    #[test]
+    fn every_used_key_is_translated() {
+        let en = bundle(Language::English);
+        let sources = [
+            include_str!("lab_ui.rs"),
+            include_str!("app.rs"),
+            include_str!("lab.rs"),
+            include_str!("renderer.rs"),
+            include_str!("dock.rs"),
+            include_str!("legend.rs"),
+            include_str!("viewer.rs"),
+        ];
+        let mut keys: Vec<String> = sources.iter().flat_map(|s| keys_used(s)).collect();
+        keys.extend((0..=VIS_MODE_COUNT).map(|m| visualization_mode_key(m).to_string()));
+        keys.extend(PerturbationType::all().iter().map(|p| p.label_key().to_string()));
+        keys.extend(DisplayTransform::all().iter().map(|t| t.label_key().to_string()));
+        keys.extend(UiTheme::all().iter().map(|t| t.label_key().to_string()));
+        keys.extend(ColorPalette::all().iter().map(|p| p.label_key().to_string()));
+        keys.extend(EventSeverity::all().iter().map(|s| s.label_key().to_string()));
+        keys.extend(BufferKind::all().iter().map(|k| k.label_key().to_string()));
+        assert!(keys.len() > 150);
+        for key in keys {
+            assert!(en.contains_key(&key), "missing translation key {}", key);
+        }
+    }
+
+    #[test]
+    fn lookup_per_language_and_substitution() {
+        // Bundles are checked directly: the active language is process-wide
+        // and other tests read it in parallel
+        let fr = bundle(Language::French);
+        assert_eq!(fr["control.restart"], "🔄 Redémarrer");
+        assert_eq!(substitute(&fr["exp.run_id"], &[("id", &"run_1")]), "ID d'exécution : run_1");
+        assert_eq!(bundle(Language::English)["control.restart"], "🔄 Restart");
+        assert_eq!(tr("no.such.key"), "no.such.key");
+    }
+}
+
+#[cfg(test)]
+mod event_log_tests {
+    //! Tests for event severities, log filtering, JSONL export, automatic alerts,
+    //! and the timeline markers drawn on the analysis plots.
+
+    use std::collections::BTreeMap;
+    use crate::lab::{events_near, EventFilter, EventSeverity, LabState, EVENTS_SCHEMA_VERSION};
+    use crate::metrics::SimDiagnostics;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn sample_lab() -> LabState {
+        let mut lab = LabState::default();
+        lab.log_event(10, "CONTROL", "Paused");
+        lab.log_event(20, "PARAM_CHANGE", "mutation_rate 1.0 → 2.0");
+        lab.log_event_with_severity(30, EventSeverity::Warn, "SNAPSHOT", "Failed: disk full");
+        lab.log_event_with_severity(40, EventSeverity::Alert, "EXTINCTION", "No live pixels remain");
+        lab
+    }
+
+    fn frames(lab: &LabState, filter: &EventFilter) -> Vec<u32> {
+        lab.events.iter().filter(|e| filter.matches(e)).map(|e| e.frame).collect()
+    }
+
+    #[test]
+    fn filter_by_severity_type_and_search() {
+        let lab = sample_lab();
+        assert!(!EventFilter::default().is_active());
+        assert_eq!(frames(&lab, &EventFilter::default()), vec![10, 20, 30, 40]);
+
+        let warn = EventFilter { min_severity: EventSeverity::Warn, ..Default::default() };
+        assert!(warn.is_active());
+        assert_eq!(frames(&lab, &warn), vec![30, 40]);
+
+        let control = EventFilter { event_type: Some("CONTROL".into()), ..Default::default() };
+        assert_eq!(frames(&lab, &control), vec![10]);
+
+        // Search is case-insensitive over type and details
+        let search = EventFilter { search: "  MUTATION ".into(), ..Default::default() };
+        assert_eq!(frames(&lab, &search), vec![20]);
+        let search = EventFilter { search: "extinct".into(), ..Default::default() };
+        assert_eq!(frames(&lab, &search), vec![40]);
+    }
+
+    #[test]
+    fn timeline_keeps_causes_and_alerts() {
+        let mut lab = sample_lab();
+        lab.log_event(50, "RESTART", "Simulation restarted");
+        lab.log_event(55, "PERTURBATION", "Explosion intensity=0.50");
+        let frames: Vec<u32> = lab.timeline_events().iter().map(|e| e.frame).collect();
+        // Pause and the failed snapshot are not causes; the extinction alert is
+        assert_eq!(frames, vec![20, 40, 50, 55]);
+
+        let timeline = lab.timeline_events();
+        let near: Vec<u32> = events_near(&timeline, 52.0, 3.0).iter().map(|e| e.frame).collect();
+        assert_eq!(near, vec![50, 55]);
+        assert!(events_near(&timeline, 30.0, 5.0).is_empty());
+
+        lab.show_event_markers = false;
+        assert!(lab.timeline_events().is_empty());
+    }
+
+    #[test]
+    fn log_line_includes_severity_tag() {
+        let lab = sample_lab();
+        assert!(lab.events[0].to_log_line().contains("frame=10 INFO CONTROL"));
+        assert!(lab.events[2].to_log_line().contains("frame=30 WARN SNAPSHOT"));
+    }
+
+    #[test]
+    fn jsonl_export_has_typed_records() {
+        let mut lab = sample_lab();
+        lab.log_param_change(50, "time_step", "0.25");
+        lab.log_param_change(60, "norm_enabled", "false");
+        lab.run_dir = std::path::PathBuf::from("/tmp/evolenia_test_events");
+        std::fs::create_dir_all(&lab.run_dir).unwrap();
+        let path = lab.export_events_jsonl().unwrap();
+
+        let text = std::fs::read_to_string(path).unwrap();
+        let records: Vec<serde_json::Value> =
+            text.lines().map(|l| serde_json::from_str(l).expect("each line is JSON")).collect();
+        assert_eq!(records.len(), lab.events.len());
+        for r in &records {
+            assert_eq!(r["schema"], EVENTS_SCHEMA_VERSION);
+            assert!(r["wall_time"].as_str().is_some_and(|t| chrono::DateTime::parse_from_rfc3339(t).is_ok()));
+        }
+        assert_eq!(records[2]["severity"], "warn");
+        assert_eq!(records[2]["type"], "SNAPSHOT");
+        assert_eq!(records[3]["frame"], 40);
+        assert!(records[0]["payload"].is_null());
+        assert_eq!(records[4]["details"], "time_step=0.25");
+        assert_eq!(records[4]["payload"]["param"], "time_step");
+        assert_eq!(records[4]["payload"]["value"].as_f64(), Some(0.25));
+        assert_eq!(records[5]["payload"]["value"], false);
+    }
+
+    #[test]
+    fn extinction_alert_fires_once() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.5; n],
+            energy: vec![0.5; n],
+            genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        let mut lab = LabState::default();
+        lab.record_metrics(&SimDiagnostics::from_snapshot(&snap), &snap, 1, 60.0);
+        snap.mass.iter_mut().for_each(|m| *m = 0.0);
+        let dead = SimDiagnostics::from_snapshot(&snap);
+        lab.record_metrics(&dead, &snap, 2, 60.0);
+        lab.record_metrics(&dead, &snap, 3, 60.0);
+
+        let alerts: Vec<_> = lab.events.iter().filter(|e| e.severity == EventSeverity::Alert).collect();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].event_type, "EXTINCTION");
+        assert_eq!(alerts[0].frame, 2);
+    }
+}
+
+#[cfg(test)]
+mod notify_tests {
+    //! Tests for the notification center and background job reporting.
+
+    use crate::lab::{EventSeverity, LabState};
+    use crate::notify::{NotificationCenter, HISTORY_LIMIT};
+
+    #[test]
+    fn job_progress_and_completion_from_worker_thread() {
+        let mut center = NotificationCenter::default();
+        let job = center.start_job("Export".into(), "EXPORT");
+        assert_eq!(center.jobs.len(), 1);
+        assert_eq!(center.jobs[0].progress, None);
+
+        let worker = std::thread::spawn(move || {
+            job.progress(0.5);
+            job.finish(Ok("Exported".into()));
+        });
+        worker.join().unwrap();
+
+        let finished = center.poll();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].result, Ok("Exported".into()));
+        assert!(center.jobs.is_empty());
+        let last = center.history.back().unwrap();
+        assert_eq!(last.severity, EventSeverity::Info);
+        assert_eq!(last.text, "Exported");
+        assert_eq!(center.toasts().count(), 1);
+        assert_eq!(center.unread, 1);
+    }
+
+    #[test]
+    fn dropped_job_reports_interrupted() {
+        let mut center = NotificationCenter::default();
+        drop(center.start_job("Snapshot".into(), "SNAPSHOT"));
+        let finished = center.poll();
+        assert!(finished[0].result.is_err());
+        let last = center.history.back().unwrap();
+        assert_eq!(last.severity, EventSeverity::Warn);
+        assert!(last.text.starts_with("Snapshot"));
+    }
+
+    #[test]
+    fn history_is_bounded_and_opening_clears_unread() {
+        let mut center = NotificationCenter::default();
+        for i in 0..HISTORY_LIMIT + 10 {
+            center.notify(EventSeverity::Info, format!("n{}", i));
+        }
+        assert_eq!(center.history.len(), HISTORY_LIMIT);
+        assert_eq!(center.history.front().unwrap().text, "n10");
+        center.open_history();
+        assert_eq!(center.unread, 0);
+        center.notify(EventSeverity::Info, "seen".into());
+        assert_eq!(center.unread, 0);
+    }
+
+    #[test]
+    fn failed_jobs_are_logged_as_warnings() {
+        let mut lab = LabState::default();
+        let job = lab.notifications.start_job("Screenshot".into(), "SCREENSHOT");
+        job.finish(Err("disk full".into()));
+        lab.poll_jobs(42);
+        let event = lab.events.last().unwrap();
+        assert_eq!(event.severity, EventSeverity::Warn);
+        assert_eq!(event.event_type, "SCREENSHOT");
+        assert_eq!(event.frame, 42);
+    }
+}
+
+#[cfg(test)]
+mod headless_progress_tests {
+    //! Tests for headless progress reporting, run summaries, the layout
+    //! benchmark, and CLI flags.
+
+    use crate::headless::{bench_report, BenchResult, HeadlessSummary, ProgressMode, ProgressSample};
+    use crate::world::{CellLayout, GridType, DEFAULT_WORLD_SIZE};
+    use crate::CliOptions;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("evolenia").chain(list.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn eta_from_average_rate() {
+        let sample = ProgressSample {
+            done: 2_500,
+            total: 10_000,
+            elapsed_secs: 5.0,
+            steps_per_sec: 500.0,
+            window_steps_per_sec: 400.0,
+        };
+        assert!((sample.eta_secs() - 15.0).abs() < 1e-9);
+        let stalled = ProgressSample { steps_per_sec: 0.0, ..sample };
+        assert_eq!(stalled.eta_secs(), 0.0);
+    }
+
+    #[test]
+    fn json_progress_line_is_machine_readable() {
+        let sample = ProgressSample {
+            done: 1_000,
+            total: 4_000,
+            elapsed_secs: 2.0,
+            steps_per_sec: 500.0,
+            window_steps_per_sec: 520.0,
+        };
+        let line = sample.json_line("progress");
+        assert!(!line.contains('\n'));
+        let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["event"], "progress");
+        assert_eq!(v["step"], 1_000);
+        assert_eq!(v["total"], 4_000);
+        assert_eq!(v["fraction"], 0.25);
+        assert_eq!(v["steps_per_sec"], 500.0);
+        assert_eq!(v["eta_secs"], 6.0);
+    }
+
+    #[test]
+    fn progress_flags_select_mode() {
+        assert_eq!(CliOptions::from_args(args(&["--headless"])).progress_mode, ProgressMode::Auto);
+        assert_eq!(CliOptions::from_args(args(&["--headless", "--quiet"])).progress_mode, ProgressMode::Quiet);
+        let cli = CliOptions::from_args(args(&["--headless", "--json-progress", "--progress-interval", "50"]));
+        assert_eq!(cli.progress_mode, ProgressMode::Json);
+        assert_eq!(cli.progress_interval, 50);
+    }
+
+    #[test]
+    fn interrupted_summary_reports_partial_run_and_save() {
+        let sample = ProgressSample {
+            done: 1_234,
+            total: 10_000,
+            elapsed_secs: 4.0,
+            steps_per_sec: 308.5,
+            window_steps_per_sec: 300.0,
+        };
+        let summary = HeadlessSummary {
+            interrupted: true,
+            snapshot_path: Some("/tmp/evo.snap".into()),
+            final_metrics: serde_json::json!({ "total_mass": 1500.0, "live_pixels": 42, "species": 3, "entropy": 2.5, "avg_energy": 0.4 }),
+        };
+        let text = summary.text(&sample);
+        assert!(text.starts_with("Headless run interrupted: 1234/10000 frames"));
+        assert!(text.contains("saved to /tmp/evo.snap"));
+        assert!(text.contains("live=42 | species=3"));
+
+        let finished = HeadlessSummary { interrupted: false, snapshot_path: None, final_metrics: serde_json::Value::Null };
+        assert_eq!(finished.text(&sample).lines().count(), 1);
+    }
+
+    #[test]
+    fn bench_subcommand_selects_layouts() {
+        let cli = CliOptions::from_args(args(&["bench", "--frames", "200"]));
+        assert!(cli.bench);
+        assert_eq!(cli.frames, 200);
+        assert_eq!(cli.layouts, CellLayout::all());
+        let cli = CliOptions::from_args(args(&["bench", "--layout", "morton"]));
+        assert_eq!(cli.layouts, [CellLayout::Morton]);
+        // Only recognized as the first argument
+        assert!(!CliOptions::from_args(args(&["--headless", "bench"])).bench);
+    }
+
+    #[test]
+    fn grid_flag_selects_lattice() {
+        assert_eq!(CliOptions::from_args(args(&["--headless"])).grid, GridType::Square);
+        assert_eq!(CliOptions::from_args(args(&["--headless", "--grid", "hex"])).grid, GridType::Hex);
+        assert_eq!(CliOptions::from_args(args(&["--grid", "octagon"])).grid, GridType::Square);
+    }
+
+    #[test]
+    fn world_flag_sets_the_size_and_filters_what_needs_it() {
+        assert_eq!(CliOptions::from_args(args(&["--headless"])).world_size, DEFAULT_WORLD_SIZE);
+        assert_eq!(CliOptions::from_args(args(&["--world", "1024x256"])).world_size, [1024, 256]);
+        assert_eq!(CliOptions::from_args(args(&["--world", "100x100"])).world_size, DEFAULT_WORLD_SIZE);
+        assert_eq!(CliOptions::from_args(args(&["--world", "big"])).world_size, DEFAULT_WORLD_SIZE);
+        // Checked against the final size, whatever the order of the flags
+        let cli = CliOptions::from_args(args(&["bench", "--layout", "morton", "--world", "1024x512"]));
+        assert!(cli.layouts.is_empty());
+        assert_eq!(CliOptions::from_args(args(&["bench", "--world", "1024x512"])).layouts, [CellLayout::RowMajor]);
+    }
+
+    #[test]
+    fn tensorboard_flag_sets_log_dir() {
+        assert_eq!(CliOptions::from_args(args(&["--headless"])).tensorboard_dir, None);
+        let cli = CliOptions::from_args(args(&["--headless", "--tensorboard", "tb/run1"]));
+        assert_eq!(cli.tensorboard_dir.as_deref(), Some("tb/run1"));
+    }
+
+    #[test]
+    fn metrics_addr_flag_enables_endpoint() {
+        assert_eq!(CliOptions::from_args(args(&["--headless"])).metrics_addr, None);
+        let cli = CliOptions::from_args(args(&["--headless", "--metrics-addr", "0.0.0.0:9898"]));
+        assert_eq!(cli.metrics_addr.as_deref(), Some("0.0.0.0:9898"));
+    }
+
+    #[test]
+    fn population_flags_set_path_and_frame() {
+        let cli = CliOptions::from_args(args(&["--headless"]));
+        assert_eq!((cli.population_path, cli.population_frame), (None, None));
+        let cli = CliOptions::from_args(args(&["--headless", "--population", "pop.parquet", "--population-frame", "500"]));
+        assert_eq!(cli.population_path.as_deref(), Some("pop.parquet"));
+        assert_eq!(cli.population_frame, Some(500));
+    }
+
+    #[test]
+    fn bench_report_compares_against_first_layout() {
+        let results = [
+            BenchResult { layout: CellLayout::RowMajor, frames: 1_000, elapsed_secs: 2.0 },
+            BenchResult { layout: CellLayout::Morton, frames: 1_000, elapsed_secs: 1.6 },
+        ];
+        assert_eq!(results[0].steps_per_sec(), 500.0);
+        assert_eq!(results[1].ms_per_step(), 1.6);
+        let report = bench_report(&results, DEFAULT_WORLD_SIZE);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(!lines[1].contains(" vs "));
+        assert!(lines[2].contains("+25.0% vs row-major"));
+    }
+}
+
+#[cfg(test)]
+mod cell_layout_tests {
+    //! Tests for Morton (Z-order) cell indexing of the flat per-pixel buffers.
+
+    use crate::world::{morton_decode, morton_encode, CellLayout, DEFAULT_WORLD_SIZE};
+
+    #[test]
+    fn morton_interleaves_coordinates() {
+        assert_eq!(morton_encode(0, 0), 0);
+        assert_eq!(morton_encode(1, 0), 1);
+        assert_eq!(morton_encode(0, 1), 2);
+        assert_eq!(morton_encode(3, 3), 15);
+        assert_eq!(morton_encode(4, 0), 16);
+        for (x, y) in [(0, 0), (5, 9), (511, 0), (300, 511), (65_535, 65_535)] {
+            assert_eq!(morton_decode(morton_encode(x, y)), (x, y));
+        }
+    }
+
+    #[test]
+    fn morton_needs_square_power_of_two_world() {
+        assert!(CellLayout::Morton.supported(512, 512));
+        assert!(!CellLayout::Morton.supported(512, 256));
+        assert!(!CellLayout::Morton.supported(500, 500));
+        assert!(CellLayout::RowMajor.supported(500, 300));
+        // The default square world can use Morton order; a non-square build cannot
+        assert_eq!(CellLayout::Morton.supported(DEFAULT_WORLD_SIZE[0], DEFAULT_WORLD_SIZE[1]), DEFAULT_WORLD_SIZE[0] == DEFAULT_WORLD_SIZE[1]);
+    }
+
+    #[test]
+    fn arrange_round_trips_and_is_a_permutation() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let row_major: Vec<f32> = (0..n).map(|i| i as f32).collect();
+        for &layout in CellLayout::all().iter().filter(|l| l.supported(DEFAULT_WORLD_SIZE[0], DEFAULT_WORLD_SIZE[1])) {
+            let arranged = layout.arrange(&row_major, DEFAULT_WORLD_SIZE[0]);
+            let (x, y) = (7, 3);
+            assert_eq!(arranged[layout.index(x, y, DEFAULT_WORLD_SIZE[0])], (y * DEFAULT_WORLD_SIZE[0] + x) as f32);
+            assert_eq!(layout.row_major(&arranged, DEFAULT_WORLD_SIZE[0]), row_major);
+            let mut sorted = arranged.clone();
+            sorted.sort_by(f32::total_cmp);
+            assert_eq!(sorted, row_major);
+        }
+    }
+
+    #[test]
+    fn layout_names_parse_back() {
+        for &layout in CellLayout::all() {
+            assert_eq!(CellLayout::parse(layout.name()), Some(layout));
+        }
+        assert_eq!(CellLayout::parse("hilbert"), None);
+    }
+}
+
+#[cfg(test)]
+mod step_budget_tests {
+    //! Tests for simulation pacing: step budget, background throttling, and frame statistics.
+
+    use std::time::{Duration, Instant};
+
+    use crate::config::{RenderCadenceSettings, SimulationParams, SpeedGuardSettings, StepBudgetSettings};
+    use crate::pacing::{BackgroundClock, FrameStats, FrameTiming, RenderClock, RenderDue, SpeedGuard, StepBudget};
+
+    /// Frame time of a fake GPU: fixed render/UI cost plus simulation steps.
+    fn simulate(settings: &StepBudgetSettings, step_ms: f32, gpu_timer: bool, frames: usize) -> (u32, f32) {
+        let mut budget = StepBudget::default();
+        let mut frame_ms = 5.0;
+        for _ in 0..frames {
+            let steps = budget.update(settings, frame_ms, gpu_timer.then_some(step_ms));
+            frame_ms = 5.0 + steps as f32 * step_ms;
+        }
+        (budget.steps(), frame_ms)
+    }
+
+    #[test]
+    fn converges_to_target_frame_time() {
+        let settings = StepBudgetSettings { enabled: true, target_fps: 30.0, max_steps: 500 };
+        for gpu_timer in [true, false] {
+            let (steps, frame_ms) = simulate(&settings, 0.5, gpu_timer, 400);
+            assert!((frame_ms - 33.3).abs() < 3.0, "gpu_timer={} frame_ms={} steps={}", gpu_timer, frame_ms, steps);
+        }
+    }
+
+    #[test]
+    fn respects_step_bounds() {
+        let capped = StepBudgetSettings { enabled: true, target_fps: 10.0, max_steps: 20 };
+        assert_eq!(simulate(&capped, 0.01, true, 200).0, 20);
+        // Even when one step blows the budget, the simulation still advances
+        let slow = StepBudgetSettings { enabled: true, target_fps: 60.0, max_steps: 200 };
+        assert_eq!(simulate(&slow, 50.0, true, 200).0, 1);
+    }
+
+    #[test]
+    fn params_without_budget_load_with_default() {
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("step_budget");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.step_budget, StepBudgetSettings::default());
+        assert!(!params.step_budget.enabled);
+    }
+
+    #[test]
+    fn background_clock_carries_fractional_steps() {
+        let start = Instant::now();
+        let mut clock = BackgroundClock::new(start);
+        // 10 steps/s: 50 ms owes half a step, the next 50 ms completes it
+        assert_eq!(clock.steps_due(start + Duration::from_millis(50), 10), 0);
+        assert_eq!(clock.steps_due(start + Duration::from_millis(100), 10), 1);
+        assert_eq!(clock.steps_due(start + Duration::from_millis(400), 10), 3);
+        // Long gaps (e.g. system sleep) are credited one second at most
+        assert_eq!(clock.steps_due(start + Duration::from_secs(60), 10), 10);
+        clock.reset(start + Duration::from_secs(61));
+        assert_eq!(clock.steps_due(start + Duration::from_secs(61), 10), 0);
+    }
+
+    #[test]
+    fn background_clock_wakes_at_most_every_20ms() {
+        let now = Instant::now();
+        let clock = BackgroundClock::new(now);
+        assert_eq!(clock.next_wake(now, 4), now + Duration::from_millis(250));
+        assert_eq!(clock.next_wake(now, 1000), now + Duration::from_millis(20));
+    }
+
+    #[test]
+    fn render_cadence_skips_frames_until_enough_steps() {
+        let start = Instant::now();
+        let every_10 = RenderCadenceSettings { every_n_steps: 10, max_fps: 0.0 };
+        let mut clock = RenderClock::new(start);
+        let soon = start + Duration::from_millis(5);
+        for _ in 0..3 {
+            assert_eq!(clock.due(&every_10, soon, true), RenderDue::Simulate);
+            clock.add_steps(3);
+        }
+        assert_eq!(clock.due(&every_10, soon, true), RenderDue::Simulate);
+        clock.add_steps(3);
+        assert_eq!(clock.due(&every_10, soon, true), RenderDue::Now);
+        clock.rendered(soon);
+        // Paused: nothing to count, render as usual; a slow simulation
+        // still refreshes the view
+        assert_eq!(clock.due(&every_10, soon, false), RenderDue::Now);
+        assert_eq!(clock.due(&every_10, soon + RenderClock::MAX_RENDER_GAP, true), RenderDue::Now);
+        // Default settings render every iteration
+        assert_eq!(clock.due(&RenderCadenceSettings::default(), soon, true), RenderDue::Now);
+    }
+
+    #[test]
+    fn render_cadence_caps_the_frame_rate() {
+        let start = Instant::now();
+        let capped = RenderCadenceSettings { every_n_steps: 1, max_fps: 20.0 };
+        let mut clock = RenderClock::new(start);
+        clock.add_steps(5);
+        let early = start + Duration::from_millis(20);
+        assert_eq!(clock.due(&capped, early, true), RenderDue::Simulate);
+        // Paused: sleep until the next frame instead of spinning
+        assert_eq!(clock.due(&capped, early, false), RenderDue::WaitUntil(start + Duration::from_millis(50)));
+        assert_eq!(clock.due(&capped, start + Duration::from_millis(50), true), RenderDue::Now);
+        // Params saved before the setting load with every frame rendered
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("render_cadence");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.render_cadence, RenderCadenceSettings { every_n_steps: 1, max_fps: 0.0 });
+    }
+
+    /// Run `turns` loop turns of a fake GPU: fixed UI cost plus the steps run.
+    fn guarded(guard: &mut SpeedGuard, settings: &SpeedGuardSettings, step_ms: f32, speed: u32, turns: usize) {
+        for _ in 0..turns {
+            let turn_ms = 5.0 + guard.steps(speed) as f32 * step_ms;
+            guard.observe(settings, turn_ms, speed);
+        }
+    }
+
+    #[test]
+    fn speed_guard_holds_slow_frames_under_the_target() {
+        let settings = SpeedGuardSettings::default();
+        let mut guard = SpeedGuard::default();
+        // 20 steps of 4 ms: 85 ms frames
+        guarded(&mut guard, &settings, 4.0, 20, 300);
+        let steps = guard.limited(20).expect("speed should be capped");
+        assert!((5.0 + steps as f32 * 4.0) <= settings.max_frame_ms, "{} steps", steps);
+        // The GPU gets faster: the cap grows back to the requested speed
+        guarded(&mut guard, &settings, 0.5, 20, 1000);
+        assert_eq!(guard.limited(20), None);
+        assert_eq!(guard.steps(20), 20);
+    }
+
+    #[test]
+    fn speed_guard_never_stops_the_simulation_or_exceeds_the_speed() {
+        let settings = SpeedGuardSettings::default();
+        let mut guard = SpeedGuard::default();
+        guarded(&mut guard, &settings, 200.0, 10, 200);
+        assert_eq!(guard.steps(10), 1);
+        // A lower requested speed is never raised by the cap
+        assert_eq!(guard.steps(1), 1);
+        guarded(&mut guard, &SpeedGuardSettings { enabled: false, ..settings }, 200.0, 10, 1);
+        assert_eq!(guard.steps(10), 10);
+        // On by default, also for params saved before the setting
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("speed_guard");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.speed_guard, SpeedGuardSettings { enabled: true, max_frame_ms: 33.0 });
+    }
+
+    #[test]
+    fn frame_stats_percentiles_over_rolling_window() {
+        let mut stats = FrameStats::new(100);
+        assert!(stats.frame_percentiles().is_none());
+        // 150 frames: the first 50 (slow) fall out of the window
+        for i in 0..150 {
+            let frame_ms = if i < 50 { 1000.0 } else { (i - 49) as f32 };
+            stats.push(FrameTiming { frame_ms, readback_ms: if i % 10 == 0 { 5.0 } else { 0.5 }, ..Default::default() });
+        }
+        assert_eq!(stats.samples().len(), 100);
+        let pct = stats.frame_percentiles().unwrap();
+        assert_eq!((pct.p50, pct.p95, pct.p99, pct.max), (50.0, 95.0, 99.0, 100.0));
+        assert_eq!(stats.readback_stalls(), 10);
+        assert_eq!(stats.mean(|t| Some(t.frame_ms)), Some(50.5));
+        // GPU time is only averaged over frames that measured it
+        assert_eq!(stats.mean(|t| t.gpu_sim_ms), None);
+        stats.push(FrameTiming { gpu_sim_ms: Some(4.0), ..Default::default() });
+        assert_eq!(stats.mean(|t| t.gpu_sim_ms), Some(4.0));
+    }
+}
+
+#[cfg(test)]
+mod resources_tests {
+    //! Tests for GPU buffer accounting and limit warnings.
+
+    use crate::lab::EventSeverity;
+    use crate::resources::{format_bytes, BufferKind, MemoryReport, TILE_HALO};
+
+    const MIB: u64 = 1024 * 1024;
+
+    /// Report checked against a 128 MiB binding / 256 MiB buffer limit.
+    fn report(width: u32, height: u32) -> MemoryReport {
+        let base = MemoryReport {
+            width: 0,
+            height: 0,
+            buffers: Vec::new(),
+            max_storage_binding: 128 * MIB,
+            max_buffer_size: 256 * MIB,
+            history_bytes: 0,
+            events_bytes: 0,
+        };
+        base.resized(width, height)
+    }
+
+    #[test]
+    fn per_pixel_layout_totals() {
+        let r = report(512, 512);
+        let pixel = 512 * 512 * 4;
+        // mass, energy, genome_b ping-pong + genome_a vec4 ping-pong + resource, velocity (vec2), trail
+        // + separable scratch (three vec4 terms, one vec4 potential) + diff reference mass
+        // + territory overlay (RGBA8) + flow field (vec2) + reserve mask + field averages (three planes)
+        // + species map (with its staging copy)
+        assert_eq!(r.total(BufferKind::Storage), pixel * (2 + 2 + 2 + 8 + 1 + 2 + 1 + 12 + 4 + 1 + 1 + 2 + 1 + 3 + 1));
+        assert_eq!(r.total(BufferKind::Staging), pixel * 9);
+        assert_eq!(r.total(BufferKind::Uniform), 0);
+        assert_eq!(r.gpu_total(), pixel * 52);
+        assert_eq!(r.largest_buffer().unwrap().bytes, pixel * 4); // vec4 fields and the genome_a staging copy
+        assert!(r.warnings().is_empty());
+    }
+
+    #[test]
+    fn warns_near_and_over_limits() {
+        // 2048²: genome_a is 64 MiB, half the binding limit
+        assert!(report(2048, 2048).warnings().is_empty());
+        // 2896²: genome_a ≈ 128 MiB × 0.9998 is near the binding limit
+        let near = report(2896, 2896).warnings();
+        let names: Vec<_> = near.iter().map(|w| w.buffer).collect();
+        assert_eq!(names, ["genome_a", "conv_terms", "conv_potential"]);
+        assert!(near.iter().all(|w| w.severity() == EventSeverity::Warn));
+        // 4096²: genome_a (256 MiB) no longer binds; the staging copy hits the buffer size limit
+        let over = report(4096, 4096).warnings();
+        assert!(over.iter().any(|w| w.buffer == "genome_a" && w.severity() == EventSeverity::Alert));
+        assert!(over.iter().any(|w| w.buffer == "staging_genome_a" && w.limit_key == "resources.limit_buffer"));
+    }
+
+    #[test]
+    fn tile_plan_splits_oversized_worlds_into_row_bands() {
+        let fits = report(2048, 2048).tile_plan().unwrap();
+        assert_eq!((fits.tiles, fits.rows, fits.halo), (1, 2048, 0));
+
+        // 4096²: a vec4 row is 64 KiB, so 2048 rows bind; minus halos, three bands
+        let r = report(4096, 4096);
+        let plan = r.tile_plan().unwrap();
+        assert_eq!((plan.tiles, plan.halo), (3, TILE_HALO));
+        assert!(plan.tiles * plan.rows >= 4096 && (plan.tiles - 1) * plan.rows < 4096);
+        assert_eq!(plan.tile_bytes, (plan.rows + 2 * TILE_HALO) as u64 * 4096 * 16);
+        assert!(plan.tile_bytes <= r.max_storage_binding);
+
+        // Not even one row and its halos fit
+        let tiny = MemoryReport { max_storage_binding: 4096 * 16 * 2 * TILE_HALO as u64, ..report(4096, 4096) };
+        assert_eq!(tiny.tile_plan(), None);
+    }
+
+    #[test]
+    fn formats_binary_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(64 * MIB), "64.0 MiB");
+        assert_eq!(format_bytes(3 * 1024 * MIB), "3.0 GiB");
+    }
+}
+
+#[cfg(test)]
+mod resize_tests {
+    //! Tests for runtime world resizes: what is refused, and how particles
+    //! follow the new size.
+
+    use crate::particles::Particle;
+    use crate::resize::{check_resize, check_startup_size, scale_particles};
+    use crate::resources::MemoryReport;
+    use crate::world::{CellLayout, GridType};
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn report() -> MemoryReport {
+        MemoryReport {
+            width: 0,
+            height: 0,
+            buffers: Vec::new(),
+            max_storage_binding: 128 * MIB,
+            max_buffer_size: 256 * MIB,
+            history_bytes: 0,
+            events_bytes: 0,
+        }
+        .resized(512, 512)
+    }
+
+    #[test]
+    fn resizes_are_checked_against_the_world_and_the_device() {
+        let check = |size, layout, grid| check_resize(size, layout, grid, &report(), 8192);
+        assert!(check([1024, 1024], CellLayout::RowMajor, GridType::Square).is_ok());
+        assert!(check([1024, 512], CellLayout::RowMajor, GridType::Square).is_ok());
+        assert!(check([96, 96], CellLayout::RowMajor, GridType::Square).is_err());
+        // Morton order needs a square, power-of-two world
+        assert!(check([1024, 512], CellLayout::Morton, GridType::Square).is_err());
+        assert!(check([1024, 1024], CellLayout::Morton, GridType::Square).is_ok());
+        // vec4 fields of 8192² exceed a 128 MiB binding
+        assert!(check([8192, 8192], CellLayout::RowMajor, GridType::Square).unwrap_err().contains("GPU limit"));
+        assert!(check_resize([2048, 2048], CellLayout::RowMajor, GridType::Square, &report(), 1024).is_err());
+    }
+
+    #[test]
+    fn startup_sizes_are_checked_against_the_device() {
+        let limits = wgpu::Limits::default();
+        assert!(check_startup_size([512, 512], GridType::Square, &limits).is_ok());
+        assert!(check_startup_size([8192, 8192], GridType::Square, &limits).unwrap_err().contains("GPU limit"));
+        // Field textures wider than the adapter allows
+        let small_textures = wgpu::Limits { max_texture_dimension_2d: 1024, ..limits };
+        assert!(check_startup_size([2048, 1024], GridType::Square, &small_textures).is_err());
+    }
+
+    #[test]
+    fn particles_scale_with_the_world() {
+        let at = |x, y| Particle { pos: [x, y], ..Default::default() };
+        let mut particles = vec![at(0.0, 0.0), at(256.0, 128.0), at(511.9, 511.9)];
+        scale_particles(&mut particles, [512, 512], [1024, 256]);
+        assert_eq!(particles[0].pos, [0.0, 0.0]);
+        assert_eq!(particles[1].pos, [512.0, 64.0]);
+        assert!(particles[2].pos[0] < 1024.0 && particles[2].pos[1] < 256.0);
+    }
+}
+
+#[cfg(test)]
+mod kernel_tests {
+    //! Tests for the separable approximation of the Lenia kernel tiers.
+
+    use crate::config::{ConvolutionSettings, SimulationParams};
+    use crate::kernel::{
+        separable_terms, separable_weight, tier_weight, window, SeparableKernelUniform, KERNEL_TAPS, TIER_RADII,
+    };
+
+    #[test]
+    fn low_rank_terms_reproduce_every_tier() {
+        for tier in 0..TIER_RADII.len() {
+            let terms = separable_terms(tier);
+            let peak = window().map(|(dx, dy)| tier_weight(tier, dx, dy)).fold(0.0, f32::max);
+            let worst = window()
+                .map(|(dx, dy)| (separable_weight(&terms, dx, dy) - tier_weight(tier, dx, dy)).abs())
+                .fold(0.0, f32::max);
+            // the xlarge ring is clipped at max_r; its circular edge is not low-rank
+            let tolerance = if tier == 3 { 0.04 } else { 0.01 };
+            assert!(worst < peak * tolerance, "tier {tier}: error {worst} vs peak {peak}");
+        }
+    }
+
+    #[test]
+    fn separable_taps_are_orthonormal() {
+        let terms = separable_terms(1);
+        for a in &terms {
+            for b in &terms {
+                let dot: f32 = (0..KERNEL_TAPS).map(|i| a.taps[i] * b.taps[i]).sum();
+                let expected = if std::ptr::eq(a, b) { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-4, "dot {dot}");
+            }
+        }
+    }
+
+    #[test]
+    fn uniform_sums_match_exact_tier_totals() {
+        let uniform = SeparableKernelUniform::compute();
+        for tier in 0..TIER_RADII.len() {
+            let exact: f32 = window().map(|(dx, dy)| tier_weight(tier, dx, dy)).sum();
+            let approx = uniform.sums[tier];
+            assert!(approx > 0.0);
+            assert!((approx - exact).abs() < exact * 0.001, "tier {tier}: {approx} vs {exact}");
+        }
+    }
+
+    #[test]
+    fn settings_select_shader_mode() {
+        let mode = |separable, compare_exact| ConvolutionSettings { separable, compare_exact, ..Default::default() }.shader_mode();
+        assert_eq!(mode(false, false), 0);
+        assert_eq!(mode(false, true), 0); // comparison needs the separable potential
+        assert_eq!(mode(true, false), 1);
+        assert_eq!(mode(true, true), 2);
+
+        // Older settings files without the section keep the exact convolution
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("convolution");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.convolution, ConvolutionSettings::default());
+    }
+}
+
+#[cfg(test)]
+mod kernel_lut_tests {
+    //! Tests for the exact-convolution kernel lookup table.
+
+    use crate::config::ConvolutionSettings;
+    use crate::kernel::{
+        blended_weight, kernel_lut, lut_bin_radius, window, KERNEL_LUT_MAX_BINS, KERNEL_LUT_STRIDE, KERNEL_MAX_D2,
+    };
+
+    #[test]
+    fn bins_hold_the_blended_kernel() {
+        let bins = 16;
+        let lut = kernel_lut(bins);
+        assert_eq!(lut.len(), bins as usize * KERNEL_LUT_STRIDE);
+        for (bin, row) in (0..bins).zip(lut.chunks_exact(KERNEL_LUT_STRIDE)) {
+            let r = lut_bin_radius(bin, bins);
+            assert_eq!(row[0], 0.0); // the center cell is never sampled
+            for (dx, dy) in [(1, 0), (3, 4), (5, 5), (12, 5)] {
+                let d2 = (dx * dx + dy * dy) as usize;
+                let w = blended_weight((d2 as f32).sqrt(), r);
+                let expected = if w > 0.001 { w } else { 0.0 };
+                assert_eq!(row[d2], expected, "r {r} d² {d2}");
+            }
+            let sum: f32 = window()
+                .map(|(dx, dy)| (dx * dx + dy * dy) as usize)
+                .filter(|&d2| d2 <= KERNEL_MAX_D2)
+                .map(|d2| row[d2])
+                .sum();
+            assert_eq!(row[KERNEL_LUT_STRIDE - 1], sum);
+            assert!(sum > 0.0);
+        }
+    }
+
+    #[test]
+    fn bins_span_the_genome_radius_range() {
+        assert_eq!(lut_bin_radius(0, 64), 3.0);
+        assert_eq!(lut_bin_radius(63, 64), 15.0);
+        // Tier boundaries blend continuously
+        for boundary in [6.0, 10.0] {
+            for d in [2.0, 5.0, 9.0] {
+                let below = blended_weight(d, boundary - 1e-4);
+                let above = blended_weight(d, boundary + 1e-4);
+                assert!((below - above).abs() < 1e-3, "r {boundary} d {d}: {below} vs {above}");
+            }
+        }
+    }
+
+    #[test]
+    fn settings_select_lut_bins() {
+        let mut conv = ConvolutionSettings::default();
+        assert_eq!(conv.kernel_lut_bins(), 64);
+        conv.lut_bins = 100_000;
+        assert_eq!(conv.kernel_lut_bins(), KERNEL_LUT_MAX_BINS);
+        conv.kernel_lut = false;
+        assert_eq!(conv.kernel_lut_bins(), 0);
+
+        // Settings saved before the LUT existed turn it on with the default bins
+        let old: ConvolutionSettings = serde_json::from_str(r#"{"separable":true,"compare_exact":false}"#).unwrap();
+        assert!(old.separable && old.kernel_lut);
+        assert_eq!(old.lut_bins, 64);
+    }
+}
+
+#[cfg(test)]
+mod specialization_tests {
+    //! Tests for the pipeline-override constants of the compute shaders.
+
+    use crate::pipeline::specialization_constants;
+    use crate::world::{WORKGROUP_LINEAR, WORKGROUP_X, WORKGROUP_Y};
+
+    #[test]
+    fn constants_cover_every_declared_override() {
+        let constants = specialization_constants(640, 480);
+        let declared: Vec<&str> = include_str!("shaders/specialization.wgsl")
+            .lines()
+            .filter_map(|line| line.strip_prefix("override "))
+            .filter_map(|decl| decl.split(':').next())
+            .collect();
+        assert_eq!(declared.len(), constants.len());
+        for name in declared {
+            assert!(constants.contains_key(name), "missing override {name}");
+        }
+        assert_eq!(constants["WORLD_WIDTH"], 640.0);
+        assert_eq!(constants["WORLD_HEIGHT"], 480.0);
+        assert_eq!(constants["WORKGROUP_X"], WORKGROUP_X as f64);
+        assert_eq!(constants["WORKGROUP_Y"], WORKGROUP_Y as f64);
+        assert_eq!(constants["WORKGROUP_LINEAR"], WORKGROUP_LINEAR as f64);
+    }
+}
+
+#[cfg(test)]
+mod normalization_cadence_tests {
+    //! Tests for the scheduling of the mass sum/normalize passes.
+
+    use crate::config::{NormalizationCadence, SimulationParams, MAX_SUM_INTERVAL};
+    use crate::world::{plan_normalization, NormalizeStep};
+
+    fn params(sum_interval: u32, drift_threshold: f32) -> SimulationParams {
+        SimulationParams {
+            normalization_cadence: NormalizationCadence { sum_interval, drift_threshold },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_cadence_measures_every_step() {
+        let p = SimulationParams::default();
+        assert_eq!(plan_normalization(&p, 0, None, None), NormalizeStep::Measure);
+        assert_eq!(plan_normalization(&p, 8, Some(7), Some(0.2)), NormalizeStep::Measure);
+    }
+
+    #[test]
+    fn disabled_normalization_skips_both_passes() {
+        let p = SimulationParams { mass_normalization_enabled: false, ..Default::default() };
+        assert_eq!(plan_normalization(&p, 5, None, None), NormalizeStep::Skip);
+    }
+
+    #[test]
+    fn reduced_cadence_estimates_between_sums() {
+        let p = params(4, 0.0);
+        let plan: Vec<_> = (10..15).map(|frame| plan_normalization(&p, frame, Some(10), None)).collect();
+        use NormalizeStep::*;
+        assert_eq!(plan[1..], [Estimate, Estimate, Estimate, Measure]);
+        // No usable sum (first step, after a skip, or a rewound frame) measures
+        assert_eq!(plan_normalization(&p, 11, None, None), Measure);
+        assert_eq!(plan_normalization(&p, 3, Some(10), None), Measure);
+        // The interval is capped
+        let p = params(1000, 0.0);
+        assert_eq!(plan_normalization(&p, MAX_SUM_INTERVAL, Some(0), None), Measure);
+    }
+
+    #[test]
+    fn small_drift_skips_until_it_grows() {
+        let p = params(1, 0.01);
+        assert_eq!(plan_normalization(&p, 5, Some(4), Some(0.004)), NormalizeStep::Skip);
+        assert_eq!(plan_normalization(&p, 5, None, Some(0.02)), NormalizeStep::Measure);
+        // Not yet measured: normalize
+        assert_eq!(plan_normalization(&p, 5, None, None), NormalizeStep::Measure);
+    }
+}
+
+#[cfg(test)]
+mod velocity_fusion_tests {
+    //! Tests for the velocity field computed inside the evolution pass.
+
+    use crate::config::SimulationParams;
+
+    #[test]
+    fn both_passes_share_one_velocity_rule() {
+        let rule = include_str!("shaders/velocity_field.wgsl");
+        assert!(rule.contains("fn advection_velocity("));
+        for shader in [include_str!("shaders/compute_velocity.wgsl"), include_str!("shaders/compute_evolution.wgsl")] {
+            assert!(shader.contains("advection_velocity("));
+            assert!(!shader.contains("fn advection_velocity("));
+        }
+    }
+
+    #[test]
+    fn params_without_flag_use_fused_pass() {
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("separate_velocity_pass");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        assert!(!params.separate_velocity_pass);
+    }
+}
+
+#[cfg(test)]
+mod anisotropy_tests {
+    //! Tests for the horizontal / vertical transport anisotropy.
+
+    use crate::config::{SimulationParams, ANISOTROPY_RANGE};
+    use crate::world::{ResourceParams, SimParams, VelocityParams};
+
+    #[test]
+    fn params_without_anisotropy_are_isotropic() {
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("anisotropy");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        assert_eq!(params.anisotropy, 1.0);
+        assert_eq!(params.transport_anisotropy(), 1.0);
+    }
+
+    #[test]
+    fn anisotropy_is_clamped_for_stable_diffusion() {
+        let mut params = SimulationParams { anisotropy: 100.0, ..Default::default() };
+        assert_eq!(params.transport_anisotropy(), ANISOTROPY_RANGE.1);
+        params.anisotropy = 0.0;
+        assert_eq!(params.transport_anisotropy(), ANISOTROPY_RANGE.0);
+        // Explicit diffusion: D · (√a + 1/√a) / 2 ≤ 1 at the slider maximum D = 0.5
+        let s = ANISOTROPY_RANGE.1.sqrt();
+        assert!(0.5 * (s + 1.0 / s) / 2.0 <= 1.0);
+    }
+
+    #[test]
+    fn anisotropy_takes_a_padding_slot() {
+        // Uniform sizes are unchanged, so WGSL structs stay 16-byte aligned
+        // (both have since grown a row for the flow field and the rotation,
+        // and SimParams one for the carrying capacity and four for mutations)
+        assert_eq!(std::mem::size_of::<SimParams>(), 160);
+        assert_eq!(std::mem::size_of::<VelocityParams>(), 32);
+        assert_eq!(std::mem::size_of::<ResourceParams>(), 32);
+        for shader in [
+            include_str!("shaders/compute_velocity.wgsl"),
+            include_str!("shaders/compute_evolution.wgsl"),
+            include_str!("shaders/compute_resources.wgsl"),
+        ] {
+            assert!(shader.contains("anisotropy: f32"));
+            assert!(shader.contains("params.anisotropy"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod perturbation_tests {
+    //! Tests for the perturbation types, the pass uniform and the
+    //! continuous mode.
+
+    use std::collections::HashSet;
+
+    use crate::config::{SimulationParams, CONTINUOUS_PERTURBATION_STEPS};
+    use crate::perturbation::{with_perturbations, PerturbationType};
+    use crate::world::{DEFAULT_WORLD_SIZE, PerturbationParams};
+
+    #[test]
+    fn every_type_with_wgsl_is_dispatched() {
+        let shader = with_perturbations(include_str!("shaders/compute_perturbation.wgsl"));
+        assert_eq!(shader.matches("fn apply_perturbation(").count(), 1);
+        for kind in PerturbationType::all() {
+            let case = format!("case {}u: {{ perturb_{}(i, dx, dy); }}", kind.shader_kind(), kind.shader_kind());
+            assert_eq!(shader.contains(&case), kind.plugin().wgsl().is_some(), "{}", kind.name());
+        }
+        assert_eq!(PerturbationType::None.shader_kind(), 0);
+        assert!(PerturbationType::None.plugin().wgsl().is_none());
+    }
+
+    #[test]
+    fn kind_codes_are_distinct() {
+        let codes: HashSet<u32> = PerturbationType::all().iter().map(|k| k.shader_kind()).collect();
+        assert_eq!(codes.len(), PerturbationType::all().len());
+        assert_eq!(PerturbationType::Barrier.shader_kind(), 5);
+    }
+
+    #[test]
+    fn types_keep_their_preset_names() {
+        // Presets store the variant name; the English name is for logs
+        assert_eq!(serde_json::to_string(&PerturbationType::NutrientPulse).unwrap(), "\"NutrientPulse\"");
+        assert_eq!(PerturbationType::NutrientPulse.name(), "Nutrient Pulse");
+        assert!(PerturbationType::Barrier.plugin().barrier());
+        assert!(!PerturbationType::Drought.plugin().barrier());
+    }
+
+    #[test]
+    fn uniform_is_in_pixels() {
+        let params = SimulationParams {
+            perturbation_type: PerturbationType::MassStorm,
+            perturbation_center_x: 0.25,
+            perturbation_center_y: 0.5,
+            perturbation_radius: 0.1,
+            ..Default::default()
+        };
+        let u = PerturbationParams::new(&params, 0.3, DEFAULT_WORLD_SIZE);
+        assert_eq!(std::mem::size_of::<PerturbationParams>() % 16, 0);
+        assert_eq!(u.kind, 3);
+        assert_eq!(u.center_x, 0.25 * DEFAULT_WORLD_SIZE[0] as f32);
+        assert_eq!(u.center_y, 0.5 * DEFAULT_WORLD_SIZE[1] as f32);
+        assert_eq!(u.radius, 0.1 * DEFAULT_WORLD_SIZE[0] as f32);
+        assert_eq!(u.intensity, 0.3);
+        assert_eq!(u.extra, [0.0; 3]);
+        // A barrier application is its walls at the configured column
+        let walls = PerturbationParams::new(&SimulationParams { perturbation_type: PerturbationType::Barrier, ..params }, 0.3, DEFAULT_WORLD_SIZE);
+        assert_eq!(walls, PerturbationParams::barrier(0.25 * DEFAULT_WORLD_SIZE[0] as f32));
+    }
+
+    #[test]
+    fn continuous_spreads_intensity_over_steps() {
+        let mut params = SimulationParams { perturbation_type: PerturbationType::Drought, ..Default::default() };
+        assert_eq!(params.continuous_perturbation_intensity(), None);
+        params.perturbation_continuous = true;
+        let per_step = params.continuous_perturbation_intensity().unwrap();
+        assert!((per_step * CONTINUOUS_PERTURBATION_STEPS - params.perturbation_intensity).abs() < 1e-6);
+        params.perturbation_type = PerturbationType::None;
+        assert_eq!(params.continuous_perturbation_intensity(), None);
+    }
+}
+
+#[cfg(test)]
+mod pattern_tests {
+    //! Tests for Lenia pattern import/export (RLE, parameter mapping, roundtrip).
+
+    use std::collections::BTreeMap;
+    use crate::pattern::{decode_rle, encode_rle, parse_pattern, Pattern};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn empty_snapshot() -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.5; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.0; n],
+            resource: vec![0.5; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    fn lenia(params: &str, cells: &str) -> String {
+        format!(r#"{{"name":"Test","code":"T1","params":{},"cells":{}}}"#, params, cells)
+    }
+
+    #[test]
+    fn rle_decodes_lenia_states() {
+        // Runs, '.'/'o', one- and two-letter levels, multi-row '$'
+        let (w, h, cells) = decode_rle("2.A$pA yO 2$o!").unwrap();
+        assert_eq!((w, h), (3, 4));
+        let level = |l: f32| l / 255.0;
+        assert_eq!(cells[..3], [0.0, 0.0, level(1.0)]);
+        assert_eq!(cells[3..6], [level(25.0), 1.0, 0.0]);
+        assert_eq!(cells[6..9], [0.0; 3]);
+        assert_eq!(cells[9..], [1.0, 0.0, 0.0]);
+        assert!(decode_rle("2A$3A%A!").is_err());
+        assert!(decode_rle("pZ!").is_err());
+    }
+
+    #[test]
+    fn rle_roundtrip_quantizes_to_255_levels() {
+        let cells: Vec<f32> = (0..60).map(|i| if i % 7 == 0 { 0.0 } else { (i as f32 * 0.37) % 1.0 }).collect();
+        let rle = encode_rle(10, &cells);
+        let (w, h, decoded) = decode_rle(&rle).unwrap();
+        assert_eq!((w, h), (10, 6));
+        for (a, b) in cells.iter().zip(&decoded) {
+            assert!((a - b).abs() <= 0.5 / 255.0 + 1e-6, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn lenia_parameters_map_to_genome() {
+        let json = lenia(r#"{"R":13,"T":10,"b":"1","m":0.15,"s":0.015,"kn":1,"gn":1}"#, r#""2.A$.yO!""#);
+        let (pattern, notes) = parse_pattern(&json).unwrap();
+        assert_eq!(pattern.name, "Test");
+        assert_eq!((pattern.width, pattern.height), (3, 2));
+        assert_eq!(pattern.genome[0], 13.0);
+        assert_eq!(pattern.genome[1], 0.15);
+        assert!((pattern.genome[2] - 0.015 * 1.5 / 2f32.sqrt()).abs() < 1e-6);
+        assert_eq!(notes.len(), 1, "{:?}", notes); // polynomial growth only
+
+        // Gaussian growth with a matching time scale maps exactly; grids load too
+        let json = lenia(r#"{"R":10,"T":10,"m":0.2,"s":0.02,"kn":2,"gn":2}"#, "[[0, 0.5], [1]]");
+        let (pattern, notes) = parse_pattern(&json).unwrap();
+        assert!(notes.is_empty(), "{:?}", notes);
+        assert_eq!(pattern.cells, [0.0, 0.5, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn unmappable_patterns_are_rejected_or_noted() {
+        let multi_ring = lenia(r#"{"R":13,"b":"1,1/2","m":0.15,"s":0.015}"#, r#""A!""#);
+        assert!(parse_pattern(&multi_ring).unwrap_err().contains("multi-ring"));
+
+        // Radius beyond the genome range: cells shrink with it
+        let json = lenia(r#"{"R":30,"T":5,"m":0.5,"s":0.015,"kn":2,"gn":2}"#, r#""10o$10o!""#);
+        let (pattern, notes) = parse_pattern(&json).unwrap();
+        assert_eq!(pattern.genome[0], 15.0);
+        assert_eq!(pattern.genome[1], 0.35);
+        assert_eq!((pattern.width, pattern.height), (5, 1));
+        assert_eq!(notes.len(), 4, "{:?}", notes); // radius, rescale, T, m
+    }
+
+    #[test]
+    fn lists_import_their_first_pattern() {
+        let entry = lenia(r#"{"R":10,"T":10,"m":0.2,"s":0.02,"kn":2,"gn":2}"#, r#""o!""#);
+        let json = format!(r#"[{{"name":"Section header"}}, {entry}, {entry}]"#);
+        let (pattern, notes) = parse_pattern(&json).unwrap();
+        assert_eq!(pattern.name, "Test");
+        assert_eq!(notes.len(), 1);
+    }
+
+    #[test]
+    fn export_then_import_preserves_creature() {
+        // A blob straddling the world's corner, so the capture wraps
+        let mut snap = empty_snapshot();
+        let w = DEFAULT_WORLD_SIZE[0] as usize;
+        for (x, y, m) in [(0, 0, 0.9), (w - 1, 0, 0.4), (0, 1, 0.6), (1, DEFAULT_WORLD_SIZE[1] as usize - 1, 0.2)] {
+            let i = y * w + x;
+            snap.mass[i] = m;
+            snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&[9.3, 0.18, 0.021, 0.4]);
+            snap.genome_b[i] = 0.004;
+        }
+        let pattern = Pattern::extract(&snap, [0, 0], 8, "corner").unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert!((pattern.genome[0] - 9.3).abs() < 1e-5);
+        assert!(Pattern::extract(&empty_snapshot(), [0, 0], 8, "none").is_none());
+
+        let (imported, notes) = parse_pattern(&pattern.to_json()).unwrap();
+        assert!(notes.is_empty(), "{:?}", notes);
+        assert_eq!(imported.name, "corner");
+        assert_eq!((imported.width, imported.height), (3, 3));
+        for (a, b) in pattern.genome.iter().zip(&imported.genome) {
+            assert!((a - b).abs() < 1e-5);
+        }
+        assert!((imported.mutation_rate - 0.004).abs() < 1e-6);
+
+        let mut target = empty_snapshot();
+        imported.stamp(&mut target, [100, 100]);
+        let near = |x: usize, y: usize, m: f32| (target.mass[y * w + x] - m).abs() <= 0.5 / 255.0 + 1e-6;
+        assert!(near(100, 100, 0.9) && near(99, 100, 0.4) && near(101, 99, 0.2) && near(100, 101, 0.6));
+        assert_eq!(target.genome_a[(100 * w + 100) * 4], imported.genome[0]);
+    }
+}
+
+#[cfg(test)]
+mod tensorboard_tests {
+    //! Tests for TensorBoard event files (CRC-32C, protobuf and TFRecord
+    //! encoding).
+
+    use crate::tensorboard::{crc32c, encode_record, masked_crc32c, put_varint, summary_event, TensorBoardLog};
+
+    /// Split an event file into record payloads, checking both checksums.
+    fn read_records(mut bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut records = Vec::new();
+        while !bytes.is_empty() {
+            let len_bytes = &bytes[..8];
+            let len = u64::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+            assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()), masked_crc32c(len_bytes));
+            let data = &bytes[12..12 + len];
+            assert_eq!(u32::from_le_bytes(bytes[12 + len..16 + len].try_into().unwrap()), masked_crc32c(data));
+            records.push(data.to_vec());
+            bytes = &bytes[16 + len..];
+        }
+        records
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn crc32c_check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(b""), 0);
+    }
+
+    #[test]
+    fn varints_use_seven_bit_groups() {
+        let mut buf = Vec::new();
+        put_varint(&mut buf, 1);
+        put_varint(&mut buf, 300);
+        put_varint(&mut buf, u32::MAX as u64);
+        assert_eq!(buf, [0x01, 0xAC, 0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    }
+
+    #[test]
+    fn summary_event_layout() {
+        let event = summary_event(1.5, 300, &[]);
+        // wall_time (field 1, fixed64), step (field 2, varint), empty summary (field 5)
+        let mut expected = vec![0x09];
+        expected.extend_from_slice(&1.5f64.to_le_bytes());
+        expected.extend_from_slice(&[0x10, 0xAC, 0x02, 0x2A, 0x00]);
+        assert_eq!(event, expected);
+        assert_eq!(read_records(&encode_record(&event)), [event]);
+    }
+
+    #[test]
+    fn event_file_holds_version_then_summaries() {
+        let dir = std::env::temp_dir().join(format!("evolenia_tb_test_{}", std::process::id()));
+        let log = TensorBoardLog::create(&dir).unwrap();
+        log.scalars(10, &[("population/total_mass", 1234.5)]).unwrap();
+        log.clone().image(20, "screenshots/mass", 1, 1, b"png").unwrap();
+
+        let path = log.path().to_path_buf();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("events.out.tfevents."));
+        let records = read_records(&std::fs::read(&path).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(records.len(), 3);
+        assert!(contains(&records[0], b"brain.Event:2"));
+        assert!(contains(&records[1], b"population/total_mass"));
+        assert!(contains(&records[1], &1234.5f32.to_le_bytes()));
+        assert!(contains(&records[2], b"screenshots/mass") && contains(&records[2], b"png"));
+    }
+}
+
+#[cfg(test)]
+mod monitor_tests {
+    //! Tests for the headless metrics endpoint (Prometheus text, routing,
+    //! HTTP roundtrip).
+
+    use crate::monitor::{metric_name, route, MetricsServer, MonitorState};
+    use std::io::{Read, Write};
+
+    fn state() -> MonitorState {
+        MonitorState {
+            frame: 1200,
+            frames_total: 5000,
+            steps_per_sec: 512.5,
+            diagnostics_frame: Some(900),
+            diagnostics: vec![("population/total_mass", 1234.5), ("diversity/species_count", 7.0)],
+        }
+    }
+
+    #[test]
+    fn tags_become_metric_names() {
+        assert_eq!(metric_name("population/total_mass"), "population_total_mass");
+        assert_eq!(metric_name("a-b.c"), "a_b_c");
+    }
+
+    #[test]
+    fn prometheus_text_lists_gauges() {
+        let text = state().prometheus_text();
+        assert!(text.contains("# TYPE evolenia_frame gauge\nevolenia_frame 1200\n"));
+        assert!(text.contains("evolenia_steps_per_second 512.5\n"));
+        assert!(text.contains("evolenia_diagnostics_frame 900\n"));
+        assert!(text.contains("evolenia_population_total_mass 1234.5\n"));
+        assert!(text.contains("evolenia_diversity_species_count 7\n"));
+        // Every sample line is "name value" preceded by HELP and TYPE
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count() * 3, text.lines().count());
+        // No diagnostics yet: only progress gauges
+        assert!(!MonitorState::default().prometheus_text().contains("diagnostics_frame"));
+    }
+
+    #[test]
+    fn routes_metrics_health_and_unknown_paths() {
+        let state = state();
+        let (status, content_type, body) = route("/metrics", &state);
+        assert_eq!(status, "200 OK");
+        assert!(content_type.starts_with("text/plain; version=0.0.4"));
+        assert!(body.contains("evolenia_frame 1200"));
+        let (status, _, body) = route("/health", &state);
+        assert_eq!(status, "200 OK");
+        let health: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["frame"], 1200);
+        assert_eq!(route("/nope", &state).0, "404 Not Found");
+    }
+
+    #[test]
+    fn serves_metrics_over_http() {
+        let mut server = MetricsServer::start("127.0.0.1:0", 100).unwrap();
+        server.set_frame(42);
+        server.set_diagnostics(40, vec![("population/live_fraction", 0.25)]);
+
+        let mut stream = std::net::TcpStream::connect(server.addr()).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("evolenia_frame 42\n"));
+        assert!(response.contains("evolenia_frames_total 100\n"));
+        assert!(response.contains("evolenia_population_live_fraction 0.25\n"));
+    }
+}
+
+#[cfg(test)]
+mod queue_tests {
+    //! Tests for the experiment queue (protocol parameters, run naming,
+    //! sequencing and progress).
+
+    use crate::config::SimulationParams;
+    use crate::lab::new_run_id;
+    use crate::queue::{EntryStatus, ExperimentProtocol, ExperimentQueue, ProtocolDraft, QueueMode};
+    use std::path::{Path, PathBuf};
+
+    fn protocol(name: &str, frames: u32) -> ExperimentProtocol {
+        ProtocolDraft { name: name.into(), frames, ..Default::default() }.build(SimulationParams::default())
+    }
+
+    #[test]
+    fn protocol_params_apply_seed_and_unpause() {
+        let mut params = SimulationParams { paused: true, ..Default::default() };
+        let draft = ProtocolDraft { seed: 7, ..Default::default() };
+        let seeded = draft.build(params.clone()).run_params();
+        assert_eq!(seeded.effective_seed(), Some(7));
+        assert!(!seeded.paused);
+
+        params.use_fixed_seed = true;
+        let random = ProtocolDraft { fixed_seed: false, ..Default::default() }.build(params).run_params();
+        assert_eq!(random.effective_seed(), None);
+    }
+
+    #[test]
+    fn run_labels_are_positional_and_filename_safe() {
+        assert_eq!(protocol("high mutation/2", 10).run_label(2), "q03_high_mutation_2");
+        let now = chrono::Local::now();
+        let (id, dir) = new_run_id(Path::new("runs"), &now, Some("q01_base"));
+        assert!(id.starts_with("run_") && id.ends_with("_q01_base"));
+        assert!(dir.starts_with("runs") && dir.ends_with(&id));
+        assert!(!new_run_id(Path::new("runs"), &now, None).0.contains('q'));
+        let (_, custom) = new_run_id(Path::new("/data/evolenia"), &now, None);
+        assert!(custom.starts_with("/data/evolenia"));
+    }
+
+    #[test]
+    fn protocols_run_one_at_a_time_in_order() {
+        let mut queue = ExperimentQueue::default();
+        queue.enqueue(protocol("a", 100));
+        queue.enqueue(protocol("b", 300));
+        assert_eq!(queue.next_to_start(), None); // not started
+
+        queue.running = true;
+        assert_eq!(queue.next_to_start(), Some(0));
+        queue.begin(0, PathBuf::from("runs/a"));
+        assert_eq!(queue.active(), Some(0));
+        assert_eq!(queue.next_to_start(), None);
+
+        queue.set_progress(0, 50);
+        assert!((queue.fraction() - 50.0 / 400.0).abs() < 1e-6);
+        queue.remove(0); // running: kept
+        assert_eq!(queue.entries.len(), 2);
+
+        queue.finish(0, EntryStatus::Done);
+        assert_eq!(queue.next_to_start(), Some(1));
+        assert!(!queue.stop_if_complete());
+        queue.begin(1, PathBuf::from("runs/b"));
+        queue.finish(1, EntryStatus::Failed("readback".into()));
+        assert_eq!(queue.counts(), (2, 2));
+        assert_eq!(queue.fraction(), 1.0);
+        assert!(queue.stop_if_complete());
+        assert!(!queue.running);
+    }
+
+    #[test]
+    fn reorder_and_clear_finished() {
+        let mut queue = ExperimentQueue::default();
+        for name in ["a", "b", "c"] {
+            queue.enqueue(protocol(name, 10));
+        }
+        queue.move_up(2);
+        queue.move_up(0); // no-op
+        let names: Vec<&str> = queue.entries.iter().map(|e| e.protocol.name.as_str()).collect();
+        assert_eq!(names, ["a", "c", "b"]);
+
+        queue.finish(0, EntryStatus::Cancelled);
+        queue.clear_finished();
+        assert_eq!(queue.entries.len(), 2);
+        assert_eq!(queue.entries[0].protocol.mode, QueueMode::Visible);
+    }
+
+    #[test]
+    fn run_next_goes_after_the_current_protocol() {
+        let mut queue = ExperimentQueue::default();
+        queue.enqueue(protocol("current", 10));
+        queue.enqueue(protocol("later", 10));
+        queue.begin(0, PathBuf::from("runs/current"));
+
+        let mut urgent = protocol("urgent", 10);
+        urgent.mode = QueueMode::Background;
+        queue.enqueue_next(urgent);
+        assert!(queue.running);
+        let names: Vec<&str> = queue.entries.iter().map(|e| e.protocol.name.as_str()).collect();
+        assert_eq!(names, ["current", "urgent", "later"]);
+
+        queue.finish(0, EntryStatus::Done);
+        assert_eq!(queue.next_to_start(), Some(1));
+        // Nothing pending: goes last
+        let mut idle = ExperimentQueue::default();
+        idle.enqueue_next(protocol("only", 10));
+        assert_eq!(idle.next_to_start(), Some(0));
+    }
+}
+
+#[cfg(test)]
+mod diff_overlay_tests {
+    //! Reference diff overlay: divergence metric and settings compatibility.
+
+    use crate::config::{DiffOverlaySettings, SimulationParams};
+    use crate::lab::ReferenceMass;
+
+    #[test]
+    fn divergence_is_mean_absolute_mass_difference() {
+        let reference = ReferenceMass { label: String::from("frame 0"), mass: vec![0.0, 0.5, 1.0, 0.25] };
+        assert_eq!(reference.divergence(&reference.mass), Some(0.0));
+        let d = reference.divergence(&[0.5, 0.5, 0.0, 0.25]).unwrap();
+        assert!((d - 0.375).abs() < 1e-6);
+        assert_eq!(reference.divergence(&[0.0; 3]), None);
+    }
+
+    #[test]
+    fn params_without_diff_overlay_still_load() {
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("diff_overlay");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.diff_overlay, DiffOverlaySettings::default());
+        assert!(!params.diff_overlay.enabled);
+    }
+}
+
+#[cfg(test)]
+mod popgen_tests {
+    //! Population genetics export: live-cell table, lineage clustering, file formats.
+
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    use crate::popgen::{encode_parquet, export_population, live_cells, population_path, PopgenFormat};
+    use crate::world::BufferSnapshot;
+
+    /// 3×2 world: cells 1, 2 and 5 are alive; 1 and 5 share a genome.
+    fn snapshot() -> BufferSnapshot {
+        let a = [6.0, 0.15, 0.015, 0.1];
+        let b = [12.0, 0.35, 0.05, 0.9];
+        let genome_a = [a, a, b, a, a, [a[0], a[1] + 0.01, a[2], a[3]]].concat();
+        BufferSnapshot {
+            size: [3, 2],
+            mass: vec![0.0, 0.5, 0.8, 0.005, 0.0, 0.3],
+            energy: vec![0.5; 6],
+            genome_a,
+            genome_b: vec![0.01, 0.02, 0.03, 0.04, 0.05, 0.06],
+            resource: vec![1.0; 6],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn live_cells_carry_coordinates_and_genomes() {
+        let cells = live_cells(&snapshot(), 3);
+        assert_eq!(cells.len(), 3);
+        assert_eq!((cells[0].x, cells[0].y, cells[0].mass), (1, 0, 0.5));
+        assert_eq!((cells[2].x, cells[2].y), (2, 1));
+        assert_eq!(cells[1].r, 12.0);
+        assert_eq!(cells[1].mut_rate, 0.03);
+        // Close genomes share a lineage, the distant one founds its own
+        let lineages: Vec<u32> = cells.iter().map(|c| c.lineage_id).collect();
+        assert_eq!(lineages, [0, 1, 0]);
+    }
+
+    #[test]
+    fn format_follows_extension() {
+        assert_eq!(PopgenFormat::from_path(Path::new("pop.parquet")), PopgenFormat::Parquet);
+        assert_eq!(PopgenFormat::from_path(Path::new("pop.PARQUET")), PopgenFormat::Parquet);
+        assert_eq!(PopgenFormat::from_path(Path::new("pop.csv")), PopgenFormat::Csv);
+        assert_eq!(PopgenFormat::from_path(Path::new("pop")), PopgenFormat::Csv);
+        let path = population_path(Path::new("runs/x"), 42, PopgenFormat::Parquet);
+        assert_eq!(path, Path::new("runs/x/population_frame000042.parquet"));
+    }
+
+    #[test]
+    fn csv_export_writes_one_row_per_live_cell() {
+        let dir = std::env::temp_dir().join(format!("evolenia_popgen_{}", std::process::id()));
+        let path = dir.join("pop.csv");
+        assert_eq!(export_population(&snapshot(), 3, &path), Ok(3));
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "x,y,mass,r,mu,sigma,agg,mut_rate,lineage_id");
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("2,0,0.800000,12.0000,"));
+        assert!(lines[2].ends_with(",1"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parquet_file_has_magic_and_footer() {
+        let bytes = encode_parquet(&live_cells(&snapshot(), 3));
+        assert_eq!(&bytes[..4], b"PAR1");
+        assert_eq!(&bytes[bytes.len() - 4..], b"PAR1");
+        let footer_len = u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap()) as usize;
+        assert!(footer_len > 0 && footer_len < bytes.len() - 12);
+        // Nine columns of three 4-byte values, each behind a page header
+        assert!(bytes.len() > 4 + 9 * 12 + footer_len + 8);
+        // Schema names are stored in the footer
+        let footer = &bytes[bytes.len() - 8 - footer_len..bytes.len() - 8];
+        let contains = |needle: &[u8]| footer.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"lineage_id") && contains(b"mut_rate"));
+    }
+}
+
+#[cfg(test)]
+mod interaction_tests {
+    //! Predation interaction matrix: counter layout and summaries.
+
+    use crate::metrics::{InteractionMatrix, AGG_CLASSES};
+
+    #[test]
+    fn counts_are_attacker_major() {
+        let mut counts = vec![0u32; AGG_CLASSES * AGG_CLASSES];
+        counts[4 * AGG_CLASSES] = 6; // class 4 attacker, class 0 defender
+        counts[1] = 2; // class 0 attacker, class 1 defender
+        counts[2 * AGG_CLASSES + 2] = 2;
+        let matrix = InteractionMatrix::from_counts(&counts);
+        assert_eq!(matrix.counts[4][0], 6);
+        assert_eq!(matrix.counts[0][1], 2);
+        assert_eq!(matrix.total(), 10);
+        assert_eq!(matrix.max(), 6);
+        assert_eq!(matrix.upward_fraction(), Some(0.6));
+    }
+
+    #[test]
+    fn empty_matrix_has_no_upward_fraction() {
+        let matrix = InteractionMatrix::from_counts(&[]);
+        assert_eq!(matrix.total(), 0);
+        assert_eq!(matrix.upward_fraction(), None);
+        assert_eq!(InteractionMatrix::class_label(0), "0.0–0.2");
+        assert_eq!(InteractionMatrix::class_label(AGG_CLASSES - 1), "0.8–1.0");
+    }
+}
+
+#[cfg(test)]
+mod territory_tests {
+    //! Territory map: sliding window of species labels, ownership, overlay colors.
+
+    use std::collections::BTreeMap;
+    use crate::territory::{species_color, territory_rgba, TerritoryMap, NO_SPECIES};
+    use crate::world::BufferSnapshot;
+
+    const A: [f32; 4] = [6.0, 0.15, 0.015, 0.1];
+    const B: [f32; 4] = [12.0, 0.35, 0.05, 0.9];
+    const WHEEL: [[f32; 4]; 6] = [
+        [1.0, 0.0, 0.0, 1.0],
+        [1.0, 1.0, 0.0, 1.0],
+        [0.0, 1.0, 0.0, 1.0],
+        [0.0, 1.0, 1.0, 1.0],
+        [0.0, 0.0, 1.0, 1.0],
+        [1.0, 0.0, 1.0, 1.0],
+    ];
+
+    /// One cell per entry: `None` = empty, otherwise a live cell with that genome.
+    fn snapshot(cells: &[Option<[f32; 4]>]) -> BufferSnapshot {
+        let n = cells.len();
+        BufferSnapshot {
+            size: [n as u32, 1],
+            mass: cells.iter().map(|c| if c.is_some() { 0.5 } else { 0.0 }).collect(),
+            energy: vec![0.5; n],
+            genome_a: cells.iter().flat_map(|c| c.unwrap_or(A)).collect(),
+            genome_b: vec![0.01; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn owner_is_majority_species_over_window() {
+        let mut map = TerritoryMap::default();
+        map.record(&snapshot(&[Some(A), Some(B), None]), 4);
+        map.record(&snapshot(&[Some(A), Some(A), None]), 4);
+        map.record(&snapshot(&[Some(B), Some(A), Some(A)]), 4);
+        assert_eq!(map.species_count(), 2);
+        let owners = map.owners();
+        assert_eq!(owners[0].0, 0);
+        assert!((owners[0].1 - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(owners[1].0, 0);
+        // Held once out of three samples: weak claim
+        assert_eq!(owners[2].0, 0);
+        assert!((owners[2].1 - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn window_evicts_old_samples() {
+        let mut map = TerritoryMap::default();
+        map.record(&snapshot(&[Some(A)]), 2);
+        map.record(&snapshot(&[Some(B)]), 2);
+        map.record(&snapshot(&[Some(B)]), 2);
+        assert_eq!(map.samples(), 2);
+        assert_eq!(map.owners(), [(1, 1.0)]);
+        map.record(&snapshot(&[None]), 2);
+        map.record(&snapshot(&[None]), 2);
+        assert_eq!(map.owners(), [(NO_SPECIES, 0.0)]);
+    }
+
+    #[test]
+    fn species_ids_stay_stable_across_samples() {
+        let mut map = TerritoryMap::default();
+        map.record(&snapshot(&[None, Some(B)]), 8);
+        map.record(&snapshot(&[Some(A), Some(B)].map(|c| c.map(|g| [g[0] + 0.01, g[1], g[2], g[3]]))), 8);
+        assert_eq!(map.species_count(), 2);
+        let owners = map.owners();
+        // B seen first keeps id 0 after drifting slightly
+        assert_eq!(owners[1], (0, 1.0));
+        assert_eq!(owners[0], (1, 0.5));
+        map.clear();
+        assert_eq!((map.samples(), map.species_count()), (0, 0));
+        assert!(map.owners().is_empty());
+    }
+
+    #[test]
+    fn areas_rank_species_by_cells() {
+        let owners = [(1, 1.0), (0, 0.5), (1, 0.2), (NO_SPECIES, 0.0), (2, 1.0)];
+        assert_eq!(TerritoryMap::areas(&owners), [(1, 2), (0, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn overlay_alpha_tracks_share() {
+        let rgba = territory_rgba(&[(0, 1.0), (NO_SPECIES, 0.0), (3, 0.5)], &WHEEL);
+        assert_eq!(rgba.len(), 12);
+        assert_eq!(&rgba[0..4], &[255, 0, 0, 255]);
+        assert_eq!(&rgba[4..8], &[0, 0, 0, 0]);
+        assert_eq!(rgba[11], 128);
+        // Neighbouring ids land far apart on the wheel
+        assert_ne!(species_color(1, &WHEEL), species_color(2, &WHEEL));
+    }
+}
+
+#[cfg(test)]
+mod plot_export_tests {
+    //! Plot export: tick layout, SVG and PNG figures, embedded metadata, file naming.
+
+    use std::path::Path;
+
+    use crate::plot_export::{
+        export_plot, format_tick, nice_ticks, plot_path, render_png, render_rgba, render_svg, PlotExportSettings,
+        PlotFigure, PlotFormat, PlotSeries,
+    };
+
+    fn figure() -> PlotFigure {
+        PlotFigure {
+            title: "Mass <total>".into(),
+            x_label: "Frame".into(),
+            y_label: "Mass".into(),
+            series: vec![
+                PlotSeries { name: "run_a".into(), points: (0..50).map(|i| [i as f64 * 300.0, (i as f64).sin()]).collect() },
+                PlotSeries { name: "run_b".into(), points: vec![[0.0, 0.5], [14_700.0, -0.5]] },
+            ],
+            markers: vec![3000.0, 1e9],
+            metadata: vec![("Run".into(), "run_42".into()), ("Seed".into(), "7".into())],
+        }
+    }
+
+    #[test]
+    fn ticks_are_round_and_cover_range() {
+        assert_eq!(nice_ticks(0.0, 14_700.0, 6), vec![0.0, 2000.0, 4000.0, 6000.0, 8000.0, 10000.0, 12000.0, 14000.0]);
+        assert_eq!(nice_ticks(-1.05, 1.05, 6), vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(format_tick(0.5, 0.5), "0.5");
+        assert_eq!(format_tick(12000.0, 2000.0), "12000");
+        assert_eq!(format_tick(-0.0001, 0.05), "0.00");
+    }
+
+    #[test]
+    fn svg_has_series_legend_markers_and_metadata() {
+        let svg = render_svg(&figure(), 800, 500);
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.contains("width=\"800\" height=\"500\""));
+        assert!(svg.contains("<title>Mass &lt;total&gt;</title>"));
+        assert!(svg.contains("&quot;Run&quot;:&quot;run_42&quot;"));
+        // Two series, one in-range dashed marker
+        assert!(svg.contains("stroke=\"#0072b2\""));
+        assert!(svg.contains("stroke=\"#d55e00\""));
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+        assert!(svg.contains(">run_b</text>"));
+        assert!(svg.contains("rotate(-90"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn png_is_opaque_with_ink_and_text_chunks() {
+        let rgba = render_rgba(&figure(), 400, 300).unwrap();
+        assert_eq!(rgba.len(), 400 * 300 * 4);
+        assert!(rgba.chunks_exact(4).all(|p| p[3] == 255));
+        assert_eq!(&rgba[..4], &[255, 255, 255, 255]);
+        // Title glyphs darken the top band
+        let dark_title = rgba.chunks_exact(4).take(400 * 30).filter(|p| p[0] < 128).count();
+        assert!(dark_title > 20, "title not drawn: {}", dark_title);
+        assert!(rgba.chunks_exact(4).any(|p| p[..3] == [0, 114, 178]));
+
+        let png = render_png(&figure(), 400, 300).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (400, 300));
+        let chunk = png.windows(4).position(|w| w == b"iTXt").expect("metadata chunk");
+        assert!(chunk < png.windows(4).position(|w| w == b"IDAT").unwrap());
+        let text = String::from_utf8_lossy(&png);
+        assert!(text.contains("Run\0\0\0\0\0run_42"));
+    }
+
+    #[test]
+    fn export_writes_clamped_file_under_plots() {
+        let dir = std::env::temp_dir().join("evolenia_plot_export_test");
+        let path = plot_path(&dir, "📈 Total Mass (A vs B)", 1200, PlotFormat::Png);
+        assert_eq!(path, dir.join("plots").join("total_mass_a_vs_b_f1200.png"));
+        assert_eq!(plot_path(Path::new("r"), "??", 0, PlotFormat::Svg), Path::new("r/plots/plot_f0.svg"));
+
+        let settings = PlotExportSettings { format: PlotFormat::Png, width: 10, height: 250 };
+        export_plot(&figure(), settings, &path).unwrap();
+        let image = image::open(&path).unwrap();
+        assert_eq!((image.width(), image.height()), (200, 250));
+
+        // Empty figures still export
+        let empty = PlotFigure { title: "Empty".into(), ..Default::default() };
+        assert!(render_svg(&empty, 300, 200).contains("Empty"));
+        assert!(render_rgba(&empty, 300, 200).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod custom_metric_tests {
+    //! Custom metric expressions: parsing, evaluation per record, CSV columns, persistence.
+
+    use crate::config::{CustomMetric, SimulationParams};
+    use crate::expr::{compile_metric, Expr};
+    use crate::lab::{write_metrics_csv, LabState, MetricsRecord};
+
+    fn record(frame: u32) -> MetricsRecord {
+        MetricsRecord {
+            frame,
+            time_ms: 0.0,
+            fps: 60.0,
+            total_mass: 200.0,
+            avg_energy: 0.5,
+            entropy: 2.0,
+            species: 4,
+            live_pixels: 1000,
+            live_fraction: 0.1,
+            predator_fraction: 0.25,
+            avg_resource: 0.8,
+            mass_std_dev: 0.1,
+            avg_radius: 10.0,
+            avg_mu: 0.15,
+            avg_sigma: 0.015,
+            avg_aggressivity: 0.3,
+            avg_mutation_rate: 0.003,
+            prey_fraction: 0.5,
+            opportunist_fraction: 0.25,
+            effective_diversity: 3.0,
+            genome_variance: 0.01,
+            total_energy: 50.0,
+            energy_flux: 0.0,
+            plugins: vec![2.0, 0.75],
+        }
+    }
+
+    fn metric(name: &str, expression: &str) -> CustomMetric {
+        CustomMetric { name: name.into(), expression: expression.into() }
+    }
+
+    fn eval(source: &str) -> f64 {
+        Expr::parse(source, &["x", "y"]).unwrap().eval(&[3.0, 4.0])
+    }
+
+    #[test]
+    fn precedence_and_functions() {
+        assert_eq!(eval("1 + 2 * x"), 7.0);
+        assert_eq!(eval("(1 + 2) * x"), 9.0);
+        assert_eq!(eval("-x ^ 2"), -9.0);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("x - y - 1"), -2.0);
+        assert_eq!(eval("sqrt(x*x + y*y)"), 5.0);
+        assert_eq!(eval("max(x, y) / min(x, y)"), 4.0 / 3.0);
+        assert_eq!(eval("1.5e2 + .5"), 150.5);
+        assert!(eval("x / (y - 4)").is_infinite());
+    }
+
+    #[test]
+    fn parse_errors_name_the_problem() {
+        let err = |s: &str| Expr::parse(s, &["x"]).unwrap_err();
+        assert_eq!(err("x * z"), "unknown field 'z'");
+        assert_eq!(err("foo(x)"), "unknown function 'foo'");
+        assert_eq!(err("min(x)"), "min() takes 2 argument(s), got 1");
+        assert_eq!(err("(x + 1"), "expected ')' at 6");
+        assert_eq!(err("x $ 2"), "unexpected '$' at 2");
+        assert_eq!(err("x +"), "unexpected end of expression");
+        assert_eq!(compile_metric(&metric(" ", "x")).unwrap_err(), "missing name");
+    }
+
+    #[test]
+    fn metrics_evaluate_over_record_fields() {
+        assert_eq!(MetricsRecord::FIELDS.join(","), MetricsRecord::csv_header());
+        let r = record(300);
+        let predators = compile_metric(&metric("predators", "predator_fraction * live_pixels")).unwrap();
+        assert_eq!(predators.eval(&r), 250.0);
+        let ratio = compile_metric(&metric(" energy per mass ", "total_energy / total_mass")).unwrap();
+        assert_eq!(ratio.name, "energy per mass");
+        assert_eq!(ratio.eval(&r), 0.25);
+    }
+
+    #[test]
+    fn csv_appends_custom_columns() {
+        let mut lab = LabState::default();
+        lab.sync_custom_metrics(&[metric("energy per mass", "total_energy / total_mass"), metric("bad", "nope")]);
+        assert_eq!(lab.custom_metrics.len(), 1);
+        let path = std::env::temp_dir().join("evolenia_custom_metrics.csv");
+        write_metrics_csv(&path, &[record(300), record(600)], &lab.custom_metrics, &lab.conditions).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].ends_with(",energy_flux,patch_count,largest_patch_fraction,energy_per_mass"));
+        assert!(lines[1].ends_with(",2,0.75,0.25"));
+        assert_eq!(lines[2].split(',').count(), MetricsRecord::field_names().len() + 1);
+        // Recompiled when the definitions change
+        lab.sync_custom_metrics(&[]);
+        assert!(lab.custom_metrics.is_empty());
+    }
+
+    #[test]
+    fn definitions_persist_in_params() {
+        let mut params = SimulationParams::default();
+        params.custom_metrics.push(metric("ratio", "total_energy / total_mass"));
+        let json = serde_json::to_string(&params).unwrap();
+        let loaded: SimulationParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.custom_metrics, params.custom_metrics);
+
+        // Presets saved before custom metrics existed still load
+        let mut old: serde_json::Value = serde_json::from_str(&json).unwrap();
+        old.as_object_mut().unwrap().remove("custom_metrics");
+        let loaded: SimulationParams = serde_json::from_value(old).unwrap();
+        assert!(loaded.custom_metrics.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod alert_tests {
+    //! Alert rules: thresholds, relative drops over a window, sustain/re-arm, lab actions.
+
+    use std::collections::BTreeMap;
+    use crate::alerts::{condition_met, describe, AlertMonitor};
+    use crate::config::{AlertRule, Comparator, CustomMetric};
+    use crate::expr::compile_metrics;
+    use crate::lab::{EventSeverity, LabState, MetricsRecord};
+    use crate::metrics::SimDiagnostics;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn history(samples: &[(u32, f32)]) -> Vec<MetricsRecord> {
+        samples.iter().map(|&(frame, entropy)| MetricsRecord { frame, entropy, ..Default::default() }).collect()
+    }
+
+    fn rule(comparator: Comparator, threshold: f64) -> AlertRule {
+        AlertRule { comparator, threshold, ..Default::default() }
+    }
+
+    #[test]
+    fn thresholds_and_relative_drops() {
+        let h = history(&[(300, 4.0), (600, 3.0), (900, 1.9)]);
+        assert!(condition_met(&rule(Comparator::Below, 2.0), &h, &[]));
+        assert!(!condition_met(&rule(Comparator::Above, 2.0), &h, &[]));
+        // 4.0 → 1.9 is a 52.5% drop within 1000 frames
+        let drop = rule(Comparator::DropsBy, 50.0);
+        assert!(condition_met(&drop, &h, &[]));
+        // With a 500-frame window the peak is 3.0: only a 36.7% drop
+        assert!(!condition_met(&AlertRule { window_frames: 500, ..drop.clone() }, &h, &[]));
+        assert!(!condition_met(&rule(Comparator::RisesBy, 10.0), &h, &[]));
+        assert!(!condition_met(&drop, &h[..1], &[]), "no earlier samples to compare");
+        assert_eq!(describe(&drop), "entropy drops by 50% within 1000 frames");
+    }
+
+    #[test]
+    fn rules_accept_custom_metrics() {
+        let custom = compile_metrics(&[CustomMetric { name: "double".into(), expression: "entropy * 2".into() }]);
+        let h = history(&[(300, 3.0)]);
+        let r = AlertRule { metric: "double".into(), ..rule(Comparator::Above, 5.0) };
+        assert!(condition_met(&r, &h, &custom));
+        assert!(!condition_met(&AlertRule { metric: "missing".into(), ..r }, &h, &custom));
+    }
+
+    #[test]
+    fn fires_once_when_sustained_then_rearms() {
+        let mut monitor = AlertMonitor::default();
+        monitor.sync(&[AlertRule { sustained: 2, ..rule(Comparator::Below, 2.0) }]);
+        let mut h = Vec::new();
+        let mut fired_at = Vec::new();
+        for (frame, entropy) in [(1, 1.0), (2, 1.0), (3, 1.0), (4, 3.0), (5, 1.0), (6, 1.0)] {
+            h.extend(history(&[(frame, entropy)]));
+            if !monitor.check(&h, &[]).is_empty() {
+                fired_at.push(frame);
+            }
+        }
+        assert_eq!(fired_at, vec![2, 6]);
+        assert!(monitor.is_firing(0));
+        // Editing the rules restarts the streaks
+        monitor.sync(&[rule(Comparator::Above, 2.0)]);
+        assert_eq!(monitor.streak(0), 0);
+    }
+
+    #[test]
+    fn lab_logs_notifies_and_requests_actions() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.5; n],
+            energy: vec![0.5; n],
+            genome_a: vec![10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        let mut lab = LabState::default();
+        let alert = AlertRule { metric: "live_pixels".into(), pause: true, snapshot: true, ..rule(Comparator::Above, 0.0) };
+        lab.alerts.sync(&[alert, AlertRule { enabled: false, ..rule(Comparator::Above, -1.0) }]);
+        lab.record_metrics(&SimDiagnostics::from_snapshot(&snap), &snap, 300, 60.0);
+
+        let alerts: Vec<_> = lab.events.iter().filter(|e| e.event_type == "ALERT_RULE").collect();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, EventSeverity::Alert);
+        assert_eq!(alerts[0].payload["condition"], "live_pixels > 0");
+        assert!(lab.alert_pause_requested && lab.snapshot_requested);
+        assert_eq!(lab.notifications.history.back().map(|n| n.severity), Some(EventSeverity::Alert));
+    }
+}
+
+#[cfg(test)]
+mod session_log_tests {
+    //! Session log capture: line format, level filtering, and the startup
+    //! buffer copied into the run's session.log.
+
+    use crate::session_log::{self, format_line, SessionLogLevel, SESSION_LOG_FILE};
+    use log::Level;
+
+    #[test]
+    fn line_format_and_levels() {
+        let line = format_line("2024-05-01 12:00:00.000", Level::Warn, "wgpu_core", &format_args!("slow {}", 3));
+        assert_eq!(line, "2024-05-01 12:00:00.000 WARN  wgpu_core: slow 3");
+        assert_eq!(SessionLogLevel::default(), SessionLogLevel::Info);
+        assert!(SessionLogLevel::Off.filter() < Level::Error);
+        assert!(SessionLogLevel::Warn.filter() >= Level::Warn);
+        assert!(SessionLogLevel::Warn.filter() < Level::Info);
+    }
+
+    #[test]
+    fn startup_lines_then_run_lines_are_archived() {
+        let dir = std::env::temp_dir().join(format!("evolenia_session_log_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        session_log::set_level(SessionLogLevel::Warn);
+        session_log::capture(Level::Warn, "wgpu_hal", &format_args!("adapter warning"));
+        session_log::capture(Level::Info, "evolenia", &format_args!("filtered out"));
+        session_log::open(&dir, "test_run").unwrap();
+        session_log::capture(Level::Error, "wgpu_core", &format_args!("device lost"));
+        session_log::close();
+        session_log::capture(Level::Error, "evolenia", &format_args!("after close"));
+        session_log::set_level(SessionLogLevel::default());
+
+        let text = std::fs::read_to_string(dir.join(SESSION_LOG_FILE)).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(text.starts_with("# EvoLenia session log — run test_run (level WARN)"));
+        let startup = text.find("WARN  wgpu_hal: adapter warning").expect("startup line");
+        let run = text.find("ERROR wgpu_core: device lost").expect("run line");
+        assert!(startup < run);
+        assert!(!text.contains("filtered out") && !text.contains("after close"));
+    }
+}
+
+#[cfg(test)]
+mod file_dialog_tests {
+    //! Tests for file dialog helpers (the dialogs themselves need a desktop).
+
+    use crate::file_dialog::with_extension;
+    use std::path::PathBuf;
+
+    #[test]
+    fn save_paths_get_the_extension_once() {
+        assert_eq!(with_extension(PathBuf::from("/tmp/a"), "snap"), PathBuf::from("/tmp/a.snap"));
+        assert_eq!(with_extension(PathBuf::from("/tmp/a.SNAP"), "snap"), PathBuf::from("/tmp/a.SNAP"));
+        assert_eq!(with_extension(PathBuf::from("run.v2"), "snap"), PathBuf::from("run.v2.snap"));
+    }
+}
+
+#[cfg(test)]
+mod thumbnail_tests {
+    //! Tests for run thumbnails and the gallery's run reload.
+
+    use std::collections::BTreeMap;
+    use crate::config::SimulationParams;
+    use crate::lab::{run_config_json, RunSummary};
+    use crate::theme::ColorPalette;
+    use crate::thumbnail::{load_color_image, save_thumbnail, thumbnail_rgba, thumbnail_size, THUMBNAIL_WIDTH};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn snapshot(live: impl Fn(u32, u32) -> bool) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mass = (0..n as u32).map(|i| if live(i % DEFAULT_WORLD_SIZE[0], i / DEFAULT_WORLD_SIZE[0]) { 0.8 } else { 0.0 }).collect();
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass,
+            energy: vec![0.5; n],
+            genome_a: [10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn live_cells_are_bright_on_a_dark_background() {
+        let palette = ColorPalette::default().uniforms();
+        let (w, h) = thumbnail_size(THUMBNAIL_WIDTH, DEFAULT_WORLD_SIZE);
+        assert_eq!((w, h), (THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * DEFAULT_WORLD_SIZE[1] / DEFAULT_WORLD_SIZE[0]));
+        // Left half alive
+        let rgba = thumbnail_rgba(&snapshot(|x, _| x < DEFAULT_WORLD_SIZE[0] / 2), THUMBNAIL_WIDTH, &palette);
+        assert_eq!(rgba.len(), (w * h * 4) as usize);
+        let brightness = |x: u32| rgba[(x * 4) as usize..(x * 4 + 3) as usize].iter().map(|&c| c as u32).sum::<u32>();
+        assert!(brightness(0) > 3 * brightness(w - 1));
+        assert!(rgba.chunks_exact(4).all(|p| p[3] == 255));
+    }
+
+    #[test]
+    fn thumbnail_round_trips_through_png() {
+        let dir = std::env::temp_dir().join(format!("evolenia_thumbnail_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = save_thumbnail(&dir, &snapshot(|x, y| x == y), &ColorPalette::default().uniforms()).unwrap();
+        let image = load_color_image(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let (w, h) = thumbnail_size(THUMBNAIL_WIDTH, DEFAULT_WORLD_SIZE);
+        assert_eq!(image.size, [w as usize, h as usize]);
+    }
+
+    #[test]
+    fn run_params_reload_from_config_json() {
+        let dir = std::env::temp_dir().join(format!("evolenia_reload_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let params = SimulationParams { use_fixed_seed: true, fixed_seed_value: 1234, ..Default::default() };
+        let config = run_config_json("run_x", "2024-01-01 00:00:00", &params, &Default::default(), DEFAULT_WORLD_SIZE);
+        std::fs::write(dir.join("config.json"), config.to_string()).unwrap();
+        let run = RunSummary {
+            run_id: "run_x".into(),
+            run_dir: dir.clone(),
+            start_time: String::new(),
+            total_frames: 0,
+            metrics_count: 0,
+            final_metrics: None,
+            thumbnail: None,
+        };
+        let loaded = run.load_params();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(loaded.unwrap().effective_seed(), Some(1234));
+        let missing = RunSummary { run_dir: dir.join("missing"), ..run };
+        assert!(missing.load_params().is_err());
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    //! Tests for the experiment report sections and the preview's markdown
+    //! subset.
+
+    use crate::config::SimulationParams;
+    use crate::lab::{LabState, MetricsRecord};
+    use crate::report::{inline_spans, parse_markdown, species_section, timeline_section, Block};
+
+    fn record(frame: u32, species: usize, entropy: f32) -> MetricsRecord {
+        MetricsRecord { frame, species, entropy, ..Default::default() }
+    }
+
+    #[test]
+    fn markdown_subset_parses_into_blocks() {
+        let source = "# Title\n\nSome text\ncontinued **here**.\n\n- **Run ID**: x\n\n\
+                      | A | B |\n|---|---|\n| 1 | a \\| b |\n\n```json\n{\n}\n```\n![Mass](plots/report_mass.png)\n";
+        assert_eq!(
+            parse_markdown(source),
+            vec![
+                Block::Heading(1, "Title".into()),
+                Block::Paragraph("Some text continued **here**.".into()),
+                Block::Bullet("**Run ID**: x".into()),
+                Block::Table(vec![vec!["A".into(), "B".into()], vec!["1".into(), "a | b".into()]]),
+                Block::Code("{\n}".into()),
+                Block::Image { alt: "Mass".into(), path: "plots/report_mass.png".into() },
+            ]
+        );
+        assert_eq!(
+            inline_spans("**Run ID**: x and **y**"),
+            vec![("Run ID".into(), true), (": x and ".into(), false), ("y".into(), true)]
+        );
+    }
+
+    #[test]
+    fn timeline_lists_causes_in_frame_order() {
+        let mut lab = LabState::default();
+        lab.log_event(500, "PERTURBATION", "Meteor | radius 20");
+        lab.log_event(100, "PARAM_CHANGE", "mutation 0.01");
+        lab.log_event(300, "SCREENSHOT", "not a cause");
+        let section = timeline_section(&lab.events);
+        let rows: Vec<&str> = section.lines().filter(|l| l.starts_with("| ") && !l.starts_with("| Frame")).collect();
+        assert_eq!(rows, vec!["| 100 | PARAM_CHANGE | mutation 0.01 |", "| 500 | PERTURBATION | Meteor \\| radius 20 |"]);
+        assert!(timeline_section(&[]).contains("No perturbations"));
+    }
+
+    #[test]
+    fn species_summary_reports_extremes_and_territories() {
+        let history = [record(100, 3, 2.0), record(200, 7, 2.5), record(300, 2, 1.0)];
+        let section = species_section(&history, &[(4, 300), (1, 100)]);
+        assert!(section.contains("**Final species**: 2"));
+        assert!(section.contains("**Peak**: 7 (frame 200)"));
+        assert!(section.contains("**Minimum**: 2 (frame 300)"));
+        assert!(section.contains("**Mean**: 4.0"));
+        assert!(section.contains("| #4 | 300 | 75.0% |"));
+        assert!(species_section(&[], &[]).contains("No metrics"));
+    }
+
+    #[test]
+    fn generated_report_embeds_its_plots() {
+        let dir = std::env::temp_dir().join(format!("evolenia_report_{}", std::process::id()));
+        let mut lab = LabState { run_dir: dir.clone(), ..Default::default() };
+        lab.metrics_history = vec![record(100, 3, 2.0), record(200, 4, 2.2)];
+        lab.log_event(150, "PERTURBATION", "Meteor");
+        let markdown = lab.generate_report(&SimulationParams::default()).unwrap();
+        let saved = lab.save_report(&markdown).unwrap();
+        let images: Vec<String> = parse_markdown(&std::fs::read_to_string(saved).unwrap())
+            .into_iter()
+            .filter_map(|b| match b {
+                Block::Image { path, .. } => Some(path),
+                _ => None,
+            })
+            .collect();
+        let all_exist = images.iter().all(|p| dir.join(p).is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(images, vec!["plots/report_entropy.png", "plots/report_mass.png", "plots/report_species.png"]);
+        assert!(all_exist);
+        assert!(markdown.contains("## Perturbation Timeline") && markdown.contains("## Species Summary"));
+    }
+}
+
+#[cfg(test)]
+mod report_template_tests {
+    //! Tests for Handlebars report templates: the built-in layout, custom
+    //! placeholders and helpers, and template errors.
+
+    use crate::config::SimulationParams;
+    use crate::lab::{LabState, MetricsRecord};
+    use crate::report::{load_template, render_template, DEFAULT_TEMPLATE};
+
+    fn lab_with_history() -> LabState {
+        let mut lab = LabState { run_id: "run_test".into(), ..Default::default() };
+        lab.metrics_history = vec![
+            MetricsRecord { frame: 100, species: 3, entropy: 2.0, ..Default::default() },
+            MetricsRecord { frame: 200, species: 5, entropy: 2.345, ..Default::default() },
+        ];
+        lab.log_event(150, "PERTURBATION", "Meteor <big>");
+        lab
+    }
+
+    #[test]
+    fn built_in_template_renders_every_section() {
+        let lab = lab_with_history();
+        let context = lab.report_context(&SimulationParams::default());
+        let markdown = render_template(&load_template("").unwrap(), &context).unwrap();
+        assert!(markdown.starts_with("# EvoLenia Experiment Report"));
+        for heading in ["## Run Info", "## Parameters", "## Final Metrics", "## Perturbation Timeline", "## Species Summary", "## Events Summary"] {
+            assert!(markdown.contains(heading), "missing {}", heading);
+        }
+        assert!(markdown.contains("- **Run ID**: run_test"));
+        assert!(markdown.contains("- **Frames**: 200"));
+        assert!(markdown.contains("- Total events: 1"));
+        assert_eq!(load_template("  ").unwrap(), DEFAULT_TEMPLATE);
+    }
+
+    #[test]
+    fn custom_template_uses_placeholders_and_helpers() {
+        let lab = lab_with_history();
+        let context = lab.report_context(&SimulationParams::default());
+        let template = "Entropy {{fixed metrics.entropy 2}} / peak {{species.peak}} @ {{species.peak_frame}}\n\
+                        {{#each timeline}}* {{frame}} {{type}}: {{details}}\n{{/each}}\
+                        {{percent 0.125 1}}\n{{param_table}}";
+        let markdown = render_template(template, &context).unwrap();
+        assert!(markdown.starts_with("Entropy 2.35 / peak 5 @ 200\n"));
+        // Markdown output: no HTML escaping of event details
+        assert!(markdown.contains("* 150 PERTURBATION: Meteor <big>\n"));
+        assert!(markdown.contains("12.5%\n| Parameter | Value |"));
+    }
+
+    #[test]
+    fn broken_or_missing_templates_are_errors() {
+        let context = serde_json::json!({});
+        assert!(render_template("{{#each timeline}}unclosed", &context).is_err());
+        assert_eq!(render_template("[{{missing.value}}]", &context).unwrap(), "[]");
+        assert!(load_template("/nonexistent/evolenia/report.md.hbs").is_err());
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    //! Tests for experiment metadata in config.json, .zenodo.json and the
+    //! report.
+
+    use crate::config::SimulationParams;
+    use crate::lab::{run_config_json, LabState};
+    use crate::metadata::{ExperimentMetadata, ZENODO_FILE};
+    use crate::report::{load_template, render_template};
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    fn metadata() -> ExperimentMetadata {
+        ExperimentMetadata {
+            author: "Doe, Jane ; Roe, Rick;".into(),
+            description: "Predator invasion sweep".into(),
+            tags: "lenia, predators,, ".into(),
+            license: " CC-BY-4.0".into(),
+        }
+    }
+
+    #[test]
+    fn lists_are_split_and_trimmed() {
+        let m = metadata();
+        assert_eq!(m.authors(), vec!["Doe, Jane", "Roe, Rick"]);
+        assert_eq!(m.tag_list(), vec!["lenia", "predators"]);
+        assert!(!m.is_empty());
+        assert!(ExperimentMetadata { tags: " , ".into(), ..Default::default() }.is_empty());
+    }
+
+    #[test]
+    fn config_and_zenodo_files_carry_metadata() {
+        let config = run_config_json("run_x", "2024-01-01 00:00:00", &SimulationParams::default(), &metadata(), DEFAULT_WORLD_SIZE);
+        assert_eq!(config["metadata"]["authors"][1], "Roe, Rick");
+        assert_eq!(config["metadata"]["license"], "CC-BY-4.0");
+
+        let dir = std::env::temp_dir().join(format!("evolenia_metadata_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        ExperimentMetadata::default().save_zenodo(&dir, "run_x").unwrap();
+        let empty_skipped = !dir.join(ZENODO_FILE).exists();
+        metadata().save_zenodo(&dir, "run_x").unwrap();
+        let zenodo: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(ZENODO_FILE)).unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(empty_skipped);
+        assert_eq!(zenodo["title"], "run_x");
+        assert_eq!(zenodo["upload_type"], "dataset");
+        assert_eq!(zenodo["creators"][0]["name"], "Doe, Jane");
+        assert_eq!(zenodo["keywords"], serde_json::json!(["lenia", "predators"]));
+        assert_eq!(zenodo["description"], "Predator invasion sweep");
+    }
+
+    #[test]
+    fn report_shows_only_filled_in_fields() {
+        let template = load_template("").unwrap();
+        let mut lab = LabState::default();
+        let plain = render_template(&template, &lab.report_context(&SimulationParams::default())).unwrap();
+        assert!(!plain.contains("**Author**") && !plain.contains("**License**"));
+
+        lab.metadata = metadata();
+        let report = render_template(&template, &lab.report_context(&SimulationParams::default())).unwrap();
+        assert!(report.contains("\n\nPredator invasion sweep\n\n## Run Info\n"));
+        assert!(report.contains("- **Author**: Doe, Jane; Roe, Rick\n"));
+        assert!(report.contains("- **Tags**: lenia, predators\n- **License**: CC-BY-4.0\n"));
+    }
+}
+
+#[cfg(test)]
+mod stats_copy_tests {
+    //! Tests for copying a metrics sample as TSV.
+
+    use crate::config::CustomMetric;
+    use crate::expr::compile_metrics;
+    use crate::lab::{metrics_tsv, MetricsRecord};
+
+    #[test]
+    fn sample_copies_as_tsv_header_and_row() {
+        let custom = compile_metrics(&[CustomMetric { name: "double entropy".into(), expression: "entropy * 2".into() }]);
+        let record = MetricsRecord { frame: 42, species: 3, entropy: 1.5, avg_mu: 0.15, ..Default::default() };
+        let tsv = metrics_tsv(&record, &custom);
+        let lines: Vec<Vec<&str>> = tsv.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 2);
+        let (header, row) = (&lines[0], &lines[1]);
+        assert_eq!(header.len(), row.len());
+        let value = |name: &str| row[header.iter().position(|h| *h == name).unwrap()];
+        assert_eq!(value("frame"), "42");
+        assert_eq!(value("entropy"), "1.500");
+        assert_eq!(value("avg_mu"), "0.1500");
+        assert_eq!(value("double_entropy"), "3");
+        assert!(!tsv.contains(','));
+    }
+}
+
+#[cfg(test)]
+mod creature_tests {
+    //! Tests for creature portraits: blob tracking across the world edge,
+    //! capture, GIF output and the gallery listing.
+
+    use std::collections::BTreeMap;
+    use image::AnimationDecoder;
+
+    use crate::creature::{list_creatures, track_blob, PortraitCapture, PortraitSettings, PORTRAIT_SIZE};
+    use crate::theme::ColorPalette;
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    /// A disc of radius 4 centered on `(cx, cy)`, wrapping.
+    fn blob_at(cx: i32, cy: i32) -> BufferSnapshot {
+        let (w, h) = (DEFAULT_WORLD_SIZE[0] as i32, DEFAULT_WORLD_SIZE[1] as i32);
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut mass = vec![0.0; n];
+        for dy in -4..=4 {
+            for dx in -4..=4 {
+                if dx * dx + dy * dy <= 16 {
+                    mass[((cy + dy).rem_euclid(h) * w + (cx + dx).rem_euclid(w)) as usize] = 0.8;
+                }
+            }
+        }
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass,
+            energy: vec![0.5; n],
+            genome_a: [10.0, 0.15, 0.02, 0.0].into_iter().cycle().take(n * 4).collect(),
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn tracking_centers_on_the_blob_across_the_edge() {
+        let snap = blob_at(1, 20);
+        let center = track_blob(&snap, [DEFAULT_WORLD_SIZE[0] as f32 - 6.0, 26.0], 16).unwrap();
+        assert!((center[0] - 1.5).abs() < 0.5 && (center[1] - 20.5).abs() < 0.5, "{:?}", center);
+        assert!(track_blob(&blob_at(200, 200), [20.0, 20.0], 16).is_none());
+    }
+
+    #[test]
+    fn portrait_follows_a_moving_creature_and_saves_a_looping_gif() {
+        let palette = ColorPalette::default().uniforms();
+        let settings = PortraitSettings { frames: 4, spacing: 2, half: 12, delay_ms: 50 };
+        let mut capture = PortraitCapture::start(&blob_at(50, 50), [46.0, 48.0], 100, "run_x", settings, &palette).unwrap();
+        assert!((capture.info.genome[2] - 0.02).abs() < 1e-6 && capture.info.genome[0] == 10.0);
+        assert_eq!(capture.info.size, [9, 9]);
+        assert!(!capture.due(101) && capture.due(102));
+        for (frame, x) in [(102, 53), (104, 56), (106, 59)] {
+            capture.add_frame(&blob_at(x, 50), frame, &palette).unwrap();
+        }
+        assert!(capture.is_complete());
+        assert_eq!(capture.info.end_frame, 106);
+
+        let dir = std::env::temp_dir().join(format!("evolenia_creatures_{}", std::process::id()));
+        let gif = capture.save(&dir, |_| {}).unwrap();
+        let frames = image::codecs::gif::GifDecoder::new(std::io::BufReader::new(std::fs::File::open(&gif).unwrap()))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        let listed = list_creatures(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].buffer().dimensions(), (PORTRAIT_SIZE, PORTRAIT_SIZE));
+        // The creature stays in the middle of every frame
+        let middle = (PORTRAIT_SIZE / 2, PORTRAIT_SIZE / 2);
+        assert!(frames.iter().all(|f| f.buffer().get_pixel(middle.0, middle.1)[0] > 60));
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].gif, gif);
+        assert_eq!(listed[0].info.frames, 4);
+    }
+
+    #[test]
+    fn capture_stops_when_the_creature_is_lost() {
+        let palette = ColorPalette::default().uniforms();
+        let settings = PortraitSettings { half: 8, ..Default::default() };
+        assert!(PortraitCapture::start(&blob_at(100, 100), [20.0, 20.0], 0, "run_x", settings, &palette).is_none());
+        let mut capture = PortraitCapture::start(&blob_at(20, 20), [20.0, 20.0], 0, "run_x", settings, &palette).unwrap();
+        assert!(capture.add_frame(&blob_at(100, 100), 4, &palette).is_err());
+    }
+}
+
+#[cfg(test)]
+mod grid_tests {
+    //! Tests for the square / hexagonal lattice choice.
+
+    use crate::config::{ConvolutionSettings, SimulationParams, ANISOTROPY_RANGE};
+    use crate::world::{DEFAULT_WORLD_SIZE, GridType};
+
+    const HEX_ROW: f32 = 0.866_025_4;
+
+    /// CPU mirror of `lattice_offset` in indexing.wgsl.
+    fn lattice_offset(grid: GridType, y: i32, dx: i32, dy: i32) -> [f32; 2] {
+        match grid {
+            GridType::Square => [dx as f32, dy as f32],
+            GridType::Hex => {
+                let shift = 0.5 * (((y + dy) & 1) - (y & 1)) as f32;
+                [dx as f32 + shift, dy as f32 * HEX_ROW]
+            }
+        }
+    }
+
+    /// CPU mirror of `hex_neighbor` in indexing.wgsl, via the axial steps.
+    fn hex_neighbors(y: i32) -> [[i32; 2]; 6] {
+        let steps = [[1, 0], [1, -1], [0, -1], [-1, 0], [-1, 1], [0, 1]];
+        steps.map(|[dq, dr]| {
+            let q = -(y - (y & 1)) / 2 + dq; // axial q of column 0, stepped
+            let r = y + dr;
+            [q + (r - (r & 1)) / 2, dr]
+        })
+    }
+
+    #[test]
+    fn grid_names_round_trip() {
+        for g in GridType::all() {
+            assert_eq!(GridType::parse(g.name()), Some(*g));
+            assert!(g.wgsl_const().contains("HEX_GRID"));
+        }
+        assert_eq!(GridType::parse("triangle"), None);
+        assert!(GridType::Hex.supported(DEFAULT_WORLD_SIZE[0], DEFAULT_WORLD_SIZE[1]));
+        assert!(!GridType::Hex.supported(512, 511));
+    }
+
+    #[test]
+    fn params_without_grid_are_square() {
+        let json = serde_json::to_value(SimulationParams { grid: GridType::Hex, ..Default::default() }).unwrap();
+        assert_eq!(json["grid"], "hex");
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("grid");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        assert_eq!(params.grid, GridType::Square);
+    }
+
+    #[test]
+    fn hex_neighbors_are_equidistant_and_ordered() {
+        for y in [0, 1, 6, 7, -1] {
+            for (k, [dx, dy]) in hex_neighbors(y).into_iter().enumerate() {
+                let [px, py] = lattice_offset(GridType::Hex, y, dx, dy);
+                assert!(((px * px + py * py).sqrt() - 1.0).abs() < 1e-5, "row {y} neighbor {k}");
+                // Counter-clockwise from +x with y pointing down, as hex_dir
+                let angle = (-py).atan2(px).to_degrees().rem_euclid(360.0);
+                assert!((angle - 60.0 * k as f32).abs() < 1e-3, "row {y} neighbor {k}: {angle}");
+            }
+        }
+    }
+
+    #[test]
+    fn hex_neighborhood_is_symmetric() {
+        for y in [0, 1] {
+            for [dx, dy] in hex_neighbors(y) {
+                let back = hex_neighbors(y + dy);
+                assert!(back.contains(&[-dx, -dy]), "row {y} offset ({dx}, {dy})");
+            }
+        }
+    }
+
+    #[test]
+    fn square_lattice_offsets_are_unchanged() {
+        assert_eq!(lattice_offset(GridType::Square, 3, -2, 5), [-2.0, 5.0]);
+        assert!(include_str!("shaders/indexing.wgsl").contains("const HEX_ROW: f32 = 0.8660254;"));
+    }
+
+    #[test]
+    fn hex_worlds_use_the_exact_kernel() {
+        let settings = ConvolutionSettings { separable: true, compare_exact: true, kernel_lut: true, lut_bins: 64 };
+        assert_eq!(GridType::Square.convolution(settings), settings);
+        let hex = GridType::Hex.convolution(settings);
+        assert_eq!(hex.shader_mode(), 0);
+        assert_eq!(hex.kernel_lut_bins(), 0);
+    }
+
+    #[test]
+    fn hex_diffusion_is_stable_across_the_anisotropy_range() {
+        // Explicit 7-point stencil: D · (2s + 4w) / 6 ≤ 1 at the slider maximum D = 0.5
+        for a in [ANISOTROPY_RANGE.0, 1.0, ANISOTROPY_RANGE.1] {
+            let s = a.sqrt();
+            let w = 0.25 * s + 0.75 / s;
+            assert!(0.5 * (2.0 * s + 4.0 * w) / 6.0 <= 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod particles_tests {
+    //! Tests for the grazer / disperser particle layer.
+
+    use rand::SeedableRng;
+
+    use crate::config::{ParticleSettings, SimulationParams};
+    use crate::particles::{initial_particles, Particle, ParticleParams, ParticleStats, MAX_PARTICLES};
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    #[test]
+    fn gpu_structs_match_the_shader_layout() {
+        // 16 floats per particle, 8 words of uniform
+        assert_eq!(std::mem::size_of::<Particle>(), 64);
+        assert_eq!(std::mem::size_of::<ParticleParams>(), 32);
+        let shader = include_str!("shaders/compute_particles.wgsl");
+        assert!(shader.contains("_pad1: f32,"));
+        assert!(shader.contains("metabolism: f32"));
+    }
+
+    #[test]
+    fn initial_pool_is_scattered_over_the_world() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let pool = initial_particles(&mut rng, DEFAULT_WORLD_SIZE);
+        assert_eq!(pool.len(), MAX_PARTICLES as usize);
+        for p in &pool {
+            assert!((0.0..DEFAULT_WORLD_SIZE[0] as f32).contains(&p.pos[0]));
+            assert!((0.0..DEFAULT_WORLD_SIZE[1] as f32).contains(&p.pos[1]));
+            assert!((p.vel[0].hypot(p.vel[1]) - 1.0).abs() < 1e-5);
+            assert_eq!((p.energy, p.cargo), (1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn active_count_is_zero_when_off_and_capped() {
+        let mut settings = ParticleSettings { count: 50_000, ..Default::default() };
+        assert_eq!(settings.active_count(), 0);
+        settings.enabled = true;
+        assert_eq!(settings.active_count(), MAX_PARTICLES);
+        assert_eq!(ParticleParams::new(&settings, 3).count, MAX_PARTICLES);
+    }
+
+    #[test]
+    fn stats_summarize_the_pool() {
+        let particle = |energy, cargo, grazed| Particle { energy, cargo, grazed, deposited: grazed - cargo, ..Default::default() };
+        let pool = [particle(1.0, 0.4, 2.0), particle(0.5, 0.1, 1.0)];
+        let stats = ParticleStats::from_particles(&pool, 0.5);
+        assert_eq!(stats.count, 2);
+        assert!((stats.mean_energy - 0.75).abs() < 1e-6);
+        assert!((stats.loaded_fraction - 0.5).abs() < 1e-6);
+        assert!((stats.cargo - 0.5).abs() < 1e-6);
+        assert!((stats.grazed - stats.deposited - stats.cargo).abs() < 1e-6);
+        assert_eq!(ParticleStats::from_particles(&[], 0.5), ParticleStats::default());
+    }
+
+    #[test]
+    fn params_without_particles_keep_the_layer_off() {
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("particles");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        assert_eq!(params.particles, ParticleSettings::default());
+        assert!(!params.particles.enabled);
+    }
+}
+
+#[cfg(test)]
+mod flow_tests {
+    //! Tests for the flow field presets, brush, uniforms and snapshot channel.
+
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use crate::config::{FlowSettings, SimulationParams};
+    use crate::flow::{FlowField, FlowPreset};
+    use crate::state_io::{load_snapshot, resample_snapshot, save_snapshot};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn cell(field: &FlowField, x: u32, y: u32) -> [f32; 2] {
+        field.data[(y * DEFAULT_WORLD_SIZE[0] + x) as usize]
+    }
+
+    fn snapshot_with_flow(flow: Vec<f32>) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.1; n],
+            energy: vec![0.5; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow,
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn presets_stay_within_unit_speed() {
+        for &preset in FlowPreset::all() {
+            let field = FlowField::generate(preset, 0.7, 3, DEFAULT_WORLD_SIZE);
+            let max = field.max_speed();
+            assert!(max > 0.5 && max <= 1.0 + 1e-5, "{:?}: max speed {}", preset, max);
+        }
+    }
+
+    #[test]
+    fn uniform_wind_follows_the_angle() {
+        let field = FlowField::generate(FlowPreset::Uniform, std::f32::consts::FRAC_PI_2, 0, DEFAULT_WORLD_SIZE);
+        for v in [cell(&field, 0, 0), cell(&field, 300, 100)] {
+            assert!(v[0].abs() < 1e-6 && (v[1] - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn vortex_turns_around_the_center() {
+        let field = FlowField::generate(FlowPreset::Vortex, 0.0, 0, DEFAULT_WORLD_SIZE);
+        let (cx, cy) = (DEFAULT_WORLD_SIZE[0] / 2, DEFAULT_WORLD_SIZE[1] / 2);
+        let r = DEFAULT_WORLD_SIZE[0].min(DEFAULT_WORLD_SIZE[1]) / 6;
+        let right = cell(&field, cx + r, cy);
+        let below = cell(&field, cx, cy + r);
+        // Clockwise on screen (y down): east of the center flows south
+        assert!(right[1] > 0.9 && right[0].abs() < 0.05);
+        assert!(below[0] < -0.9 && below[1].abs() < 0.05);
+        // Quiet at the world edge, so the torus seam barely shows
+        assert!(cell(&field, 0, cy)[1].abs() < 0.1);
+    }
+
+    #[test]
+    fn curl_noise_is_divergence_free_and_seamless() {
+        let field = FlowField::generate(FlowPreset::Curl, 0.0, 42, DEFAULT_WORLD_SIZE);
+        let (w, h) = (DEFAULT_WORLD_SIZE[0], DEFAULT_WORLD_SIZE[1]);
+        let mut divergence = 0.0f32;
+        let mut speed = 0.0f32;
+        for y in 0..h {
+            for x in 0..w {
+                let dx = cell(&field, (x + 1) % w, y)[0] - cell(&field, (x + w - 1) % w, y)[0];
+                let dy = cell(&field, x, (y + 1) % h)[1] - cell(&field, x, (y + h - 1) % h)[1];
+                divergence += (0.5 * (dx + dy)).abs();
+                let v = cell(&field, x, y);
+                speed += v[0].hypot(v[1]);
+            }
+        }
+        // Central differences of a smooth solenoidal field: residue only
+        assert!(divergence < 1e-3 * speed, "divergence {} vs speed {}", divergence, speed);
+        // The same seed gives the same field, another seed another one
+        assert_eq!(field, FlowField::generate(FlowPreset::Curl, 0.0, 42, DEFAULT_WORLD_SIZE));
+        assert_ne!(field, FlowField::generate(FlowPreset::Curl, 0.0, 43, DEFAULT_WORLD_SIZE));
+    }
+
+    #[test]
+    fn brush_blends_toward_the_stroke_and_wraps() {
+        let mut field = FlowField::zeros(DEFAULT_WORLD_SIZE);
+        field.paint([0.5, 0.5], [3.0, 0.0], 8.0, DEFAULT_WORLD_SIZE);
+        // Full weight at the center, direction clamped to unit speed
+        assert_eq!(cell(&field, 0, 0), [1.0, 0.0]);
+        // Soft falloff, across the seam too
+        let near = cell(&field, DEFAULT_WORLD_SIZE[0] - 3, DEFAULT_WORLD_SIZE[1] - 1)[0];
+        assert!(near > 0.0 && near < 1.0);
+        assert_eq!(cell(&field, 20, 20), [0.0, 0.0]);
+
+        field.paint([0.5, 0.5], [0.0, 0.0], 8.0, DEFAULT_WORLD_SIZE);
+        assert_eq!(cell(&field, 0, 0), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn settings_scale_the_field_only_when_enabled() {
+        let mut settings = FlowSettings { strength: 0.4, resource_transport: 0.5, ..Default::default() };
+        assert_eq!(settings.velocity_scale(), 0.0);
+        assert_eq!(settings.resource_scale(), 0.0);
+        settings.enabled = true;
+        assert_eq!(settings.velocity_scale(), 0.4);
+        assert_eq!(settings.resource_scale(), 0.2);
+
+        // Configs saved before the flow field load with it off
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("flow");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        assert_eq!(params.flow, FlowSettings::default());
+    }
+
+    #[test]
+    fn shaders_add_the_flow_to_advection_and_resources() {
+        let rule = include_str!("shaders/velocity_field.wgsl");
+        assert!(rule.contains("fn with_flow("));
+        for shader in [include_str!("shaders/compute_velocity.wgsl"), include_str!("shaders/compute_evolution.wgsl")] {
+            assert!(shader.contains("var flow_field: texture_2d<f32>"));
+            assert!(shader.contains("params.flow_strength"));
+        }
+        assert!(include_str!("shaders/compute_resources.wgsl").contains("params.flow_transport"));
+        assert!(include_str!("shaders/render.wgsl").contains("fn flow_streaks("));
+    }
+
+    #[test]
+    fn snapshot_carries_the_flow_field() {
+        let flow = FlowField::generate(FlowPreset::Shear, 0.3, 0, DEFAULT_WORLD_SIZE);
+        assert_eq!(FlowField::from_flat(&flow.flat(), DEFAULT_WORLD_SIZE), Some(flow.clone()));
+        assert_eq!(FlowField::from_flat(&[0.0; 6], DEFAULT_WORLD_SIZE), None);
+
+        let path = std::env::temp_dir().join(format!("evolenia_flow_{}.snap", std::process::id()));
+        let path = path.to_str().unwrap();
+        save_snapshot(path, &snapshot_with_flow(flow.flat())).unwrap();
+        let loaded = load_snapshot(path).unwrap();
+        assert_eq!(loaded.flow, flow.flat());
+
+        // No field: saved as an empty channel
+        save_snapshot(path, &snapshot_with_flow(Vec::new())).unwrap();
+        assert!(load_snapshot(path).unwrap().flow.is_empty());
+
+        // Files from before the flow channel simply end after the resources
+        let bytes = fs::read(path).unwrap();
+        fs::write(path, &bytes[..bytes.len() - 12]).unwrap(); // length + CRC of the empty channel
+        let old = load_snapshot(path).unwrap();
+        let _ = fs::remove_file(path);
+        assert!(old.flow.is_empty());
+        assert_eq!(old.resource.len(), (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize);
+    }
+
+    #[test]
+    fn resampling_keeps_two_values_per_cell() {
+        let small = BufferSnapshot {
+            size: [2, 2],
+            mass: vec![0.0; 4],
+            energy: vec![0.0; 4],
+            genome_a: vec![0.0; 16],
+            genome_b: vec![0.0; 4],
+            resource: vec![0.0; 4],
+            flow: (0..8).map(|i| i as f32).collect(),
+            channels: BTreeMap::new(),
+        };
+        let big = resample_snapshot(&small, [4, 4]);
+        assert_eq!(big.flow.len(), 32);
+        assert_eq!(&big.flow[..4], &[0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(&big.flow[30..], &[6.0, 7.0]);
+        let none = resample_snapshot(&BufferSnapshot { flow: Vec::new(), ..small }, [4, 4]);
+        assert!(none.flow.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod rotation_tests {
+    //! Tests for the rotating world (Coriolis-like deflection).
+
+    use crate::config::{RotationSettings, SimulationParams, OMEGA_RANGE};
+    use crate::world::{DEFAULT_WORLD_SIZE, SimParams, VelocityParams};
+
+    /// CPU mirror of `coriolis` in velocity_field.wgsl.
+    fn coriolis(vel: [f32; 2], p: [f32; 2], omega: f32, center: [f32; 2]) -> [f32; 2] {
+        let size = [DEFAULT_WORLD_SIZE[0] as f32, DEFAULT_WORLD_SIZE[1] as f32];
+        let d = |a: usize| {
+            let d = (p[a] - center[a]).abs() % size[a];
+            d.min(size[a] - d)
+        };
+        let r = d(0).hypot(d(1));
+        let angle = 2.0 * omega * (std::f32::consts::FRAC_PI_2 * r / (0.5 * size[0]).hypot(0.5 * size[1])).cos();
+        let (s, c) = angle.sin_cos();
+        [vel[0] * c - vel[1] * s, vel[0] * s + vel[1] * c]
+    }
+
+    #[test]
+    fn rotation_fills_the_uniform_rows() {
+        assert_eq!(std::mem::offset_of!(SimParams, rotation_center), 64);
+        assert_eq!(std::mem::offset_of!(VelocityParams, rotation_center), 24);
+        for shader in [include_str!("shaders/compute_velocity.wgsl"), include_str!("shaders/compute_evolution.wgsl")] {
+            assert!(shader.contains("rotation_center: vec2<f32>"));
+            assert!(shader.contains("coriolis(vel,"));
+        }
+        assert!(include_str!("shaders/velocity_field.wgsl").contains("fn coriolis("));
+    }
+
+    #[test]
+    fn omega_is_zero_when_off_and_clamped() {
+        let mut rotation = RotationSettings { omega: 2.0, center: [0.25, 0.5], ..Default::default() };
+        assert_eq!(rotation.effective_omega(), 0.0);
+        rotation.enabled = true;
+        assert_eq!(rotation.effective_omega(), OMEGA_RANGE);
+        rotation.omega = -0.2;
+        assert_eq!(rotation.effective_omega(), -0.2);
+        assert_eq!(rotation.center_cells(
+ DEFAULT_WORLD_SIZE,), [DEFAULT_WORLD_SIZE[0] as f32 * 0.25, DEFAULT_WORLD_SIZE[1] as f32 * 0.5]);
+
+        // Configs saved before rotating worlds load without rotation
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut obj = json.as_object().unwrap().clone();
+        obj.remove("rotation");
+        let params: SimulationParams = serde_json::from_value(obj.into()).unwrap();
+        assert_eq!(params.rotation.effective_omega(), 0.0);
+    }
+
+    #[test]
+    fn deflection_turns_clockwise_and_fades_away_from_the_pole() {
+        let center = [100.0, 100.0];
+        // At the pole: eastward motion turns south (y down), speed kept
+        let v = coriolis([1.0, 0.0], center, 0.2, center);
+        assert!(v[1] > 0.0);
+        assert!((v[0].hypot(v[1]) - 1.0).abs() < 1e-6);
+        assert!((v[1].atan2(v[0]) - 0.4).abs() < 1e-5);
+        // Negative ω turns the other way
+        assert!(coriolis([1.0, 0.0], center, -0.2, center)[1] < 0.0);
+        // The farthest point around the torus is not deflected at all
+        let antipode = [center[0] + DEFAULT_WORLD_SIZE[0] as f32 * 0.5, center[1] + DEFAULT_WORLD_SIZE[1] as f32 * 0.5];
+        let v = coriolis([1.0, 0.0], antipode, 0.2, center);
+        assert!(v[1].abs() < 1e-5);
+        // Distance wraps: a cell just across the seam is as close as its mirror
+        let across = coriolis([1.0, 0.0], [center[0] - 110.0 + DEFAULT_WORLD_SIZE[0] as f32, center[1]], 0.2, center);
+        let mirror = coriolis([1.0, 0.0], [center[0] + 110.0, center[1]], 0.2, center);
+        assert!((across[1] - mirror[1]).abs() < 1e-5);
+    }
+}
+
+#[cfg(test)]
+mod barrier_tests {
+    //! Tests for barrier perturbations (walls, lifetime) and the divergence
+    //! metrics of the two halves they split the world into.
+
+    use std::collections::BTreeMap;
+    use crate::config::{SimulationParams, BARRIER_WIDTH};
+    use crate::perturbation::PerturbationType;
+    use crate::kernel::KERNEL_MAX_R;
+    use crate::metrics::split_divergence;
+    use crate::world::{Barrier, BufferSnapshot, DEFAULT_WORLD_SIZE, PerturbationParams};
+
+    /// Live cells everywhere, with genome `genome(x)` per column.
+    fn snapshot_with(genome: impl Fn(u32) -> [f32; 4]) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let genome_a = (0..n).flat_map(|i| genome(i as u32 % DEFAULT_WORLD_SIZE[0])).collect();
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.5; n],
+            energy: vec![0.5; n],
+            genome_a,
+            genome_b: vec![0.001; n],
+            resource: vec![0.5; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn walls_are_wider_than_the_kernel() {
+        let walls = PerturbationParams::barrier(100.0);
+        assert_eq!(walls.kind, PerturbationType::Barrier.shader_kind());
+        assert_eq!(walls.center_x, 100.0);
+        assert!(walls.radius * 2.0 == BARRIER_WIDTH);
+        assert!(BARRIER_WIDTH > KERNEL_MAX_R as f32 + 1.0);
+    }
+
+    #[test]
+    fn barrier_lifts_after_its_frames() {
+        let mut barrier = Some(Barrier { x: 0.0, frames_left: 3 });
+        let mut applied = 0;
+        while let Some(b) = barrier {
+            applied += 1;
+            barrier = b.tick();
+        }
+        assert_eq!(applied, 3);
+    }
+
+    #[test]
+    fn barrier_never_runs_continuously() {
+        let params = SimulationParams {
+            perturbation_type: PerturbationType::Barrier,
+            perturbation_continuous: true,
+            ..Default::default()
+        };
+        assert_eq!(params.continuous_perturbation_intensity(), None);
+    }
+
+    #[test]
+    fn mixed_population_has_no_divergence() {
+        let snap = snapshot_with(|x| [8.0, 0.2 + 0.1 * (x % 2) as f32, 0.05, 0.3]);
+        let d = split_divergence(&snap, DEFAULT_WORLD_SIZE[0], 0.0);
+        assert!((d.mass[0] - d.mass[1]).abs() < 1e-3);
+        assert!(d.index().unwrap() < 0.1, "index {:?}", d.index());
+    }
+
+    #[test]
+    fn separated_halves_diverge() {
+        // Split at x = 0: columns [0, W/2) form half 0, the rest half 1
+        let half = DEFAULT_WORLD_SIZE[0] / 2;
+        let snap = snapshot_with(|x| {
+            let mu = if x < half { 0.2 } else { 0.6 };
+            [8.0, mu + 0.01 * (x % 2) as f32, 0.05, 0.3]
+        });
+        let d = split_divergence(&snap, DEFAULT_WORLD_SIZE[0], 0.0);
+        assert!((d.between - 0.4).abs() < 1e-3, "between {}", d.between);
+        assert_eq!(d.species, [1, 1]);
+        assert!(d.index().unwrap() > 10.0);
+
+        // Splitting across the two populations mixes them again
+        let shifted = split_divergence(&snap, DEFAULT_WORLD_SIZE[0], half as f32 / 2.0);
+        assert!(shifted.between < 1e-3);
+    }
+
+    #[test]
+    fn empty_half_has_no_index() {
+        let mut snap = snapshot_with(|_| [8.0, 0.3, 0.05, 0.3]);
+        for (i, m) in snap.mass.iter_mut().enumerate() {
+            if (i as u32 % DEFAULT_WORLD_SIZE[0]) >= DEFAULT_WORLD_SIZE[0] / 2 {
+                *m = 0.0;
+            }
+        }
+        let d = split_divergence(&snap, DEFAULT_WORLD_SIZE[0], 0.0);
+        assert_eq!(d.mass[1], 0.0);
+        assert_eq!(d.index(), None);
+    }
+}
+
+#[cfg(test)]
+mod transplant_tests {
+    //! Tests for translocation experiments: copying a region's population
+    //! over another region, and undoing it.
+
+    use std::collections::BTreeMap;
+    use crate::transplant::{Patch, Transplant, TransplantHistory, UNDO_DEPTH};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn index(x: u32, y: u32) -> usize {
+        (y * DEFAULT_WORLD_SIZE[0] + x) as usize
+    }
+
+    /// An empty world with rich resources, and one colony of genome `mu` = 0.2
+    /// around (40, 40).
+    fn world_with_colony() -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.0; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.001; n],
+            resource: vec![0.8; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        for y in 38..=42 {
+            for x in 38..=42 {
+                let i = index(x, y);
+                snap.mass[i] = 0.6;
+                snap.energy[i] = 0.4;
+                snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&[7.0, 0.2, 0.03, 0.1]);
+                snap.genome_b[i] = 0.004;
+            }
+        }
+        snap
+    }
+
+    #[test]
+    fn paste_moves_organisms_but_not_the_environment() {
+        let mut snap = world_with_colony();
+        let desert = index(200, 100);
+        snap.resource[desert] = 0.05;
+
+        let patch = Patch::copy(&snap, [40, 40], 4);
+        assert_eq!(patch.side, 9);
+        assert!((patch.total_mass() - 25.0 * 0.6).abs() < 1e-4);
+
+        let replaced = patch.paste(&mut snap, [200, 100]);
+        assert_eq!(replaced.total_mass(), 0.0);
+        assert_eq!(snap.mass[desert], 0.6);
+        assert_eq!(snap.energy[desert], 0.4);
+        assert_eq!(snap.genome_a[desert * 4 + 1], 0.2);
+        assert_eq!(snap.genome_b[desert], 0.004);
+        assert_eq!(snap.resource[desert], 0.05);
+        // The source population stays where it was
+        assert_eq!(snap.mass[index(40, 40)], 0.6);
+    }
+
+    #[test]
+    fn patches_wrap_around_the_torus() {
+        let mut snap = world_with_colony();
+        Patch::copy(&snap, [40, 40], 2).paste(&mut snap, [0, 0]);
+        assert_eq!(snap.mass[index(DEFAULT_WORLD_SIZE[0] - 1, DEFAULT_WORLD_SIZE[1] - 1)], 0.6);
+        assert_eq!(snap.mass[index(1, 1)], 0.6);
+        assert_eq!(snap.mass[index(DEFAULT_WORLD_SIZE[0] - 3, 0)], 0.0);
+    }
+
+    #[test]
+    fn undo_restores_the_destination() {
+        let mut snap = world_with_colony();
+        let before = world_with_colony();
+        let mut history = TransplantHistory::default();
+
+        let replaced = Patch::copy(&snap, [40, 40], 8).paste(&mut snap, [44, 44]);
+        history.push(Transplant { frame: 10, from: [40, 40], to: [44, 44], replaced });
+        assert_ne!(snap.mass, before.mass);
+
+        let undone = history.undo(&mut snap).expect("one transplant to undo");
+        assert_eq!(undone.frame, 10);
+        assert_eq!(snap.mass, before.mass);
+        assert_eq!(snap.genome_a, before.genome_a);
+        assert!(history.is_empty());
+        assert!(history.undo(&mut snap).is_none());
+    }
+
+    #[test]
+    fn history_keeps_the_newest_transplants() {
+        let snap = world_with_colony();
+        let mut history = TransplantHistory::default();
+        for frame in 0..UNDO_DEPTH as u32 + 3 {
+            let replaced = Patch::copy(&snap, [0, 0], 1);
+            history.push(Transplant { frame, from: [0, 0], to: [0, 0], replaced });
+        }
+        assert_eq!(history.len(), UNDO_DEPTH);
+        let mut scratch = world_with_colony();
+        assert_eq!(history.undo(&mut scratch).unwrap().frame, UNDO_DEPTH as u32 + 2);
+    }
+}
+
+#[cfg(test)]
+mod reserve_tests {
+    //! Tests for reserve zones: rasterized masks, the GPU layout of their
+    //! parameters, and the resident-lineage statistics.
+
+    use std::collections::BTreeMap;
+    use crate::metrics::genome_distance;
+    use crate::reserves::{
+        reserve_mask, reserve_stats, reserve_uniforms, zone_mean_genome, ReserveShape, ReserveUniform, ReserveZone,
+        MAX_RESERVES,
+    };
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn index(x: u32, y: u32) -> usize {
+        (y * DEFAULT_WORLD_SIZE[0] + x) as usize
+    }
+
+    fn circle(center: [f32; 2], radius: f32) -> ReserveZone {
+        ReserveZone { shape: ReserveShape::Circle { center, radius }, ..Default::default() }
+    }
+
+    /// Live cells with genome `resident` in the left half of the world, and
+    /// a distant lineage in the right half.
+    fn two_lineages(resident: [f32; 4]) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let genome_a = (0..n)
+            .flat_map(|i| if (i as u32 % DEFAULT_WORLD_SIZE[0]) < DEFAULT_WORLD_SIZE[0] / 2 { resident } else { [4.0, 0.3, 0.07, 0.9] })
+            .collect();
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.5; n],
+            energy: vec![0.5; n],
+            genome_a,
+            genome_b: vec![0.003; n],
+            resource: vec![0.5; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn layout_matches_shader() {
+        let shader = include_str!("shaders/compute_evolution.wgsl");
+        assert!(shader.contains(&format!("const MAX_RESERVES: u32 = {MAX_RESERVES}u;")));
+        assert!(shader.contains("var<uniform> reserves: array<Reserve, MAX_RESERVES>"));
+        // Uniform array elements must be 16-byte aligned
+        assert_eq!(std::mem::size_of::<ReserveUniform>(), 32);
+    }
+
+    #[test]
+    fn circles_wrap_around_the_torus() {
+        let mask = reserve_mask(&[circle([0.0, 0.0], 0.05)], DEFAULT_WORLD_SIZE);
+        assert_eq!(mask[index(0, 0)], 1.0);
+        assert_eq!(mask[index(DEFAULT_WORLD_SIZE[0] - 2, DEFAULT_WORLD_SIZE[1] - 2)], 1.0);
+        assert_eq!(mask[index(DEFAULT_WORLD_SIZE[0] / 2, DEFAULT_WORLD_SIZE[1] / 2)], 0.0);
+    }
+
+    #[test]
+    fn polygons_use_even_odd_fill() {
+        let square = ReserveZone {
+            shape: ReserveShape::Polygon { points: vec![[0.25, 0.25], [0.75, 0.25], [0.75, 0.75], [0.25, 0.75]] },
+            ..Default::default()
+        };
+        let mask = reserve_mask(&[square], DEFAULT_WORLD_SIZE);
+        assert_eq!(mask[index(DEFAULT_WORLD_SIZE[0] / 2, DEFAULT_WORLD_SIZE[1] / 2)], 1.0);
+        assert_eq!(mask[index(DEFAULT_WORLD_SIZE[0] / 8, DEFAULT_WORLD_SIZE[1] / 2)], 0.0);
+        let area = mask.iter().filter(|&&z| z > 0.0).count() as f32;
+        let expected = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as f32 / 4.0;
+        assert!((area / expected - 1.0).abs() < 0.02, "area {area} vs {expected}");
+    }
+
+    #[test]
+    fn first_enabled_zone_wins_and_ids_follow_the_list() {
+        let disabled = ReserveZone { enabled: false, ..circle([0.5, 0.5], 0.3) };
+        let mask = reserve_mask(&[disabled, circle([0.5, 0.5], 0.1), circle([0.5, 0.5], 0.2)], DEFAULT_WORLD_SIZE);
+        assert_eq!(mask[index(DEFAULT_WORLD_SIZE[0] / 2, DEFAULT_WORLD_SIZE[1] / 2)], 2.0);
+        assert_eq!(mask[index(DEFAULT_WORLD_SIZE[0] / 2 + (DEFAULT_WORLD_SIZE[0] as f32 * 0.15) as u32, DEFAULT_WORLD_SIZE[1] / 2)], 3.0);
+
+        let many: Vec<ReserveZone> = (0..MAX_RESERVES + 2).map(|_| circle([0.5, 0.5], 0.1)).collect();
+        assert!(reserve_mask(&many[MAX_RESERVES..], DEFAULT_WORLD_SIZE).iter().any(|&z| z > 0.0));
+        let ignored = [vec![ReserveZone { enabled: false, ..Default::default() }; MAX_RESERVES], many].concat();
+        assert!(reserve_mask(&ignored, DEFAULT_WORLD_SIZE).iter().all(|&z| z == 0.0));
+        assert_eq!(reserve_uniforms(&ignored).len(), MAX_RESERVES);
+    }
+
+    #[test]
+    fn residents_are_within_tolerance() {
+        let zone = ReserveZone { resident: [8.0, 0.15, 0.03, 0.2], tolerance: 0.05, ..Default::default() };
+        assert!(zone.is_resident(&[8.0, 0.17, 0.03, 0.2]));
+        assert!(!zone.is_resident(&[8.0, 0.25, 0.03, 0.2]));
+        let d = genome_distance((8.0, 0.15, 0.03, 0.2), (8.0, 0.25, 0.03, 0.2));
+        assert!(d > zone.tolerance);
+    }
+
+    #[test]
+    fn stats_split_resident_mass_by_zone() {
+        let resident = [8.0, 0.15, 0.03, 0.2];
+        let snap = two_lineages(resident);
+        // A band over the left quarter: residents live there and beyond it
+        let zone = ReserveZone {
+            resident,
+            shape: ReserveShape::Polygon { points: vec![[0.0, 0.0], [0.25, 0.0], [0.25, 1.0], [0.0, 1.0]] },
+            ..Default::default()
+        };
+        let zones = [zone];
+        let mask = reserve_mask(&zones, DEFAULT_WORLD_SIZE);
+        let stats = reserve_stats(&snap, &mask, &zones);
+        let quarter = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1] / 4) as usize;
+        assert_eq!(stats[0].cells, quarter);
+        assert!((stats[0].inside - 0.5 * quarter as f32).abs() < 1.0);
+        assert!((stats[0].outside - 0.5 * quarter as f32).abs() < 1.0);
+
+        let mean = zone_mean_genome(&snap, &mask, 1).unwrap();
+        assert!(genome_distance((mean[0], mean[1], mean[2], mean[3]), (8.0, 0.15, 0.03, 0.2)) < 1e-4);
+        assert_eq!(zone_mean_genome(&snap, &mask, 2), None);
+    }
+}
+
+#[cfg(test)]
+mod carrying_capacity_tests {
+    //! Tests for the local carrying-capacity model and how it replaces the
+    //! global mass normalization.
+
+    use crate::config::{CarryingCapacitySettings, SimulationParams, CAPACITY_MIN};
+    use crate::world::{plan_normalization, NormalizeStep};
+
+    fn params(enabled: bool, keep_normalization: bool) -> SimulationParams {
+        SimulationParams {
+            carrying_capacity: CarryingCapacitySettings { enabled, keep_normalization, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn capacity_replaces_normalization_unless_kept() {
+        assert_eq!(plan_normalization(&params(true, false), 5, None, None), NormalizeStep::Skip);
+        assert_eq!(plan_normalization(&params(true, true), 5, None, None), NormalizeStep::Measure);
+        assert_eq!(plan_normalization(&params(false, false), 5, None, None), NormalizeStep::Measure);
+    }
+
+    #[test]
+    fn capacity_follows_resources_within_bounds() {
+        let settings = CarryingCapacitySettings { base: 0.1, per_resource: 0.8, ..Default::default() };
+        assert!((settings.capacity(0.0) - 0.1).abs() < 1e-6);
+        assert!((settings.capacity(0.5) - 0.5).abs() < 1e-6);
+        assert_eq!(settings.capacity(2.0), 1.0);
+        let barren = CarryingCapacitySettings { base: 0.0, per_resource: 0.0, ..Default::default() };
+        assert_eq!(barren.capacity(1.0), CAPACITY_MIN);
+    }
+
+    #[test]
+    fn shader_uses_the_same_floor() {
+        let shader = include_str!("shaders/compute_evolution.wgsl");
+        assert!(shader.contains(&format!("const CAPACITY_MIN: f32 = {CAPACITY_MIN};")));
+        assert!(shader.contains("params.capacity_per_resource * resource_map[i]"));
+    }
+
+    #[test]
+    fn older_configs_have_no_capacity() {
+        let json = serde_json::to_value(SimulationParams::default()).unwrap();
+        let mut object = json.as_object().unwrap().clone();
+        object.remove("carrying_capacity");
+        let params: SimulationParams = serde_json::from_value(object.into()).unwrap();
+        assert!(!params.carrying_capacity.enabled);
+    }
+}
+
+#[cfg(test)]
+mod demographic_noise_tests {
+    //! Tests for the optional demographic noise on growth.
+
+    use crate::config::{DemographicNoiseSettings, SimulationParams, NOISE_AMPLITUDE_MAX};
+
+    #[test]
+    fn noise_is_off_unless_enabled() {
+        let off = DemographicNoiseSettings { enabled: false, amplitude: 0.2, seed: 7 };
+        assert_eq!(off.effective_amplitude(), 0.0);
+        let on = DemographicNoiseSettings { enabled: true, ..off };
+        assert_eq!(on.effective_amplitude(), 0.2);
+        let loud = DemographicNoiseSettings { enabled: true, amplitude: 10.0, seed: 0 };
+        assert_eq!(loud.effective_amplitude(), NOISE_AMPLITUDE_MAX);
+    }
+
+    #[test]
+    fn shader_scales_noise_by_inverse_sqrt_mass() {
+        let shader = include_str!("shaders/compute_evolution.wgsl");
+        assert!(shader.contains("demographic_noise: f32"));
+        assert!(shader.contains("noise_seed: u32"));
+        assert!(shader.contains("rand_normal(noise_seed) / sqrt(m)"));
+    }
+
+    #[test]
+    fn older_configs_have_no_noise() {
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("demographic_noise");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.demographic_noise, DemographicNoiseSettings::default());
+        assert!(!params.demographic_noise.enabled);
+    }
+}
+
+#[cfg(test)]
+mod mutation_operator_tests {
+    //! Tests for the selectable mutation operators and per-gene rates.
+
+    use crate::config::{MutationOperator, MutationSettings, SimulationParams};
+    use crate::lab::run_config_json;
+    use crate::metadata::ExperimentMetadata;
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    #[test]
+    fn shader_ids_match_the_wgsl_constants() {
+        let shader = include_str!("shaders/compute_evolution.wgsl");
+        for (op, name) in MutationOperator::all().iter().zip(["UNIFORM", "GAUSSIAN", "CAUCHY", "JUMP", "CORRELATED"]) {
+            let line = format!("const MUTATION_{name}: u32 = {}u;", op.shader_id());
+            assert!(shader.contains(&line), "missing `{line}`");
+        }
+    }
+
+    #[test]
+    fn default_keeps_uniform_steps_on_every_gene() {
+        let settings = MutationSettings::default();
+        assert_eq!(settings.operator, MutationOperator::Uniform);
+        assert_eq!(settings.gene_rates, [1.0; 4]);
+    }
+
+    #[test]
+    fn older_configs_mutate_uniformly() {
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("mutation");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.mutation, MutationSettings::default());
+    }
+
+    #[test]
+    fn run_config_records_the_operator() {
+        let params = SimulationParams {
+            mutation: MutationSettings { operator: MutationOperator::Cauchy, gene_rates: [1.0, 0.0, 0.5, 2.0], ..Default::default() },
+            ..Default::default()
+        };
+        let config = run_config_json("run", "now", &params, &ExperimentMetadata::default(), DEFAULT_WORLD_SIZE);
+        assert_eq!(config["params"]["mutation"]["operator"], "Cauchy");
+        assert_eq!(config["params"]["mutation"]["gene_rates"][3], 2.0);
+    }
+}
+
+#[cfg(test)]
+mod gene_constraint_tests {
+    //! Tests for the per-gene bounds and locks of the mutation step.
+
+    use crate::config::{GeneConstraints, SimulationParams, GENE_LIMITS};
+
+    #[test]
+    fn default_bounds_are_the_gene_limits() {
+        let (lo, hi) = GeneConstraints::default().bounds();
+        for g in 0..4 {
+            assert_eq!((lo[g], hi[g]), GENE_LIMITS[g]);
+        }
+        assert_eq!(GeneConstraints::default().lock_mask(), 0);
+    }
+
+    #[test]
+    fn bounds_stay_ordered_within_the_limits() {
+        let constraints = GeneConstraints {
+            min: [0.0, 0.2, 0.05, 0.8],
+            max: [20.0, 0.1, 0.06, 0.5],
+            locked: [false; 4],
+        };
+        let (lo, hi) = constraints.bounds();
+        assert_eq!((lo[0], hi[0]), GENE_LIMITS[0]);
+        assert_eq!((lo[1], hi[1]), (0.2, 0.2));
+        assert_eq!((lo[2], hi[2]), (0.05, 0.06));
+        assert_eq!((lo[3], hi[3]), (0.8, 0.8));
+    }
+
+    #[test]
+    fn only_sigma_evolves() {
+        let constraints = GeneConstraints { locked: [true, true, false, true], ..Default::default() };
+        assert_eq!(constraints.lock_mask(), 0b1011);
+    }
+
+    #[test]
+    fn shader_clamps_to_the_uniform_bounds() {
+        let shader = include_str!("shaders/compute_evolution.wgsl");
+        assert!(shader.contains("clamp(genome_a_new + step, params.gene_min, params.gene_max)"));
+        assert!(shader.contains("select(mutated, genome_a_new, gene_locked())"));
+    }
+
+    #[test]
+    fn older_configs_are_unconstrained() {
+        let mut json = serde_json::to_value(SimulationParams::default()).unwrap();
+        json.as_object_mut().unwrap().remove("gene_constraints");
+        let params: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(params.gene_constraints, GeneConstraints::default());
+    }
+}
+
+#[cfg(test)]
+mod landscape_tests {
+    //! Tests for the fitness landscape probe: cloning a micro-world and
+    //! sweeping genes over a grid.
+
+    use std::collections::BTreeMap;
+    use std::sync::atomic::AtomicBool;
+
+    use crate::kernel::kernel_taps;
+    use crate::landscape::{sweep, MicroWorld, ProbeSettings, SweepAxis, PROBE_SIZE};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    /// An empty world with one colony of genome `mu` = 0.2 straddling the
+    /// (0, 0) corner.
+    fn world_with_corner_colony() -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.0; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.001; n],
+            resource: vec![0.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        for y in [DEFAULT_WORLD_SIZE[1] - 2, DEFAULT_WORLD_SIZE[1] - 1, 0, 1] {
+            for x in [DEFAULT_WORLD_SIZE[0] - 2, DEFAULT_WORLD_SIZE[0] - 1, 0, 1] {
+                let i = (y * DEFAULT_WORLD_SIZE[0] + x) as usize;
+                snap.mass[i] = 0.5;
+                snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&[7.0, 0.2, 0.03, 0.1]);
+            }
+        }
+        snap
+    }
+
+    #[test]
+    fn kernel_taps_are_normalized_and_symmetric() {
+        for r in [3.0, 7.5, 15.0] {
+            let taps = kernel_taps(r);
+            let sum: f32 = taps.iter().map(|t| t.2).sum();
+            assert!((sum - 1.0).abs() < 1e-4);
+            assert!(taps.iter().all(|&(dx, dy, _)| (dx, dy) != (0, 0)));
+            let weight = |x, y| taps.iter().find(|t| (t.0, t.1) == (x, y)).map(|t| t.2);
+            assert_eq!(weight(3, 1), weight(-1, -3));
+        }
+    }
+
+    #[test]
+    fn clone_wraps_and_takes_the_colony_genome() {
+        let world = MicroWorld::clone_from(&world_with_corner_colony(), [0, 0]);
+        assert_eq!(world.mass.len(), PROBE_SIZE * PROBE_SIZE);
+        assert!((world.total_mass() - 16.0 * 0.5).abs() < 1e-5);
+        assert_eq!(world.genome, [7.0, 0.2, 0.03, 0.1]);
+    }
+
+    #[test]
+    fn empty_clone_has_no_growth() {
+        let world = MicroWorld { mass: vec![0.0; PROBE_SIZE * PROBE_SIZE], genome: [8.0, 0.15, 0.03, 0.2] };
+        assert_eq!(world.growth(world.genome, 10), 0.0);
+    }
+
+    #[test]
+    fn sparse_clone_shrinks_in_a_dense_niche() {
+        // A lone 4×4 speck perceives almost no density: with μ far above
+        // it, growth is negative everywhere and the clone shrinks
+        let world = MicroWorld::clone_from(&world_with_corner_colony(), [0, 0]);
+        assert!(world.growth([7.0, 0.35, 0.01, 0.1], 3) < 1.0);
+    }
+
+    #[test]
+    fn sweep_covers_the_grid() {
+        let world = MicroWorld::clone_from(&world_with_corner_colony(), [0, 0]);
+        let settings = ProbeSettings {
+            x: SweepAxis { gene: 1, range: (0.1, 0.3) },
+            y: Some(SweepAxis { gene: 2, range: (0.01, 0.05) }),
+            resolution: 3,
+            steps: 1,
+            ..Default::default()
+        };
+        assert_eq!(settings.genome_at(world.genome, 2, 0), [7.0, 0.3, 0.01, 0.1]);
+        let landscape = sweep(&world, &settings, &AtomicBool::new(false), &|_| {}).unwrap();
+        assert_eq!(landscape.growth.len(), 9);
+        assert_eq!(landscape.base_genome, world.genome);
+        let (point, best) = landscape.best().unwrap();
+        assert_eq!(landscape.at(point.0, point.1), best);
+
+        let one_gene = ProbeSettings { y: None, ..settings };
+        assert_eq!(sweep(&world, &one_gene, &AtomicBool::new(false), &|_| {}).unwrap().growth.len(), 3);
+    }
+
+    #[test]
+    fn cancelled_sweep_returns_nothing() {
+        let world = MicroWorld::clone_from(&world_with_corner_colony(), [0, 0]);
+        let settings = ProbeSettings { steps: 1, ..Default::default() };
+        assert!(sweep(&world, &settings, &AtomicBool::new(true), &|_| {}).is_none());
+    }
+}
+
+#[cfg(test)]
+mod invasion_tests {
+    //! Tests for the invasion assay: seeding the mutant, measuring its share
+    //! and judging the outcome.
+
+    use std::collections::BTreeMap;
+    use crate::invasion::{inject_mutant, InvasionOutcome, InvasionResult, InvasionSample, InvasionSettings};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    const RESIDENT: [f32; 4] = [7.0, 0.2, 0.03, 0.1];
+
+    /// A world whose left half is alive with the resident genome.
+    fn resident_world() -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.5; n],
+            genome_a: RESIDENT.repeat(n),
+            genome_b: vec![0.001; n],
+            resource: vec![0.5; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        for (i, m) in snap.mass.iter_mut().enumerate() {
+            if (i as u32 % DEFAULT_WORLD_SIZE[0]) < DEFAULT_WORLD_SIZE[0] / 2 {
+                *m = 0.5;
+            }
+        }
+        snap
+    }
+
+    fn result(shares: &[(u32, f32)]) -> InvasionResult {
+        InvasionResult {
+            settings: InvasionSettings::default(),
+            frame: 0,
+            injected: 1,
+            samples: shares.iter().map(|&(step, share)| InvasionSample { step, mutant_mass: share, total_mass: 1.0 }).collect(),
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn mutant_is_rare_and_only_among_the_living() {
+        let mut snap = resident_world();
+        let settings = InvasionSettings { density: 0.02, ..Default::default() };
+        let injected = inject_mutant(&mut snap, &settings);
+        let living = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1] / 2) as f32;
+        assert!((injected as f32 / living - 0.02).abs() < 0.005);
+
+        let sample = InvasionSample::measure(&snap, &settings, 0);
+        assert!((sample.mutant_mass - injected as f32 * 0.5).abs() < 1e-2 * injected as f32);
+        assert!((sample.share() - injected as f32 / living).abs() < 1e-4);
+        for (i, g) in snap.genome_a.chunks_exact(4).enumerate() {
+            if snap.mass[i] == 0.0 {
+                assert_eq!(g, RESIDENT);
+            }
+        }
+    }
+
+    #[test]
+    fn injection_is_seeded() {
+        let settings = InvasionSettings::default();
+        let (mut a, mut b) = (resident_world(), resident_world());
+        inject_mutant(&mut a, &settings);
+        inject_mutant(&mut b, &settings);
+        assert_eq!(a.genome_a, b.genome_a);
+    }
+
+    #[test]
+    fn outcome_follows_the_share() {
+        let growing = result(&[(0, 0.01), (1000, 0.02)]);
+        assert_eq!(growing.outcome(), Some(InvasionOutcome::Invades));
+        assert!((growing.fitness().unwrap() - 2f32.ln()).abs() < 1e-4);
+
+        let shrinking = result(&[(0, 0.01), (500, 0.005)]);
+        assert_eq!(shrinking.outcome(), Some(InvasionOutcome::Declines));
+        assert!(shrinking.fitness().unwrap() < 0.0);
+
+        let lost = result(&[(0, 0.01), (500, 0.0)]);
+        assert_eq!(lost.outcome(), Some(InvasionOutcome::Lost));
+        assert_eq!(lost.fitness(), None);
+    }
+
+    #[test]
+    fn csv_has_one_row_per_sample() {
+        let dir = std::env::temp_dir().join(format!("evolenia_invasion_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("invasion_0.csv");
+        result(&[(0, 0.01), (100, 0.015)]).write_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "step,mutant_mass,total_mass,mutant_share");
+        assert_eq!(lines[2], "100,0.0150,1.0000,0.015000");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod ltee_tests {
+    //! Tests for the LTEE mode: transfer schedule, frozen samples, the
+    //! bottleneck, spawning and the archive.
+
+    use std::collections::BTreeMap;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::config::LteeSettings;
+    use crate::ltee::{dilute, list_samples, load_sample, sample_path, save_sample, spawn_sample, FossilCell, FossilSample};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    const HEAVY: [f32; 4] = [9.0, 0.2, 0.03, 0.8];
+    const LIGHT: [f32; 4] = [5.0, 0.1, 0.02, 0.1];
+
+    /// A world with one heavy cell (mass 0.9) and one light cell (mass 0.1).
+    fn two_cell_world() -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.0; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.0; n],
+            resource: vec![0.5; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        for (i, m, g) in [(10, 0.9, HEAVY), (20, 0.1, LIGHT)] {
+            snap.mass[i] = m;
+            snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&g);
+        }
+        snap
+    }
+
+    #[test]
+    fn transfers_fall_on_interval_multiples() {
+        let ltee = LteeSettings { enabled: true, interval: 1000, ..Default::default() };
+        assert!(ltee.due(999, 1000));
+        assert!(ltee.due(996, 1004));
+        assert!(!ltee.due(1000, 1004));
+        assert!(!LteeSettings { enabled: false, ..ltee }.due(999, 1000));
+        assert!(!ltee.bottleneck());
+        assert!(LteeSettings { keep: 0.1, ..ltee }.bottleneck());
+    }
+
+    #[test]
+    fn frozen_genomes_are_mass_weighted() {
+        let snap = two_cell_world();
+        let sample = FossilSample::freeze(&snap, 2000, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(sample.cells.len(), 2000);
+        assert_eq!(sample.living_cells, 2);
+        let heavy = sample.cells.iter().filter(|c| c.genome == HEAVY).count() as f32 / 2000.0;
+        assert!((heavy - 0.9).abs() < 0.03, "heavy share {heavy}");
+        let mean = sample.mean_genome();
+        assert!((mean[0] - (9.0 * heavy + 5.0 * (1.0 - heavy))).abs() < 1e-3);
+
+        let empty = BufferSnapshot { mass: vec![0.0; snap.mass.len()], ..snap };
+        assert!(FossilSample::freeze(&empty, 10, &mut StdRng::seed_from_u64(1)).is_none());
+    }
+
+    #[test]
+    fn bottleneck_keeps_about_the_survivor_fraction() {
+        let mut snap = two_cell_world();
+        snap.mass.iter_mut().take(10_000).for_each(|m| *m = 0.5);
+        let (kept, removed) = dilute(&mut snap, 0.1, &mut StdRng::seed_from_u64(7));
+        assert_eq!(kept + removed, 10_000);
+        assert!((kept as f32 / 10_000.0 - 0.1).abs() < 0.02);
+        assert_eq!(snap.mass.iter().filter(|&&m| m > 0.0).count(), kept);
+    }
+
+    #[test]
+    fn spawn_seeds_a_wrapping_disc() {
+        let mut snap = two_cell_world();
+        let sample = FossilSample {
+            version: 1,
+            run_id: String::from("run"),
+            frame: 5000,
+            transfer: 1,
+            living_cells: 1,
+            cells: vec![FossilCell { genome: LIGHT, mutation_rate: 0.004, mass: 0.3 }],
+        };
+        let spawned = spawn_sample(&mut snap, &sample, [0, 0], 2, &mut StdRng::seed_from_u64(3));
+        assert_eq!(spawned, 13);
+        let corner = ((DEFAULT_WORLD_SIZE[1] - 1) * DEFAULT_WORLD_SIZE[0] + DEFAULT_WORLD_SIZE[0] - 1) as usize;
+        assert!(snap.mass[corner] > 0.0);
+        assert_eq!(&snap.genome_a[corner * 4..corner * 4 + 4], &LIGHT);
+        assert_eq!(snap.genome_b[corner], 0.004);
+    }
+
+    #[test]
+    fn archive_round_trips_in_transfer_order() {
+        let dir = std::env::temp_dir().join(format!("evolenia_ltee_{}", std::process::id()));
+        let snap = two_cell_world();
+        let mut sample = FossilSample::freeze(&snap, 5, &mut StdRng::seed_from_u64(2)).unwrap();
+        for (transfer, frame) in [(2, 10_000), (1, 5000), (10, 50_000)] {
+            sample.transfer = transfer;
+            sample.frame = frame;
+            save_sample(&sample_path(&dir, transfer, frame), &sample).unwrap();
+        }
+        let archive = list_samples(&dir);
+        let names: Vec<_> = archive.iter().map(|p| p.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, ["transfer_001_f5000.json", "transfer_002_f10000.json", "transfer_010_f50000.json"]);
+        let loaded = load_sample(&archive[2]).unwrap();
+        assert_eq!(loaded, sample);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod arena_tests {
+    //! Tests for the head-to-head arena: mirrored placement, lineage
+    //! crediting, verdicts and the tournament table.
+
+    use crate::arena::{
+        mirror, neutral_arena, round_robin, standings, ArenaSample, ArenaSettings, Contestant, MatchOutcome,
+        MatchResult,
+    };
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    fn contestant(name: &str, genome: [f32; 4]) -> Contestant {
+        Contestant { name: name.to_string(), genome, mutation_rate: 0.001, pattern: None }
+    }
+
+    fn result(a: &str, b: &str, share_a: f32) -> MatchResult {
+        MatchResult {
+            a: a.to_string(),
+            b: b.to_string(),
+            samples: vec![ArenaSample { step: 100, mass_a: share_a, mass_b: 1.0 - share_a }],
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn placement_is_mirrored_and_seeded() {
+        let settings = ArenaSettings::default();
+        let placements = settings.placements(
+ DEFAULT_WORLD_SIZE,);
+        assert_eq!(placements, settings.placements(
+ DEFAULT_WORLD_SIZE,));
+        assert_ne!(placements, ArenaSettings { seed: 2, ..settings }.placements(
+ DEFAULT_WORLD_SIZE,));
+        for p in &placements {
+            assert!(p[0] < DEFAULT_WORLD_SIZE[0] / 2 && mirror(*p, DEFAULT_WORLD_SIZE)[0] >= DEFAULT_WORLD_SIZE[0] / 2);
+            assert_eq!(mirror(mirror(*p, DEFAULT_WORLD_SIZE), DEFAULT_WORLD_SIZE), *p);
+        }
+    }
+
+    #[test]
+    fn arena_starts_even_between_lineages() {
+        let a = contestant("a", [7.0, 0.15, 0.02, 0.1]);
+        let b = contestant("b", [12.0, 0.25, 0.05, 0.6]);
+        let snap = neutral_arena(&a, &b, &ArenaSettings::default(), DEFAULT_WORLD_SIZE);
+        let sample = ArenaSample::measure(&snap, a.genome, b.genome, 0);
+        assert!(sample.mass_a > 0.0);
+        assert!((sample.share_a() - 0.5).abs() < 1e-3, "share {}", sample.share_a());
+        let left: f32 = snap.mass.iter().enumerate().filter(|(i, _)| (*i as u32 % DEFAULT_WORLD_SIZE[0]) < DEFAULT_WORLD_SIZE[0] / 2).map(|(_, m)| m).sum();
+        assert!((left - sample.mass_a).abs() < 1e-2 * left);
+        assert_eq!(snap.mass.len(), (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize);
+    }
+
+    #[test]
+    fn descendants_are_credited_to_the_nearer_genome() {
+        let a = [7.0, 0.15, 0.02, 0.1];
+        let b = [12.0, 0.25, 0.05, 0.6];
+        let mut snap = neutral_arena(&contestant("a", a), &contestant("b", b), &ArenaSettings { seeds: 1, ..Default::default() }, DEFAULT_WORLD_SIZE);
+        for g in snap.genome_a.chunks_exact_mut(4) {
+            g.copy_from_slice(&[7.5, 0.16, 0.021, 0.15]); // a drifted a
+        }
+        let sample = ArenaSample::measure(&snap, a, b, 10);
+        assert_eq!(sample.mass_b, 0.0);
+        assert_eq!(sample.share_a(), 1.0);
+    }
+
+    #[test]
+    fn verdicts_leave_a_draw_band() {
+        assert_eq!(result("x", "y", 0.8).outcome(), Some(MatchOutcome::WinA));
+        assert_eq!(result("x", "y", 0.2).winner(), Some("y"));
+        assert_eq!(result("x", "y", 0.55).outcome(), Some(MatchOutcome::Draw));
+        assert_eq!(result("x", "y", 0.55).winner(), None);
+        let extinct = MatchResult { samples: vec![ArenaSample::default()], ..result("x", "y", 0.0) };
+        assert_eq!(extinct.outcome(), Some(MatchOutcome::Draw));
+    }
+
+    #[test]
+    fn round_robin_table_ranks_by_points() {
+        assert_eq!(round_robin(3), [(0, 1), (0, 2), (1, 2)]);
+        assert!(round_robin(1).is_empty());
+        let table = standings(&[result("x", "y", 0.9), result("x", "z", 0.5), result("y", "z", 0.1)]);
+        let names: Vec<&str> = table.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["x", "z", "y"]); // x and z tie on 1.5, by name
+        assert_eq!(table[1].points(), 1.5);
+        assert_eq!((table[0].wins, table[0].draws, table[0].losses), (1, 1, 0));
+        assert_eq!(table[2].losses, 2);
+    }
+}
+
+#[cfg(test)]
+mod speciation_tests {
+    //! Tests for speciation detection: clustering, lineage matching and the
+    //! persistence rule.
+
+    use std::collections::BTreeMap;
+    use crate::config::SpeciationSettings;
+    use crate::speciation::{genome_clusters, GenomeCluster, SpeciationTracker};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    const A: [f32; 4] = [8.0, 0.15, 0.03, 0.1];
+    const B: [f32; 4] = [8.0, 0.15, 0.03, 0.9];
+
+    fn settings(persistence: u32) -> SpeciationSettings {
+        SpeciationSettings { enabled: true, persistence, ..Default::default() }
+    }
+
+    fn cluster(centroid: [f32; 4], share: f32) -> GenomeCluster {
+        GenomeCluster { centroid, share }
+    }
+
+    #[test]
+    fn two_genomes_make_two_clusters() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![0.5; n],
+            genome_a: A.repeat(n),
+            genome_b: vec![0.001; n],
+            resource: vec![0.5; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        for i in 0..3000 {
+            snap.mass[i] = 0.5;
+            if i % 4 == 0 {
+                // a quarter of the cells, with a slightly drifted genome b
+                let b = [B[0] + 0.1, B[1], B[2], B[3] - 0.02];
+                snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&b);
+            }
+        }
+        let clusters = genome_clusters(&snap);
+        assert_eq!(clusters.len(), 2);
+        assert!((clusters[0].share - 0.75).abs() < 1e-3);
+        assert!((clusters[0].centroid[3] - A[3]).abs() < 1e-4);
+        assert!((clusters[1].centroid[3] - 0.88).abs() < 1e-4);
+    }
+
+    #[test]
+    fn persistent_split_is_reported_once() {
+        let mut tracker = SpeciationTracker::default();
+        assert!(tracker.update(&[cluster(A, 1.0)], 100, &settings(2)).is_empty());
+        let parent = tracker.lineages[0].id;
+
+        // B appears next to A: a candidate split, too recent to report.
+        let near_b = [A[0], A[1], A[2], 0.35];
+        let split = [cluster(A, 0.7), cluster(near_b, 0.3)];
+        assert!(tracker.update(&split, 200, &settings(2)).is_empty());
+        assert!(!tracker.lineages[1].confirmed);
+
+        let events = tracker.update(&split, 300, &settings(2));
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].parent, events[0].split_frame, events[0].frame), (parent, 200, 300));
+        assert_eq!(events[0].centroids, [A, near_b]);
+        assert_eq!(tracker.lineages[1].label(), format!("S{} ← S{}", events[0].child, parent));
+
+        assert!(tracker.update(&split, 400, &settings(2)).is_empty());
+        assert_eq!(tracker.detected, 1);
+    }
+
+    #[test]
+    fn transient_and_distant_clusters_are_not_speciation() {
+        let mut tracker = SpeciationTracker::default();
+        tracker.update(&[cluster(A, 1.0)], 0, &settings(3));
+        // A short-lived offshoot disappears before the persistence.
+        let near_b = [A[0], A[1], A[2], 0.35];
+        tracker.update(&[cluster(A, 0.8), cluster(near_b, 0.2)], 1, &settings(3));
+        assert!(tracker.update(&[cluster(A, 1.0)], 2, &settings(3)).is_empty());
+        assert_eq!(tracker.lineages.len(), 1);
+
+        // An unrelated genome far from every lineage has no parent.
+        tracker.update(&[cluster(A, 0.5), cluster(B, 0.5)], 3, &settings(1));
+        assert_eq!(tracker.lineages[1].parent, None);
+        // Clusters under the minimum share are ignored.
+        tracker.update(&[cluster(A, 0.98), cluster(B, 0.02)], 4, &settings(1));
+        assert_eq!(tracker.lineages.len(), 1);
+        assert_eq!(tracker.detected, 0);
+    }
+}
+
+#[cfg(test)]
+mod forensics_tests {
+    //! Tests for extinction forensics: the rewind buffer, lost lineages and
+    //! the report folder.
+
+    use std::collections::BTreeMap;
+    use crate::config::SpeciationSettings;
+    use crate::forensics::{report_dir, Extinction, ExtinctionReport, RewindBuffer};
+    use crate::lab::{EventSeverity, LabEvent, MetricsRecord};
+    use crate::speciation::{GenomeCluster, SpeciationTracker};
+    use crate::world::BufferSnapshot;
+
+    fn snapshot(mass: f32) -> BufferSnapshot {
+        BufferSnapshot {
+            size: [4, 4],
+            mass: vec![mass; 16],
+            energy: vec![0.5; 16],
+            genome_a: vec![0.1; 64],
+            genome_b: vec![0.001; 16],
+            resource: vec![1.0; 16],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    fn event(frame: u32, event_type: &str) -> LabEvent {
+        LabEvent {
+            frame,
+            time_ms: 0.0,
+            wall_time: String::new(),
+            severity: EventSeverity::Info,
+            event_type: event_type.to_string(),
+            details: format!("{event_type} at {frame}"),
+            payload: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn rewind_keeps_the_newest_snapshots() {
+        let mut rewind = RewindBuffer::default();
+        assert!(rewind.is_empty());
+        for (frame, mass) in [(100, 0.5), (200, 0.3), (300, 0.0)] {
+            rewind.push(frame, &snapshot(mass), 2);
+        }
+        assert_eq!(rewind.len(), 2);
+        assert_eq!(rewind.bytes(), (16 * 8 * 4) as u64 * 2);
+        let alive = rewind.latest_where(|s| s.mass.iter().any(|&m| m > 0.0));
+        assert_eq!(alive.map(|(frame, _)| frame), Some(200));
+        assert_eq!(rewind.latest_where(|_| true).map(|(frame, _)| frame), Some(300));
+        rewind.push(400, &snapshot(0.0), 1);
+        assert_eq!(rewind.len(), 1);
+    }
+
+    #[test]
+    fn only_established_lineages_are_lost() {
+        let settings = SpeciationSettings { enabled: true, persistence: 2, ..Default::default() };
+        let a = GenomeCluster { centroid: [8.0, 0.15, 0.03, 0.1], share: 0.5 };
+        let b = GenomeCluster { centroid: [8.0, 0.15, 0.03, 0.9], share: 0.5 };
+        let mut tracker = SpeciationTracker::default();
+        tracker.update(&[a, b], 0, &settings);
+        tracker.update(&[a], 1, &settings); // b had only one sample
+        assert!(tracker.lost.is_empty());
+        tracker.update(&[a, b], 2, &settings);
+        tracker.update(&[a, b], 3, &settings);
+        tracker.update(&[a], 4, &settings);
+        assert_eq!(tracker.lost.len(), 1);
+        assert_eq!(tracker.lost[0].centroid, b.centroid);
+    }
+
+    #[test]
+    fn report_folder_gathers_the_evidence() {
+        let run_dir = std::env::temp_dir().join(format!("evolenia_forensics_{}", std::process::id()));
+        let history: Vec<MetricsRecord> = (1..=5)
+            .map(|k| MetricsRecord { frame: k * 100, live_pixels: 500 - k * 100, ..Default::default() })
+            .collect();
+        let events = [event(50, "SEED"), event(300, "PERTURBATION"), event(500, "EXTINCTION")];
+        let snap = snapshot(0.2);
+        let report = ExtinctionReport {
+            what: Extinction::Population,
+            frame: 500,
+            run_id: "run_test",
+            history: &history[2..],
+            custom: &[],
+            conditions: &Default::default(),
+            events: events.iter().filter(|e| e.frame >= 300).collect(),
+            snapshot: Some((400, &snap)),
+        };
+        let dir = report_dir(&run_dir, 500, &report.what);
+        assert!(dir.ends_with("extinction_000500_population"));
+        let files = report.write(&dir).unwrap();
+        let names: Vec<String> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, ["diagnostics.csv", "events.jsonl", "snapshot_frame000400.snap", "README.md"]);
+
+        let csv = std::fs::read_to_string(dir.join("diagnostics.csv")).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        let jsonl = std::fs::read_to_string(dir.join("events.jsonl")).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        assert!(jsonl.contains("PERTURBATION") && !jsonl.contains("SEED"));
+        let readme = std::fs::read_to_string(dir.join("README.md")).unwrap();
+        assert!(readme.contains("# Extinction report: the whole population"));
+        assert!(readme.contains("## Events since frame 300"));
+        assert!(readme.contains("world state at frame 400"));
+        let loaded = crate::state_io::load_snapshot(&dir.join("snapshot_frame000400.snap").to_string_lossy());
+        assert!(loaded.is_err() || loaded.unwrap().mass.len() == 16);
+        std::fs::remove_dir_all(&run_dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod averages_tests {
+    //! Tests for the time-averaged fields: the running-mean weights, the
+    //! .npy writer and the export folder.
+
+    use crate::averages::{export, export_dir, field_rgba, npy_header, write_npy, FieldAverages};
+    use crate::config::FieldAverageSettings;
+    use crate::world::{AverageParams, DEFAULT_WORLD_SIZE};
+
+    #[test]
+    fn weights_average_then_decay() {
+        let settings = FieldAverageSettings { enabled: true, window: 4 };
+        let weights: Vec<f32> = (0..6).map(|n| settings.weight(n)).collect();
+        assert_eq!(weights, [1.0, 0.5, 1.0 / 3.0, 0.25, 0.25, 0.25]);
+        // Applied as the shader does, the first `window` steps give the plain mean
+        let mut mean = 0.0f32;
+        for (n, value) in [2.0f32, 4.0, 6.0, 8.0].into_iter().enumerate() {
+            mean += (value - mean) * settings.weight(n as u32);
+        }
+        assert!((mean - 5.0).abs() < 1e-6);
+        assert_eq!(FieldAverageSettings { window: 0, ..settings }.weight(10), 1.0);
+        assert_eq!(std::mem::size_of::<AverageParams>(), 16);
+    }
+
+    #[test]
+    fn npy_header_is_aligned_and_parsable() {
+        for shape in [&[3usize][..], &[512, 512], &[4, 5, 2]] {
+            let header = npy_header(shape);
+            assert_eq!(header.len() % 64, 0);
+            assert_eq!(&header[..8], b"\x93NUMPY\x01\x00");
+            assert_eq!(u16::from_le_bytes([header[8], header[9]]) as usize, header.len() - 10);
+            assert_eq!(*header.last().unwrap(), b'\n');
+        }
+        let dict = |shape: &[usize]| String::from_utf8(npy_header(shape)[10..].to_vec()).unwrap();
+        assert!(dict(&[3]).starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (3,), }"));
+        assert!(dict(&[4, 5, 2]).contains("'shape': (4, 5, 2)"));
+    }
+
+    #[test]
+    fn npy_holds_the_values() {
+        let path = std::env::temp_dir().join(format!("evolenia_npy_{}.npy", std::process::id()));
+        let values = [0.5f32, -1.0, 2.25, 0.0, 1e-3, 7.0];
+        write_npy(&path, &values, &[2, 3]).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let data: Vec<f32> = bytes[npy_header(&[2, 3]).len()..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(data, values);
+        assert!(write_npy(&path, &values, &[4, 2]).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn png_colors_span_the_range() {
+        let rgba = field_rgba(&[-1.0, 0.0, 0.5, 1.0, 2.0], (0.0, 1.0));
+        assert_eq!(rgba.len(), 20);
+        assert_eq!(rgba[0..4], rgba[4..8]); // clamped below
+        assert_eq!(rgba[12..16], rgba[16..20]); // clamped above
+        assert!(rgba[14] < 60 && rgba[12] > 240); // bright yellow at the top
+        assert!(rgba.chunks(4).all(|p| p[3] == 255));
+    }
+
+    #[test]
+    fn export_writes_arrays_images_and_sidecar() {
+        let run_dir = std::env::temp_dir().join(format!("evolenia_averages_{}", std::process::id()));
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let averages = FieldAverages {
+            size: DEFAULT_WORLD_SIZE,
+            mass: (0..n).map(|i| (i % 7) as f32 * 0.1).collect(),
+            resource: vec![0.8; n],
+            speed: vec![0.0; n],
+            samples: 250,
+        };
+        let dir = export_dir(&run_dir, 1200);
+        assert!(dir.ends_with("averages_001200"));
+        let files = export(&dir, &averages, 1200, 1000, "run_test").unwrap();
+        let names: Vec<String> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(
+            names,
+            ["mass_mean.npy", "mass_mean.png", "resource_mean.npy", "resource_mean.png", "speed_mean.npy", "speed_mean.png", "averages.json"]
+        );
+        let npy = std::fs::metadata(dir.join("mass_mean.npy")).unwrap().len() as usize;
+        assert_eq!(npy, npy_header(&[DEFAULT_WORLD_SIZE[1] as usize, DEFAULT_WORLD_SIZE[0] as usize]).len() + n * 4);
+        let png = image::open(dir.join("speed_mean.png")).unwrap();
+        assert_eq!((png.width(), png.height()), (DEFAULT_WORLD_SIZE[0], DEFAULT_WORLD_SIZE[1]));
+        let meta: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("averages.json")).unwrap()).unwrap();
+        assert_eq!(meta["samples"], 250);
+        assert_eq!(meta["window"], 1000);
+        assert_eq!(meta["shape"], serde_json::json!([DEFAULT_WORLD_SIZE[1], DEFAULT_WORLD_SIZE[0]]));
+        assert!(meta["png_ranges"]["mass"].is_array());
+        std::fs::remove_dir_all(&run_dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod vectors_tests {
+    //! Tests for the velocity field export: block means, the quiver plot
+    //! and the files written.
+
+    use crate::averages::npy_header;
+    use crate::config::VelocityExportSettings;
+    use crate::vectors::{block_means, export_velocity, quiver, speeds, velocity_stem, INDEX_FILE, VELOCITY_DIR};
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    fn uniform(vx: f32, vy: f32) -> Vec<f32> {
+        (0..DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]).flat_map(|_| [vx, vy]).collect()
+    }
+
+    #[test]
+    fn cadence_counts_samples() {
+        let settings = VelocityExportSettings { enabled: true, every: 3, ..Default::default() };
+        let due: Vec<u32> = (1..=9).filter(|&k| settings.due(k)).collect();
+        assert_eq!(due, [3, 6, 9]);
+        assert!(!VelocityExportSettings { enabled: false, ..settings }.due(3));
+        assert!(!VelocityExportSettings { npy: false, quiver: false, ..settings }.due(3));
+        assert!(VelocityExportSettings { every: 0, ..settings }.due(1));
+    }
+
+    #[test]
+    fn block_means_average_each_block() {
+        let mut velocity = uniform(0.0, 0.0);
+        // Only the top-left cell of the first block moves
+        velocity[0] = 0.64;
+        let (cols, rows, means) = block_means(&velocity, DEFAULT_WORLD_SIZE, 8);
+        assert_eq!((cols, rows), (DEFAULT_WORLD_SIZE[0].div_ceil(8), DEFAULT_WORLD_SIZE[1].div_ceil(8)));
+        assert!((means[0][0] - 0.01).abs() < 1e-6);
+        assert!(means[1..].iter().all(|m| *m == [0.0, 0.0]));
+        assert_eq!(speeds(&[3.0, 4.0, 0.0, -1.0]), [5.0, 1.0]);
+    }
+
+    #[test]
+    fn quiver_points_along_the_flow() {
+        let plot = quiver(&uniform(0.2, 0.0), DEFAULT_WORLD_SIZE, 16);
+        assert!((plot.scale - 0.9 * 16.0 / 0.2).abs() < 1e-3);
+        let is_arrow = |x: u32, y: u32| {
+            let i = ((y * DEFAULT_WORLD_SIZE[0] + x) * 4) as usize;
+            plot.rgba[i] > 200 && plot.rgba[i + 2] < 200
+        };
+        // Block centers at 8, 24, …: the shaft runs along the row through the center
+        assert!(is_arrow(2, 8) && is_arrow(13, 8));
+        assert!(!is_arrow(8, 2) && !is_arrow(8, 13));
+        // The head is at the right end: barbs go back up-left and down-left
+        assert!(is_arrow(12, 7) || is_arrow(12, 6));
+        assert!(is_arrow(12, 9) || is_arrow(12, 10));
+
+        let still = quiver(&uniform(0.0, 0.0), DEFAULT_WORLD_SIZE, 16);
+        assert_eq!(still.scale, 0.0);
+        assert!(still.rgba.chunks(4).all(|p| p[0] == p[2]));
+    }
+
+    #[test]
+    fn export_writes_array_plot_and_index() {
+        let run_dir = std::env::temp_dir().join(format!("evolenia_velocity_{}", std::process::id()));
+        let settings = VelocityExportSettings::default();
+        let velocity = uniform(0.3, -0.4);
+        let files = export_velocity(&run_dir, 500, &velocity, DEFAULT_WORLD_SIZE, &settings).unwrap();
+        assert_eq!(files, [velocity_stem(&run_dir, 500).with_extension("npy"), velocity_stem(&run_dir, 500).with_extension("png")]);
+        assert!(files[0].ends_with("velocity/velocity_f000500.npy"));
+        let npy = std::fs::read(&files[0]).unwrap();
+        let header = npy_header(&[DEFAULT_WORLD_SIZE[1] as usize, DEFAULT_WORLD_SIZE[0] as usize, 2]);
+        assert_eq!(npy.len(), header.len() + velocity.len() * 4);
+        assert_eq!(f32::from_le_bytes(npy[header.len() + 4..header.len() + 8].try_into().unwrap()), -0.4);
+
+        let npy_only = VelocityExportSettings { quiver: false, ..settings };
+        assert_eq!(export_velocity(&run_dir, 600, &velocity, DEFAULT_WORLD_SIZE, &npy_only).unwrap().len(), 1);
+        let index = std::fs::read_to_string(run_dir.join(VELOCITY_DIR).join(INDEX_FILE)).unwrap();
+        let lines: Vec<&str> = index.lines().collect();
+        assert_eq!(lines[0], "frame,max_speed,mean_speed,arrow_scale");
+        assert!(lines[1].starts_with("500,0.500000,0.500000,"));
+        assert!(lines[2].ends_with(',')); // no plot, no scale
+        std::fs::remove_dir_all(&run_dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod tape_tests {
+    //! Tests for simulation tapes: downsampling, the write/read round trip,
+    //! recovery from torn or corrupted files, and playback.
+
+    use std::collections::BTreeMap;
+    use std::io::Write;
+
+    use crate::capture::PngSequence;
+    use crate::lab::MetricsRecord;
+    use crate::tape::{
+        downsample, seek_frame, tape_path, tape_size, upsample, PlaybackResume, SequenceExport, TapeHeader, TapePlayer,
+        TapeReader, TapeRecorder, TapeSettings, TapeWriter,
+    };
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn snapshot(seed: f32) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let wave = |i: usize, k: f32| ((i as f32 * 0.013 + seed) * k).sin() * 0.5 + 0.5;
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: (0..n).map(|i| wave(i, 1.0)).collect(),
+            energy: (0..n).map(|i| wave(i, 2.0) * 3.0).collect(),
+            genome_a: (0..n * 4).map(|i| wave(i, 0.5)).collect(),
+            genome_b: (0..n).map(|i| wave(i, 0.7)).collect(),
+            resource: (0..n).map(|i| wave(i, 0.3) * 10.0).collect(),
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    fn temp_tape(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("evolenia_tape_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        tape_path(&dir, 0)
+    }
+
+    fn record(path: &std::path::Path, settings: TapeSettings, frames: &[u32]) -> TapeWriter {
+        let mut writer = TapeWriter::create(path, TapeHeader::new("run", &settings, DEFAULT_WORLD_SIZE)).unwrap();
+        for &frame in frames {
+            let metrics = MetricsRecord { frame, species: 3, entropy: 1.25, ..Default::default() };
+            writer.write_frame(frame, &metrics, &snapshot(frame as f32)).unwrap();
+        }
+        writer
+    }
+
+    #[test]
+    fn downsample_averages_scalars_and_keeps_heaviest_genome() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mut snap = BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.0; n],
+            energy: vec![1.0; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.0; n],
+            resource: vec![0.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        };
+        // Block (0, 0) of a 4× downsample: one heavy cell at (1, 2)
+        let heavy = (2 * DEFAULT_WORLD_SIZE[0] + 1) as usize;
+        snap.mass[heavy] = 1.6;
+        snap.mass[0] = 0.8;
+        snap.genome_a[heavy * 4..heavy * 4 + 4].copy_from_slice(&[0.1, 0.2, 0.3, 0.4]);
+        snap.genome_b[heavy] = 0.9;
+        let small = downsample(&snap, 4);
+        let [w, h] = tape_size(DEFAULT_WORLD_SIZE,4);
+        assert_eq!(small.mass.len(), (w * h) as usize);
+        assert!((small.mass[0] - 2.4 / 16.0).abs() < 1e-6);
+        assert_eq!(small.energy[0], 1.0);
+        assert_eq!(&small.genome_a[0..4], &[0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(small.genome_b[0], 0.9);
+        assert!(small.flow.is_empty());
+        assert_eq!(downsample(&snap, 1).mass, snap.mass);
+    }
+
+    #[test]
+    fn round_trip_is_within_quantization_error() {
+        let path = temp_tape("round_trip");
+        let settings = TapeSettings { downsample: 2, interval: 10 };
+        let writer = record(&path, settings, &[0, 10, 20]);
+        assert_eq!(writer.frames, 3);
+        assert_eq!(writer.bytes, std::fs::metadata(&path).unwrap().len());
+        writer.finish().unwrap();
+
+        let mut reader = TapeReader::open(&path).unwrap();
+        assert_eq!(reader.header.size, tape_size(DEFAULT_WORLD_SIZE,2));
+        assert_eq!(reader.index.iter().map(|e| e.0).collect::<Vec<_>>(), [0, 10, 20]);
+        let played = reader.read(1).unwrap();
+        assert_eq!(played.frame, 10);
+        assert_eq!((played.metrics.frame, played.metrics.species), (10, 3));
+        let expected = downsample(&snapshot(10.0), 2);
+        for (got, want) in [
+            (&played.snapshot.mass, &expected.mass),
+            (&played.snapshot.energy, &expected.energy),
+            (&played.snapshot.genome_a, &expected.genome_a),
+            (&played.snapshot.resource, &expected.resource),
+        ] {
+            assert_eq!(got.len(), want.len());
+            let lo = want.iter().copied().fold(f32::INFINITY, f32::min);
+            let hi = want.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let tolerance = (hi - lo) / u16::MAX as f32 + 1e-5;
+            assert!(got.iter().zip(want.iter()).all(|(g, w)| (g - w).abs() <= tolerance));
+        }
+        assert!(reader.read(3).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn torn_tape_plays_up_to_last_complete_frame() {
+        let path = temp_tape("torn");
+        record(&path, TapeSettings { downsample: 8, interval: 5 }, &[0, 5, 10]).finish().unwrap();
+        let full = std::fs::metadata(&path).unwrap().len();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(full - 100).unwrap();
+        let mut reader = TapeReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.read(1).unwrap().frame, 5);
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn corrupted_frame_fails_checksum() {
+        let path = temp_tape("corrupt");
+        record(&path, TapeSettings { downsample: 8, interval: 5 }, &[0]).finish().unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        let k = bytes.len() - 10;
+        bytes[k] ^= 0xff;
+        std::fs::File::create(&path).unwrap().write_all(&bytes).unwrap();
+        let mut reader = TapeReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 1);
+        let error = reader.read(0).err().expect("corrupted frame read");
+        assert!(error.to_string().contains("checksum"));
+
+        std::fs::write(&path, b"not a tape").unwrap();
+        assert!(TapeReader::open(&path).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn recorder_is_due_only_while_recording() {
+        let path = temp_tape("due");
+        let mut recorder = TapeRecorder { next_frame: 20, ..Default::default() };
+        assert!(!recorder.due(25));
+        recorder.writer = Some(record(&path, recorder.settings, &[]));
+        assert!(recorder.is_recording());
+        assert!(!recorder.due(19));
+        assert!(recorder.due(20) && recorder.due(21));
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn upsample_restores_block_constant_fields() {
+        let settings = TapeSettings { downsample: 4, interval: 1 };
+        let header = TapeHeader::new("run", &settings, DEFAULT_WORLD_SIZE);
+        let blocky = upsample(&downsample(&snapshot(3.0), 4), &header);
+        assert_eq!(blocky.mass.len(), (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize);
+        assert_eq!(blocky.genome_a.len(), blocky.mass.len() * 4);
+        // Block-constant fields survive the round trip (genomes exactly)
+        let again = upsample(&downsample(&blocky, 4), &header);
+        assert!(again.mass.iter().zip(&blocky.mass).all(|(a, b)| (a - b).abs() < 1e-6));
+        assert_eq!(again.genome_a, blocky.genome_a);
+        assert_eq!(blocky.mass[0], blocky.mass[(3 * DEFAULT_WORLD_SIZE[0] + 3) as usize]);
+    }
+
+    #[test]
+    fn seek_finds_last_frame_at_or_before() {
+        let index = [(100, 0), (110, 0), (120, 0)];
+        assert_eq!(seek_frame(&index, 0), 0);
+        assert_eq!(seek_frame(&index, 100), 0);
+        assert_eq!(seek_frame(&index, 119), 1);
+        assert_eq!(seek_frame(&index, 120), 2);
+        assert_eq!(seek_frame(&index, 9999), 2);
+        assert_eq!(seek_frame(&[], 5), 0);
+    }
+
+    #[test]
+    fn player_ticks_at_rate_and_follows_export() {
+        let path = temp_tape("player");
+        record(&path, TapeSettings { downsample: 16, interval: 10 }, &[0, 10, 20, 30, 40]).finish().unwrap();
+        let resume = PlaybackResume { snapshot: snapshot(0.0), frame: 77, paused: false };
+        let mut player = TapePlayer::new(TapeReader::open(&path).unwrap(), &path, resume);
+        assert!(player.needs_upload());
+        player.tick(1.0); // not playing
+        assert_eq!(player.position, 0);
+
+        player.playing = true;
+        player.fps = 10.0;
+        player.tick(0.25); // 2.5 frames due
+        assert_eq!(player.position, 2);
+        player.tick(0.05); // the half frame carried over
+        assert_eq!(player.position, 3);
+        player.tick(10.0);
+        assert_eq!((player.position, player.playing), (4, false));
+
+        player.seek(25);
+        assert_eq!((player.position, player.frame_at(player.position)), (2, 20));
+        player.shown = Some(2);
+        assert!(!player.needs_upload());
+
+        let dir = path.parent().unwrap().to_path_buf();
+        let sink = Box::new(PngSequence::create(&dir).unwrap());
+        player.export = Some(SequenceExport { path: dir.clone(), sink, next: 3, end: 4, written: 1 });
+        player.tick(0.0);
+        assert_eq!(player.position, 3);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod replay_tests {
+    //! Tests for the replay check: state comparison, tape tolerances, the
+    //! snapshot sequence and the report.
+
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+
+    use crate::replay::{
+        compare, quantization_step, report_path, snapshot_sequence, ChannelDiff, Checkpoint, ReplayResult, ReplaySource,
+    };
+    use crate::tape::{start_params_path, start_snapshot_path};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn snap(cells: usize, value: f32) -> BufferSnapshot {
+        BufferSnapshot {
+            size: [cells as u32, 1],
+            mass: vec![value; cells],
+            energy: vec![value; cells],
+            genome_a: vec![value; cells * 4],
+            genome_b: vec![value; cells],
+            resource: vec![value; cells],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    fn diff(name: &'static str, differing: usize) -> ChannelDiff {
+        ChannelDiff { name, max_diff: differing as f32 * 0.5, differing, first_cell: (differing > 0).then_some(3) }
+    }
+
+    #[test]
+    fn compare_counts_values_beyond_tolerance() {
+        let recorded = snap(8, 1.0);
+        let mut replayed = snap(8, 1.0);
+        assert!(compare(&recorded, &replayed, [0.0; 5]).iter().all(|d| d.differing == 0 && d.max_diff == 0.0));
+
+        replayed.mass[5] = 1.25;
+        replayed.genome_a[4 * 6 + 2] = 0.5; // gene 2 of cell 6
+        let diffs = compare(&recorded, &replayed, [0.0; 5]);
+        assert_eq!(diffs.iter().map(|d| d.name).collect::<Vec<_>>(), ["mass", "energy", "genome_a", "genome_b", "resource"]);
+        assert_eq!((diffs[0].differing, diffs[0].max_diff, diffs[0].first_cell), (1, 0.25, Some(5)));
+        assert_eq!((diffs[2].differing, diffs[2].first_cell), (1, Some(6)));
+        assert_eq!(diffs[1].differing, 0);
+
+        // Within a channel's own tolerance
+        let diffs = compare(&recorded, &replayed, [0.3, 0.0, 0.5, 0.0, 0.0]);
+        assert!(diffs.iter().all(|d| d.differing == 0));
+        assert_eq!(diffs[0].max_diff, 0.25);
+    }
+
+    #[test]
+    fn compare_treats_nan_and_length_mismatch() {
+        let mut recorded = snap(4, 0.0);
+        let mut replayed = snap(4, 0.0);
+        recorded.energy[1] = f32::NAN;
+        replayed.energy[1] = f32::NAN;
+        assert_eq!(compare(&recorded, &replayed, [0.0; 5])[1].differing, 0);
+        replayed.energy[2] = f32::NAN;
+        let energy = &compare(&recorded, &replayed, [1.0; 5])[1];
+        assert_eq!((energy.differing, energy.max_diff), (1, f32::INFINITY));
+
+        replayed.resource.pop();
+        assert_eq!(compare(&recorded, &replayed, [0.0; 5])[4].differing, 1);
+    }
+
+    #[test]
+    fn quantization_step_spans_range() {
+        assert_eq!(quantization_step(&[2.0, 2.0]), 0.0);
+        assert!((quantization_step(&[0.0, 0.5, 65535.0]) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn snapshot_sequence_sorts_by_frame_in_name() {
+        let dir = std::env::temp_dir().join(format!("evolenia_replay_seq_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["snapshot_frame001200.snap", "snapshot_frame000100.snap", "recording_f000600.snap", "notes.txt", "start.snap"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let frames: Vec<u32> = snapshot_sequence(&dir).unwrap().iter().map(|f| f.0).collect();
+        assert_eq!(frames, [100, 600, 1200]);
+        assert!(snapshot_sequence(&dir.join("missing")).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn result_reports_first_divergence_and_csv() {
+        let clean = Checkpoint { frame: 100, channels: vec![diff("mass", 0), diff("energy", 0)] };
+        let bad = Checkpoint { frame: 110, channels: vec![diff("mass", 2), diff("energy", 0)] };
+        let result = ReplayResult {
+            source: ReplaySource::Tape(PathBuf::from("runs/x/recording_f000100.tape")),
+            start_frame: 100,
+            size: DEFAULT_WORLD_SIZE,
+            downsample: 4,
+            checkpoints: vec![clean, bad.clone()],
+            cancelled: false,
+        };
+        assert_eq!(result.first_divergence(), Some(&bad));
+        // Cell 3 of a 4× tape is the block at world x = 12
+        assert_eq!(result.cell_xy(3), (12, 0));
+        assert_eq!(result.cell_xy(DEFAULT_WORLD_SIZE[0] as usize / 4 + 1), (4, 4));
+        assert_eq!(report_path(Path::new("runs/x"), &result.source), Path::new("runs/x/replay_check_recording_f000100.csv"));
+
+        let path = std::env::temp_dir().join(format!("evolenia_replay_{}.csv", std::process::id()));
+        result.write_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("frame,mass_max_diff,mass_differing,energy_max_diff,"));
+        assert!(lines[0].ends_with(",diverged"));
+        assert_eq!(lines[1], "100,0e0,0,0e0,0,0");
+        assert_eq!(lines[2], "110,1e0,2,0e0,0,1");
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn tape_start_files_sit_next_to_the_tape() {
+        let tape = Path::new("runs/x/recording_f000100.tape");
+        assert_eq!(start_snapshot_path(tape), Path::new("runs/x/recording_f000100.snap"));
+        assert_eq!(start_params_path(tape), Path::new("runs/x/recording_f000100.params.json"));
+    }
+}
+
+#[cfg(test)]
+mod restart_tests {
+    //! Restarts reuse the GPU buffers, so the initial state must be a pure
+    //! function of the seed.
+
+    use crate::world::{DEFAULT_WORLD_SIZE, InitialFields};
+
+    #[test]
+    fn initial_fields_follow_the_seed() {
+        let a = InitialFields::generate(Some(7), DEFAULT_WORLD_SIZE);
+        let b = InitialFields::generate(Some(7), DEFAULT_WORLD_SIZE);
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        assert_eq!(a.mass.len(), n);
+        assert_eq!(a.energy.len(), n);
+        assert_eq!(a.genome_a.len(), n * 4);
+        assert_eq!(a.genome_b.len(), n);
+        assert_eq!(a.resource.len(), n);
+        assert_eq!(a.mass, b.mass);
+        assert_eq!(a.genome_a, b.genome_a);
+        assert_eq!(a.resource, b.resource);
+        assert_ne!(a.mass, InitialFields::generate(Some(8), DEFAULT_WORLD_SIZE).mass);
+    }
+
+    #[test]
+    fn initial_fields_take_the_size_asked_for() {
+        // A resize builds the next world while the current size stays
+        let fields = InitialFields::generate(Some(7), [128, 64]);
+        assert_eq!(fields.mass.len(), 128 * 64);
+        assert_eq!(fields.genome_a.len(), 128 * 64 * 4);
+        assert!(fields.particles.iter().all(|p| p.pos[0] < 128.0 && p.pos[1] < 64.0));
+    }
+}
+
+#[cfg(test)]
+mod cell_state_tests {
+    //! The interleaved cell buffers: CPU packing in both cell layouts, and
+    //! the struct shared with the shaders.
+
+    use crate::world::{interleave_cells, split_cells, CellLayout, CellState, DEFAULT_WORLD_SIZE};
+
+    fn fields() -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let mass = (0..n).map(|i| i as f32 / n as f32).collect();
+        let energy = (0..n).map(|i| (i % 7) as f32).collect();
+        let genome_b = (0..n).map(|i| 0.001 * (i % 5) as f32).collect();
+        (mass, energy, genome_b)
+    }
+
+    #[test]
+    fn cells_round_trip_in_every_layout() {
+        let (mass, energy, genome_b) = fields();
+        for &layout in CellLayout::all() {
+            let cells = interleave_cells(layout, DEFAULT_WORLD_SIZE[0], &mass, &energy, &genome_b);
+            assert_eq!(cells.len(), mass.len());
+            let (m, e, g) = split_cells(layout, DEFAULT_WORLD_SIZE[0], &cells);
+            assert_eq!((m, e, g), (mass.clone(), energy.clone(), genome_b.clone()), "{}", layout.name());
+        }
+    }
+
+    #[test]
+    fn cells_follow_the_layout_order() {
+        let (mass, energy, genome_b) = fields();
+        let cells = interleave_cells(CellLayout::Morton, DEFAULT_WORLD_SIZE[0], &mass, &energy, &genome_b);
+        let (x, y) = (3, 2);
+        let row_major = (y * crate::world::DEFAULT_WORLD_SIZE[0] + x) as usize;
+        let cell = cells[CellLayout::Morton.index(x, y, crate::world::DEFAULT_WORLD_SIZE[0])];
+        assert_eq!(cell, CellState { mass: mass[row_major], energy: energy[row_major], mutation_rate: genome_b[row_major] });
+    }
+
+    #[test]
+    fn cell_struct_matches_the_shaders() {
+        assert_eq!(std::mem::size_of::<CellState>(), 12);
+        let shader = include_str!("shaders/indexing.wgsl");
+        let body = shader.split("struct Cell {").nth(1).and_then(|s| s.split('}').next()).expect("struct Cell");
+        let fields: Vec<&str> = body.lines().filter_map(|l| l.trim().split(':').next()).filter(|f| !f.is_empty()).collect();
+        assert_eq!(fields, ["mass", "energy", "mutation_rate"]);
+        // Only genome A is still written through a storage texture
+        assert_eq!(include_str!("shaders/compute_evolution.wgsl").matches("texture_storage_2d").count(), 1);
+        for shader in [include_str!("shaders/normalize_mass.wgsl"), include_str!("shaders/compute_perturbation.wgsl")] {
+            assert!(!shader.contains("texture_storage_2d"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod channels_tests {
+    //! The channel registry: generated bindings and declarations, cell
+    //! layout conversion, and named channels in snapshot files.
+
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use crate::channels::{
+        arrange, layout_entries, row_major, wgsl_declarations, ChannelPass, CHANNELS, CHANNEL_BINDING, TRAIL,
+    };
+    use crate::state_io::{load_snapshot, resample_snapshot, save_snapshot};
+    use crate::world::{BufferSnapshot, CellLayout, DEFAULT_WORLD_SIZE};
+
+    fn snapshot(channels: BTreeMap<String, Vec<f32>>) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.2; n],
+            energy: vec![0.5; n],
+            genome_a: vec![0.1; n * 4],
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels,
+        }
+    }
+
+    #[test]
+    fn registry_specs_are_valid() {
+        for (i, spec) in CHANNELS.iter().enumerate() {
+            assert!([1, 2, 4].contains(&spec.components), "{}: vec3 has no tight array stride", spec.name);
+            assert!(!spec.passes.is_empty(), "{} is bound nowhere", spec.name);
+            assert!(CHANNELS[..i].iter().all(|other| other.name != spec.name), "{} registered twice", spec.name);
+        }
+    }
+
+    #[test]
+    fn trail_is_declared_where_it_is_bound() {
+        let trail = wgsl_declarations(ChannelPass::Trail);
+        assert_eq!(trail, format!("@group(0) @binding({CHANNEL_BINDING}) var<storage, read_write> {TRAIL}: array<f32>;\n"));
+        assert!(wgsl_declarations(ChannelPass::Render).contains("var<storage, read> trail: array<f32>;"));
+        for pass in [ChannelPass::Evolution, ChannelPass::Trail, ChannelPass::Species, ChannelPass::Render] {
+            assert_eq!(layout_entries(pass).len(), wgsl_declarations(pass).lines().count());
+        }
+    }
+
+    #[test]
+    fn hand_wired_bindings_stay_below_the_channels() {
+        let shaders = [
+            include_str!("shaders/compute_evolution.wgsl"),
+            include_str!("shaders/compute_trail.wgsl"),
+            include_str!("shaders/compute_species.wgsl"),
+            include_str!("shaders/render.wgsl"),
+        ];
+        for shader in shaders {
+            for binding in shader.split("@binding(").skip(1) {
+                let n: u32 = binding.split(')').next().unwrap().parse().unwrap();
+                assert!(n < CHANNEL_BINDING);
+            }
+            assert!(!shader.contains("var<storage, read_write> trail") && !shader.contains("var<storage, read> trail"));
+        }
+    }
+
+    #[test]
+    fn channels_round_trip_through_the_cell_layout() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let values: Vec<f32> = (0..n * 2).map(|i| i as f32).collect();
+        for &layout in CellLayout::all() {
+            assert_eq!(row_major(layout, DEFAULT_WORLD_SIZE[0], &arrange(layout, DEFAULT_WORLD_SIZE[0], &values, 2), 2), values, "{}", layout.name());
+        }
+        let morton = arrange(CellLayout::Morton, DEFAULT_WORLD_SIZE[0], &values, 2);
+        let j = CellLayout::Morton.index(3, 1, DEFAULT_WORLD_SIZE[0]);
+        let i = (DEFAULT_WORLD_SIZE[0] + 3) as usize;
+        assert_eq!(&morton[j * 2..j * 2 + 2], &values[i * 2..i * 2 + 2]);
+    }
+
+    #[test]
+    fn named_channels_survive_save_and_load() {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        let channels = BTreeMap::from([
+            ("age".to_string(), (0..n).map(|i| i as f32).collect()),
+            ("toxin".to_string(), vec![0.25; n * 2]),
+        ]);
+        let path = "/tmp/evolenia_channels.snap";
+        save_snapshot(path, &snapshot(channels.clone())).unwrap();
+        let loaded = load_snapshot(path).unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(loaded.channels, channels);
+
+        let path = "/tmp/evolenia_no_channels.snap";
+        save_snapshot(path, &snapshot(BTreeMap::new())).unwrap();
+        let loaded = load_snapshot(path).unwrap();
+        let _ = fs::remove_file(path);
+        assert!(loaded.channels.is_empty());
+    }
+
+    #[test]
+    fn resampling_keeps_channel_width() {
+        let small = BufferSnapshot {
+            size: [2, 2],
+            mass: vec![0.0; 4],
+            energy: vec![0.0; 4],
+            genome_a: vec![0.0; 16],
+            genome_b: vec![0.0; 4],
+            resource: vec![0.0; 4],
+            flow: Vec::new(),
+            channels: BTreeMap::from([("toxin".to_string(), (0..8).map(|i| i as f32).collect())]),
+        };
+        let big = resample_snapshot(&small, [4, 4]);
+        let toxin = &big.channels["toxin"];
+        assert_eq!(toxin.len(), 32);
+        assert_eq!(&toxin[..4], &[0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(&toxin[30..], &[6.0, 7.0]);
+    }
+}
+
+#[cfg(test)]
+mod metric_plugins_tests {
+    //! Metric providers: the patch metrics, and provider metrics showing up
+    //! as record fields, CSV columns and expression names.
+
+    use std::collections::BTreeMap;
+
+    use crate::config::CustomMetric;
+    use crate::expr::compile_metric;
+    use crate::lab::{LabState, MetricsRecord};
+    use crate::metric_plugins::{compute_plugins, patch_sizes, plugin_fields, PROVIDERS};
+    use crate::world::{BufferSnapshot, DEFAULT_WORLD_SIZE};
+
+    fn snapshot(live: impl Fn(u32, u32) -> bool) -> BufferSnapshot {
+        let n = (DEFAULT_WORLD_SIZE[0] * DEFAULT_WORLD_SIZE[1]) as usize;
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: (0..n as u32).map(|i| if live(i % DEFAULT_WORLD_SIZE[0], i / DEFAULT_WORLD_SIZE[0]) { 0.5 } else { 0.0 }).collect(),
+            energy: vec![0.5; n],
+            genome_a: vec![0.0; n * 4],
+            genome_b: vec![0.003; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn patches_are_connected_across_the_edges() {
+        #[rustfmt::skip]
+        let mass = [
+            0.5, 0.0, 0.0, 0.5,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.5, 0.5, 0.0,
+            0.5, 0.0, 0.0, 0.5,
+        ];
+        let mut sizes = patch_sizes(&mass, 4, 4);
+        sizes.sort();
+        // The four corners touch through the wrap; the middle pair is apart
+        assert_eq!(sizes, [2, 4]);
+        assert!(patch_sizes(&[0.0; 16], 4, 4).is_empty());
+    }
+
+    #[test]
+    fn patch_metrics_count_separate_blobs() {
+        let blobs = snapshot(|x, y| (x < 4 && y < 4) || ((20..22).contains(&x) && (20..22).contains(&y)));
+        let values = compute_plugins(&blobs);
+        assert_eq!(values.len(), plugin_fields().len());
+        assert_eq!(&values[..2], &[2.0, 0.8]);
+        assert_eq!(&compute_plugins(&snapshot(|_, _| false))[..2], &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn provider_names_are_new_fields() {
+        let fields = plugin_fields();
+        assert_eq!(fields.len(), PROVIDERS.iter().map(|p| p.names().len()).sum::<usize>());
+        for (i, name) in fields.iter().enumerate() {
+            assert!(!MetricsRecord::FIELDS.contains(name) && !fields[..i].contains(name), "{name} is taken");
+            assert!(!name.contains(',') && !name.contains(char::is_whitespace));
+        }
+    }
+
+    #[test]
+    fn provider_metrics_are_record_fields() {
+        let snap = snapshot(|x, y| x < 4 && y < 4);
+        let mut lab = LabState::default();
+        lab.record_metrics(&crate::metrics::SimDiagnostics::from_snapshot(&snap), &snap, 10, 60.0);
+        let record = &lab.metrics_history[0];
+        assert_eq!(record.plugin(0), 1.0);
+        let names = MetricsRecord::field_names();
+        assert_eq!(names.len(), record.field_values().len());
+        let def = CustomMetric { name: "mean patch".into(), expression: "live_pixels / patch_count".into() };
+        assert_eq!(compile_metric(&def).unwrap().eval(record), 16.0);
+        // Records without provider values (older CSVs) read as NaN
+        assert!(MetricsRecord::default().plugin(0).is_nan());
+    }
+}
+
+#[cfg(test)]
+mod user_modes_tests {
+    //! User visualization modes: source checks, splicing into the render
+    //! shader and the mode numbering shared with the built-in modes.
+
+    use std::path::Path;
+
+    use crate::config::{display_adjust_slot, visualization_mode_key, SimulationParams, VIS_MODE_COUNT};
+    use crate::lab::LabState;
+    use crate::user_modes::{check_source, list_user_modes, with_user_mode, MODE_FUNCTION, USER_MODES_DIR};
+
+    const RENDER: &str = include_str!("shaders/render.wgsl");
+
+    #[test]
+    fn user_modes_follow_the_builtin_ones() {
+        let first = format!("const USER_MODE_FIRST: u32 = {}u;", VIS_MODE_COUNT);
+        assert!(RENDER.contains(&first), "render.wgsl must start user modes at VIS_MODE_COUNT");
+        assert_eq!(visualization_mode_key(VIS_MODE_COUNT + 3), "mode.user");
+    }
+
+    #[test]
+    fn mode_source_replaces_the_stub() {
+        let mode = "fn user_mode_color(c: ModeInput) -> vec3<f32> {\n    return vec3<f32>(c.energy);\n}";
+        let composed = with_user_mode(RENDER, mode);
+        assert!(composed.contains(mode));
+        assert_eq!(composed.matches(MODE_FUNCTION).count(), 1, "the stub is gone");
+        assert!(composed.contains("fn mode_color("), "the rest of the shader is kept");
+        // A shader without the stub is left as is
+        assert_eq!(with_user_mode("fn main() {}", mode), "fn main() {}");
+    }
+
+    #[test]
+    fn mode_sources_are_checked() {
+        assert!(check_source("fn user_mode_color(c: ModeInput) -> vec3<f32> { return vec3<f32>(0.0); }").is_ok());
+        assert!(check_source("fn other(c: ModeInput) -> vec3<f32> { return vec3<f32>(0.0); }").is_err());
+        let bound = "@group(0) @binding(40) var<storage, read> x: array<f32>;\nfn user_mode_color(c: ModeInput) -> vec3<f32> { return vec3<f32>(x[0]); }";
+        assert!(check_source(bound).is_err());
+    }
+
+    #[test]
+    fn bundled_modes_pass_the_checks() {
+        let paths = list_user_modes(Path::new(USER_MODES_DIR));
+        assert!(!paths.is_empty());
+        for path in paths {
+            let source = std::fs::read_to_string(&path).unwrap();
+            assert!(check_source(&source).is_ok(), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn user_modes_share_the_mass_display_adjust() {
+        assert_eq!(display_adjust_slot(VIS_MODE_COUNT), 2);
+        let mut params = SimulationParams { visualization_mode: VIS_MODE_COUNT + 1, ..Default::default() };
+        params.current_display_adjust_mut().gamma = 2.5;
+        params.visualization_mode = 2;
+        assert_eq!(params.current_display_adjust().gamma, 2.5);
+    }
+
+    #[test]
+    fn user_mode_names_follow_the_builtin_ones() {
+        let lab = LabState { user_modes: vec!["contours".to_string()], ..Default::default() };
+        assert_eq!(lab.mode_name(2), "Mass Density");
+        assert_eq!(lab.mode_name(VIS_MODE_COUNT), "contours");
+        assert_eq!(lab.mode_name(VIS_MODE_COUNT + 1), "Unknown");
+    }
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    //! Parameter schedules: keyframe interpolation, formulas in `frame`,
+    //! application to the parameters and their place in saved parameters.
+
+    use crate::config::SimulationParams;
+    use crate::schedule::{apply_schedules, param_mut, Keyframe, ParamSchedule, ScheduleCurve, SCHEDULABLE};
+
+    fn keyframes(keys: &[(u32, f32)]) -> ParamSchedule {
+        ParamSchedule {
+            param: "resource_feed_rate".into(),
+            enabled: true,
+            curve: ScheduleCurve::Keyframes(keys.iter().map(|&(frame, value)| Keyframe { frame, value }).collect()),
+        }
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_hold() {
+        // Unsorted on purpose: keyframes are ordered when evaluated
+        let s = keyframes(&[(50_000, 0.005), (0, 0.02)]);
+        assert_eq!(s.value_at(0), Some(0.02));
+        assert!((s.value_at(25_000).unwrap() - 0.0125).abs() < 1e-6);
+        assert_eq!(s.value_at(80_000), Some(0.005));
+        let late = keyframes(&[(100, 1.0), (200, 2.0)]);
+        assert_eq!(late.value_at(10), Some(1.0));
+        assert!(keyframes(&[]).check().is_err());
+    }
+
+    #[test]
+    fn formulas_are_in_frame() {
+        let s = ParamSchedule {
+            curve: ScheduleCurve::Formula("max(0.005, 0.02 - 0.015 * frame / 50000)".into()),
+            ..keyframes(&[])
+        };
+        assert!(s.check().is_ok());
+        assert!((s.value_at(25_000).unwrap() - 0.0125).abs() < 1e-6);
+        assert_eq!(s.value_at(100_000), Some(0.005));
+        let undefined = ParamSchedule { curve: ScheduleCurve::Formula("1 / frame".into()), ..s.clone() };
+        assert_eq!(undefined.value_at(0), None);
+        let bad = ParamSchedule { curve: ScheduleCurve::Formula("feed * 2".into()), ..s };
+        assert!(bad.check().is_err());
+        assert_eq!(bad.value_at(10), None);
+    }
+
+    #[test]
+    fn every_schedulable_name_is_a_parameter() {
+        let mut params = SimulationParams::default();
+        for name in SCHEDULABLE {
+            assert!(param_mut(&mut params, name).is_some(), "{name}");
+        }
+        assert!(param_mut(&mut params, "paused").is_none());
+        let unknown = ParamSchedule { param: "paused".into(), ..keyframes(&[(0, 1.0)]) };
+        assert!(unknown.check().is_err());
+    }
+
+    #[test]
+    fn enabled_schedules_set_their_parameter() {
+        let mut params = SimulationParams {
+            schedules: vec![
+                keyframes(&[(0, 0.02), (100, 0.01)]),
+                ParamSchedule { param: "mutation_rate".into(), enabled: false, ..keyframes(&[(0, 4.0)]) },
+            ],
+            ..Default::default()
+        };
+        let mutation_rate = params.mutation_rate;
+        apply_schedules(&mut params, 50);
+        assert!((params.resource_feed_rate - 0.015).abs() < 1e-6);
+        assert_eq!(params.mutation_rate, mutation_rate);
+    }
+
+    #[test]
+    fn schedules_are_saved_with_the_parameters() {
+        let params = SimulationParams { schedules: vec![ParamSchedule::default()], ..Default::default() };
+        let json = serde_json::to_string(&params).unwrap();
+        let back: SimulationParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.schedules, params.schedules);
+        // Presets from before schedules load without any
+        let mut old: serde_json::Value = serde_json::from_str(&json).unwrap();
+        old.as_object_mut().unwrap().remove("schedules");
+        assert!(serde_json::from_value::<SimulationParams>(old).unwrap().schedules.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod preset_diff_tests {
+    //! Preset diff: changed fields, selective merge, nested details.
+
+    use crate::config::SimulationParams;
+    use crate::preset_diff::{summarize, PresetDiff};
+
+    fn preset() -> SimulationParams {
+        let base = SimulationParams::default();
+        SimulationParams {
+            mutation_rate: base.mutation_rate * 2.0,
+            resource_feed_rate: 0.05,
+            bloom: crate::config::BloomSettings { enabled: !base.bloom.enabled, ..base.bloom },
+            ..base
+        }
+    }
+
+    #[test]
+    fn lists_only_changed_fields() {
+        let current = SimulationParams::default();
+        let diff = PresetDiff::new("p", &current, &preset());
+        let mut fields = diff.selected();
+        fields.sort();
+        assert_eq!(fields, ["bloom", "mutation_rate", "resource_feed_rate"]);
+        assert!(PresetDiff::new("same", &current, &current.clone()).is_empty());
+    }
+
+    #[test]
+    fn merge_applies_only_selected_fields() {
+        let current = SimulationParams { time_step: 0.123, ..SimulationParams::default() };
+        let mut diff = PresetDiff::new("p", &current, &preset());
+        diff.select_all(false);
+        diff.changes.iter_mut().find(|c| c.field == "mutation_rate").unwrap().apply = true;
+        let merged = diff.merged(&current).unwrap();
+        assert_eq!(merged.mutation_rate, preset().mutation_rate);
+        assert_eq!(merged.resource_feed_rate, current.resource_feed_rate);
+        assert_eq!(merged.bloom.enabled, current.bloom.enabled);
+        // Unselected fields keep their current value even though they differ.
+        assert_eq!(merged.time_step, 0.123);
+        diff.select_all(false);
+        assert_eq!(diff.merged(&current).unwrap().mutation_rate, current.mutation_rate);
+    }
+
+    #[test]
+    fn details_name_nested_leaves() {
+        let diff = PresetDiff::new("p", &SimulationParams::default(), &preset());
+        let bloom = diff.changes.iter().find(|c| c.field == "bloom").unwrap();
+        let details = bloom.details();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].0, "bloom.enabled");
+    }
+
+    #[test]
+    fn summaries_round_and_truncate() {
+        assert_eq!(summarize(&serde_json::json!(0.019999999552965164)), "0.02");
+        assert_eq!(summarize(&serde_json::json!("a")), "a");
+        let long = summarize(&serde_json::json!("x".repeat(100)));
+        assert!(long.ends_with('…') && long.chars().count() < 60);
+    }
+}
+
+#[cfg(test)]
+mod param_lock_tests {
+    //! Parameter locks and safety mode: which edits are put back.
+
+    use crate::config::SimulationParams;
+    use crate::param_lock::{enforce, field_names, ParamLocks, FREE_FIELDS};
+
+    fn edited() -> SimulationParams {
+        SimulationParams {
+            mutation_rate: 2.0,
+            visualization_mode: 3,
+            ..SimulationParams::default()
+        }
+    }
+
+    #[test]
+    fn free_fields_are_real_fields() {
+        let names = field_names(&SimulationParams::default());
+        for field in FREE_FIELDS {
+            assert!(names.iter().any(|n| n == field), "{field}");
+        }
+    }
+
+    #[test]
+    fn unlocked_edits_pass() {
+        let before = SimulationParams::default();
+        let mut params = edited();
+        assert!(enforce(&ParamLocks::default(), true, &before, &mut params).is_none());
+        assert_eq!(params.mutation_rate, 2.0);
+    }
+
+    #[test]
+    fn locked_field_is_put_back() {
+        let locks = ParamLocks { locked: ["mutation_rate".to_string()].into(), ..Default::default() };
+        let before = SimulationParams::default();
+        let mut params = edited();
+        let blocked = enforce(&locks, false, &before, &mut params).unwrap();
+        assert_eq!(params.mutation_rate, before.mutation_rate);
+        assert_eq!(params.visualization_mode, 3);
+        assert!(!blocked.all);
+        assert_eq!(blocked.attempted["mutation_rate"], serde_json::json!(2.0));
+    }
+
+    #[test]
+    fn safety_mode_blocks_dynamics_during_runs() {
+        let mut locks = ParamLocks { safety_mode: true, ..Default::default() };
+        assert!(!locks.is_engaged(false));
+        assert!(locks.blocks("mutation_rate", true));
+        assert!(!locks.blocks("visualization_mode", true));
+        assert!(!locks.blocks("mutation_rate", false));
+
+        let before = SimulationParams::default();
+        let mut params = SimulationParams { mutation_rate: 2.0, ..before.clone() };
+        assert!(enforce(&locks, true, &before, &mut params).unwrap().all);
+        assert_eq!(params.mutation_rate, before.mutation_rate);
+
+        locks.unlocked = true;
+        assert!(!locks.is_engaged(true));
+        let mut params = SimulationParams { mutation_rate: 2.0, ..before.clone() };
+        assert!(enforce(&locks, true, &before, &mut params).is_none());
+    }
+}
+
+#[cfg(test)]
+mod conditions_tests {
+    //! Named conditions: spans, frame labels and the metrics.csv column.
+
+    use crate::conditions::{csv_label, Conditions};
+    use crate::lab::{write_metrics_csv, MetricsRecord};
+
+    fn two_conditions() -> Conditions {
+        let mut conditions = Conditions::default();
+        conditions.begin("baseline", 100);
+        conditions.begin(" post-drought ", 300);
+        conditions
+    }
+
+    #[test]
+    fn beginning_ends_the_current_condition() {
+        let mut conditions = two_conditions();
+        assert_eq!(conditions.spans[0].end, Some(300));
+        assert_eq!(conditions.current().unwrap().name, "post-drought");
+        assert_eq!(conditions.end(500).unwrap().end, Some(500));
+        assert!(conditions.current().is_none());
+        assert!(conditions.end(600).is_none());
+    }
+
+    #[test]
+    fn frames_get_the_label_of_their_span() {
+        let conditions = two_conditions();
+        assert_eq!(conditions.label_at(50), None);
+        assert_eq!(conditions.label_at(100), Some("baseline"));
+        assert_eq!(conditions.label_at(299), Some("baseline"));
+        assert_eq!(conditions.label_at(300), Some("post-drought"));
+        assert_eq!(conditions.label_at(1_000_000), Some("post-drought"));
+        assert_eq!(csv_label("a,b\nc"), "a_b_c");
+    }
+
+    #[test]
+    fn metrics_csv_gets_a_condition_column() {
+        let dir = std::env::temp_dir().join(format!("evolenia_conditions_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.csv");
+        let records: Vec<MetricsRecord> =
+            [0, 200, 400].iter().map(|&frame| MetricsRecord { frame, ..Default::default() }).collect();
+
+        write_metrics_csv(&path, &records, &[], &Conditions::default()).unwrap();
+        let plain = std::fs::read_to_string(&path).unwrap();
+        assert!(!plain.lines().next().unwrap().ends_with(",condition"));
+
+        let conditions = two_conditions();
+        write_metrics_csv(&path, &records, &[], &conditions).unwrap();
+        let labelled = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = labelled.lines().collect();
+        assert!(lines[0].ends_with(",condition"));
+        assert!(lines[1].ends_with(','));
+        assert!(lines[2].ends_with(",baseline"));
+        assert!(lines[3].ends_with(",post-drought"));
+
+        let saved = conditions.save(&dir).unwrap().unwrap();
+        let spans: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(saved).unwrap()).unwrap();
+        assert_eq!(spans[1]["end"], serde_json::Value::Null);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod condition_stats_tests {
+    //! Condition statistics: summaries, effect size, Mann-Whitney and the
+    //! report section.
+
+    use crate::condition_stats::{
+        compare, conditions_section, mann_whitney, normal_cdf, summarize, t95, ConditionStats, GroupRef,
+    };
+    use crate::lab::{LabState, MetricsRecord};
+
+    #[test]
+    fn summary_has_a_t_interval() {
+        let s = summarize(&[1.0, 2.0, 3.0, 4.0, f64::NAN]).unwrap();
+        assert_eq!(s.n, 4);
+        assert!((s.mean - 2.5).abs() < 1e-12);
+        assert!((s.std_dev - 1.290994).abs() < 1e-6);
+        let half = 3.182 * s.std_dev / 2.0;
+        assert!((s.ci_high - (2.5 + half)).abs() < 1e-9);
+        assert!(summarize(&[1.0]).is_none());
+        assert!((t95(60) - 2.000).abs() < 0.002);
+    }
+
+    #[test]
+    fn normal_cdf_matches_known_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.0) - 0.158655).abs() < 1e-5);
+    }
+
+    #[test]
+    fn mann_whitney_handles_separation_and_ties() {
+        // Fully separated samples: U = 0, small p
+        let a: Vec<f64> = (0..10).map(f64::from).collect();
+        let b: Vec<f64> = (10..20).map(f64::from).collect();
+        let m = mann_whitney(&a, &b).unwrap();
+        assert_eq!(m.u, 0.0);
+        assert!(m.p < 0.001);
+        // Identical samples: no evidence of a difference
+        let same = mann_whitney(&a, &a).unwrap();
+        assert_eq!(same.u, 50.0);
+        assert!(same.p > 0.9);
+        // All tied: zero variance, p = 1
+        assert_eq!(mann_whitney(&[1.0, 1.0], &[1.0, 1.0]).unwrap().p, 1.0);
+        assert!(mann_whitney(&[], &a).is_none());
+    }
+
+    #[test]
+    fn comparison_reports_effect_size() {
+        let c = compare("x", &[1.0, 2.0, 3.0], &[3.0, 4.0, 5.0]);
+        assert_eq!(c.effect_size, Some(2.0));
+        assert!(compare("x", &[1.0, 1.0], &[1.0, 1.0]).effect_size.is_none());
+    }
+
+    #[test]
+    fn report_section_summarizes_each_condition() {
+        let mut lab = LabState::default();
+        assert_eq!(conditions_section(&lab, &ConditionStats::default()), "");
+        lab.metrics_history = (0..10)
+            .map(|k| MetricsRecord { frame: k * 100, total_mass: if k < 5 { 10.0 } else { 20.0 } + k as f32, ..Default::default() })
+            .collect();
+        lab.conditions.begin("baseline", 0);
+        lab.conditions.begin("drought", 500);
+        let group = |condition: &str| GroupRef { run: None, run_id: lab.run_id.clone(), condition: condition.to_string() };
+        let stats = ConditionStats {
+            metrics: vec!["total_mass".to_string()],
+            a: Some(group("baseline")),
+            b: Some(group("drought")),
+            results: None,
+        };
+        let section = conditions_section(&lab, &stats);
+        assert!(section.starts_with("## Conditions"));
+        assert!(section.contains("| baseline | 0–500 | 12.0000 ["));
+        assert!(section.contains("| drought | 500–end | 27.0000 ["));
+        assert!(section.contains("baseline vs "));
+        assert!(section.contains("| total_mass |"));
+    }
+}
+
+#[cfg(test)]
+mod species_map_tests {
+    //! GPU species map: channel bindings, centroid upload and the CPU
+    //! resolution of its labels.
+
+    use std::collections::BTreeMap;
+
+    use crate::channels::{wgsl_declarations, ChannelPass, CHANNELS, SPECIES};
+    use crate::config::SimulationParams;
+    use crate::metrics::GenomeClusters;
+    use crate::territory::{
+        centroid_table, species_color, species_palette, TerritoryMap, MAX_SPECIES, NO_SPECIES, UNASSIGNED,
+    };
+    use crate::world::{BufferSnapshot, SpeciesParams, DEFAULT_WORLD_SIZE};
+
+    const A: [f32; 4] = [6.0, 0.15, 0.015, 0.1];
+    const B: [f32; 4] = [12.0, 0.35, 0.05, 0.9];
+
+    fn snapshot(genomes: &[[f32; 4]]) -> BufferSnapshot {
+        let n = genomes.len();
+        BufferSnapshot {
+            size: DEFAULT_WORLD_SIZE,
+            mass: vec![0.5; n],
+            energy: vec![0.5; n],
+            genome_a: genomes.iter().flatten().copied().collect(),
+            genome_b: vec![0.01; n],
+            resource: vec![1.0; n],
+            flow: Vec::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn species_pass_writes_the_channel_render_reads() {
+        let spec = CHANNELS.iter().find(|c| c.name == SPECIES).unwrap();
+        assert!(!spec.snapshot && spec.staging);
+        assert!(wgsl_declarations(ChannelPass::Species).contains("var<storage, read_write> species: array<f32>;"));
+        assert!(!wgsl_declarations(ChannelPass::Species).contains("trail"));
+        assert!(wgsl_declarations(ChannelPass::Render).contains("var<storage, read> species: array<f32>;"));
+    }
+
+    #[test]
+    fn centroid_table_is_padded_to_max_species() {
+        let mut clusters = GenomeClusters::new(MAX_SPECIES);
+        clusters.assign((A[0], A[1], A[2], A[3]));
+        clusters.assign((B[0], B[1], B[2], B[3]));
+        let table = centroid_table(&clusters);
+        assert_eq!(table.len(), MAX_SPECIES);
+        assert_eq!(table[0], A);
+        assert_eq!(table[1], B);
+        assert!(table[2..].iter().all(|c| *c == [0.0; 4]));
+        let params = SpeciesParams::new(&clusters);
+        assert_eq!((params.count, params.full), (2, 0));
+    }
+
+    #[test]
+    fn full_table_is_flagged_for_the_shader() {
+        let mut clusters = GenomeClusters::new(1);
+        assert!(!clusters.is_full());
+        clusters.assign((A[0], A[1], A[2], A[3]));
+        assert!(clusters.is_full());
+        assert_eq!(SpeciesParams::new(&clusters).full, 1);
+    }
+
+    #[test]
+    fn unassigned_cells_found_clusters_on_the_cpu() {
+        let mut map = TerritoryMap::default();
+        let labels = map.resolve(&[-1.0, UNASSIGNED, UNASSIGNED], &snapshot(&[A, A, B]));
+        assert_eq!(labels, vec![NO_SPECIES, 0, 1]);
+        assert_eq!(map.species().representatives().len(), 2);
+        // Labels the GPU already resolved are kept as they are
+        let labels = map.resolve(&[1.0, 0.0, -1.0], &snapshot(&[B, A, A]));
+        assert_eq!(labels, vec![1, 0, NO_SPECIES]);
+        assert_eq!(map.species_count(), 2);
+    }
+
+    #[test]
+    fn species_map_is_off_and_optional_in_saved_params() {
+        let params = SimulationParams::default();
+        assert!(!params.species_map.enabled);
+        let mut json = serde_json::to_value(&params).unwrap();
+        json.as_object_mut().unwrap().remove("species_map");
+        let loaded: SimulationParams = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.species_map, params.species_map);
+    }
+
+    #[test]
+    fn drifting_species_keeps_its_id() {
+        let mut map = TerritoryMap::default();
+        // Each step drifts by less than the threshold, ten steps by far more
+        for step in 0..10 {
+            let genome = [A[0], A[1] + 0.1 * step as f32, A[2], A[3]];
+            assert_eq!(map.label(&snapshot(&[genome, genome])), vec![0, 0], "step {step}");
+        }
+        assert_eq!(map.species_count(), 1);
+        let drifted = map.species().representatives()[0];
+        assert!((drifted.1 - (A[1] + 0.9)).abs() < 1e-5);
+        // A separate population still founds a species with its own color
+        assert_eq!(map.label(&snapshot(&[A, B])), vec![1, 2]);
+    }
+
+    #[test]
+    fn palette_matches_the_legend_colors() {
+        let wheel = crate::theme::ColorPalette::default().uniforms().cyclic;
+        let palette = species_palette(&wheel);
+        for id in [0u16, 1, 7, 63] {
+            let rgb = species_color(id, &wheel);
+            let expected = palette[id as usize].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            assert_eq!(&expected[..3], &rgb[..]);
+        }
+        assert_ne!(palette[0], palette[1]);
+    }
+}
+
+#[cfg(test)]
+mod legend_tests {
+    //! Visualization legends: scale ticks, display inversion, swatches and
+    //! screenshot compositing.
+
+    use crate::config::{DisplayAdjust, DisplayTransform, SimulationParams, SpeciesMapSettings, VIS_MODE_COUNT};
+    use crate::legend::{composite, field_at, legend_for, scale_color, ModeLegend};
+    use crate::theme::ColorPalette;
+
+    fn legend(params: &SimulationParams, areas: &[(u16, usize)]) -> Option<ModeLegend> {
+        legend_for(params, &ColorPalette::default().uniforms(), areas, 3)
+    }
+
+    /// render.wgsl display_adjust, for the round trip.
+    fn display(v: f32, a: &DisplayAdjust) -> f32 {
+        let t = ((v * a.exposure - a.range_min) / (a.range_max - a.range_min)).clamp(0.0, 1.0);
+        let t = match a.transform {
+            DisplayTransform::Log => (1.0 + 100.0 * t).ln() / 101f32.ln(),
+            _ => t,
+        };
+        t.powf(1.0 / a.gamma)
+    }
+
+    #[test]
+    fn field_at_inverts_the_display_adjustment() {
+        for transform in [DisplayTransform::Linear, DisplayTransform::Log] {
+            let adjust = DisplayAdjust { exposure: 2.0, gamma: 1.8, range_min: 0.1, range_max: 0.9, transform, ..Default::default() };
+            for d in [0.0, 0.3, 0.7, 1.0] {
+                let v = field_at(d, &adjust).unwrap();
+                assert!((display(v, &adjust) - d).abs() < 1e-4, "{:?} at {}", transform, d);
+            }
+        }
+        let equalized = DisplayAdjust { transform: DisplayTransform::Equalize, ..Default::default() };
+        assert_eq!(field_at(0.5, &equalized), None);
+    }
+
+    #[test]
+    fn continuous_modes_get_ticked_scales() {
+        let params = SimulationParams { visualization_mode: 2, ..Default::default() };
+        let Some(ModeLegend::Scale { ticks, stops, .. }) = legend(&params, &[]) else {
+            panic!("mass density is a scale");
+        };
+        assert_eq!(stops.len(), 2);
+        let labels: Vec<&str> = ticks.iter().map(|(_, l)| l.as_str()).collect();
+        assert_eq!(labels, ["0.00", "0.25", "0.50", "0.75", "1.00"]);
+
+        let flux = SimulationParams { visualization_mode: 6, ..Default::default() };
+        let Some(ModeLegend::Scale { ticks, .. }) = legend(&flux, &[]) else {
+            panic!("flux is a scale");
+        };
+        assert_eq!(ticks.first().unwrap().1, "-180°");
+        assert_eq!(ticks.last().unwrap().1, "180°");
+    }
+
+    #[test]
+    fn categorical_modes_get_swatches() {
+        let trophic = SimulationParams { visualization_mode: 7, ..Default::default() };
+        assert!(matches!(legend(&trophic, &[]), Some(ModeLegend::Swatches { entries, .. }) if entries.len() == 3));
+
+        let species = SimulationParams {
+            visualization_mode: 0,
+            species_map: SpeciesMapSettings { enabled: true, colors: true },
+            ..Default::default()
+        };
+        let Some(ModeLegend::Swatches { entries, .. }) = legend(&species, &[(4, 900), (1, 300)]) else {
+            panic!("species colors are swatches");
+        };
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].1.contains('4'));
+        // Without territories, the known species in id order
+        let Some(ModeLegend::Swatches { entries, .. }) = legend(&species, &[]) else {
+            panic!("species colors are swatches");
+        };
+        assert_eq!(entries.len(), 3);
+
+        let user = SimulationParams { visualization_mode: VIS_MODE_COUNT, ..Default::default() };
+        assert_eq!(legend(&user, &[]), None);
+    }
+
+    #[test]
+    fn scale_color_interpolates_between_stops() {
+        let stops = [[0, 0, 0], [200, 100, 0], [0, 0, 255]];
+        assert_eq!(scale_color(&stops, 0.0), [0, 0, 0]);
+        assert_eq!(scale_color(&stops, 0.25), [100, 50, 0]);
+        assert_eq!(scale_color(&stops, 1.0), [0, 0, 255]);
+        assert_eq!(scale_color(&stops, 2.0), [0, 0, 255]);
+    }
+
+    #[test]
+    fn composite_draws_in_the_bottom_left_corner() {
+        let (w, h) = (640u32, 480u32);
+        let mut rgba = vec![255u8; (w * h * 4) as usize];
+        let params = SimulationParams { visualization_mode: 1, ..Default::default() };
+        composite(&mut rgba, w, h, &legend(&params, &[]).unwrap()).unwrap();
+        let pixel = |x: u32, y: u32| &rgba[((y * w + x) * 4) as usize..((y * w + x) * 4 + 3) as usize];
+        assert_ne!(pixel(30, h - 30), [255, 255, 255]);
+        assert_eq!(pixel(w - 10, 10), [255, 255, 255]);
+        assert!(composite(&mut rgba[..16], w, h, &legend(&params, &[]).unwrap()).is_err());
+    }
+}
+
+#[cfg(test)]
+mod histogram_tests {
+    //! Histogram overlay: field selection, binned range and the shares shown
+    //! under the bars.
+
+    use crate::config::{DisplayAdjust, HistogramField, SimulationParams};
+    use crate::histogram::{max_value, FieldHistogram, HistogramParams, HISTOGRAM_BINS};
+
+    fn histogram(counts: Vec<u32>, max_value: f32) -> FieldHistogram {
+        FieldHistogram { field: HistogramField::Mass, counts, max_value, frame: 0 }
+    }
+
+    #[test]
+    fn auto_follows_the_visualization_mode() {
+        assert_eq!(HistogramField::Auto.resolve(1), HistogramField::Energy);
+        for mode in [0, 2, 5, 7] {
+            assert_eq!(HistogramField::Auto.resolve(mode), HistogramField::Mass);
+        }
+        assert_eq!(HistogramField::Resource.resolve(1), HistogramField::Resource);
+        assert_eq!(SimulationParams::default().histogram.field, HistogramField::Auto);
+    }
+
+    #[test]
+    fn mass_range_covers_the_display_window() {
+        let adjust = DisplayAdjust { exposure: 0.5, range_max: 1.0, ..Default::default() };
+        assert_eq!(max_value(HistogramField::Mass, &adjust), 2.0);
+        assert_eq!(max_value(HistogramField::Mass, &DisplayAdjust::default()), 1.0);
+        assert_eq!(max_value(HistogramField::Energy, &adjust), 1.0);
+        let params = HistogramParams::new(HistogramField::Resource, 1.0);
+        assert_eq!((params.field, params.bins), (2, HISTOGRAM_BINS));
+    }
+
+    #[test]
+    fn shares_count_whole_bins() {
+        // 4 bins of 0.25 over [0, 1]
+        let h = histogram(vec![5, 3, 0, 2], 1.0);
+        assert_eq!(h.total(), 10);
+        assert!((h.share_above(0.5) - 0.2).abs() < 1e-6);
+        assert!((h.share_above(0.4) - 0.2).abs() < 1e-6); // the bin holding 0.4 straddles it
+        assert!((h.share_above(0.0) - 1.0).abs() < 1e-6);
+        assert!((h.saturated_share() - 0.2).abs() < 1e-6);
+        assert_eq!(histogram(vec![0; 4], 1.0).share_above(0.5), 0.0);
+    }
+
+    #[test]
+    fn heights_are_relative_to_the_tallest_bin() {
+        let h = histogram(vec![100, 10, 0, 1], 1.0);
+        assert_eq!(h.heights(false), vec![1.0, 0.1, 0.0, 0.01]);
+        let log = h.heights(true);
+        assert_eq!(log[0], 1.0);
+        assert!(log[3] > 0.01 && log[1] > 0.1, "log counts lift the tails");
+        assert_eq!(histogram(vec![0; 4], 1.0).heights(true), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn shader_params_match_the_uniform() {
+        let shader = include_str!("shaders/compute_histogram.wgsl");
+        let body = shader.split("struct Params {").nth(1).and_then(|s| s.split('}').next()).expect("struct Params");
+        let fields: Vec<&str> = body.lines().filter_map(|l| l.trim().split(':').next()).filter(|f| !f.is_empty()).collect();
+        assert_eq!(fields, ["field", "bins", "max_value", "live_mass"]);
+        assert_eq!(std::mem::size_of::<HistogramParams>(), 16);
+    }
+}
+
+#[cfg(test)]
+mod render_resolution_tests {
+    //! Render resolution and world fit: scene target sizing, the zoom-1 fit
+    //! modes and their agreement with pointer mapping.
+
+    use crate::camera::{CameraState, WorldFit, DEFAULT_LETTERBOX, RENDER_SCALE_RANGE};
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    fn fitted(fit: WorldFit) -> CameraState {
+        CameraState { fit, ..Default::default() }
+    }
+
+    #[test]
+    fn render_size_scales_the_window_within_limits() {
+        let camera = CameraState { render_scale: 0.5, ..Default::default() };
+        assert_eq!(camera.render_size(3840, 2160, 8192), [1920, 1080]);
+        let camera = CameraState { render_scale: 2.0, ..Default::default() };
+        assert_eq!(camera.render_size(1920, 1080, 8192), [3840, 2160]);
+        // Over the device limit: shrunk, keeping the aspect ratio
+        assert_eq!(camera.render_size(3840, 2160, 4096), [4096, 2304]);
+        // Out-of-range settings are clamped
+        let camera = CameraState { render_scale: 100.0, ..Default::default() };
+        assert_eq!(camera.render_size(100, 50, 8192), [(100.0 * RENDER_SCALE_RANGE.1) as u32, (50.0 * RENDER_SCALE_RANGE.1) as u32]);
+        assert_eq!(CameraState::default().render_size(0, 0, 8192), [1, 1]);
+    }
+
+    #[test]
+    fn integer_fit_gives_whole_pixels_per_cell() {
+        let (w, h) = (3840, 2160); // 512² world: 4.22 px per cell when fitted
+        assert_eq!(DEFAULT_WORLD_SIZE[0], 512);
+        assert!((fitted(WorldFit::Integer).cell_px(w, h, DEFAULT_WORLD_SIZE) - 4.0).abs() < 1e-4);
+        // Below one px per cell: a whole number of cells per pixel
+        let px = fitted(WorldFit::Integer).cell_px(400, 300, DEFAULT_WORLD_SIZE);
+        assert!((1.0 / px - (1.0 / px).round()).abs() < 1e-4, "{} px per cell", px);
+        assert!(fitted(WorldFit::Integer).cell_px(400, 300, DEFAULT_WORLD_SIZE) <= fitted(WorldFit::Fit).cell_px(400, 300, DEFAULT_WORLD_SIZE));
+    }
+
+    #[test]
+    fn fill_covers_the_window_and_fit_contains_the_world() {
+        let (w, h) = (1600, 900);
+        let corners = [[0.0, 0.0], [1.0, 1.0]];
+        let fit = fitted(WorldFit::Fit);
+        let fill = fitted(WorldFit::Fill);
+        let [a, b] = corners.map(|uv| fit.screen_to_world(uv, w, h, DEFAULT_WORLD_SIZE));
+        assert!(a[0] < 0.0 && b[0] > 1.0, "fit letterboxes the wide window");
+        assert!((a[1] - 0.0).abs() < 1e-5 && (b[1] - 1.0).abs() < 1e-5);
+        let [a, b] = corners.map(|uv| fill.screen_to_world(uv, w, h, DEFAULT_WORLD_SIZE));
+        assert!((a[0] - 0.0).abs() < 1e-5 && (b[0] - 1.0).abs() < 1e-5);
+        assert!(a[1] > 0.0 && b[1] < 1.0, "fill crops the world vertically");
+        assert!((fill.cell_px(w, h, DEFAULT_WORLD_SIZE) - w as f32 / DEFAULT_WORLD_SIZE[0] as f32).abs() < 1e-4);
+        assert_eq!(DEFAULT_WORLD_SIZE[0], DEFAULT_WORLD_SIZE[1]);
+    }
+
+    #[test]
+    fn uniforms_carry_the_fitted_zoom_and_letterbox() {
+        let camera = CameraState { zoom: 2.0, letterbox: [0.0, 0.0, 0.0], ..fitted(WorldFit::Fill) };
+        let u = camera.uniforms(1600, 900, DEFAULT_WORLD_SIZE);
+        assert!((u.zoom - 2.0 * 1600.0 / 900.0).abs() < 1e-4);
+        assert_eq!(u.letterbox, [0.0, 0.0, 0.0]);
+        // Settings saved before these options load with the old view
+        let old: CameraState = serde_json::from_str(r#"{"offset":[0.0,0.0],"zoom":2.0}"#).unwrap();
+        assert_eq!((old.render_scale, old.fit, old.letterbox), (1.0, WorldFit::Fit, DEFAULT_LETTERBOX));
+        let render = include_str!("shaders/render.wgsl");
+        assert!(!render.contains("vec3<f32>(0.08, 0.08, 0.10)"), "outside color comes from the camera");
+    }
+}
+
+#[cfg(test)]
+mod analysis_window_tests {
+    //! Tests for the detachable analysis window.
+
+    use crate::camera::CameraState;
+    use crate::config::SimulationParams;
+    use crate::dock::LabTab;
+    use crate::lab::LabState;
+    use crate::lab_ui::{render_analysis_window, render_lab_ui};
+    use crate::settings::{UiSettings, WindowGeometry};
+
+    fn run_main_ui(lab: &mut LabState) {
+        let ctx = egui::Context::default();
+        let mut params = SimulationParams::default();
+        let mut camera = CameraState::default();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| render_lab_ui(ctx, &mut params, lab, &mut camera));
+    }
+
+    #[test]
+    fn detaching_moves_the_panel_out_of_the_dock() {
+        let mut lab = LabState { show_analysis_panel: true, ..Default::default() };
+        run_main_ui(&mut lab);
+        assert!(lab.dock_state.find_tab(&LabTab::Analysis).is_some());
+
+        lab.analysis_detached = true;
+        run_main_ui(&mut lab);
+        assert!(lab.dock_state.find_tab(&LabTab::Analysis).is_none());
+        assert!(lab.show_analysis_panel, "a detached panel stays shown");
+
+        lab.analysis_detached = false;
+        run_main_ui(&mut lab);
+        assert!(lab.dock_state.find_tab(&LabTab::Analysis).is_some());
+    }
+
+    #[test]
+    fn analysis_window_ui_runs_on_its_own_context() {
+        let mut lab = LabState { show_analysis_panel: true, analysis_detached: true, ..Default::default() };
+        let mut params = SimulationParams::default();
+        let ctx = egui::Context::default();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| render_analysis_window(ctx, &mut params, &mut lab));
+        assert!(lab.show_analysis_panel && lab.analysis_detached);
+    }
+
+    #[test]
+    fn detached_state_and_placement_are_saved() {
+        let geometry = WindowGeometry { width: 1280, height: 1024, position: Some([1920, 0]), maximized: false };
+        let lab = LabState { analysis_detached: true, analysis_window: Some(geometry), ..Default::default() };
+        let json = serde_json::to_string(&UiSettings::capture(&lab, &CameraState::default(), None)).unwrap();
+        let loaded: UiSettings = serde_json::from_str(&json).unwrap();
+        let mut lab2 = LabState::default();
+        loaded.apply(&mut lab2, &mut CameraState::default());
+        assert!(lab2.analysis_detached);
+        assert_eq!(lab2.analysis_window, Some(geometry));
+
+        // Settings saved before detaching existed keep the panel docked
+        let old: UiSettings = serde_json::from_str(r#"{"show_analysis_panel": true}"#).unwrap();
+        assert!(!old.analysis_detached);
+        assert!(old.analysis_window.is_none());
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    //! Tests for the frame stream (wire format, viewer feed, TCP roundtrip
+    //! between a headless run and a viewer).
+
+    use crate::capture::FrameSink;
+    use crate::stream::{
+        LinkStatus, StreamClient, StreamFrame, StreamHello, StreamMessage, StreamMetrics, StreamServer, ViewerFeed,
+        HISTORY_LIMIT, STREAM_VERSION,
+    };
+    use crate::CliOptions;
+    use std::time::{Duration, Instant};
+
+    fn frame(n: u32) -> StreamFrame {
+        StreamFrame { frame: n, width: 2, height: 1, rgba: vec![10, 20, 30, 255, 40, 50, 60, 255] }
+    }
+
+    fn metrics(frame: u32, diagnostics_frame: Option<u32>, mass: f32) -> StreamMessage {
+        StreamMessage::Metrics(StreamMetrics {
+            frame,
+            frames_total: 1000,
+            steps_per_sec: 250.0,
+            diagnostics_frame,
+            diagnostics: vec![("population/total_mass".into(), mass), ("diversity/species_count".into(), 3.0)],
+        })
+    }
+
+    /// Wait up to two seconds for `done` to hold on the client's feed.
+    fn wait_for(client: &StreamClient, done: impl Fn(&ViewerFeed) -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if done(&client.feed()) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn messages_roundtrip_through_the_wire_format() {
+        let hello = StreamMessage::Hello(StreamHello { version: STREAM_VERSION, world: [512, 512], frames_total: 10 });
+        let messages = [hello, StreamMessage::Frame(frame(7)), metrics(7, Some(5), 12.5)];
+        let bytes: Vec<u8> = messages.iter().flat_map(|m| m.encode()).collect();
+        let mut reader = bytes.as_slice();
+        for message in &messages {
+            assert_eq!(&StreamMessage::read_from(&mut reader).unwrap(), message);
+        }
+        assert!(StreamMessage::read_from(&mut reader).is_err(), "end of stream");
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        let mut bytes = StreamMessage::Frame(frame(1)).encode();
+        bytes[5 + 4] = 3; // width 3: pixel data no longer matches
+        assert_eq!(
+            StreamMessage::read_from(&mut bytes.as_slice()).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+        let unknown = [9u8, 0, 0, 0, 0];
+        assert!(StreamMessage::read_from(&mut unknown.as_slice()).is_err());
+        let huge = [2u8, 0xff, 0xff, 0xff, 0xff];
+        assert!(StreamMessage::read_from(&mut huge.as_slice()).is_err());
+    }
+
+    #[test]
+    fn feed_keeps_one_history_sample_per_diagnostics_frame() {
+        let mut feed = ViewerFeed::default();
+        let hello = StreamHello { version: STREAM_VERSION, world: [512, 512], frames_total: 1000 };
+        feed.apply(StreamMessage::Hello(hello.clone())).unwrap();
+        assert_eq!(feed.status, LinkStatus::Connected);
+        // Progress updates repeat the last sample: recorded once
+        for (frame, sample, mass) in [(10, None, 0.0), (300, Some(300), 5.0), (330, Some(300), 5.0), (600, Some(600), 7.0)] {
+            feed.apply(metrics(frame, sample, mass)).unwrap();
+        }
+        assert_eq!(feed.series("population/total_mass"), vec![[300.0, 5.0], [600.0, 7.0]]);
+        assert_eq!(feed.metrics.frame, 600);
+        feed.apply(StreamMessage::Frame(frame(600))).unwrap();
+        feed.apply(StreamMessage::Frame(frame(630))).unwrap();
+        assert_eq!(feed.frame_serial, 2);
+
+        // A rewound run starts over; the history stays bounded
+        feed.apply(metrics(10, Some(0), 1.0)).unwrap();
+        assert_eq!(feed.series("population/total_mass"), vec![[0.0, 1.0]]);
+        for i in 1..=HISTORY_LIMIT as u32 + 10 {
+            feed.apply(metrics(i, Some(i), 1.0)).unwrap();
+        }
+        assert_eq!(feed.history.len(), HISTORY_LIMIT);
+
+        let other = StreamHello { version: STREAM_VERSION + 1, ..hello };
+        assert!(feed.apply(StreamMessage::Hello(other)).is_err());
+    }
+
+    #[test]
+    fn viewer_receives_frames_and_diagnostics_over_tcp() {
+        let mut server = StreamServer::start("127.0.0.1:0", [512, 512], 1000).unwrap();
+        let addr = server.addr().to_string();
+        let client = StreamClient::connect(&addr);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while server.viewer_count() == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(server.viewer_count(), 1);
+        assert!(wait_for(&client, |f| f.status == LinkStatus::Connected));
+        assert_eq!(client.feed().hello.as_ref().map(|h| h.world), Some([512, 512]));
+
+        server.set_frame(120);
+        server.write_frame(120, &frame(120).rgba, 2, 1).unwrap(); // as a headless run's frame sink
+        server.set_diagnostics(120, &[("population/total_mass", 42.0)]);
+        assert!(wait_for(&client, |f| f.metrics.diagnostics_frame == Some(120)));
+        let feed = client.feed();
+        assert_eq!(feed.frame, Some(frame(120)));
+        assert_eq!(feed.metrics.frame, 120);
+        assert_eq!(feed.series("population/total_mass"), vec![[120.0, 42.0]]);
+        drop(feed);
+
+        // A viewer joining later gets the last frame right away
+        let late = StreamClient::connect(&addr);
+        assert!(wait_for(&late, |f| f.frame.as_ref().map(|f| f.frame) == Some(120)));
+        assert_eq!(late.feed().metrics.diagnostics_frame, Some(120));
+    }
+
+    #[test]
+    fn stream_and_view_flags_parse() {
+        let args = |a: &[&str]| std::iter::once("evolenia").chain(a.iter().copied()).map(String::from).collect();
+        let cli = CliOptions::from_args(args(&["--headless", "--stream-addr", "0.0.0.0:9899", "--stream-interval", "0"]));
+        assert_eq!(cli.stream_addr.as_deref(), Some("0.0.0.0:9899"));
+        assert_eq!((cli.stream_interval, cli.stream_width), (1, 256));
+        let cli = CliOptions::from_args(args(&["view", "node:9899"]));
+        assert_eq!(cli.view_addr.as_deref(), Some("node:9899"));
+        assert!(CliOptions::from_args(args(&["--headless", "view", "x"])).view_addr.is_none());
+    }
+
+    #[test]
+    fn record_flags_parse() {
+        let args = |a: &[&str]| std::iter::once("evolenia").chain(a.iter().copied()).map(String::from).collect();
+        let cli = CliOptions::from_args(args(&["--headless"]));
+        assert_eq!((cli.record_path, cli.record_interval, cli.record_width), (None, 30, 512));
+        let cli = CliOptions::from_args(args(&["--headless", "--record", "run.mp4", "--record-interval", "0", "--record-width", "640"]));
+        assert_eq!(cli.record_path.as_deref(), Some("run.mp4"));
+        assert_eq!((cli.record_interval, cli.record_width), (1, 640));
+    }
+}
+
+#[cfg(test)]
+mod startup_tests {
+    //! First-launch benchmark: recommendations for fast, slow and integrated
+    //! GPUs, and the saved choice.
+
+    use crate::camera::CameraState;
+    use crate::lab::LabState;
+    use crate::settings::UiSettings;
+    use crate::startup::{Recommendation, StartupBench, StartupChoice};
+    use crate::world::DEFAULT_WORLD_SIZE;
+
+    fn bench(ms_per_step: f32, integrated: bool) -> StartupBench {
+        StartupBench { gpu: "test".into(), integrated, ms_per_step }
+    }
+
+    #[test]
+    fn fast_discrete_gpu_gets_full_settings() {
+        let rec = Recommendation::for_bench(&bench(0.5, false), DEFAULT_WORLD_SIZE[0]);
+        assert_eq!(rec.simulation_speed, 20); // capped at the slider range
+        assert!(rec.vsync);
+        assert_eq!(rec.render_scale, 1.0);
+        assert!(rec.world_edge > DEFAULT_WORLD_SIZE[0]);
+    }
+
+    #[test]
+    fn slow_gpu_gets_lower_settings() {
+        let rec = Recommendation::for_bench(&bench(50.0, false), DEFAULT_WORLD_SIZE[0]);
+        assert_eq!(rec.simulation_speed, 1);
+        assert!(!rec.vsync);
+        assert_eq!(rec.render_scale, 0.5);
+        assert!(rec.world_edge < DEFAULT_WORLD_SIZE[0]);
+
+        // Integrated GPUs share the work with rendering
+        let rec = Recommendation::for_bench(&bench(1.0, true), DEFAULT_WORLD_SIZE[0]);
+        assert_eq!(rec.render_scale, 0.75);
+    }
+
+    #[test]
+    fn suggested_world_edge_is_a_power_of_two_in_range() {
+        for ms in [0.01, 0.3, 2.0, 7.5, 40.0, 500.0] {
+            let edge = Recommendation::for_bench(&bench(ms, false), DEFAULT_WORLD_SIZE[0]).world_edge;
+            assert!(edge.is_power_of_two(), "{edge}");
+            assert!((128..=2048).contains(&edge), "{edge}");
+        }
+    }
+
+    #[test]
+    fn choice_is_saved_and_absent_by_default() {
+        let old: UiSettings = serde_json::from_str("{}").unwrap();
+        assert!(old.startup.is_none());
+
+        let choice = StartupChoice {
+            bench: bench(2.0, true),
+            recommendation: Recommendation::for_bench(&bench(2.0, true), DEFAULT_WORLD_SIZE[0]),
+            applied: true,
+        };
+        let lab = LabState { startup_choice: Some(choice.clone()), ..Default::default() };
+        let json = serde_json::to_string(&UiSettings::capture(&lab, &CameraState::default(), None)).unwrap();
+        let loaded: UiSettings = serde_json::from_str(&json).unwrap();
+        let mut lab2 = LabState::default();
+        loaded.apply(&mut lab2, &mut CameraState::default());
+        assert_eq!(lab2.startup_choice, Some(choice));
+    }
+}
+
+#[cfg(test)]
+mod crash_tests {
+    //! Crash reports: the recorded context and the report text.
+
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    use crate::config::SimulationParams;
+    use crate::crash::{CrashContext, CRASH_EVENTS, REFRESH_INTERVAL};
+    use crate::lab::LabState;
+
+    fn lab_with_events(count: u32) -> LabState {
+        let mut lab = LabState::default();
+        for frame in 0..count {
+            lab.log_event(frame, "TEST", &format!("event {frame}"));
+        }
+        lab
+    }
+
+    #[test]
+    fn refresh_keeps_the_last_events_and_is_rate_limited() {
+        let lab = lab_with_events(80);
+        let params = SimulationParams::default();
+        let mut context = CrashContext::default();
+        let t0 = Instant::now();
+        context.refresh(t0, 10, &params, &lab.events);
+        assert_eq!(context.events.len(), CRASH_EVENTS);
+        assert!(context.events[0].contains("event 30"));
+        assert!(context.events.last().unwrap().contains("event 79"));
+        assert!(context.params_json.as_deref().unwrap().contains("simulation_speed"));
+
+        // The frame follows every call; events wait for the interval
+        let lab = lab_with_events(3);
+        context.refresh(t0 + Duration::from_millis(100), 11, &params, &lab.events);
+        assert_eq!(context.frame, 11);
+        assert_eq!(context.events.len(), CRASH_EVENTS);
+        context.refresh(t0 + REFRESH_INTERVAL, 12, &params, &lab.events);
+        assert_eq!(context.events.len(), 3);
+    }
+
+    #[test]
+    fn report_has_panic_gpu_world_params_and_events() {
+        let mut context = CrashContext {
+            adapter: Some(wgpu::AdapterInfo {
+                name: "Test GPU".into(),
+                vendor: 0x10de,
+                device: 0x2204,
+                device_type: wgpu::DeviceType::DiscreteGpu,
+                driver: "test".into(),
+                driver_info: "1.0".into(),
+                backend: wgpu::Backend::Vulkan,
+            }),
+            world: [512, 512],
+            ..Default::default()
+        };
+        context.refresh(Instant::now(), 42, &SimulationParams::default(), &lab_with_events(2).events);
+        let report = context.format_report("20260101_120000", "index out of bounds", "src/world.rs:10:5", "<bt>");
+        for expected in
+            ["panic: index out of bounds", "src/world.rs:10:5", "Test GPU", "Vulkan", "512x512", "frame: 42", "simulation_speed", "event 1", "<bt>"]
+        {
+            assert!(report.contains(expected), "missing {expected:?} in\n{report}");
+        }
+
+        // Before GPU setup and outside a run
+        let report = CrashContext::default().format_report("t", "boom", "here", "");
+        assert!(report.contains("adapter: not initialized"));
+        assert!(report.contains("run: none"));
+        assert!(report.contains("not recorded"));
+    }
+
+    #[test]
+    fn report_goes_to_the_run_directory() {
+        let context = CrashContext { run_dir: Some(PathBuf::from("runs/2026-01-01/run_1")), ..Default::default() };
+        assert_eq!(context.report_path("20260101_120000"), Path::new("runs/2026-01-01/run_1/crash_20260101_120000.txt"));
+        assert_eq!(CrashContext::default().report_path("t"), Path::new("./crash_t.txt"));
+    }
+}
+
+#[cfg(test)]
+mod gpu_debug_tests {
+    //! GPU validation toggle: instance flags, error deduplication, the CLI
+    //! flag and the saved setting.
+
+    use crate::camera::CameraState;
+    use crate::gpu_debug::{instance_flags, GpuErrorLog, GpuErrorReport, UNCAPTURED};
+    use crate::lab::LabState;
+    use crate::settings::UiSettings;
+    use crate::CliOptions;
+
+    #[test]
+    fn validation_enables_the_debug_layers() {
+        let flags = instance_flags(true);
+        assert!(flags.contains(wgpu::InstanceFlags::VALIDATION));
+        assert!(flags.contains(wgpu::InstanceFlags::DEBUG));
+    }
+
+    #[test]
+    fn each_distinct_error_is_reported_once() {
+        let mut log = GpuErrorLog::default();
+        for _ in 0..100 {
+            log.record("render_submit", "Validation Error: bind group mismatch".into());
+        }
+        log.record(UNCAPTURED, "Validation Error: bind group mismatch".into());
+        let reports = log.drain();
+        assert_eq!(
+            reports,
+            vec![
+                GpuErrorReport { scope: "render_submit", message: "Validation Error: bind group mismatch".into() },
+                GpuErrorReport { scope: UNCAPTURED, message: "Validation Error: bind group mismatch".into() },
+            ]
+        );
+        assert_eq!(log.counts(), (2, 101));
+
+        // Later frames with the same error add nothing to the event log
+        log.record("render_submit", "Validation Error: bind group mismatch".into());
+        assert!(log.drain().is_empty());
+        assert_eq!(log.counts(), (2, 102));
+    }
+
+    #[test]
+    fn cli_flag_and_saved_setting() {
+        let args = |list: &[&str]| std::iter::once("evolenia").chain(list.iter().copied()).map(String::from).collect();
+        assert!(!CliOptions::from_args(args(&[])).gpu_validation);
+        assert!(CliOptions::from_args(args(&["--gpu-validation"])).gpu_validation);
+
+        let lab = LabState { gpu_validation: true, ..Default::default() };
+        let json = serde_json::to_string(&UiSettings::capture(&lab, &CameraState::default(), None)).unwrap();
+        let loaded: UiSettings = serde_json::from_str(&json).unwrap();
+        let mut lab2 = LabState::default();
+        loaded.apply(&mut lab2, &mut CameraState::default());
+        assert!(lab2.gpu_validation);
+        assert!(!serde_json::from_str::<UiSettings>("{}").unwrap().gpu_validation);
+    }
+}
+
+#[cfg(test)]
+mod capture_tests {
+    //! Screenshot conversion: channel order, sRGB encoding and row padding
+    //! for each supported surface format; the PNG and video sinks.
+
+    use std::path::Path;
+
+    use crate::capture::{
+        encode_png, f16_to_f32, ffmpeg_args, is_video_path, linear_to_srgb, open_frame_sink, sequence_frame_path, write_png,
+        CaptureFormat, FrameSink, StagingPool, TexelLayout, VideoSink,
+    };
+
+    fn convert(format: wgpu::TextureFormat, texels: &[u8]) -> Vec<u8> {
+        let capture = CaptureFormat::for_texture(format).unwrap();
+        let bpp = capture.bytes_per_pixel() as usize;
+        let width = (texels.len() / bpp) as u32;
+        let padded = capture.padded_bytes_per_row(width);
+        let mut data = texels.to_vec();
+        data.resize(padded as usize, 0xAB); // row padding is skipped
+        capture.srgb_rgba8(&data, width, 1, padded)
+    }
+
+    #[test]
+    fn srgb_surfaces_keep_bytes_in_either_channel_order() {
+        assert_eq!(convert(wgpu::TextureFormat::Rgba8UnormSrgb, &[10, 20, 30, 255]), [10, 20, 30, 255]);
+        assert_eq!(convert(wgpu::TextureFormat::Bgra8UnormSrgb, &[30, 20, 10, 255]), [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn linear_surfaces_are_srgb_encoded() {
+        // Linear 0.5 → sRGB 188; black and white are fixed points
+        let mid = (linear_to_srgb(0.5) * 255.0).round() as u8;
+        assert_eq!(mid, 188);
+        assert_eq!(convert(wgpu::TextureFormat::Rgba8Unorm, &[0, 128, 255, 255]), [0, 188, 255, 255]);
+        assert_eq!(convert(wgpu::TextureFormat::Bgra8Unorm, &[255, 128, 0, 255]), [0, 188, 255, 255]);
+
+        // Half floats: 0.5 = 0x3800, 1.0 = 0x3C00, overbright clamps to white
+        let half = |v: u16| v.to_le_bytes();
+        let texel: Vec<u8> = [half(0x3800), half(0x3C00), half(0x4400), half(0x3C00)].concat();
+        assert_eq!(convert(wgpu::TextureFormat::Rgba16Float, &texel), [188, 255, 255, 255]);
+
+        // 10-bit: R=1023, G=0, B=512, A=3
+        let packed: u32 = 1023 | (512 << 20) | (3 << 30);
+        assert_eq!(convert(wgpu::TextureFormat::Rgb10a2Unorm, &packed.to_le_bytes()), [255, 0, 188, 255]);
+    }
+
+    #[test]
+    fn rows_are_unpadded() {
+        let capture = CaptureFormat::for_texture(wgpu::TextureFormat::Bgra8UnormSrgb).unwrap();
+        assert_eq!(capture.layout, TexelLayout::Bgra8);
+        let (width, height) = (3, 2);
+        let padded = capture.padded_bytes_per_row(width);
+        assert_eq!(padded, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let mut data = vec![0u8; (padded * height) as usize];
+        data[padded as usize..padded as usize + 4].copy_from_slice(&[3, 2, 1, 4]); // first texel of row 1
+        let rgba = capture.srgb_rgba8(&data, width, height, padded);
+        assert_eq!(rgba.len(), (width * height * 4) as usize);
+        assert_eq!(rgba[12..16], [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn half_float_decoding_and_unsupported_formats() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0xC000), -2.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24)); // smallest subnormal
+        assert!(f16_to_f32(0x7C00).is_infinite());
+        assert!(CaptureFormat::for_texture(wgpu::TextureFormat::R32Float).is_none());
+    }
+
+    #[test]
+    fn staging_buffers_are_reused_by_size() {
+        let created = std::cell::Cell::new(0);
+        let create = |size: u64| {
+            created.set(created.get() + 1);
+            (created.get(), size)
+        };
+        let mut pool = StagingPool::new(2);
+        let a = pool.acquire(1024, create);
+        pool.release(a, 1024);
+        assert_eq!(pool.acquire(1024, create), a); // same window size: reused
+        let b = pool.acquire(4096, create); // resized window: new buffer
+        assert_eq!(created.get(), 2);
+
+        // Beyond the capacity the oldest free buffer is dropped
+        pool.release(a, 1024);
+        pool.release(b, 4096);
+        pool.release((9, 64), 64);
+        assert_eq!(pool.acquire(4096, create), b);
+        assert_eq!(pool.acquire(1024, create), (3, 1024));
+    }
+
+    #[test]
+    fn png_sinks_round_trip_and_number_sequences() {
+        let rgba: Vec<u8> = (0..2 * 3 * 4).map(|i| (i * 10) as u8).collect();
+        let png = encode_png(&rgba, 2, 3).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!((decoded.width(), decoded.height()), (2, 3));
+        assert_eq!(decoded.into_raw(), rgba);
+
+        let dir = std::env::temp_dir().join(format!("evolenia_capture_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = sequence_frame_path(&dir, 7);
+        assert_eq!(path, dir.join("frame_00007.png"));
+        write_png(&path, &rgba, 2, 3).unwrap();
+        assert_eq!(image::open(&path).unwrap().to_rgba8().into_raw(), rgba);
+        assert!(write_png(Path::new("/nonexistent/dir/x.png"), &rgba, 2, 3).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn frame_sinks_number_images_and_pick_video_by_extension() {
+        let rgba = vec![200u8; 4 * 4 * 4];
+        let dir = std::env::temp_dir().join(format!("evolenia_sink_{}", std::process::id()));
+        let mut sink = open_frame_sink(&dir.join("run"), 30.0).unwrap(); // directory: PNG images
+        for frame in [100, 130] {
+            sink.write_frame(frame, &rgba, 4, 4).unwrap();
+        }
+        sink.finish().unwrap();
+        assert!(sequence_frame_path(&dir.join("run"), 0).exists());
+        assert!(sequence_frame_path(&dir.join("run"), 1).exists());
+        assert!(!sequence_frame_path(&dir.join("run"), 2).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(is_video_path(Path::new("out/run.mp4")));
+        assert!(is_video_path(Path::new("run.WEBM")));
+        assert!(!is_video_path(Path::new("run_frames")));
+        assert!(!is_video_path(Path::new("still.png")));
+
+        // A video sink starts ffmpeg at its first frame; unused, it is a no-op
+        let mut video = VideoSink::new(Path::new("never.mp4"), 30.0);
+        video.finish().unwrap();
+        assert!(!Path::new("never.mp4").exists());
+    }
+
+    #[test]
+    fn ffmpeg_reads_raw_rgba_at_the_frame_size() {
+        let args = ffmpeg_args(Path::new("out/run.mp4"), 513, 257, 24.0);
+        let after = |flag: &str| args[args.iter().position(|a| a == flag).unwrap() + 1].as_str();
+        assert_eq!((after("-f"), after("-s"), after("-r"), after("-i")), ("rawvideo", "513x257", "24", "-"));
+        assert_eq!(after("-pix_fmt"), "rgba"); // input; the output is yuv420p
+        assert!(args.iter().any(|a| a.starts_with("pad=")), "odd sizes are padded for yuv420p");
+        assert_eq!(args.last().map(String::as_str), Some("out/run.mp4"));
     }
 }
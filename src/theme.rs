@@ -0,0 +1,189 @@
+// ============================================================================
+// theme.rs — EvoLenia v2
+// UI themes (egui visuals) and color palettes for the categorical render
+// modes, including colorblind-safe options. Palettes are uploaded to the
+// render shader as a small uniform table of colors.
+// ============================================================================
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+// ======================== UI Theme ========================
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UiTheme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl UiTheme {
+    pub fn all() -> &'static [UiTheme] {
+        &[UiTheme::Dark, UiTheme::Light]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            UiTheme::Dark => "theme.dark",
+            UiTheme::Light => "theme.light",
+        }
+    }
+
+    /// egui visuals for this theme. Panels stay opaque so the simulation
+    /// never bleeds through UI text.
+    pub fn visuals(&self) -> egui::Visuals {
+        match self {
+            UiTheme::Dark => {
+                // Enhanced dark theme - OPAQUE backgrounds with vibrant accents
+                let mut visuals = egui::Visuals::dark();
+                visuals.window_fill = egui::Color32::from_rgb(22, 24, 32);
+                visuals.panel_fill = egui::Color32::from_rgb(18, 20, 28);
+                visuals.extreme_bg_color = egui::Color32::from_rgb(12, 14, 20);
+                visuals.faint_bg_color = egui::Color32::from_rgb(28, 32, 42);
+                visuals.window_stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(70, 130, 180));
+                visuals.widgets.noninteractive.bg_fill = egui::Color32::from_rgb(32, 36, 48);
+                visuals.widgets.noninteractive.fg_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(160, 170, 190));
+                visuals.widgets.inactive.bg_fill = egui::Color32::from_rgb(45, 50, 65);
+                visuals.widgets.inactive.fg_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(180, 190, 210));
+                visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(60, 90, 130);
+                visuals.widgets.hovered.fg_stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(220, 230, 255));
+                visuals.widgets.active.bg_fill = egui::Color32::from_rgb(80, 140, 200);
+                visuals.widgets.active.fg_stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
+                visuals.selection.bg_fill = egui::Color32::from_rgb(50, 120, 180);
+                visuals.selection.stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 180, 255));
+                visuals.hyperlink_color = egui::Color32::from_rgb(100, 200, 255);
+                visuals
+            }
+            UiTheme::Light => {
+                let mut visuals = egui::Visuals::light();
+                visuals.window_fill = egui::Color32::from_rgb(246, 247, 250);
+                visuals.panel_fill = egui::Color32::from_rgb(238, 240, 245);
+                visuals.window_stroke = egui::Stroke::new(1.5, egui::Color32::from_rgb(70, 130, 180));
+                visuals.selection.bg_fill = egui::Color32::from_rgb(150, 200, 240);
+                visuals.selection.stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(30, 90, 150));
+                visuals.hyperlink_color = egui::Color32::from_rgb(20, 100, 180);
+                visuals
+            }
+        }
+    }
+}
+
+// ======================== Color Palettes ========================
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorPalette {
+    #[default]
+    Classic,   // original saturated RGB / red-green colors
+    OkabeIto,  // Okabe & Ito (2008): distinguishable under all common CVD types
+    TolBright, // Paul Tol's "bright" qualitative scheme, also CVD-safe
+}
+
+impl ColorPalette {
+    pub fn all() -> &'static [ColorPalette] {
+        &[ColorPalette::Classic, ColorPalette::OkabeIto, ColorPalette::TolBright]
+    }
+
+    /// Translation key for the UI label (see i18n.rs).
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            ColorPalette::Classic => "palette.classic",
+            ColorPalette::OkabeIto => "palette.okabe_ito",
+            ColorPalette::TolBright => "palette.tol_bright",
+        }
+    }
+
+    /// Color table consumed by render.wgsl (binding 9).
+    pub fn uniforms(&self) -> PaletteUniforms {
+        match self {
+            ColorPalette::Classic => PaletteUniforms {
+                species: [rgb(1.0, 0.0, 0.0), rgb(0.0, 1.0, 0.0), rgb(0.0, 0.0, 1.0)],
+                predator_glow: rgb(1.0, 0.5, 0.0),
+                heat_low: rgb(0.0, 0.2, 1.0),
+                heat_high: rgb(1.0, 0.2, 0.0),
+                prey: rgb(0.0, 1.0, 0.0),
+                predator: rgb(1.0, 0.0, 0.0),
+                healthy: rgb(0.0, 0.9, 0.9),
+                starving: rgb(0.9, 0.0, 0.7),
+                trophic: [
+                    rgb(0.1, 0.85, 0.15), // prey
+                    rgb(0.1, 0.7, 0.6),   // opportunist (low aggressivity)
+                    rgb(0.3, 0.3, 0.9),   // opportunist (high aggressivity)
+                    rgb(1.0, 0.5, 0.0),   // predator (low aggressivity)
+                    rgb(1.0, 0.0, 0.0),   // predator (high aggressivity)
+                ],
+                // HSV hue wheel at s = 0.8, v = 1 (hsv is piecewise linear
+                // between these six stops)
+                cyclic: [
+                    rgb(1.0, 0.2, 0.2),
+                    rgb(1.0, 1.0, 0.2),
+                    rgb(0.2, 1.0, 0.2),
+                    rgb(0.2, 1.0, 1.0),
+                    rgb(0.2, 0.2, 1.0),
+                    rgb(1.0, 0.2, 1.0),
+                ],
+            },
+            ColorPalette::OkabeIto => {
+                let orange = rgb(0.902, 0.624, 0.0);
+                let sky_blue = rgb(0.337, 0.706, 0.914);
+                let bluish_green = rgb(0.0, 0.620, 0.451);
+                let yellow = rgb(0.941, 0.894, 0.259);
+                let blue = rgb(0.0, 0.447, 0.698);
+                let vermillion = rgb(0.835, 0.369, 0.0);
+                let reddish_purple = rgb(0.8, 0.475, 0.655);
+                PaletteUniforms {
+                    species: [orange, sky_blue, reddish_purple],
+                    predator_glow: vermillion,
+                    heat_low: blue,
+                    heat_high: orange,
+                    prey: sky_blue,
+                    predator: vermillion,
+                    healthy: sky_blue,
+                    starving: vermillion,
+                    trophic: [sky_blue, bluish_green, blue, orange, vermillion],
+                    cyclic: [orange, yellow, bluish_green, sky_blue, blue, reddish_purple],
+                }
+            }
+            ColorPalette::TolBright => {
+                let blue = rgb(0.267, 0.467, 0.667);
+                let cyan = rgb(0.4, 0.8, 0.933);
+                let green = rgb(0.133, 0.533, 0.2);
+                let yellow = rgb(0.8, 0.733, 0.267);
+                let red = rgb(0.933, 0.4, 0.467);
+                let purple = rgb(0.667, 0.2, 0.467);
+                PaletteUniforms {
+                    species: [red, yellow, blue],
+                    predator_glow: red,
+                    heat_low: blue,
+                    heat_high: red,
+                    prey: cyan,
+                    predator: red,
+                    healthy: cyan,
+                    starving: purple,
+                    trophic: [cyan, green, blue, yellow, red],
+                    cyclic: [blue, cyan, green, yellow, red, purple],
+                }
+            }
+        }
+    }
+}
+
+const fn rgb(r: f32, g: f32, b: f32) -> [f32; 4] {
+    [r, g, b, 1.0]
+}
+
+/// Render palette uniform (matches `Palette` in render.wgsl; vec4 slots).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct PaletteUniforms {
+    pub species: [[f32; 4]; 3], // basis for the genome r / μ / σ channels (mode 0)
+    pub predator_glow: [f32; 4],
+    pub heat_low: [f32; 4], // energy heatmap (mode 1)
+    pub heat_high: [f32; 4],
+    pub prey: [f32; 4], // predator/prey (mode 4)
+    pub predator: [f32; 4],
+    pub healthy: [f32; 4], // metabolic stress (mode 5)
+    pub starving: [f32; 4],
+    pub trophic: [[f32; 4]; 5], // trophic roles (mode 7)
+    pub cyclic: [[f32; 4]; 6],  // hue wheel for diversity / flux direction (modes 3, 6)
+}
@@ -0,0 +1,94 @@
+// ============================================================================
+// thumbnail.rs — EvoLenia v2 Research Lab
+// Run thumbnails: when a run is finalized its final world state is drawn on
+// the CPU in the species-color mode (genome r / μ / σ as the palette's
+// species basis, brightness from mass) and box-filtered down to a small PNG
+// in the run directory. The analysis panel's run gallery shows these, so
+// they do not depend on the camera, the active visualization mode or UI
+// overlays at the time of finalization.
+// ============================================================================
+
+use std::path::{Path, PathBuf};
+
+use crate::capture::write_png;
+use crate::display::auto_range;
+use crate::theme::PaletteUniforms;
+use crate::world::BufferSnapshot;
+
+/// File name inside the run directory.
+pub const THUMBNAIL_FILE: &str = "thumbnail.png";
+
+/// Thumbnail width in pixels (height follows the world aspect ratio).
+pub const THUMBNAIL_WIDTH: u32 = 128;
+
+const BACKGROUND: [f32; 3] = [0.02, 0.02, 0.05]; // as in render.wgsl
+const PREDATOR_THRESHOLD: f32 = 0.7;
+
+/// Linear species color of one cell (render.wgsl mode 0).
+pub(crate) fn cell_color(snap: &BufferSnapshot, i: usize, (lo, hi): (f32, f32), palette: &PaletteUniforms) -> [f32; 3] {
+    let g = &snap.genome_a[i * 4..i * 4 + 4]; // r, mu, sigma, aggressivity
+    let weights = [(g[0] / 15.0).clamp(0.0, 1.0), (g[1] * 5.0).clamp(0.0, 1.0), (g[2] / 0.06).clamp(0.0, 1.0)];
+    let glow = if g[3] >= PREDATOR_THRESHOLD { 0.3 } else { 0.0 };
+    let intensity = ((snap.mass[i] - lo) / (hi - lo)).clamp(0.0, 1.0);
+    std::array::from_fn(|c| {
+        let species: f32 = (0..3).map(|k| weights[k] * palette.species[k][c]).sum();
+        let color = (species + glow * palette.predator_glow[c]).clamp(0.0, 1.0);
+        BACKGROUND[c] + (color - BACKGROUND[c]) * intensity
+    })
+}
+
+/// Linear [0, 1] channel to an sRGB byte.
+pub(crate) fn srgb8(linear: f32) -> u8 {
+    (linear.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8
+}
+
+/// Thumbnail size for `width` of a `world`-sized snapshot: (width, height),
+/// each at least 1.
+pub fn thumbnail_size(width: u32, world: [u32; 2]) -> (u32, u32) {
+    let width = width.clamp(1, world[0]);
+    (width, (width * world[1] / world[0]).max(1))
+}
+
+/// sRGB RGBA8 thumbnail of a world snapshot, `width` pixels wide.
+pub fn thumbnail_rgba(snap: &BufferSnapshot, width: u32, palette: &PaletteUniforms) -> Vec<u8> {
+    let [world_width, world_height] = snap.size;
+    let (w, h) = thumbnail_size(width, snap.size);
+    // Empty space stays background; the brightest 1% of live cells saturate
+    let range = (0.0, auto_range(&snap.mass, 1.0).1);
+    let mut rgba = Vec::with_capacity((w * h * 4) as usize);
+    for ty in 0..h {
+        let (y0, y1) = (ty * world_height / h, ((ty + 1) * world_height / h).max(ty * world_height / h + 1));
+        for tx in 0..w {
+            let (x0, x1) = (tx * world_width / w, ((tx + 1) * world_width / w).max(tx * world_width / w + 1));
+            let mut sum = [0.0f32; 3];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let color = cell_color(snap, (y * world_width + x) as usize, range, palette);
+                    (0..3).for_each(|c| sum[c] += color[c]);
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as f32;
+            for s in sum {
+                rgba.push(srgb8(s / count));
+            }
+            rgba.push(255);
+        }
+    }
+    rgba
+}
+
+/// Write `<run_dir>/thumbnail.png` for the final state of a run.
+pub fn save_thumbnail(run_dir: &Path, snap: &BufferSnapshot, palette: &PaletteUniforms) -> Result<PathBuf, String> {
+    let (w, h) = thumbnail_size(THUMBNAIL_WIDTH, snap.size);
+    let rgba = thumbnail_rgba(snap, THUMBNAIL_WIDTH, palette);
+    let path = run_dir.join(THUMBNAIL_FILE);
+    write_png(&path, &rgba, w, h)?;
+    Ok(path)
+}
+
+/// Read an image file (thumbnail, report plot) for display in egui.
+pub fn load_color_image(path: &Path) -> Result<egui::ColorImage, String> {
+    let image = image::open(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
@@ -0,0 +1,119 @@
+// ============================================================================
+// transplant.rs — EvoLenia v2 Research Lab
+// Translocation experiments: copy the population of a square region — mass,
+// energy and genomes, cell by cell — and paste it over another region, e.g.
+// from an oasis into a desert. The destination keeps its environment
+// (resources, flow); only the organisms move. Each paste keeps the cells it
+// overwrote, so it can be undone.
+// ============================================================================
+
+use crate::world::BufferSnapshot;
+
+/// Transplants kept for undo; older ones can no longer be undone.
+pub const UNDO_DEPTH: usize = 16;
+
+/// The organisms of a square region of the world, row-major.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Patch {
+    pub side: usize,
+    pub mass: Vec<f32>,
+    pub energy: Vec<f32>,
+    pub genome_a: Vec<f32>, // flat vec4 per cell
+    pub genome_b: Vec<f32>,
+}
+
+/// Index of each patch cell in a `size` world for a patch of `side`
+/// centered on `center`, wrapping around the torus.
+fn patch_cells(size: [u32; 2], center: [u32; 2], side: usize) -> impl Iterator<Item = usize> {
+    let [width, height] = size.map(|edge| edge as usize);
+    let x0 = center[0] as usize + width - side / 2;
+    let y0 = center[1] as usize + height - side / 2;
+    (0..side * side).map(move |k| {
+        let wx = (x0 + k % side) % width;
+        let wy = (y0 + k / side) % height;
+        wy * width + wx
+    })
+}
+
+impl Patch {
+    /// Copy the square of side 2·`half` + 1 (at most the world) around `center`.
+    pub fn copy(snap: &BufferSnapshot, center: [u32; 2], half: u32) -> Self {
+        let side = (2 * half + 1).min(snap.size[0]).min(snap.size[1]) as usize;
+        let mut patch = Patch {
+            side,
+            mass: Vec::with_capacity(side * side),
+            energy: Vec::with_capacity(side * side),
+            genome_a: Vec::with_capacity(side * side * 4),
+            genome_b: Vec::with_capacity(side * side),
+        };
+        for i in patch_cells(snap.size, center, side) {
+            patch.mass.push(snap.mass[i]);
+            patch.energy.push(snap.energy[i]);
+            patch.genome_a.extend_from_slice(&snap.genome_a[i * 4..i * 4 + 4]);
+            patch.genome_b.push(snap.genome_b[i]);
+        }
+        patch
+    }
+
+    /// Write the patch over the square around `center`, returning the cells
+    /// it replaced (pasting those back undoes it).
+    pub fn paste(&self, snap: &mut BufferSnapshot, center: [u32; 2]) -> Patch {
+        let replaced = Patch::copy(snap, center, self.side as u32 / 2);
+        for (k, i) in patch_cells(snap.size, center, self.side).enumerate() {
+            snap.mass[i] = self.mass[k];
+            snap.energy[i] = self.energy[k];
+            snap.genome_a[i * 4..i * 4 + 4].copy_from_slice(&self.genome_a[k * 4..k * 4 + 4]);
+            snap.genome_b[i] = self.genome_b[k];
+        }
+        replaced
+    }
+
+    pub fn total_mass(&self) -> f32 {
+        self.mass.iter().sum()
+    }
+}
+
+/// A transplant done on the world, with what it overwrote at the destination.
+#[derive(Clone, Debug)]
+pub struct Transplant {
+    pub frame: u32,
+    pub from: [u32; 2], // pixels
+    pub to: [u32; 2],
+    pub replaced: Patch,
+}
+
+/// Transplants that can still be undone, newest last.
+#[derive(Default)]
+pub struct TransplantHistory {
+    entries: Vec<Transplant>,
+}
+
+impl TransplantHistory {
+    pub fn push(&mut self, transplant: Transplant) {
+        if self.entries.len() == UNDO_DEPTH {
+            self.entries.remove(0);
+        }
+        self.entries.push(transplant);
+    }
+
+    /// Undo the newest transplant on `snap`: its destination gets back the
+    /// cells it had before, as they were then.
+    pub fn undo(&mut self, snap: &mut BufferSnapshot) -> Option<Transplant> {
+        let transplant = self.entries.pop()?;
+        transplant.replaced.paste(snap, transplant.to);
+        Some(transplant)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget every transplant (the world they applied to is gone).
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
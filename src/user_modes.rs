@@ -0,0 +1,98 @@
+// ============================================================================
+// user_modes.rs — EvoLenia v2
+// User-defined visualization modes: small WGSL files in vis_modes/, each
+// defining `fn user_mode_color(c: ModeInput) -> vec3<f32>` over the fields
+// of a world pixel (mass, energy, genome A, mutation rate, resource level,
+// advection velocity and display-adjusted mass; see ModeInput in
+// render.wgsl). A file is spliced into the render shader in place of its
+// stub, compiled into a flat and a 3D surface pipeline under a validation
+// error scope, and listed after the built-in modes as mode
+// VIS_MODE_COUNT + k. Files are loaded at startup and on reload; one that
+// fails to compile is reported and skipped.
+// ============================================================================
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::pipeline::Pipelines;
+
+pub const USER_MODES_DIR: &str = "vis_modes";
+
+/// Signature a user mode file must define.
+pub const MODE_FUNCTION: &str = "fn user_mode_color(";
+
+/// Markers around the stub in render.wgsl.
+const STUB_BEGIN: &str = "// USER MODE BEGIN";
+const STUB_END: &str = "// USER MODE END";
+
+/// A compiled user visualization mode.
+pub struct UserMode {
+    pub name: String,                     // file stem
+    pub views: [wgpu::RenderPipeline; 2], // flat, 3D surface
+}
+
+/// `*.wgsl` files of the user modes directory, sorted by name.
+pub fn list_user_modes(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    paths.retain(|p| p.extension().is_some_and(|e| e == "wgsl"));
+    paths.sort();
+    paths
+}
+
+/// Checks a user mode source before compilation: it defines the mode
+/// function and declares no resources of its own (it sees only ModeInput).
+pub fn check_source(source: &str) -> Result<(), String> {
+    if !source.contains(MODE_FUNCTION) {
+        return Err(format!("missing `{MODE_FUNCTION}c: ModeInput) -> vec3<f32>`"));
+    }
+    if source.contains("@group") || source.contains("@binding") {
+        return Err("user modes cannot declare bindings".to_string());
+    }
+    if source.contains(STUB_BEGIN) || source.contains(STUB_END) {
+        return Err("user modes cannot contain the stub markers".to_string());
+    }
+    Ok(())
+}
+
+/// `render_source` with its user mode stub replaced by `mode_source`.
+pub fn with_user_mode(render_source: &str, mode_source: &str) -> String {
+    match (render_source.find(STUB_BEGIN), render_source.find(STUB_END)) {
+        (Some(begin), Some(end)) if begin < end => {
+            format!("{}{}\n{}", &render_source[..begin], mode_source, &render_source[end..])
+        }
+        _ => render_source.to_string(),
+    }
+}
+
+/// Compile the mode in `path` against the current render pipelines.
+pub fn compile(device: &wgpu::Device, pipelines: &Pipelines, path: &Path) -> Result<UserMode, String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    check_source(&source)?;
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let views = pipelines.create_user_views(device, &source);
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        return Err(error.to_string());
+    }
+    let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok(UserMode { name, views })
+}
+
+/// Compile every mode of `dir`, in file order; the failures come back as
+/// (file name, error).
+pub fn load_user_modes(
+    device: &wgpu::Device,
+    pipelines: &Pipelines,
+    dir: &Path,
+) -> (Vec<UserMode>, Vec<(String, String)>) {
+    let mut modes = Vec::new();
+    let mut errors = Vec::new();
+    for path in list_user_modes(dir) {
+        match compile(device, pipelines, &path) {
+            Ok(mode) => modes.push(mode),
+            Err(e) => errors.push((path.file_name().unwrap_or_default().to_string_lossy().into_owned(), e)),
+        }
+    }
+    (modes, errors)
+}
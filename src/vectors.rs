@@ -0,0 +1,181 @@
+// ============================================================================
+// vectors.rs — EvoLenia v2 Research Lab
+// Velocity field export for offline analysis of advection: the velocity
+// buffer (cells per step, y pointing down the rows) is written as a NumPy
+// array of shape (height, width, 2) and/or drawn as a quiver plot, one arrow
+// per block of cells over the speed in gray. Exports go to `velocity/` in
+// the run directory, with an index.csv row per frame giving the speeds and
+// the arrow scale of its plot.
+// ============================================================================
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use image::ImageEncoder;
+
+use crate::averages::write_npy;
+use crate::config::VelocityExportSettings;
+use crate::display::auto_range;
+
+/// Export directory inside a run directory.
+pub const VELOCITY_DIR: &str = "velocity";
+/// One row per export: frame, speeds and arrow scale.
+pub const INDEX_FILE: &str = "index.csv";
+
+/// The longest block arrow spans this fraction of the block.
+const ARROW_FILL: f32 = 0.9;
+/// Arrows shorter than this (pixels) are not drawn.
+const MIN_ARROW: f32 = 1.0;
+const ARROW_COLOR: [u8; 3] = [240, 230, 140];
+
+/// `velocity/velocity_f<frame>` without extension.
+pub fn velocity_stem(run_dir: &Path, frame: u32) -> PathBuf {
+    run_dir.join(VELOCITY_DIR).join(format!("velocity_f{frame:06}"))
+}
+
+/// Speed of each cell of a flat (vx, vy) field.
+pub fn speeds(velocity: &[f32]) -> Vec<f32> {
+    velocity.chunks_exact(2).map(|v| v[0].hypot(v[1])).collect()
+}
+
+/// Mean velocity of each `stride` × `stride` block of a `size` world,
+/// row-major; the last row and column of blocks may be partial. Returns
+/// (columns, rows, means).
+pub fn block_means(velocity: &[f32], [width, height]: [u32; 2], stride: u32) -> (u32, u32, Vec<[f32; 2]>) {
+    let stride = stride.max(1);
+    let (cols, rows) = (width.div_ceil(stride), height.div_ceil(stride));
+    let mut sums = vec![([0.0f32; 2], 0u32); (cols * rows) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) as usize;
+            let (sum, count) = &mut sums[((y / stride) * cols + x / stride) as usize];
+            sum[0] += velocity[i * 2];
+            sum[1] += velocity[i * 2 + 1];
+            *count += 1;
+        }
+    }
+    let means = sums.iter().map(|(sum, count)| sum.map(|s| s / *count as f32)).collect();
+    (cols, rows, means)
+}
+
+// ======================== Quiver Plot ========================
+
+/// A rendered quiver plot.
+pub struct Quiver {
+    pub rgba: Vec<u8>, // one pixel per cell of the world
+    pub scale: f32,    // arrow pixels per cell/step of block mean velocity
+}
+
+fn plot(rgba: &mut [u8], [width, height]: [u32; 2], x: f32, y: f32, color: [u8; 3]) {
+    let (x, y) = (x.round(), y.round());
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+        return;
+    }
+    let i = (y as usize * width as usize + x as usize) * 4;
+    rgba[i..i + 3].copy_from_slice(&color);
+}
+
+fn line(rgba: &mut [u8], size: [u32; 2], from: [f32; 2], to: [f32; 2], color: [u8; 3]) {
+    let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil().max(1.0);
+    for k in 0..=steps as u32 {
+        let t = k as f32 / steps;
+        plot(rgba, size, from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t, color);
+    }
+}
+
+/// Quiver plot of a flat (vx, vy) field of a `size` world: per-cell speed
+/// in gray (its 99th percentile white), one arrow per `stride` block from
+/// the block center, scaled so that the fastest block mean spans most of a
+/// block.
+pub fn quiver(velocity: &[f32], size: [u32; 2], stride: u32) -> Quiver {
+    let stride = stride.max(1);
+    let speed = speeds(velocity);
+    let top = auto_range(&speed, 1.0).1;
+    let mut rgba: Vec<u8> = speed
+        .iter()
+        .flat_map(|&s| {
+            let g = (12.0 + (s / top).clamp(0.0, 1.0) * 100.0) as u8;
+            [g, g, g, 255]
+        })
+        .collect();
+
+    let (cols, rows, means) = block_means(velocity, size, stride);
+    let longest = means.iter().map(|m| m[0].hypot(m[1])).fold(0.0, f32::max);
+    let scale = if longest > 0.0 { ARROW_FILL * stride as f32 / longest } else { 0.0 };
+    for row in 0..rows {
+        for col in 0..cols {
+            let [vx, vy] = means[(row * cols + col) as usize];
+            let length = vx.hypot(vy) * scale;
+            if length < MIN_ARROW {
+                continue;
+            }
+            let (dx, dy) = (vx / vx.hypot(vy), vy / vx.hypot(vy));
+            let center = [(col as f32 + 0.5) * stride as f32, (row as f32 + 0.5) * stride as f32];
+            let tail = [center[0] - dx * length / 2.0, center[1] - dy * length / 2.0];
+            let tip = [center[0] + dx * length / 2.0, center[1] + dy * length / 2.0];
+            line(&mut rgba, size, tail, tip, ARROW_COLOR);
+            // Head: two barbs at ±30° back from the tip
+            let barb = (length * 0.35).max(2.0);
+            let (cos, sin) = (30f32.to_radians().cos(), 30f32.to_radians().sin());
+            for side in [1.0, -1.0] {
+                let bx = -(dx * cos - side * dy * sin);
+                let by = -(dy * cos + side * dx * sin);
+                line(&mut rgba, size, tip, [tip[0] + bx * barb, tip[1] + by * barb], ARROW_COLOR);
+            }
+        }
+    }
+    Quiver { rgba, scale }
+}
+
+// ======================== Export ========================
+
+/// Write `velocity_f<frame>.npy` and/or `.png` to the run's velocity
+/// directory and add the frame to its index. Returns the files written.
+pub fn export_velocity(
+    run_dir: &Path,
+    frame: u32,
+    velocity: &[f32],
+    size: [u32; 2],
+    settings: &VelocityExportSettings,
+) -> Result<Vec<PathBuf>, String> {
+    let stem = velocity_stem(run_dir, frame);
+    let dir = run_dir.join(VELOCITY_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    let mut files = Vec::new();
+
+    if settings.npy {
+        let path = stem.with_extension("npy");
+        write_npy(&path, velocity, &[size[1] as usize, size[0] as usize, 2])?;
+        files.push(path);
+    }
+
+    let mut scale = None;
+    if settings.quiver {
+        let path = stem.with_extension("png");
+        let plot = quiver(velocity, size, settings.stride);
+        let file = fs::File::create(&path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+        image::codecs::png::PngEncoder::new(std::io::BufWriter::new(file))
+            .write_image(&plot.rgba, size[0], size[1], image::ExtendedColorType::Rgba8)
+            .map_err(|e| format!("Failed to encode {:?}: {}", path, e))?;
+        scale = Some(plot.scale);
+        files.push(path);
+    }
+
+    let index = dir.join(INDEX_FILE);
+    let new = !index.exists();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&index)
+        .map_err(|e| format!("Failed to open {:?}: {}", index, e))?;
+    if new {
+        writeln!(file, "frame,max_speed,mean_speed,arrow_scale").map_err(|e| format!("Write error: {}", e))?;
+    }
+    let speed = speeds(velocity);
+    let max = speed.iter().copied().fold(0.0, f32::max);
+    let mean = speed.iter().sum::<f32>() / speed.len().max(1) as f32;
+    let scale = scale.map_or_else(String::new, |s| format!("{s:.3}"));
+    writeln!(file, "{},{:.6},{:.6},{}", frame, max, mean, scale).map_err(|e| format!("Write error: {}", e))?;
+    Ok(files)
+}
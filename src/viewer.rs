@@ -0,0 +1,222 @@
+// ============================================================================
+// viewer.rs — EvoLenia v2
+// Lightweight viewer for a streamed headless run (`evolenia view
+// <host:port>`, stream.rs): one egui window showing the latest downsampled
+// frame, the run progress and the diagnostics with a plot of their history.
+// No simulation or world buffers are created, so it runs on a laptop while
+// the run itself stays on a cluster node.
+// ============================================================================
+
+use std::time::Instant;
+
+use egui_plot::{Line, Plot, PlotPoints};
+use winit::application::ApplicationHandler;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::window::WindowId;
+
+use crate::analysis_window::{AnalysisWindow, ANALYSIS_FRAME_INTERVAL};
+use crate::i18n::{self, tr, trf};
+use crate::settings::{UiSettings, SETTINGS_PATH};
+use crate::stream::{LinkStatus, StreamClient, ViewerFeed};
+
+/// Diagnostic plotted until another is picked.
+const DEFAULT_PLOT_METRIC: &str = "population/total_mass";
+
+struct ViewerGpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    window: AnalysisWindow,
+}
+
+struct ViewerApp {
+    client: StreamClient,
+    gpu: Option<ViewerGpu>,
+    style: egui::Context, // theme and zoom of the lab settings
+    texture: Option<(u64, egui::TextureHandle)>, // (frame serial, texture)
+    plot_metric: String,
+    error: Option<String>,
+}
+
+/// Open the viewer window on the stream at `addr` until it is closed.
+pub fn run_viewer(addr: &str) -> Result<(), String> {
+    let style = egui::Context::default();
+    if let Some(settings) = UiSettings::load(SETTINGS_PATH) {
+        i18n::set_language(settings.language);
+        style.set_visuals(settings.ui_theme.visuals());
+        style.set_zoom_factor(settings.ui_scale.clamp(0.5, 3.0));
+    }
+    let mut app = ViewerApp {
+        client: StreamClient::connect(addr),
+        gpu: None,
+        style,
+        texture: None,
+        plot_metric: DEFAULT_PLOT_METRIC.to_string(),
+        error: None,
+    };
+    let event_loop = EventLoop::new().map_err(|e| e.to_string())?;
+    event_loop.run_app(&mut app).map_err(|e| e.to_string())?;
+    app.error.map_or(Ok(()), Err)
+}
+
+impl ViewerApp {
+    fn open(event_loop: &ActiveEventLoop) -> Result<ViewerGpu, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .ok_or("Failed to get a GPU adapter for the viewer")?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("evolenia_viewer_device"),
+                ..Default::default()
+            },
+            None,
+        ))
+        .map_err(|e| format!("Failed to create viewer device: {e}"))?;
+        let window = AnalysisWindow::open(
+            event_loop,
+            "EvoLenia v2 — Viewer",
+            &instance,
+            &adapter,
+            &device,
+            wgpu::PresentMode::Fifo,
+            None,
+        )?;
+        Ok(ViewerGpu { device, queue, window })
+    }
+}
+
+impl ApplicationHandler for ViewerApp {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_some() {
+            return;
+        }
+        match Self::open(event_loop) {
+            Ok(gpu) => self.gpu = Some(gpu),
+            Err(e) => {
+                self.error = Some(e);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        let Some(gpu) = &mut self.gpu else {
+            return;
+        };
+        gpu.window.on_window_event(&event);
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => gpu.window.resize(&gpu.device, size.width, size.height),
+            WindowEvent::RedrawRequested => {
+                let (client, texture, plot_metric) = (&self.client, &mut self.texture, &mut self.plot_metric);
+                gpu.window.redraw(&gpu.device, &gpu.queue, &self.style, |ctx| {
+                    render_viewer(ctx, client.addr(), &client.feed(), texture, plot_metric);
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Frames arrive at the stream rate: redraw on a timer, not every loop
+        if let Some(gpu) = &self.gpu {
+            let now = Instant::now();
+            if gpu.window.redraw_due(now) {
+                gpu.window.window.request_redraw();
+            }
+            event_loop.set_control_flow(ControlFlow::WaitUntil(now + ANALYSIS_FRAME_INTERVAL));
+        }
+    }
+}
+
+fn render_viewer(
+    ctx: &egui::Context,
+    addr: &str,
+    feed: &ViewerFeed,
+    texture: &mut Option<(u64, egui::TextureHandle)>,
+    plot_metric: &mut String,
+) {
+    egui::TopBottomPanel::top("viewer_status").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            match &feed.status {
+                LinkStatus::Connecting => ui.label(trf("viewer.connecting", &[("addr", &addr)])),
+                LinkStatus::Connected => ui.label(trf("viewer.connected", &[("addr", &addr)])),
+                LinkStatus::Disconnected(error) => ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    trf("viewer.disconnected", &[("addr", &addr), ("error", error)]),
+                ),
+            };
+            if feed.hello.is_some() {
+                ui.separator();
+                let m = &feed.metrics;
+                ui.label(trf("viewer.progress", &[("frame", &m.frame), ("total", &m.frames_total)]));
+                ui.label(trf("viewer.steps_per_sec", &[("rate", &format!("{:.0}", m.steps_per_sec))]));
+            }
+        });
+    });
+
+    egui::SidePanel::right("viewer_metrics").default_width(320.0).show(ctx, |ui| {
+        ui.heading(tr("viewer.diagnostics"));
+        let Some(sample_frame) = feed.metrics.diagnostics_frame else {
+            ui.label(tr("viewer.no_diagnostics"));
+            return;
+        };
+        ui.label(trf("viewer.sample_frame", &[("frame", &sample_frame)]));
+        egui::ComboBox::from_label(tr("viewer.plot_metric"))
+            .selected_text(plot_metric.as_str())
+            .show_ui(ui, |ui| {
+                for (tag, _) in &feed.metrics.diagnostics {
+                    ui.selectable_value(plot_metric, tag.clone(), tag);
+                }
+            });
+        Plot::new("viewer_plot")
+            .height(180.0)
+            .allow_scroll(false)
+            .show(ui, |plot| plot.line(Line::new(PlotPoints::from(feed.series(plot_metric))).name(plot_metric.as_str())));
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("viewer_diagnostics").striped(true).show(ui, |ui| {
+                for (tag, value) in &feed.metrics.diagnostics {
+                    ui.label(tag);
+                    ui.monospace(format!("{:.4}", value));
+                    ui.end_row();
+                }
+            });
+        });
+    });
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let Some(frame) = &feed.frame else {
+            ui.centered_and_justified(|ui| ui.label(tr("viewer.waiting")));
+            return;
+        };
+        if texture.as_ref().is_none_or(|(serial, _)| *serial != feed.frame_serial) {
+            let image =
+                egui::ColorImage::from_rgba_unmultiplied([frame.width as usize, frame.height as usize], &frame.rgba);
+            match texture {
+                Some((serial, handle)) => {
+                    handle.set(image, egui::TextureOptions::NEAREST);
+                    *serial = feed.frame_serial;
+                }
+                None => {
+                    let handle = ctx.load_texture("viewer_frame", image, egui::TextureOptions::NEAREST);
+                    *texture = Some((feed.frame_serial, handle));
+                }
+            }
+        }
+        if let Some((_, handle)) = texture {
+            // Largest size that fits the panel, keeping the frame aspect ratio
+            let available = ui.available_size();
+            let scale = (available.x / frame.width as f32).min(available.y / frame.height as f32).max(0.0);
+            let size = egui::vec2(frame.width as f32, frame.height as f32) * scale;
+            ui.centered_and_justified(|ui| ui.add(egui::Image::new((handle.id(), size)).fit_to_exact_size(size)));
+        }
+    });
+}
@@ -1,35 +1,130 @@
 // ============================================================================
 // world.rs — EvoLenia v2
-// WorldState: manages all GPU buffers (ping-pong pairs) and provides
+// WorldState: manages all GPU resources (ping-pong pairs) and provides
 // initialization with random seed clusters for the simulation.
+// Mass, energy and genome B are interleaved per cell in one storage buffer
+// per ping-pong side (CellState), in the world's cell layout: a pass binds
+// the whole state at once, and buffers can be read and written in place on
+// any device. Genome A stays a 2D texture for filtered sampling in the
+// renderer; auxiliary fields are flat storage buffers. Snapshots use the
+// flat row-major layout either way.
 // ============================================================================
 
+use std::collections::BTreeMap;
+
 use bytemuck::{Pod, Zeroable};
 use rand::Rng;
 use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 use wgpu::util::DeviceExt;
 
-use crate::config::SimulationParams;
+use crate::averages::FieldAverages;
+use crate::channels::ChannelBuffers;
+use crate::config::{ConvolutionSettings, FieldAverageSettings, HistogramField, SimulationParams, BARRIER_WIDTH, GENE_LIMITS, MAX_SUM_INTERVAL};
+use crate::display::identity_lut;
+use crate::flow::FlowField;
+use crate::histogram::{HistogramParams, HISTOGRAM_BINS};
+use crate::metrics::{GenomeClusters, InteractionMatrix, AGG_CLASSES, SPECIES_THRESHOLD};
+use crate::popgen::LIVE_MASS_THRESHOLD;
+use crate::reserves::{reserve_mask, reserve_uniforms, ReserveZone};
+use crate::perturbation::PerturbationType;
+use crate::particles::{initial_particles, Particle, ParticleParams, ParticleStats, MAX_PARTICLES};
+use crate::kernel::{kernel_lut, SeparableKernelUniform, KERNEL_LUT_MAX_BINS, KERNEL_LUT_STRIDE, SEPARABLE_RANK};
+use crate::territory::{centroid_table, species_palette, MAX_SPECIES};
+use crate::theme::ColorPalette;
 
 // ======================== Constants ========================
 
-// Performance tuning:
+// Performance tuning (`--world WxH` at startup, or resize in the Lab):
 // - 512×512 = 4× faster than 1024×1024 (good for development/testing)
-// - 1024×1024 = balanced (default, ~60 FPS on M1 Pro)
+// - 1024×1024 = balanced (~60 FPS on M1 Pro)
 // - 2048×2048 = highest quality (requires powerful GPU)
-pub const WORLD_WIDTH: u32 = 512;   // Try 512 for 4× speed boost
-pub const WORLD_HEIGHT: u32 = 512;
+// Width and height may differ (e.g. 2048×512 "rivers", with anisotropy > 1);
+// the width must stay a multiple of 64, and Morton layout needs a square.
+pub const DEFAULT_WORLD_SIZE: [u32; 2] = [512, 512];
+/// Smallest and largest world edge accepted (`check_world_size`).
+pub const MIN_WORLD_EDGE: u32 = 64;
+pub const MAX_WORLD_EDGE: u32 = 8192;
 pub const WORKGROUP_X: u32 = 16;
 pub const WORKGROUP_Y: u32 = 16;
+pub const WORKGROUP_LINEAR: u32 = 256; // 1D passes (mass reduction/normalization)
 pub const DT: f32 = 0.1;        // reduced for stability (was 0.1), try 0.1 for 2× speed
 pub const TARGET_FILL: f32 = 0.15; // 15% initial mass fill
 
-pub fn total_pixels() -> u32 {
-    WORLD_WIDTH * WORLD_HEIGHT
+/// Texture formats of the field textures: one f32 per pixel (reference mass,
+/// reserve mask) or a vec4 (genome A).
+pub const SCALAR_FIELD_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+pub const GENOME_FIELD_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+/// Territory overlay (territory.rs): species color and ownership strength.
+pub const TERRITORY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+/// Flow field (flow.rs): one vector per cell.
+pub const FLOW_FIELD_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Float;
+/// Separable convolution scratch: one channel per kernel tier.
+pub const CONV_FIELD_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+// ======================== World Size ========================
+
+/// Whether a world can be `width` × `height`. Field textures are copied to
+/// and from buffers row by row without padding, so the width must be a
+/// multiple of 64; the height must fill whole 16-row workgroups.
+pub fn check_world_size([width, height]: [u32; 2]) -> Result<(), String> {
+    let edges = MIN_WORLD_EDGE..=MAX_WORLD_EDGE;
+    if !edges.contains(&width) || !edges.contains(&height) {
+        return Err(format!("world edges must be between {} and {}", MIN_WORLD_EDGE, MAX_WORLD_EDGE));
+    }
+    if !(width * 4).is_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) {
+        return Err(format!("world width {} is not a multiple of 64", width));
+    }
+    if !height.is_multiple_of(WORKGROUP_Y) {
+        return Err(format!("world height {} is not a multiple of {}", height, WORKGROUP_Y));
+    }
+    Ok(())
+}
+
+/// Parse a `WIDTHxHEIGHT` world size (`x` or `×`), or one edge for a square.
+pub fn parse_world_size(text: &str) -> Option<[u32; 2]> {
+    let text = text.trim();
+    match text.split_once(['x', 'X', '×']) {
+        Some((w, h)) => Some([w.trim().parse().ok()?, h.trim().parse().ok()?]),
+        None => text.parse().ok().map(|edge| [edge, edge]),
+    }
 }
 
-pub fn target_total_mass() -> f32 {
-    WORLD_WIDTH as f32 * WORLD_HEIGHT as f32 * TARGET_FILL
+/// Mass the normalization holds a `size` world at.
+pub fn target_mass_of([width, height]: [u32; 2]) -> f32 {
+    width as f32 * height as f32 * TARGET_FILL
+}
+
+// ======================== Mass Normalization Cadence ========================
+
+/// How the mass normalization passes are encoded for one step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalizeStep {
+    Skip,     // neither pass: normalization off, or drift below threshold
+    Measure,  // sum pass, then normalize with the fresh total
+    Estimate, // normalize only, extrapolating from the last two sums
+}
+
+/// Decide the normalization passes of the step at `frame`, given the frame of
+/// the latest usable sum pass and the drift measured at the last readback.
+pub fn plan_normalization(
+    params: &SimulationParams,
+    frame: u32,
+    last_sum_frame: Option<u32>,
+    drift: Option<f32>,
+) -> NormalizeStep {
+    let cadence = params.normalization_cadence;
+    if !params.mass_normalization_enabled
+        || params.carrying_capacity.replaces_normalization()
+        || drift.is_some_and(|d| d < cadence.drift_threshold)
+    {
+        return NormalizeStep::Skip;
+    }
+    let interval = cadence.sum_interval.clamp(1, MAX_SUM_INTERVAL);
+    match last_sum_frame.and_then(|f| frame.checked_sub(f)) {
+        Some(gap) if gap < interval => NormalizeStep::Estimate,
+        _ => NormalizeStep::Measure,
+    }
 }
 
 // ======================== Uniform Structs ========================
@@ -46,9 +141,27 @@ pub struct SimParams {
     pub radius_cost_exp: f32,
     pub agg_mobility: f32,
     pub starvation_severity: f32,
-    pub _pad1: u32,
-    pub _pad2: u32,
-    pub _pad3: u32,
+    pub convolution: u32, // 0 = exact, 1 = separable, 2 = separable + comparison
+    pub kernel_lut_bins: u32, // radius bins in kernel_lut, 0 = evaluate exp()
+    pub fused_velocity: u32, // 1 = evolution computes velocities, 0 = separate velocity pass
+    pub interactions: u32,   // 1 = count genome takeovers into interaction_counts
+    pub anisotropy: f32,     // horizontal / vertical advection strength
+    pub flow_strength: f32,  // scale of the flow field added to the velocity (0 = off)
+    pub rotation: f32,       // Coriolis angular velocity ω (0 = no rotation)
+    pub rotation_center: [f32; 2], // pole, in cells
+    pub capacity: u32,              // 1 = logistic growth against the local carrying capacity
+    pub capacity_base: f32,         // K = base + per_resource · resource
+    pub capacity_per_resource: f32,
+    pub demographic_noise: f32, // amplitude of the growth noise (0 = off)
+    pub noise_seed: u32,        // stream of the demographic noise
+    pub mutation_operator: u32, // MutationOperator::shader_id
+    pub gene_rates: [f32; 4],   // per-gene mutation step multiplier
+    pub mutation_jump: f32,
+    pub mutation_correlation: f32,
+    pub gene_lock: u32,         // bit g = gene g does not mutate
+    pub _pad9: u32,
+    pub gene_min: [f32; 4],     // mutations keep genes in [gene_min, gene_max]
+    pub gene_max: [f32; 4],
 }
 
 #[repr(C)]
@@ -57,7 +170,10 @@ pub struct VelocityParams {
     pub width: u32,
     pub height: u32,
     pub frame: u32,
-    pub _pad: u32,
+    pub anisotropy: f32,
+    pub flow_strength: f32, // as in SimParams
+    pub rotation: f32,
+    pub rotation_center: [f32; 2],
 }
 
 #[repr(C)]
@@ -68,8 +184,8 @@ pub struct ResourceParams {
     pub diffusion: f32,
     pub feed_rate: f32,
     pub consumption: f32,
-    pub _pad1: u32,
-    pub _pad2: u32,
+    pub anisotropy: f32, // horizontal / vertical diffusion strength
+    pub flow_transport: f32, // scale of the flow field advecting resources (0 = off)
     pub _pad3: u32,
 }
 
@@ -81,9 +197,107 @@ pub struct NormalizeParams {
     pub target_mass_x1000: u32,
     pub damping_x1000: u32,
     pub enabled: u32,
-    pub _pad1: u32,
-    pub _pad2: u32,
-    pub _pad3: u32,
+    pub sum_slot: u32,        // mass_sum slot of the latest sum (the other holds the previous one)
+    pub steps_since_sum: u32, // 0 = this step measures, else extrapolate
+    pub drift_interval: u32,  // steps between the last two sums, 0 = unknown
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct TrailParams {
+    pub width: u32,
+    pub height: u32,
+    pub decay: f32,
+    pub gain: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SpeciesParams {
+    pub count: u32,     // clusters in the centroid table
+    pub full: u32,      // 1 = the cluster table is full
+    pub threshold: f32, // genome distance joining a cluster
+    pub live_mass: f32, // lighter cells are empty
+}
+
+impl SpeciesParams {
+    pub fn new(species: &GenomeClusters) -> Self {
+        Self {
+            count: species.count().min(MAX_SPECIES) as u32,
+            full: species.is_full() as u32,
+            threshold: SPECIES_THRESHOLD,
+            live_mass: LIVE_MASS_THRESHOLD,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct AverageParams {
+    pub width: u32,
+    pub height: u32,
+    pub weight: f32, // of the newest step
+    pub _pad: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct PerturbationParams {
+    pub kind: u32,      // PerturbationType::shader_kind
+    pub center_x: f32,  // pixels
+    pub center_y: f32,
+    pub radius: f32,    // pixels
+    pub intensity: f32,
+    pub extra: [f32; 3], // type-specific (Perturbation::uniform)
+}
+
+impl PerturbationParams {
+    /// The configured perturbation of a `size` world at the given
+    /// intensity, as its type builds it (perturbation.rs).
+    pub fn new(params: &SimulationParams, intensity: f32, size: [u32; 2]) -> Self {
+        params.perturbation_type.plugin().uniform(params, intensity, size)
+    }
+
+    /// The configured disc of a `size` world at the given intensity, in
+    /// pixel units.
+    pub fn disc(params: &SimulationParams, intensity: f32, [width, height]: [u32; 2]) -> Self {
+        let w = width as f32;
+        Self {
+            kind: params.perturbation_type.shader_kind(),
+            center_x: params.perturbation_center_x * w,
+            center_y: params.perturbation_center_y * height as f32,
+            radius: params.perturbation_radius * w,
+            intensity,
+            extra: [0.0; 3],
+        }
+    }
+
+    /// The walls of a barrier at column `x` (and `x` + width/2), in pixels.
+    pub fn barrier(x: f32) -> Self {
+        Self {
+            kind: PerturbationType::Barrier.shader_kind(),
+            center_x: x,
+            center_y: 0.0,
+            radius: BARRIER_WIDTH * 0.5,
+            intensity: 1.0,
+            extra: [0.0; 3],
+        }
+    }
+}
+
+/// A raised barrier: two walls, at column `x` and half a world away, that
+/// clear everything under them every step until `frames_left` runs out.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Barrier {
+    pub x: f32, // pixels
+    pub frames_left: u32,
+}
+
+impl Barrier {
+    /// The barrier after one more step, None once it has been lifted.
+    pub fn tick(self) -> Option<Self> {
+        (self.frames_left > 1).then(|| Self { frames_left: self.frames_left - 1, ..self })
+    }
 }
 
 #[repr(C)]
@@ -92,64 +306,53 @@ pub struct RenderParams {
     pub width: u32,
     pub height: u32,
     pub visualization_mode: u32,
-    pub _pad: u32,
+    pub diff_range: f32, // |mass - reference| shown at full overlay color
+    pub exposure: f32,
+    pub gamma: f32,
+    pub range_min: f32,
+    pub range_max: f32,
+    pub transform: u32, // 0 = linear, 1 = log, 2 = histogram-equalized
+    pub hdr_emission: f32,
+    pub trail_opacity: f32, // 0 = trail layer hidden
+    pub diff_opacity: f32,  // 0 = reference diff overlay hidden
+    pub territory_opacity: f32, // 0 = territory overlay hidden
+    pub particle_opacity: f32,  // 0 = particle markers hidden
+    pub flow_opacity: f32,      // 0 = flow streamlines hidden
+    pub species_colors: u32,    // 1 = Species Color uses the species palette by species map id
 }
 
 // ======================== WorldState ========================
 
 /// Raw CPU-side snapshot of simulation buffers (obtained via GPU readback).
+#[derive(Clone)]
 pub struct BufferSnapshot {
+    pub size: [u32; 2], // width × height of the world the fields were read from
     pub mass: Vec<f32>,
     pub energy: Vec<f32>,
     pub genome_a: Vec<f32>, // flat vec4 per pixel (len = n*4)
     pub genome_b: Vec<f32>,
     pub resource: Vec<f32>,
+    pub flow: Vec<f32>, // flat vec2 per pixel, row-major; empty = no flow field
+    pub channels: BTreeMap<String, Vec<f32>>, // snapshot channels of the registry (channels.rs), row-major
 }
 
-pub struct WorldState {
-    // Ping-pong buffer index: 0 or 1
-    pub current: usize,
-
-    // Mass buffers (ping-pong)
-    pub mass: [wgpu::Buffer; 2],
-    // Energy buffers (ping-pong)
-    pub energy: [wgpu::Buffer; 2],
-    // Genome A buffers: vec4(r, mu, sigma, aggressivity) (ping-pong)
-    pub genome_a: [wgpu::Buffer; 2],
-    // Genome B buffers: f32 mutation_rate (ping-pong)
-    pub genome_b: [wgpu::Buffer; 2],
-
-    // Single buffers (updated in-place)
-    pub resource_map: wgpu::Buffer,
-    pub velocity: wgpu::Buffer,
-
-    // Atomic sum buffer for mass normalization
-    pub mass_sum: wgpu::Buffer,
-
-    // Staging buffers for CPU readback (diagnostics)
-    pub staging_mass: wgpu::Buffer,
-    pub staging_energy: wgpu::Buffer,
-    pub staging_genome_a: wgpu::Buffer,
-    pub staging_genome_b: wgpu::Buffer,
-    pub staging_resource: wgpu::Buffer,
-
-    // Uniform buffers
-    pub sim_params_buffer: wgpu::Buffer,
-    pub velocity_params_buffer: wgpu::Buffer,
-    pub resource_params_buffer: wgpu::Buffer,
-    pub normalize_params_buffer: wgpu::Buffer,
-    pub render_params_buffer: wgpu::Buffer,
-
-    pub frame: u32,
+/// CPU state of a new world: the seeded ecosystem and resource landscape
+/// (row-major) and the particle pool.
+pub struct InitialFields {
+    pub mass: Vec<f32>,
+    pub energy: Vec<f32>,
+    pub genome_a: Vec<f32>, // flat vec4 per pixel
+    pub genome_b: Vec<f32>,
+    pub resource: Vec<f32>,
+    pub particles: Vec<Particle>,
 }
 
-impl WorldState {
-    pub fn new(device: &wgpu::Device) -> Self {
-        Self::new_with_seed(device, None)
-    }
-
-    pub fn new_with_seed(device: &wgpu::Device, seed: Option<u64>) -> Self {
-        let n = total_pixels() as usize;
+impl InitialFields {
+    /// Generate the initial state of a `size` world; the same seed and size
+    /// give the same state.
+    pub fn generate(seed: Option<u64>, size: [u32; 2]) -> Self {
+        let [width, height] = size;
+        let n = (width * height) as usize;
         let mut rng: rand::rngs::StdRng = match seed {
             Some(s) => rand::rngs::StdRng::seed_from_u64(s),
             None => rand::rngs::StdRng::from_entropy(),
@@ -173,14 +376,14 @@ impl WorldState {
         //   4. Spirals — rotating arm patterns
         //   5. Scattered noise patches — diffuse low-density clouds
 
-        let w = WORLD_WIDTH as i32;
-        let h = WORLD_HEIGHT as i32;
+        let w = width as i32;
+        let h = height as i32;
 
         // Helper: toroidal pixel index
         let pixel_idx = |px: i32, py: i32| -> usize {
             let wx = ((px % w) + w) % w;
             let wy = ((py % h) + h) % h;
-            (wy as u32 * WORLD_WIDTH + wx as u32) as usize
+            (wy as u32 * width + wx as u32) as usize
         };
 
         // Helper: write a pixel with mass/genome, blending with existing mass
@@ -338,7 +541,7 @@ impl WorldState {
         for _ in 0..num_patches {
             let cx = rng.gen_range(0..w);
             let cy = rng.gen_range(0..h);
-            let patch_r = rng.gen_range(15..40) as i32;
+            let patch_r: i32 = rng.gen_range(15..40);
             let density: f32 = rng.gen_range(0.05..0.15);
             let (genome, mut_rate) = random_genome(&mut rng);
 
@@ -514,16 +717,170 @@ impl WorldState {
         let freq_x: f32 = rng.gen_range(1.0..4.0) * std::f32::consts::TAU / w as f32;
         let freq_y: f32 = rng.gen_range(1.0..4.0) * std::f32::consts::TAU / h as f32;
         let phase: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
-        for py in 0..WORLD_HEIGHT {
-            for px in 0..WORLD_WIDTH {
-                let idx = (py * WORLD_WIDTH + px) as usize;
+        for py in 0..height {
+            for px in 0..width {
+                let idx = (py * width + px) as usize;
                 let wave = (px as f32 * freq_x + py as f32 * freq_y + phase).sin() * 0.1;
                 resource_data[idx] = (resource_data[idx] + wave).clamp(0.05, 1.0);
             }
         }
 
-        // Flatten genome_a to f32 for bytemuck
-        let genome_a_flat: Vec<f32> = genome_a_data.iter().flat_map(|g| g.iter().copied()).collect();
+
+        let particles = initial_particles(&mut rng, size);
+        Self {
+            mass: mass_data,
+            energy: energy_data,
+            genome_a: genome_a_data.iter().flat_map(|g| g.iter().copied()).collect(),
+            genome_b: genome_b_data,
+            resource: resource_data,
+            particles,
+        }
+    }
+}
+
+pub struct WorldState {
+    // Ping-pong buffer index: 0 or 1
+    pub current: usize,
+
+    // Cell state: mass, energy and genome B (mutation rate) per cell,
+    // interleaved (ping-pong, cells ordered by `layout`)
+    pub cells: [wgpu::Buffer; 2],
+    // Genome A fields: vec4(r, mu, sigma, aggressivity) (ping-pong)
+    pub genome_a: [wgpu::Texture; 2],
+
+    // Single buffers (updated in-place), cells ordered by `layout`
+    pub resource_map: wgpu::Buffer,
+    pub velocity: wgpu::Buffer,
+    // Registered per-cell channels (channels.rs), e.g. the trail afterimage
+    pub channels: ChannelBuffers,
+    // Running means of mass, resource and flow speed (three planes, see
+    // averages.rs) and the number of steps averaged so far
+    pub field_average: wgpu::Buffer,
+    pub average_samples: u32,
+    // Mass of a loaded reference snapshot, for the diff overlay
+    pub reference_mass: wgpu::Texture,
+    // Territory overlay: owner species color, alpha = share of the window held
+    pub territory: wgpu::Texture,
+    // Species map pass (compute_species.wgsl): cluster representatives, one
+    // vec4 per species id, and the color of each id for the render
+    pub species_centroids: wgpu::Buffer,
+    pub species_palette: wgpu::Buffer,
+    // Flow field: the authored CPU copy and its upload (see flow.rs)
+    pub flow: FlowField,
+    pub flow_field: wgpu::Texture,
+    // Temporary walls (PerturbationType::Barrier), applied before every step
+    pub barrier: Option<Barrier>,
+    pub barrier_params_buffer: wgpu::Buffer,
+    // Reserve zones (reserves.rs): zone id per cell, the per-zone uniform
+    // array, and the zones they were built from (with the CPU copy of the ids)
+    pub reserve_mask: wgpu::Texture,
+    pub reserve_params_buffer: wgpu::Buffer,
+    pub reserves: Vec<ReserveZone>,
+    pub reserve_ids: Vec<f32>,
+
+    // Atomic sum buffer for mass normalization: two slots alternating
+    // between sum passes, plus the cadence bookkeeping (see plan_normalization)
+    pub mass_sum: wgpu::Buffer,
+    pub mass_sum_slot: u32,
+    pub mass_measured_frame: Option<u32>, // frame of the latest sum pass, None = none usable
+    pub mass_drift_interval: u32,
+    pub mass_drift: Option<f32>, // |total / target - 1| at the last readback
+
+    // Separable convolution (see kernel.rs): horizontal pass output per
+    // separable term, per-tier kernel sums, decomposed kernel weights, and
+    // the accumulated error against the exact convolution
+    pub conv_terms: [wgpu::Texture; SEPARABLE_RANK],
+    pub conv_potential: wgpu::Texture,
+    pub separable_kernel_buffer: wgpu::Buffer,
+    pub conv_error: wgpu::Buffer,
+    pub staging_conv_error: wgpu::Buffer,
+    // Predation counts by attacker × defender aggressivity class (metrics.rs)
+    pub interaction_counts: wgpu::Buffer,
+    pub staging_interactions: wgpu::Buffer,
+    // Bin counts of the histogram overlay pass (histogram.rs)
+    pub histogram_bins: wgpu::Buffer,
+    pub staging_histogram: wgpu::Buffer,
+    // Grazer / disperser pool (particles.rs), the per-cell flux and depositor
+    // claims it leaves for the next apply pass, and its render markers
+    pub particles: wgpu::Buffer,
+    pub particle_flux: wgpu::Buffer,
+    pub particle_claims: wgpu::Buffer,
+    pub particle_markers: wgpu::Buffer,
+    pub staging_particles: wgpu::Buffer,
+    // Exact-convolution weights by genome radius bin (kernel.rs), and the
+    // bin count currently uploaded
+    pub kernel_lut: wgpu::Buffer,
+    pub kernel_lut_bins: u32,
+
+    // Staging buffers for CPU readback (diagnostics)
+    pub staging_cells: wgpu::Buffer,
+    pub staging_genome_a: wgpu::Buffer,
+    pub staging_resource: wgpu::Buffer,
+
+    // Uniform buffers
+    pub sim_params_buffer: wgpu::Buffer,
+    pub velocity_params_buffer: wgpu::Buffer,
+    pub resource_params_buffer: wgpu::Buffer,
+    pub normalize_params_buffer: wgpu::Buffer,
+    pub trail_params_buffer: wgpu::Buffer,
+    pub species_params_buffer: wgpu::Buffer,
+    pub histogram_params_buffer: wgpu::Buffer,
+    pub average_params_buffer: wgpu::Buffer,
+    pub perturbation_params_buffer: wgpu::Buffer,
+    pub particle_params_buffer: wgpu::Buffer,
+    pub render_params_buffer: wgpu::Buffer,
+
+    // Histogram-equalization CDF lookup table for the render shader
+    pub display_lut: wgpu::Buffer,
+    // Mode colors (see theme.rs)
+    pub palette_buffer: wgpu::Buffer,
+
+    // Cell order of the flat per-pixel buffers
+    pub layout: CellLayout,
+    // Lattice the cells sit on, fixed for the lifetime of the world
+    pub grid: GridType,
+    // Width × height in cells, fixed for the lifetime of the world
+    pub size: [u32; 2],
+
+    pub frame: u32,
+}
+
+impl WorldState {
+    /// World of `size` cells (see `check_world_size`).
+    pub fn new_with_seed(device: &wgpu::Device, queue: &wgpu::Queue, seed: Option<u64>, size: [u32; 2]) -> Self {
+        Self::new_with_layout(device, queue, seed, CellLayout::RowMajor, size)
+    }
+
+    /// World on the `grid` lattice (must be supported for `size`, see
+    /// `GridType::supported`).
+    pub fn new_with_grid(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        seed: Option<u64>,
+        grid: GridType,
+        size: [u32; 2],
+    ) -> Self {
+        Self { grid, ..Self::new_with_seed(device, queue, seed, size) }
+    }
+
+    /// World whose flat per-pixel buffers use `layout` (must be supported
+    /// for `size`, see `CellLayout::supported`).
+    pub fn new_with_layout(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        seed: Option<u64>,
+        layout: CellLayout,
+        size: [u32; 2],
+    ) -> Self {
+        let n = (size[0] * size[1]) as usize;
+        let InitialFields {
+            mass: mass_data,
+            energy: energy_data,
+            genome_a: genome_a_flat,
+            genome_b: genome_b_data,
+            resource: resource_data,
+            particles: particle_pool,
+        } = InitialFields::generate(seed, size);
 
         let usage = wgpu::BufferUsages::STORAGE
             | wgpu::BufferUsages::COPY_SRC
@@ -538,31 +895,85 @@ impl WorldState {
             })
         };
 
+        let create_field = |label: &str, format: wgpu::TextureFormat, data: &[f32]| -> wgpu::Texture {
+            device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: field_extent(size),
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING
+                        | wgpu::TextureUsages::STORAGE_BINDING
+                        | wgpu::TextureUsages::COPY_SRC
+                        | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                bytemuck::cast_slice(data),
+            )
+        };
+
         let zeros_f32 = vec![0.0f32; n];
         let zeros_vec2 = vec![0.0f32; n * 2];
         let zeros_vec4 = vec![0.0f32; n * 4];
 
         // Ping-pong pairs
-        let mass = [
-            create_f32_buffer("mass_0", &mass_data),
-            create_f32_buffer("mass_1", &zeros_f32),
-        ];
-        let energy = [
-            create_f32_buffer("energy_0", &energy_data),
-            create_f32_buffer("energy_1", &zeros_f32),
+        let create_cells = |label: &str, data: &[CellState]| -> wgpu::Buffer {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(data),
+                usage,
+            })
+        };
+        let cells = [
+            create_cells("cells_0", &interleave_cells(layout, size[0], &mass_data, &energy_data, &genome_b_data)),
+            create_cells("cells_1", &vec![CellState::default(); n]),
         ];
         let genome_a = [
-            create_f32_buffer("genome_a_0", &genome_a_flat),
-            create_f32_buffer("genome_a_1", &zeros_vec4),
-        ];
-        let genome_b = [
-            create_f32_buffer("genome_b_0", &genome_b_data),
-            create_f32_buffer("genome_b_1", &zeros_f32),
+            create_field("genome_a_0", GENOME_FIELD_FORMAT, &genome_a_flat),
+            create_field("genome_a_1", GENOME_FIELD_FORMAT, &zeros_vec4),
         ];
 
         // Single buffers
-        let resource_map = create_f32_buffer("resource_map", &resource_data);
+        let resource_map = create_f32_buffer("resource_map", &layout.arrange(&resource_data, size[0]));
         let velocity = create_f32_buffer("velocity", &zeros_vec2);
+        let field_average = create_f32_buffer("field_average", &vec![0.0f32; n * 3]);
+        let reference_mass = create_field("reference_mass", SCALAR_FIELD_FORMAT, &zeros_f32);
+        // Only sampled: Rg32Float has no storage support on downlevel adapters.
+        let flow_field = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("flow_field"),
+                size: field_extent(size),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: FLOW_FIELD_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&zeros_vec2),
+        );
+        let reserve_mask = create_field("reserve_mask", SCALAR_FIELD_FORMAT, &zeros_f32);
+        let territory = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("territory"),
+                size: field_extent(size),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: TERRITORY_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &vec![0u8; n * 4],
+        );
 
         // Atomic sum buffer for normalization (2 atomic u32s)
         let mass_sum = device.create_buffer(&wgpu::BufferDescriptor {
@@ -574,10 +985,108 @@ impl WorldState {
             mapped_at_creation: false,
         });
 
+        // Separable convolution scratch (rewritten every step it is used)
+        let create_scratch = |label: &str| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: field_extent(size),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: CONV_FIELD_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            })
+        };
+        let conv_terms = std::array::from_fn(|k| create_scratch(&format!("conv_term_{k}")));
+        let conv_potential = create_scratch("conv_potential");
+        let separable_kernel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("separable_kernel"),
+            contents: bytemuck::bytes_of(&SeparableKernelUniform::compute()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let conv_error = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("conv_error"),
+            size: 8, // 2 x u32
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_conv_error = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging_conv_error"),
+            size: 8,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let interactions_size = (AGG_CLASSES * AGG_CLASSES * std::mem::size_of::<u32>()) as u64;
+        let interaction_counts = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("interaction_counts"),
+            size: interactions_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_interactions = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging_interactions"),
+            size: interactions_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let histogram_size = (HISTOGRAM_BINS as usize * std::mem::size_of::<u32>()) as u64;
+        let histogram_bins = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("histogram_bins"),
+            size: histogram_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_histogram = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging_histogram"),
+            size: histogram_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let particles = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("particles"),
+            contents: bytemuck::cast_slice(&particle_pool),
+            usage,
+        });
+        let cell_counter_buffer = |label: &str| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: n as u64 * std::mem::size_of::<u32>() as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST, // cleared by `reset`
+                mapped_at_creation: false,
+            })
+        };
+        let particle_flux = cell_counter_buffer("particle_flux");
+        let particle_claims = cell_counter_buffer("particle_claims");
+        let particle_markers = cell_counter_buffer("particle_markers");
+        let staging_particles = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging_particles"),
+            size: (MAX_PARTICLES as usize * std::mem::size_of::<Particle>()) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Filled on the first dynamic uniform update that enables the LUT
+        let kernel_lut = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("kernel_lut"),
+            size: (KERNEL_LUT_MAX_BINS as usize * KERNEL_LUT_STRIDE * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         // ---- Uniform Buffers ----
         let sim_params = SimParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: size[0],
+            height: size[1],
             frame: 0,
             dt: DT,
             mutation_rate_mult: 1.0,
@@ -585,9 +1094,27 @@ impl WorldState {
             radius_cost_exp: 1.5,
             agg_mobility: 0.3,
             starvation_severity: 0.05,
-            _pad1: 0,
-            _pad2: 0,
-            _pad3: 0,
+            convolution: 0,
+            kernel_lut_bins: 0,
+            fused_velocity: 1,
+            interactions: 0,
+            anisotropy: 1.0,
+            flow_strength: 0.0,
+            rotation: 0.0,
+            rotation_center: [0.0; 2],
+            capacity: 0,
+            capacity_base: 0.0,
+            capacity_per_resource: 0.0,
+            demographic_noise: 0.0,
+            noise_seed: 0,
+            mutation_operator: 0,
+            gene_rates: [1.0; 4],
+            mutation_jump: 1.0,
+            mutation_correlation: 0.0,
+            gene_lock: 0,
+            _pad9: 0,
+            gene_min: GENE_LIMITS.map(|(lo, _)| lo),
+            gene_max: GENE_LIMITS.map(|(_, hi)| hi),
         };
         let sim_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("sim_params"),
@@ -596,10 +1123,13 @@ impl WorldState {
         });
 
         let velocity_params = VelocityParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: size[0],
+            height: size[1],
             frame: 0,
-            _pad: 0,
+            anisotropy: 1.0,
+            flow_strength: 0.0,
+            rotation: 0.0,
+            rotation_center: [0.0; 2],
         };
         let velocity_params_buffer =
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -609,13 +1139,13 @@ impl WorldState {
             });
 
         let resource_params = ResourceParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: size[0],
+            height: size[1],
             diffusion: 0.08,
             feed_rate: 0.010,
             consumption: 0.08,
-            _pad1: 0,
-            _pad2: 0,
+            anisotropy: 1.0,
+            flow_transport: 0.0,
             _pad3: 0,
         };
         let resource_params_buffer =
@@ -626,14 +1156,14 @@ impl WorldState {
             });
 
         let normalize_params = NormalizeParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
-            target_mass_x1000: (target_total_mass() * 1000.0) as u32,
+            width: size[0],
+            height: size[1],
+            target_mass_x1000: (target_mass_of(size) * 1000.0) as u32,
             damping_x1000: 300,
             enabled: 1,
-            _pad1: 0,
-            _pad2: 0,
-            _pad3: 0,
+            sum_slot: 0,
+            steps_since_sum: 0,
+            drift_interval: 0,
         };
         let normalize_params_buffer =
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -642,11 +1172,88 @@ impl WorldState {
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
+        let trail_params = TrailParams {
+            width: size[0],
+            height: size[1],
+            decay: 0.95,
+            gain: 20.0,
+        };
+        let trail_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("trail_params"),
+            contents: bytemuck::bytes_of(&trail_params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let species_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("species_params"),
+            contents: bytemuck::bytes_of(&SpeciesParams::new(&GenomeClusters::new(MAX_SPECIES))),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let histogram_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("histogram_params"),
+            contents: bytemuck::bytes_of(&HistogramParams::new(HistogramField::Mass, 1.0)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let species_centroids = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("species_centroids"),
+            size: (MAX_SPECIES * std::mem::size_of::<[f32; 4]>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let species_palette = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("species_palette"),
+            contents: bytemuck::cast_slice(&species_palette(&ColorPalette::default().uniforms().cyclic)),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let average_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("average_params"),
+            size: std::mem::size_of::<AverageParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let perturbation_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("perturbation_params"),
+            size: std::mem::size_of::<PerturbationParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let barrier_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("barrier_params"),
+            size: std::mem::size_of::<PerturbationParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let reserve_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("reserve_params"),
+            contents: bytemuck::cast_slice(&reserve_uniforms(&[])),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let particle_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("particle_params"),
+            size: std::mem::size_of::<ParticleParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let render_params = RenderParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: size[0],
+            height: size[1],
             visualization_mode: 0, // Default: Species Color
-            _pad: 0,
+            diff_range: 1.0,
+            exposure: 1.0,
+            gamma: 1.0,
+            range_min: 0.0,
+            range_max: 1.0,
+            transform: 0,
+            hdr_emission: 0.0,
+            trail_opacity: 0.0,
+            diff_opacity: 0.0,
+            territory_opacity: 0.0,
+            particle_opacity: 0.0,
+            flow_opacity: 0.0,
+            species_colors: 0,
         };
         let render_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("render_params"),
@@ -654,19 +1261,25 @@ impl WorldState {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        let display_lut = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("display_lut"),
+            contents: bytemuck::cast_slice(&identity_lut()),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let palette_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("palette"),
+            contents: bytemuck::bytes_of(&ColorPalette::default().uniforms()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
         // ---- Staging Buffers for CPU readback ----
         let staging_usage = wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST;
         let n_bytes_f32 = (n * std::mem::size_of::<f32>()) as u64;
 
-        let staging_mass = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("staging_mass"),
-            size: n_bytes_f32,
-            usage: staging_usage,
-            mapped_at_creation: false,
-        });
-        let staging_energy = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("staging_energy"),
-            size: n_bytes_f32,
+        let staging_cells = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging_cells"),
+            size: (n * std::mem::size_of::<CellState>()) as u64,
             usage: staging_usage,
             mapped_at_creation: false,
         });
@@ -676,12 +1289,6 @@ impl WorldState {
             usage: staging_usage,
             mapped_at_creation: false,
         });
-        let staging_genome_b = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("staging_genome_b"),
-            size: n_bytes_f32,
-            usage: staging_usage,
-            mapped_at_creation: false,
-        });
         let staging_resource = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("staging_resource"),
             size: n_bytes_f32,
@@ -691,32 +1298,123 @@ impl WorldState {
 
         WorldState {
             current: 0,
-            mass,
-            energy,
+            cells,
             genome_a,
-            genome_b,
             resource_map,
             velocity,
+            channels: ChannelBuffers::new(device, size),
+            field_average,
+            average_samples: 0,
+            reference_mass,
+            territory,
+            species_centroids,
+            species_palette,
+            flow: FlowField::zeros(size),
+            flow_field,
+            barrier: None,
+            barrier_params_buffer,
+            reserve_mask,
+            reserve_params_buffer,
+            reserves: Vec::new(),
+            reserve_ids: vec![0.0; n],
             mass_sum,
-            staging_mass,
-            staging_energy,
+            mass_sum_slot: 0,
+            mass_measured_frame: None,
+            mass_drift_interval: 0,
+            mass_drift: None,
+            conv_terms,
+            conv_potential,
+            separable_kernel_buffer,
+            conv_error,
+            staging_conv_error,
+            interaction_counts,
+            staging_interactions,
+            histogram_bins,
+            staging_histogram,
+            particles,
+            particle_flux,
+            particle_claims,
+            particle_markers,
+            staging_particles,
+            kernel_lut,
+            kernel_lut_bins: 0,
+            staging_cells,
             staging_genome_a,
-            staging_genome_b,
             staging_resource,
             sim_params_buffer,
             velocity_params_buffer,
             resource_params_buffer,
             normalize_params_buffer,
+            trail_params_buffer,
+            species_params_buffer,
+            histogram_params_buffer,
+            average_params_buffer,
+            perturbation_params_buffer,
+            particle_params_buffer,
             render_params_buffer,
+            display_lut,
+            palette_buffer,
+            layout,
+            grid: GridType::Square,
+            size,
             frame: 0,
         }
     }
 
-    /// Overwrite simulation buffers from a CPU snapshot.
+    /// Start over as `new_with_grid` would at the current size, but in the
+    /// existing buffers: no allocation, and every bind group built
+    /// on them stays valid. Pipelines need rebuilding only if `grid`
+    /// differs from the current grid (it is compiled into the shaders).
+    /// The authored flow field, the diff reference and the kernel lookup
+    /// table are kept.
+    pub fn reset(&mut self, queue: &wgpu::Queue, seed: Option<u64>, grid: GridType) {
+        let n = (self.size[0] * self.size[1]) as usize;
+        let init = InitialFields::generate(seed, self.size);
+        let zeros = vec![0.0f32; n * 4];
+        let zero = |k: usize| bytemuck::cast_slice(&zeros[..n * k]);
+
+        let cells = interleave_cells(self.layout, self.size[0], &init.mass, &init.energy, &init.genome_b);
+        queue.write_buffer(&self.cells[0], 0, bytemuck::cast_slice(&cells));
+        queue.write_buffer(&self.cells[1], 0, zero(3));
+        write_field(queue, &self.genome_a[0], bytemuck::cast_slice(&init.genome_a));
+        write_field(queue, &self.genome_a[1], zero(4));
+        queue.write_buffer(&self.resource_map, 0, bytemuck::cast_slice(&self.layout.arrange(&init.resource, self.size[0])));
+        queue.write_buffer(&self.velocity, 0, zero(2));
+        self.channels.clear(queue);
+        queue.write_buffer(&self.field_average, 0, zero(3));
+        write_field(queue, &self.territory, zero(1)); // RGBA8: 4 bytes per cell
+        self.upload_species(queue, &GenomeClusters::new(MAX_SPECIES));
+        write_field(queue, &self.reserve_mask, zero(1));
+        queue.write_buffer(&self.reserve_params_buffer, 0, bytemuck::cast_slice(&reserve_uniforms(&[])));
+        queue.write_buffer(&self.mass_sum, 0, &[0u8; 8]);
+        queue.write_buffer(&self.conv_error, 0, &[0u8; 8]);
+        queue.write_buffer(&self.interaction_counts, 0, &[0u8; AGG_CLASSES * AGG_CLASSES * 4]);
+        queue.write_buffer(&self.histogram_bins, 0, &[0u8; HISTOGRAM_BINS as usize * 4]);
+        queue.write_buffer(&self.particles, 0, bytemuck::cast_slice(&init.particles));
+        for counters in [&self.particle_flux, &self.particle_claims, &self.particle_markers] {
+            queue.write_buffer(counters, 0, zero(1));
+        }
+        queue.write_buffer(&self.display_lut, 0, bytemuck::cast_slice(&identity_lut()));
+
+        self.current = 0;
+        self.frame = 0;
+        self.grid = grid;
+        self.average_samples = 0;
+        self.barrier = None;
+        self.reserves = Vec::new();
+        self.reserve_ids = vec![0.0; n];
+        self.mass_sum_slot = 0;
+        self.mass_measured_frame = None;
+        self.mass_drift_interval = 0;
+        self.mass_drift = None;
+    }
+
+    /// Overwrite simulation fields from a CPU snapshot.
     /// Returns false if snapshot dimensions are incompatible with current world size.
     pub fn apply_snapshot(&mut self, queue: &wgpu::Queue, snapshot: &BufferSnapshot) -> bool {
-        let n = total_pixels() as usize;
-        if snapshot.mass.len() != n
+        let n = (self.size[0] * self.size[1]) as usize;
+        if snapshot.size != self.size
+            || snapshot.mass.len() != n
             || snapshot.energy.len() != n
             || snapshot.genome_a.len() != n * 4
             || snapshot.genome_b.len() != n
@@ -724,25 +1422,86 @@ impl WorldState {
         {
             return false;
         }
+        let flow = if snapshot.flow.is_empty() {
+            FlowField::zeros(self.size)
+        } else {
+            match FlowField::from_flat(&snapshot.flow, self.size) {
+                Some(flow) => flow,
+                None => return false,
+            }
+        };
 
-        let write_mass = bytemuck::cast_slice(snapshot.mass.as_slice());
-        let write_energy = bytemuck::cast_slice(snapshot.energy.as_slice());
+        let cells = interleave_cells(self.layout, self.size[0], &snapshot.mass, &snapshot.energy, &snapshot.genome_b);
+        let write_cells = bytemuck::cast_slice(cells.as_slice());
         let write_genome_a = bytemuck::cast_slice(snapshot.genome_a.as_slice());
-        let write_genome_b = bytemuck::cast_slice(snapshot.genome_b.as_slice());
-        let write_resource = bytemuck::cast_slice(snapshot.resource.as_slice());
+        let resource = self.layout.arrange(&snapshot.resource, self.size[0]);
+        let write_resource = bytemuck::cast_slice(resource.as_slice());
 
         for i in 0..2 {
-            queue.write_buffer(&self.mass[i], 0, write_mass);
-            queue.write_buffer(&self.energy[i], 0, write_energy);
-            queue.write_buffer(&self.genome_a[i], 0, write_genome_a);
-            queue.write_buffer(&self.genome_b[i], 0, write_genome_b);
+            queue.write_buffer(&self.cells[i], 0, write_cells);
+            write_field(queue, &self.genome_a[i], write_genome_a);
         }
         queue.write_buffer(&self.resource_map, 0, write_resource);
+        self.channels.upload(queue, self.layout, &snapshot.channels);
+        self.flow = flow;
+        self.upload_flow(queue);
 
         self.current = 0;
         true
     }
 
+    /// Upload `flow` after it was generated or painted.
+    pub fn upload_flow(&self, queue: &wgpu::Queue) {
+        write_field(queue, &self.flow_field, bytemuck::cast_slice(&self.flow.data));
+    }
+
+    /// Rebuild and upload the reserve mask and parameters if `zones` changed
+    /// since the last upload.
+    pub fn sync_reserves(&mut self, queue: &wgpu::Queue, zones: &[ReserveZone]) {
+        if self.reserves == zones {
+            return;
+        }
+        let shapes_changed = self.reserves.len() != zones.len()
+            || self.reserves.iter().zip(zones).any(|(a, b)| a.shape != b.shape || a.enabled != b.enabled);
+        if shapes_changed {
+            self.reserve_ids = reserve_mask(zones, self.size);
+            write_field(queue, &self.reserve_mask, bytemuck::cast_slice(&self.reserve_ids));
+        }
+        queue.write_buffer(&self.reserve_params_buffer, 0, bytemuck::cast_slice(&reserve_uniforms(zones)));
+        self.reserves = zones.to_vec();
+    }
+
+    /// Upload the mass field the diff overlay compares against.
+    /// Returns false if its size doesn't match the world.
+    pub fn upload_reference_mass(&self, queue: &wgpu::Queue, mass: &[f32]) -> bool {
+        if mass.len() != (self.size[0] * self.size[1]) as usize {
+            return false;
+        }
+        write_field(queue, &self.reference_mass, bytemuck::cast_slice(mass));
+        true
+    }
+
+    /// Upload the territory overlay (RGBA8, row-major).
+    /// Returns false if its size doesn't match the world.
+    pub fn upload_territory(&self, queue: &wgpu::Queue, rgba: &[u8]) -> bool {
+        if rgba.len() != (self.size[0] * self.size[1]) as usize * 4 {
+            return false;
+        }
+        write_field(queue, &self.territory, rgba);
+        true
+    }
+
+    /// Upload the species clusters the species map pass labels cells with.
+    pub fn upload_species(&self, queue: &wgpu::Queue, species: &GenomeClusters) {
+        queue.write_buffer(&self.species_params_buffer, 0, bytemuck::bytes_of(&SpeciesParams::new(species)));
+        queue.write_buffer(&self.species_centroids, 0, bytemuck::cast_slice(&centroid_table(species)));
+    }
+
+    /// Upload the species colors for the palette's hue `wheel`.
+    pub fn upload_species_palette(&self, queue: &wgpu::Queue, wheel: &[[f32; 4]; 6]) {
+        queue.write_buffer(&self.species_palette, 0, bytemuck::cast_slice(&species_palette(wheel)));
+    }
+
     /// Swap ping-pong buffers after a frame
     pub fn swap(&mut self) {
         self.current = 1 - self.current;
@@ -764,8 +1523,8 @@ impl WorldState {
     /// Kept minimal for performance: only fields actually consumed every frame.
     pub fn update_step_uniforms(&self, queue: &wgpu::Queue) {
         let sim_params = SimParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: self.size[0],
+            height: self.size[1],
             frame: self.frame,
             dt: DT,
             mutation_rate_mult: 1.0,
@@ -773,9 +1532,27 @@ impl WorldState {
             radius_cost_exp: 1.5,
             agg_mobility: 0.3,
             starvation_severity: 0.05,
-            _pad1: 0,
-            _pad2: 0,
-            _pad3: 0,
+            convolution: 0,
+            kernel_lut_bins: 0,
+            fused_velocity: 1,
+            interactions: 0,
+            anisotropy: 1.0,
+            flow_strength: 0.0,
+            rotation: 0.0,
+            rotation_center: [0.0; 2],
+            capacity: 0,
+            capacity_base: 0.0,
+            capacity_per_resource: 0.0,
+            demographic_noise: 0.0,
+            noise_seed: 0,
+            mutation_operator: 0,
+            gene_rates: [1.0; 4],
+            mutation_jump: 1.0,
+            mutation_correlation: 0.0,
+            gene_lock: 0,
+            _pad9: 0,
+            gene_min: GENE_LIMITS.map(|(lo, _)| lo),
+            gene_max: GENE_LIMITS.map(|(_, hi)| hi),
         };
         queue.write_buffer(&self.sim_params_buffer, 0, bytemuck::bytes_of(&sim_params));
 
@@ -783,11 +1560,27 @@ impl WorldState {
         queue.write_buffer(&self.mass_sum, 0, bytemuck::bytes_of(&[0u32; 2]));
     }
 
+    /// Convolution settings this world can run (see `GridType::convolution`).
+    pub fn convolution(&self, params: &SimulationParams) -> ConvolutionSettings {
+        self.grid.convolution(params.convolution)
+    }
+
     /// Update all uniforms using dynamic parameters from the Research Lab UI.
-    pub fn update_step_uniforms_dynamic(&self, queue: &wgpu::Queue, params: &SimulationParams) {
+    /// Returns how the mass normalization passes are to be encoded this step.
+    pub fn update_step_uniforms_dynamic(&mut self, queue: &wgpu::Queue, params: &SimulationParams) -> NormalizeStep {
+        let normalize = self.plan_step_normalization(params);
+        self.sync_reserves(queue, &params.reserves);
+        let convolution = self.convolution(params);
+        let lut_bins = convolution.kernel_lut_bins();
+        let (gene_min, gene_max) = params.gene_constraints.bounds();
+        if lut_bins > 0 && lut_bins != self.kernel_lut_bins {
+            queue.write_buffer(&self.kernel_lut, 0, bytemuck::cast_slice(&kernel_lut(lut_bins)));
+            self.kernel_lut_bins = lut_bins;
+        }
+
         let sim_params = SimParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: self.size[0],
+            height: self.size[1],
             frame: self.frame,
             dt: DT * params.time_step,
             mutation_rate_mult: params.mutation_rate,
@@ -795,132 +1588,173 @@ impl WorldState {
             radius_cost_exp: params.radius_cost_exponent,
             agg_mobility: params.agg_mobility_tradeoff,
             starvation_severity: params.starvation_severity,
-            _pad1: 0,
-            _pad2: 0,
-            _pad3: 0,
+            convolution: convolution.shader_mode(),
+            kernel_lut_bins: lut_bins,
+            fused_velocity: if params.separate_velocity_pass { 0 } else { 1 },
+            interactions: if params.interaction_matrix { 1 } else { 0 },
+            anisotropy: params.transport_anisotropy(),
+            flow_strength: params.flow.velocity_scale(),
+            rotation: params.rotation.effective_omega(),
+            rotation_center: params.rotation.center_cells(self.size),
+            capacity: params.carrying_capacity.enabled as u32,
+            capacity_base: params.carrying_capacity.base,
+            capacity_per_resource: params.carrying_capacity.per_resource,
+            demographic_noise: params.demographic_noise.effective_amplitude(),
+            noise_seed: params.demographic_noise.seed,
+            mutation_operator: params.mutation.operator.shader_id(),
+            gene_rates: params.mutation.gene_rates,
+            mutation_jump: params.mutation.jump_size.max(1.0),
+            mutation_correlation: params.mutation.correlation.clamp(0.0, 1.0),
+            gene_lock: params.gene_constraints.lock_mask(),
+            _pad9: 0,
+            gene_min,
+            gene_max,
         };
         queue.write_buffer(&self.sim_params_buffer, 0, bytemuck::bytes_of(&sim_params));
 
         let resource_params = ResourceParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
+            width: self.size[0],
+            height: self.size[1],
             diffusion: params.resource_diffusion,
             feed_rate: params.resource_feed_rate,
             consumption: params.resource_consumption,
-            _pad1: 0,
-            _pad2: 0,
+            anisotropy: params.transport_anisotropy(),
+            flow_transport: params.flow.resource_scale(),
             _pad3: 0,
         };
         queue.write_buffer(&self.resource_params_buffer, 0, bytemuck::bytes_of(&resource_params));
 
+        let velocity_params = VelocityParams {
+            width: self.size[0],
+            height: self.size[1],
+            frame: self.frame,
+            anisotropy: params.transport_anisotropy(),
+            flow_strength: params.flow.velocity_scale(),
+            rotation: params.rotation.effective_omega(),
+            rotation_center: params.rotation.center_cells(self.size),
+        };
+        queue.write_buffer(&self.velocity_params_buffer, 0, bytemuck::bytes_of(&velocity_params));
+
         let normalize_params = NormalizeParams {
-            width: WORLD_WIDTH,
-            height: WORLD_HEIGHT,
-            target_mass_x1000: (target_total_mass() * params.target_mass_multiplier * 1000.0) as u32,
+            width: self.size[0],
+            height: self.size[1],
+            target_mass_x1000: (target_mass_of(self.size) * params.target_mass_multiplier * 1000.0) as u32,
             damping_x1000: (params.mass_damping * 1000.0) as u32,
             enabled: if params.mass_normalization_enabled { 1 } else { 0 },
-            _pad1: 0,
-            _pad2: 0,
-            _pad3: 0,
+            sum_slot: self.mass_sum_slot,
+            steps_since_sum: self.mass_measured_frame.map_or(0, |f| self.frame.wrapping_sub(f)),
+            drift_interval: self.mass_drift_interval,
         };
         queue.write_buffer(&self.normalize_params_buffer, 0, bytemuck::bytes_of(&normalize_params));
 
-        // Reset mass_sum atomic to 0 before each normalization pass
-        queue.write_buffer(&self.mass_sum, 0, bytemuck::bytes_of(&[0u32; 2]));
+        let trail_params = TrailParams {
+            width: self.size[0],
+            height: self.size[1],
+            decay: params.trail.decay,
+            gain: params.trail.gain,
+        };
+        queue.write_buffer(&self.trail_params_buffer, 0, bytemuck::bytes_of(&trail_params));
+
+        if params.particles.enabled {
+            let particle_params = ParticleParams::new(&params.particles, self.frame);
+            queue.write_buffer(&self.particle_params_buffer, 0, bytemuck::bytes_of(&particle_params));
+        }
+
+        // Reset the slot the sum pass accumulates into
+        if normalize == NormalizeStep::Measure {
+            queue.write_buffer(&self.mass_sum, self.mass_sum_slot as u64 * 4, bytemuck::bytes_of(&0u32));
+        }
+        normalize
     }
 
-    /// Apply an ecological perturbation to the simulation buffers (CPU-side readback + writeback).
-    /// This performs a synchronous GPU readback, modifies the data, and writes it back.
-    pub fn apply_perturbation(
-        &self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        params: &SimulationParams,
-    ) {
-        use crate::config::PerturbationType;
+    /// Advance the normalization cadence for the step about to be encoded.
+    fn plan_step_normalization(&mut self, params: &SimulationParams) -> NormalizeStep {
+        let step = plan_normalization(params, self.frame, self.mass_measured_frame, self.mass_drift);
+        match step {
+            NormalizeStep::Skip => self.mass_measured_frame = None,
+            NormalizeStep::Measure => {
+                let gap = self.mass_measured_frame.map_or(0, |f| self.frame.wrapping_sub(f));
+                self.mass_drift_interval = if gap <= MAX_SUM_INTERVAL { gap } else { 0 };
+                self.mass_sum_slot = 1 - self.mass_sum_slot;
+                self.mass_measured_frame = Some(self.frame);
+            }
+            NormalizeStep::Estimate => {}
+        }
+        step
+    }
 
-        let snap = match self.readback_snapshot(device, queue) {
-            Some(s) => s,
-            None => return,
+    /// Set the weight of the averaging pass about to be encoded and count
+    /// its step.
+    pub fn update_average_uniform(&mut self, queue: &wgpu::Queue, settings: &FieldAverageSettings) {
+        let average_params = AverageParams {
+            width: self.size[0],
+            height: self.size[1],
+            weight: settings.weight(self.average_samples),
+            _pad: 0,
         };
+        queue.write_buffer(&self.average_params_buffer, 0, bytemuck::bytes_of(&average_params));
+        self.average_samples = self.average_samples.saturating_add(1);
+    }
 
-        let w = WORLD_WIDTH as f32;
-        let h = WORLD_HEIGHT as f32;
-        let cx = params.perturbation_center_x * w;
-        let cy = params.perturbation_center_y * h;
-        let radius = params.perturbation_radius * w;
-        let intensity = params.perturbation_intensity;
-
-        let mut resource = snap.resource.clone();
-        let mut mass = snap.mass.clone();
-        let mut energy = snap.energy.clone();
-        let mut genome_b = snap.genome_b.clone();
+    /// Start the running means over: the next averaging pass overwrites them.
+    pub fn reset_averages(&mut self) {
+        self.average_samples = 0;
+    }
 
-        let cur = self.cur();
+    /// Synchronous readback of the time-averaged fields, row-major.
+    /// None before the first averaging pass.
+    pub fn read_field_averages(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<FieldAverages> {
+        if self.average_samples == 0 {
+            return None;
+        }
+        let n = (self.size[0] * self.size[1]) as usize;
+        let planes = read_buffer_f32(device, queue, &self.field_average)?;
+        let plane = |k: usize| self.layout.row_major(&planes[k * n..(k + 1) * n], self.size[0]);
+        Some(FieldAverages { size: self.size, mass: plane(0), resource: plane(1), speed: plane(2), samples: self.average_samples })
+    }
 
-        for py in 0..WORLD_HEIGHT {
-            for px in 0..WORLD_WIDTH {
-                let idx = (py * WORLD_WIDTH + px) as usize;
-                // Toroidal distance
-                let mut dx = px as f32 - cx;
-                let mut dy = py as f32 - cy;
-                if dx > w * 0.5 { dx -= w; }
-                if dx < -w * 0.5 { dx += w; }
-                if dy > h * 0.5 { dy -= h; }
-                if dy < -h * 0.5 { dy += h; }
-                let dist = (dx * dx + dy * dy).sqrt();
-                if dist > radius { continue; }
-
-                let falloff = 1.0 - dist / radius;
-
-                match params.perturbation_type {
-                    PerturbationType::Drought => {
-                        // Reduce resources in area
-                        resource[idx] *= 1.0 - intensity * falloff * 0.8;
-                        resource[idx] = resource[idx].max(0.01);
-                    }
-                    PerturbationType::NutrientPulse => {
-                        // Boost resources in area
-                        resource[idx] += intensity * falloff * 0.5;
-                        resource[idx] = resource[idx].min(1.0);
-                    }
-                    PerturbationType::MassStorm => {
-                        // Catastrophe: reduce mass and energy
-                        let kill = intensity * falloff * 0.7;
-                        mass[idx] *= 1.0 - kill;
-                        energy[idx] *= 1.0 - kill * 0.5;
-                    }
-                    PerturbationType::MutationBurst => {
-                        // Amplify mutation rate locally (temporarily via genome_b)
-                        if mass[idx] > 0.01 {
-                            genome_b[idx] = (genome_b[idx] + intensity * falloff * 0.005).min(0.01);
-                        }
-                    }
-                    PerturbationType::None => {}
-                }
-            }
+    /// Synchronous readback of the velocity field: flat (vx, vy) per cell,
+    /// row-major, in cells per step.
+    pub fn read_velocity(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Vec<f32>> {
+        let data = read_buffer_f32(device, queue, &self.velocity)?;
+        if self.layout == CellLayout::RowMajor {
+            return Some(data);
         }
+        let component = |c: usize| self.layout.row_major(&data.iter().skip(c).step_by(2).copied().collect::<Vec<_>>(), self.size[0]);
+        let (vx, vy) = (component(0), component(1));
+        Some(vx.iter().zip(&vy).flat_map(|(&x, &y)| [x, y]).collect())
+    }
 
-        // Write back modified buffers
-        queue.write_buffer(&self.resource_map, 0, bytemuck::cast_slice(&resource));
-        queue.write_buffer(&self.mass[cur], 0, bytemuck::cast_slice(&mass));
-        queue.write_buffer(&self.energy[cur], 0, bytemuck::cast_slice(&energy));
-        queue.write_buffer(&self.genome_b[cur], 0, bytemuck::cast_slice(&genome_b));
+    /// Set the perturbation the next perturbation pass applies.
+    pub fn update_perturbation_uniform(&self, queue: &wgpu::Queue, params: &SimulationParams, intensity: f32) {
+        let perturbation = PerturbationParams::new(params, intensity, self.size);
+        queue.write_buffer(&self.perturbation_params_buffer, 0, bytemuck::bytes_of(&perturbation));
+    }
 
-        log::info!(
-            "Perturbation applied: {:?} at ({:.0},{:.0}) r={:.0} i={:.2}",
-            params.perturbation_type, cx, cy, radius, intensity
-        );
+    /// Raise a barrier at column `x` (pixels) for `frames` steps, replacing
+    /// any barrier already up.
+    pub fn raise_barrier(&mut self, queue: &wgpu::Queue, x: f32, frames: u32) {
+        let walls = PerturbationParams::barrier(x);
+        queue.write_buffer(&self.barrier_params_buffer, 0, bytemuck::bytes_of(&walls));
+        self.barrier = (frames > 0).then_some(Barrier { x, frames_left: frames });
     }
 
-    /// Perform a synchronous GPU readback of all simulation buffers.
+    /// Count down the barrier for the step about to be encoded; true if its
+    /// walls must be applied to that step.
+    pub fn barrier_step(&mut self) -> bool {
+        let active = self.barrier.is_some();
+        self.barrier = self.barrier.and_then(Barrier::tick);
+        active
+    }
+
+    /// Perform a synchronous GPU readback of all simulation state.
     /// This is expensive — call only every N frames for diagnostics.
     pub fn readback_snapshot(
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) -> Option<BufferSnapshot> {
-        let n = total_pixels() as usize;
+        let n = (self.size[0] * self.size[1]) as usize;
         let n_bytes = (n * std::mem::size_of::<f32>()) as u64;
         let cur = self.cur();
 
@@ -928,10 +1762,8 @@ impl WorldState {
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("readback_encoder"),
         });
-        encoder.copy_buffer_to_buffer(&self.mass[cur], 0, &self.staging_mass, 0, n_bytes);
-        encoder.copy_buffer_to_buffer(&self.energy[cur], 0, &self.staging_energy, 0, n_bytes);
-        encoder.copy_buffer_to_buffer(&self.genome_a[cur], 0, &self.staging_genome_a, 0, n_bytes * 4);
-        encoder.copy_buffer_to_buffer(&self.genome_b[cur], 0, &self.staging_genome_b, 0, n_bytes);
+        encoder.copy_buffer_to_buffer(&self.cells[cur], 0, &self.staging_cells, 0, self.staging_cells.size());
+        copy_field_to_buffer(&mut encoder, &self.genome_a[cur], &self.staging_genome_a);
         encoder.copy_buffer_to_buffer(&self.resource_map, 0, &self.staging_resource, 0, n_bytes);
         queue.submit(std::iter::once(encoder.finish()));
 
@@ -951,12 +1783,376 @@ impl WorldState {
             if floats.len() >= count { Some(floats) } else { None }
         };
 
-        let mass = read_staging(&self.staging_mass, n)?;
-        let energy = read_staging(&self.staging_energy, n)?;
+        let cells: Vec<CellState> = bytemuck::cast_slice(&read_staging(&self.staging_cells, n * 3)?[..n * 3]).to_vec();
+        let (mass, energy, genome_b) = split_cells(self.layout, self.size[0], &cells);
         let genome_a = read_staging(&self.staging_genome_a, n * 4)?;
-        let genome_b = read_staging(&self.staging_genome_b, n)?;
-        let resource = read_staging(&self.staging_resource, n)?;
+        let resource = self.layout.row_major(&read_staging(&self.staging_resource, n)?[..n], self.size[0]);
+
+        let flow = if self.flow.is_zero() { Vec::new() } else { self.flow.flat() };
+        let channels = self.channels.read_snapshot(device, queue, self.layout, cur)?;
+
+        Some(BufferSnapshot { size: self.size, mass, energy, genome_a, genome_b, resource, flow, channels })
+    }
+
+    /// Mean |U_separable − U_exact| over the cells compared since the last
+    /// call (convolution comparison mode), then reset the accumulator.
+    /// None when no cell was compared.
+    pub fn read_convolution_error(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<f32> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("conv_error_readback"),
+        });
+        encoder.copy_buffer_to_buffer(&self.conv_error, 0, &self.staging_conv_error, 0, 8);
+        encoder.clear_buffer(&self.conv_error, 0, None);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.staging_conv_error.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        let [sum, count]: [u32; 2] = bytemuck::pod_read_unaligned(&slice.get_mapped_range());
+        self.staging_conv_error.unmap();
+        (count > 0).then(|| sum as f32 / 1e5 / count as f32)
+    }
 
-        Some(BufferSnapshot { mass, energy, genome_a, genome_b, resource })
+    /// Summary of the first `count` particles of the pool.
+    pub fn read_particle_stats(&self, device: &wgpu::Device, queue: &wgpu::Queue, count: u32, capacity: f32) -> Option<ParticleStats> {
+        let particles = self.read_particles(device, queue, count)?;
+        Some(ParticleStats::from_particles(&particles, capacity))
     }
+
+    /// Synchronous readback of the first `count` particles of the pool.
+    pub fn read_particles(&self, device: &wgpu::Device, queue: &wgpu::Queue, count: u32) -> Option<Vec<Particle>> {
+        let size = (count.min(MAX_PARTICLES) as usize * std::mem::size_of::<Particle>()) as u64;
+        if size == 0 {
+            return Some(Vec::new());
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("particles_readback"),
+        });
+        encoder.copy_buffer_to_buffer(&self.particles, 0, &self.staging_particles, 0, size);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.staging_particles.slice(..size);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        let particles: Vec<Particle> = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+        self.staging_particles.unmap();
+        Some(particles)
+    }
+
+    /// Bin counts of the histogram pass encoded since the last call, then
+    /// reset for the next one.
+    pub fn read_histogram(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Vec<u32>> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("histogram_readback"),
+        });
+        encoder.copy_buffer_to_buffer(&self.histogram_bins, 0, &self.staging_histogram, 0, self.staging_histogram.size());
+        encoder.clear_buffer(&self.histogram_bins, 0, None);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.staging_histogram.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        let counts: Vec<u32> = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+        self.staging_histogram.unmap();
+        Some(counts)
+    }
+
+    /// Predation counts accumulated since the last call, then reset.
+    pub fn read_interactions(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<InteractionMatrix> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("interactions_readback"),
+        });
+        encoder.copy_buffer_to_buffer(&self.interaction_counts, 0, &self.staging_interactions, 0, self.staging_interactions.size());
+        encoder.clear_buffer(&self.interaction_counts, 0, None);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.staging_interactions.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        let counts: Vec<u32> = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+        self.staging_interactions.unmap();
+        Some(InteractionMatrix::from_counts(&counts))
+    }
+}
+
+/// Copy a storage buffer to a temporary staging buffer and read it back
+/// (for one-shot exports; the diagnostics readback keeps its own staging).
+fn read_buffer_f32(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> Option<Vec<f32>> {
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("staging_export"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("export_readback"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, staging.size());
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = staging.slice(..);
+    let (tx, rx) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = tx.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    rx.recv().ok()?.ok()?;
+    let data: Vec<f32> = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+    staging.unmap();
+    Some(data)
+}
+
+// ======================== Cell State ========================
+
+/// State of one cell in the interleaved cell buffers; mirrors `Cell` in
+/// indexing.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct CellState {
+    pub mass: f32,
+    pub energy: f32,
+    pub mutation_rate: f32, // genome B
+}
+
+/// Interleave row-major mass, energy and genome B fields of a `width`-wide
+/// world into a cell buffer ordered by `layout`.
+pub fn interleave_cells(
+    layout: CellLayout,
+    width: u32,
+    mass: &[f32],
+    energy: &[f32],
+    genome_b: &[f32],
+) -> Vec<CellState> {
+    let mut cells = vec![CellState::default(); mass.len()];
+    for (i, ((&mass, &energy), &mutation_rate)) in mass.iter().zip(energy).zip(genome_b).enumerate() {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        cells[layout.index(x, y, width)] = CellState { mass, energy, mutation_rate };
+    }
+    cells
+}
+
+/// Inverse of `interleave_cells`: the row-major (mass, energy, genome B) fields.
+pub fn split_cells(layout: CellLayout, width: u32, cells: &[CellState]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+    let n = cells.len();
+    let (mut mass, mut energy, mut genome_b) = (Vec::with_capacity(n), Vec::with_capacity(n), Vec::with_capacity(n));
+    for i in 0..n as u32 {
+        let cell = cells[layout.index(i % width, i / width, width)];
+        mass.push(cell.mass);
+        energy.push(cell.energy);
+        genome_b.push(cell.mutation_rate);
+    }
+    (mass, energy, genome_b)
+}
+
+// ======================== Cell Layout ========================
+
+/// Order of cells in the flat per-pixel buffers (resource map, velocity,
+/// trail). Field textures are tiled by the driver and unaffected; snapshots
+/// are always row-major.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CellLayout {
+    #[default]
+    RowMajor,
+    Morton, // Z-order: bit-interleaved (x, y), neighbors stay close in memory
+}
+
+impl CellLayout {
+    pub fn all() -> &'static [CellLayout] {
+        &[CellLayout::RowMajor, CellLayout::Morton]
+    }
+
+    /// Name used on the command line (`--layout`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellLayout::RowMajor => "row-major",
+            CellLayout::Morton => "morton",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|l| l.name() == name)
+    }
+
+    /// Morton order covers exactly the world only when it is square and a
+    /// power of two wide (up to 65536, the range of the 16-bit interleave).
+    pub fn supported(&self, width: u32, height: u32) -> bool {
+        match self {
+            CellLayout::RowMajor => true,
+            CellLayout::Morton => width == height && width.is_power_of_two() && width <= 1 << 16,
+        }
+    }
+
+    /// Flat index of cell (x, y); mirrors `cell_index` in indexing.wgsl.
+    pub fn index(&self, x: u32, y: u32, width: u32) -> usize {
+        match self {
+            CellLayout::RowMajor => (y * width + x) as usize,
+            CellLayout::Morton => morton_encode(x, y) as usize,
+        }
+    }
+
+    /// Reorder a row-major field (one value per cell) of a `width`-wide
+    /// world into this layout.
+    pub fn arrange(&self, row_major: &[f32], width: u32) -> Vec<f32> {
+        if *self == CellLayout::RowMajor {
+            return row_major.to_vec();
+        }
+        let mut out = vec![0.0; row_major.len()];
+        for (i, &v) in row_major.iter().enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            out[self.index(x, y, width)] = v;
+        }
+        out
+    }
+
+    /// Inverse of `arrange`: back to row-major order.
+    pub fn row_major(&self, data: &[f32], width: u32) -> Vec<f32> {
+        if *self == CellLayout::RowMajor {
+            return data.to_vec();
+        }
+        let mut out = vec![0.0; data.len()];
+        for (i, &v) in data.iter().enumerate() {
+            let (x, y) = morton_decode(i as u32);
+            out[(y * width + x) as usize] = v;
+        }
+        out
+    }
+
+    /// WGSL prelude selecting this layout for indexing.wgsl.
+    pub fn wgsl_const(&self) -> &'static str {
+        match self {
+            CellLayout::RowMajor => "const MORTON_LAYOUT: bool = false;",
+            CellLayout::Morton => "const MORTON_LAYOUT: bool = true;",
+        }
+    }
+}
+
+/// Interleave the low 16 bits of x (even bits) and y (odd bits).
+pub fn morton_encode(x: u32, y: u32) -> u32 {
+    fn spread(v: u32) -> u32 {
+        let mut b = v & 0x0000_ffff;
+        b = (b | (b << 8)) & 0x00ff_00ff;
+        b = (b | (b << 4)) & 0x0f0f_0f0f;
+        b = (b | (b << 2)) & 0x3333_3333;
+        (b | (b << 1)) & 0x5555_5555
+    }
+    spread(x) | (spread(y) << 1)
+}
+
+/// Inverse of `morton_encode`.
+pub fn morton_decode(i: u32) -> (u32, u32) {
+    fn compact(v: u32) -> u32 {
+        let mut b = v & 0x5555_5555;
+        b = (b | (b >> 1)) & 0x3333_3333;
+        b = (b | (b >> 2)) & 0x0f0f_0f0f;
+        b = (b | (b >> 4)) & 0x00ff_00ff;
+        (b | (b >> 8)) & 0x0000_ffff
+    }
+    (compact(i), compact(i >> 1))
+}
+
+// ======================== Grid Type ========================
+
+/// Lattice the cells sit on. Hex is an odd-r offset layout: cells keep their
+/// (x, y) texture coordinates, odd rows are shifted half a cell to the right
+/// and rows are √3/2 apart, so every cell has six equidistant neighbors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GridType {
+    #[default]
+    Square,
+    Hex,
+}
+
+impl GridType {
+    pub fn all() -> &'static [GridType] {
+        &[GridType::Square, GridType::Hex]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GridType::Square => "square",
+            GridType::Hex => "hex",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|g| g.name() == name)
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            GridType::Square => "params.grid_square",
+            GridType::Hex => "params.grid_hex",
+        }
+    }
+
+    /// Offset rows only tile a torus with an even number of rows.
+    pub fn supported(&self, _width: u32, height: u32) -> bool {
+        match self {
+            GridType::Square => true,
+            GridType::Hex => height.is_multiple_of(2),
+        }
+    }
+
+    /// The separable passes and the kernel LUT assume square-lattice
+    /// distances, so hex worlds always run the exact kernel.
+    pub fn convolution(&self, settings: ConvolutionSettings) -> ConvolutionSettings {
+        match self {
+            GridType::Square => settings,
+            GridType::Hex => ConvolutionSettings { separable: false, kernel_lut: false, ..settings },
+        }
+    }
+
+    /// WGSL prelude selecting this lattice for indexing.wgsl.
+    pub fn wgsl_const(&self) -> &'static str {
+        match self {
+            GridType::Square => "const HEX_GRID: bool = false;",
+            GridType::Hex => "const HEX_GRID: bool = true;",
+        }
+    }
+}
+
+// ======================== Field Textures ========================
+
+fn field_extent([width, height]: [u32; 2]) -> wgpu::Extent3d {
+    wgpu::Extent3d { width, height, depth_or_array_layers: 1 }
+}
+
+/// Row-major layout of a whole field texture in a buffer (rows unpadded).
+fn field_layout(texture: &wgpu::Texture) -> wgpu::TexelCopyBufferLayout {
+    let texel_bytes = texture.format().block_copy_size(None).unwrap_or(4);
+    wgpu::TexelCopyBufferLayout {
+        offset: 0,
+        bytes_per_row: Some(texture.width() * texel_bytes),
+        rows_per_image: Some(texture.height()),
+    }
+}
+
+/// Upload a full field from flat row-major data.
+fn write_field(queue: &wgpu::Queue, texture: &wgpu::Texture, data: &[u8]) {
+    queue.write_texture(texture.as_image_copy(), data, field_layout(texture), texture.size());
+}
+
+fn copy_field_to_buffer(encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture, buffer: &wgpu::Buffer) {
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo { buffer, layout: field_layout(texture) },
+        texture.size(),
+    );
 }
@@ -0,0 +1,11 @@
+// Energy contours: mass tinted by energy, with a dark line every 0.1 of
+// energy and the resource landscape showing through empty space.
+// Copy this file to start a new mode; see ModeInput in src/shaders/render.wgsl
+// for the fields available.
+fn user_mode_color(c: ModeInput) -> vec3<f32> {
+    let band = fract(c.energy * 10.0);
+    let line = smoothstep(0.0, 0.08, band) * smoothstep(1.0, 0.92, band);
+    let tint = mix(vec3<f32>(0.15, 0.35, 0.9), vec3<f32>(1.0, 0.8, 0.2), clamp(c.energy, 0.0, 1.0));
+    let ground = vec3<f32>(0.02, 0.05 + 0.1 * c.resource_level, 0.03);
+    return mix(ground, tint * (0.4 + 0.6 * line), c.intensity);
+}